@@ -0,0 +1,25 @@
+// Phase 5 compliance benchmark entrypoint
+// Run with `cargo bench --bench phase5_compliance`
+
+use claude_code_security::performance::benchmarks::run_phase5_compliance_benchmark;
+
+#[tokio::main]
+async fn main() {
+    let results = run_phase5_compliance_benchmark().await;
+
+    println!("\n📋 Target comparison:");
+    for comparison in &results.target_comparisons {
+        let status = if comparison.met { "✅" } else { "❌" };
+        println!(
+            "  {} {:?}: {:.2} {} (target {:.2})",
+            status, comparison.category, comparison.measured, comparison.unit, comparison.target
+        );
+    }
+
+    if !results.targets_met {
+        for recommendation in &results.recommendations {
+            println!("  • {}", recommendation);
+        }
+        std::process::exit(1);
+    }
+}
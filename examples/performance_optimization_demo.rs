@@ -153,7 +153,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     println!("\n🧪 Running Phase 5 Compliance Benchmark...");
     println!("  This may take a few minutes...");
     
-    let benchmark_results = run_phase5_compliance_benchmark(None).await;
+    let benchmark_results = run_phase5_compliance_benchmark().await;
     
     println!("\n📋 Benchmark Results Summary:");
     println!("  Overall Score: {:.1}%", benchmark_results.overall_score);
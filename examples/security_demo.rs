@@ -18,12 +18,17 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         token_storage_path: temp_dir.join("tokens.json"),
         audit_log_path: temp_dir.join("audit.log"),
         enable_encryption: true,
+        on_encryption_unavailable: Default::default(),
+        permission_check: Default::default(),
         enable_audit_logging: true,
         require_pkce: true,
         token_rotation_enabled: true,
         max_concurrent_oauth_flows: 3,
+        oauth_rate_limit: Default::default(),
+        oauth_flow_ttl_minutes: 10,
         session_timeout_minutes: 60,
         require_secure_transport: false, // Disabled for demo
+        violation_webhook: None,
     };
 
     let security_manager = SecurityManager::new(security_config)?;
@@ -56,6 +61,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         scopes: vec!["api".to_string(), "subscription".to_string()],
         require_max_subscription: false, // Disabled for demo
         enable_subscription_check: false, // Disabled for demo
+        ..ClaudeAuthConfig::default()
     };
 
     let storage_path = temp_dir.join("claude_tokens.json");
@@ -82,9 +88,9 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         
         // Create sample token data
         let sample_tokens = claude_code_security::security::secure_token_storage::TokenData {
-            access_token: "demo_access_token_123".to_string(),
-            refresh_token: "demo_refresh_token_456".to_string(),
-            id_token: "demo_id_token_789".to_string(),
+            access_token: "demo_access_token_123".into(),
+            refresh_token: "demo_refresh_token_456".into(),
+            id_token: "demo_id_token_789".into(),
             expires_at: chrono::Utc::now() + chrono::Duration::hours(1),
             account_id: Some("demo_account".to_string()),
             provider: "claude".to_string(),
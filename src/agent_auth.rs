@@ -1,10 +1,32 @@
 use std::collections::HashMap;
+use std::path::Path;
 use std::sync::Arc;
 use tokio::sync::RwLock;
 use chrono::Duration;
 use serde::Serialize;
 
+use crate::auth::{ClaudeAuth, UnifiedAuthManager};
+use crate::claude_auth::{ClaudeAuthConfig, ClaudeAuthMode, ClaudeSessionToken, ClaudeTokenData, SecureClaudeAuth, IMPORTABLE_ENV_VARS};
 use crate::claude_quota::{ClaudeQuotaManager, ClaudeAuthType, AgentQuotaAllocation};
+use crate::configuration::active_profile_home;
+use crate::security::secure_token_storage::TokenData;
+use crate::security::SecureTokenStorage;
+
+/// Default lifetime of the scoped credential issued to each agent
+pub const DEFAULT_AGENT_CREDENTIAL_TTL: Duration = Duration::hours(2);
+
+/// Scopes granted to agent credentials
+const AGENT_CREDENTIAL_SCOPES: [&str; 1] = ["api"];
+
+/// Environment variable holding an agent's scoped credential
+///
+/// It is not an Anthropic API key: the agent's host redeems it with
+/// [`AgentAuthCoordinator::access_token_for_agent`] when forwarding the agent's requests.
+pub const AGENT_CREDENTIAL_ENV_VAR: &str = "CLAUDE_AGENT_CREDENTIAL";
+
+/// Secure storage, in the active profile's home, for the copy of the OAuth credential
+/// that agent credentials are minted from
+const AGENT_ISSUER_STORAGE_FILE: &str = "claude_agent_issuer.json";
 
 /// Agent authentication environment setup
 #[derive(Debug, Clone)]
@@ -17,6 +39,8 @@ pub struct AgentAuthEnvironment {
     pub quota_allocation: Option<AgentQuotaAllocation>,
     /// Session tracking ID
     pub session_id: String,
+    /// Scoped credential placed in [`AGENT_CREDENTIAL_ENV_VAR`] instead of the master key
+    pub credential: Option<ClaudeSessionToken>,
 }
 
 /// Agent authentication coordinator for Claude Code environments
@@ -31,7 +55,13 @@ pub struct AgentAuthCoordinator {
     base_env: HashMap<String, String>,
     
     /// Authentication manager for Claude authentication
-    auth_manager: Option<Arc<AuthManager>>,
+    auth_manager: Option<Arc<UnifiedAuthManager>>,
+
+    /// Holder of the master credential, which mints each agent's scoped credential
+    credential_issuer: Option<Arc<SecureClaudeAuth>>,
+
+    /// Lifetime of agent credentials
+    credential_ttl: Duration,
 }
 
 impl AgentAuthCoordinator {
@@ -44,13 +74,16 @@ impl AgentAuthCoordinator {
             active_auth_sessions: Arc::new(RwLock::new(HashMap::new())),
             base_env,
             auth_manager: None,
+            credential_issuer: None,
+            credential_ttl: DEFAULT_AGENT_CREDENTIAL_TTL,
         }
     }
 
-    /// Create new authentication coordinator with auth manager
+    /// Create new authentication coordinator with auth manager, minting agent credentials with `issuer`
     pub fn new_with_auth_manager(
         quota_manager: Arc<ClaudeQuotaManager>, 
-        auth_manager: Arc<AuthManager>
+        auth_manager: Arc<UnifiedAuthManager>,
+        issuer: Arc<SecureClaudeAuth>,
     ) -> Self {
         let base_env = Self::detect_base_claude_env();
         
@@ -59,24 +92,33 @@ impl AgentAuthCoordinator {
             active_auth_sessions: Arc::new(RwLock::new(HashMap::new())),
             base_env,
             auth_manager: Some(auth_manager),
+            credential_issuer: Some(issuer),
+            credential_ttl: DEFAULT_AGENT_CREDENTIAL_TTL,
         }
     }
+
+    /// Issue each agent a scoped credential minted by `issuer`
+    ///
+    /// The master credential stays with the issuer; agents only ever see their own
+    /// session token. Without an issuer, agent setup fails.
+    pub fn with_credential_issuer(mut self, issuer: Arc<SecureClaudeAuth>) -> Self {
+        self.credential_issuer = Some(issuer);
+        self
+    }
+
+    /// Expire agent credentials after `ttl`
+    pub fn with_credential_ttl(mut self, ttl: Duration) -> Self {
+        self.credential_ttl = ttl;
+        self
+    }
     
-    /// Detect Claude authentication from environment
+    /// Detect Claude settings from environment
+    ///
+    /// API keys are deliberately not inherited: agents get a scoped credential from
+    /// [`setup_claude_agent_auth`](Self::setup_claude_agent_auth) instead.
     fn detect_base_claude_env() -> HashMap<String, String> {
         let mut env = HashMap::new();
         
-        // Check for Claude API credentials
-        if let Ok(claude_key) = std::env::var("CLAUDE_API_KEY") {
-            env.insert("CLAUDE_API_KEY".to_string(), claude_key.clone());
-            env.insert("ANTHROPIC_API_KEY".to_string(), claude_key);
-        }
-        
-        if let Ok(anthropic_key) = std::env::var("ANTHROPIC_API_KEY") {
-            env.insert("ANTHROPIC_API_KEY".to_string(), anthropic_key.clone());
-            env.insert("CLAUDE_API_KEY".to_string(), anthropic_key);
-        }
-        
         // Claude base URL if configured
         if let Ok(base_url) = std::env::var("ANTHROPIC_BASE_URL") {
             env.insert("ANTHROPIC_BASE_URL".to_string(), base_url.clone());
@@ -112,29 +154,21 @@ impl AgentAuthCoordinator {
 
         if let Some(ref auth_manager) = self.auth_manager {
             // Use the new Claude authentication system
-            if let Some(claude_auth) = auth_manager.claude_auth() {
+            if let Some(claude_auth) = auth_manager.claude_auth().await {
+                // Only confirms the credential works; its value never reaches the agent
                 match claude_auth.get_token().await {
-                    Ok(token) => {
-                        // Set Claude authentication token
-                        env_vars.insert("ANTHROPIC_API_KEY".to_string(), token);
-                        env_vars.insert("CLAUDE_API_KEY".to_string(), claude_auth.api_key.unwrap_or_default());
-                        
+                    Ok(_) => {
                         // Set subscription information
-                        if let Some(tier) = claude_auth.get_subscription_tier() {
+                        if let Some(tier) = &claude_auth.subscription_tier {
                             env_vars.insert("CLAUDE_SUBSCRIPTION_TIER".to_string(), tier.clone());
                             
                             // Update auth type based on subscription
-                            auth_type = match tier.as_str() {
-                                "max" => {
-                                    env_vars.insert("CLAUDE_MAX_USER".to_string(), "true".to_string());
-                                    ClaudeAuthType::Max { daily_limit: 500_000 } // Claude Max typical limits
-                                },
-                                "pro" => {
-                                    env_vars.insert("CLAUDE_PRO_USER".to_string(), "true".to_string());
-                                    ClaudeAuthType::ApiKey { requests_per_minute: 300, tokens_per_minute: 200_000 }
-                                },
-                                _ => ClaudeAuthType::ApiKey { requests_per_minute: 60, tokens_per_minute: 100_000 }
-                            };
+                            auth_type = auth_type_for_tier(tier);
+                            match tier.as_str() {
+                                "max" => { env_vars.insert("CLAUDE_MAX_USER".to_string(), "true".to_string()); },
+                                "pro" => { env_vars.insert("CLAUDE_PRO_USER".to_string(), "true".to_string()); },
+                                _ => {}
+                            }
                         }
 
                         // Check if user has Max subscription
@@ -146,20 +180,22 @@ impl AgentAuthCoordinator {
                         tracing::warn!("Failed to get Claude token: {}, falling back to environment detection", e);
                     }
                 }
-            } else if let Some(AuthProvider::Claude(claude_auth)) = auth_manager.get_optimal_provider().await {
-                // Fallback to optimal provider if direct Claude auth not available
-                if let Ok(token) = claude_auth.get_token().await {
-                    env_vars.insert("ANTHROPIC_API_KEY".to_string(), token);
-                    if let Some(api_key) = &claude_auth.api_key {
-                        env_vars.insert("CLAUDE_API_KEY".to_string(), api_key.clone());
-                    }
-                }
             }
         }
         
         // Allocate quota for this agent based on determined auth type
         let quota_allocation = self.quota_manager.allocate_agent_quota(agent_id).await
             .map_err(|e| format!("Quota allocation error: {}", e))?;
+
+        // Tie a scoped credential to the allocation; the quota is handed back if minting fails
+        let credential = match self.mint_agent_credential() {
+            Ok(credential) => credential,
+            Err(e) => {
+                self.quota_manager.release_agent_quota(agent_id).await.ok();
+                return Err(e);
+            }
+        };
+        env_vars.insert(AGENT_CREDENTIAL_ENV_VAR.to_string(), credential.token.expose_secret().clone());
         
         // Add quota-specific environment variables
         env_vars.insert(
@@ -184,6 +220,7 @@ impl AgentAuthCoordinator {
             auth_type,
             quota_allocation: Some(quota_allocation),
             session_id: agent_id.to_string(),
+            credential: Some(credential),
         };
         
         // Store active session
@@ -195,10 +232,53 @@ impl AgentAuthCoordinator {
         Ok(auth_env)
     }
     
-    /// Release authentication for an agent
+    /// Scoped credential for a new agent
+    ///
+    /// Fails without an issuer rather than starting the agent with no credential at all.
+    fn mint_agent_credential(&self) -> Result<ClaudeSessionToken, String> {
+        let issuer = self.credential_issuer.as_ref()
+            .ok_or_else(|| "No credential issuer configured; cannot issue an agent credential".to_string())?;
+        let scopes = AGENT_CREDENTIAL_SCOPES.iter().map(|s| s.to_string()).collect();
+        issuer
+            .mint_session_token(scopes, self.credential_ttl)
+            .map_err(|e| format!("Agent credential error: {}", e))
+    }
+
+    /// Revoke the credential issued with `auth_env`, if any
+    fn revoke_agent_credential(&self, auth_env: &AgentAuthEnvironment) {
+        if let (Some(issuer), Some(credential)) = (&self.credential_issuer, &auth_env.credential) {
+            if let Err(e) = issuer.revoke_session_token(&credential.session_id) {
+                tracing::warn!(agent_id = %auth_env.session_id, "Failed to revoke agent credential: {}", e);
+            }
+        }
+    }
+
+    /// Access token for a request made with an agent's scoped credential
+    ///
+    /// This is how the coordinator serves agent requests without handing out the master
+    /// credential; `presented` must be the token issued to `agent_id`.
+    pub async fn access_token_for_agent(&self, agent_id: &str, presented: &str) -> Result<ClaudeTokenData, String> {
+        let issuer = self.credential_issuer.as_ref()
+            .ok_or_else(|| "No credential issuer configured".to_string())?;
+        let credential = self.active_auth_sessions.read().await
+            .get(agent_id)
+            .and_then(|auth_env| auth_env.credential.clone())
+            .ok_or_else(|| format!("No credential issued to agent {}", agent_id))?;
+        if credential.token.expose_secret() != presented {
+            return Err(format!("Credential does not belong to agent {}", agent_id));
+        }
+
+        issuer
+            .get_token(&ClaudeAuthMode::SessionToken(credential))
+            .map_err(|e| format!("Agent credential rejected: {}", e))
+    }
+
+    /// Release authentication for an agent, revoking its credential
     pub async fn release_agent_auth(&self, agent_id: &str) -> Result<(), String> {
         // Remove from active sessions
-        self.active_auth_sessions.write().await.remove(agent_id);
+        if let Some(auth_env) = self.active_auth_sessions.write().await.remove(agent_id) {
+            self.revoke_agent_credential(&auth_env);
+        }
         
         // Release quota
         self.quota_manager.release_agent_quota(agent_id).await?;
@@ -239,7 +319,13 @@ impl AgentAuthCoordinator {
         let mut sessions = self.active_auth_sessions.write().await;
         let active_quota_agents = self.quota_manager.get_quota_status().await.active_agent_ids;
         
-        sessions.retain(|agent_id, _| active_quota_agents.contains(agent_id));
+        sessions.retain(|agent_id, auth_env| {
+            let active = active_quota_agents.contains(agent_id);
+            if !active {
+                self.revoke_agent_credential(auth_env);
+            }
+            active
+        });
         
         cleaned
     }
@@ -288,17 +374,87 @@ pub struct ClaudeAuthHealthStatus {
     pub can_allocate_new_agents: bool,
 }
 
-/// Create a global authentication coordinator
+// Global authentication coordinator
 lazy_static::lazy_static! {
     static ref GLOBAL_AUTH_COORDINATOR: Arc<RwLock<Option<Arc<AgentAuthCoordinator>>>> = 
         Arc::new(RwLock::new(None));
 }
 
-/// Initialize global Claude authentication coordinator
+/// Initialize global Claude authentication coordinator from the credentials in `~/.codex`
 pub async fn init_claude_auth_coordinator() -> Result<Arc<AgentAuthCoordinator>, String> {
-    let auth_type = ClaudeAuthType::detect_from_env()
-        .ok_or_else(|| "No Claude authentication detected in environment".to_string())?;
-    
+    let codex_home = std::env::home_dir().unwrap_or_default().join(".codex");
+    init_claude_auth_coordinator_in(&codex_home, ClaudeAuthConfig::default()).await
+}
+
+/// Initialize global Claude authentication coordinator from the Claude credential configured
+/// under `codex_home`
+///
+/// Agent credentials are minted from the OAuth tokens in the active profile's
+/// `claude_auth.json` (or `auth_config`'s `auth_file_path`). An API key can't be narrowed
+/// to a scoped credential, so with one in `ANTHROPIC_API_KEY`, `CLAUDE_API_KEY` or the auth
+/// file this fails instead of handing agents the key itself.
+pub async fn init_claude_auth_coordinator_in(
+    codex_home: &Path,
+    auth_config: ClaudeAuthConfig,
+) -> Result<Arc<AgentAuthCoordinator>, String> {
+    init_claude_auth_coordinator_with_env(codex_home, auth_config, |var| std::env::var(var).ok()).await
+}
+
+async fn init_claude_auth_coordinator_with_env(
+    codex_home: &Path,
+    auth_config: ClaudeAuthConfig,
+    env: impl Fn(&str) -> Option<String>,
+) -> Result<Arc<AgentAuthCoordinator>, String> {
+    if let Some(var) = IMPORTABLE_ENV_VARS.iter().find(|var| env(var).is_some_and(|value| !value.trim().is_empty())) {
+        return Err(format!(
+            "{} holds an API key, which can't be delegated to agents; log in with a Claude subscription to run agents",
+            var
+        ));
+    }
+
+    let claude_auth = ClaudeAuth::load_with_config(codex_home, auth_config.clone()).await
+        .map_err(|e| format!("Claude credential error: {}", e))?
+        .ok_or_else(|| "No Claude authentication configured".to_string())?;
+    if claude_auth.api_key.is_some() || claude_auth.oauth_tokens.is_none() {
+        return Err("Claude is configured with an API key, which can't be delegated to agents; log in with a Claude subscription to run agents".to_string());
+    }
+    // Refreshes the access token first if it is about to expire
+    claude_auth.get_token().await
+        .map_err(|e| format!("Claude credential error: {}", e))?;
+    let tokens = claude_auth.current_oauth_tokens().ok_or_else(|| "Claude OAuth tokens missing".to_string())?;
+
+    let profile_home = active_profile_home(codex_home).map_err(|e| format!("Claude credential error: {}", e))?;
+    let storage_path = profile_home.join(AGENT_ISSUER_STORAGE_FILE);
+    SecureTokenStorage::new(storage_path.clone())
+        .and_then(|storage| storage.store_tokens(&TokenData {
+            access_token: tokens.access_token.clone(),
+            refresh_token: tokens.refresh_token.clone().unwrap_or_default(),
+            id_token: Default::default(),
+            expires_at: tokens.expires_at,
+            account_id: None,
+            provider: "claude".to_string(),
+        }))
+        .map_err(|e| format!("Agent credential issuer error: {}", e))?;
+    let issuer = SecureClaudeAuth::new(auth_config, storage_path)
+        .map_err(|e| format!("Agent credential issuer error: {}", e))?;
+
+    install_claude_auth_coordinator(auth_type_for_tier(&tokens.subscription_tier), Arc::new(issuer)).await
+}
+
+/// Quota limits typical of a Claude subscription tier
+fn auth_type_for_tier(tier: &str) -> ClaudeAuthType {
+    match tier {
+        "max" => ClaudeAuthType::Max { daily_limit: 500_000 }, // Claude Max typical limits
+        "pro" => ClaudeAuthType::ApiKey { requests_per_minute: 300, tokens_per_minute: 200_000 },
+        _ => ClaudeAuthType::ApiKey { requests_per_minute: 60, tokens_per_minute: 100_000 },
+    }
+}
+
+/// Make a coordinator for `auth_type` that mints agent credentials with `issuer` the global one
+async fn install_claude_auth_coordinator(
+    auth_type: ClaudeAuthType,
+    issuer: Arc<SecureClaudeAuth>,
+) -> Result<Arc<AgentAuthCoordinator>, String> {
     let quota_manager = match auth_type {
         ClaudeAuthType::Max { daily_limit } => {
             Arc::new(ClaudeQuotaManager::new_max_subscription(daily_limit, 5))
@@ -308,7 +464,7 @@ pub async fn init_claude_auth_coordinator() -> Result<Arc<AgentAuthCoordinator>,
         },
     };
     
-    let coordinator = Arc::new(AgentAuthCoordinator::new(quota_manager));
+    let coordinator = Arc::new(AgentAuthCoordinator::new(quota_manager).with_credential_issuer(issuer));
     
     *GLOBAL_AUTH_COORDINATOR.write().await = Some(coordinator.clone());
     
@@ -325,11 +481,14 @@ mod tests {
     use super::*;
     use std::sync::Arc;
     use crate::claude_quota::ClaudeQuotaManager;
+    use tempfile::tempdir;
 
     #[tokio::test]
     async fn test_agent_auth_setup() {
+        let temp_dir = tempdir().unwrap();
         let quota_manager = Arc::new(ClaudeQuotaManager::new_max_subscription(10000, 3));
-        let coordinator = AgentAuthCoordinator::new(quota_manager);
+        let coordinator = AgentAuthCoordinator::new(quota_manager)
+            .with_credential_issuer(credential_issuer(temp_dir.path()));
         
         let agent_id = "test_agent";
         let auth_env = coordinator.setup_claude_agent_auth(agent_id).await;
@@ -339,6 +498,8 @@ mod tests {
         
         assert!(env.env_vars.contains_key("CLAUDE_AGENT_ID"));
         assert!(env.env_vars.contains_key("CLAUDE_SESSION_ID"));
+        assert!(env.env_vars.contains_key(AGENT_CREDENTIAL_ENV_VAR));
+        assert!(!env.env_vars.contains_key("ANTHROPIC_API_KEY"));
         assert!(env.quota_allocation.is_some());
         
         // Cleanup
@@ -347,8 +508,10 @@ mod tests {
     
     #[tokio::test]
     async fn test_concurrent_agent_limits() {
+        let temp_dir = tempdir().unwrap();
         let quota_manager = Arc::new(ClaudeQuotaManager::new_max_subscription(10000, 2));
-        let coordinator = AgentAuthCoordinator::new(quota_manager);
+        let coordinator = AgentAuthCoordinator::new(quota_manager)
+            .with_credential_issuer(credential_issuer(temp_dir.path()));
         
         // Should be able to allocate 2 agents
         let agent1 = coordinator.setup_claude_agent_auth("agent1").await;
@@ -365,4 +528,130 @@ mod tests {
         coordinator.release_agent_auth("agent1").await.unwrap();
         coordinator.release_agent_auth("agent2").await.unwrap();
     }
+
+    fn credential_issuer(dir: &Path) -> Arc<SecureClaudeAuth> {
+        let storage_path = dir.join("claude_tokens.json");
+        SecureTokenStorage::new(storage_path.clone())
+            .unwrap()
+            .store_tokens(&TokenData {
+                access_token: "master-access-token".into(),
+                refresh_token: "master-refresh-token".into(),
+                id_token: "master-id-token".into(),
+                expires_at: chrono::Utc::now() + Duration::hours(8),
+                account_id: None,
+                provider: "claude".to_string(),
+            })
+            .unwrap();
+        Arc::new(SecureClaudeAuth::new(ClaudeAuthConfig::default(), storage_path).unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_agents_get_distinct_scoped_credentials() {
+        let temp_dir = tempdir().unwrap();
+        let quota_manager = Arc::new(ClaudeQuotaManager::new_max_subscription(10000, 3));
+        let issuer = credential_issuer(temp_dir.path());
+        let coordinator = AgentAuthCoordinator::new(quota_manager).with_credential_issuer(issuer.clone());
+
+        let agent1 = coordinator.setup_claude_agent_auth("agent1").await.unwrap();
+        let agent2 = coordinator.setup_claude_agent_auth("agent2").await.unwrap();
+        let token1 = agent1.env_vars[AGENT_CREDENTIAL_ENV_VAR].clone();
+        let token2 = agent2.env_vars[AGENT_CREDENTIAL_ENV_VAR].clone();
+        assert_ne!(token1, token2);
+        for env in [&agent1.env_vars, &agent2.env_vars] {
+            assert!(env.values().all(|value| !value.contains("master")));
+        }
+
+        // Tokens are redeemable only by the agent they were issued to
+        let redeemed = coordinator.access_token_for_agent("agent1", &token1).await.unwrap();
+        assert_eq!(redeemed.access_token.expose_secret(), "master-access-token");
+        assert!(coordinator.access_token_for_agent("agent1", &token2).await.is_err());
+
+        coordinator.release_agent_auth("agent1").await.unwrap();
+        assert!(coordinator.access_token_for_agent("agent1", &token1).await.is_err());
+        let revoked = ClaudeAuthMode::SessionToken(agent1.credential.clone().unwrap());
+        assert!(issuer.get_token(&revoked).is_err());
+        assert!(coordinator.access_token_for_agent("agent2", &token2).await.is_ok());
+
+        coordinator.release_agent_auth("agent2").await.unwrap();
+    }
+    #[tokio::test]
+    async fn test_setup_without_issuer_fails_and_returns_quota() {
+        let quota_manager = Arc::new(ClaudeQuotaManager::new_max_subscription(10000, 1));
+        let coordinator = AgentAuthCoordinator::new(quota_manager.clone());
+
+        let err = coordinator.setup_claude_agent_auth("agent1").await.unwrap_err();
+        assert!(err.contains("No credential issuer"), "{}", err);
+        assert!(quota_manager.can_allocate_agent().await.unwrap());
+        assert!(coordinator.get_active_agents().await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_global_coordinator_issues_agent_credentials() {
+        let temp_dir = tempdir().unwrap();
+        let auth_type = ClaudeAuthType::ApiKey { requests_per_minute: 60, tokens_per_minute: 100_000 };
+        install_claude_auth_coordinator(auth_type, credential_issuer(temp_dir.path())).await.unwrap();
+
+        let coordinator = get_claude_auth_coordinator().await.unwrap();
+        let env = coordinator.setup_claude_agent_auth("global_agent").await.unwrap();
+        let token = env.env_vars[AGENT_CREDENTIAL_ENV_VAR].clone();
+        assert!(!token.contains("master"));
+        assert!(coordinator.access_token_for_agent("global_agent", &token).await.is_ok());
+
+        coordinator.release_agent_auth("global_agent").await.unwrap();
+    }
+
+    fn write_oauth_auth_file(codex_home: &Path) {
+        let claude_json = serde_json::json!({
+            "oauth_tokens": {
+                "access_token": "configured-access-token",
+                "refresh_token": "configured-refresh-token",
+                "expires_at": chrono::Utc::now() + Duration::hours(8),
+                "subscription_tier": "max",
+                "token_type": "Bearer",
+                "scope": ["api"],
+            }
+        });
+        std::fs::write(codex_home.join("claude_auth.json"), claude_json.to_string()).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_init_mints_agent_credentials_from_configured_oauth_tokens() {
+        let codex_home = tempdir().unwrap();
+        write_oauth_auth_file(codex_home.path());
+
+        let coordinator = init_claude_auth_coordinator_with_env(codex_home.path(), ClaudeAuthConfig::default(), |_| None)
+            .await
+            .unwrap();
+        let env = coordinator.setup_claude_agent_auth("configured_agent").await.unwrap();
+        let quota_status = coordinator.quota_manager.get_quota_status().await;
+        assert_eq!(quota_status.auth_type, ClaudeAuthType::Max { daily_limit: 500_000 });
+        let token = env.env_vars[AGENT_CREDENTIAL_ENV_VAR].clone();
+        assert!(env.env_vars.values().all(|value| !value.contains("configured-")));
+
+        let redeemed = coordinator.access_token_for_agent("configured_agent", &token).await.unwrap();
+        assert_eq!(redeemed.access_token.expose_secret(), "configured-access-token");
+
+        coordinator.release_agent_auth("configured_agent").await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_init_rejects_api_keys() {
+        let codex_home = tempdir().unwrap();
+        write_oauth_auth_file(codex_home.path());
+        let env = |var: &str| (var == "ANTHROPIC_API_KEY").then(|| "sk-ant-master".to_string());
+        let err = init_claude_auth_coordinator_with_env(codex_home.path(), ClaudeAuthConfig::default(), env)
+            .await
+            .err()
+            .unwrap();
+        assert!(err.contains("ANTHROPIC_API_KEY") && err.contains("can't be delegated"), "{}", err);
+
+        let api_key_home = tempdir().unwrap();
+        let claude_json = serde_json::json!({"api_key": "sk-ant-master"});
+        std::fs::write(api_key_home.path().join("claude_auth.json"), claude_json.to_string()).unwrap();
+        let err = init_claude_auth_coordinator_with_env(api_key_home.path(), ClaudeAuthConfig::default(), |_| None)
+            .await
+            .err()
+            .unwrap();
+        assert!(err.contains("can't be delegated"), "{}", err);
+    }
 }
\ No newline at end of file
@@ -8,7 +8,7 @@ use crate::claude_quota::ClaudeAuthType;
 pub async fn initialize_claude_auth_system() -> Result<(), String> {
     match init_claude_auth_coordinator().await {
         Ok(_coordinator) => {
-            println!("✅ Claude authentication system initialized successfully");
+            tracing::info!("Claude authentication system initialized");
             
             // Optionally setup the global agent manager with Claude auth
             // Note: In real integration, you would setup the agent manager here
@@ -21,8 +21,7 @@ pub async fn initialize_claude_auth_system() -> Result<(), String> {
             Ok(())
         }
         Err(e) => {
-            println!("⚠️  Claude authentication not available: {}", e);
-            println!("💡 Agents will run without Claude quota management");
+            tracing::warn!(error = %e, "Claude authentication not available; agents will run without Claude quota management");
             Ok(()) // Not a fatal error
         }
     }
@@ -57,7 +56,7 @@ pub async fn setup_agent_manager_claude_auth(
         // In real integration:
         // let mut manager = agent_manager.write().await;
         // manager.set_claude_auth_coordinator(coordinator);
-        println!("✅ Claude authentication would be setup for agent manager");
+        tracing::debug!("Claude authentication would be set up for agent manager");
         Ok(())
     } else {
         Err("Claude authentication coordinator not initialized".to_string())
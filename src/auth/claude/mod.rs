@@ -3,13 +3,32 @@
 /// Provides comprehensive Claude authentication support including API keys,
 /// OAuth tokens, subscription management, and quota tracking.
 
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, LocalResult, NaiveTime, Offset, TimeZone, Utc};
+use chrono_tz::Tz;
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 use std::collections::HashMap;
+use std::future::Future;
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use tokio::sync::RwLock;
 
+use crate::auth::migration::{BackupManager, MigrationConfig};
+use crate::claude_auth::timestamp::parse_response_timestamp;
+use crate::claude_auth::secure_claude_auth::{
+    log_verification_skipped, network_error_is_auth_fatal, network_error_is_retryable, parse_retry_after,
+    revoke_token, ClaudeAuthConfig,
+};
+use crate::configuration::active_profile_home;
+use crate::performance::connection_pool::ClaudeConnectionPool;
+use crate::performance::memory_optimization::{AgentMemoryLease, MemoryOptimizer};
+use crate::performance::token_optimization::{TokenOptimizer, TokenRefreshResult};
+use crate::security::audit_logger::{log_audit_event, AuditEvent, AuthEventType, Severity};
+use crate::security::clock::{system_clock, Clock};
+use crate::security::secret::Secret;
+use crate::security::secret_ref::SecretRef;
+use crate::security::{write_atomic_async, LoginLock, LoginLockError};
+
 /// Claude authentication modes
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum ClaudeAuthMode {
@@ -26,17 +45,36 @@ pub enum ClaudeAuthMode {
 pub struct ClaudeAuth {
     pub mode: ClaudeAuthMode,
     pub subscription_tier: Option<String>,
-    pub api_key: Option<String>,
+    pub api_key: Option<Secret<String>>,
+    /// `file:` or `env:` reference `api_key` was read from, re-read by
+    /// [`refresh_api_key`](Self::refresh_api_key)
+    pub api_key_ref: Option<SecretRef>,
     pub oauth_tokens: Option<ClaudeTokenData>,
+    /// Tokens from the latest refresh, superseding `oauth_tokens`; shared by clones so a
+    /// refresh through one copy is seen by all of them
+    pub refreshed_oauth_tokens: Arc<std::sync::RwLock<Option<ClaudeTokenData>>>,
+    /// `claude_auth.json` the credentials were loaded from, where refreshed tokens are saved
+    pub auth_file: Option<PathBuf>,
     pub client: reqwest::Client,
     pub quota_manager: Arc<RwLock<ClaudeQuotaManager>>,
+    /// Endpoints and offline behaviour; defaults honour `CODEX_OFFLINE`
+    pub auth_config: ClaudeAuthConfig,
+    /// OAuth tokens this close to `expires_at` are refreshed before use
+    pub refresh_skew: std::time::Duration,
+    /// Coalesces concurrent refreshes of the same token into one request
+    pub token_optimizer: Arc<TokenOptimizer>,
+    /// Time source for token expiry checks
+    pub clock: Arc<dyn Clock>,
 }
 
+/// Default [`ClaudeAuth::refresh_skew`]
+pub const DEFAULT_REFRESH_SKEW: std::time::Duration = std::time::Duration::from_secs(60);
+
 /// Claude OAuth token data
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ClaudeTokenData {
-    pub access_token: String,
-    pub refresh_token: Option<String>,
+    pub access_token: Secret<String>,
+    pub refresh_token: Option<Secret<String>>,
     pub expires_at: DateTime<Utc>,
     pub subscription_tier: String,
     pub token_type: String,
@@ -50,7 +88,9 @@ pub struct ClaudeSubscription {
     pub features: Vec<String>,
     pub quota_limit: u64,
     pub quota_used: u64,
-    pub quota_reset_date: DateTime<Utc>,
+    /// `None` when the endpoint sent no reset date or one that couldn't be parsed
+    #[serde(default)]
+    pub quota_reset_date: Option<DateTime<Utc>>,
     pub active: bool,
 }
 
@@ -62,6 +102,99 @@ pub struct ClaudeQuotaManager {
     pub concurrent_limit: u16,
     pub active_agents: HashMap<String, AgentQuota>,
     pub last_reset: DateTime<Utc>,
+    /// Estimated memory footprint reserved per admitted agent
+    pub agent_memory_estimate_mb: u64,
+    /// How often the background sweeper reclaims expired allocations
+    pub sweep_interval: std::time::Duration,
+    memory_optimizer: Option<Arc<MemoryOptimizer>>,
+    memory_leases: HashMap<String, Arc<AgentMemoryLease>>,
+    /// What happens when a completed request used more than the agent was allocated
+    pub overage_policy: OveragePolicy,
+    /// Whether `daily_limit` came from the subscription rather than the built-in default
+    pub limit_from_subscription: bool,
+    /// How often `daily_limit` is re-read from the subscription endpoint
+    pub limit_refresh_interval: chrono::Duration,
+    limit_checked_at: Option<DateTime<Utc>>,
+    /// When the subscription last said its quota resets
+    pub subscription_reset_date: Option<DateTime<Utc>>,
+    /// Set by [`begin_drain`](Self::begin_drain); new allocations are refused
+    draining: bool,
+    /// Reset at a fixed local time each day; `None` resets 24h after the last reset
+    pub reset_schedule: Option<QuotaResetSchedule>,
+    clock: Arc<dyn Clock>,
+}
+
+/// Daily quota reset aligned to a wall-clock time, e.g. midnight in America/Los_Angeles
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct QuotaResetSchedule {
+    /// IANA timezone the reset time is written in
+    pub timezone: Tz,
+    /// Local time of day the quota resets at
+    pub reset_at: NaiveTime,
+}
+
+impl QuotaResetSchedule {
+    /// Reset every day at `reset_at` local time in `timezone`
+    pub fn new(timezone: Tz, reset_at: NaiveTime) -> Self {
+        Self { timezone, reset_at }
+    }
+
+    /// Reset every day at local midnight in `timezone`
+    pub fn midnight(timezone: Tz) -> Self {
+        Self::new(timezone, NaiveTime::MIN)
+    }
+
+    /// First reset strictly after `after`
+    ///
+    /// When the clocks go back and the reset time occurs twice, the earlier one counts.
+    /// When they go forward past it, the reset happens as far after the skipped instant
+    /// as the jump, using the offset in effect before the change.
+    pub fn next_reset_after(&self, after: DateTime<Utc>) -> DateTime<Utc> {
+        let mut date = after.with_timezone(&self.timezone).date_naive();
+        loop {
+            let reset_at = self.instant_on(date.and_time(self.reset_at));
+            if reset_at > after {
+                return reset_at;
+            }
+            date = date.succ_opt().expect("reset date stays within chrono's range");
+        }
+    }
+
+    fn instant_on(&self, local: chrono::NaiveDateTime) -> DateTime<Utc> {
+        match self.timezone.from_local_datetime(&local) {
+            LocalResult::Single(reset_at) | LocalResult::Ambiguous(reset_at, _) => reset_at.with_timezone(&Utc),
+            LocalResult::None => {
+                let offset_before = self
+                    .timezone
+                    .offset_from_utc_datetime(&(local - chrono::Duration::days(1)))
+                    .fix();
+                (local - chrono::Duration::seconds(offset_before.local_minus_utc() as i64)).and_utc()
+            }
+        }
+    }
+}
+
+/// Handling of requests that consume more tokens than their agent's allocation
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OveragePolicy {
+    /// Cap usage at the allocation and log the excess as a warning
+    #[default]
+    Clamp,
+    /// Grow the allocation out of the remaining daily quota, clamping only once that runs out
+    ExpandWithinDailyQuota,
+}
+
+/// Outcome of debiting a completed request against an agent's allocation
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UsageDebit {
+    pub agent_id: String,
+    /// Tokens charged to the allocation, including any expansion
+    pub charged_tokens: u64,
+    /// Tokens added to the allocation from the daily quota
+    pub expanded_tokens: u64,
+    /// Tokens consumed beyond what could be charged
+    pub unaccounted_tokens: u64,
 }
 
 /// Agent-specific quota allocation
@@ -72,6 +205,108 @@ pub struct AgentQuota {
     pub used_tokens: u64,
     pub created_at: DateTime<Utc>,
     pub expires_at: DateTime<Utc>,
+    /// Key the allocation was requested with; repeating it returns this allocation
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub idempotency_key: Option<String>,
+}
+
+/// Default [`ClaudeQuotaManager::sweep_interval`]
+pub const DEFAULT_QUOTA_SWEEP_INTERVAL: std::time::Duration = std::time::Duration::from_secs(300);
+
+/// Default [`ClaudeQuotaManager::limit_refresh_interval`], matching the daily subscription check
+pub const DEFAULT_QUOTA_LIMIT_REFRESH_INTERVAL: chrono::Duration = chrono::Duration::hours(24);
+
+/// How often [`ClaudeAuth::drain_agents`] checks whether every agent has released its quota
+const AGENT_DRAIN_POLL_INTERVAL: std::time::Duration = std::time::Duration::from_millis(25);
+
+/// Quota accounting as persisted at shutdown
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QuotaSnapshot {
+    pub daily_limit: u64,
+    pub current_usage: u64,
+    pub last_reset: DateTime<Utc>,
+    pub limit_from_subscription: bool,
+    /// Allocations still held when the snapshot was taken
+    pub active_agents: Vec<AgentQuota>,
+    pub taken_at: DateTime<Utc>,
+}
+
+/// Outcome of [`ClaudeAuth::drain_agents`]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AgentDrainReport {
+    /// Agents that released their quota within the grace period
+    pub released: Vec<String>,
+    /// Allocations still held at the deadline, reclaimed by force
+    pub reclaimed: Vec<AgentQuota>,
+}
+
+/// Allocations this close to `expires_at` are flagged in usage reports
+pub const AGENT_EXPIRY_WARNING: chrono::Duration = chrono::Duration::minutes(15);
+
+/// Usage of a single agent's quota allocation
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AgentUsageReport {
+    pub agent_id: String,
+    pub allocated_tokens: u64,
+    pub used_tokens: u64,
+    /// Share of the allocation consumed, 0-100 (can exceed 100 on overrun)
+    pub utilization_percent: f64,
+    pub age_seconds: i64,
+    pub expires_at: DateTime<Utc>,
+    pub near_expiry: bool,
+}
+
+/// Per-agent usage with aggregate totals
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AgentUsageSummary {
+    /// Sorted by tokens used, highest first
+    pub agents: Vec<AgentUsageReport>,
+    pub total_allocated: u64,
+    pub total_used: u64,
+    pub near_expiry_count: usize,
+}
+
+/// Expected shape of a Claude API key, checked before any network verification
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ApiKeyFormat {
+    /// Required key prefix
+    pub prefix: String,
+    /// Minimum total key length, prefix included
+    pub min_length: usize,
+}
+
+impl Default for ApiKeyFormat {
+    fn default() -> Self {
+        Self {
+            prefix: "sk-ant-".to_string(),
+            min_length: 40,
+        }
+    }
+}
+
+impl ApiKeyFormat {
+    /// Same length rules with a different prefix, for future key formats
+    pub fn with_prefix(prefix: impl Into<String>) -> Self {
+        Self {
+            prefix: prefix.into(),
+            ..Self::default()
+        }
+    }
+
+    /// Cheap offline shape check: prefix, minimum length and `[A-Za-z0-9_-]` characters
+    pub fn validate(&self, api_key: &str) -> Result<(), ClaudeAuthError> {
+        let well_formed = api_key.starts_with(&self.prefix)
+            && api_key.len() >= self.min_length
+            && api_key
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+
+        if well_formed {
+            Ok(())
+        } else {
+            Err(ClaudeAuthError::InvalidCredentials)
+        }
+    }
 }
 
 /// Claude authentication errors
@@ -100,28 +335,210 @@ pub enum ClaudeAuthError {
     
     #[error("Concurrent limit exceeded")]
     ConcurrentLimitExceeded,
+
+    #[error("Insufficient memory to admit agent: {0}")]
+    MemoryPressure(String),
+
+    #[error("Invalid configuration: {0}")]
+    InvalidConfiguration(String),
+
+    #[error("No quota allocated for agent {0}")]
+    UnknownAgent(String),
+
+    #[error("Idempotency key {key} already allocated quota for agent {agent_id}")]
+    IdempotencyKeyConflict { key: String, agent_id: String },
+
+    #[error("Rate limited by the Claude API")]
+    RateLimited { retry_after: Option<std::time::Duration> },
+
+    #[error(transparent)]
+    LoginLocked(#[from] LoginLockError),
+
+    #[error("Claude credentials in {} are corrupt; log in again to recreate them", path.display())]
+    CorruptAuthFile { path: PathBuf },
+
+    #[error("Shutting down; no new agent quota is being allocated")]
+    ShuttingDown,
+}
+
+impl ClaudeAuthError {
+    /// Whether repeating the same call later may succeed
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            ClaudeAuthError::NetworkError(e) => network_error_is_retryable(e),
+            ClaudeAuthError::RateLimited { .. }
+            | ClaudeAuthError::ConcurrentLimitExceeded
+            | ClaudeAuthError::MemoryPressure(_) => true,
+            _ => false,
+        }
+    }
+
+    /// Whether the credentials or subscription were refused, so only a new login helps
+    pub fn is_auth_fatal(&self) -> bool {
+        match self {
+            ClaudeAuthError::NetworkError(e) => network_error_is_auth_fatal(e),
+            ClaudeAuthError::InvalidCredentials
+            | ClaudeAuthError::SubscriptionExpired
+            | ClaudeAuthError::OAuthError(_)
+            | ClaudeAuthError::CorruptAuthFile { .. } => true,
+            _ => false,
+        }
+    }
+
+    /// Wait requested by the API's `Retry-After` header
+    pub fn retry_after(&self) -> Option<std::time::Duration> {
+        match self {
+            ClaudeAuthError::RateLimited { retry_after } => *retry_after,
+            _ => None,
+        }
+    }
+}
+
+/// [`ClaudeAuthError::RateLimited`] for a 429 response
+fn rate_limit_error(response: &reqwest::Response) -> Option<ClaudeAuthError> {
+    (response.status() == reqwest::StatusCode::TOO_MANY_REQUESTS)
+        .then(|| ClaudeAuthError::RateLimited { retry_after: parse_retry_after(response) })
+}
+
+/// `claude_auth.json` of the active profile under `codex_home`
+pub fn claude_auth_file(codex_home: &Path) -> std::io::Result<PathBuf> {
+    active_profile_home(codex_home)
+        .map(|home| home.join("claude_auth.json"))
+        .map_err(std::io::Error::other)
+}
+
+/// `claude_auth.json` used under `config`: its `auth_file_path` override when set,
+/// otherwise the active profile's, see [`claude_auth_file`]
+pub fn claude_auth_file_for(codex_home: &Path, config: &ClaudeAuthConfig) -> std::io::Result<PathBuf> {
+    match &config.auth_file_path {
+        Some(path) => Ok(path.clone()),
+        None => claude_auth_file(codex_home),
+    }
+}
+
+/// Auth file that setup under `config` writes, checking an override's location first
+fn configured_auth_file(codex_home: &Path, config: &ClaudeAuthConfig) -> std::io::Result<PathBuf> {
+    let path = claude_auth_file_for(codex_home, config)?;
+    if config.auth_file_path.is_some() {
+        check_auth_file_location(&path)?;
+    }
+    Ok(path)
+}
+
+/// Check that an overridden auth file can be written where it is configured
+///
+/// The parent directory must exist and accept new files. A parent that other users can
+/// read is allowed, since the file itself is written owner-only, but is logged as a warning.
+pub fn check_auth_file_location(path: &Path) -> std::io::Result<()> {
+    let parent = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    if !std::fs::metadata(parent)?.is_dir() {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::NotADirectory,
+            format!("auth file directory {} is not a directory", parent.display()),
+        ));
+    }
+
+    let probe = parent.join(format!(".claude_auth.probe-{}", std::process::id()));
+    std::fs::OpenOptions::new().write(true).create_new(true).open(&probe).map_err(|e| {
+        std::io::Error::new(e.kind(), format!("auth file directory {} is not writable: {}", parent.display(), e))
+    })?;
+    let _ = std::fs::remove_file(&probe);
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        if std::fs::metadata(parent)?.permissions().mode() & 0o004 != 0 {
+            tracing::warn!(directory = %parent.display(), "Claude auth file directory is world-readable");
+        }
+    }
+    Ok(())
 }
 
 impl ClaudeAuth {
     /// Create Claude auth from codex home directory
+    ///
+    /// Credentials are read from the active profile, see [`claude_auth_file`]. A file
+    /// that can't be parsed fails with [`std::io::ErrorKind::InvalidData`] wrapping
+    /// [`ClaudeAuthError::CorruptAuthFile`]; [`load_from_codex_home`](Self::load_from_codex_home)
+    /// also tries to recover it from a backup.
     pub fn from_codex_home(
         codex_home: &Path,
-        preferred_auth_mode: ClaudeAuthMode,
+        _preferred_auth_mode: ClaudeAuthMode,
         originator: &str,
     ) -> std::io::Result<Option<Self>> {
-        let claude_auth_file = codex_home.join("claude_auth.json");
-        let client = reqwest::Client::builder()
-            .user_agent(format!("CodeProject/{} ({})", env!("CARGO_PKG_VERSION"), originator))
-            .timeout(std::time::Duration::from_secs(30))
-            .build()
-            .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e))?;
+        Self::from_codex_home_with_config(codex_home, ClaudeAuthConfig::default().with_originator(originator))
+    }
+
+    /// [`from_codex_home`](Self::from_codex_home) under `auth_config`, honouring its
+    /// `auth_file_path` override
+    pub fn from_codex_home_with_config(
+        codex_home: &Path,
+        auth_config: ClaudeAuthConfig,
+    ) -> std::io::Result<Option<Self>> {
+        let claude_auth_file = claude_auth_file_for(codex_home, &auth_config)?;
+        let client = Self::configured_client(&auth_config)?;
 
         if !claude_auth_file.exists() {
             return Ok(None);
         }
 
         let content = std::fs::read_to_string(&claude_auth_file)?;
-        let auth_data: serde_json::Value = serde_json::from_str(&content)?;
+        Self::from_auth_file_content(&claude_auth_file, &content, auth_config, client)
+    }
+
+    /// [`from_codex_home`](Self::from_codex_home) without blocking the async runtime
+    ///
+    /// The profile lookup runs on the blocking pool and the auth file is read with
+    /// `tokio::fs`; parsing and errors are the same as the sync version.
+    pub async fn from_codex_home_async(
+        codex_home: &Path,
+        _preferred_auth_mode: ClaudeAuthMode,
+        originator: &str,
+    ) -> std::io::Result<Option<Self>> {
+        Self::from_codex_home_with_config_async(codex_home, ClaudeAuthConfig::default().with_originator(originator)).await
+    }
+
+    /// [`from_codex_home_with_config`](Self::from_codex_home_with_config) without blocking the async runtime
+    pub async fn from_codex_home_with_config_async(
+        codex_home: &Path,
+        auth_config: ClaudeAuthConfig,
+    ) -> std::io::Result<Option<Self>> {
+        let home = codex_home.to_path_buf();
+        let lookup_config = auth_config.clone();
+        let claude_auth_file = tokio::task::spawn_blocking(move || claude_auth_file_for(&home, &lookup_config))
+            .await
+            .map_err(std::io::Error::other)??;
+        let client = Self::configured_client(&auth_config)?;
+
+        if !tokio::fs::try_exists(&claude_auth_file).await.unwrap_or(false) {
+            return Ok(None);
+        }
+
+        let content = tokio::fs::read_to_string(&claude_auth_file).await?;
+        // Resolving a `file:` API key reads the secret file
+        tokio::task::spawn_blocking(move || Self::from_auth_file_content(&claude_auth_file, &content, auth_config, client))
+            .await
+            .map_err(std::io::Error::other)?
+    }
+
+    fn configured_client(auth_config: &ClaudeAuthConfig) -> std::io::Result<reqwest::Client> {
+        auth_config
+            .http_client()
+            .map_err(std::io::Error::other)
+    }
+
+    /// Credentials from the contents of `claude_auth_file`, shared by the sync and async loaders
+    fn from_auth_file_content(
+        claude_auth_file: &Path,
+        content: &str,
+        auth_config: ClaudeAuthConfig,
+        client: reqwest::Client,
+    ) -> std::io::Result<Option<Self>> {
+        let auth_data: serde_json::Value = serde_json::from_str(content)
+            .map_err(|_| corrupt_auth_file(claude_auth_file))?;
 
         // Check if setup is required
         if auth_data.get("setup_required").and_then(|v| v.as_bool()).unwrap_or(false) {
@@ -130,23 +547,35 @@ impl ClaudeAuth {
 
         let quota_manager = Arc::new(RwLock::new(ClaudeQuotaManager::default()));
 
-        // Try to load API key
+        // Try to load API key, which may be a `file:` or `env:` reference
         if let Some(api_key) = auth_data.get("api_key").and_then(|v| v.as_str()) {
+            let api_key_ref = SecretRef::parse(api_key);
+            let resolved = api_key_ref
+                .resolve()
+                .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
             return Ok(Some(Self {
                 mode: ClaudeAuthMode::ApiKey,
                 subscription_tier: auth_data.get("subscription_tier")
                     .and_then(|v| v.as_str())
                     .map(|s| s.to_string()),
-                api_key: Some(api_key.to_string()),
+                api_key: Some(resolved),
+                api_key_ref: api_key_ref.is_indirect().then_some(api_key_ref),
                 oauth_tokens: None,
+                refreshed_oauth_tokens: Default::default(),
+                auth_file: Some(claude_auth_file.to_path_buf()),
                 client,
                 quota_manager,
+                auth_config: auth_config.clone(),
+                refresh_skew: DEFAULT_REFRESH_SKEW,
+                token_optimizer: Arc::new(TokenOptimizer::new()),
+                clock: system_clock(),
             }));
         }
 
         // Try to load OAuth tokens
         if let Some(tokens_data) = auth_data.get("oauth_tokens") {
-            let tokens: ClaudeTokenData = serde_json::from_value(tokens_data.clone())?;
+            let tokens: ClaudeTokenData = serde_json::from_value(tokens_data.clone())
+                .map_err(|_| corrupt_auth_file(claude_auth_file))?;
             
             let mode = match tokens.subscription_tier.as_str() {
                 "max" => ClaudeAuthMode::MaxSubscription,
@@ -158,29 +587,95 @@ impl ClaudeAuth {
                 mode,
                 subscription_tier: Some(tokens.subscription_tier.clone()),
                 api_key: None,
+                api_key_ref: None,
                 oauth_tokens: Some(tokens),
+                refreshed_oauth_tokens: Default::default(),
+                auth_file: Some(claude_auth_file.to_path_buf()),
                 client,
                 quota_manager,
+                auth_config,
+                refresh_skew: DEFAULT_REFRESH_SKEW,
+                token_optimizer: Arc::new(TokenOptimizer::new()),
+                clock: system_clock(),
             }));
         }
 
         Ok(None)
     }
 
+    /// [`from_codex_home`](Self::from_codex_home), recovering a corrupt auth file from backup
+    ///
+    /// A crash mid-write can leave `claude_auth.json` truncated. The damaged file is kept
+    /// next to it as `claude_auth.json.corrupt` and the newest backup holding a parseable
+    /// copy is restored; with no such backup this fails with
+    /// [`ClaudeAuthError::CorruptAuthFile`] so the user knows to log in again.
+    pub async fn load_from_codex_home(
+        codex_home: &Path,
+        _preferred_auth_mode: ClaudeAuthMode,
+        originator: &str,
+    ) -> Result<Option<Self>, ClaudeAuthError> {
+        Self::load_with_config(codex_home, ClaudeAuthConfig::default().with_originator(originator)).await
+    }
+
+    /// [`load_from_codex_home`](Self::load_from_codex_home) under `auth_config`, honouring
+    /// its `auth_file_path` override
+    pub async fn load_with_config(
+        codex_home: &Path,
+        auth_config: ClaudeAuthConfig,
+    ) -> Result<Option<Self>, ClaudeAuthError> {
+        let path = match Self::from_codex_home_with_config_async(codex_home, auth_config.clone()).await {
+            Err(e) => match corrupt_auth_file_path(&e) {
+                Some(path) => path.to_path_buf(),
+                None => return Err(e.into()),
+            },
+            loaded => return loaded.map_err(ClaudeAuthError::from),
+        };
+
+        let recovered_from = recover_auth_file(codex_home, &path).await;
+        log_corrupt_auth_file(&path, recovered_from.as_deref());
+        if recovered_from.is_none() {
+            return Err(ClaudeAuthError::CorruptAuthFile { path });
+        }
+
+        Self::from_codex_home_with_config_async(codex_home, auth_config).await.map_err(|e| {
+            match corrupt_auth_file_path(&e) {
+                Some(path) => ClaudeAuthError::CorruptAuthFile { path: path.to_path_buf() },
+                None => e.into(),
+            }
+        })
+    }
+
+    /// Re-read an API key configured as a `file:` or `env:` reference, so a rotated
+    /// secret is picked up; returns whether the key changed
+    pub fn refresh_api_key(&mut self) -> Result<bool, ClaudeAuthError> {
+        let Some(api_key_ref) = &self.api_key_ref else {
+            return Ok(false);
+        };
+        let resolved = api_key_ref
+            .resolve()
+            .map_err(|e| ClaudeAuthError::InvalidConfiguration(e.to_string()))?;
+        if self.api_key.as_ref() == Some(&resolved) {
+            return Ok(false);
+        }
+        self.api_key = Some(resolved);
+        Ok(true)
+    }
+
     /// Get authentication token
     pub async fn get_token(&self) -> Result<String, ClaudeAuthError> {
         match &self.mode {
             ClaudeAuthMode::ApiKey => {
-                self.api_key.clone()
+                self.api_key.as_ref()
+                    .map(|api_key| api_key.expose_secret().clone())
                     .ok_or(ClaudeAuthError::InvalidCredentials)
             }
             ClaudeAuthMode::MaxSubscription | ClaudeAuthMode::ProSubscription => {
-                if let Some(tokens) = &self.oauth_tokens {
-                    if tokens.expires_at > Utc::now() {
-                        Ok(tokens.access_token.clone())
+                if let Some(tokens) = self.current_oauth_tokens() {
+                    if self.within_refresh_skew(&tokens) {
+                        // Refresh ahead of expiry so the token can't lapse mid-request
+                        self.refresh_oauth_token(&tokens).await
                     } else {
-                        // Token expired, try to refresh
-                        self.refresh_oauth_token().await
+                        Ok(tokens.access_token.expose_secret().clone())
                     }
                 } else {
                     Err(ClaudeAuthError::InvalidCredentials)
@@ -189,7 +684,29 @@ impl ClaudeAuth {
         }
     }
 
+    /// Use `config` for endpoints and offline behaviour
+    ///
+    /// Endpoints are validated before each request, so a bad URL surfaces as
+    /// [`ClaudeAuthError::InvalidConfiguration`] rather than a request to the wrong host.
+    ///
+    /// The HTTP client is rebuilt so requests carry the config's user agent.
+    pub fn with_auth_config(mut self, config: ClaudeAuthConfig) -> Self {
+        match config.http_client() {
+            Ok(client) => self.client = client,
+            Err(e) => tracing::warn!(error = %e, "Failed to build HTTP client, keeping the previous one"),
+        }
+        self.auth_config = config;
+        self
+    }
+
+    fn endpoints(&self) -> Result<&ClaudeAuthConfig, ClaudeAuthError> {
+        validate_endpoints(&self.auth_config)?;
+        Ok(&self.auth_config)
+    }
+
     /// Check if user has Claude Max subscription
+    ///
+    /// In offline mode this is `assume_max_subscription` rather than a network check.
     pub async fn has_max_subscription(&self) -> bool {
         match self.verify_subscription().await {
             Ok(subscription) => subscription.tier == "max" && subscription.active,
@@ -199,20 +716,42 @@ impl ClaudeAuth {
 
     /// Verify Claude subscription status
     pub async fn verify_subscription(&self) -> Result<ClaudeSubscription, ClaudeAuthError> {
+        if self.auth_config.offline_mode {
+            log_verification_skipped("subscription", Some(self.auth_config.client_id.clone()));
+            let assumed = self.auth_config.offline_subscription();
+            return Ok(ClaudeSubscription {
+                tier: assumed.tier,
+                features: assumed.features,
+                quota_limit: 0,
+                quota_used: 0,
+                quota_reset_date: Some(self.clock.now() + chrono::Duration::days(1)),
+                active: assumed.active,
+            });
+        }
+
+        let endpoints = self.endpoints()?;
         let token = self.get_token().await?;
         
         let response = self.client
-            .get("https://api.anthropic.com/v1/subscription")
+            .get(&endpoints.subscription_endpoint)
             .bearer_auth(&token)
             .send()
             .await?;
 
+        if let Some(error) = rate_limit_error(&response) {
+            return Err(error);
+        }
         if !response.status().is_success() {
             return Err(ClaudeAuthError::SubscriptionExpired);
         }
 
         let subscription_data: serde_json::Value = response.json().await?;
-        
+        let quota_reset_date = parse_response_timestamp(&subscription_data, "quota_reset_date")
+            .unwrap_or_else(|e| {
+                tracing::warn!(error = %e, "Ignoring unparseable quota reset date from the subscription endpoint");
+                None
+            });
+
         Ok(ClaudeSubscription {
             tier: subscription_data.get("tier")
                 .and_then(|v| v.as_str())
@@ -231,38 +770,147 @@ impl ClaudeAuth {
             quota_used: subscription_data.get("quota_used")
                 .and_then(|v| v.as_u64())
                 .unwrap_or(0),
-            quota_reset_date: subscription_data.get("quota_reset_date")
-                .and_then(|v| v.as_str())
-                .and_then(|s| DateTime::parse_from_rfc3339(s).ok())
-                .map(|dt| dt.with_timezone(&Utc))
-                .unwrap_or_else(|| Utc::now() + chrono::Duration::days(1)),
+            quota_reset_date,
             active: subscription_data.get("active")
                 .and_then(|v| v.as_bool())
                 .unwrap_or(false),
         })
     }
 
+    /// Use `skew` as the proactive refresh window
+    pub fn with_refresh_skew(mut self, skew: std::time::Duration) -> Self {
+        self.refresh_skew = skew;
+        self
+    }
+
+    /// Read the time from `clock` for token expiry and the quota manager's daily reset
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        // Freshly built auth has no other holders of the quota lock
+        if let Ok(mut quota_manager) = self.quota_manager.try_write() {
+            quota_manager.set_clock(Arc::clone(&clock));
+        }
+        self.clock = clock;
+        self
+    }
+
+    /// Share a token optimizer so refreshes coalesce across auth instances
+    pub fn with_token_optimizer(mut self, optimizer: Arc<TokenOptimizer>) -> Self {
+        self.token_optimizer = optimizer;
+        self
+    }
+
+    /// Whether the OAuth access token is close enough to expiry that [`get_token`](Self::get_token)
+    /// will refresh it
+    pub fn needs_token_refresh(&self) -> bool {
+        matches!(self.mode, ClaudeAuthMode::MaxSubscription | ClaudeAuthMode::ProSubscription)
+            && self.current_oauth_tokens().is_some_and(|tokens| self.within_refresh_skew(&tokens))
+    }
+
+    /// OAuth tokens in use: the latest refresh's if there was one, otherwise `oauth_tokens`
+    pub fn current_oauth_tokens(&self) -> Option<ClaudeTokenData> {
+        self.refreshed_oauth_tokens
+            .read()
+            .unwrap()
+            .clone()
+            .or_else(|| self.oauth_tokens.clone())
+    }
+
+    fn within_refresh_skew(&self, tokens: &ClaudeTokenData) -> bool {
+        let skew = chrono::Duration::from_std(self.refresh_skew).unwrap_or_else(|_| chrono::Duration::zero());
+        tokens.expires_at - skew <= self.clock.now()
+    }
+
     /// Refresh OAuth token
-    async fn refresh_oauth_token(&self) -> Result<String, ClaudeAuthError> {
-        let tokens = self.oauth_tokens.as_ref()
-            .ok_or(ClaudeAuthError::InvalidCredentials)?;
-        
+    ///
+    /// Concurrent callers holding the same refresh token share a single request. The new
+    /// tokens, including a rotated refresh token, replace `tokens` for every clone and are
+    /// saved to [`auth_file`](Self::auth_file).
+    async fn refresh_oauth_token(&self, tokens: &ClaudeTokenData) -> Result<String, ClaudeAuthError> {
         let refresh_token = tokens.refresh_token.as_ref()
+            .map(|refresh_token| refresh_token.expose_secret().as_str())
             .ok_or(ClaudeAuthError::InvalidCredentials)?;
 
+        // Callers are grouped by a digest so the token itself never becomes a map key or id
+        let credential_id = refresh_credential_id(refresh_token);
+        // The shared result only carries a message; keep our own error's type when we made the request
+        let mut own_error = None;
+        let result = self.token_optimizer
+            .refresh_coalesced(&credential_id, || async {
+                let start = std::time::Instant::now();
+                let outcome = self.request_token_refresh(refresh_token).await;
+                let refreshed = outcome.as_ref().ok();
+                let result = TokenRefreshResult {
+                    request_id: credential_id.clone(),
+                    success: outcome.is_ok(),
+                    new_token: refreshed.map(|refreshed| refreshed.access_token.clone()),
+                    new_refresh_token: refreshed.and_then(|refreshed| refreshed.refresh_token.clone()),
+                    expires_at: refreshed.and_then(|refreshed| refreshed.expires_at),
+                    error: outcome.as_ref().err().map(|e| e.to_string()),
+                    refresh_time_ms: start.elapsed().as_millis() as u64,
+                };
+                own_error = outcome.err();
+                result
+            })
+            .await;
+
+        let token = match result.new_token {
+            Some(token) if result.success => token,
+            _ => return Err(own_error.unwrap_or_else(|| ClaudeAuthError::OAuthError(
+                result.error.unwrap_or_else(|| "Token refresh failed".to_string()),
+            ))),
+        };
+
+        let refreshed = ClaudeTokenData {
+            access_token: token.clone().into(),
+            // Servers that don't rotate refresh tokens leave the old one valid
+            refresh_token: result.new_refresh_token.map(Secret::from).or_else(|| tokens.refresh_token.clone()),
+            // Without an expiry in the response the old one is kept, so the next call refreshes again
+            expires_at: result.expires_at.unwrap_or(tokens.expires_at),
+            ..tokens.clone()
+        };
+        self.store_refreshed_tokens(refreshed).await;
+        Ok(token)
+    }
+
+    /// Make `refreshed` the current tokens and save them to the auth file
+    ///
+    /// Callers that shared a refresh all land here; only the first to arrive writes the file.
+    /// A failed write is logged rather than returned, since the new tokens are already usable.
+    async fn store_refreshed_tokens(&self, refreshed: ClaudeTokenData) {
+        {
+            let mut current = self.refreshed_oauth_tokens.write().unwrap();
+            if current.as_ref().is_some_and(|current| current.access_token == refreshed.access_token) {
+                return;
+            }
+            *current = Some(refreshed.clone());
+        }
+
+        let Some(auth_file) = &self.auth_file else {
+            return;
+        };
+        if let Err(e) = save_oauth_tokens(auth_file, &refreshed).await {
+            tracing::warn!(path = %auth_file.display(), error = %e, "Failed to save refreshed Claude OAuth tokens");
+        }
+    }
+
+    async fn request_token_refresh(&self, refresh_token: &str) -> Result<RefreshedTokens, ClaudeAuthError> {
+        let endpoints = self.endpoints()?;
         let refresh_request = serde_json::json!({
             "grant_type": "refresh_token",
             "refresh_token": refresh_token,
-            "client_id": "code_project_client_id", // Would be configured
+            "client_id": endpoints.client_id,
         });
 
         let response = self.client
-            .post("https://auth.anthropic.com/oauth/token")
+            .post(&endpoints.token_endpoint)
             .header("Content-Type", "application/json")
             .json(&refresh_request)
             .send()
             .await?;
 
+        if let Some(error) = rate_limit_error(&response) {
+            return Err(error);
+        }
         if !response.status().is_success() {
             return Err(ClaudeAuthError::OAuthError("Token refresh failed".to_string()));
         }
@@ -272,13 +920,83 @@ impl ClaudeAuth {
             .and_then(|v| v.as_str())
             .ok_or(ClaudeAuthError::OAuthError("No access token in response".to_string()))?;
 
-        Ok(new_access_token.to_string())
+        Ok(RefreshedTokens {
+            access_token: new_access_token.to_string(),
+            refresh_token: token_response.get("refresh_token")
+                .and_then(|v| v.as_str())
+                .map(|s| s.to_string()),
+            expires_at: token_response.get("expires_in")
+                .and_then(|v| v.as_i64())
+                .map(|expires_in| self.clock.now() + chrono::Duration::seconds(expires_in)),
+        })
+    }
+
+    /// Revoke OAuth tokens at Anthropic; API keys have nothing to revoke
+    pub async fn revoke_oauth_tokens(&self) -> Result<(), ClaudeAuthError> {
+        let Some(tokens) = self.current_oauth_tokens() else {
+            return Ok(());
+        };
+        if self.auth_config.offline_mode {
+            log_verification_skipped("revocation", Some(self.auth_config.client_id.clone()));
+            return Ok(());
+        }
+
+        let endpoints = self.endpoints()?;
+        if let Some(refresh_token) = &tokens.refresh_token {
+            revoke_token(&self.client, endpoints, refresh_token.expose_secret(), "refresh_token")
+                .await
+                .map_err(|e| ClaudeAuthError::OAuthError(e.to_string()))?;
+        }
+        revoke_token(&self.client, endpoints, tokens.access_token.expose_secret(), "access_token")
+            .await
+            .map_err(|e| ClaudeAuthError::OAuthError(e.to_string()))
     }
 
     /// Allocate quota for an agent
+    ///
+    /// The daily limit is refreshed from the subscription first when it is due.
     pub async fn allocate_agent_quota(&self, agent_id: &str, estimated_usage: u64) -> Result<AgentQuota, ClaudeAuthError> {
+        self.allocate_agent_quota_with_key(agent_id, estimated_usage, None).await
+    }
+
+    /// [`allocate_agent_quota`](Self::allocate_agent_quota), deduplicated by `idempotency_key`,
+    /// see [`ClaudeQuotaManager::allocate_quota_with_key`]
+    pub async fn allocate_agent_quota_with_key(
+        &self,
+        agent_id: &str,
+        estimated_usage: u64,
+        idempotency_key: Option<&str>,
+    ) -> Result<AgentQuota, ClaudeAuthError> {
+        self.refresh_quota_limit().await;
+        let mut quota_manager = self.quota_manager.write().await;
+        quota_manager.allocate_quota_with_key(agent_id, estimated_usage, idempotency_key).await
+    }
+
+    /// Refresh the daily limit from the verified subscription's `quota_limit` when due
+    ///
+    /// When the subscription can't be queried the current limit stays, which is the
+    /// built-in default until a check has succeeded. Returns the limit in effect.
+    pub async fn refresh_quota_limit(&self) -> u64 {
+        {
+            let quota_manager = self.quota_manager.read().await;
+            if !quota_manager.needs_limit_refresh() {
+                return quota_manager.daily_limit;
+            }
+        }
+
+        let subscription = match self.verify_subscription().await {
+            Ok(subscription) => Some(subscription),
+            Err(e) => {
+                tracing::warn!(error = %e, "Subscription quota unavailable, keeping current daily limit");
+                None
+            }
+        };
         let mut quota_manager = self.quota_manager.write().await;
-        quota_manager.allocate_quota(agent_id, estimated_usage).await
+        quota_manager.record_subscription_limit(subscription.as_ref().map(|subscription| subscription.quota_limit));
+        if let Some(reset_date) = subscription.and_then(|subscription| subscription.quota_reset_date) {
+            quota_manager.subscription_reset_date = Some(reset_date);
+        }
+        quota_manager.daily_limit
     }
 
     /// Release quota from an agent
@@ -293,26 +1011,115 @@ impl ClaudeAuth {
         Ok(quota_manager.get_remaining_quota())
     }
 
+    /// Time left until the quota resets, see [`ClaudeQuotaManager::quota_reset_in`]
+    pub async fn quota_reset_in(&self) -> Option<std::time::Duration> {
+        self.quota_manager.read().await.quota_reset_in()
+    }
+
+    /// Stop allocating quota and wait up to `grace` for agents to release theirs
+    ///
+    /// Whatever is still allocated at the deadline is reclaimed. Allocation stays closed
+    /// afterwards, so this is only for shutdown.
+    pub async fn drain_agents(&self, grace: std::time::Duration) -> AgentDrainReport {
+        let waiting: Vec<String> = {
+            let mut quota_manager = self.quota_manager.write().await;
+            quota_manager.begin_drain();
+            quota_manager.active_agents.keys().cloned().collect()
+        };
+
+        let deadline = tokio::time::Instant::now() + grace;
+        while !self.quota_manager.read().await.active_agents.is_empty() {
+            let now = tokio::time::Instant::now();
+            if now >= deadline {
+                break;
+            }
+            tokio::time::sleep(AGENT_DRAIN_POLL_INTERVAL.min(deadline - now)).await;
+        }
+
+        let reclaimed = self.quota_manager.write().await.reclaim_all();
+        let released = waiting
+            .into_iter()
+            .filter(|agent_id| reclaimed.iter().all(|quota| &quota.agent_id != agent_id))
+            .collect();
+        AgentDrainReport { released, reclaimed }
+    }
+
+    /// Snapshot of the quota accounting
+    pub async fn quota_snapshot(&self) -> QuotaSnapshot {
+        self.quota_manager.read().await.snapshot()
+    }
+
+    /// Start reclaiming quota from agents that never released it
+    pub async fn start_quota_sweeper(&self) -> tokio::task::JoinHandle<()> {
+        ClaudeQuotaManager::spawn_expiry_sweeper(Arc::clone(&self.quota_manager)).await
+    }
+
     /// Setup Claude authentication with API key
     pub async fn setup_with_api_key(codex_home: &Path, api_key: &str) -> Result<(), ClaudeAuthError> {
-        let claude_auth_file = codex_home.join("claude_auth.json");
-        
-        // Verify API key works
-        let client = reqwest::Client::new();
-        let test_response = client
-            .post("https://api.anthropic.com/v1/messages")
-            .bearer_auth(api_key)
-            .header("Content-Type", "application/json")
-            .header("anthropic-version", "2023-06-01")
-            .json(&serde_json::json!({
-                "model": "claude-3-haiku-20240307",
-                "max_tokens": 10,
-                "messages": [{"role": "user", "content": "test"}]
-            }))
-            .send()
-            .await?;
+        Self::setup_with_api_key_configured(codex_home, api_key, &ClaudeAuthConfig::default()).await
+    }
+
+    /// Setup Claude authentication with API key under `config`
+    ///
+    /// Offline mode still checks the key format but trusts it without a network round trip.
+    pub async fn setup_with_api_key_configured(
+        codex_home: &Path,
+        api_key: &str,
+        config: &ClaudeAuthConfig,
+    ) -> Result<(), ClaudeAuthError> {
+        validate_endpoints(config)?;
+        let claude_auth_file = configured_auth_file(codex_home, config)?;
+        let format = ApiKeyFormat::default();
+        if config.offline_mode {
+            let client_id = config.client_id.clone();
+            Self::setup_api_key_file(codex_home, claude_auth_file, api_key, &format, |_| async move {
+                log_verification_skipped("api_key", Some(client_id));
+                Ok(true)
+            })
+            .await
+        } else {
+            let client = http_client(config)?;
+            let messages_endpoint = config.messages_endpoint.clone();
+            Self::setup_api_key_file(codex_home, claude_auth_file, api_key, &format, |key| {
+                verify_api_key_online(client, messages_endpoint, key)
+            })
+            .await
+        }
+    }
+
+    /// Setup Claude authentication with API key, using a custom key format and verifier
+    ///
+    /// Malformed keys are rejected before `verify` is called, so no request is made for them.
+    pub async fn setup_with_api_key_using<F, Fut>(
+        codex_home: &Path,
+        api_key: &str,
+        format: &ApiKeyFormat,
+        verify: F,
+    ) -> Result<(), ClaudeAuthError>
+    where
+        F: FnOnce(String) -> Fut,
+        Fut: Future<Output = Result<bool, ClaudeAuthError>>,
+    {
+        Self::setup_api_key_file(codex_home, claude_auth_file(codex_home)?, api_key, format, verify).await
+    }
+
+    async fn setup_api_key_file<F, Fut>(
+        codex_home: &Path,
+        claude_auth_file: PathBuf,
+        api_key: &str,
+        format: &ApiKeyFormat,
+        verify: F,
+    ) -> Result<(), ClaudeAuthError>
+    where
+        F: FnOnce(String) -> Fut,
+        Fut: Future<Output = Result<bool, ClaudeAuthError>>,
+    {
+        let _lock = LoginLock::acquire(codex_home)?;
+
+        format.validate(api_key)?;
 
-        if !test_response.status().is_success() {
+        // Verify API key works
+        if !verify(api_key.to_string()).await? {
             return Err(ClaudeAuthError::InvalidCredentials);
         }
 
@@ -328,24 +1135,25 @@ impl ClaudeAuth {
         });
 
         let content = serde_json::to_string_pretty(&auth_data)?;
-        tokio::fs::write(&claude_auth_file, content).await?;
-
-        // Set secure permissions
-        #[cfg(unix)]
-        {
-            use std::os::unix::fs::PermissionsExt;
-            let mut perms = tokio::fs::metadata(&claude_auth_file).await?.permissions();
-            perms.set_mode(0o600);
-            tokio::fs::set_permissions(&claude_auth_file, perms).await?;
-        }
+        write_atomic_async(&claude_auth_file, content, 0o600).await?;
 
         Ok(())
     }
 
     /// Setup Claude authentication with OAuth
     pub async fn setup_with_oauth(codex_home: &Path, tokens: ClaudeTokenData) -> Result<(), ClaudeAuthError> {
-        let claude_auth_file = codex_home.join("claude_auth.json");
-        
+        Self::setup_with_oauth_configured(codex_home, tokens, &ClaudeAuthConfig::default()).await
+    }
+
+    /// Setup Claude authentication with OAuth, writing to `config`'s auth file
+    pub async fn setup_with_oauth_configured(
+        codex_home: &Path,
+        tokens: ClaudeTokenData,
+        config: &ClaudeAuthConfig,
+    ) -> Result<(), ClaudeAuthError> {
+        let claude_auth_file = configured_auth_file(codex_home, config)?;
+        let _lock = LoginLock::acquire(codex_home)?;
+
         let auth_data = serde_json::json!({
             "version": "2.0",
             "enabled": true,
@@ -358,45 +1166,237 @@ impl ClaudeAuth {
         });
 
         let content = serde_json::to_string_pretty(&auth_data)?;
-        tokio::fs::write(&claude_auth_file, content).await?;
-
-        // Set secure permissions
-        #[cfg(unix)]
-        {
-            use std::os::unix::fs::PermissionsExt;
-            let mut perms = tokio::fs::metadata(&claude_auth_file).await?.permissions();
-            perms.set_mode(0o600);
-            tokio::fs::set_permissions(&claude_auth_file, perms).await?;
-        }
+        write_atomic_async(&claude_auth_file, content, 0o600).await?;
 
         Ok(())
     }
 }
 
-impl ClaudeQuotaManager {
-    /// Allocate quota for an agent
-    pub async fn allocate_quota(&mut self, agent_id: &str, estimated_usage: u64) -> Result<AgentQuota, ClaudeAuthError> {
-        // Check if we have enough quota remaining
-        let remaining = self.get_remaining_quota();
-        if remaining < estimated_usage {
-            return Err(ClaudeAuthError::QuotaExceeded {
-                requested: estimated_usage,
-                available: remaining,
-            });
-        }
+/// Tokens from a refresh response
+struct RefreshedTokens {
+    access_token: String,
+    /// Present when the server rotated the refresh token
+    refresh_token: Option<String>,
+    /// From `expires_in`, when the server sent one
+    expires_at: Option<DateTime<Utc>>,
+}
 
-        // Check concurrent agent limit
-        if self.active_agents.len() >= self.concurrent_limit as usize {
+/// Replace the OAuth tokens in the auth file at `path`, keeping its other fields
+async fn save_oauth_tokens(path: &Path, tokens: &ClaudeTokenData) -> Result<(), ClaudeAuthError> {
+    let content = tokio::fs::read_to_string(path).await?;
+    let mut auth_data: serde_json::Value = serde_json::from_str(&content)?;
+    auth_data["oauth_tokens"] = serde_json::to_value(tokens)?;
+    auth_data["last_refreshed"] = serde_json::Value::String(Utc::now().to_rfc3339());
+    write_atomic_async(path, serde_json::to_string_pretty(&auth_data)?, 0o600).await?;
+    Ok(())
+}
+
+/// Error returned by [`ClaudeAuth::from_codex_home`] for an unparseable auth file
+fn corrupt_auth_file(path: &Path) -> std::io::Error {
+    std::io::Error::new(
+        std::io::ErrorKind::InvalidData,
+        ClaudeAuthError::CorruptAuthFile { path: path.to_path_buf() },
+    )
+}
+
+/// Path of the corrupt auth file an error from [`corrupt_auth_file`] refers to
+fn corrupt_auth_file_path(error: &std::io::Error) -> Option<&Path> {
+    match error.get_ref()?.downcast_ref::<ClaudeAuthError>()? {
+        ClaudeAuthError::CorruptAuthFile { path } => Some(path),
+        _ => None,
+    }
+}
+
+/// Restore `path` from the newest backup holding a parseable copy, returning that backup's id
+///
+/// The corrupt file is copied aside first so nothing is lost if every backup is bad.
+async fn recover_auth_file(codex_home: &Path, path: &Path) -> Option<String> {
+    let relative = path.strip_prefix(codex_home).ok()?;
+    let relative = relative
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/");
+
+    let mut corrupt_copy = path.as_os_str().to_owned();
+    corrupt_copy.push(".corrupt");
+    tokio::fs::copy(path, corrupt_copy).await.ok()?;
+
+    let backups = BackupManager::new(codex_home, &MigrationConfig::default());
+    for handle in backups.list_backups().await.ok()? {
+        let holds_file = backups
+            .restorable_files(&handle)
+            .await
+            .is_ok_and(|files| files.contains(&relative));
+        if !holds_file || backups.restore_file(&handle, &relative).await.is_err() {
+            continue;
+        }
+        let restored = tokio::fs::read_to_string(path).await.unwrap_or_default();
+        if serde_json::from_str::<serde_json::Value>(&restored).is_ok() {
+            return Some(handle.id);
+        }
+    }
+    None
+}
+
+fn log_corrupt_auth_file(path: &Path, recovered_from: Option<&str>) {
+    match recovered_from {
+        Some(backup_id) => tracing::warn!(path = %path.display(), backup_id, "Recovered corrupt Claude auth file from backup"),
+        None => tracing::error!(path = %path.display(), "Claude auth file is corrupt and no backup could restore it"),
+    }
+    let _ = log_audit_event(AuditEvent {
+        timestamp: Utc::now(),
+        event_type: AuthEventType::AuthFileCorrupted,
+        user_id: None,
+        session_id: None,
+        client_id: None,
+        ip_address: None,
+        user_agent: None,
+        success: recovered_from.is_some(),
+        error_message: recovered_from.is_none().then(|| "No backup could restore the auth file".to_string()),
+        metadata: serde_json::json!({
+            "path": path.display().to_string(),
+            "recovered_from": recovered_from,
+        }),
+        severity: if recovered_from.is_some() { Severity::Warning } else { Severity::Error },
+    });
+}
+
+/// Endpoint validation, reported with this module's error type
+fn validate_endpoints(config: &ClaudeAuthConfig) -> Result<(), ClaudeAuthError> {
+    config
+        .validate_endpoints()
+        .map_err(|e| ClaudeAuthError::InvalidConfiguration(e.to_string()))
+}
+
+fn http_client(config: &ClaudeAuthConfig) -> Result<reqwest::Client, ClaudeAuthError> {
+    config.http_client().map_err(|e| match e {
+        crate::claude_auth::ClaudeAuthError::Network(e) => ClaudeAuthError::NetworkError(e),
+        e => ClaudeAuthError::InvalidConfiguration(e.to_string()),
+    })
+}
+
+/// Verify an API key with a minimal Messages API request
+async fn verify_api_key_online(
+    client: reqwest::Client,
+    messages_endpoint: String,
+    api_key: String,
+) -> Result<bool, ClaudeAuthError> {
+    let test_response = client
+        .post(&messages_endpoint)
+        .bearer_auth(api_key)
+        .header("Content-Type", "application/json")
+        .header("anthropic-version", "2023-06-01")
+        .json(&serde_json::json!({
+            "model": "claude-3-haiku-20240307",
+            "max_tokens": 10,
+            "messages": [{"role": "user", "content": "test"}]
+        }))
+        .send()
+        .await?;
+
+    Ok(test_response.status().is_success())
+}
+
+impl ClaudeQuotaManager {
+    /// Gate agent allocation on memory headroom tracked by the optimizer
+    pub fn with_memory_optimizer(mut self, optimizer: Arc<MemoryOptimizer>) -> Self {
+        self.memory_optimizer = Some(optimizer);
+        self
+    }
+
+    /// Sweep expired allocations at `interval` once the sweeper is spawned
+    pub fn with_sweep_interval(mut self, interval: std::time::Duration) -> Self {
+        self.sweep_interval = interval;
+        self
+    }
+
+    /// Reset the daily quota at a fixed local time instead of 24h after the last reset
+    pub fn with_reset_schedule(mut self, schedule: QuotaResetSchedule) -> Self {
+        self.reset_schedule = Some(schedule);
+        self
+    }
+
+    /// Read the time from `clock` for daily resets and allocation timestamps
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.set_clock(clock);
+        self
+    }
+
+    fn set_clock(&mut self, clock: Arc<dyn Clock>) {
+        // The reset window starts from the new clock's notion of now
+        self.last_reset = clock.now();
+        self.clock = clock;
+    }
+
+    /// Allocate quota for an agent
+    pub async fn allocate_quota(&mut self, agent_id: &str, estimated_usage: u64) -> Result<AgentQuota, ClaudeAuthError> {
+        self.allocate_quota_with_key(agent_id, estimated_usage, None).await
+    }
+
+    /// Allocate quota for an agent, at most once per `idempotency_key`
+    ///
+    /// While an allocation made with the same key is held, it is returned as is instead of
+    /// charging a second one, so a retried or resumed agent start isn't counted twice. The
+    /// key is forgotten once that allocation is released, expires or is reclaimed.
+    pub async fn allocate_quota_with_key(
+        &mut self,
+        agent_id: &str,
+        estimated_usage: u64,
+        idempotency_key: Option<&str>,
+    ) -> Result<AgentQuota, ClaudeAuthError> {
+        if self.draining {
+            return Err(ClaudeAuthError::ShuttingDown);
+        }
+
+        if self.should_reset_quota() {
+            self.reset_daily_quota();
+        }
+
+        if let Some(key) = idempotency_key {
+            let existing = self.active_agents.values().find(|quota| quota.idempotency_key.as_deref() == Some(key));
+            match existing {
+                Some(quota) if quota.agent_id == agent_id => return Ok(quota.clone()),
+                Some(quota) => {
+                    return Err(ClaudeAuthError::IdempotencyKeyConflict {
+                        key: key.to_string(),
+                        agent_id: quota.agent_id.clone(),
+                    })
+                }
+                None => {}
+            }
+        }
+
+        // Check if we have enough quota remaining
+        let remaining = self.get_remaining_quota();
+        if remaining < estimated_usage {
+            return Err(ClaudeAuthError::QuotaExceeded {
+                requested: estimated_usage,
+                available: remaining,
+            });
+        }
+
+        // Check concurrent agent limit
+        if self.active_agents.len() >= self.concurrent_limit as usize {
             return Err(ClaudeAuthError::ConcurrentLimitExceeded);
         }
 
+        // Bound concurrency by memory headroom, not just the fixed agent limit
+        if let Some(optimizer) = &self.memory_optimizer {
+            let lease = optimizer
+                .try_admit_agent(self.agent_memory_estimate_mb)
+                .map_err(|e| ClaudeAuthError::MemoryPressure(e.to_string()))?;
+            self.memory_leases.insert(agent_id.to_string(), Arc::new(lease));
+        }
+
         // Create quota allocation
         let quota = AgentQuota {
             agent_id: agent_id.to_string(),
             allocated_tokens: estimated_usage,
             used_tokens: 0,
-            created_at: Utc::now(),
-            expires_at: Utc::now() + chrono::Duration::hours(2),
+            created_at: self.clock.now(),
+            expires_at: self.clock.now() + chrono::Duration::hours(2),
+            idempotency_key: idempotency_key.map(str::to_string),
         };
 
         self.active_agents.insert(agent_id.to_string(), quota.clone());
@@ -407,6 +1407,7 @@ impl ClaudeQuotaManager {
 
     /// Release quota from an agent
     pub async fn release_quota(&mut self, agent_id: &str) -> Result<u64, ClaudeAuthError> {
+        self.memory_leases.remove(agent_id);
         if let Some(quota) = self.active_agents.remove(agent_id) {
             let unused = quota.allocated_tokens.saturating_sub(quota.used_tokens);
             self.current_usage = self.current_usage.saturating_sub(unused);
@@ -428,60 +1429,369 @@ impl ClaudeQuotaManager {
         }
     }
 
+    /// Debit the tokens a completed request actually consumed
+    ///
+    /// Usage within the allocation is charged as is; anything beyond it is handled
+    /// according to [`overage_policy`](Self::overage_policy).
+    pub fn record_request_usage(&mut self, agent_id: &str, tokens_used: u64) -> Result<UsageDebit, ClaudeAuthError> {
+        let remaining_daily = self.get_remaining_quota();
+        let policy = self.overage_policy;
+        let quota = self
+            .active_agents
+            .get_mut(agent_id)
+            .ok_or_else(|| ClaudeAuthError::UnknownAgent(agent_id.to_string()))?;
+
+        let headroom = quota.allocated_tokens.saturating_sub(quota.used_tokens);
+        let overage = tokens_used.saturating_sub(headroom);
+        let expanded_tokens = match policy {
+            OveragePolicy::Clamp => 0,
+            OveragePolicy::ExpandWithinDailyQuota => overage.min(remaining_daily),
+        };
+
+        quota.allocated_tokens += expanded_tokens;
+        let charged_tokens = tokens_used - overage + expanded_tokens;
+        quota.used_tokens += charged_tokens;
+        self.current_usage += expanded_tokens;
+
+        let debit = UsageDebit {
+            agent_id: agent_id.to_string(),
+            charged_tokens,
+            expanded_tokens,
+            unaccounted_tokens: overage - expanded_tokens,
+        };
+        if debit.unaccounted_tokens > 0 {
+            log_quota_overage(quota, &debit, self.clock.now());
+        }
+        Ok(debit)
+    }
+
+    /// Handle overages with `policy`
+    pub fn with_overage_policy(mut self, policy: OveragePolicy) -> Self {
+        self.overage_policy = policy;
+        self
+    }
+
+    /// Re-read the subscription limit every `interval`
+    pub fn with_limit_refresh_interval(mut self, interval: chrono::Duration) -> Self {
+        self.limit_refresh_interval = interval;
+        self
+    }
+
+    /// Whether `daily_limit` is due to be refreshed from the subscription
+    pub fn needs_limit_refresh(&self) -> bool {
+        self.limit_checked_at
+            .is_none_or(|checked_at| self.clock.now() - checked_at >= self.limit_refresh_interval)
+    }
+
+    /// Record a subscription check, adopting its `quota_limit` as the daily limit
+    ///
+    /// `None` (the subscription couldn't be queried) or a zero limit keeps the current
+    /// one. Existing allocations are never revoked: after a cut they stay valid and new
+    /// allocations wait until usage drops under the new limit.
+    pub fn record_subscription_limit(&mut self, quota_limit: Option<u64>) {
+        self.limit_checked_at = Some(self.clock.now());
+        if let Some(limit) = quota_limit.filter(|&limit| limit > 0) {
+            self.daily_limit = limit;
+            self.limit_from_subscription = true;
+        }
+    }
+
+    /// Drop allocations past `expires_at` and return their unused tokens to the pool
+    ///
+    /// Covers agents that crashed without releasing their quota. Returns the
+    /// reclaimed agent ids.
+    pub fn sweep_expired(&mut self) -> Vec<String> {
+        let now = self.clock.now();
+        let expired: Vec<String> = self
+            .active_agents
+            .values()
+            .filter(|quota| quota.expires_at <= now)
+            .map(|quota| quota.agent_id.clone())
+            .collect();
+
+        self.reclaim(&expired, "expired");
+        expired
+    }
+
+    /// Refuse new allocations from now on; existing agents keep their quota
+    pub fn begin_drain(&mut self) {
+        self.draining = true;
+    }
+
+    /// Whether [`begin_drain`](Self::begin_drain) has been called
+    pub fn is_draining(&self) -> bool {
+        self.draining
+    }
+
+    /// Reclaim every remaining allocation, returning what was taken back
+    pub fn reclaim_all(&mut self) -> Vec<AgentQuota> {
+        let agent_ids: Vec<String> = self.active_agents.keys().cloned().collect();
+        self.reclaim(&agent_ids, "shutdown")
+    }
+
+    /// Return the unused tokens of `agent_ids` to the pool, logging each as reclaimed
+    fn reclaim(&mut self, agent_ids: &[String], reason: &str) -> Vec<AgentQuota> {
+        let now = self.clock.now();
+        let mut reclaimed = Vec::new();
+        for agent_id in agent_ids {
+            self.memory_leases.remove(agent_id);
+            if let Some(quota) = self.active_agents.remove(agent_id) {
+                let unused = quota.allocated_tokens.saturating_sub(quota.used_tokens);
+                self.current_usage = self.current_usage.saturating_sub(unused);
+                log_quota_reclaimed(&quota, unused, now, reason);
+                reclaimed.push(quota);
+            }
+        }
+        reclaimed
+    }
+
+    /// Current accounting, for persisting across restarts
+    pub fn snapshot(&self) -> QuotaSnapshot {
+        let mut active_agents: Vec<AgentQuota> = self.active_agents.values().cloned().collect();
+        active_agents.sort_by(|a, b| a.agent_id.cmp(&b.agent_id));
+        QuotaSnapshot {
+            daily_limit: self.daily_limit,
+            current_usage: self.current_usage,
+            last_reset: self.last_reset,
+            limit_from_subscription: self.limit_from_subscription,
+            active_agents,
+            taken_at: self.clock.now(),
+        }
+    }
+
+    /// Run [`sweep_expired`](Self::sweep_expired) every `sweep_interval`
+    ///
+    /// The task ends once every other handle to the manager has been dropped.
+    pub async fn spawn_expiry_sweeper(manager: Arc<RwLock<ClaudeQuotaManager>>) -> tokio::task::JoinHandle<()> {
+        let period = manager.read().await.sweep_interval;
+        let manager = Arc::downgrade(&manager);
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(period);
+            // The first tick completes immediately
+            ticker.tick().await;
+            loop {
+                ticker.tick().await;
+                let Some(manager) = manager.upgrade() else {
+                    break;
+                };
+                let reclaimed = manager.write().await.sweep_expired();
+                if !reclaimed.is_empty() {
+                    tracing::info!(agents = ?reclaimed, "Reclaimed expired agent quota");
+                }
+            }
+        })
+    }
+
+    /// Usage of each active agent, highest usage first
+    pub fn usage_by_agent(&self) -> Vec<AgentUsageReport> {
+        let now = self.clock.now();
+        let mut reports: Vec<AgentUsageReport> = self
+            .active_agents
+            .values()
+            .map(|quota| AgentUsageReport {
+                agent_id: quota.agent_id.clone(),
+                allocated_tokens: quota.allocated_tokens,
+                used_tokens: quota.used_tokens,
+                utilization_percent: if quota.allocated_tokens == 0 {
+                    0.0
+                } else {
+                    quota.used_tokens as f64 / quota.allocated_tokens as f64 * 100.0
+                },
+                age_seconds: (now - quota.created_at).num_seconds().max(0),
+                expires_at: quota.expires_at,
+                near_expiry: quota.expires_at - now <= AGENT_EXPIRY_WARNING,
+            })
+            .collect();
+
+        reports.sort_by(|a, b| {
+            b.used_tokens
+                .cmp(&a.used_tokens)
+                .then_with(|| a.agent_id.cmp(&b.agent_id))
+        });
+        reports
+    }
+
+    /// [`usage_by_agent`](Self::usage_by_agent) with totals across all agents
+    pub fn usage_summary(&self) -> AgentUsageSummary {
+        let agents = self.usage_by_agent();
+        AgentUsageSummary {
+            total_allocated: agents.iter().map(|a| a.allocated_tokens).sum(),
+            total_used: agents.iter().map(|a| a.used_tokens).sum(),
+            near_expiry_count: agents.iter().filter(|a| a.near_expiry).count(),
+            agents,
+        }
+    }
+
     /// Check if quota reset is needed
     pub fn should_reset_quota(&self) -> bool {
-        Utc::now() - self.last_reset > chrono::Duration::days(1)
+        let now = self.clock.now();
+        match &self.reset_schedule {
+            Some(schedule) => now >= schedule.next_reset_after(self.last_reset),
+            None => now - self.last_reset > chrono::Duration::days(1),
+        }
+    }
+
+    /// When the local daily window ends, per [`reset_schedule`](Self::reset_schedule)
+    fn daily_reset_at(&self) -> DateTime<Utc> {
+        match &self.reset_schedule {
+            Some(schedule) => schedule.next_reset_after(self.last_reset),
+            None => self.last_reset + chrono::Duration::days(1),
+        }
+    }
+
+    /// Time left until the quota resets
+    ///
+    /// Uses the subscription's reset date while it is in the future, otherwise the end of
+    /// the local daily window. `None` once that window is overdue for a reset.
+    pub fn quota_reset_in(&self) -> Option<std::time::Duration> {
+        let now = self.clock.now();
+        let reset_at = self
+            .subscription_reset_date
+            .filter(|reset_at| *reset_at > now)
+            .unwrap_or_else(|| self.daily_reset_at());
+        (reset_at - now).to_std().ok()
     }
 
     /// Reset daily quota
     pub fn reset_daily_quota(&mut self) {
         self.current_usage = 0;
         self.active_agents.clear();
-        self.last_reset = Utc::now();
+        self.memory_leases.clear();
+        self.last_reset = self.clock.now();
     }
 }
 
 impl Default for ClaudeQuotaManager {
     fn default() -> Self {
         Self {
-            daily_limit: 1_000_000, // 1M tokens per day (example)
+            daily_limit: 1_000_000, // Fallback until the subscription reports its limit
             current_usage: 0,
             concurrent_limit: 10,
             active_agents: HashMap::new(),
             last_reset: Utc::now(),
+            agent_memory_estimate_mb: 50, // 50MB per agent session target
+            sweep_interval: DEFAULT_QUOTA_SWEEP_INTERVAL,
+            memory_optimizer: None,
+            memory_leases: HashMap::new(),
+            overage_policy: OveragePolicy::default(),
+            limit_from_subscription: false,
+            limit_refresh_interval: DEFAULT_QUOTA_LIMIT_REFRESH_INTERVAL,
+            limit_checked_at: None,
+            subscription_reset_date: None,
+            draining: false,
+            reset_schedule: None,
+            clock: system_clock(),
         }
     }
 }
 
+fn log_quota_overage(quota: &AgentQuota, debit: &UsageDebit, now: DateTime<Utc>) {
+    tracing::warn!(
+        agent_id = %quota.agent_id,
+        unaccounted_tokens = debit.unaccounted_tokens,
+        "Agent used more tokens than its quota allocation"
+    );
+    let _ = log_audit_event(AuditEvent {
+        timestamp: now,
+        event_type: AuthEventType::QuotaOverage,
+        user_id: None,
+        session_id: None,
+        client_id: None,
+        ip_address: None,
+        user_agent: None,
+        success: false,
+        error_message: Some(format!("{} tokens over allocation", debit.unaccounted_tokens)),
+        metadata: serde_json::json!({
+            "agent_id": quota.agent_id,
+            "allocated_tokens": quota.allocated_tokens,
+            "expanded_tokens": debit.expanded_tokens,
+            "unaccounted_tokens": debit.unaccounted_tokens,
+        }),
+        severity: Severity::Warning,
+    });
+}
+
+fn log_quota_reclaimed(quota: &AgentQuota, unused_tokens: u64, now: DateTime<Utc>, reason: &str) {
+    let _ = log_audit_event(AuditEvent {
+        timestamp: now,
+        event_type: AuthEventType::QuotaReclaimed,
+        user_id: None,
+        session_id: None,
+        client_id: None,
+        ip_address: None,
+        user_agent: None,
+        success: true,
+        error_message: None,
+        metadata: serde_json::json!({
+            "agent_id": quota.agent_id,
+            "allocated_tokens": quota.allocated_tokens,
+            "used_tokens": quota.used_tokens,
+            "reclaimed_tokens": unused_tokens,
+            "expired_at": quota.expires_at,
+            "reason": reason,
+        }),
+        severity: Severity::Warning,
+    });
+}
+
+/// Coalescing key for refreshes made with `refresh_token`
+fn refresh_credential_id(refresh_token: &str) -> String {
+    let digest: String = Sha256::digest(refresh_token.as_bytes()).iter().map(|b| format!("{:02x}", b)).collect();
+    format!("claude:{}", digest)
+}
+
 /// Claude OAuth flow implementation
 pub struct ClaudeOAuthFlow {
     client_id: String,
-    client_secret: Option<String>,
     redirect_uri: String,
     scopes: Vec<String>,
     client: reqwest::Client,
+    auth_config: ClaudeAuthConfig,
 }
 
 impl ClaudeOAuthFlow {
     /// Create new OAuth flow
     pub fn new(client_id: String, redirect_uri: String) -> Self {
-        let client = reqwest::Client::new();
+        let auth_config = ClaudeAuthConfig::default();
+        let client = auth_config.http_client().unwrap_or_else(|e| {
+            tracing::warn!(error = %e, "Failed to build HTTP client, falling back to defaults");
+            reqwest::Client::new()
+        });
         let scopes = vec!["api".to_string(), "subscription".to_string()];
 
         Self {
             client_id,
-            client_secret: None,
             redirect_uri,
             scopes,
             client,
+            auth_config,
+        }
+    }
+
+    /// Use the endpoints and user agent from `config`
+    pub fn with_auth_config(mut self, config: ClaudeAuthConfig) -> Self {
+        match config.http_client() {
+            Ok(client) => self.client = client,
+            Err(e) => tracing::warn!(error = %e, "Failed to build HTTP client, keeping the previous one"),
+        }
+        self.auth_config = config;
+        self
+    }
+
+    /// Reuse the pooled HTTP client instead of opening fresh connections per flow
+    pub fn with_connection_pool(mut self, pool: Option<&ClaudeConnectionPool>) -> Self {
+        if let Some(pool) = pool {
+            self.client = pool.shared_client();
         }
+        self
     }
 
     /// Generate authorization URL
     pub fn generate_auth_url(&self, state: &str) -> String {
         let scope = self.scopes.join(" ");
         format!(
-            "https://auth.anthropic.com/oauth/authorize?client_id={}&redirect_uri={}&scope={}&response_type=code&state={}",
+            "{}?client_id={}&redirect_uri={}&scope={}&response_type=code&state={}",
+            self.auth_config.auth_endpoint,
             urlencoding::encode(&self.client_id),
             urlencoding::encode(&self.redirect_uri),
             urlencoding::encode(&scope),
@@ -491,6 +1801,7 @@ impl ClaudeOAuthFlow {
 
     /// Exchange authorization code for tokens
     pub async fn exchange_code(&self, code: &str) -> Result<ClaudeTokenData, ClaudeAuthError> {
+        validate_endpoints(&self.auth_config)?;
         let token_request = serde_json::json!({
             "grant_type": "authorization_code",
             "client_id": self.client_id,
@@ -499,12 +1810,15 @@ impl ClaudeOAuthFlow {
         });
 
         let response = self.client
-            .post("https://auth.anthropic.com/oauth/token")
+            .post(&self.auth_config.token_endpoint)
             .header("Content-Type", "application/json")
             .json(&token_request)
             .send()
             .await?;
 
+        if let Some(error) = rate_limit_error(&response) {
+            return Err(error);
+        }
         if !response.status().is_success() {
             return Err(ClaudeAuthError::OAuthError("Token exchange failed".to_string()));
         }
@@ -524,10 +1838,10 @@ impl ClaudeOAuthFlow {
             .unwrap_or("free");
 
         Ok(ClaudeTokenData {
-            access_token: access_token.to_string(),
+            access_token: access_token.into(),
             refresh_token: token_response.get("refresh_token")
                 .and_then(|v| v.as_str())
-                .map(|s| s.to_string()),
+                .map(Secret::from),
             expires_at: Utc::now() + chrono::Duration::seconds(expires_in as i64),
             subscription_tier: subscription_tier.to_string(),
             token_type: token_response.get("token_type")
@@ -545,14 +1859,26 @@ impl ClaudeOAuthFlow {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::security::MockClock;
     use tempfile::tempdir;
+    use wiremock::matchers::{any, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    const WELL_FORMED_KEY: &str = "sk-ant-REDACTED";
 
     #[tokio::test]
     async fn test_claude_auth_from_api_key() {
         let temp_dir = tempdir().unwrap();
         
-        // Setup API key auth
-        ClaudeAuth::setup_with_api_key(temp_dir.path(), "sk-test-key").await.unwrap();
+        // Setup API key auth with the network step mocked out
+        let verified = std::sync::atomic::AtomicBool::new(false);
+        ClaudeAuth::setup_with_api_key_using(temp_dir.path(), WELL_FORMED_KEY, &ApiKeyFormat::default(), |key| {
+            verified.store(key == WELL_FORMED_KEY, std::sync::atomic::Ordering::SeqCst);
+            async { Ok(true) }
+        })
+        .await
+        .unwrap();
+        assert!(verified.load(std::sync::atomic::Ordering::SeqCst));
         
         // Load auth
         let auth = ClaudeAuth::from_codex_home(temp_dir.path(), ClaudeAuthMode::ApiKey, "test").unwrap();
@@ -560,7 +1886,852 @@ mod tests {
         
         let auth = auth.unwrap();
         assert_eq!(auth.mode, ClaudeAuthMode::ApiKey);
-        assert_eq!(auth.api_key.as_ref().unwrap(), "sk-test-key");
+        assert_eq!(auth.api_key.as_ref().unwrap().expose_secret(), WELL_FORMED_KEY);
+    }
+
+    #[tokio::test]
+    async fn test_malformed_api_keys_rejected_offline() {
+        let temp_dir = tempdir().unwrap();
+        let long_wrong_prefix = "sk-proj-abcdefghijklmnopqrstuvwxyz0123456789";
+
+        for key in ["", long_wrong_prefix, "sk-ant-short", "sk-ant-api03-has spaces in it 0123456789abc"] {
+            let result = ClaudeAuth::setup_with_api_key_using(temp_dir.path(), key, &ApiKeyFormat::default(), |_| async {
+                panic!("network verification must not run for malformed keys")
+            })
+            .await;
+            assert!(matches!(result, Err(ClaudeAuthError::InvalidCredentials)), "key {:?}", key);
+        }
+        assert!(!temp_dir.path().join("claude_auth.json").exists());
+
+        // A configurable prefix admits future key formats
+        let format = ApiKeyFormat::with_prefix("sk-proj-");
+        assert!(format.validate(long_wrong_prefix).is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_login_is_rejected_while_lock_held() {
+        let temp_dir = tempdir().unwrap();
+        let lock = LoginLock::acquire(temp_dir.path()).unwrap();
+
+        let result = ClaudeAuth::setup_with_api_key_using(temp_dir.path(), WELL_FORMED_KEY, &ApiKeyFormat::default(), |_| async {
+            panic!("a rejected login must not reach verification")
+        })
+        .await;
+        match result {
+            Err(ClaudeAuthError::LoginLocked(e)) => assert!(e.to_string().contains("Another login is in progress")),
+            other => panic!("expected LoginLocked, got {:?}", other),
+        }
+        assert!(!temp_dir.path().join("claude_auth.json").exists());
+
+        drop(lock);
+        ClaudeAuth::setup_with_api_key_using(temp_dir.path(), WELL_FORMED_KEY, &ApiKeyFormat::default(), |_| async { Ok(true) })
+            .await
+            .unwrap();
+        assert!(temp_dir.path().join("claude_auth.json").exists());
+
+        // The lock is released after a failed login too
+        let result = ClaudeAuth::setup_with_api_key_using(temp_dir.path(), WELL_FORMED_KEY, &ApiKeyFormat::default(), |_| async { Ok(false) })
+            .await;
+        assert!(matches!(result, Err(ClaudeAuthError::InvalidCredentials)));
+        assert!(LoginLock::acquire(temp_dir.path()).is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_profiles_keep_separate_credentials() {
+        use crate::configuration::UnifiedConfigManager;
+
+        const CLIENT_KEY: &str = "sk-ant-REDACTED";
+        let temp_dir = tempdir().unwrap();
+        let mut config = UnifiedConfigManager::new(temp_dir.path().to_path_buf()).unwrap();
+        config.add_profile("personal").unwrap();
+        config.add_profile("client-a").unwrap();
+
+        for (profile, key) in [("personal", WELL_FORMED_KEY), ("client-a", CLIENT_KEY)] {
+            config.use_profile(Some(profile)).unwrap();
+            ClaudeAuth::setup_with_api_key_using(temp_dir.path(), key, &ApiKeyFormat::default(), |_| async { Ok(true) })
+                .await
+                .unwrap();
+        }
+
+        async fn active_token(codex_home: &Path) -> String {
+            let auth = ClaudeAuth::from_codex_home(codex_home, ClaudeAuthMode::ApiKey, "test").unwrap().unwrap();
+            auth.get_token().await.unwrap()
+        }
+
+        assert_eq!(active_token(temp_dir.path()).await, CLIENT_KEY);
+        config.use_profile(Some("personal")).unwrap();
+        assert_eq!(active_token(temp_dir.path()).await, WELL_FORMED_KEY);
+        config.use_profile(Some("client-a")).unwrap();
+        assert_eq!(active_token(temp_dir.path()).await, CLIENT_KEY);
+
+        // Neither profile's key is visible from the default credentials
+        config.use_profile(None).unwrap();
+        assert!(ClaudeAuth::from_codex_home(temp_dir.path(), ClaudeAuthMode::ApiKey, "test").unwrap().is_none());
+        assert!(!temp_dir.path().join("claude_auth.json").exists());
+    }
+
+    #[tokio::test]
+    async fn test_rejected_by_verifier_is_invalid_credentials() {
+        let temp_dir = tempdir().unwrap();
+        let result = ClaudeAuth::setup_with_api_key_using(temp_dir.path(), WELL_FORMED_KEY, &ApiKeyFormat::default(), |_| async {
+            Ok(false)
+        })
+        .await;
+        assert!(matches!(result, Err(ClaudeAuthError::InvalidCredentials)));
+    }
+
+    #[tokio::test]
+    async fn test_truncated_auth_file_without_backup_is_reported_as_corrupt() {
+        let temp_dir = tempdir().unwrap();
+        let path = claude_auth_file(temp_dir.path()).unwrap();
+        std::fs::write(&path, r#"{"version": "2.0", "auth_mode": "api_key", "api_key": "sk-ant-ap"#).unwrap();
+
+        let error = ClaudeAuth::from_codex_home(temp_dir.path(), ClaudeAuthMode::ApiKey, "test").unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+
+        let error = ClaudeAuth::load_from_codex_home(temp_dir.path(), ClaudeAuthMode::ApiKey, "test")
+            .await
+            .unwrap_err();
+        assert!(matches!(error, ClaudeAuthError::CorruptAuthFile { path: ref reported } if *reported == path));
+        assert!(error.is_auth_fatal());
+        assert!(error.to_string().contains("log in again"));
+    }
+
+    #[tokio::test]
+    async fn test_async_loader_matches_sync_loader_without_blocking() {
+        fn summary(auth: &ClaudeAuth) -> (ClaudeAuthMode, Option<String>, Option<String>, Option<String>, String) {
+            (
+                auth.mode.clone(),
+                auth.subscription_tier.clone(),
+                auth.api_key.as_ref().map(|key| key.expose_secret().clone()),
+                auth.oauth_tokens.as_ref().map(|tokens| tokens.access_token.expose_secret().clone()),
+                auth.auth_config.effective_user_agent(),
+            )
+        }
+
+        let temp_dir = tempdir().unwrap();
+        let path = claude_auth_file(temp_dir.path()).unwrap();
+        let oauth = serde_json::json!({
+            "oauth_tokens": {
+                "access_token": "max-access-token",
+                "refresh_token": "max-refresh-token",
+                "expires_at": Utc::now() + chrono::Duration::hours(1),
+                "subscription_tier": "max",
+                "token_type": "Bearer",
+                "scope": ["api"]
+            }
+        });
+        let api_key = serde_json::json!({"api_key": WELL_FORMED_KEY, "subscription_tier": "pro"});
+
+        for content in [oauth.to_string(), api_key.to_string(), r#"{"setup_required": true}"#.to_string()] {
+            std::fs::write(&path, &content).unwrap();
+            let sync = ClaudeAuth::from_codex_home(temp_dir.path(), ClaudeAuthMode::ApiKey, "vscode").unwrap();
+            let loaded = ClaudeAuth::from_codex_home_async(temp_dir.path(), ClaudeAuthMode::ApiKey, "vscode").await.unwrap();
+            assert_eq!(sync.as_ref().map(summary), loaded.as_ref().map(summary), "{content}");
+        }
+
+        std::fs::write(&path, r#"{"api_key": "sk-ant-ap"#).unwrap();
+        let sync = ClaudeAuth::from_codex_home(temp_dir.path(), ClaudeAuthMode::ApiKey, "test").unwrap_err();
+        let loaded = ClaudeAuth::from_codex_home_async(temp_dir.path(), ClaudeAuthMode::ApiKey, "test").await.unwrap_err();
+        assert_eq!((sync.kind(), sync.to_string()), (loaded.kind(), loaded.to_string()));
+
+        // The auth file is a FIFO only filled once another task on this current-thread
+        // runtime gets to run, so a loader reading it on the runtime thread would never see the data
+        #[cfg(unix)]
+        {
+            /// Write `content` to the FIFO at `path` once a task on this runtime has run, or feed
+            /// a stuck reader "stuck" after 10s so the test fails instead of hanging
+            fn fill_fifo_from_runtime(path: std::path::PathBuf, content: String) {
+                let (ready, runtime_ran) = std::sync::mpsc::channel();
+                tokio::spawn(async move { ready.send(()).ok() });
+                std::thread::spawn(move || {
+                    let content = match runtime_ran.recv_timeout(std::time::Duration::from_secs(10)) {
+                        Ok(()) => content,
+                        Err(_) => "stuck".to_string(),
+                    };
+                    let _ = std::fs::write(&path, content);
+                });
+            }
+
+            let fifo_home = tempdir().unwrap();
+            let fifo = claude_auth_file(fifo_home.path()).unwrap();
+            assert!(std::process::Command::new("mkfifo").arg(&fifo).status().unwrap().success());
+            fill_fifo_from_runtime(fifo, api_key.to_string());
+
+            std::fs::write(&path, api_key.to_string()).unwrap();
+            let sync = ClaudeAuth::from_codex_home(temp_dir.path(), ClaudeAuthMode::ApiKey, "test").unwrap().unwrap();
+            let loaded = ClaudeAuth::from_codex_home_async(fifo_home.path(), ClaudeAuthMode::ApiKey, "test")
+                .await
+                .unwrap()
+                .unwrap();
+            assert_eq!(summary(&sync), summary(&loaded));
+
+            // Likewise for an API key read from a `file:` secret
+            let secret_home = tempdir().unwrap();
+            let secret = secret_home.path().join("claude_api_key");
+            assert!(std::process::Command::new("mkfifo").args(["-m", "600"]).arg(&secret).status().unwrap().success());
+            let api_key_ref = serde_json::json!({"api_key": format!("file:{}", secret.display())});
+            std::fs::write(claude_auth_file(secret_home.path()).unwrap(), api_key_ref.to_string()).unwrap();
+            fill_fifo_from_runtime(secret, WELL_FORMED_KEY.to_string());
+
+            let loaded = ClaudeAuth::from_codex_home_async(secret_home.path(), ClaudeAuthMode::ApiKey, "test")
+                .await
+                .unwrap()
+                .unwrap();
+            assert_eq!(loaded.api_key.unwrap().expose_secret(), WELL_FORMED_KEY);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_truncated_auth_file_recovered_from_backup() {
+        let temp_dir = tempdir().unwrap();
+        ClaudeAuth::setup_with_api_key_using(temp_dir.path(), WELL_FORMED_KEY, &ApiKeyFormat::default(), |_| async {
+            Ok(true)
+        })
+        .await
+        .unwrap();
+        BackupManager::new(temp_dir.path(), &MigrationConfig::default())
+            .create_incremental_backup(None)
+            .await
+            .unwrap();
+
+        // Simulate a crash halfway through rewriting the file
+        let path = claude_auth_file(temp_dir.path()).unwrap();
+        let content = std::fs::read_to_string(&path).unwrap();
+        let truncated = &content[..content.len() / 2];
+        std::fs::write(&path, truncated).unwrap();
+
+        let auth = ClaudeAuth::load_from_codex_home(temp_dir.path(), ClaudeAuthMode::ApiKey, "test")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(auth.api_key.as_ref().map(|key| key.expose_secret().as_str()), Some(WELL_FORMED_KEY));
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), content);
+        assert_eq!(std::fs::read_to_string(path.with_file_name("claude_auth.json.corrupt")).unwrap(), truncated);
+    }
+
+    #[tokio::test]
+    async fn test_offline_mode_makes_no_http_calls() {
+        let temp_dir = tempdir().unwrap();
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        listener.set_nonblocking(true).unwrap();
+        let config = ClaudeAuthConfig {
+            subscription_endpoint: format!("http://{}/v1/subscription", listener.local_addr().unwrap()),
+            offline_mode: true,
+            assume_max_subscription: true,
+            ..ClaudeAuthConfig::default()
+        };
+
+        // The key is trusted without verification, but the auth file is still written
+        ClaudeAuth::setup_with_api_key_configured(temp_dir.path(), WELL_FORMED_KEY, &config)
+            .await
+            .unwrap();
+        let auth = ClaudeAuth::from_codex_home(temp_dir.path(), ClaudeAuthMode::ApiKey, "test")
+            .unwrap()
+            .unwrap()
+            .with_auth_config(config.clone());
+        assert_eq!(auth.api_key.as_ref().map(|key| key.expose_secret().as_str()), Some(WELL_FORMED_KEY));
+
+        // The configured assumption stands in for the subscription endpoint
+        assert!(auth.has_max_subscription().await);
+        let auth = auth.with_auth_config(ClaudeAuthConfig { assume_max_subscription: false, ..config });
+        assert!(!auth.has_max_subscription().await);
+
+        let accepted = listener.accept();
+        assert!(matches!(accepted, Err(ref e) if e.kind() == std::io::ErrorKind::WouldBlock));
+
+        // Offline mode does not waive the format check
+        let result = ClaudeAuth::setup_with_api_key_configured(temp_dir.path(), "sk-ant-short", &auth.auth_config).await;
+        assert!(matches!(result, Err(ClaudeAuthError::InvalidCredentials)));
+    }
+
+    /// Mock token endpoint answering every refresh with "refreshed-token"
+    async fn spawn_token_endpoint() -> (String, MockServer) {
+        spawn_json_endpoint("/oauth/token", r#"{"access_token":"refreshed-token"}"#).await
+    }
+
+    /// Mock endpoint at `endpoint_path` answering every request with `body`
+    async fn spawn_json_endpoint(endpoint_path: &str, body: &'static str) -> (String, MockServer) {
+        let server = MockServer::start().await;
+        Mock::given(path(endpoint_path))
+            .respond_with(ResponseTemplate::new(200).set_body_raw(body, "application/json"))
+            .mount(&server)
+            .await;
+        (format!("{}{}", server.uri(), endpoint_path), server)
+    }
+
+    /// Number of requests `server` has received
+    async fn hits(server: &MockServer) -> usize {
+        server.received_requests().await.unwrap().len()
+    }
+
+    /// Mock server answering every request with a body that satisfies all endpoints
+    async fn spawn_recording_server() -> MockServer {
+        let server = MockServer::start().await;
+        Mock::given(any())
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "access_token": "mock-token",
+                "refresh_token": "mock-refresh",
+                "expires_in": 3600,
+                "tier": "max",
+                "active": true,
+            })))
+            .mount(&server)
+            .await;
+        server
+    }
+
+    /// "METHOD /path" of a request
+    fn request_line(request: &wiremock::Request) -> String {
+        format!("{} {}", request.method, request.url.path())
+    }
+
+    /// `describe` of every request `server` has received, sorted
+    async fn recorded(server: &MockServer, describe: fn(&wiremock::Request) -> String) -> Vec<String> {
+        let mut seen: Vec<String> = server.received_requests().await.unwrap().iter().map(describe).collect();
+        seen.sort();
+        seen
+    }
+
+    fn config_for(base: &str) -> ClaudeAuthConfig {
+        ClaudeAuthConfig {
+            auth_endpoint: format!("{}/oauth/authorize", base),
+            token_endpoint: format!("{}/oauth/token", base),
+            revoke_endpoint: format!("{}/oauth/revoke", base),
+            subscription_endpoint: format!("{}/v1/subscription", base),
+            messages_endpoint: format!("{}/v1/messages", base),
+            offline_mode: false,
+            ..ClaudeAuthConfig::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_all_requests_use_configured_endpoints() {
+        let temp_dir = tempdir().unwrap();
+        let server = spawn_recording_server().await;
+        let base = server.uri();
+        let config = config_for(&base);
+
+        ClaudeAuth::setup_with_api_key_configured(temp_dir.path(), WELL_FORMED_KEY, &config)
+            .await
+            .unwrap();
+
+        // Expiring OAuth tokens: the subscription check refreshes first
+        let auth = oauth_auth(chrono::Duration::seconds(30), String::new()).with_auth_config(config.clone());
+        assert_eq!(auth.verify_subscription().await.unwrap().tier, "max");
+        auth.revoke_oauth_tokens().await.unwrap();
+
+        let flow = ClaudeOAuthFlow::new("client".to_string(), "http://localhost:3000/callback".to_string())
+            .with_auth_config(config);
+        assert!(flow.generate_auth_url("state").starts_with(&format!("{}/oauth/authorize?", base)));
+        assert_eq!(flow.exchange_code("authorization-code").await.unwrap().access_token.expose_secret(), "mock-token");
+
+        assert_eq!(
+            recorded(&server, request_line).await,
+            [
+                "GET /v1/subscription",
+                "POST /oauth/revoke",
+                "POST /oauth/revoke",
+                "POST /oauth/token",
+                "POST /oauth/token",
+                "POST /v1/messages",
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_every_entry_point_sends_configured_user_agent() {
+        use crate::claude_auth::secure_claude_auth::{default_user_agent, SecureClaudeAuth};
+
+        let temp_dir = tempdir().unwrap();
+        let storage_dir = tempdir().unwrap();
+        let server = spawn_recording_server().await;
+        let base = server.uri();
+        let config = ClaudeAuthConfig {
+            user_agent: Some("acme-gateway/2.0".to_string()),
+            ..config_for(&base)
+        };
+
+        ClaudeAuth::setup_with_api_key_configured(temp_dir.path(), WELL_FORMED_KEY, &config)
+            .await
+            .unwrap();
+        let auth = oauth_auth(chrono::Duration::seconds(30), String::new()).with_auth_config(config.clone());
+        auth.get_token().await.unwrap();
+        let flow = ClaudeOAuthFlow::new("client".to_string(), "http://localhost:3000/callback".to_string())
+            .with_auth_config(config.clone());
+        flow.exchange_code("authorization-code").await.unwrap();
+        let secure = SecureClaudeAuth::new(config, storage_dir.path().join("claude_tokens.json")).unwrap();
+        secure.verify_subscription("access-token").await.unwrap();
+
+        // Without an override the user agent names the originator
+        let from_home = ClaudeAuth::from_codex_home(temp_dir.path(), ClaudeAuthMode::ApiKey, "vscode").unwrap().unwrap();
+        from_home.client.get(format!("{}/v1/models", base)).send().await.unwrap();
+
+        let with_user_agent = |request: &wiremock::Request| {
+            let user_agent = request.headers.get("user-agent").map_or("<none>", |value| value.to_str().unwrap());
+            format!("{} {}", request_line(request), user_agent)
+        };
+        assert_eq!(
+            recorded(&server, with_user_agent).await,
+            [
+                format!("GET /v1/models {}", default_user_agent("vscode")),
+                "GET /v1/subscription acme-gateway/2.0".to_string(),
+                "POST /oauth/token acme-gateway/2.0".to_string(),
+                "POST /oauth/token acme-gateway/2.0".to_string(),
+                "POST /v1/messages acme-gateway/2.0".to_string(),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_plaintext_remote_endpoint_is_rejected_before_any_request() {
+        let temp_dir = tempdir().unwrap();
+        let config = ClaudeAuthConfig {
+            messages_endpoint: "http://proxy.example.com/v1/messages".to_string(),
+            offline_mode: false,
+            ..ClaudeAuthConfig::default()
+        };
+
+        let result = ClaudeAuth::setup_with_api_key_configured(temp_dir.path(), WELL_FORMED_KEY, &config).await;
+        assert!(matches!(result, Err(ClaudeAuthError::InvalidConfiguration(_))));
+
+        let auth = oauth_auth(chrono::Duration::seconds(30), String::new()).with_auth_config(config);
+        assert!(matches!(auth.get_token().await, Err(ClaudeAuthError::InvalidConfiguration(_))));
+        assert!(matches!(auth.verify_subscription().await, Err(ClaudeAuthError::InvalidConfiguration(_))));
+    }
+
+    fn oauth_auth(expires_in: chrono::Duration, token_endpoint: String) -> ClaudeAuth {
+        ClaudeAuth {
+            mode: ClaudeAuthMode::MaxSubscription,
+            subscription_tier: Some("max".to_string()),
+            api_key: None,
+            api_key_ref: None,
+            oauth_tokens: Some(ClaudeTokenData {
+                access_token: "current-token".into(),
+                refresh_token: Some("refresh-token".into()),
+                expires_at: Utc::now() + expires_in,
+                subscription_tier: "max".to_string(),
+                token_type: "Bearer".to_string(),
+                scope: vec!["api".to_string()],
+            }),
+            refreshed_oauth_tokens: Default::default(),
+            auth_file: None,
+            client: reqwest::Client::new(),
+            quota_manager: Arc::new(RwLock::new(ClaudeQuotaManager::default())),
+            auth_config: ClaudeAuthConfig {
+                token_endpoint,
+                offline_mode: false,
+                ..ClaudeAuthConfig::default()
+            },
+            refresh_skew: DEFAULT_REFRESH_SKEW,
+            token_optimizer: Arc::new(TokenOptimizer::new()),
+            clock: system_clock(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_token_within_skew_is_refreshed_once_per_burst() {
+        let (endpoint, server) = spawn_token_endpoint().await;
+        let auth = oauth_auth(chrono::Duration::seconds(30), endpoint)
+            .with_refresh_skew(std::time::Duration::from_secs(60));
+
+        let (a, b, c) = tokio::join!(auth.get_token(), auth.get_token(), auth.get_token());
+
+        for token in [a, b, c] {
+            assert_eq!(token.unwrap(), "refreshed-token");
+        }
+        assert_eq!(hits(&server).await, 1);
+    }
+
+    #[tokio::test]
+    async fn test_refreshed_tokens_are_kept_and_saved() {
+        let temp_dir = tempdir().unwrap();
+        let (endpoint, server) = spawn_json_endpoint(
+            "/oauth/token",
+            r#"{"access_token":"refreshed-token","refresh_token":"rotated-refresh-token","expires_in":3600}"#,
+        ).await;
+        let mut auth = oauth_auth(chrono::Duration::seconds(30), endpoint);
+        ClaudeAuth::setup_with_oauth(temp_dir.path(), auth.oauth_tokens.clone().unwrap()).await.unwrap();
+        auth.auth_file = Some(temp_dir.path().join("claude_auth.json"));
+
+        assert_eq!(auth.get_token().await.unwrap(), "refreshed-token");
+        assert_eq!(auth.clone().get_token().await.unwrap(), "refreshed-token");
+        assert_eq!(hits(&server).await, 1);
+        assert!(!auth.needs_token_refresh());
+
+        let saved = ClaudeAuth::from_codex_home(temp_dir.path(), ClaudeAuthMode::MaxSubscription, "test")
+            .unwrap()
+            .unwrap()
+            .oauth_tokens
+            .unwrap();
+        assert_eq!(saved.access_token.expose_secret(), "refreshed-token");
+        assert_eq!(saved.refresh_token.unwrap().expose_secret(), "rotated-refresh-token");
+        assert!(saved.expires_at > Utc::now() + chrono::Duration::minutes(59));
+    }
+
+    #[test]
+    fn test_refresh_coalescing_key_hides_refresh_token() {
+        let id = refresh_credential_id("rt-secret-value");
+        assert!(!id.contains("rt-secret-value"));
+        assert_eq!(id, refresh_credential_id("rt-secret-value"));
+        assert_ne!(id, refresh_credential_id("rt-other-value"));
+    }
+
+    #[tokio::test]
+    async fn test_token_outside_skew_is_not_refreshed() {
+        let (endpoint, server) = spawn_token_endpoint().await;
+        let auth = oauth_auth(chrono::Duration::minutes(5), endpoint)
+            .with_refresh_skew(std::time::Duration::from_secs(60));
+
+        assert_eq!(auth.get_token().await.unwrap(), "current-token");
+        assert_eq!(hits(&server).await, 0);
+    }
+
+    #[tokio::test]
+    async fn test_advancing_clock_into_skew_triggers_refresh() {
+        let (endpoint, server) = spawn_token_endpoint().await;
+        let clock = MockClock::default();
+        let mut auth = oauth_auth(chrono::Duration::minutes(5), endpoint)
+            .with_clock(Arc::new(clock.clone()));
+        auth.oauth_tokens.as_mut().unwrap().expires_at = clock.now() + chrono::Duration::minutes(5);
+
+        assert_eq!(auth.get_token().await.unwrap(), "current-token");
+        assert_eq!(hits(&server).await, 0);
+
+        clock.advance(chrono::Duration::minutes(4) + chrono::Duration::seconds(30));
+        assert_eq!(auth.get_token().await.unwrap(), "refreshed-token");
+        assert_eq!(hits(&server).await, 1);
+    }
+
+    /// Mock server answering every request with `response`
+    async fn spawn_status_server(response: ResponseTemplate) -> MockServer {
+        let server = MockServer::start().await;
+        Mock::given(any()).respond_with(response).mount(&server).await;
+        server
+    }
+
+    #[tokio::test]
+    async fn test_network_timeout_is_retryable() {
+        // Accepts connections but never answers
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            let mut held = Vec::new();
+            while let Ok((socket, _)) = listener.accept().await {
+                held.push(socket);
+            }
+        });
+
+        let client = reqwest::Client::builder()
+            .timeout(std::time::Duration::from_millis(50))
+            .build()
+            .unwrap();
+        let error = ClaudeAuthError::from(client.get(format!("http://{}", addr)).send().await.unwrap_err());
+        assert!(error.is_retryable());
+        assert!(!error.is_auth_fatal());
+        assert_eq!(error.retry_after(), None);
+    }
+
+    #[tokio::test]
+    async fn test_unauthorized_is_auth_fatal() {
+        let server = spawn_status_server(ResponseTemplate::new(401)).await;
+        let response = reqwest::get(server.uri()).await.unwrap();
+        let error = ClaudeAuthError::from(response.error_for_status().unwrap_err());
+        assert!(error.is_auth_fatal());
+        assert!(!error.is_retryable());
+
+        assert!(ClaudeAuthError::InvalidCredentials.is_auth_fatal());
+        assert!(ClaudeAuthError::SubscriptionExpired.is_auth_fatal());
+        assert!(ClaudeAuthError::ConcurrentLimitExceeded.is_retryable());
+        let neither = ClaudeAuthError::UnknownAgent("agent".to_string());
+        assert!(!neither.is_retryable() && !neither.is_auth_fatal());
+    }
+
+    #[tokio::test]
+    async fn test_rate_limited_refresh_carries_retry_after() {
+        let server = spawn_status_server(ResponseTemplate::new(429).insert_header("retry-after", "7")).await;
+        let auth = oauth_auth(chrono::Duration::seconds(30), format!("{}/oauth/token", server.uri()));
+
+        let error = auth.get_token().await.unwrap_err();
+        assert!(matches!(error, ClaudeAuthError::RateLimited { .. }));
+        assert!(error.is_retryable());
+        assert!(!error.is_auth_fatal());
+        assert_eq!(error.retry_after(), Some(std::time::Duration::from_secs(7)));
+    }
+
+    #[tokio::test]
+    async fn test_daily_quota_resets_after_day_boundary() {
+        let start = DateTime::parse_from_rfc3339("2025-03-01T23:30:00Z").unwrap().with_timezone(&Utc);
+        let clock = MockClock::new(start);
+        let mut quota_manager = ClaudeQuotaManager::default().with_clock(Arc::new(clock.clone()));
+        quota_manager.daily_limit = 1000;
+
+        quota_manager.allocate_quota("agent1", 1000).await.unwrap();
+        assert!(matches!(
+            quota_manager.allocate_quota("agent2", 1).await,
+            Err(ClaudeAuthError::QuotaExceeded { .. })
+        ));
+
+        clock.advance(chrono::Duration::hours(12));
+        assert!(!quota_manager.should_reset_quota());
+
+        clock.advance(chrono::Duration::hours(12) + chrono::Duration::seconds(1));
+        assert!(quota_manager.should_reset_quota());
+
+        let quota = quota_manager.allocate_quota("agent2", 400).await.unwrap();
+        assert_eq!(quota.created_at, clock.now());
+        assert_eq!(quota_manager.last_reset, clock.now());
+        assert_eq!(quota_manager.get_remaining_quota(), 600);
+        assert!(!quota_manager.active_agents.contains_key("agent1"));
+    }
+
+    #[tokio::test]
+    async fn test_scheduled_quota_reset_follows_local_midnight_across_dst() {
+        let at = |timestamp: &str| DateTime::parse_from_rfc3339(timestamp).unwrap().with_timezone(&Utc);
+        // 20:00 PST on the eve of the spring-forward change
+        let clock = MockClock::new(at("2025-03-09T04:00:00Z"));
+        let mut quota_manager = ClaudeQuotaManager::default()
+            .with_clock(Arc::new(clock.clone()))
+            .with_reset_schedule(QuotaResetSchedule::midnight(chrono_tz::America::Los_Angeles));
+        quota_manager.daily_limit = 1000;
+        quota_manager.allocate_quota("agent1", 1000).await.unwrap();
+
+        // 23:59:59 PST, a second before local midnight and well inside a rolling window
+        clock.set(at("2025-03-09T07:59:59Z"));
+        assert!(!quota_manager.should_reset_quota());
+        assert_eq!(quota_manager.quota_reset_in(), Some(std::time::Duration::from_secs(1)));
+        // Local midnight, four hours after the last reset
+        clock.set(at("2025-03-09T08:00:00Z"));
+        assert!(quota_manager.should_reset_quota());
+        quota_manager.allocate_quota("agent2", 400).await.unwrap();
+        assert_eq!(quota_manager.get_remaining_quota(), 600);
+
+        // The next local midnight is PDT, only 23 hours later
+        clock.set(at("2025-03-10T06:59:59Z"));
+        assert!(!quota_manager.should_reset_quota());
+        clock.set(at("2025-03-10T07:00:00Z"));
+        assert!(quota_manager.should_reset_quota());
+
+        // A reset time skipped by spring-forward fires an hour later; a repeated one fires the first time
+        let schedule = QuotaResetSchedule::new(
+            chrono_tz::America::Los_Angeles,
+            NaiveTime::from_hms_opt(2, 30, 0).unwrap(),
+        );
+        assert_eq!(schedule.next_reset_after(at("2025-03-09T08:00:00Z")), at("2025-03-09T10:30:00Z"));
+        let schedule = QuotaResetSchedule::new(
+            chrono_tz::America::Los_Angeles,
+            NaiveTime::from_hms_opt(1, 30, 0).unwrap(),
+        );
+        assert_eq!(schedule.next_reset_after(at("2025-11-02T07:00:00Z")), at("2025-11-02T08:30:00Z"));
+        assert_eq!(schedule.next_reset_after(at("2025-11-02T08:30:00Z")), at("2025-11-03T09:30:00Z"));
+    }
+
+    #[tokio::test]
+    async fn test_idempotency_key_allocates_once() {
+        let clock = MockClock::new(Utc::now());
+        let mut quota_manager = ClaudeQuotaManager::default().with_clock(Arc::new(clock.clone()));
+        quota_manager.daily_limit = 10_000;
+
+        let first = quota_manager.allocate_quota_with_key("agent1", 1000, Some("start-1")).await.unwrap();
+        clock.advance(chrono::Duration::seconds(5));
+        let retried = quota_manager.allocate_quota_with_key("agent1", 1000, Some("start-1")).await.unwrap();
+        assert_eq!(retried.created_at, first.created_at);
+        assert_eq!(quota_manager.active_agents.len(), 1);
+        assert_eq!(quota_manager.current_usage, 1000);
+        assert!(matches!(
+            quota_manager.allocate_quota_with_key("agent2", 1000, Some("start-1")).await,
+            Err(ClaudeAuthError::IdempotencyKeyConflict { agent_id, .. }) if agent_id == "agent1"
+        ));
+
+        quota_manager.allocate_quota_with_key("agent2", 1000, Some("start-2")).await.unwrap();
+        assert_eq!(quota_manager.active_agents.len(), 2);
+        assert_eq!(quota_manager.current_usage, 2000);
+
+        // Released: the key may allocate again
+        quota_manager.release_quota("agent1").await.unwrap();
+        let reallocated = quota_manager.allocate_quota_with_key("agent1", 1000, Some("start-1")).await.unwrap();
+        assert_eq!(reallocated.created_at, clock.now());
+        assert_eq!(quota_manager.current_usage, 2000);
+
+        // Expired: likewise
+        clock.advance(chrono::Duration::hours(3));
+        quota_manager.sweep_expired();
+        quota_manager.allocate_quota_with_key("agent2", 1000, Some("start-2")).await.unwrap();
+        assert_eq!(quota_manager.active_agents.len(), 1);
+    }
+
+    #[test]
+    fn test_quota_reset_in_from_subscription_reset_date() {
+        use crate::auth::unified::format_reset_in;
+
+        let start = DateTime::parse_from_rfc3339("2025-03-01T08:00:00Z").unwrap().with_timezone(&Utc);
+        let clock = MockClock::new(start);
+        let mut quota_manager = ClaudeQuotaManager::default().with_clock(Arc::new(clock.clone()));
+        quota_manager.subscription_reset_date =
+            Some(DateTime::parse_from_rfc3339("2025-03-01T11:12:00Z").unwrap().with_timezone(&Utc));
+
+        let reset_in = quota_manager.quota_reset_in().unwrap();
+        assert_eq!(reset_in, std::time::Duration::from_secs(3 * 3600 + 12 * 60));
+        assert_eq!(format_reset_in(reset_in), "resets in 3h 12m");
+
+        clock.advance(chrono::Duration::hours(3) + chrono::Duration::seconds(30));
+        assert_eq!(format_reset_in(quota_manager.quota_reset_in().unwrap()), "resets in 11m");
+
+        // Past the subscription's date, the local daily window decides
+        clock.advance(chrono::Duration::hours(1));
+        assert_eq!(
+            quota_manager.quota_reset_in(),
+            Some(std::time::Duration::from_secs(19 * 3600 + 59 * 60 + 30))
+        );
+        clock.advance(chrono::Duration::days(1));
+        assert_eq!(quota_manager.quota_reset_in(), None);
+    }
+
+    #[tokio::test]
+    async fn test_daily_limit_adopted_from_subscription() {
+        let (endpoint, server) = spawn_json_endpoint(
+            "/v1/subscription",
+            r#"{"tier":"max","active":true,"quota_limit":250000,"quota_used":0}"#,
+        )
+        .await;
+        let clock = MockClock::default();
+        // Outlives the refresh interval, so only the subscription endpoint is called
+        let auth = oauth_auth(chrono::Duration::days(2), String::new())
+            .with_auth_config(ClaudeAuthConfig {
+                subscription_endpoint: endpoint,
+                offline_mode: false,
+                ..ClaudeAuthConfig::default()
+            })
+            .with_clock(Arc::new(clock.clone()));
+
+        auth.allocate_agent_quota("agent1", 200_000).await.unwrap();
+        {
+            let quota_manager = auth.quota_manager.read().await;
+            assert_eq!(quota_manager.daily_limit, 250_000);
+            assert!(quota_manager.limit_from_subscription);
+        }
+        assert!(matches!(
+            auth.allocate_agent_quota("agent2", 100_000).await,
+            Err(ClaudeAuthError::QuotaExceeded { available: 50_000, .. })
+        ));
+        // Within the refresh interval the subscription is not queried again
+        assert_eq!(hits(&server).await, 1);
+
+        // A mid-day cut below current usage leaves the existing allocation alone
+        auth.quota_manager.write().await.record_subscription_limit(Some(150_000));
+        assert!(auth.quota_manager.read().await.active_agents.contains_key("agent1"));
+        assert_eq!(auth.get_remaining_quota().await.unwrap(), 0);
+        assert_eq!(auth.release_agent_quota("agent1").await.unwrap(), 0);
+        assert_eq!(auth.get_remaining_quota().await.unwrap(), 150_000);
+
+        clock.advance(DEFAULT_QUOTA_LIMIT_REFRESH_INTERVAL);
+        assert_eq!(auth.refresh_quota_limit().await, 250_000);
+        assert_eq!(hits(&server).await, 2);
+    }
+
+    #[tokio::test]
+    async fn test_daily_limit_falls_back_to_default_when_subscription_unavailable() {
+        let server = spawn_status_server(ResponseTemplate::new(503)).await;
+        let auth = oauth_auth(chrono::Duration::hours(1), String::new()).with_auth_config(ClaudeAuthConfig {
+            subscription_endpoint: format!("{}/v1/subscription", server.uri()),
+            offline_mode: false,
+            ..ClaudeAuthConfig::default()
+        });
+
+        assert_eq!(auth.refresh_quota_limit().await, ClaudeQuotaManager::default().daily_limit);
+        assert!(!auth.quota_manager.read().await.limit_from_subscription);
+    }
+
+    #[tokio::test]
+    async fn test_usage_by_agent_sorted_with_totals() {
+        let clock = MockClock::default();
+        let mut quota_manager = ClaudeQuotaManager::default().with_clock(Arc::new(clock.clone()));
+
+        quota_manager.allocate_quota("planner", 1000).await.unwrap();
+        clock.advance(chrono::Duration::minutes(30));
+        quota_manager.allocate_quota("coder", 4000).await.unwrap();
+        quota_manager.allocate_quota("reviewer", 2000).await.unwrap();
+        quota_manager.update_agent_usage("planner", 900);
+        quota_manager.update_agent_usage("coder", 2000);
+        quota_manager.update_agent_usage("reviewer", 100);
+
+        // The planner's two-hour allocation now has ten minutes left
+        clock.advance(chrono::Duration::minutes(80));
+        let summary = quota_manager.usage_summary();
+
+        let order: Vec<&str> = summary.agents.iter().map(|a| a.agent_id.as_str()).collect();
+        assert_eq!(order, ["coder", "planner", "reviewer"]);
+        assert_eq!(summary.total_allocated, 7000);
+        assert_eq!(summary.total_used, 3000);
+        assert_eq!(summary.near_expiry_count, 1);
+
+        let planner = &summary.agents[1];
+        assert!(planner.near_expiry);
+        assert_eq!(planner.utilization_percent, 90.0);
+        assert_eq!(planner.age_seconds, 110 * 60);
+        assert_eq!(summary.agents[0].utilization_percent, 50.0);
+    }
+
+    #[tokio::test]
+    async fn test_sweep_reclaims_expired_allocations() {
+        let clock = MockClock::default();
+        let mut quota_manager = ClaudeQuotaManager::default().with_clock(Arc::new(clock.clone()));
+        quota_manager.concurrent_limit = 1;
+
+        quota_manager.allocate_quota("crashed", 1000).await.unwrap();
+        quota_manager.update_agent_usage("crashed", 300);
+        assert!(matches!(
+            quota_manager.allocate_quota("next", 100).await,
+            Err(ClaudeAuthError::ConcurrentLimitExceeded)
+        ));
+
+        // Not yet expired: nothing to reclaim
+        clock.advance(chrono::Duration::hours(1));
+        assert!(quota_manager.sweep_expired().is_empty());
+
+        clock.advance(chrono::Duration::hours(1) + chrono::Duration::seconds(1));
+        assert_eq!(quota_manager.sweep_expired(), ["crashed"]);
+
+        // Used tokens stay spent; the unused 700 return to the pool
+        assert!(quota_manager.active_agents.is_empty());
+        assert_eq!(quota_manager.current_usage, 300);
+        quota_manager.allocate_quota("next", 100).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_overage_clamped_at_allocation() {
+        let mut quota_manager = ClaudeQuotaManager::default();
+        quota_manager.daily_limit = 10_000;
+        quota_manager.allocate_quota("agent1", 1000).await.unwrap();
+
+        let debit = quota_manager.record_request_usage("agent1", 1500).unwrap();
+
+        assert_eq!(debit.charged_tokens, 1000);
+        assert_eq!(debit.expanded_tokens, 0);
+        assert_eq!(debit.unaccounted_tokens, 500);
+        assert_eq!(quota_manager.active_agents["agent1"].used_tokens, 1000);
+        assert_eq!(quota_manager.get_remaining_quota(), 9000);
+        assert!(matches!(
+            quota_manager.record_request_usage("missing", 1),
+            Err(ClaudeAuthError::UnknownAgent(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_overage_expands_within_daily_quota() {
+        let mut quota_manager = ClaudeQuotaManager::default().with_overage_policy(OveragePolicy::ExpandWithinDailyQuota);
+        quota_manager.daily_limit = 1300;
+        quota_manager.allocate_quota("agent1", 1000).await.unwrap();
+
+        // Only 300 tokens of daily quota are left to cover the 500 overage
+        let debit = quota_manager.record_request_usage("agent1", 1500).unwrap();
+
+        assert_eq!(debit.charged_tokens, 1300);
+        assert_eq!(debit.expanded_tokens, 300);
+        assert_eq!(debit.unaccounted_tokens, 200);
+        let quota = &quota_manager.active_agents["agent1"];
+        assert_eq!((quota.allocated_tokens, quota.used_tokens), (1300, 1300));
+        assert_eq!(quota_manager.get_remaining_quota(), 0);
     }
 
     #[tokio::test]
@@ -607,4 +2778,26 @@ mod tests {
             assert!(matches!(result, Err(ClaudeAuthError::ConcurrentLimitExceeded)));
         });
     }
+
+    #[tokio::test]
+    async fn test_quota_allocation_bounded_by_memory() {
+        use crate::performance::memory_optimization::MemoryConfig;
+
+        let optimizer = Arc::new(MemoryOptimizer::with_config(MemoryConfig {
+            agent_admission_budget_mb: 100,
+            ..Default::default()
+        }));
+        let mut quota_manager = ClaudeQuotaManager::default().with_memory_optimizer(Arc::clone(&optimizer));
+
+        // Budget fits two 50MB agents even though the concurrent limit allows ten
+        quota_manager.allocate_quota("agent1", 100).await.unwrap();
+        quota_manager.allocate_quota("agent2", 100).await.unwrap();
+        let result = quota_manager.allocate_quota("agent3", 100).await;
+        assert!(matches!(result, Err(ClaudeAuthError::MemoryPressure(_))));
+
+        // Releasing an agent returns its reservation
+        quota_manager.release_quota("agent1").await.unwrap();
+        assert_eq!(optimizer.admitted_bytes(), 50 * 1024 * 1024);
+        quota_manager.allocate_quota("agent3", 100).await.unwrap();
+    }
 }
\ No newline at end of file
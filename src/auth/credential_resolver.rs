@@ -0,0 +1,282 @@
+//! # Claude Credential Precedence
+//! 
+//! A Claude credential can be found in several places at once: an environment variable,
+//! encrypted secure storage, the active profile's `claude_auth.json` and the one directly
+//! in codex_home. [`CredentialResolver`] lists every credential it finds, picks one by a
+//! configurable source and kind order, and reports the rest as shadowed so diagnostics
+//! can explain why a credential is being ignored.
+
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use crate::claude_auth::IMPORTABLE_ENV_VARS;
+use crate::configuration::active_profile_home;
+
+/// Where a credential was found
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CredentialSource {
+    /// `ANTHROPIC_API_KEY` or `CLAUDE_API_KEY`
+    Environment,
+    /// Encrypted `claude_tokens.json` in the active profile's home
+    Keyring,
+    /// `claude_auth.json` of the active profile, when one is selected
+    Profile,
+    /// `claude_auth.json` directly in codex_home
+    File,
+}
+
+impl fmt::Display for CredentialSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            CredentialSource::Environment => "environment",
+            CredentialSource::Keyring => "secure storage",
+            CredentialSource::Profile => "profile",
+            CredentialSource::File => "file",
+        })
+    }
+}
+
+/// What kind of credential was found
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CredentialKind {
+    ApiKey,
+    OAuth,
+}
+
+impl fmt::Display for CredentialKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            CredentialKind::ApiKey => "API key",
+            CredentialKind::OAuth => "OAuth tokens",
+        })
+    }
+}
+
+/// A credential found by [`CredentialResolver::discover`]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DiscoveredCredential {
+    pub source: CredentialSource,
+    /// `None` for secure storage, whose contents can't be read without decrypting them
+    pub kind: Option<CredentialKind>,
+    /// Environment variable name or file path
+    pub location: String,
+}
+
+impl fmt::Display for DiscoveredCredential {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.kind {
+            Some(kind) => write!(f, "{} from {} ({})", kind, self.source, self.location),
+            None => write!(f, "credentials from {} ({})", self.source, self.location),
+        }
+    }
+}
+
+/// Credential in use and the ones it hides
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ResolvedCredentials {
+    pub active: Option<DiscoveredCredential>,
+    /// Every other credential found, in precedence order
+    pub shadowed: Vec<DiscoveredCredential>,
+}
+
+/// Picks one Claude credential out of every source that has one
+///
+/// By default the environment wins over secure storage, which wins over the profile's
+/// `claude_auth.json`, which wins over the one in codex_home. Within a source an API key
+/// is preferred over OAuth tokens, as in [`ClaudeAuth::from_codex_home`](crate::auth::ClaudeAuth::from_codex_home).
+/// Sources left out of the order are never chosen but are still reported as shadowed.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CredentialResolver {
+    pub source_order: Vec<CredentialSource>,
+    pub kind_order: Vec<CredentialKind>,
+}
+
+impl Default for CredentialResolver {
+    fn default() -> Self {
+        Self {
+            source_order: vec![
+                CredentialSource::Environment,
+                CredentialSource::Keyring,
+                CredentialSource::Profile,
+                CredentialSource::File,
+            ],
+            kind_order: vec![CredentialKind::ApiKey, CredentialKind::OAuth],
+        }
+    }
+}
+
+impl CredentialResolver {
+    /// Resolver with a custom source priority, highest first
+    pub fn with_source_order(mut self, order: Vec<CredentialSource>) -> Self {
+        self.source_order = order;
+        self
+    }
+
+    /// Resolver with a custom credential kind priority within a source, highest first
+    pub fn with_kind_order(mut self, order: Vec<CredentialKind>) -> Self {
+        self.kind_order = order;
+        self
+    }
+
+    /// Resolve the credentials under `codex_home` and in the process environment
+    pub fn resolve(&self, codex_home: &Path) -> ResolvedCredentials {
+        self.resolve_with_env(codex_home, |var| std::env::var(var).ok())
+    }
+
+    pub fn resolve_with_env(&self, codex_home: &Path, env: impl Fn(&str) -> Option<String>) -> ResolvedCredentials {
+        let mut found = Self::discover(codex_home, env);
+        // Stable sort keeps discovery order between equally ranked credentials
+        found.sort_by_key(|credential| self.rank(credential));
+
+        let mut found = found.into_iter();
+        match found.next() {
+            Some(first) if self.source_order.contains(&first.source) => {
+                ResolvedCredentials { active: Some(first), shadowed: found.collect() }
+            }
+            first => ResolvedCredentials { active: None, shadowed: first.into_iter().chain(found).collect() },
+        }
+    }
+
+    /// Every Claude credential present, in the default source order
+    pub fn discover(codex_home: &Path, env: impl Fn(&str) -> Option<String>) -> Vec<DiscoveredCredential> {
+        let mut found = Vec::new();
+
+        for var in IMPORTABLE_ENV_VARS {
+            if env(var).is_some_and(|value| !value.trim().is_empty()) {
+                found.push(DiscoveredCredential {
+                    source: CredentialSource::Environment,
+                    kind: Some(CredentialKind::ApiKey),
+                    location: var.to_string(),
+                });
+            }
+        }
+
+        // An unreadable profile selection is reported elsewhere; look in codex_home instead
+        let profile_home = active_profile_home(codex_home).unwrap_or_else(|_| codex_home.to_path_buf());
+        for file in ["claude_tokens.json", "claude_tokens.UNENCRYPTED.json"] {
+            let path = profile_home.join(file);
+            if path.exists() {
+                found.push(DiscoveredCredential {
+                    source: CredentialSource::Keyring,
+                    kind: None,
+                    location: path.display().to_string(),
+                });
+            }
+        }
+
+        if profile_home != codex_home {
+            found.extend(file_credentials(CredentialSource::Profile, profile_home.join("claude_auth.json")));
+        }
+        found.extend(file_credentials(CredentialSource::File, codex_home.join("claude_auth.json")));
+
+        found
+    }
+
+    /// Sort key: position of the source, then of the kind; unlisted entries sort last
+    fn rank(&self, credential: &DiscoveredCredential) -> (usize, usize) {
+        let source = self.source_order.iter().position(|source| *source == credential.source);
+        let kind = credential.kind.and_then(|kind| self.kind_order.iter().position(|k| *k == kind));
+        (source.unwrap_or(usize::MAX), kind.unwrap_or(usize::MAX))
+    }
+}
+
+/// API key and OAuth tokens stored in a `claude_auth.json`
+fn file_credentials(source: CredentialSource, path: PathBuf) -> Vec<DiscoveredCredential> {
+    let Some(auth_data) = std::fs::read_to_string(&path)
+        .ok()
+        .and_then(|content| serde_json::from_str::<serde_json::Value>(&content).ok())
+    else {
+        return Vec::new();
+    };
+
+    let mut found = Vec::new();
+    if auth_data.get("api_key").and_then(|v| v.as_str()).is_some() {
+        found.push(CredentialKind::ApiKey);
+    }
+    if auth_data.get("oauth_tokens").is_some_and(|v| v.is_object()) {
+        found.push(CredentialKind::OAuth);
+    }
+    found
+        .into_iter()
+        .map(|kind| DiscoveredCredential { source, kind: Some(kind), location: path.display().to_string() })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn write_file_credentials(codex_home: &Path) {
+        std::fs::write(
+            codex_home.join("claude_auth.json"),
+            r#"{"api_key": "sk-ant-file", "oauth_tokens": {"access_token": "oauth"}}"#,
+        )
+        .unwrap();
+    }
+
+    fn env_key(var: &str) -> Option<String> {
+        (var == "CLAUDE_API_KEY").then(|| "sk-ant-env".to_string())
+    }
+
+    #[test]
+    fn test_environment_shadows_file_credentials() {
+        let temp_dir = tempdir().unwrap();
+        write_file_credentials(temp_dir.path());
+
+        let resolved = CredentialResolver::default().resolve_with_env(temp_dir.path(), env_key);
+
+        let active = resolved.active.unwrap();
+        assert_eq!(active.source, CredentialSource::Environment);
+        assert_eq!(active.location, "CLAUDE_API_KEY");
+        let shadowed: Vec<_> = resolved.shadowed.iter().map(|c| (c.source, c.kind)).collect();
+        assert_eq!(
+            shadowed,
+            vec![
+                (CredentialSource::File, Some(CredentialKind::ApiKey)),
+                (CredentialSource::File, Some(CredentialKind::OAuth)),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_custom_order_prefers_file_oauth() {
+        let temp_dir = tempdir().unwrap();
+        write_file_credentials(temp_dir.path());
+        std::fs::write(temp_dir.path().join("claude_tokens.json"), "{}").unwrap();
+
+        let resolver = CredentialResolver::default()
+            .with_source_order(vec![CredentialSource::File, CredentialSource::Keyring])
+            .with_kind_order(vec![CredentialKind::OAuth, CredentialKind::ApiKey]);
+        let resolved = resolver.resolve_with_env(temp_dir.path(), env_key);
+
+        let active = resolved.active.unwrap();
+        assert_eq!((active.source, active.kind), (CredentialSource::File, Some(CredentialKind::OAuth)));
+        let shadowed: Vec<_> = resolved.shadowed.iter().map(|c| (c.source, c.kind)).collect();
+        assert_eq!(
+            shadowed,
+            vec![
+                (CredentialSource::File, Some(CredentialKind::ApiKey)),
+                (CredentialSource::Keyring, None),
+                // Left out of the order, so never chosen
+                (CredentialSource::Environment, Some(CredentialKind::ApiKey)),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_no_credentials_resolves_to_none() {
+        let temp_dir = tempdir().unwrap();
+        let resolved = CredentialResolver::default().resolve_with_env(temp_dir.path(), |_| None);
+        assert_eq!(resolved, ResolvedCredentials::default());
+
+        // Only an excluded source has a credential
+        let resolver = CredentialResolver::default().with_source_order(vec![CredentialSource::File]);
+        let resolved = resolver.resolve_with_env(temp_dir.path(), env_key);
+        assert!(resolved.active.is_none());
+        assert_eq!(resolved.shadowed.len(), 1);
+    }
+}
@@ -1,14 +1,21 @@
-/// # Backup Management System
-/// 
-/// Provides secure backup and restoration capabilities for authentication data.
-/// Supports encrypted backups, versioning, and automatic cleanup.
+//! # Backup Management System
+//! 
+//! Provides secure backup and restoration capabilities for authentication data.
+//! Supports encrypted backups, versioning, and automatic cleanup.
+//!
+//! Incremental backups cover the whole codex home: each file is stored once as a
+//! content-addressed blob under `.backups/blobs`, and every backup records a manifest
+//! mapping paths to blob hashes, so files unchanged since an earlier backup cost nothing.
 
 use super::{MigrationConfig, MigrationError, MigrationResult};
+use crate::security::write_atomic_async;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use sha2::{Digest, Sha256};
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::path::{Path, PathBuf};
-use uuid::Uuid;
+use std::sync::atomic::{self, AtomicI64};
 
 /// Backup handle for tracking and restoration
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -19,6 +26,65 @@ pub struct BackupHandle {
     pub metadata: BackupMetadata,
     pub encrypted: bool,
     pub checksum: String,
+    #[serde(default)]
+    pub kind: BackupKind,
+}
+
+/// Creation time, in microseconds, of the last backup id this process handed out
+static LAST_BACKUP_MICROS: AtomicI64 = AtomicI64::new(0);
+
+impl BackupHandle {
+    /// Id and creation time for a new backup
+    ///
+    /// Ids are the UTC creation time to the microsecond followed by a random suffix, e.g.
+    /// `20250301T120000123456Z-9f2c4e1a`, so they sort as strings in creation order. Two
+    /// backups in the same microsecond get consecutive timestamps; the suffix keeps ids
+    /// from separate processes apart.
+    fn next_id() -> (String, DateTime<Utc>) {
+        let now = Utc::now().timestamp_micros();
+        let previous = LAST_BACKUP_MICROS
+            .fetch_update(atomic::Ordering::SeqCst, atomic::Ordering::SeqCst, |last| Some(now.max(last + 1)))
+            .expect("update closure always succeeds");
+        let created_at = DateTime::from_timestamp_micros(now.max(previous + 1))
+            .expect("current time is within chrono's range");
+        let id = format!("{}-{:08x}", created_at.format("%Y%m%dT%H%M%S%6fZ"), rand::random::<u32>());
+        (id, created_at)
+    }
+
+    /// Order handles newest first, falling back to the id when creation times tie
+    pub fn newest_first(a: &BackupHandle, b: &BackupHandle) -> Ordering {
+        b.created_at.cmp(&a.created_at).then_with(|| b.id.cmp(&a.id))
+    }
+}
+
+/// What a backup's `file_path` holds
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BackupKind {
+    /// A copy of auth.json
+    #[default]
+    Full,
+    /// A [`BackupManifest`] whose files live in the shared blob store
+    Incremental,
+}
+
+/// Files captured by an incremental backup
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackupManifest {
+    /// Backup whose manifest unchanged files were matched against
+    pub base_id: Option<String>,
+    /// Paths relative to the codex home
+    pub files: BTreeMap<String, ManifestEntry>,
+}
+
+/// One file in a [`BackupManifest`]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ManifestEntry {
+    /// SHA-256 of the plaintext contents, naming the blob
+    pub hash: String,
+    pub size: u64,
+    /// Backup that stored the blob; earlier than this one when the file was unchanged
+    pub stored_by: String,
 }
 
 /// Metadata associated with a backup
@@ -81,14 +147,8 @@ impl BackupManager {
             ));
         }
 
-        // Generate unique backup ID
-        let backup_id = Uuid::new_v4().to_string();
-        let timestamp = Utc::now();
-        let backup_filename = format!("auth_backup_{}_{}.json", 
-            timestamp.format("%Y%m%d_%H%M%S"), 
-            &backup_id[..8]
-        );
-        let backup_path = self.backup_dir.join(&backup_filename);
+        let (backup_id, timestamp) = BackupHandle::next_id();
+        let backup_path = self.backup_dir.join(format!("auth_backup_{}.json", backup_id));
 
         // Read original file
         let auth_content = tokio::fs::read_to_string(&auth_file).await
@@ -108,18 +168,9 @@ impl BackupManager {
         };
 
         // Write backup file
-        tokio::fs::write(&backup_path, &backup_content).await
+        write_atomic_async(&backup_path, backup_content.clone(), 0o600).await
             .map_err(|e| MigrationError::BackupFailed(format!("Failed to write backup: {}", e)))?;
 
-        // Set secure permissions
-        #[cfg(unix)]
-        {
-            use std::os::unix::fs::PermissionsExt;
-            let mut perms = tokio::fs::metadata(&backup_path).await?.permissions();
-            perms.set_mode(0o600);
-            tokio::fs::set_permissions(&backup_path, perms).await?;
-        }
-
         // Calculate checksum
         let checksum = self.calculate_checksum(&backup_content);
 
@@ -130,18 +181,119 @@ impl BackupManager {
             metadata,
             encrypted: self.config.encrypt_backups,
             checksum,
+            kind: BackupKind::Full,
         };
 
         // Save backup handle
         self.save_backup_handle(&handle).await?;
 
-        if self.config.verbose_logging {
-            println!("Created backup: {} at {}", handle.id, handle.file_path.display());
+        verbose_event!(self.config.verbose_logging, backup_id = %handle.id, path = %handle.file_path.display(), "Created backup");
+
+        Ok(handle)
+    }
+
+    /// Back up every file under the codex home, storing only contents not already held
+    ///
+    /// Files whose hash matches their entry in `base` keep pointing at the blob that
+    /// backup stored. Backups and the login lock are not included.
+    pub async fn create_incremental_backup(&self, base: Option<BackupHandle>) -> MigrationResult<BackupHandle> {
+        self.ensure_backup_dir().await?;
+        tokio::fs::create_dir_all(self.blob_dir()).await?;
+
+        let base_manifest = match &base {
+            Some(handle) if handle.kind == BackupKind::Incremental => Some(self.read_manifest(handle).await?),
+            Some(handle) => {
+                return Err(MigrationError::BackupFailed(format!(
+                    "Backup {} is not incremental and can't be used as a base",
+                    handle.id
+                )))
+            }
+            None => None,
+        };
+
+        let (backup_id, timestamp) = BackupHandle::next_id();
+        let mut manifest = BackupManifest { base_id: base.as_ref().map(|h| h.id.clone()), files: BTreeMap::new() };
+        let mut total_size = 0;
+
+        for relative in self.collect_backup_files().await? {
+            let content = tokio::fs::read(self.codex_home.join(&relative)).await
+                .map_err(|e| MigrationError::BackupFailed(format!("Failed to read {}: {}", relative, e)))?;
+            let hash = hex_sha256(&content);
+            total_size += content.len() as u64;
+
+            let reused = base_manifest
+                .as_ref()
+                .and_then(|m| m.files.get(&relative))
+                .filter(|entry| entry.hash == hash && self.blob_path(&hash).exists())
+                .map(|entry| entry.stored_by.clone());
+
+            let stored_by = match reused {
+                Some(stored_by) => stored_by,
+                None => {
+                    let blob_path = self.blob_path(&hash);
+                    if !blob_path.exists() {
+                        let blob = if self.config.encrypt_backups { self.apply_cipher(&content)? } else { content.clone() };
+                        write_atomic_async(&blob_path, blob, 0o600).await
+                            .map_err(|e| MigrationError::BackupFailed(format!("Failed to store {}: {}", relative, e)))?;
+                    }
+                    backup_id.clone()
+                }
+            };
+
+            manifest.files.insert(relative, ManifestEntry { hash, size: content.len() as u64, stored_by });
         }
 
+        let auth_file = self.codex_home.join("auth.json");
+        let mut metadata = match tokio::fs::read_to_string(&auth_file).await.ok()
+            .and_then(|content| serde_json::from_str::<serde_json::Value>(&content).ok())
+        {
+            Some(auth_data) => self.extract_backup_metadata(&auth_data, &auth_file).await?,
+            None => BackupMetadata {
+                original_file_size: 0,
+                auth_mode: "Unknown".to_string(),
+                has_tokens: false,
+                has_api_key: false,
+                backup_version: String::new(),
+                system_info: Self::system_info(),
+            },
+        };
+        metadata.original_file_size = total_size;
+        metadata.backup_version = "2.0".to_string();
+
+        let manifest_bytes = serde_json::to_vec_pretty(&manifest)?;
+        let manifest_path = self.backup_dir.join(format!("incremental_backup_{}.manifest.json", backup_id));
+        write_atomic_async(&manifest_path, manifest_bytes.clone(), 0o600).await
+            .map_err(|e| MigrationError::BackupFailed(format!("Failed to write manifest: {}", e)))?;
+
+        let handle = BackupHandle {
+            id: backup_id,
+            created_at: timestamp,
+            file_path: manifest_path,
+            metadata,
+            encrypted: self.config.encrypt_backups,
+            checksum: self.calculate_checksum(&manifest_bytes),
+            kind: BackupKind::Incremental,
+        };
+        self.save_backup_handle(&handle).await?;
+
+        verbose_event!(
+            self.config.verbose_logging,
+            backup_id = %handle.id,
+            files = manifest.files.len(),
+            stored = manifest.files.values().filter(|e| e.stored_by == handle.id).count(),
+            "Created incremental backup"
+        );
+
         Ok(handle)
     }
 
+    /// Manifest of an incremental backup
+    pub async fn read_manifest(&self, handle: &BackupHandle) -> MigrationResult<BackupManifest> {
+        let content = tokio::fs::read(&handle.file_path).await
+            .map_err(|e| MigrationError::BackupFailed(format!("Failed to read manifest: {}", e)))?;
+        Ok(serde_json::from_slice(&content)?)
+    }
+
     /// Verify a backup's integrity
     pub async fn verify_backup(&self, handle: &BackupHandle) -> MigrationResult<BackupVerification> {
         let mut verification = BackupVerification {
@@ -170,8 +322,25 @@ impl BackupManager {
             verification.is_valid = false;
         }
 
-        // Test decryption if encrypted
-        if handle.encrypted {
+        if handle.kind == BackupKind::Incremental {
+            verification.can_decrypt = true;
+            match serde_json::from_slice::<BackupManifest>(&backup_content) {
+                Ok(manifest) => {
+                    for (relative, entry) in &manifest.files {
+                        if let Err(e) = self.read_blob(entry, handle.encrypted).await {
+                            verification.errors.push(format!("{}: {}", relative, e));
+                            verification.can_decrypt = false;
+                            verification.is_valid = false;
+                        }
+                    }
+                }
+                Err(e) => {
+                    verification.errors.push(format!("Unreadable manifest: {}", e));
+                    verification.is_valid = false;
+                }
+            }
+        } else if handle.encrypted {
+            // Test decryption if encrypted
             match self.decrypt_content(&backup_content) {
                 Ok(_) => verification.can_decrypt = true,
                 Err(e) => {
@@ -203,6 +372,10 @@ impl BackupManager {
             ));
         }
 
+        if handle.kind == BackupKind::Incremental {
+            return self.restore_from_manifest(handle).await;
+        }
+
         // Read backup content
         let backup_content = tokio::fs::read(&handle.file_path).await?;
         
@@ -225,25 +398,76 @@ impl BackupManager {
         }
 
         // Write restored content
-        tokio::fs::write(&auth_file, auth_content).await?;
+        write_atomic_async(&auth_file, auth_content, 0o600).await?;
 
-        // Set secure permissions
-        #[cfg(unix)]
-        {
-            use std::os::unix::fs::PermissionsExt;
-            let mut perms = tokio::fs::metadata(&auth_file).await?.permissions();
-            perms.set_mode(0o600);
-            tokio::fs::set_permissions(&auth_file, perms).await?;
+        verbose_event!(self.config.verbose_logging, backup_id = %handle.id, "Restored auth.json from backup");
+
+        Ok(())
+    }
+
+    /// Rewrite every file in an incremental backup's manifest with its backed-up bytes
+    ///
+    /// All blobs are read and checked before anything is written.
+    async fn restore_from_manifest(&self, handle: &BackupHandle) -> MigrationResult<()> {
+        let manifest = self.read_manifest(handle).await?;
+
+        let mut restored = Vec::with_capacity(manifest.files.len());
+        for (relative, entry) in &manifest.files {
+            let content = self.read_blob(entry, handle.encrypted).await
+                .map_err(|e| MigrationError::BackupFailed(format!("Cannot restore {}: {}", relative, e)))?;
+            restored.push((self.codex_home.join(relative), content));
         }
 
-        if self.config.verbose_logging {
-            println!("Restored auth.json from backup: {}", handle.id);
+        for (path, content) in restored {
+            write_atomic_async(&path, content, 0o600).await?;
         }
 
+        verbose_event!(self.config.verbose_logging, backup_id = %handle.id, files = manifest.files.len(), "Restored incremental backup");
+
         Ok(())
     }
 
-    /// List all available backups
+    /// Files under the codex home mapped to their SHA-256, for spotting what a phase changed
+    pub async fn snapshot_files(&self) -> MigrationResult<BTreeMap<String, String>> {
+        let mut snapshot = BTreeMap::new();
+        for relative in self.collect_backup_files().await? {
+            let content = tokio::fs::read(self.codex_home.join(&relative)).await?;
+            snapshot.insert(relative, hex_sha256(&content));
+        }
+        Ok(snapshot)
+    }
+
+    /// Files [`restore_file`](Self::restore_file) can bring back from `handle`
+    pub async fn restorable_files(&self, handle: &BackupHandle) -> MigrationResult<Vec<String>> {
+        match handle.kind {
+            BackupKind::Full => Ok(vec!["auth.json".to_string()]),
+            BackupKind::Incremental => Ok(self.read_manifest(handle).await?.files.into_keys().collect()),
+        }
+    }
+
+    /// Restore a single file from `handle`, leaving everything else under the codex home alone
+    pub async fn restore_file(&self, handle: &BackupHandle, relative: &str) -> MigrationResult<()> {
+        match handle.kind {
+            BackupKind::Full if relative == "auth.json" => self.restore_from_backup(handle).await,
+            BackupKind::Full => Err(MigrationError::BackupFailed(
+                format!("Backup {} only holds auth.json, not {}", handle.id, relative)
+            )),
+            BackupKind::Incremental => {
+                let manifest = self.read_manifest(handle).await?;
+                let entry = manifest.files.get(relative).ok_or_else(|| MigrationError::BackupFailed(
+                    format!("Backup {} does not hold {}", handle.id, relative)
+                ))?;
+                let content = self.read_blob(entry, handle.encrypted).await?;
+                write_atomic_async(&self.codex_home.join(relative), content, 0o600).await?;
+                Ok(())
+            }
+        }
+    }
+
+    /// List all available backups, newest first
+    ///
+    /// Backups created at the same instant are ordered by id, so the order is stable
+    /// across calls; see [`BackupHandle::newest_first`].
     pub async fn list_backups(&self) -> MigrationResult<Vec<BackupHandle>> {
         if !self.backup_dir.exists() {
             return Ok(Vec::new());
@@ -261,8 +485,7 @@ impl BackupManager {
             }
         }
 
-        // Sort by creation time (newest first)
-        handles.sort_by(|a, b| b.created_at.cmp(&a.created_at));
+        handles.sort_by(BackupHandle::newest_first);
         Ok(handles)
     }
 
@@ -292,50 +515,37 @@ impl BackupManager {
             tokio::fs::rename(handle_path, archived_handle_path).await?;
         }
 
-        if self.config.verbose_logging {
-            println!("Archived backup: {}", backup_id);
-        }
+        verbose_event!(self.config.verbose_logging, backup_id = %backup_id, "Archived backup");
 
         Ok(())
     }
 
     /// Clean up old backups based on retention policy
+    ///
+    /// Removes backups past the retention period and the oldest beyond `max_backups`,
+    /// in [`list_backups`](Self::list_backups) order. The newest backup is always kept.
     pub async fn cleanup_old_backups(&self) -> MigrationResult<()> {
         let handles = self.list_backups().await?;
         let retention_cutoff = Utc::now() - chrono::Duration::days(self.config.backup_retention_days as i64);
         let mut removed_count = 0;
 
-        // Remove backups older than retention period (keeping at least one)
-        for handle in handles.iter().skip(1) { // Skip the newest backup
-            if handle.created_at < retention_cutoff {
-                self.delete_backup(&handle.id).await?;
-                removed_count += 1;
-            }
-        }
-
-        // Enforce max backup limit
-        if handles.len() > self.config.max_backups {
-            let excess_count = handles.len() - self.config.max_backups;
-            for handle in handles.iter().skip(self.config.max_backups) {
-                self.delete_backup(&handle.id).await?;
+        for (position, handle) in handles.iter().enumerate().skip(1) {
+            if position >= self.config.max_backups || handle.created_at < retention_cutoff {
+                self.delete_backup(handle).await?;
                 removed_count += 1;
             }
         }
 
-        if self.config.verbose_logging && removed_count > 0 {
-            println!("Cleaned up {} old backups", removed_count);
+        if removed_count > 0 {
+            self.collect_unreferenced_blobs().await?;
+            verbose_event!(self.config.verbose_logging, removed_count, "Cleaned up old backups");
         }
 
         Ok(())
     }
 
     /// Delete a specific backup
-    async fn delete_backup(&self, backup_id: &str) -> MigrationResult<()> {
-        let handles = self.list_backups().await?;
-        let handle = handles.iter()
-            .find(|h| h.id == backup_id)
-            .ok_or_else(|| MigrationError::BackupFailed(format!("Backup {} not found", backup_id)))?;
-
+    async fn delete_backup(&self, handle: &BackupHandle) -> MigrationResult<()> {
         // Remove backup file
         if handle.file_path.exists() {
             tokio::fs::remove_file(&handle.file_path).await?;
@@ -350,6 +560,93 @@ impl BackupManager {
         Ok(())
     }
 
+    /// Remove blobs no remaining manifest refers to, archived ones included
+    async fn collect_unreferenced_blobs(&self) -> MigrationResult<()> {
+        let blob_dir = self.blob_dir();
+        if !blob_dir.exists() {
+            return Ok(());
+        }
+
+        let mut referenced = HashSet::new();
+        for dir in [self.backup_dir.clone(), self.backup_dir.join("archived")] {
+            let Ok(mut entries) = tokio::fs::read_dir(&dir).await else { continue };
+            while let Some(entry) = entries.next_entry().await? {
+                let path = entry.path();
+                if path.extension().and_then(|s| s.to_str()) != Some("handle") {
+                    continue;
+                }
+                let Ok(mut handle) = self.load_backup_handle(&path).await else { continue };
+                if handle.kind != BackupKind::Incremental {
+                    continue;
+                }
+                if !handle.file_path.exists() {
+                    // Archiving moves the manifest next to its handle
+                    handle.file_path = dir.join(handle.file_path.file_name().unwrap_or_default());
+                }
+                match self.read_manifest(&handle).await {
+                    Ok(manifest) => referenced.extend(manifest.files.into_values().map(|e| e.hash)),
+                    // Keep everything rather than risk deleting blobs a manifest needs
+                    Err(_) => return Ok(()),
+                }
+            }
+        }
+
+        let mut entries = tokio::fs::read_dir(&blob_dir).await?;
+        while let Some(entry) = entries.next_entry().await? {
+            if !referenced.contains(entry.file_name().to_string_lossy().as_ref()) {
+                tokio::fs::remove_file(entry.path()).await?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Files under the codex home to back up, relative and with `/` separators
+    async fn collect_backup_files(&self) -> MigrationResult<Vec<String>> {
+        let mut files = Vec::new();
+        let mut pending = vec![self.codex_home.clone()];
+
+        while let Some(dir) = pending.pop() {
+            let mut entries = tokio::fs::read_dir(&dir).await?;
+            while let Some(entry) = entries.next_entry().await? {
+                let path = entry.path();
+                if path == self.backup_dir || entry.file_name() == crate::security::login_lock::LOGIN_LOCK_FILE {
+                    continue;
+                }
+                let file_type = entry.file_type().await?;
+                if file_type.is_dir() {
+                    pending.push(path);
+                } else if file_type.is_file() {
+                    if let Ok(relative) = path.strip_prefix(&self.codex_home) {
+                        let parts: Vec<_> = relative.components().map(|c| c.as_os_str().to_string_lossy()).collect();
+                        files.push(parts.join("/"));
+                    }
+                }
+            }
+        }
+
+        files.sort();
+        Ok(files)
+    }
+
+    fn blob_dir(&self) -> PathBuf {
+        self.backup_dir.join("blobs")
+    }
+
+    fn blob_path(&self, hash: &str) -> PathBuf {
+        self.blob_dir().join(hash)
+    }
+
+    /// Plaintext of a manifest entry's blob, checked against its hash
+    async fn read_blob(&self, entry: &ManifestEntry, encrypted: bool) -> MigrationResult<Vec<u8>> {
+        let stored = tokio::fs::read(self.blob_path(&entry.hash)).await
+            .map_err(|e| MigrationError::BackupFailed(format!("Missing blob {}: {}", entry.hash, e)))?;
+        let content = if encrypted { self.apply_cipher(&stored)? } else { stored };
+        if hex_sha256(&content) != entry.hash {
+            return Err(MigrationError::BackupFailed(format!("Blob {} is corrupted", entry.hash)));
+        }
+        Ok(content)
+    }
+
     /// Ensure backup directory exists
     async fn ensure_backup_dir(&self) -> MigrationResult<()> {
         if !self.backup_dir.exists() {
@@ -381,22 +678,25 @@ impl BackupManager {
             "Unknown".to_string()
         };
 
-        let mut system_info = HashMap::new();
-        system_info.insert("hostname".to_string(), 
-            gethostname::gethostname().to_string_lossy().to_string());
-        system_info.insert("platform".to_string(), std::env::consts::OS.to_string());
-        system_info.insert("arch".to_string(), std::env::consts::ARCH.to_string());
-
         Ok(BackupMetadata {
             original_file_size: file_metadata.len(),
             auth_mode,
             has_tokens,
             has_api_key,
             backup_version: "1.0".to_string(),
-            system_info,
+            system_info: Self::system_info(),
         })
     }
 
+    fn system_info() -> HashMap<String, String> {
+        let mut system_info = HashMap::new();
+        system_info.insert("hostname".to_string(), 
+            gethostname::gethostname().to_string_lossy().to_string());
+        system_info.insert("platform".to_string(), std::env::consts::OS.to_string());
+        system_info.insert("arch".to_string(), std::env::consts::ARCH.to_string());
+        system_info
+    }
+
     /// Save backup handle to disk
     async fn save_backup_handle(&self, handle: &BackupHandle) -> MigrationResult<()> {
         let handle_path = self.backup_dir.join(format!("{}.handle", handle.id));
@@ -440,31 +740,19 @@ impl BackupManager {
 
     /// Encrypt content using XOR cipher (simple encryption for demo)
     fn encrypt_content(&self, content: &str) -> MigrationResult<Vec<u8>> {
-        if let Some(key) = &self.encryption_key {
-            let content_bytes = content.as_bytes();
-            let mut encrypted = Vec::with_capacity(content_bytes.len());
-            
-            for (i, &byte) in content_bytes.iter().enumerate() {
-                encrypted.push(byte ^ key[i % key.len()]);
-            }
-            
-            Ok(encrypted)
-        } else {
-            Err(MigrationError::BackupFailed("No encryption key available".to_string()))
-        }
+        self.apply_cipher(content.as_bytes())
     }
 
     /// Decrypt content using XOR cipher
     fn decrypt_content(&self, encrypted: &[u8]) -> MigrationResult<String> {
+        String::from_utf8(self.apply_cipher(encrypted)?)
+            .map_err(|e| MigrationError::BackupFailed(format!("Decryption failed: {}", e)))
+    }
+
+    /// XOR `bytes` with the key; the same call encrypts and decrypts
+    fn apply_cipher(&self, bytes: &[u8]) -> MigrationResult<Vec<u8>> {
         if let Some(key) = &self.encryption_key {
-            let mut decrypted = Vec::with_capacity(encrypted.len());
-            
-            for (i, &byte) in encrypted.iter().enumerate() {
-                decrypted.push(byte ^ key[i % key.len()]);
-            }
-            
-            String::from_utf8(decrypted)
-                .map_err(|e| MigrationError::BackupFailed(format!("Decryption failed: {}", e)))
+            Ok(bytes.iter().enumerate().map(|(i, &byte)| byte ^ key[i % key.len()]).collect())
         } else {
             Err(MigrationError::BackupFailed("No encryption key available".to_string()))
         }
@@ -489,6 +777,10 @@ impl BackupManager {
     }
 }
 
+fn hex_sha256(content: &[u8]) -> String {
+    Sha256::digest(content).iter().map(|b| format!("{:02x}", b)).collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -554,6 +846,143 @@ mod tests {
         assert_eq!(backups_after.len(), 2);
     }
 
+    #[tokio::test]
+    async fn test_backups_in_quick_succession_list_newest_first() {
+        let temp_dir = tempdir().unwrap();
+        let mut config = MigrationConfig::default();
+        config.max_backups = 3;
+        let manager = BackupManager::new(temp_dir.path(), &config);
+        tokio::fs::write(temp_dir.path().join("auth.json"), r#"{"test": "data"}"#).await.unwrap();
+
+        // No sleeps: several of these land in the same millisecond
+        let mut created = Vec::new();
+        for _ in 0..6 {
+            created.push(manager.create_backup().await.unwrap().id);
+        }
+        let mut sorted = created.clone();
+        sorted.sort();
+        assert_eq!(sorted, created, "ids sort in creation order");
+        assert_eq!(created.iter().collect::<HashSet<_>>().len(), created.len());
+
+        let listed: Vec<String> = manager.list_backups().await.unwrap().into_iter().map(|h| h.id).collect();
+        let newest_first: Vec<String> = created.iter().rev().cloned().collect();
+        assert_eq!(listed, newest_first);
+        let relisted: Vec<String> = manager.list_backups().await.unwrap().into_iter().map(|h| h.id).collect();
+        assert_eq!(relisted, listed);
+
+        // Cleanup keeps exactly the newest max_backups
+        manager.cleanup_old_backups().await.unwrap();
+        let kept: Vec<String> = manager.list_backups().await.unwrap().into_iter().map(|h| h.id).collect();
+        assert_eq!(kept, newest_first[..3]);
+        assert_eq!(manager.get_backup_count().await.unwrap(), 3);
+    }
+
+    #[test]
+    fn test_backups_with_equal_timestamps_order_by_id() {
+        let handle = |id: &str| BackupHandle {
+            id: id.to_string(),
+            created_at: DateTime::from_timestamp(1_700_000_000, 0).unwrap(),
+            file_path: PathBuf::new(),
+            metadata: BackupMetadata {
+                original_file_size: 0,
+                auth_mode: "Unknown".to_string(),
+                has_tokens: false,
+                has_api_key: false,
+                backup_version: String::new(),
+                system_info: HashMap::new(),
+            },
+            encrypted: false,
+            checksum: String::new(),
+            kind: BackupKind::Full,
+        };
+        let mut handles = vec![handle("b"), handle("c"), handle("a")];
+        handles.sort_by(BackupHandle::newest_first);
+        let ids: Vec<&str> = handles.iter().map(|h| h.id.as_str()).collect();
+        assert_eq!(ids, ["c", "b", "a"]);
+    }
+
+    async fn write_codex_home(root: &Path, auth: &str) {
+        tokio::fs::create_dir_all(root.join("sessions")).await.unwrap();
+        tokio::fs::write(root.join("auth.json"), auth).await.unwrap();
+        tokio::fs::write(root.join("config.toml"), "model = \"o3\"\n").await.unwrap();
+        let history: Vec<u8> = (0..64 * 1024).map(|i| (i % 251) as u8).collect();
+        tokio::fs::write(root.join("sessions").join("history.bin"), history).await.unwrap();
+    }
+
+    async fn blob_count(root: &Path) -> usize {
+        let mut entries = tokio::fs::read_dir(root.join(".backups").join("blobs")).await.unwrap();
+        let mut count = 0;
+        while entries.next_entry().await.unwrap().is_some() {
+            count += 1;
+        }
+        count
+    }
+
+    #[tokio::test]
+    async fn test_incremental_backup_stores_only_changed_files() {
+        let temp_dir = tempdir().unwrap();
+        let manager = BackupManager::new(temp_dir.path(), &MigrationConfig::default());
+        write_codex_home(temp_dir.path(), r#"{"OPENAI_API_KEY": "sk-first"}"#).await;
+
+        let first = manager.create_incremental_backup(None).await.unwrap();
+        assert_eq!(first.kind, BackupKind::Incremental);
+        assert_eq!(blob_count(temp_dir.path()).await, 3);
+
+        tokio::fs::write(temp_dir.path().join("auth.json"), r#"{"OPENAI_API_KEY": "sk-second"}"#).await.unwrap();
+        let second = manager.create_incremental_backup(Some(first.clone())).await.unwrap();
+
+        // Only the changed auth.json produced a new blob
+        assert_eq!(blob_count(temp_dir.path()).await, 4);
+        let manifest = manager.read_manifest(&second).await.unwrap();
+        assert_eq!(manifest.base_id.as_deref(), Some(first.id.as_str()));
+        assert_eq!(manifest.files["sessions/history.bin"].stored_by, first.id);
+        assert_eq!(manifest.files["config.toml"].stored_by, first.id);
+        assert_eq!(manifest.files["auth.json"].stored_by, second.id);
+        assert!(!manifest.files.keys().any(|path| path.starts_with(".backups")));
+
+        // A full backup can't serve as a base
+        let full = manager.create_backup().await.unwrap();
+        assert!(manager.create_incremental_backup(Some(full)).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_incremental_restore_reconstructs_exact_bytes() {
+        let temp_dir = tempdir().unwrap();
+        let manager = BackupManager::new(temp_dir.path(), &MigrationConfig::default());
+        write_codex_home(temp_dir.path(), r#"{"OPENAI_API_KEY": "sk-original"}"#).await;
+        let history_path = temp_dir.path().join("sessions").join("history.bin");
+        let original_history = tokio::fs::read(&history_path).await.unwrap();
+
+        let base = manager.create_incremental_backup(None).await.unwrap();
+        tokio::fs::write(temp_dir.path().join("config.toml"), "model = \"gpt-5\"\n").await.unwrap();
+        let handle = manager.create_incremental_backup(Some(base)).await.unwrap();
+        assert!(manager.verify_backup(&handle).await.unwrap().is_valid);
+
+        // Blobs are encrypted at rest by default
+        let manifest = manager.read_manifest(&handle).await.unwrap();
+        let stored = tokio::fs::read(manager.blob_path(&manifest.files["auth.json"].hash)).await.unwrap();
+        assert!(!String::from_utf8_lossy(&stored).contains("sk-original"));
+
+        tokio::fs::write(temp_dir.path().join("auth.json"), r#"{"modified": true}"#).await.unwrap();
+        tokio::fs::remove_file(&history_path).await.unwrap();
+        manager.restore_from_backup(&handle).await.unwrap();
+
+        assert_eq!(
+            tokio::fs::read_to_string(temp_dir.path().join("auth.json")).await.unwrap(),
+            r#"{"OPENAI_API_KEY": "sk-original"}"#
+        );
+        assert_eq!(tokio::fs::read(&history_path).await.unwrap(), original_history);
+        assert_eq!(
+            tokio::fs::read_to_string(temp_dir.path().join("config.toml")).await.unwrap(),
+            "model = \"gpt-5\"\n"
+        );
+
+        // A corrupted blob fails verification instead of restoring wrong bytes
+        tokio::fs::write(manager.blob_path(&manifest.files["auth.json"].hash), b"garbage").await.unwrap();
+        assert!(!manager.verify_backup(&handle).await.unwrap().is_valid);
+        assert!(manager.restore_from_backup(&handle).await.is_err());
+    }
+
     #[tokio::test]
     async fn test_encrypted_backup() {
         let temp_dir = tempdir().unwrap();
@@ -4,14 +4,15 @@
 /// Preserves all existing data while adding Claude authentication capabilities.
 
 use super::{BackupHandle, MigrationConfig, MigrationError, MigrationResult};
+use crate::security::write_atomic_async;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::path::{Path, PathBuf};
 
-/// Result of a migration operation
+/// Outcome of rewriting the auth files into the unified format
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct MigrationResult {
+pub struct MigrationOutcome {
     pub success: bool,
     pub migrated_providers: Vec<String>,
     pub preserved_data: Vec<String>,
@@ -32,6 +33,30 @@ pub struct OriginalAuthJson {
     pub last_refresh: Option<DateTime<Utc>>,
 }
 
+/// Credential shape detected in a legacy auth.json
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LegacyAuthShape {
+    /// Bare `{"OPENAI_API_KEY": "..."}`
+    ApiKey,
+    /// ChatGPT OAuth token blob without an API key
+    ChatGptTokens,
+    /// Both an API key and ChatGPT tokens
+    ApiKeyAndTokens,
+}
+
+impl OriginalAuthJson {
+    /// Detect which credentials the legacy file carries; `None` if it has neither
+    pub fn shape(&self) -> Option<LegacyAuthShape> {
+        let has_key = self.openai_api_key.as_deref().is_some_and(|key| !key.trim().is_empty());
+        match (has_key, self.tokens.is_some()) {
+            (true, true) => Some(LegacyAuthShape::ApiKeyAndTokens),
+            (true, false) => Some(LegacyAuthShape::ApiKey),
+            (false, true) => Some(LegacyAuthShape::ChatGptTokens),
+            (false, false) => None,
+        }
+    }
+}
+
 /// Token data structure from original auth
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TokenData {
@@ -51,6 +76,9 @@ pub struct UnifiedAuthJson {
     pub migration_info: MigrationInfo,
     pub providers: HashMap<String, ProviderAuth>,
     pub preferences: AuthPreferences,
+    /// Ask the unified manager to write OpenAI key changes through to auth.json
+    #[serde(default)]
+    pub maintain_legacy_auth_json: bool,
 }
 
 fn default_version() -> String {
@@ -162,9 +190,9 @@ impl AuthMigrator {
     }
 
     /// Execute migration to unified format
-    pub async fn migrate_to_unified_format(&self, backup_handle: &BackupHandle) -> MigrationResult<MigrationResult> {
+    pub async fn migrate_to_unified_format(&self, backup_handle: &BackupHandle) -> MigrationResult<MigrationOutcome> {
         let start_time = Utc::now();
-        let mut result = MigrationResult {
+        let mut result = MigrationOutcome {
             success: false,
             migrated_providers: Vec::new(),
             preserved_data: Vec::new(),
@@ -174,14 +202,9 @@ impl AuthMigrator {
             metadata: HashMap::new(),
         };
 
-        // Load original auth.json
-        let original_auth = match self.load_original_auth().await {
-            Ok(auth) => auth,
-            Err(e) => {
-                result.metadata.insert("error".to_string(), e.to_string());
-                return Ok(result);
-            }
-        };
+        // Load original auth.json; nothing is written if it is missing or malformed
+        let (original_auth, shape) = self.load_original_auth().await?;
+        result.metadata.insert("legacy_auth_shape".to_string(), format!("{:?}", shape));
 
         // Create unified auth structure
         let unified_auth = self.create_unified_auth(&original_auth, backup_handle).await?;
@@ -190,8 +213,9 @@ impl AuthMigrator {
         self.preserve_original_auth().await?;
         result.preserved_data.push("original_auth.json".to_string());
 
-        // Write unified auth file
+        // Write unified auth file and make sure it reads back
         self.write_unified_auth(&unified_auth).await?;
+        self.verify_unified_auth().await?;
         result.created_files.push("unified_auth.json".to_string());
 
         // Update main auth.json to point to unified system
@@ -212,15 +236,17 @@ impl AuthMigrator {
         result.metadata.insert("backup_id".to_string(), backup_handle.id.clone());
         result.metadata.insert("migration_timestamp".to_string(), start_time.to_rfc3339());
 
-        if self.config.verbose_logging {
-            println!("Migration completed successfully in {:?}", result.migration_duration);
-        }
+        verbose_event!(
+            self.config.verbose_logging,
+            latency_ms = result.migration_duration.num_milliseconds().max(0) as u64,
+            "Migration completed successfully"
+        );
 
         Ok(result)
     }
 
-    /// Load the original auth.json file
-    async fn load_original_auth(&self) -> MigrationResult<OriginalAuthJson> {
+    /// Load the original auth.json file and detect its credential shape
+    async fn load_original_auth(&self) -> MigrationResult<(OriginalAuthJson, LegacyAuthShape)> {
         let auth_file = self.codex_home.join("auth.json");
         if !auth_file.exists() {
             return Err(MigrationError::ExtensionFailed(
@@ -234,7 +260,11 @@ impl AuthMigrator {
                 format!("Failed to parse original auth.json: {}", e)
             ))?;
 
-        Ok(auth)
+        let shape = auth.shape().ok_or_else(|| MigrationError::ValidationFailed(
+            "Original auth.json contains neither OPENAI_API_KEY nor ChatGPT tokens".to_string()
+        ))?;
+
+        Ok((auth, shape))
     }
 
     /// Create unified authentication structure from original data
@@ -242,9 +272,19 @@ impl AuthMigrator {
         let now = Utc::now();
         let mut providers = HashMap::new();
 
+        let mut preserved_data_types = Vec::new();
+        match original.shape() {
+            Some(LegacyAuthShape::ApiKey) => preserved_data_types.push("openai_api_key".to_string()),
+            Some(LegacyAuthShape::ChatGptTokens) => preserved_data_types.push("openai_tokens".to_string()),
+            Some(LegacyAuthShape::ApiKeyAndTokens) | None => {
+                preserved_data_types.push("openai_tokens".to_string());
+                preserved_data_types.push("openai_api_key".to_string());
+            }
+        }
+
         // Migrate OpenAI authentication data
         let openai_auth = ProviderAuth::OpenAI {
-            api_key: original.openai_api_key.clone(),
+            api_key: original.openai_api_key.clone().filter(|key| !key.trim().is_empty()),
             oauth_tokens: original.tokens.as_ref().map(|tokens| OpenAITokens {
                 id_token: tokens.id_token.clone(),
                 access_token: tokens.access_token.clone(),
@@ -279,10 +319,15 @@ impl AuthMigrator {
                 migration_date: now,
                 backup_id: backup_handle.id.clone(),
                 migration_tool_version: env!("CARGO_PKG_VERSION").to_string(),
-                preserved_data_types: vec!["openai_tokens".to_string(), "openai_api_key".to_string()],
+                preserved_data_types,
             },
             providers,
-            preferences: AuthPreferences::default(),
+            // Legacy files only ever held OpenAI credentials
+            preferences: AuthPreferences {
+                preferred_provider: Some("openai".to_string()),
+                ..AuthPreferences::default()
+            },
+            maintain_legacy_auth_json: self.config.maintain_legacy_auth_json,
         })
     }
 
@@ -292,36 +337,30 @@ impl AuthMigrator {
         let backup_file = self.codex_home.join("auth.json.pre_migration");
 
         if auth_file.exists() {
-            tokio::fs::copy(&auth_file, &backup_file).await?;
-            
-            // Set secure permissions
-            #[cfg(unix)]
-            {
-                use std::os::unix::fs::PermissionsExt;
-                let mut perms = tokio::fs::metadata(&backup_file).await?.permissions();
-                perms.set_mode(0o600);
-                tokio::fs::set_permissions(&backup_file, perms).await?;
-            }
+            let content = tokio::fs::read(&auth_file).await?;
+            write_atomic_async(&backup_file, content, 0o600).await?;
         }
 
         Ok(())
     }
 
-    /// Write the unified authentication file
+    /// Write the unified authentication file atomically (temp file + rename)
     async fn write_unified_auth(&self, unified_auth: &UnifiedAuthJson) -> MigrationResult<()> {
         let unified_file = self.codex_home.join("unified_auth.json");
         let content = serde_json::to_string_pretty(unified_auth)?;
-        
-        tokio::fs::write(&unified_file, content).await?;
+        write_atomic_async(&unified_file, content, 0o600).await?;
 
-        // Set secure permissions
-        #[cfg(unix)]
-        {
-            use std::os::unix::fs::PermissionsExt;
-            let mut perms = tokio::fs::metadata(&unified_file).await?.permissions();
-            perms.set_mode(0o600);
-            tokio::fs::set_permissions(&unified_file, perms).await?;
-        }
+        Ok(())
+    }
+
+    /// Check that the written unified_auth.json parses back into `UnifiedAuthJson`
+    async fn verify_unified_auth(&self) -> MigrationResult<()> {
+        let unified_file = self.codex_home.join("unified_auth.json");
+        let content = tokio::fs::read_to_string(&unified_file).await?;
+        serde_json::from_str::<UnifiedAuthJson>(&content)
+            .map_err(|e| MigrationError::ValidationFailed(
+                format!("unified_auth.json did not parse back after writing: {}", e)
+            ))?;
 
         Ok(())
     }
@@ -333,17 +372,7 @@ impl AuthMigrator {
         // Create a bridge structure that maintains backward compatibility
         let bridge_auth = self.create_bridge_auth(unified_auth).await?;
         let content = serde_json::to_string_pretty(&bridge_auth)?;
-        
-        tokio::fs::write(&auth_file, content).await?;
-
-        // Set secure permissions
-        #[cfg(unix)]
-        {
-            use std::os::unix::fs::PermissionsExt;
-            let mut perms = tokio::fs::metadata(&auth_file).await?.permissions();
-            perms.set_mode(0o600);
-            tokio::fs::set_permissions(&auth_file, perms).await?;
-        }
+        write_atomic_async(&auth_file, content, 0o600).await?;
 
         Ok(())
     }
@@ -389,9 +418,23 @@ impl AuthMigrator {
         }
     }
 
+    /// Claude auth file: the configured override, otherwise the one in codex_home
+    fn claude_auth_file(&self) -> PathBuf {
+        self.config
+            .auth_file_path
+            .clone()
+            .unwrap_or_else(|| self.codex_home.join("claude_auth.json"))
+    }
+
     /// Create Claude auth placeholder file
+    ///
+    /// An overridden location may be a shared secrets directory that already holds
+    /// credentials, so an existing file there is left alone.
     async fn create_claude_auth_placeholder(&self) -> MigrationResult<()> {
-        let claude_file = self.codex_home.join("claude_auth.json");
+        let claude_file = self.claude_auth_file();
+        if self.config.auth_file_path.is_some() && tokio::fs::try_exists(&claude_file).await.unwrap_or(false) {
+            return Ok(());
+        }
         
         let placeholder = serde_json::json!({
             "version": "2.0",
@@ -405,16 +448,7 @@ impl AuthMigrator {
         });
 
         let content = serde_json::to_string_pretty(&placeholder)?;
-        tokio::fs::write(&claude_file, content).await?;
-
-        // Set secure permissions
-        #[cfg(unix)]
-        {
-            use std::os::unix::fs::PermissionsExt;
-            let mut perms = tokio::fs::metadata(&claude_file).await?.permissions();
-            perms.set_mode(0o600);
-            tokio::fs::set_permissions(&claude_file, perms).await?;
-        }
+        write_atomic_async(&claude_file, content, 0o600).await?;
 
         Ok(())
     }
@@ -462,7 +496,7 @@ impl AuthMigrator {
         }
 
         // Check Claude placeholder
-        let claude_file = self.codex_home.join("claude_auth.json");
+        let claude_file = self.claude_auth_file();
         result.claude_placeholder_created = claude_file.exists();
 
         Ok(result)
@@ -497,9 +531,7 @@ impl AuthMigrator {
 
     /// Rollback migration if needed
     pub async fn rollback_migration(&self, backup_handle: &BackupHandle) -> MigrationResult<()> {
-        if self.config.verbose_logging {
-            println!("Rolling back migration using backup: {}", backup_handle.id);
-        }
+        tracing::warn!(backup_id = %backup_handle.id, "Rolling back migration");
 
         // Restore original auth.json from backup
         let backup_manager = super::BackupManager::new(&self.codex_home, &self.config);
@@ -523,9 +555,7 @@ impl AuthMigrator {
             }
         }
 
-        if self.config.verbose_logging {
-            println!("Migration rollback completed successfully");
-        }
+        verbose_event!(self.config.verbose_logging, backup_id = %backup_handle.id, "Migration rollback completed");
 
         Ok(())
     }
@@ -578,6 +608,7 @@ mod tests {
             },
             encrypted: false,
             checksum: "test-checksum".to_string(),
+            kind: super::super::backup_manager::BackupKind::Full,
         };
 
         // Execute migration
@@ -603,6 +634,17 @@ mod tests {
         } else {
             panic!("OpenAI provider not found or invalid");
         }
+        assert_eq!(unified_auth.preferences.preferred_provider.as_deref(), Some("openai"));
+        assert_eq!(unified_auth.migration_info.preserved_data_types, vec!["openai_api_key".to_string()]);
+        assert_eq!(result.metadata.get("legacy_auth_shape").map(String::as_str), Some("ApiKey"));
+
+        // Written with owner-only permissions
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = std::fs::metadata(temp_dir.path().join("unified_auth.json")).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
     }
 
     #[tokio::test]
@@ -641,6 +683,7 @@ mod tests {
             },
             encrypted: false,
             checksum: "test-checksum-oauth".to_string(),
+            kind: super::super::backup_manager::BackupKind::Full,
         };
 
         // Execute migration
@@ -661,6 +704,45 @@ mod tests {
         } else {
             panic!("OpenAI provider tokens not preserved");
         }
+        assert_eq!(unified_auth.preferences.preferred_provider.as_deref(), Some("openai"));
+        assert_eq!(result.metadata.get("legacy_auth_shape").map(String::as_str), Some("ChatGptTokens"));
+    }
+
+    #[tokio::test]
+    async fn test_migration_rejects_malformed_legacy_auth() {
+        let config = MigrationConfig::default();
+
+        for content in ["{ \"OPENAI_API_KEY\": ", "{}", "{ \"OPENAI_API_KEY\": \"  \" }"] {
+            let temp_dir = tempdir().unwrap();
+            let migrator = AuthMigrator::new(temp_dir.path(), &config);
+            tokio::fs::write(temp_dir.path().join("auth.json"), content).await.unwrap();
+
+            let backup_handle = BackupHandle {
+                id: "test-malformed".to_string(),
+                created_at: Utc::now(),
+                file_path: temp_dir.path().join("backup.json"),
+                metadata: super::super::backup_manager::BackupMetadata {
+                    original_file_size: content.len() as u64,
+                    auth_mode: "Unknown".to_string(),
+                    has_tokens: false,
+                    has_api_key: false,
+                    backup_version: "1.0".to_string(),
+                    system_info: HashMap::new(),
+                },
+                encrypted: false,
+                checksum: "test-malformed-checksum".to_string(),
+                kind: super::super::backup_manager::BackupKind::Full,
+            };
+
+            let result = migrator.migrate_to_unified_format(&backup_handle).await;
+            assert!(result.is_err(), "expected error for {:?}", content);
+
+            // The legacy file is left untouched and nothing new is created
+            assert!(!temp_dir.path().join("unified_auth.json").exists());
+            assert!(!temp_dir.path().join("auth.json.pre_migration").exists());
+            let remaining = tokio::fs::read_to_string(temp_dir.path().join("auth.json")).await.unwrap();
+            assert_eq!(remaining, content);
+        }
     }
 
     #[tokio::test]
@@ -693,6 +775,7 @@ mod tests {
             },
             encrypted: false,
             checksum: "test-compat-checksum".to_string(),
+            kind: super::super::backup_manager::BackupKind::Full,
         };
 
         migrator.migrate_to_unified_format(&backup_handle).await.unwrap();
@@ -24,10 +24,15 @@ pub mod testing;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
+use tokio::sync::mpsc;
+use tokio_util::sync::CancellationToken;
+use tracing::Instrument;
 
-pub use backup_manager::BackupManager;
-pub use migrator::AuthMigrator;
+use crate::security::{LoginLock, LoginLockError};
+
+pub use backup_manager::{BackupHandle, BackupKind, BackupManager, BackupManifest, ManifestEntry};
+pub use migrator::{AuthMigrator, MigrationOutcome};
 pub use validator::MigrationValidator;
 pub use rollback::RollbackManager;
 pub use testing::MigrationTester;
@@ -42,6 +47,26 @@ pub struct MigrationProgress {
     pub backup_handle: Option<String>,
     pub rollback_available: bool,
     pub metadata: HashMap<String, String>,
+    /// Files each executed phase changed, oldest first; lets rollback undo only those
+    #[serde(default)]
+    pub journal: Vec<JournalEntry>,
+}
+
+/// How a file under the codex home changed while a phase ran
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum FileChange {
+    Created,
+    Modified,
+    Removed,
+}
+
+/// One file change recorded in [`MigrationProgress::journal`]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct JournalEntry {
+    pub phase: MigrationPhase,
+    /// Path relative to the codex home, with `/` separators
+    pub path: String,
+    pub change: FileChange,
 }
 
 /// Migration phases in order
@@ -82,6 +107,38 @@ impl MigrationPhase {
     }
 }
 
+/// Progress file under the codex home; rewritten between phases, so never journaled
+const PROGRESS_FILE: &str = ".migration_progress.json";
+
+/// Metadata key set when a phase's changes couldn't be journaled
+const JOURNAL_INCOMPLETE: &str = "journal_incomplete";
+
+/// Phases from Backup through Cleanup, which together make up 100% progress
+const WORKING_PHASE_COUNT: usize = 5;
+
+/// What a [`MigrationEvent`] reports
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MigrationEventKind {
+    PhaseStarted,
+    PhaseCompleted,
+    PhaseFailed,
+    RollbackStarted,
+    RollbackCompleted,
+    RollbackFailed,
+    /// Every phase finished; the last event of a successful migration
+    Completed,
+}
+
+/// Live progress from [`MigrationCoordinator::execute_migration_with_progress`]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MigrationEvent {
+    pub kind: MigrationEventKind,
+    pub phase: MigrationPhase,
+    /// Share of the working phases completed so far, 0-100
+    pub percent: u8,
+    pub message: String,
+}
+
 /// Migration configuration options
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MigrationConfig {
@@ -91,12 +148,26 @@ pub struct MigrationConfig {
     pub auto_rollback_on_failure: bool,
     /// Validate tokens before migration
     pub validate_tokens_before_migration: bool,
+    /// Stop the migration when a stored credential is expired or rejected, rather than
+    /// only reporting it
+    #[serde(default)]
+    pub abort_on_invalid_tokens: bool,
     /// Create encrypted backups
     pub encrypt_backups: bool,
     /// Backup retention period in days
     pub backup_retention_days: u32,
     /// Enable verbose logging
     pub verbose_logging: bool,
+    /// Back up the whole codex home incrementally instead of copying auth.json
+    #[serde(default)]
+    pub incremental_backups: bool,
+    /// Keep auth.json in step with later OpenAI credential changes, for tooling that
+    /// still reads it directly
+    #[serde(default)]
+    pub maintain_legacy_auth_json: bool,
+    /// `claude_auth.json` location used instead of codex_home
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub auth_file_path: Option<PathBuf>,
 }
 
 impl Default for MigrationConfig {
@@ -105,9 +176,13 @@ impl Default for MigrationConfig {
             max_backups: 10,
             auto_rollback_on_failure: true,
             validate_tokens_before_migration: true,
+            abort_on_invalid_tokens: false,
             encrypt_backups: true,
             backup_retention_days: 30,
             verbose_logging: false,
+            incremental_backups: false,
+            maintain_legacy_auth_json: false,
+            auth_file_path: None,
         }
     }
 }
@@ -147,6 +222,12 @@ pub enum MigrationError {
     
     #[error("Network error: {0}")]
     NetworkError(#[from] reqwest::Error),
+
+    #[error(transparent)]
+    LoginLocked(#[from] LoginLockError),
+
+    #[error("Migration cancelled")]
+    Cancelled,
 }
 
 /// Main migration coordinator
@@ -182,7 +263,38 @@ impl MigrationCoordinator {
     }
 
     /// Execute the complete migration process
+    ///
+    /// Holds the login lock throughout, so a concurrent `code auth login` fails fast
+    /// instead of writing credentials mid-migration.
     pub async fn execute_migration(&mut self) -> MigrationResult<MigrationProgress> {
+        // Nobody listens, so every send fails immediately
+        let (events, _) = mpsc::channel(1);
+        self.execute_migration_with_progress(events).await
+    }
+
+    /// Execute the migration, reporting each phase start and finish, failures and
+    /// rollback on `events` as they happen
+    ///
+    /// Events are best effort: a dropped receiver doesn't stop the migration.
+    pub async fn execute_migration_with_progress(
+        &mut self,
+        events: mpsc::Sender<MigrationEvent>,
+    ) -> MigrationResult<MigrationProgress> {
+        self.execute_migration_with_cancellation(events, CancellationToken::new()).await
+    }
+
+    /// Execute the migration until it completes or `cancel` is triggered
+    ///
+    /// Cancellation is checked between phases. A cancelled migration is rolled back when
+    /// a backup exists, left in [`MigrationPhase::RolledBack`] and reported as
+    /// [`MigrationError::Cancelled`]. Once the irreversible Cleanup phase has started,
+    /// cancellation is ignored and the migration runs to completion.
+    pub async fn execute_migration_with_cancellation(
+        &mut self,
+        events: mpsc::Sender<MigrationEvent>,
+        cancel: CancellationToken,
+    ) -> MigrationResult<MigrationProgress> {
+        let _lock = LoginLock::acquire(&self.codex_home)?;
         let mut progress = MigrationProgress {
             phase: MigrationPhase::Backup,
             started_at: Utc::now(),
@@ -191,21 +303,32 @@ impl MigrationCoordinator {
             backup_handle: None,
             rollback_available: false,
             metadata: HashMap::new(),
+            journal: Vec::new(),
         };
 
         // Store initial progress
         self.store_progress(&progress).await?;
 
         // Execute each phase with automatic rollback on failure
-        if let Err(e) = self.execute_phases(&mut progress).await {
-            if self.config.auto_rollback_on_failure && progress.rollback_available {
+        if let Err(e) = self.execute_phases(&mut progress, &events, &cancel).await {
+            let cancelled = matches!(e, MigrationError::Cancelled);
+            if cancelled && !progress.rollback_available {
+                // Cancelled before the backup, so nothing has changed yet
+                progress.phase = MigrationPhase::RolledBack;
+                self.store_progress(&progress).await?;
+                return Err(e);
+            }
+            if (cancelled || self.config.auto_rollback_on_failure) && progress.rollback_available {
+                emit(&events, MigrationEventKind::RollbackStarted, &progress, format!("Rolling back after: {}", e)).await;
                 match self.execute_rollback(&mut progress).await {
                     Ok(_) => {
                         progress.phase = MigrationPhase::RolledBack;
                         self.store_progress(&progress).await?;
+                        emit(&events, MigrationEventKind::RollbackCompleted, &progress, "Restored the pre-migration state").await;
                         return Err(e);
                     }
                     Err(rollback_err) => {
+                        emit(&events, MigrationEventKind::RollbackFailed, &progress, rollback_err.to_string()).await;
                         return Err(MigrationError::RollbackFailed(format!(
                             "Original error: {}. Rollback error: {}", e, rollback_err
                         )));
@@ -215,30 +338,51 @@ impl MigrationCoordinator {
             return Err(e);
         }
 
+        if cancel.is_cancelled() {
+            tracing::info!("Cancellation requested during the Cleanup phase; the migration was completed instead");
+        }
         progress.phase = MigrationPhase::Completed;
         self.store_progress(&progress).await?;
+        emit(&events, MigrationEventKind::Completed, &progress, "Migration completed").await;
         Ok(progress)
     }
 
     /// Execute all migration phases sequentially
-    async fn execute_phases(&mut self, progress: &mut MigrationProgress) -> MigrationResult<()> {
+    async fn execute_phases(
+        &mut self,
+        progress: &mut MigrationProgress,
+        events: &mpsc::Sender<MigrationEvent>,
+        cancel: &CancellationToken,
+    ) -> MigrationResult<()> {
         while !progress.phase.is_terminal() {
-            if self.config.verbose_logging {
-                println!("Executing phase: {:?}", progress.phase);
+            if cancel.is_cancelled() {
+                tracing::info!(phase = ?progress.phase, "Migration cancelled");
+                return Err(MigrationError::Cancelled);
             }
+            emit(events, MigrationEventKind::PhaseStarted, progress, format!("Starting {:?} phase", progress.phase)).await;
+            let span = tracing::info_span!(
+                "migration_phase",
+                phase = ?progress.phase,
+                latency_ms = tracing::field::Empty,
+            );
+            let started = std::time::Instant::now();
+
+            let result = async {
+                verbose_event!(self.config.verbose_logging, "Executing migration phase");
+                self.execute_journaled_phase(progress).await
+            }
+            .instrument(span.clone())
+            .await;
 
-            let result = match progress.phase {
-                MigrationPhase::Backup => self.execute_backup_phase(progress).await,
-                MigrationPhase::Validation => self.execute_validation_phase(progress).await,
-                MigrationPhase::Extension => self.execute_extension_phase(progress).await,
-                MigrationPhase::Testing => self.execute_testing_phase(progress).await,
-                MigrationPhase::Cleanup => self.execute_cleanup_phase(progress).await,
-                _ => unreachable!("Terminal phases should not be executed"),
-            };
+            span.record("latency_ms", started.elapsed().as_millis() as u64);
+            if let Err(e) = &result {
+                tracing::warn!(parent: &span, error = %e, "Migration phase failed");
+            }
 
             match result {
                 Ok(_) => {
                     progress.completed_phases.push(progress.phase.clone());
+                    emit(events, MigrationEventKind::PhaseCompleted, progress, format!("{:?} phase completed", progress.phase)).await;
                     if let Some(next_phase) = progress.phase.next() {
                         progress.phase = next_phase;
                     }
@@ -246,6 +390,7 @@ impl MigrationCoordinator {
                 }
                 Err(e) => {
                     progress.failed_phases.push((progress.phase.clone(), e.to_string()));
+                    emit(events, MigrationEventKind::PhaseFailed, progress, format!("{:?} phase failed: {}", progress.phase, e)).await;
                     self.store_progress(progress).await?;
                     return Err(e);
                 }
@@ -255,9 +400,44 @@ impl MigrationCoordinator {
         Ok(())
     }
 
+    /// Execute the current phase, journaling the files it creates, modifies or removes
+    ///
+    /// Changes are recorded even when the phase fails part way, since rollback has to
+    /// undo those too.
+    async fn execute_journaled_phase(&mut self, progress: &mut MigrationProgress) -> MigrationResult<()> {
+        let before = self.backup_manager.snapshot_files().await?;
+        let result = match progress.phase {
+            MigrationPhase::Backup => self.execute_backup_phase(progress).await,
+            MigrationPhase::Validation => self.execute_validation_phase(progress).await,
+            MigrationPhase::Extension => self.execute_extension_phase(progress).await,
+            MigrationPhase::Testing => self.execute_testing_phase(progress).await,
+            MigrationPhase::Cleanup => self.execute_cleanup_phase(progress).await,
+            _ => unreachable!("Terminal phases should not be executed"),
+        };
+
+        match self.backup_manager.snapshot_files().await {
+            Ok(after) => {
+                let changes = journal_changes(&progress.phase, &before, &after);
+                progress.journal.extend(changes);
+            }
+            Err(e) => {
+                tracing::warn!(error = %e, "Could not journal phase changes; rollback will restore the full backup");
+                progress.metadata.insert(JOURNAL_INCOMPLETE.to_string(), "true".to_string());
+            }
+        }
+        result
+    }
+
     /// Execute backup phase
     async fn execute_backup_phase(&mut self, progress: &mut MigrationProgress) -> MigrationResult<()> {
-        let backup_handle = self.backup_manager.create_backup().await?;
+        let backup_handle = if self.config.incremental_backups {
+            let base = self.backup_manager.list_backups().await?
+                .into_iter()
+                .find(|handle| handle.kind == BackupKind::Incremental);
+            self.backup_manager.create_incremental_backup(base).await?
+        } else {
+            self.backup_manager.create_backup().await?
+        };
         progress.backup_handle = Some(backup_handle.id.clone());
         progress.rollback_available = true;
         progress.metadata.insert("backup_created_at".to_string(), Utc::now().to_rfc3339());
@@ -281,10 +461,14 @@ impl MigrationCoordinator {
 
     /// Execute extension phase
     async fn execute_extension_phase(&mut self, progress: &mut MigrationProgress) -> MigrationResult<()> {
-        let backup_handle = progress.backup_handle.as_ref()
+        let backup_id = progress.backup_handle.as_deref()
             .ok_or_else(|| MigrationError::InvalidState("No backup handle available".to_string()))?;
+        let backup_handle = self.backup_manager.list_backups().await?
+            .into_iter()
+            .find(|handle| handle.id == backup_id)
+            .ok_or_else(|| MigrationError::InvalidState(format!("Backup not found: {}", backup_id)))?;
 
-        let migration_result = self.migrator.migrate_to_unified_format(backup_handle).await?;
+        let migration_result = self.migrator.migrate_to_unified_format(&backup_handle).await?;
         
         progress.metadata.insert("migration_completed_at".to_string(), Utc::now().to_rfc3339());
         progress.metadata.insert("migrated_providers".to_string(), 
@@ -322,14 +506,52 @@ impl MigrationCoordinator {
     }
 
     /// Execute rollback to previous state
+    ///
+    /// Undoes the journaled changes of the phases that ran, newest first, so files the
+    /// migration never touched are left alone. Falls back to a full restore from the
+    /// backup when some change can't be undone on its own.
     async fn execute_rollback(&mut self, progress: &mut MigrationProgress) -> MigrationResult<()> {
-        if let Some(backup_handle) = &progress.backup_handle {
-            self.rollback_manager.rollback_migration(backup_handle).await?;
-            progress.metadata.insert("rolled_back_at".to_string(), Utc::now().to_rfc3339());
-            Ok(())
-        } else {
-            Err(MigrationError::RollbackFailed("No backup handle available".to_string()))
+        let backup_id = progress.backup_handle.clone()
+            .ok_or_else(|| MigrationError::RollbackFailed("No backup handle available".to_string()))?;
+
+        let mode = match self.undo_journal(progress, &backup_id).await {
+            Ok(()) => "phase",
+            Err(e) => {
+                tracing::warn!(error = %e, "Phase-level undo not possible; restoring the full backup");
+                self.rollback_manager.rollback_migration(&backup_id).await?;
+                "full"
+            }
+        };
+
+        progress.metadata.insert("rollback_mode".to_string(), mode.to_string());
+        progress.metadata.insert("rolled_back_at".to_string(), Utc::now().to_rfc3339());
+        Ok(())
+    }
+
+    /// Revert every journaled file to its state before the migration touched it
+    ///
+    /// The whole plan is checked against the backup before any file is changed.
+    async fn undo_journal(&self, progress: &MigrationProgress, backup_id: &str) -> MigrationResult<()> {
+        if progress.metadata.contains_key(JOURNAL_INCOMPLETE) {
+            return Err(MigrationError::InvalidState("Migration journal is incomplete".to_string()));
+        }
+
+        let handle = self.backup_manager.list_backups().await?
+            .into_iter()
+            .find(|handle| handle.id == backup_id)
+            .ok_or_else(|| MigrationError::RollbackFailed(format!("Backup not found: {}", backup_id)))?;
+        let restorable = self.backup_manager.restorable_files(&handle).await?;
+
+        for step in plan_undo(&progress.journal, &restorable)? {
+            match step {
+                UndoStep::Remove(path) => match tokio::fs::remove_file(self.codex_home.join(&path)).await {
+                    Err(e) if e.kind() != std::io::ErrorKind::NotFound => return Err(e.into()),
+                    _ => {}
+                },
+                UndoStep::Restore(path) => self.backup_manager.restore_file(&handle, &path).await?,
+            }
         }
+        Ok(())
     }
 
     /// Store migration progress to memory
@@ -339,7 +561,7 @@ impl MigrationCoordinator {
         
         // In a real implementation, this would use the memory management system
         // For now, we'll simulate it with a local file
-        let progress_file = self.codex_home.join(".migration_progress.json");
+        let progress_file = self.codex_home.join(PROGRESS_FILE);
         tokio::fs::write(progress_file, progress_json).await?;
         
         Ok(())
@@ -347,7 +569,7 @@ impl MigrationCoordinator {
 
     /// Get current migration progress
     pub async fn get_progress(&self) -> MigrationResult<Option<MigrationProgress>> {
-        let progress_file = self.codex_home.join(".migration_progress.json");
+        let progress_file = self.codex_home.join(PROGRESS_FILE);
         
         if !progress_file.exists() {
             return Ok(None);
@@ -421,6 +643,69 @@ impl MigrationCoordinator {
     }
 }
 
+/// Journal entries for the files that differ between two snapshots
+fn journal_changes(
+    phase: &MigrationPhase,
+    before: &BTreeMap<String, String>,
+    after: &BTreeMap<String, String>,
+) -> Vec<JournalEntry> {
+    let mut changes = Vec::new();
+    for (path, hash) in after {
+        let change = match before.get(path) {
+            None => FileChange::Created,
+            Some(previous) if previous != hash => FileChange::Modified,
+            Some(_) => continue,
+        };
+        changes.push((path, change));
+    }
+    changes.extend(before.keys().filter(|path| !after.contains_key(*path)).map(|path| (path, FileChange::Removed)));
+
+    changes.into_iter()
+        .filter(|(path, _)| path.as_str() != PROGRESS_FILE)
+        .map(|(path, change)| JournalEntry { phase: phase.clone(), path: path.clone(), change })
+        .collect()
+}
+
+#[derive(Debug, PartialEq)]
+enum UndoStep {
+    Remove(String),
+    Restore(String),
+}
+
+/// Steps returning each journaled file to its state before the first phase that touched
+/// it, newest change first
+///
+/// Files the migration created are removed; anything else has to be in the backup.
+fn plan_undo(journal: &[JournalEntry], restorable: &[String]) -> MigrationResult<Vec<UndoStep>> {
+    let mut first_changes: Vec<&JournalEntry> = Vec::new();
+    for entry in journal {
+        if !first_changes.iter().any(|seen| seen.path == entry.path) {
+            first_changes.push(entry);
+        }
+    }
+
+    first_changes.into_iter().rev().map(|entry| match entry.change {
+        FileChange::Created => Ok(UndoStep::Remove(entry.path.clone())),
+        _ if restorable.contains(&entry.path) => Ok(UndoStep::Restore(entry.path.clone())),
+        _ => Err(MigrationError::RollbackFailed(format!(
+            "{} changed during the {:?} phase but is not in the backup", entry.path, entry.phase
+        ))),
+    }).collect()
+}
+
+/// Send a progress event; a closed channel only means nobody is watching
+async fn emit(
+    events: &mpsc::Sender<MigrationEvent>,
+    kind: MigrationEventKind,
+    progress: &MigrationProgress,
+    message: impl Into<String>,
+) {
+    let percent = (progress.completed_phases.len().min(WORKING_PHASE_COUNT) * 100 / WORKING_PHASE_COUNT) as u8;
+    let _ = events
+        .send(MigrationEvent { kind, phase: progress.phase.clone(), percent, message: message.into() })
+        .await;
+}
+
 /// Summary of migration status
 #[derive(Debug, Serialize, Deserialize)]
 pub struct MigrationStatusSummary {
@@ -455,6 +740,253 @@ mod tests {
         assert_eq!(coordinator.codex_home, temp_dir.path());
     }
 
+    /// Records the `phase` field of every `migration_phase` span
+    #[derive(Default)]
+    struct PhaseSpanRecorder {
+        phases: std::sync::Arc<std::sync::Mutex<Vec<String>>>,
+        next_id: std::sync::atomic::AtomicU64,
+    }
+
+    struct PhaseVisitor(Option<String>);
+
+    impl tracing::field::Visit for PhaseVisitor {
+        fn record_debug(&mut self, field: &tracing::field::Field, value: &dyn std::fmt::Debug) {
+            if field.name() == "phase" {
+                self.0 = Some(format!("{:?}", value));
+            }
+        }
+    }
+
+    impl tracing::Subscriber for PhaseSpanRecorder {
+        fn enabled(&self, _: &tracing::Metadata<'_>) -> bool {
+            true
+        }
+
+        fn new_span(&self, attrs: &tracing::span::Attributes<'_>) -> tracing::span::Id {
+            if attrs.metadata().name() == "migration_phase" {
+                let mut visitor = PhaseVisitor(None);
+                attrs.record(&mut visitor);
+                self.phases.lock().unwrap().extend(visitor.0);
+            }
+            let id = self.next_id.fetch_add(1, std::sync::atomic::Ordering::SeqCst) + 1;
+            tracing::span::Id::from_u64(id)
+        }
+
+        fn record(&self, _: &tracing::span::Id, _: &tracing::span::Record<'_>) {}
+        fn record_follows_from(&self, _: &tracing::span::Id, _: &tracing::span::Id) {}
+        fn event(&self, _: &tracing::Event<'_>) {}
+        fn enter(&self, _: &tracing::span::Id) {}
+        fn exit(&self, _: &tracing::span::Id) {}
+    }
+
+    #[tokio::test]
+    async fn test_migration_emits_span_per_phase() {
+        let temp_dir = tempdir().unwrap();
+        tokio::fs::write(temp_dir.path().join("auth.json"), r#"{"OPENAI_API_KEY": "sk-test"}"#).await.unwrap();
+
+        let recorder = PhaseSpanRecorder::default();
+        let phases = std::sync::Arc::clone(&recorder.phases);
+        let _guard = tracing::subscriber::set_default(recorder);
+
+        let mut coordinator = MigrationCoordinator::new(temp_dir.path().to_path_buf(), MigrationConfig::default());
+        let mut progress = MigrationProgress {
+            phase: MigrationPhase::Backup,
+            started_at: Utc::now(),
+            completed_phases: Vec::new(),
+            failed_phases: Vec::new(),
+            backup_handle: None,
+            rollback_available: false,
+            metadata: HashMap::new(),
+            journal: Vec::new(),
+        };
+        let (events, _) = mpsc::channel(1);
+        let _ = coordinator.execute_phases(&mut progress, &events, &CancellationToken::new()).await;
+
+        // Every phase that ran, whether it completed or failed, got exactly one span
+        let executed: Vec<String> = progress.completed_phases.iter()
+            .chain(progress.failed_phases.iter().map(|(phase, _)| phase))
+            .map(|phase| format!("{:?}", phase))
+            .collect();
+        assert!(!executed.is_empty());
+        assert_eq!(*phases.lock().unwrap(), executed);
+    }
+
+    async fn collect_events(
+        coordinator: &mut MigrationCoordinator,
+    ) -> (MigrationResult<MigrationProgress>, Vec<MigrationEvent>) {
+        let (tx, mut rx) = mpsc::channel(64);
+        let result = coordinator.execute_migration_with_progress(tx).await;
+        let mut events = Vec::new();
+        while let Some(event) = rx.recv().await {
+            events.push(event);
+        }
+        (result, events)
+    }
+
+    #[tokio::test]
+    async fn test_migration_progress_events_follow_phase_order() {
+        let temp_dir = tempdir().unwrap();
+        tokio::fs::write(temp_dir.path().join("auth.json"), r#"{"OPENAI_API_KEY": "sk-test"}"#).await.unwrap();
+
+        let config = MigrationConfig { validate_tokens_before_migration: false, ..MigrationConfig::default() };
+        let mut coordinator = MigrationCoordinator::new(temp_dir.path().to_path_buf(), config);
+        let (result, events) = collect_events(&mut coordinator).await;
+        result.unwrap();
+
+        let mut expected = Vec::new();
+        for phase in [
+            MigrationPhase::Backup,
+            MigrationPhase::Validation,
+            MigrationPhase::Extension,
+            MigrationPhase::Testing,
+            MigrationPhase::Cleanup,
+        ] {
+            expected.push((MigrationEventKind::PhaseStarted, phase.clone()));
+            expected.push((MigrationEventKind::PhaseCompleted, phase));
+        }
+        expected.push((MigrationEventKind::Completed, MigrationPhase::Completed));
+
+        let sequence: Vec<_> = events.iter().map(|e| (e.kind, e.phase.clone())).collect();
+        assert_eq!(sequence, expected);
+        assert!(events.windows(2).all(|pair| pair[0].percent <= pair[1].percent));
+        assert_eq!(events[1].percent, 20);
+        assert_eq!(events.last().unwrap().percent, 100);
+    }
+
+    #[tokio::test]
+    async fn test_failed_phase_is_reported() {
+        // Without auth.json there is nothing to back up
+        let temp_dir = tempdir().unwrap();
+        let mut coordinator = MigrationCoordinator::new(temp_dir.path().to_path_buf(), MigrationConfig::default());
+        let (result, events) = collect_events(&mut coordinator).await;
+
+        assert!(result.is_err());
+        let sequence: Vec<_> = events.iter().map(|e| (e.kind, e.phase.clone())).collect();
+        assert_eq!(
+            sequence,
+            vec![
+                (MigrationEventKind::PhaseStarted, MigrationPhase::Backup),
+                (MigrationEventKind::PhaseFailed, MigrationPhase::Backup),
+            ]
+        );
+        assert!(events[1].message.contains("auth.json"));
+    }
+
+    #[tokio::test]
+    async fn test_cancel_after_backup_rolls_back_to_pre_migration_state() {
+        let temp_dir = tempdir().unwrap();
+        let original_auth = r#"{"OPENAI_API_KEY": "sk-test"}"#;
+        tokio::fs::write(temp_dir.path().join("auth.json"), original_auth).await.unwrap();
+
+        let config = MigrationConfig { validate_tokens_before_migration: false, ..MigrationConfig::default() };
+        let mut coordinator = MigrationCoordinator::new(temp_dir.path().to_path_buf(), config);
+        let cancel = CancellationToken::new();
+        let (tx, mut rx) = mpsc::channel::<MigrationEvent>(1);
+
+        // With room for one event, reporting the backup's completion waits for the listener
+        // to take its start, so the cancellation is in place before the next phase check
+        let listener = async {
+            let mut events = Vec::new();
+            while let Some(event) = rx.recv().await {
+                if event.kind == MigrationEventKind::PhaseStarted && event.phase == MigrationPhase::Backup {
+                    cancel.cancel();
+                }
+                events.push(event);
+            }
+            events
+        };
+        let (result, events) = tokio::join!(
+            coordinator.execute_migration_with_cancellation(tx, cancel.clone()),
+            listener
+        );
+
+        assert!(matches!(result, Err(MigrationError::Cancelled)));
+        let sequence: Vec<_> = events.iter().map(|e| e.kind).collect();
+        assert_eq!(
+            sequence,
+            vec![
+                MigrationEventKind::PhaseStarted,
+                MigrationEventKind::PhaseCompleted,
+                MigrationEventKind::RollbackStarted,
+                MigrationEventKind::RollbackCompleted,
+            ]
+        );
+
+        let stored = coordinator.get_progress().await.unwrap().unwrap();
+        assert_eq!(stored.phase, MigrationPhase::RolledBack);
+        assert_eq!(stored.completed_phases, vec![MigrationPhase::Backup]);
+        for artifact in ["unified_auth.json", "claude_auth.json", "auth.json.pre_migration"] {
+            assert!(!temp_dir.path().join(artifact).exists(), "{} should not exist", artifact);
+        }
+        assert_eq!(tokio::fs::read_to_string(temp_dir.path().join("auth.json")).await.unwrap(), original_auth);
+    }
+
+    #[tokio::test]
+    async fn test_testing_failure_reverts_only_extension_artifacts() {
+        let temp_dir = tempdir().unwrap();
+        let original_auth = r#"{"OPENAI_API_KEY": "sk-test"}"#;
+        tokio::fs::write(temp_dir.path().join("auth.json"), original_auth).await.unwrap();
+
+        let config = MigrationConfig { validate_tokens_before_migration: false, ..MigrationConfig::default() };
+        let mut coordinator = MigrationCoordinator::new(temp_dir.path().to_path_buf(), config);
+        let mut progress = MigrationProgress {
+            phase: MigrationPhase::Backup,
+            started_at: Utc::now(),
+            completed_phases: Vec::new(),
+            failed_phases: Vec::new(),
+            backup_handle: None,
+            rollback_available: false,
+            metadata: HashMap::new(),
+            journal: Vec::new(),
+        };
+        while progress.phase != MigrationPhase::Testing {
+            coordinator.execute_journaled_phase(&mut progress).await.unwrap();
+            progress.completed_phases.push(progress.phase.clone());
+            progress.phase = progress.phase.next().unwrap();
+        }
+        assert!(progress.journal.iter().any(|entry| {
+            entry.phase == MigrationPhase::Extension && entry.path == "unified_auth.json" && entry.change == FileChange::Created
+        }));
+
+        // Edited by the user after the backup; not the migration's to revert
+        tokio::fs::write(temp_dir.path().join("config.toml"), "model = \"o3\"\n").await.unwrap();
+        progress.failed_phases.push((MigrationPhase::Testing, "simulated test failure".to_string()));
+        coordinator.execute_rollback(&mut progress).await.unwrap();
+
+        assert_eq!(progress.metadata["rollback_mode"], "phase");
+        for artifact in ["unified_auth.json", "claude_auth.json", "auth.json.pre_migration"] {
+            assert!(!temp_dir.path().join(artifact).exists(), "{} should be removed", artifact);
+        }
+        assert_eq!(tokio::fs::read_to_string(temp_dir.path().join("auth.json")).await.unwrap(), original_auth);
+        assert_eq!(tokio::fs::read_to_string(temp_dir.path().join("config.toml")).await.unwrap(), "model = \"o3\"\n");
+
+        let backup_id = progress.backup_handle.clone().unwrap();
+        let handle = coordinator.backup_manager.list_backups().await.unwrap()
+            .into_iter()
+            .find(|handle| handle.id == backup_id)
+            .unwrap();
+        assert!(coordinator.backup_manager.verify_backup(&handle).await.unwrap().is_valid);
+    }
+
+    #[test]
+    fn test_undo_plan_reverts_first_change_and_rejects_files_outside_backup() {
+        let entry = |phase: MigrationPhase, path: &str, change| JournalEntry { phase, path: path.to_string(), change };
+        let mut journal = vec![
+            entry(MigrationPhase::Extension, "unified_auth.json", FileChange::Created),
+            entry(MigrationPhase::Extension, "auth.json", FileChange::Modified),
+            entry(MigrationPhase::Testing, "unified_auth.json", FileChange::Modified),
+        ];
+        let restorable = vec!["auth.json".to_string()];
+
+        assert_eq!(
+            plan_undo(&journal, &restorable).unwrap(),
+            vec![UndoStep::Restore("auth.json".to_string()), UndoStep::Remove("unified_auth.json".to_string())]
+        );
+
+        journal.push(entry(MigrationPhase::Testing, "config.toml", FileChange::Modified));
+        assert!(matches!(plan_undo(&journal, &restorable), Err(MigrationError::RollbackFailed(_))));
+    }
+
     #[tokio::test]
     async fn test_migration_needed_detection() {
         let temp_dir = tempdir().unwrap();
@@ -98,9 +98,7 @@ impl RollbackManager {
             metadata: HashMap::new(),
         };
 
-        if self.config.verbose_logging {
-            println!("Starting rollback for backup: {}", backup_id);
-        }
+        tracing::warn!(backup_id = %backup_id, "Starting rollback");
 
         // Find backup handle
         let backup_handle = self.find_backup_handle(backup_id).await?;
@@ -132,9 +130,11 @@ impl RollbackManager {
         result.metadata.insert("backup_id".to_string(), backup_id.to_string());
         result.metadata.insert("rollback_version".to_string(), env!("CARGO_PKG_VERSION").to_string());
 
-        if self.config.verbose_logging {
-            println!("Rollback completed in {:?}", result.rollback_duration);
-        }
+        verbose_event!(
+            self.config.verbose_logging,
+            latency_ms = result.rollback_duration.num_milliseconds().max(0) as u64,
+            "Rollback completed"
+        );
 
         Ok(result)
     }
@@ -274,17 +274,14 @@ impl RollbackManager {
     /// Execute rollback plan step by step
     async fn execute_rollback_plan(&self, plan: &RollbackPlan, result: &mut RollbackResult) -> MigrationResult<()> {
         for step in &plan.steps {
-            if self.config.verbose_logging {
-                println!("Executing rollback step {}: {}", step.order, step.description);
-            }
+            verbose_event!(self.config.verbose_logging, step = step.order, description = %step.description, "Executing rollback step");
 
             match self.execute_rollback_step(step, result).await {
                 Ok(_) => {
-                    if self.config.verbose_logging {
-                        println!("Step {} completed successfully", step.order);
-                    }
+                    tracing::debug!(step = step.order, "Rollback step completed");
                 }
                 Err(e) => {
+                    tracing::warn!(step = step.order, critical = step.critical, error = %e, "Rollback step failed");
                     if step.critical {
                         return Err(MigrationError::RollbackFailed(
                             format!("Critical rollback step {} failed: {}", step.order, e)
@@ -492,9 +489,7 @@ impl RollbackManager {
 
     /// Emergency rollback - fastest possible restore
     pub async fn emergency_rollback(&self) -> MigrationResult<RollbackResult> {
-        if self.config.verbose_logging {
-            println!("Executing emergency rollback...");
-        }
+        tracing::warn!("Executing emergency rollback");
 
         // Find the most recent valid backup
         let candidates = self.list_rollback_candidates().await?;
@@ -537,9 +532,11 @@ impl RollbackManager {
         result.metadata.insert("emergency_rollback".to_string(), "true".to_string());
         result.metadata.insert("backup_id".to_string(), latest_backup.id.clone());
 
-        if self.config.verbose_logging {
-            println!("Emergency rollback completed in {:?}", result.rollback_duration);
-        }
+        verbose_event!(
+            self.config.verbose_logging,
+            latency_ms = result.rollback_duration.num_milliseconds().max(0) as u64,
+            "Emergency rollback completed"
+        );
 
         Ok(result)
     }
@@ -571,6 +568,7 @@ mod tests {
             },
             encrypted: false,
             checksum: "test-checksum".to_string(),
+            kind: super::super::backup_manager::BackupKind::Full,
         };
 
         // Create the backup file for validation
@@ -603,6 +601,7 @@ mod tests {
             },
             encrypted: false,
             checksum: "test-checksum".to_string(),
+            kind: super::super::backup_manager::BackupKind::Full,
         };
 
         // Create migration artifacts
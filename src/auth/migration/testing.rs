@@ -3,7 +3,7 @@
 /// Provides comprehensive testing of migration functionality to ensure data integrity,
 /// backward compatibility, and proper system behavior during and after migration.
 
-use super::{MigrationConfig, MigrationError, MigrationResult};
+use super::{MigrationConfig, MigrationResult};
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
@@ -60,7 +60,7 @@ pub struct TestResult {
 }
 
 /// Test categories for organization
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum TestCategory {
     DataIntegrity,
     BackwardCompatibility,
@@ -158,9 +158,7 @@ impl MigrationTester {
         let mut test_results = Vec::new();
         let mut category_results = HashMap::new();
 
-        if self.config.verbose_logging {
-            println!("Starting comprehensive migration test suite...");
-        }
+        verbose_event!(self.config.verbose_logging, "Starting migration test suite");
 
         // Initialize category tracking
         for category in self.get_test_categories() {
@@ -244,10 +242,13 @@ impl MigrationTester {
             environment_info: self.gather_environment_info().await?,
         };
 
-        if self.config.verbose_logging {
-            println!("Test suite completed: {}/{} tests passed ({:.1}% success rate)", 
-                passed_tests, total_tests, success_rate);
-        }
+        verbose_event!(
+            self.config.verbose_logging,
+            passed = passed_tests,
+            total = total_tests,
+            success_rate,
+            "Migration test suite completed"
+        );
 
         Ok(result)
     }
@@ -501,10 +502,10 @@ impl MigrationTester {
 
         // Check that original data is preserved in unified format
         let unified_content = tokio::fs::read_to_string(&unified_file).await?;
-        let unified_auth: super::migrator::UnifiedAuthJson = serde_json::from_str(&unified_content)?;
+        let unified_auth: crate::auth::migration::migrator::UnifiedAuthJson = serde_json::from_str(&unified_content)?;
         
         // Verify OpenAI provider has data
-        if let Some(super::migrator::ProviderAuth::OpenAI { api_key, oauth_tokens, .. }) = unified_auth.providers.get("openai") {
+        if let Some(crate::auth::migration::migrator::ProviderAuth::OpenAI { api_key, oauth_tokens, .. }) = unified_auth.providers.get("openai") {
             Ok(api_key.is_some() || oauth_tokens.is_some())
         } else {
             Ok(false)
@@ -530,7 +531,7 @@ impl MigrationTester {
         }
 
         let content = tokio::fs::read_to_string(&unified_file).await?;
-        let unified_auth: super::migrator::UnifiedAuthJson = serde_json::from_str(&content)?;
+        let unified_auth: crate::auth::migration::migrator::UnifiedAuthJson = serde_json::from_str(&content)?;
         
         // Check version and required fields
         Ok(unified_auth.version == "2.0" && 
@@ -582,9 +583,9 @@ impl MigrationTester {
         }
 
         let content = tokio::fs::read_to_string(&unified_file).await?;
-        let unified_auth: super::migrator::UnifiedAuthJson = serde_json::from_str(&content)?;
+        let unified_auth: crate::auth::migration::migrator::UnifiedAuthJson = serde_json::from_str(&content)?;
         
-        if let Some(super::migrator::ProviderAuth::OpenAI { enabled, .. }) = unified_auth.providers.get("openai") {
+        if let Some(crate::auth::migration::migrator::ProviderAuth::OpenAI { enabled, .. }) = unified_auth.providers.get("openai") {
             Ok(*enabled)
         } else {
             Ok(false)
@@ -603,7 +604,7 @@ impl MigrationTester {
         }
 
         let content = tokio::fs::read_to_string(&unified_file).await?;
-        let unified_auth: super::migrator::UnifiedAuthJson = serde_json::from_str(&content)?;
+        let unified_auth: crate::auth::migration::migrator::UnifiedAuthJson = serde_json::from_str(&content)?;
         
         Ok(unified_auth.providers.len() >= 2) // OpenAI and Claude
     }
@@ -838,11 +839,11 @@ mod tests {
         tokio::fs::write(&auth_file, r#"{"OPENAI_API_KEY": "test"}"#).await.unwrap();
 
         let unified_file = temp_dir.path().join("unified_auth.json");
-        let unified_auth = super::migrator::UnifiedAuthJson {
+        let unified_auth = crate::auth::migration::migrator::UnifiedAuthJson {
             version: "2.0".to_string(),
             created_at: Utc::now(),
             last_updated: Utc::now(),
-            migration_info: super::migrator::MigrationInfo {
+            migration_info: crate::auth::migration::migrator::MigrationInfo {
                 migrated_from_version: "1.0".to_string(),
                 migration_date: Utc::now(),
                 backup_id: "test-backup".to_string(),
@@ -851,7 +852,7 @@ mod tests {
             },
             providers: {
                 let mut providers = HashMap::new();
-                providers.insert("openai".to_string(), super::migrator::ProviderAuth::OpenAI {
+                providers.insert("openai".to_string(), crate::auth::migration::migrator::ProviderAuth::OpenAI {
                     api_key: Some("test".to_string()),
                     oauth_tokens: None,
                     last_refresh: None,
@@ -861,7 +862,8 @@ mod tests {
                 });
                 providers
             },
-            preferences: super::migrator::AuthPreferences::default(),
+            preferences: crate::auth::migration::migrator::AuthPreferences::default(),
+            maintain_legacy_auth_json: false,
         };
         let unified_content = serde_json::to_string_pretty(&unified_auth).unwrap();
         tokio::fs::write(&unified_file, unified_content).await.unwrap();
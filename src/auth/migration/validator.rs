@@ -3,10 +3,12 @@
 /// Provides comprehensive validation of authentication systems before, during, and after migration.
 /// Ensures data integrity and functional correctness throughout the migration process.
 
-use super::{MigrationConfig, MigrationError, MigrationResult};
+use super::{MigrationConfig, MigrationResult};
+use crate::auth::claude::ClaudeAuth;
+use crate::claude_auth::secure_claude_auth::{status_is_retryable, ClaudeAuthConfig};
+use crate::security::resolve_secret;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
 use std::path::{Path, PathBuf};
 
 /// Comprehensive validation result
@@ -93,14 +95,51 @@ pub struct TokenValidationResult {
     pub expires_at: Option<DateTime<Utc>>,
     pub needs_refresh: bool,
     pub error: Option<String>,
+    /// HTTP status the provider answered the probe with; `None` if it was never reached
+    #[serde(default)]
+    pub status_code: Option<u16>,
 }
 
+impl TokenValidationResult {
+    fn new(provider: &str, token_type: &str) -> Self {
+        Self {
+            provider: provider.to_string(),
+            token_type: token_type.to_string(),
+            valid: false,
+            expires_at: None,
+            needs_refresh: false,
+            error: None,
+            status_code: None,
+        }
+    }
+
+    /// Whether the credential could not be checked, as opposed to being refused
+    pub fn is_unverified(&self) -> bool {
+        !self.valid && !self.needs_refresh && self.status_code.is_none()
+    }
+
+    /// Whether the provider answered with a throttling or server error that may clear up
+    pub fn is_retryable(&self) -> bool {
+        self.status_code
+            .and_then(|status| reqwest::StatusCode::from_u16(status).ok())
+            .is_some_and(status_is_retryable)
+    }
+}
+
+/// Lightweight authenticated OpenAI endpoint used to probe stored credentials
+pub const DEFAULT_OPENAI_PROBE_ENDPOINT: &str = "https://api.openai.com/v1/models";
+
+/// Lightweight authenticated Anthropic endpoint used to probe stored credentials
+pub const DEFAULT_CLAUDE_PROBE_ENDPOINT: &str = "https://api.anthropic.com/v1/models";
+
 /// Migration validator implementation
 #[derive(Debug)]
 pub struct MigrationValidator {
     codex_home: PathBuf,
     config: MigrationConfig,
     client: reqwest::Client,
+    openai_probe_endpoint: String,
+    claude_probe_endpoint: String,
 }
 
 impl MigrationValidator {
@@ -115,19 +154,31 @@ impl MigrationValidator {
             codex_home: codex_home.to_path_buf(),
             config: config.clone(),
             client,
+            openai_probe_endpoint: DEFAULT_OPENAI_PROBE_ENDPOINT.to_string(),
+            claude_probe_endpoint: DEFAULT_CLAUDE_PROBE_ENDPOINT.to_string(),
         }
     }
 
+    /// Probe OpenAI credentials against `endpoint` instead of the public API
+    pub fn with_openai_probe_endpoint(mut self, endpoint: impl Into<String>) -> Self {
+        self.openai_probe_endpoint = endpoint.into();
+        self
+    }
+
+    /// Probe Claude credentials against `endpoint` instead of the public API
+    pub fn with_claude_probe_endpoint(mut self, endpoint: impl Into<String>) -> Self {
+        self.claude_probe_endpoint = endpoint.into();
+        self
+    }
+
     /// Validate existing authentication before migration
     pub async fn validate_existing_auth(&self) -> MigrationResult<ValidationResult> {
         let start_time = std::time::Instant::now();
         let mut checks = Vec::new();
         let mut errors = Vec::new();
-        let mut warnings = Vec::new();
+        let warnings = Vec::new();
 
-        if self.config.verbose_logging {
-            println!("Starting pre-migration validation...");
-        }
+        verbose_event!(self.config.verbose_logging, stage = "pre_migration", "Starting validation");
 
         // File system checks
         checks.extend(self.validate_file_system().await?);
@@ -166,9 +217,6 @@ impl MigrationValidator {
         let result = ValidationResult {
             is_valid: errors.iter().all(|e| !matches!(e.severity, ErrorSeverity::Critical)),
             validation_timestamp: Utc::now(),
-            checks_performed: checks.clone(),
-            errors,
-            warnings,
             performance_metrics: ValidationMetrics {
                 total_duration_ms: total_duration,
                 checks_count: checks.len(),
@@ -179,15 +227,19 @@ impl MigrationValidator {
                     checks.iter().map(|c| c.duration_ms as f64).sum::<f64>() / checks.len() as f64 
                 },
             },
+            checks_performed: checks.clone(),
+            errors,
+            warnings,
         };
 
-        if self.config.verbose_logging {
-            println!("Pre-migration validation completed: {} checks, {} passed, {} failed", 
-                result.performance_metrics.checks_count,
-                result.performance_metrics.passed_count,
-                result.performance_metrics.failed_count
-            );
-        }
+        verbose_event!(
+            self.config.verbose_logging,
+            stage = "pre_migration",
+            checks = result.performance_metrics.checks_count,
+            passed = result.performance_metrics.passed_count,
+            failed = result.performance_metrics.failed_count,
+            "Validation completed"
+        );
 
         Ok(result)
     }
@@ -197,11 +249,9 @@ impl MigrationValidator {
         let start_time = std::time::Instant::now();
         let mut checks = Vec::new();
         let mut errors = Vec::new();
-        let mut warnings = Vec::new();
+        let warnings = Vec::new();
 
-        if self.config.verbose_logging {
-            println!("Starting post-migration validation...");
-        }
+        verbose_event!(self.config.verbose_logging, stage = "post_migration", "Starting validation");
 
         // Unified auth file validation
         checks.extend(self.validate_unified_auth_file().await?);
@@ -238,9 +288,6 @@ impl MigrationValidator {
         let result = ValidationResult {
             is_valid: errors.iter().all(|e| !matches!(e.severity, ErrorSeverity::Critical)),
             validation_timestamp: Utc::now(),
-            checks_performed: checks,
-            errors,
-            warnings,
             performance_metrics: ValidationMetrics {
                 total_duration_ms: total_duration,
                 checks_count: checks.len(),
@@ -251,15 +298,19 @@ impl MigrationValidator {
                     checks.iter().map(|c| c.duration_ms as f64).sum::<f64>() / checks.len() as f64 
                 },
             },
+            checks_performed: checks,
+            errors,
+            warnings,
         };
 
-        if self.config.verbose_logging {
-            println!("Post-migration validation completed: {} checks, {} passed, {} failed", 
-                result.performance_metrics.checks_count,
-                result.performance_metrics.passed_count,
-                result.performance_metrics.failed_count
-            );
-        }
+        verbose_event!(
+            self.config.verbose_logging,
+            stage = "post_migration",
+            checks = result.performance_metrics.checks_count,
+            passed = result.performance_metrics.passed_count,
+            failed = result.performance_metrics.failed_count,
+            "Validation completed"
+        );
 
         Ok(result)
     }
@@ -269,30 +320,30 @@ impl MigrationValidator {
         let mut checks = Vec::new();
 
         // Check if codex home directory exists
-        checks.push(self.time_check("codex_home_exists", || {
+        checks.push(self.time_check("codex_home_exists", || async {
             self.codex_home.exists()
         }, ValidationCategory::FileSystem, Some("Codex home directory must exist".to_string())).await);
 
         // Check if codex home is writable
-        checks.push(self.time_check("codex_home_writable", || {
+        checks.push(self.time_check("codex_home_writable", || async {
             self.is_directory_writable(&self.codex_home)
         }, ValidationCategory::FileSystem, Some("Codex home must be writable".to_string())).await);
 
         // Check auth.json exists
         let auth_file = self.codex_home.join("auth.json");
-        checks.push(self.time_check("auth_file_exists", || {
+        checks.push(self.time_check("auth_file_exists", || async {
             auth_file.exists()
         }, ValidationCategory::FileSystem, Some("auth.json file must exist for migration".to_string())).await);
 
         // Check auth.json permissions
         if auth_file.exists() {
-            checks.push(self.time_check("auth_file_permissions", || {
+            checks.push(self.time_check("auth_file_permissions", || async {
                 self.check_secure_permissions(&auth_file)
             }, ValidationCategory::Security, Some("auth.json must have secure permissions (0o600)".to_string())).await);
         }
 
         // Check available disk space
-        checks.push(self.time_check("sufficient_disk_space", || {
+        checks.push(self.time_check("sufficient_disk_space", || async {
             self.check_disk_space()
         }, ValidationCategory::FileSystem, Some("Sufficient disk space required for migration".to_string())).await);
 
@@ -335,23 +386,157 @@ impl MigrationValidator {
         Ok(checks)
     }
 
-    /// Validate existing tokens
+    /// Validate existing tokens by probing each stored credential
     async fn validate_existing_tokens(&self) -> MigrationResult<Vec<ValidationCheck>> {
         let mut checks = Vec::new();
-        let auth_file = self.codex_home.join("auth.json");
 
-        if !auth_file.exists() {
-            return Ok(checks);
+        let start = std::time::Instant::now();
+        if let Some(result) = self.probe_openai_credential().await {
+            checks.push(Self::token_check("openai", "OpenAI", &result, start.elapsed()));
         }
 
-        // Test token validity
-        checks.push(self.time_check("openai_token_valid", || async {
-            self.test_openai_token_validity().await
-        }, ValidationCategory::TokenValidity, Some("OpenAI tokens must be valid".to_string())).await);
+        let start = std::time::Instant::now();
+        if let Some(result) = self.probe_claude_credential().await {
+            checks.push(Self::token_check("claude", "Claude", &result, start.elapsed()));
+        }
 
         Ok(checks)
     }
 
+    /// Probe every stored credential: the OpenAI one in auth.json and the Claude one, if any
+    pub async fn probe_stored_credentials(&self) -> Vec<TokenValidationResult> {
+        let mut results = Vec::new();
+        results.extend(self.probe_openai_credential().await);
+        results.extend(self.probe_claude_credential().await);
+        results
+    }
+
+    /// Probe the stored credential of one provider, `openai` or `claude`, if there is one
+    pub async fn probe_stored_credential(&self, provider: &str) -> Option<TokenValidationResult> {
+        match provider {
+            "openai" => self.probe_openai_credential().await,
+            "claude" => self.probe_claude_credential().await,
+            _ => None,
+        }
+    }
+
+    /// Check for one probed credential, named so its severity and suggestion can be looked up
+    fn token_check(key: &str, provider: &str, result: &TokenValidationResult, elapsed: std::time::Duration) -> ValidationCheck {
+        let credential = result.token_type.replace('_', " ");
+        let (name, error_message) = if result.valid {
+            (format!("{}_token_valid", key), None)
+        } else if result.is_unverified() {
+            (
+                format!("{}_token_unverified", key),
+                Some(format!(
+                    "Could not check the {} {}: {}",
+                    provider,
+                    credential,
+                    result.error.as_deref().unwrap_or("no response")
+                )),
+            )
+        } else {
+            let reason = match (result.status_code, result.expires_at) {
+                (Some(status), _) => format!("was rejected with HTTP {}", status),
+                (None, Some(expires_at)) => format!("expired at {}", expires_at.format("%Y-%m-%d %H:%M UTC")),
+                (None, None) => result.error.clone().unwrap_or_else(|| "is invalid".to_string()),
+            };
+            (format!("{}_token_valid", key), Some(format!("{} {} {}", provider, credential, reason)))
+        };
+
+        ValidationCheck {
+            name,
+            category: ValidationCategory::TokenValidity,
+            passed: result.valid,
+            duration_ms: elapsed.as_millis() as u64,
+            details: Some(format!("{} {} must be accepted by the provider", provider, credential)),
+            error_message,
+        }
+    }
+
+    /// Probe the OpenAI API key or access token in auth.json, if there is one
+    ///
+    /// A `file:` or `env:` key reference is resolved first.
+    async fn probe_openai_credential(&self) -> Option<TokenValidationResult> {
+        let content = tokio::fs::read_to_string(self.codex_home.join("auth.json")).await.ok()?;
+        let auth_data: serde_json::Value = serde_json::from_str(&content).ok()?;
+
+        let (token_type, token) = match auth_data.get("OPENAI_API_KEY").and_then(|v| v.as_str()) {
+            Some(api_key) => match resolve_secret(api_key) {
+                Ok(api_key) => ("api_key", api_key.expose_secret().clone()),
+                Err(e) => {
+                    let mut result = TokenValidationResult::new("openai", "api_key");
+                    result.needs_refresh = true;
+                    result.error = Some(format!("could not be read: {}", e));
+                    return Some(result);
+                }
+            },
+            None => ("access_token", auth_data.get("tokens")?.get("access_token")?.as_str()?.to_string()),
+        };
+        let request = self.client.get(&self.openai_probe_endpoint).bearer_auth(token);
+        Some(Self::probe(TokenValidationResult::new("openai", token_type), request).await)
+    }
+
+    /// Claude configuration of the credential probe, honouring the auth file override
+    fn claude_auth_config(&self) -> ClaudeAuthConfig {
+        ClaudeAuthConfig::default()
+            .with_originator("migration")
+            .with_auth_file_path(self.config.auth_file_path.clone())
+    }
+
+    /// Probe the Claude API key or OAuth token of the active profile, or at the configured
+    /// auth file override, if there is one
+    ///
+    /// Expired OAuth tokens are reported without a request.
+    async fn probe_claude_credential(&self) -> Option<TokenValidationResult> {
+        let claude_auth = match ClaudeAuth::from_codex_home_with_config_async(&self.codex_home, self.claude_auth_config()).await {
+            Ok(claude_auth) => claude_auth?,
+            Err(e) => {
+                let mut result = TokenValidationResult::new("claude", "credentials");
+                result.needs_refresh = true;
+                result.error = Some(format!("could not be read: {}", e));
+                return Some(result);
+            }
+        };
+
+        let request = self.client
+            .get(&self.claude_probe_endpoint)
+            .header("anthropic-version", "2023-06-01");
+        if let Some(api_key) = &claude_auth.api_key {
+            let request = request.header("x-api-key", api_key.expose_secret());
+            return Some(Self::probe(TokenValidationResult::new("claude", "api_key"), request).await);
+        }
+
+        let tokens = claude_auth.oauth_tokens?;
+        let mut result = TokenValidationResult::new("claude", "oauth_token");
+        result.expires_at = Some(tokens.expires_at);
+        if tokens.expires_at <= Utc::now() {
+            result.needs_refresh = true;
+            return Some(result);
+        }
+        Some(Self::probe(result, request.bearer_auth(tokens.access_token.expose_secret())).await)
+    }
+
+    /// Send `request` and record whether the provider accepted the credential
+    async fn probe(mut result: TokenValidationResult, request: reqwest::RequestBuilder) -> TokenValidationResult {
+        match request.send().await {
+            Ok(response) => {
+                let status = response.status();
+                result.status_code = Some(status.as_u16());
+                result.valid = status.is_success();
+                result.needs_refresh = matches!(
+                    status,
+                    reqwest::StatusCode::UNAUTHORIZED | reqwest::StatusCode::FORBIDDEN
+                );
+                if !result.valid {
+                    result.error = Some(format!("HTTP {}", status));
+                }
+            }
+            Err(e) => result.error = Some(e.to_string()),
+        }
+        result
+    }
+
     /// Validate network connectivity
     async fn validate_network_connectivity(&self) -> MigrationResult<Vec<ValidationCheck>> {
         let mut checks = Vec::new();
@@ -374,14 +559,14 @@ impl MigrationValidator {
         let mut checks = Vec::new();
 
         // Check for environment variable exposure
-        checks.push(self.time_check("no_env_var_exposure", || {
+        checks.push(self.time_check("no_env_var_exposure", || async {
             !self.check_environment_variable_exposure()
         }, ValidationCategory::Security, Some("API keys should not be exposed in environment variables".to_string())).await);
 
         // Check backup directory security
         let backup_dir = self.codex_home.join(".backups");
         if backup_dir.exists() {
-            checks.push(self.time_check("backup_dir_secure", || {
+            checks.push(self.time_check("backup_dir_secure", || async {
                 self.check_secure_permissions(&backup_dir)
             }, ValidationCategory::Security, Some("Backup directory must have secure permissions".to_string())).await);
         }
@@ -395,7 +580,7 @@ impl MigrationValidator {
         let unified_file = self.codex_home.join("unified_auth.json");
 
         // Check unified auth file exists
-        checks.push(self.time_check("unified_auth_exists", || {
+        checks.push(self.time_check("unified_auth_exists", || async {
             unified_file.exists()
         }, ValidationCategory::FileSystem, Some("unified_auth.json must be created during migration".to_string())).await);
 
@@ -511,7 +696,7 @@ impl MigrationValidator {
 
         // Check backup file exists
         let backup_file = self.codex_home.join("auth.json.pre_migration");
-        checks.push(self.time_check("backup_file_exists", || {
+        checks.push(self.time_check("backup_file_exists", || async {
             backup_file.exists()
         }, ValidationCategory::DataIntegrity, Some("Pre-migration backup must exist".to_string())).await);
 
@@ -563,55 +748,6 @@ impl MigrationValidator {
         }
     }
 
-    /// Test OpenAI token validity
-    async fn test_openai_token_validity(&self) -> bool {
-        let auth_file = self.codex_home.join("auth.json");
-        match tokio::fs::read_to_string(&auth_file).await {
-            Ok(content) => {
-                if let Ok(auth_data) = serde_json::from_str::<serde_json::Value>(&content) {
-                    if let Some(api_key) = auth_data.get("OPENAI_API_KEY").and_then(|v| v.as_str()) {
-                        return self.test_openai_api_key(api_key).await;
-                    }
-                    if let Some(tokens) = auth_data.get("tokens") {
-                        if let Some(access_token) = tokens.get("access_token").and_then(|v| v.as_str()) {
-                            return self.test_openai_access_token(access_token).await;
-                        }
-                    }
-                }
-            }
-            Err(_) => {}
-        }
-        false
-    }
-
-    /// Test OpenAI API key
-    async fn test_openai_api_key(&self, api_key: &str) -> bool {
-        let response = self.client
-            .get("https://api.openai.com/v1/models")
-            .bearer_auth(api_key)
-            .send()
-            .await;
-
-        match response {
-            Ok(resp) => resp.status().is_success(),
-            Err(_) => false,
-        }
-    }
-
-    /// Test OpenAI access token
-    async fn test_openai_access_token(&self, access_token: &str) -> bool {
-        let response = self.client
-            .get("https://api.openai.com/v1/models")
-            .header("Authorization", format!("Bearer {}", access_token))
-            .send()
-            .await;
-
-        match response {
-            Ok(resp) => resp.status().is_success(),
-            Err(_) => false,
-        }
-    }
-
     /// Test network connectivity to endpoint
     async fn test_connectivity(&self, url: &str) -> bool {
         match self.client.head(url).send().await {
@@ -679,7 +815,9 @@ impl MigrationValidator {
             "codex_home_exists" | "auth_file_exists" | "unified_auth_valid" => ErrorSeverity::Critical,
             "auth_json_valid" | "auth_has_credentials" | "migration_markers_present" => ErrorSeverity::High,
             "auth_file_permissions" | "backup_file_exists" => ErrorSeverity::Medium,
-            "openai_token_valid" | "network_connectivity" => ErrorSeverity::Low,
+            "openai_token_valid" | "claude_token_valid" if self.config.abort_on_invalid_tokens => ErrorSeverity::Critical,
+            "openai_token_valid" | "claude_token_valid" | "openai_token_unverified" | "claude_token_unverified"
+            | "network_connectivity" => ErrorSeverity::Low,
             _ => ErrorSeverity::Medium,
         }
     }
@@ -691,7 +829,11 @@ impl MigrationValidator {
             "auth_file_exists" => Some("Run 'code auth login' to create authentication".to_string()),
             "auth_json_valid" => Some("Check auth.json file for syntax errors".to_string()),
             "auth_file_permissions" => Some("Run 'chmod 600 ~/.codex/auth.json' to secure the file".to_string()),
-            "openai_token_valid" => Some("Refresh your OpenAI authentication".to_string()),
+            "openai_token_valid" => Some("Run 'code auth login --provider openai' to sign in to OpenAI again".to_string()),
+            "claude_token_valid" => Some("Run 'code auth login --provider claude' to sign in to Claude again".to_string()),
+            "openai_token_unverified" | "claude_token_unverified" => {
+                Some("Check your network connection; the credential was not changed".to_string())
+            }
             _ => None,
         }
     }
@@ -706,6 +848,33 @@ impl MigrationValidator {
 mod tests {
     use super::*;
     use tempfile::tempdir;
+    use wiremock::matchers::{header, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    /// Server accepting the OpenAI key `sk-good` and refusing everything else with 401
+    async fn probe_server() -> MockServer {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/openai/v1/models"))
+            .and(header("authorization", "Bearer sk-good"))
+            .respond_with(ResponseTemplate::new(200))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(401))
+            .mount(&server)
+            .await;
+        server
+    }
+
+    async fn probing_validator(codex_home: &Path, config: &MigrationConfig, server: &MockServer) -> MigrationValidator {
+        tokio::fs::write(codex_home.join("auth.json"), r#"{"OPENAI_API_KEY": "sk-good"}"#).await.unwrap();
+        tokio::fs::write(codex_home.join("claude_auth.json"), r#"{"api_key": "sk-ant-revoked"}"#).await.unwrap();
+
+        MigrationValidator::new(codex_home, config)
+            .with_openai_probe_endpoint(format!("{}/openai/v1/models", server.uri()))
+            .with_claude_probe_endpoint(format!("{}/claude/v1/models", server.uri()))
+    }
 
     #[tokio::test]
     async fn test_file_system_validation() {
@@ -784,4 +953,75 @@ mod tests {
         // Should be valid overall (allowing some network failures)
         assert!(result.is_valid || result.errors.iter().all(|e| !matches!(e.severity, ErrorSeverity::Critical)));
     }
-}
\ No newline at end of file
+
+    #[tokio::test]
+    async fn test_token_probe_reports_each_provider() {
+        let temp_dir = tempdir().unwrap();
+        let server = probe_server().await;
+        let validator = probing_validator(temp_dir.path(), &MigrationConfig::default(), &server).await;
+
+        let results = validator.probe_stored_credentials().await;
+        let openai = results.iter().find(|r| r.provider == "openai").unwrap();
+        assert!(openai.valid);
+        assert_eq!(openai.status_code, Some(200));
+
+        let claude = results.iter().find(|r| r.provider == "claude").unwrap();
+        assert!(!claude.valid);
+        assert!(claude.needs_refresh);
+        assert_eq!(claude.status_code, Some(401));
+
+        let checks = validator.validate_existing_tokens().await.unwrap();
+        assert!(checks.iter().find(|c| c.name == "openai_token_valid").unwrap().passed);
+        let claude_check = checks.iter().find(|c| c.name == "claude_token_valid").unwrap();
+        assert!(!claude_check.passed);
+        assert!(claude_check.error_message.as_deref().unwrap().contains("HTTP 401"));
+    }
+
+    #[tokio::test]
+    async fn test_openai_probe_resolves_key_references() {
+        let temp_dir = tempdir().unwrap();
+        let server = probe_server().await;
+        let validator = probing_validator(temp_dir.path(), &MigrationConfig::default(), &server).await;
+
+        let secret_file = temp_dir.path().join("openai_key");
+        std::fs::write(&secret_file, "sk-good\n").unwrap();
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            std::fs::set_permissions(&secret_file, std::fs::Permissions::from_mode(0o600)).unwrap();
+        }
+        let auth_json = serde_json::json!({ "OPENAI_API_KEY": format!("file:{}", secret_file.display()) });
+        tokio::fs::write(temp_dir.path().join("auth.json"), auth_json.to_string()).await.unwrap();
+        let result = validator.probe_openai_credential().await.unwrap();
+        assert!(result.valid);
+        assert_eq!(result.status_code, Some(200));
+
+        tokio::fs::write(temp_dir.path().join("auth.json"), r#"{"OPENAI_API_KEY": "env:CODE_TEST_UNSET_OPENAI_KEY"}"#)
+            .await
+            .unwrap();
+        let result = validator.probe_openai_credential().await.unwrap();
+        assert!(!result.valid);
+        assert_eq!(result.status_code, None);
+        assert!(result.error.as_deref().unwrap().contains("CODE_TEST_UNSET_OPENAI_KEY"));
+    }
+
+    #[tokio::test]
+    async fn test_rejected_token_aborts_only_when_configured() {
+        let temp_dir = tempdir().unwrap();
+        let server = probe_server().await;
+
+        let validator = probing_validator(temp_dir.path(), &MigrationConfig::default(), &server).await;
+        let result = validator.validate_existing_auth().await.unwrap();
+        let error = result.errors.iter().find(|e| e.message.starts_with("Claude")).unwrap();
+        assert!(matches!(error.severity, ErrorSeverity::Low));
+        assert!(error.suggestion.as_deref().unwrap().contains("--provider claude"));
+        assert!(!result.errors.iter().any(|e| e.message.starts_with("OpenAI")));
+
+        let config = MigrationConfig { abort_on_invalid_tokens: true, ..MigrationConfig::default() };
+        let validator = probing_validator(temp_dir.path(), &config, &server).await;
+        let result = validator.validate_existing_auth().await.unwrap();
+        let error = result.errors.iter().find(|e| e.message.starts_with("Claude")).unwrap();
+        assert!(matches!(error.severity, ErrorSeverity::Critical));
+        assert!(!result.is_valid);
+    }
+}
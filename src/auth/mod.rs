@@ -52,6 +52,7 @@
 ///     priority: Priority::High,
 ///     user_preference: None,
 ///     required_features: vec![],
+///     session_id: None,
 /// };
 /// 
 /// let auth_token = auth_manager.get_auth_token(&context).await?;
@@ -84,25 +85,50 @@
 /// ClaudeAuth::setup_with_oauth(&codex_home, claude_tokens).await?;
 /// ```
 
+/// Log at info when a `verbose_logging` flag is set, otherwise at debug
+///
+/// The flags only raise the level; subscribers filtering on debug see everything either way.
+macro_rules! verbose_event {
+    ($verbose:expr, $($arg:tt)+) => {
+        if $verbose {
+            tracing::info!($($arg)+)
+        } else {
+            tracing::debug!($($arg)+)
+        }
+    };
+}
+
 pub mod claude;
+pub mod credential_resolver;
 pub mod unified;
 pub mod migration;
+pub mod telemetry;
+pub mod usage_ledger;
 
 // Re-export main types for convenient access
-pub use claude::{ClaudeAuth, ClaudeAuthMode, ClaudeAuthError, ClaudeTokenData, ClaudeSubscription};
+pub use claude::{
+    ClaudeAuth, ClaudeAuthMode, ClaudeAuthError, ClaudeTokenData, ClaudeSubscription,
+    AgentUsageReport, AgentUsageSummary, OveragePolicy, UsageDebit, AgentDrainReport, QuotaSnapshot, QuotaResetSchedule,
+};
+pub use credential_resolver::{
+    CredentialResolver, CredentialSource, CredentialKind, DiscoveredCredential, ResolvedCredentials,
+};
 pub use unified::{
     UnifiedAuthManager, ProviderType, ProviderSelectionStrategy, AuthContext, AuthProvider,
-    TaskType, Priority, ProviderStatus, UnifiedAuthError, UnifiedAuthConfig,
+    TaskType, Priority, ProviderStatus, ProviderCapabilities, UnifiedAuthError, UnifiedAuthConfig,
+    CandidateScore, CostClass, SelectionExplanation, SelectionFactor, ShutdownReport, format_reset_in,
 };
 pub use migration::{
     MigrationCoordinator, MigrationConfig, MigrationProgress, MigrationPhase, MigrationError,
-    MigrationResult as MigrationOpResult,
+    MigrationEvent, MigrationEventKind, FileChange, JournalEntry, MigrationResult as MigrationOpResult,
 };
+pub use telemetry::{TelemetrySink, NoopTelemetrySink, SelectionTelemetry, LatencyBucket};
+pub use usage_ledger::{UsageLedger, UsageEntry, UsageGroupBy, UsageSummary, UsageTotals, USAGE_LEDGER_FILE};
 
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
-use std::path::{Path, PathBuf};
+use std::path::PathBuf;
 
 /// Main authentication manager that provides a unified interface
 /// for both migration and ongoing authentication operations
@@ -146,6 +172,9 @@ impl Default for AuthManagerConfig {
 pub struct AuthSystemStatus {
     /// Whether the system is ready to use
     pub ready: bool,
+    /// Whether any provider has credentials, or legacy ones are waiting to be migrated;
+    /// `false` on a machine where nothing has been set up yet
+    pub configured: bool,
     /// Whether migration is needed
     pub migration_needed: bool,
     /// Current migration progress (if any)
@@ -161,7 +190,9 @@ pub struct AuthSystemStatus {
 /// System health indicators
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SystemHealth {
-    /// All critical components working
+    /// Combined level across the filesystem and every configured provider
+    pub overall: HealthLevel,
+    /// Usable: the filesystem is accessible and at least one provider works
     pub healthy: bool,
     /// Individual component status
     pub components: HashMap<String, ComponentHealth>,
@@ -169,6 +200,27 @@ pub struct SystemHealth {
     pub warnings: Vec<String>,
 }
 
+/// Overall level of [`SystemHealth`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum HealthLevel {
+    /// Every configured provider works
+    Healthy,
+    /// At least one provider works but another is failing, so requests fall back
+    Degraded,
+    /// No provider works, or the codex home is inaccessible
+    Unhealthy,
+}
+
+impl std::fmt::Display for HealthLevel {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            HealthLevel::Healthy => write!(f, "Healthy"),
+            HealthLevel::Degraded => write!(f, "Degraded"),
+            HealthLevel::Unhealthy => write!(f, "Unhealthy"),
+        }
+    }
+}
+
 /// Individual component health
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ComponentHealth {
@@ -193,7 +245,11 @@ impl AuthenticationManager {
     }
 
     /// Create with custom configuration
-    pub async fn with_config(codex_home: PathBuf, config: AuthManagerConfig) -> Result<Self, UnifiedAuthError> {
+    pub async fn with_config(codex_home: PathBuf, mut config: AuthManagerConfig) -> Result<Self, UnifiedAuthError> {
+        // Migration writes the Claude placeholder where the unified system will look for it
+        if config.migration_config.auth_file_path.is_none() {
+            config.migration_config.auth_file_path = config.unified_config.auth_file_path.clone();
+        }
         let mut manager = Self {
             codex_home,
             unified_manager: None,
@@ -217,9 +273,7 @@ impl AuthenticationManager {
             );
 
             if migration_coordinator.is_migration_needed().await.unwrap_or(false) {
-                if self.config.verbose_logging {
-                    println!("Migration needed - setting up migration coordinator");
-                }
+                verbose_event!(self.config.verbose_logging, "Migration needed; setting up migration coordinator");
                 self.migration_coordinator = Some(migration_coordinator);
                 return Ok(());
             }
@@ -234,9 +288,11 @@ impl AuthenticationManager {
 
         self.unified_manager = Some(unified_manager);
 
-        if self.config.verbose_logging {
-            println!("Unified authentication manager initialized");
-        }
+        verbose_event!(
+            self.config.verbose_logging,
+            strategy = ?self.config.default_strategy,
+            "Unified authentication manager initialized"
+        );
 
         Ok(())
     }
@@ -262,10 +318,13 @@ impl AuthenticationManager {
         };
 
         let health = self.assess_system_health(&provider_status).await;
-        let ready = !migration_needed && health.healthy;
+        // Degraded still serves requests through the working providers
+        let ready = !migration_needed && health.overall != HealthLevel::Unhealthy;
+        let configured = migration_needed || provider_status.values().any(|status| !status.disabled);
 
         Ok(AuthSystemStatus {
             ready,
+            configured,
             migration_needed,
             migration_progress,
             provider_status,
@@ -319,6 +378,15 @@ impl AuthenticationManager {
         }
     }
 
+    /// Explain which provider would be selected for a context, without recording usage
+    pub async fn explain_selection(&self, context: &AuthContext) -> Result<SelectionExplanation, UnifiedAuthError> {
+        if let Some(manager) = &self.unified_manager {
+            Ok(manager.explain_selection(context).await)
+        } else {
+            Err(UnifiedAuthError::ConfigError("System not ready - migration may be needed".to_string()))
+        }
+    }
+
     /// Record usage statistics for learning
     pub async fn record_usage(&self, provider_type: ProviderType, context: &AuthContext, success: bool, response_time_ms: f64) {
         if let Some(manager) = &self.unified_manager {
@@ -326,21 +394,29 @@ impl AuthenticationManager {
         }
     }
 
+    /// Ledger of completed requests, for billing reconciliation
+    pub fn usage_ledger(&self) -> UsageLedger {
+        UsageLedger::new(&self.codex_home)
+    }
+
     /// Add Claude authentication
     pub async fn add_claude_auth(&mut self, setup_type: ClaudeSetupType) -> Result<(), UnifiedAuthError> {
         match setup_type {
             ClaudeSetupType::ApiKey(api_key) => {
-                ClaudeAuth::setup_with_api_key(&self.codex_home, &api_key).await
+                let auth_config = self.config.unified_config.claude_auth_config("auth_manager");
+                ClaudeAuth::setup_with_api_key_configured(&self.codex_home, &api_key, &auth_config).await
                     .map_err(|e| UnifiedAuthError::ClaudeError(e))?;
             }
             ClaudeSetupType::OAuth(tokens) => {
-                ClaudeAuth::setup_with_oauth(&self.codex_home, tokens).await
+                let auth_config = self.config.unified_config.claude_auth_config("auth_manager");
+                ClaudeAuth::setup_with_oauth_configured(&self.codex_home, tokens, &auth_config).await
                     .map_err(|e| UnifiedAuthError::ClaudeError(e))?;
             }
         }
 
         // Refresh unified manager if available
         if let Some(manager) = &self.unified_manager {
+            manager.invalidate_cached_tokens(&ProviderType::Claude).await;
             manager.refresh_all_provider_status().await?;
         }
 
@@ -351,7 +427,18 @@ impl AuthenticationManager {
     pub async fn remove_provider(&mut self, provider_type: ProviderType) -> Result<(), UnifiedAuthError> {
         match provider_type {
             ProviderType::Claude => {
-                let claude_file = self.codex_home.join("claude_auth.json");
+                // Invalidate OAuth tokens server-side first; a stolen copy of the
+                // file would otherwise stay usable until the tokens expire
+                let auth_config = self.config.unified_config.claude_auth_config("auth_manager");
+                if let Ok(Some(claude_auth)) =
+                    ClaudeAuth::from_codex_home_with_config_async(&self.codex_home, auth_config.clone()).await
+                {
+                    if let Err(e) = claude_auth.revoke_oauth_tokens().await {
+                        tracing::warn!("Claude token revocation failed, removing local credentials anyway: {}", e);
+                    }
+                }
+
+                let claude_file = claude::claude_auth_file_for(&self.codex_home, &auth_config)?;
                 if claude_file.exists() {
                     tokio::fs::remove_file(claude_file).await
                         .map_err(|e| UnifiedAuthError::IoError(e))?;
@@ -361,9 +448,12 @@ impl AuthenticationManager {
                 // For OpenAI, we might want to preserve for backward compatibility
                 // This could be implemented as disabling rather than removing
                 return Err(UnifiedAuthError::ConfigError(
-                    "Cannot remove OpenAI provider - use logout instead".to_string()
+                    "Cannot remove OpenAI provider - use logout, or leave it out of enabled_providers".to_string()
                 ));
             }
+            ProviderType::Custom(_) => {
+                // No credentials of a custom provider are stored under codex_home
+            }
         }
 
         // Update unified manager
@@ -399,7 +489,8 @@ impl AuthenticationManager {
 
         // Check providers
         let mut any_provider_available = false;
-        for (provider_type, status) in provider_status {
+        let mut any_provider_failing = false;
+        for (provider_type, status) in provider_status.iter().filter(|(_, status)| !status.disabled) {
             let component_name = format!("provider_{:?}", provider_type).to_lowercase();
             
             let health_status = if status.available && status.authenticated {
@@ -411,8 +502,10 @@ impl AuthenticationManager {
                     HealthStatus::Healthy
                 }
             } else if status.available {
+                any_provider_failing = true;
                 HealthStatus::Warning
             } else {
+                any_provider_failing = true;
                 HealthStatus::Critical
             };
 
@@ -434,7 +527,17 @@ impl AuthenticationManager {
             warnings.push("Migration pending - some features may be limited".to_string());
         }
 
+        let overall = if !healthy {
+            HealthLevel::Unhealthy
+        } else if any_provider_failing {
+            warnings.push("Some providers are failing; requests fall back to the working ones".to_string());
+            HealthLevel::Degraded
+        } else {
+            HealthLevel::Healthy
+        };
+
         SystemHealth {
+            overall,
             healthy,
             components,
             warnings,
@@ -461,6 +564,22 @@ impl AuthenticationManager {
         }
     }
 
+    /// Drain agent sessions and persist state for a clean restart
+    ///
+    /// Call on a shutdown signal. See [`UnifiedAuthManager::shutdown`] for the sequence;
+    /// without a unified manager there is nothing to drain and only the audit log is flushed.
+    pub async fn shutdown(&self, grace: std::time::Duration) -> Result<ShutdownReport, UnifiedAuthError> {
+        match &self.unified_manager {
+            Some(manager) => manager.shutdown(grace).await,
+            None => {
+                if let Err(e) = crate::security::audit_logger::flush_audit_log() {
+                    tracing::warn!(error = %e, "Failed to flush the audit log at shutdown");
+                }
+                Ok(ShutdownReport::default())
+            }
+        }
+    }
+
     /// Update authentication strategy
     pub fn set_provider_strategy(&mut self, strategy: ProviderSelectionStrategy) {
         self.config.default_strategy = strategy.clone();
@@ -470,6 +589,8 @@ impl AuthenticationManager {
     }
 
     /// Check if system is ready for normal operation
+    ///
+    /// A degraded system counts as ready; only an unhealthy one does not.
     pub async fn is_ready(&self) -> bool {
         match self.get_system_status().await {
             Ok(status) => status.ready,
@@ -497,6 +618,7 @@ pub mod convenience {
             priority: Priority::Medium,
             user_preference: None,
             required_features: vec![],
+            session_id: None,
         }
     }
 
@@ -508,6 +630,7 @@ pub mod convenience {
             priority,
             user_preference: None,
             required_features: vec!["multi_agent".to_string()],
+            session_id: None,
         }
     }
 
@@ -519,6 +642,7 @@ pub mod convenience {
             priority: Priority::Low,
             user_preference: None,
             required_features: vec!["high_throughput".to_string()],
+            session_id: None,
         }
     }
 
@@ -530,6 +654,7 @@ pub mod convenience {
             priority: Priority::High,
             user_preference: None,
             required_features: vec!["low_latency".to_string()],
+            session_id: None,
         }
     }
 
@@ -563,6 +688,46 @@ mod tests {
         assert!(status.provider_status.contains_key(&ProviderType::OpenAI));
     }
 
+    #[tokio::test]
+    async fn test_auth_file_override_used_for_setup_load_and_logout() {
+        use crate::claude_auth::secure_claude_auth::ClaudeAuthConfig;
+
+        let temp_dir = tempdir().unwrap();
+        let codex_home = temp_dir.path().join("home");
+        let secrets_dir = temp_dir.path().join("secrets");
+        std::fs::create_dir_all(&codex_home).unwrap();
+        std::fs::create_dir_all(&secrets_dir).unwrap();
+        let auth_file = secrets_dir.join("claude.json");
+
+        let mut config = AuthManagerConfig::default();
+        config.unified_config.auth_file_path = Some(auth_file.clone());
+        let claude_config = ClaudeAuthConfig {
+            offline_mode: true,
+            ..config.unified_config.claude_auth_config("test")
+        };
+
+        ClaudeAuth::setup_with_api_key_configured(&codex_home, "sk-ant-REDACTED", &claude_config)
+            .await
+            .unwrap();
+        assert!(auth_file.exists());
+        assert!(!codex_home.join("claude_auth.json").exists());
+
+        let mut manager = AuthenticationManager::with_config(codex_home.clone(), config).await.unwrap();
+        let status = manager.get_system_status().await.unwrap();
+        assert!(status.provider_status[&ProviderType::Claude].authenticated);
+
+        manager.remove_provider(ProviderType::Claude).await.unwrap();
+        assert!(!auth_file.exists());
+
+        // The override's directory has to exist before anything is written there
+        let missing = ClaudeAuthConfig {
+            auth_file_path: Some(temp_dir.path().join("missing").join("claude.json")),
+            ..claude_config
+        };
+        let result = ClaudeAuth::setup_with_api_key_configured(&codex_home, "sk-ant-REDACTED", &missing).await;
+        assert!(matches!(result, Err(ClaudeAuthError::IoError(_))));
+    }
+
     #[tokio::test]
     async fn test_migration_detection() {
         let temp_dir = tempdir().unwrap();
@@ -610,4 +775,79 @@ mod tests {
         // Should have at least one provider
         assert!(!status.provider_status.is_empty());
     }
-}
\ No newline at end of file
+
+    #[tokio::test]
+    async fn test_empty_codex_home_reports_no_providers_configured() {
+        let temp_dir = tempdir().unwrap();
+        let auth_manager = AuthenticationManager::new(temp_dir.path().to_path_buf()).await.unwrap();
+
+        let status = auth_manager.get_system_status().await.unwrap();
+        assert!(!status.configured);
+        assert!(!status.migration_needed);
+
+        let context = convenience::code_generation_context(None);
+        let error = auth_manager.get_auth_token(&context).await.unwrap_err();
+        assert!(matches!(error, UnifiedAuthError::NoProvidersConfigured));
+        assert!(error.to_string().contains("code auth login --provider claude"), "{}", error);
+        assert!(matches!(
+            auth_manager.get_optimal_provider(&context).await,
+            Err(UnifiedAuthError::NoProvidersConfigured)
+        ));
+
+        // Legacy credentials waiting for migration count as configured
+        tokio::fs::write(temp_dir.path().join("auth.json"), r#"{"OPENAI_API_KEY": "sk-test"}"#).await.unwrap();
+        let auth_manager = AuthenticationManager::new(temp_dir.path().to_path_buf()).await.unwrap();
+        assert!(auth_manager.get_system_status().await.unwrap().configured);
+    }
+
+    fn provider_status(provider_type: ProviderType, available: bool, authenticated: bool) -> ProviderStatus {
+        ProviderStatus {
+            provider_type,
+            available,
+            authenticated,
+            subscription_tier: None,
+            quota_remaining: None,
+            quota_reset_in: None,
+            rate_limit_status: unified::RateLimitStatus {
+                requests_remaining: None,
+                tokens_remaining: None,
+                reset_time: None,
+                current_usage: 0.0,
+            },
+            last_verified: None,
+            error_message: None,
+            stale: false,
+            disabled: false,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_health_level_from_provider_states() {
+        let temp_dir = tempdir().unwrap();
+        let manager = AuthenticationManager {
+            codex_home: temp_dir.path().to_path_buf(),
+            unified_manager: None,
+            migration_coordinator: None,
+            config: AuthManagerConfig::default(),
+        };
+        let statuses = |openai: (bool, bool), claude: (bool, bool)| {
+            HashMap::from([
+                (ProviderType::OpenAI, provider_status(ProviderType::OpenAI, openai.0, openai.1)),
+                (ProviderType::Claude, provider_status(ProviderType::Claude, claude.0, claude.1)),
+            ])
+        };
+
+        let health = manager.assess_system_health(&statuses((true, true), (true, true))).await;
+        assert_eq!(health.overall, HealthLevel::Healthy);
+        assert!(health.warnings.is_empty());
+
+        // Claude is down but OpenAI still serves requests
+        let health = manager.assess_system_health(&statuses((true, true), (false, false))).await;
+        assert_eq!(health.overall, HealthLevel::Degraded);
+        assert!(health.healthy);
+
+        let health = manager.assess_system_health(&statuses((true, false), (false, false))).await;
+        assert_eq!(health.overall, HealthLevel::Unhealthy);
+        assert!(!health.healthy);
+    }
+}
@@ -0,0 +1,73 @@
+//! # Selection Telemetry
+//!
+//! Opt-in hook for embedders collecting provider-selection outcomes to tune the adaptive
+//! selector. Nothing is emitted unless a [`TelemetrySink`] is set in
+//! [`UnifiedAuthConfig::telemetry_sink`](super::UnifiedAuthConfig::telemetry_sink).
+//!
+//! Records carry no personal data or secrets: no user, account, agent or session ids, no
+//! credentials, no request content and no custom provider names. Latency is reported as a
+//! coarse bucket rather than an exact duration.
+
+use serde::{Deserialize, Serialize};
+use std::fmt::Debug;
+
+use super::unified::{ProviderType, TaskType};
+
+/// Receives one record per usage report
+pub trait TelemetrySink: Debug + Send + Sync {
+    fn record(&self, record: &SelectionTelemetry);
+}
+
+/// Sink that discards every record
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NoopTelemetrySink;
+
+impl TelemetrySink for NoopTelemetrySink {
+    fn record(&self, _record: &SelectionTelemetry) {}
+}
+
+/// Coarse response time
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum LatencyBucket {
+    #[serde(rename = "under_100ms")]
+    Under100Ms,
+    #[serde(rename = "under_1s")]
+    Under1S,
+    #[serde(rename = "under_10s")]
+    Under10S,
+    #[serde(rename = "over_10s")]
+    Over10S,
+}
+
+impl LatencyBucket {
+    pub fn from_millis(response_time_ms: f64) -> Self {
+        match response_time_ms {
+            ms if ms < 100.0 => LatencyBucket::Under100Ms,
+            ms if ms < 1_000.0 => LatencyBucket::Under1S,
+            ms if ms < 10_000.0 => LatencyBucket::Under10S,
+            _ => LatencyBucket::Over10S,
+        }
+    }
+}
+
+/// Anonymized outcome of one request on the selected provider
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SelectionTelemetry {
+    pub task_type: String,
+    /// Built-in provider id, or `custom` for any custom provider
+    pub provider: String,
+    pub success: bool,
+    pub latency: LatencyBucket,
+}
+
+impl SelectionTelemetry {
+    pub fn new(provider: &ProviderType, task_type: &TaskType, success: bool, response_time_ms: f64) -> Self {
+        Self {
+            task_type: format!("{:?}", task_type),
+            // Custom provider ids are chosen by the user and may name them or their employer
+            provider: if provider.is_builtin() { provider.id().to_string() } else { "custom".to_string() },
+            success,
+            latency: LatencyBucket::from_millis(response_time_ms),
+        }
+    }
+}
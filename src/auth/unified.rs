@@ -3,20 +3,29 @@
 /// Provides a single interface for managing both OpenAI and Claude authentication,
 /// with intelligent provider selection and seamless fallback mechanisms.
 
-use super::claude::{ClaudeAuth, ClaudeAuthMode, ClaudeAuthError};
+use super::claude::{AgentDrainReport, ClaudeAuth, ClaudeAuthError, UsageDebit};
+use super::telemetry::{SelectionTelemetry, TelemetrySink};
+use super::usage_ledger::{UsageEntry, UsageLedger};
+use crate::claude_auth::secure_claude_auth::ClaudeAuthConfig;
+use crate::performance::{PerformanceCoordinator, PerformanceMetrics};
+use crate::configuration::ProviderSchedule;
+use crate::performance::authentication_cache::{AuthenticationCache, CachedAuth};
+use crate::security::audit_logger::{flush_audit_log, log_audit_event, AuditEvent, AuthEventType, Severity};
+use crate::security::clock::{system_clock, Clock};
+use crate::security::secret::masked;
+use crate::security::secret_ref::{resolve_secret, SecretRefError};
+use crate::security::write_atomic_async;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
-use std::path::{Path, PathBuf};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 use tokio::sync::RwLock;
+use tracing::Instrument;
 
-/// Provider types supported by the unified system
-#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
-pub enum ProviderType {
-    OpenAI,
-    Claude,
-}
+/// Provider types supported by the unified system, shared with the configuration schema
+pub use crate::configuration::ProviderType;
 
 /// Authentication provider wrapper
 #[derive(Debug, Clone)]
@@ -26,7 +35,7 @@ pub enum AuthProvider {
 }
 
 /// OpenAI authentication (simplified wrapper for existing types)
-#[derive(Debug, Clone)]
+#[derive(Clone)]
 pub struct OpenAIAuth {
     // This would wrap the existing CodexAuth from the original codebase
     pub mode: String, // "ChatGPT" or "ApiKey"
@@ -34,6 +43,42 @@ pub struct OpenAIAuth {
     pub has_tokens: bool,
 }
 
+impl std::fmt::Debug for OpenAIAuth {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("OpenAIAuth")
+            .field("mode", &self.mode)
+            .field("api_key", &self.api_key.as_deref().map(masked))
+            .field("has_tokens", &self.has_tokens)
+            .finish()
+    }
+}
+
+impl OpenAIAuth {
+    /// Credentials described by the contents of `auth.json`
+    ///
+    /// `OPENAI_API_KEY` may be a `file:` or `env:` reference, resolved here.
+    pub fn from_auth_json(auth_data: &serde_json::Value) -> Result<Self, SecretRefError> {
+        let api_key = auth_data.get("OPENAI_API_KEY")
+            .and_then(|v| v.as_str())
+            .map(|value| resolve_secret(value).map(|secret| secret.expose_secret().clone()))
+            .transpose()?;
+
+        let has_tokens = auth_data.get("tokens").is_some();
+
+        let mode = if has_tokens && api_key.is_none() {
+            "ChatGPT".to_string()
+        } else {
+            "ApiKey".to_string()
+        };
+
+        Ok(OpenAIAuth {
+            mode,
+            api_key,
+            has_tokens,
+        })
+    }
+}
+
 /// Provider selection strategy
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum ProviderSelectionStrategy {
@@ -59,6 +104,9 @@ pub struct AuthContext {
     pub priority: Priority,
     pub user_preference: Option<ProviderType>,
     pub required_features: Vec<String>,
+    /// Conversation or session the request belongs to; its first selection is pinned
+    /// for the rest of the session
+    pub session_id: Option<String>,
 }
 
 /// Types of tasks that may influence provider selection
@@ -89,9 +137,102 @@ pub struct ProviderStatus {
     pub authenticated: bool,
     pub subscription_tier: Option<String>,
     pub quota_remaining: Option<u64>,
+    /// Time left until `quota_remaining` is replenished, see [`format_reset_in`]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub quota_reset_in: Option<std::time::Duration>,
     pub rate_limit_status: RateLimitStatus,
     pub last_verified: Option<DateTime<Utc>>,
     pub error_message: Option<String>,
+    /// Older than `cache_status_duration_seconds`; a background refresh is replacing it
+    #[serde(default)]
+    pub stale: bool,
+    /// Left out of `enabled_providers`: never selected, probed or health checked
+    #[serde(default)]
+    pub disabled: bool,
+}
+
+/// Human form of a time until quota reset, e.g. `resets in 3h 12m`
+pub fn format_reset_in(reset_in: std::time::Duration) -> String {
+    let total_minutes = reset_in.as_secs() / 60;
+    let (days, hours, minutes) = (total_minutes / (24 * 60), total_minutes / 60 % 24, total_minutes % 60);
+    match (days, hours, minutes) {
+        (0, 0, 0) => "resets in under a minute".to_string(),
+        (0, 0, _) => format!("resets in {}m", minutes),
+        (0, _, _) => format!("resets in {}h {}m", hours, minutes),
+        _ => format!("resets in {}d {}h", days, hours),
+    }
+}
+
+/// What a provider can serve, negotiated against `AuthContext::required_features`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProviderCapabilities {
+    pub supports_images: bool,
+    pub supports_streaming: bool,
+    pub supports_multi_agent: bool,
+    pub low_latency: bool,
+    pub high_throughput: bool,
+    /// Largest context window the provider accepts, in tokens
+    pub max_tokens: u64,
+}
+
+/// Context window a provider needs to satisfy the `long_context` feature
+pub const LONG_CONTEXT_MIN_TOKENS: u64 = 100_000;
+
+impl ProviderCapabilities {
+    /// Built-in capabilities for a provider type
+    pub fn for_provider(provider_type: &ProviderType) -> Self {
+        match provider_type {
+            ProviderType::OpenAI => Self {
+                supports_images: true,
+                supports_streaming: true,
+                supports_multi_agent: true,
+                low_latency: true,
+                high_throughput: true,
+                max_tokens: 128_000,
+            },
+            ProviderType::Claude => Self {
+                supports_images: false,
+                supports_streaming: true,
+                supports_multi_agent: true,
+                low_latency: true,
+                high_throughput: true,
+                max_tokens: 200_000,
+            },
+            // Nothing is known about a custom provider until it registers its capabilities
+            ProviderType::Custom(_) => Self {
+                supports_images: false,
+                supports_streaming: false,
+                supports_multi_agent: false,
+                low_latency: false,
+                high_throughput: false,
+                max_tokens: 0,
+            },
+        }
+    }
+
+    /// Whether this provider satisfies a required feature
+    ///
+    /// Features without a capability mapping don't constrain selection.
+    pub fn satisfies(&self, feature: &str) -> bool {
+        match feature {
+            "images" | "vision" => self.supports_images,
+            "streaming" => self.supports_streaming,
+            "multi_agent" => self.supports_multi_agent,
+            "low_latency" => self.low_latency,
+            "high_throughput" => self.high_throughput,
+            "long_context" => self.max_tokens >= LONG_CONTEXT_MIN_TOKENS,
+            _ => true,
+        }
+    }
+
+    /// Required features this provider cannot serve
+    pub fn missing_features(&self, required_features: &[String]) -> Vec<String> {
+        required_features
+            .iter()
+            .filter(|feature| !self.satisfies(feature))
+            .cloned()
+            .collect()
+    }
 }
 
 /// Rate limiting status
@@ -103,6 +244,81 @@ pub struct RateLimitStatus {
     pub current_usage: f64, // Percentage of limit used
 }
 
+/// Whether a provider's usage is billed per request or covered by a subscription
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CostClass {
+    /// Covered by a Claude Max or Pro subscription
+    Included,
+    /// Billed per token through an API key
+    Metered,
+}
+
+/// The consideration that settled a provider selection
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SelectionFactor {
+    /// The strategy's preferred provider was usable
+    StrategyPreference,
+    /// The user named the provider
+    UserChoice,
+    /// The preferred provider was unusable, so the fallback was taken
+    Fallback,
+    /// Past successful requests of the same task type went to this provider
+    LearnedPreference,
+    /// Highest subscription tier among usable providers
+    SubscriptionTier,
+    /// Remaining subscription quota covers the estimated tokens
+    Quota,
+    /// Cheapest provider for the estimated task size
+    Cost,
+    /// The provider schedule prefers this provider at the current time
+    Schedule,
+    /// The only provider serving every required feature
+    CapabilityMatch,
+    /// No provider could be selected
+    NoSuitableProvider,
+}
+
+/// Score components for one provider considered by [`UnifiedAuthManager::explain_selection`]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CandidateScore {
+    pub provider_type: ProviderType,
+    pub subscription_tier: Option<String>,
+    /// Share of recorded requests that succeeded; `None` before any were recorded
+    pub success_rate: Option<f64>,
+    pub average_latency_ms: Option<f64>,
+    pub quota_remaining: Option<u64>,
+    pub cost: CostClass,
+    /// Required features the provider can't serve; empty when it matches
+    pub missing_features: Vec<String>,
+    /// Why the provider can't serve the context; `None` when it is usable
+    pub rejection: Option<String>,
+}
+
+/// Why the selection strategy would pick a provider for a context
+///
+/// Candidates are ranked with the selected provider first, then the remaining usable
+/// providers by success rate and latency, then unusable ones.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SelectionExplanation {
+    pub strategy: ProviderSelectionStrategy,
+    pub candidates: Vec<CandidateScore>,
+    pub selected: Option<ProviderType>,
+    pub deciding_factor: SelectionFactor,
+    pub reason: String,
+}
+
+/// A Claude subscription found to have lapsed or dropped tier since it was cached
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SubscriptionDowngrade {
+    pub previous_tier: Option<String>,
+    /// `None` when the subscription is no longer active
+    pub current_tier: Option<String>,
+    /// Provider selection for the checked context once the downgrade is applied
+    pub reselected: Option<ProviderType>,
+}
+
 /// Unified authentication manager
 #[derive(Debug)]
 pub struct UnifiedAuthManager {
@@ -110,20 +326,105 @@ pub struct UnifiedAuthManager {
     strategy: ProviderSelectionStrategy,
     providers: Arc<RwLock<HashMap<ProviderType, AuthProvider>>>,
     status_cache: Arc<RwLock<HashMap<ProviderType, ProviderStatus>>>,
+    status_refresh_in_flight: Arc<AtomicBool>,
     usage_stats: Arc<RwLock<UsageStats>>,
+    performance: Arc<PerformanceCoordinator>,
+    auth_cache: Arc<AuthenticationCache>,
+    capabilities: HashMap<ProviderType, ProviderCapabilities>,
+    /// Provider each session is pinned to
+    session_pins: Arc<RwLock<HashMap<String, SessionPin>>>,
+    /// Providers kept out of selection after a failure until a re-probe finds them healthy
+    cooldowns: Arc<RwLock<HashMap<ProviderType, ProviderCooldown>>>,
     config: UnifiedAuthConfig,
+    clock: Arc<dyn Clock>,
+}
+
+/// Outcome of [`UnifiedAuthManager::shutdown`]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ShutdownReport {
+    /// What happened to the Claude agents active at shutdown
+    pub agents: AgentDrainReport,
+    /// Where the final Claude quota accounting was written, if Claude is configured
+    pub quota_state_file: Option<PathBuf>,
+}
+
+/// Hours a session pin survives without being used
+pub const SESSION_PIN_IDLE_HOURS: i64 = 24;
+
+/// Provider a session is pinned to, and when the pin was last used
+#[derive(Debug, Clone)]
+struct SessionPin {
+    provider: ProviderType,
+    last_used: DateTime<Utc>,
+}
+
+/// A provider that failed and is skipped by selection until it recovers
+#[derive(Debug, Clone)]
+struct ProviderCooldown {
+    reason: String,
+    since: DateTime<Utc>,
+    next_probe_at: DateTime<Utc>,
+}
+
+/// Order of Claude subscription tiers; anything other than Max or Pro ranks as none
+fn subscription_rank(tier: Option<&str>) -> u8 {
+    match tier {
+        Some("max") => 2,
+        Some("pro") => 1,
+        _ => 0,
+    }
 }
 
+/// Final Claude quota accounting, written by [`UnifiedAuthManager::shutdown`]
+pub const QUOTA_STATE_FILE: &str = "claude_quota_state.json";
+
 /// Configuration for unified authentication
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct UnifiedAuthConfig {
     pub enable_fallback: bool,
+    /// How long cached provider status counts as fresh
     pub cache_status_duration_seconds: u64,
     pub auto_refresh_tokens: bool,
     pub monitor_quota: bool,
     pub load_balance_agents: bool,
     pub max_concurrent_claude_agents: u16,
     pub preference_learning_enabled: bool,
+    /// Time-of-day windows that override the strategy while they are open
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub provider_schedule: Option<ProviderSchedule>,
+    /// Providers probed at once by a status refresh
+    #[serde(default = "default_status_refresh_concurrency")]
+    pub status_refresh_concurrency: usize,
+    /// A provider whose status probe takes longer is reported unavailable
+    #[serde(default = "default_status_probe_timeout_ms")]
+    pub status_probe_timeout_ms: u64,
+    /// Opt-in receiver of anonymized selection outcomes, one per [`UnifiedAuthManager::record_usage`];
+    /// records carry no personal data or secrets. Off by default and never persisted.
+    #[serde(skip)]
+    pub telemetry_sink: Option<Arc<dyn TelemetrySink>>,
+    /// `claude_auth.json` location used instead of the active profile's home
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub auth_file_path: Option<PathBuf>,
+    /// Providers that may be used; `None` enables every provider. A disabled provider
+    /// keeps its stored credentials but is never loaded, selected or probed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub enabled_providers: Option<Vec<ProviderType>>,
+    /// How often a provider in cooldown after a failed request is probed again; once a
+    /// probe finds it healthy the strategy may prefer it again
+    #[serde(default = "default_fallback_reprobe_interval_seconds")]
+    pub fallback_reprobe_interval_seconds: u64,
+}
+
+fn default_fallback_reprobe_interval_seconds() -> u64 {
+    60
+}
+
+fn default_status_refresh_concurrency() -> usize {
+    4
+}
+
+fn default_status_probe_timeout_ms() -> u64 {
+    10_000
 }
 
 impl Default for UnifiedAuthConfig {
@@ -136,6 +437,44 @@ impl Default for UnifiedAuthConfig {
             load_balance_agents: true,
             max_concurrent_claude_agents: 10,
             preference_learning_enabled: true,
+            provider_schedule: None,
+            status_refresh_concurrency: default_status_refresh_concurrency(),
+            status_probe_timeout_ms: default_status_probe_timeout_ms(),
+            telemetry_sink: None,
+            auth_file_path: None,
+            enabled_providers: None,
+            fallback_reprobe_interval_seconds: default_fallback_reprobe_interval_seconds(),
+        }
+    }
+}
+
+impl UnifiedAuthConfig {
+    /// Claude configuration for requests from `originator`, carrying the auth file override
+    pub fn claude_auth_config(&self, originator: &str) -> ClaudeAuthConfig {
+        ClaudeAuthConfig::default()
+            .with_originator(originator)
+            .with_auth_file_path(self.auth_file_path.clone())
+    }
+
+    pub fn is_provider_enabled(&self, provider_type: &ProviderType) -> bool {
+        self.enabled_providers.as_ref().is_none_or(|enabled| enabled.contains(provider_type))
+    }
+
+    /// Built-in providers left out of `enabled_providers`
+    pub fn disabled_providers(&self) -> Vec<ProviderType> {
+        [ProviderType::OpenAI, ProviderType::Claude]
+            .into_iter()
+            .filter(|provider_type| !self.is_provider_enabled(provider_type))
+            .collect()
+    }
+
+    /// Reject an `enabled_providers` list that leaves nothing to select
+    pub fn validate(&self) -> Result<(), UnifiedAuthError> {
+        match &self.enabled_providers {
+            Some(enabled) if enabled.is_empty() => Err(UnifiedAuthError::ConfigError(
+                "enabled_providers is empty; at least one provider must remain enabled".to_string(),
+            )),
+            _ => Ok(()),
         }
     }
 }
@@ -188,13 +527,30 @@ impl UnifiedAuthManager {
         strategy: ProviderSelectionStrategy, 
         config: UnifiedAuthConfig
     ) -> Result<Self, UnifiedAuthError> {
+        config.validate()?;
+        let performance = Arc::new(PerformanceCoordinator::new());
+        let auth_cache = performance.get_cache();
+
         let mut manager = Self {
             codex_home,
             strategy,
             providers: Arc::new(RwLock::new(HashMap::new())),
             status_cache: Arc::new(RwLock::new(HashMap::new())),
+            status_refresh_in_flight: Arc::new(AtomicBool::new(false)),
             usage_stats: Arc::new(RwLock::new(UsageStats::default())),
+            performance,
+            auth_cache,
+            capabilities: [ProviderType::OpenAI, ProviderType::Claude]
+                .into_iter()
+                .map(|provider_type| {
+                    let capabilities = ProviderCapabilities::for_provider(&provider_type);
+                    (provider_type, capabilities)
+                })
+                .collect(),
+            session_pins: Arc::new(RwLock::new(HashMap::new())),
+            cooldowns: Arc::new(RwLock::new(HashMap::new())),
             config,
+            clock: system_clock(),
         };
 
         // Load existing providers
@@ -210,7 +566,117 @@ impl UnifiedAuthManager {
     }
 
     /// Get the optimal provider for a given context
+    ///
+    /// Providers that can't serve every required feature are excluded before the
+    /// selection strategy runs. Providers in cooldown whose re-probe is due are probed
+    /// first, so a recovered provider can win this selection.
     pub async fn get_optimal_provider(&self, context: &AuthContext) -> Result<AuthProvider, UnifiedAuthError> {
+        let span = tracing::debug_span!(
+            "provider_selection",
+            strategy = ?self.strategy,
+            task_type = ?context.task_type,
+            provider = tracing::field::Empty,
+            latency_ms = tracing::field::Empty,
+        );
+        let started = std::time::Instant::now();
+        self.reprobe_cooled_down_providers().instrument(span.clone()).await;
+        let result = self.select_session_provider(context).instrument(span.clone()).await;
+
+        span.record("latency_ms", started.elapsed().as_millis() as u64);
+        match &result {
+            Ok(provider) => {
+                span.record("provider", Self::provider_cache_key(provider));
+                tracing::debug!(parent: &span, "Provider selected");
+            }
+            Err(e) => tracing::warn!(parent: &span, error = %e, "No provider selected"),
+        }
+        result
+    }
+
+    /// Pinned provider of the context's session, or a fresh selection that becomes its pin
+    ///
+    /// A session stays on its provider while that provider is still loaded, authenticated
+    /// and capable, even if the strategy would now pick another; once it isn't, the
+    /// session falls back to normal selection and is pinned to the new provider.
+    async fn select_session_provider(&self, context: &AuthContext) -> Result<AuthProvider, UnifiedAuthError> {
+        let Some(session_id) = context.session_id.as_deref() else {
+            return self.select_provider(context).await;
+        };
+
+        if let Some(pinned) = self.pinned_provider(session_id).await {
+            if let Some(provider) = self.pinned_provider_if_usable(pinned.clone(), context).await {
+                if let Some(pin) = self.session_pins.write().await.get_mut(session_id) {
+                    pin.last_used = self.clock.now();
+                }
+                return Ok(provider);
+            }
+            tracing::info!(session_id, provider = pinned.id(), "Pinned provider unavailable, re-pinning session");
+        }
+
+        let provider = self.select_provider(context).await?;
+        self.pin_provider(session_id, Self::provider_type_of(&provider)).await;
+        Ok(provider)
+    }
+
+    async fn pinned_provider_if_usable(&self, provider_type: ProviderType, context: &AuthContext) -> Option<AuthProvider> {
+        if let Some(status) = self.status_cache.read().await.get(&provider_type) {
+            if status.disabled || !status.available || !status.authenticated {
+                return None;
+            }
+        }
+        self.get_capable_provider(provider_type, context).await.ok()
+    }
+
+    /// Pin `session_id` to `provider` until [`unpin`](Self::unpin) is called, the
+    /// provider becomes unavailable or is removed, or the session goes unused for
+    /// [`SESSION_PIN_IDLE_HOURS`]
+    ///
+    /// Idle pins of other sessions are dropped here.
+    pub async fn pin_provider(&self, session_id: &str, provider: ProviderType) {
+        let now = self.clock.now();
+        let mut pins = self.session_pins.write().await;
+        pins.retain(|_, pin| !Self::pin_is_idle(pin, now));
+        pins.insert(session_id.to_string(), SessionPin { provider, last_used: now });
+    }
+
+    /// Forget the pin of `session_id`, returning the provider it was pinned to
+    pub async fn unpin(&self, session_id: &str) -> Option<ProviderType> {
+        self.session_pins.write().await.remove(session_id).map(|pin| pin.provider)
+    }
+
+    /// Provider `session_id` is currently pinned to
+    pub async fn pinned_provider(&self, session_id: &str) -> Option<ProviderType> {
+        let now = self.clock.now();
+        self.session_pins
+            .read()
+            .await
+            .get(session_id)
+            .filter(|pin| !Self::pin_is_idle(pin, now))
+            .map(|pin| pin.provider.clone())
+    }
+
+    fn pin_is_idle(pin: &SessionPin, now: DateTime<Utc>) -> bool {
+        now - pin.last_used > chrono::Duration::hours(SESSION_PIN_IDLE_HOURS)
+    }
+
+    async fn select_provider(&self, context: &AuthContext) -> Result<AuthProvider, UnifiedAuthError> {
+        if self.providers.read().await.is_empty() {
+            return Err(UnifiedAuthError::NoProvidersConfigured);
+        }
+        self.negotiate_capabilities(context).await?;
+
+        if let Some(scheduled) = self.scheduled_provider() {
+            return match scheduled {
+                ProviderType::Claude => {
+                    self.get_provider_with_fallback(ProviderType::Claude, ProviderType::OpenAI, context).await
+                }
+                ProviderType::OpenAI => {
+                    self.get_provider_with_fallback(ProviderType::OpenAI, ProviderType::Claude, context).await
+                }
+                custom => self.get_capable_provider(custom, context).await,
+            };
+        }
+
         match self.strategy {
             ProviderSelectionStrategy::PreferClaude => {
                 self.get_provider_with_fallback(ProviderType::Claude, ProviderType::OpenAI, context).await
@@ -219,7 +685,7 @@ impl UnifiedAuthManager {
                 self.get_provider_with_fallback(ProviderType::OpenAI, ProviderType::Claude, context).await
             }
             ProviderSelectionStrategy::UserChoice(ref provider_type) => {
-                self.get_specific_provider(provider_type.clone()).await
+                self.get_capable_provider(provider_type.clone(), context).await
             }
             ProviderSelectionStrategy::CostOptimized => {
                 self.get_cost_optimized_provider(context).await
@@ -233,6 +699,367 @@ impl UnifiedAuthManager {
         }
     }
 
+    /// Provider the schedule prefers right now
+    ///
+    /// An explicit [`UserChoice`](ProviderSelectionStrategy::UserChoice) outranks the
+    /// schedule, and outside every window the strategy applies as usual.
+    fn scheduled_provider(&self) -> Option<ProviderType> {
+        if matches!(self.strategy, ProviderSelectionStrategy::UserChoice(_)) {
+            return None;
+        }
+        self.config.provider_schedule.as_ref()?.provider_at(self.clock.now())
+    }
+
+    /// Explain which provider the current strategy would select for `context`, and why
+    ///
+    /// Runs the same selection as [`get_optimal_provider`](Self::get_optimal_provider)
+    /// without recording usage or probing providers, so it is safe to call from status
+    /// commands.
+    pub async fn explain_selection(&self, context: &AuthContext) -> SelectionExplanation {
+        let mut candidates = self.score_candidates(context).await;
+        let outcome = self.select_provider(context).await;
+        let selected = outcome.as_ref().ok().map(Self::provider_type_of);
+
+        let (deciding_factor, reason) = match &outcome {
+            Ok(_) => self.deciding_factor(selected.as_ref().unwrap(), &candidates, context).await,
+            Err(e) => (SelectionFactor::NoSuitableProvider, e.to_string()),
+        };
+
+        candidates.sort_by(|a, b| {
+            let rank = |c: &CandidateScore| (selected.as_ref() != Some(&c.provider_type), c.rejection.is_some());
+            rank(a)
+                .cmp(&rank(b))
+                .then_with(|| {
+                    let rate = |c: &CandidateScore| c.success_rate.unwrap_or(-1.0);
+                    rate(b).partial_cmp(&rate(a)).unwrap_or(std::cmp::Ordering::Equal)
+                })
+                .then_with(|| {
+                    let latency = |c: &CandidateScore| c.average_latency_ms.unwrap_or(f64::MAX);
+                    latency(a).partial_cmp(&latency(b)).unwrap_or(std::cmp::Ordering::Equal)
+                })
+                .then_with(|| a.provider_type.id().cmp(b.provider_type.id()))
+        });
+
+        SelectionExplanation {
+            strategy: self.strategy.clone(),
+            candidates,
+            selected,
+            deciding_factor,
+            reason,
+        }
+    }
+
+    async fn score_candidates(&self, context: &AuthContext) -> Vec<CandidateScore> {
+        let providers = self.providers.read().await.clone();
+        let status_cache = self.status_cache.read().await.clone();
+        let cooldowns = self.cooldowns.read().await.clone();
+        let (success_rates, latencies): (HashMap<_, _>, HashMap<_, _>) = {
+            let usage_stats = self.usage_stats.read().await;
+            (
+                usage_stats.success_rates.clone(),
+                usage_stats
+                    .provider_usage
+                    .iter()
+                    .map(|(provider_type, usage)| (provider_type.clone(), usage.average_response_time_ms))
+                    .collect(),
+            )
+        };
+
+        let mut provider_types: Vec<ProviderType> = providers.keys().cloned().collect();
+        for built_in in [ProviderType::OpenAI, ProviderType::Claude] {
+            if !provider_types.contains(&built_in) {
+                provider_types.push(built_in);
+            }
+        }
+
+        let mut candidates = Vec::new();
+        for provider_type in provider_types {
+            let status = status_cache.get(&provider_type);
+            let subscription_tier = status.and_then(|s| s.subscription_tier.clone());
+            let missing_features = self.missing_features(&provider_type, context);
+
+            let rejection = if !missing_features.is_empty() {
+                Some(format!("missing features: {}", missing_features.join(", ")))
+            } else if let Some(cooldown) = cooldowns.get(&provider_type) {
+                Some(format!("cooling down after a failure: {}", cooldown.reason))
+            } else if let Some(provider) = providers.get(&provider_type) {
+                if status.is_some_and(|s| !s.authenticated) {
+                    Some("not authenticated".to_string())
+                } else {
+                    match self.is_provider_suitable(provider, context).await {
+                        Ok(true) => None,
+                        Ok(false) => Some("insufficient quota or too many concurrent agents".to_string()),
+                        Err(e) => Some(e.to_string()),
+                    }
+                }
+            } else if !self.config.is_provider_enabled(&provider_type) {
+                Some("disabled".to_string())
+            } else {
+                Some("not configured".to_string())
+            };
+
+            let cost = match (&provider_type, subscription_tier.as_deref()) {
+                (ProviderType::Claude, Some("max" | "pro")) => CostClass::Included,
+                _ => CostClass::Metered,
+            };
+
+            candidates.push(CandidateScore {
+                success_rate: success_rates.get(&provider_type).copied(),
+                average_latency_ms: latencies.get(&provider_type).copied(),
+                quota_remaining: status.and_then(|s| s.quota_remaining),
+                subscription_tier,
+                cost,
+                missing_features,
+                rejection,
+                provider_type,
+            });
+        }
+        candidates
+    }
+
+    /// Which consideration made the strategy settle on `selected`
+    async fn deciding_factor(
+        &self,
+        selected: &ProviderType,
+        candidates: &[CandidateScore],
+        context: &AuthContext,
+    ) -> (SelectionFactor, String) {
+        let rejection_of = |provider_type: &ProviderType| {
+            candidates
+                .iter()
+                .find(|c| &c.provider_type == provider_type)
+                .and_then(|c| c.rejection.clone())
+                .unwrap_or_else(|| "unavailable".to_string())
+        };
+
+        if !matches!(self.strategy, ProviderSelectionStrategy::UserChoice(_))
+            && !context.required_features.is_empty()
+            && candidates
+                .iter()
+                .filter(|c| &c.provider_type != selected)
+                .all(|c| !c.missing_features.is_empty())
+        {
+            return (
+                SelectionFactor::CapabilityMatch,
+                format!("only {} serves {}", selected, context.required_features.join(", ")),
+            );
+        }
+
+        if let Some(scheduled) = self.scheduled_provider() {
+            return if selected == &scheduled {
+                (SelectionFactor::Schedule, format!("{} is scheduled for the current time", scheduled))
+            } else {
+                (SelectionFactor::Fallback, format!("scheduled {} rejected: {}", scheduled, rejection_of(&scheduled)))
+            };
+        }
+
+        match &self.strategy {
+            ProviderSelectionStrategy::PreferClaude | ProviderSelectionStrategy::PreferOpenAI => {
+                let primary = match self.strategy {
+                    ProviderSelectionStrategy::PreferClaude => ProviderType::Claude,
+                    _ => ProviderType::OpenAI,
+                };
+                if selected == &primary {
+                    (SelectionFactor::StrategyPreference, format!("{} is preferred and usable", primary))
+                } else {
+                    (SelectionFactor::Fallback, format!("preferred {} rejected: {}", primary, rejection_of(&primary)))
+                }
+            }
+            ProviderSelectionStrategy::UserChoice(_) => {
+                (SelectionFactor::UserChoice, format!("{} was chosen explicitly", selected))
+            }
+            ProviderSelectionStrategy::CostOptimized => {
+                let candidate = candidates.iter().find(|c| &c.provider_type == selected);
+                match (selected, candidate.map(|c| c.cost)) {
+                    (ProviderType::Claude, Some(CostClass::Included)) => (
+                        SelectionFactor::Quota,
+                        "Claude Max quota covers the estimated tokens".to_string(),
+                    ),
+                    (ProviderType::Claude, _) => (
+                        SelectionFactor::Cost,
+                        "small tasks are cheapest on the Claude API key".to_string(),
+                    ),
+                    _ => (SelectionFactor::Cost, format!("{} is the default for larger tasks", selected)),
+                }
+            }
+            ProviderSelectionStrategy::Adaptive | ProviderSelectionStrategy::BestSubscription => {
+                let task_type_key = format!("{:?}", context.task_type);
+                let learned = self.usage_stats.read().await.task_type_preferences.get(&task_type_key).cloned();
+                if matches!(self.strategy, ProviderSelectionStrategy::Adaptive)
+                    && self.config.preference_learning_enabled
+                    && learned.as_ref() == Some(selected)
+                {
+                    (
+                        SelectionFactor::LearnedPreference,
+                        format!("{} tasks last succeeded on {}", task_type_key, selected),
+                    )
+                } else {
+                    let tier = candidates
+                        .iter()
+                        .find(|c| &c.provider_type == selected)
+                        .and_then(|c| c.subscription_tier.clone())
+                        .unwrap_or_else(|| "api key".to_string());
+                    (SelectionFactor::SubscriptionTier, format!("{} ({}) is the best usable subscription", selected, tier))
+                }
+            }
+        }
+    }
+
+    fn provider_type_of(provider: &AuthProvider) -> ProviderType {
+        match provider {
+            AuthProvider::Claude(_) => ProviderType::Claude,
+            AuthProvider::OpenAI(_) => ProviderType::OpenAI,
+        }
+    }
+
+    /// Fail with the unmet features when no loaded provider can serve the context
+    async fn negotiate_capabilities(&self, context: &AuthContext) -> Result<(), UnifiedAuthError> {
+        if context.required_features.is_empty() {
+            return Ok(());
+        }
+
+        let providers = self.providers.read().await;
+        if providers.is_empty() {
+            // Nothing to negotiate with; let the strategy report the missing provider
+            return Ok(());
+        }
+
+        let mut missing_features = Vec::new();
+        for provider_type in providers.keys() {
+            let missing = self.missing_features(provider_type, context);
+            if missing.is_empty() {
+                return Ok(());
+            }
+            missing_features.extend(missing);
+        }
+
+        missing_features.sort();
+        missing_features.dedup();
+        Err(UnifiedAuthError::NoCapableProvider { missing_features })
+    }
+
+    fn missing_features(&self, provider_type: &ProviderType, context: &AuthContext) -> Vec<String> {
+        self.capabilities
+            .get(provider_type)
+            .cloned()
+            .unwrap_or_else(|| ProviderCapabilities::for_provider(provider_type))
+            .missing_features(&context.required_features)
+    }
+
+    /// Get a provider by type, provided it can serve every required feature and isn't
+    /// in cooldown
+    async fn get_capable_provider(
+        &self,
+        provider_type: ProviderType,
+        context: &AuthContext,
+    ) -> Result<AuthProvider, UnifiedAuthError> {
+        let missing_features = self.missing_features(&provider_type, context);
+        if !missing_features.is_empty() {
+            return Err(UnifiedAuthError::NoCapableProvider { missing_features });
+        }
+        if self.cooldowns.read().await.contains_key(&provider_type) {
+            return Err(UnifiedAuthError::ProviderNotAvailable(provider_type));
+        }
+        self.get_specific_provider(provider_type).await
+    }
+
+    /// Keep `provider_type` out of selection until a re-probe finds it healthy
+    ///
+    /// Called when a request on the provider fails; the first re-probe is due one
+    /// `fallback_reprobe_interval_seconds` from now. Marking a provider already in
+    /// cooldown keeps its schedule.
+    pub async fn mark_provider_failed(&self, provider_type: ProviderType, reason: impl Into<String>) {
+        let now = self.clock.now();
+        let reason = reason.into();
+        tracing::info!(provider = provider_type.id(), reason = %reason, "Provider in cooldown");
+        self.cooldowns.write().await.entry(provider_type).or_insert(ProviderCooldown {
+            reason,
+            since: now,
+            next_probe_at: now + self.reprobe_interval(),
+        });
+    }
+
+    /// Whether `provider_type` is in cooldown after a failure
+    pub async fn is_cooling_down(&self, provider_type: &ProviderType) -> bool {
+        self.cooldowns.read().await.contains_key(provider_type)
+    }
+
+    fn reprobe_interval(&self) -> chrono::Duration {
+        chrono::Duration::seconds(self.config.fallback_reprobe_interval_seconds.min(i64::MAX as u64) as i64)
+    }
+
+    /// Probe every provider in cooldown whose re-probe is due, returning those that recovered
+    ///
+    /// A recovered provider leaves cooldown, its fresh status replaces the cached one and
+    /// an info event is audited. One that is still unhealthy is probed again an interval later.
+    ///
+    /// Each due probe is claimed by moving its next probe an interval ahead before it runs,
+    /// so concurrent callers don't probe the same provider; they carry on without waiting.
+    pub async fn reprobe_cooled_down_providers(&self) -> Vec<ProviderType> {
+        let now = self.clock.now();
+        let due: Vec<ProviderType> = self
+            .cooldowns
+            .write()
+            .await
+            .iter_mut()
+            .filter(|(_, cooldown)| cooldown.next_probe_at <= now)
+            .map(|(provider_type, cooldown)| {
+                cooldown.next_probe_at = now + self.reprobe_interval();
+                provider_type.clone()
+            })
+            .collect();
+
+        let (_, probe_timeout) = self.probe_limits();
+        let mut recovered = Vec::new();
+        for provider_type in due {
+            let Ok(provider) = self.get_specific_provider(provider_type.clone()).await else {
+                // Removed providers have nothing left to recover
+                self.cooldowns.write().await.remove(&provider_type);
+                continue;
+            };
+            let status = match tokio::time::timeout(probe_timeout, Self::get_provider_status(&provider)).await {
+                Ok(status) => status,
+                Err(_) => Self::timed_out_status(&provider_type, probe_timeout),
+            };
+            let healthy = status.available && status.authenticated;
+            self.status_cache.write().await.insert(provider_type.clone(), status);
+
+            if !healthy {
+                continue;
+            }
+            let Some(cooldown) = self.cooldowns.write().await.remove(&provider_type) else {
+                continue;
+            };
+
+            let cooldown_seconds = (now - cooldown.since).num_seconds();
+            tracing::info!(provider = provider_type.id(), cooldown_seconds, "Provider recovered");
+            let _ = log_audit_event(AuditEvent {
+                timestamp: Utc::now(),
+                event_type: AuthEventType::ProviderRecovered,
+                user_id: None,
+                session_id: None,
+                client_id: None,
+                ip_address: None,
+                user_agent: None,
+                success: true,
+                error_message: None,
+                metadata: serde_json::json!({
+                    "provider": provider_type.id(),
+                    "failure": cooldown.reason,
+                    "cooldown_seconds": cooldown_seconds,
+                }),
+                severity: Severity::Info,
+            });
+            recovered.push(provider_type);
+        }
+        recovered
+    }
+
+    /// Override the capabilities declared for a provider
+    pub fn set_provider_capabilities(&mut self, provider_type: ProviderType, capabilities: ProviderCapabilities) {
+        self.capabilities.insert(provider_type, capabilities);
+    }
+
     /// Get provider with fallback logic
     async fn get_provider_with_fallback(
         &self, 
@@ -241,22 +1068,32 @@ impl UnifiedAuthManager {
         context: &AuthContext
     ) -> Result<AuthProvider, UnifiedAuthError> {
         // Try primary provider first
-        if let Ok(provider) = self.get_specific_provider(primary.clone()).await {
-            if self.is_provider_suitable(&provider, context).await? {
-                return Ok(provider);
+        let mut primary_error = None;
+        if let Ok(provider) = self.get_capable_provider(primary.clone(), context).await {
+            match self.is_provider_suitable(&provider, context).await {
+                Ok(true) => return Ok(provider),
+                Ok(false) => {}
+                // Throttling and refused credentials only rule out this provider
+                Err(e) if e.is_retryable() || e.is_auth_fatal() => primary_error = Some(e),
+                Err(e) => return Err(e),
             }
         }
 
         // Fallback to secondary provider if enabled
         if self.config.enable_fallback {
-            if let Ok(provider) = self.get_specific_provider(fallback).await {
+            if let Ok(provider) = self.get_capable_provider(fallback, context).await {
                 if self.is_provider_suitable(&provider, context).await? {
+                    // Stay on the fallback until the primary is seen healthy again
+                    if let Some(e) = &primary_error {
+                        self.mark_provider_failed(primary, e.to_string()).await;
+                    }
                     return Ok(provider);
                 }
             }
         }
 
-        Err(UnifiedAuthError::NoSuitableProvider)
+        // Surface why the primary failed so callers can honour `retry_after`
+        Err(primary_error.unwrap_or(UnifiedAuthError::NoSuitableProvider))
     }
 
     /// Get specific provider by type
@@ -276,7 +1113,9 @@ impl UnifiedAuthManager {
             if claude_status.subscription_tier.as_ref().map(|t| t == "max").unwrap_or(false) {
                 if let Some(quota_remaining) = claude_status.quota_remaining {
                     if quota_remaining > context.estimated_tokens.unwrap_or(1000) {
-                        return self.get_specific_provider(ProviderType::Claude).await;
+                        if let Ok(provider) = self.get_capable_provider(ProviderType::Claude, context).await {
+                            return Ok(provider);
+                        }
                     }
                 }
             }
@@ -285,14 +1124,14 @@ impl UnifiedAuthManager {
         // Fall back to OpenAI or Claude API key based on estimated cost
         if let Some(estimated_tokens) = context.estimated_tokens {
             if estimated_tokens < 10000 { // Small tasks - use Claude API key
-                if let Ok(provider) = self.get_specific_provider(ProviderType::Claude).await {
+                if let Ok(provider) = self.get_capable_provider(ProviderType::Claude, context).await {
                     return Ok(provider);
                 }
             }
         }
 
         // Default to OpenAI
-        self.get_specific_provider(ProviderType::OpenAI).await
+        self.get_capable_provider(ProviderType::OpenAI, context).await
     }
 
     /// Get adaptive provider based on usage patterns
@@ -306,7 +1145,7 @@ impl UnifiedAuthManager {
         // Check if we have a learned preference for this task type
         let task_type_key = format!("{:?}", context.task_type);
         if let Some(preferred_provider) = usage_stats.task_type_preferences.get(&task_type_key) {
-            if let Ok(provider) = self.get_specific_provider(preferred_provider.clone()).await {
+            if let Ok(provider) = self.get_capable_provider(preferred_provider.clone(), context).await {
                 if self.is_provider_suitable(&provider, context).await? {
                     return Ok(provider);
                 }
@@ -335,7 +1174,7 @@ impl UnifiedAuthManager {
                     // Check subscription tier if required
                     if let Some(required) = required_tier {
                         if status.subscription_tier.as_ref() == Some(required) {
-                            if let Ok(provider) = self.get_specific_provider(provider_type.clone()).await {
+                            if let Ok(provider) = self.get_capable_provider(provider_type.clone(), context).await {
                                 if self.is_provider_suitable(&provider, context).await? {
                                     return Ok(provider);
                                 }
@@ -343,7 +1182,7 @@ impl UnifiedAuthManager {
                         }
                     } else {
                         // No specific tier required
-                        if let Ok(provider) = self.get_specific_provider(provider_type.clone()).await {
+                        if let Ok(provider) = self.get_capable_provider(provider_type.clone(), context).await {
                             if self.is_provider_suitable(&provider, context).await? {
                                 return Ok(provider);
                             }
@@ -388,17 +1227,26 @@ impl UnifiedAuthManager {
     }
 
     /// Load providers from disk
+    ///
+    /// Disabled providers are skipped; their credentials stay on disk untouched.
     async fn load_providers(&mut self) -> Result<(), UnifiedAuthError> {
         let mut providers = HashMap::new();
 
         // Load OpenAI authentication (using existing logic)
-        if let Some(openai_auth) = self.load_openai_auth().await? {
-            providers.insert(ProviderType::OpenAI, AuthProvider::OpenAI(openai_auth));
+        if self.config.is_provider_enabled(&ProviderType::OpenAI) {
+            if let Some(openai_auth) = self.load_openai_auth().await? {
+                providers.insert(ProviderType::OpenAI, AuthProvider::OpenAI(openai_auth));
+            }
         }
 
         // Load Claude authentication
-        if let Some(claude_auth) = ClaudeAuth::from_codex_home(&self.codex_home, ClaudeAuthMode::MaxSubscription, "unified_auth") {
-            providers.insert(ProviderType::Claude, AuthProvider::Claude(claude_auth));
+        if self.config.is_provider_enabled(&ProviderType::Claude) {
+            let claude_auth = ClaudeAuth::load_with_config(&self.codex_home, self.config.claude_auth_config("unified_auth"))
+                .await
+                .map_err(UnifiedAuthError::ClaudeError)?;
+            if let Some(claude_auth) = claude_auth {
+                providers.insert(ProviderType::Claude, AuthProvider::Claude(claude_auth));
+            }
         }
 
         *self.providers.write().await = providers;
@@ -406,6 +1254,8 @@ impl UnifiedAuthManager {
     }
 
     /// Load OpenAI authentication (simplified)
+    ///
+    /// `OPENAI_API_KEY` may be a `file:` or `env:` reference to the key.
     async fn load_openai_auth(&self) -> Result<Option<OpenAIAuth>, UnifiedAuthError> {
         let auth_file = self.codex_home.join("auth.json");
         if !auth_file.exists() {
@@ -415,41 +1265,219 @@ impl UnifiedAuthManager {
         let content = tokio::fs::read_to_string(&auth_file).await?;
         let auth_data: serde_json::Value = serde_json::from_str(&content)?;
 
-        let api_key = auth_data.get("OPENAI_API_KEY")
-            .and_then(|v| v.as_str())
-            .map(|s| s.to_string());
+        OpenAIAuth::from_auth_json(&auth_data)
+            .map(Some)
+            .map_err(|e| UnifiedAuthError::ConfigError(e.to_string()))
+    }
 
-        let has_tokens = auth_data.get("tokens").is_some();
+    /// Re-read API keys configured as `file:` or `env:` references, so rotated secrets are
+    /// used from now on; returns the providers whose key changed
+    ///
+    /// Cached tokens of a changed provider are dropped.
+    pub async fn refresh_secret_references(&self) -> Result<Vec<ProviderType>, UnifiedAuthError> {
+        let reloaded_openai = match self.config.is_provider_enabled(&ProviderType::OpenAI) {
+            true => self.load_openai_auth().await?,
+            false => None,
+        };
 
-        let mode = if has_tokens && api_key.is_none() {
-            "ChatGPT".to_string()
-        } else {
-            "ApiKey".to_string()
+        let mut changed = Vec::new();
+        {
+            let mut providers = self.providers.write().await;
+            if let (Some(AuthProvider::OpenAI(current)), Some(reloaded)) =
+                (providers.get_mut(&ProviderType::OpenAI), reloaded_openai)
+            {
+                if current.api_key != reloaded.api_key {
+                    *current = reloaded;
+                    changed.push(ProviderType::OpenAI);
+                }
+            }
+            if let Some(AuthProvider::Claude(claude_auth)) = providers.get_mut(&ProviderType::Claude) {
+                if claude_auth.refresh_api_key().map_err(UnifiedAuthError::ClaudeError)? {
+                    changed.push(ProviderType::Claude);
+                }
+            }
+        }
+
+        for provider_type in &changed {
+            tracing::info!(provider = provider_type.id(), "Picked up a rotated API key");
+            self.invalidate_cached_tokens(provider_type).await;
+        }
+        Ok(changed)
+    }
+
+    /// Mirror an OpenAI key into unified_auth.json and the legacy auth.json
+    ///
+    /// Only runs when unified_auth.json has `maintain_legacy_auth_json` set; other
+    /// fields of both files are preserved. Returns whether anything was written.
+    async fn sync_legacy_auth_json(&self, openai_auth: &OpenAIAuth) -> Result<bool, UnifiedAuthError> {
+        let unified_file = self.codex_home.join("unified_auth.json");
+        let mut unified: serde_json::Value = match tokio::fs::read_to_string(&unified_file).await {
+            Ok(content) => serde_json::from_str(&content)?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(false),
+            Err(e) => return Err(e.into()),
+        };
+        let Some(fields) = unified.as_object_mut() else {
+            return Ok(false);
         };
+        if fields.get("maintain_legacy_auth_json").and_then(serde_json::Value::as_bool) != Some(true) {
+            return Ok(false);
+        }
 
-        Ok(Some(OpenAIAuth {
-            mode,
-            api_key,
-            has_tokens,
-        }))
+        let api_key = openai_auth.api_key.clone().map_or(serde_json::Value::Null, serde_json::Value::String);
+        if let Some(openai) = fields.get_mut("providers")
+            .and_then(|providers| providers.get_mut("openai"))
+            .and_then(serde_json::Value::as_object_mut)
+        {
+            openai.insert("api_key".to_string(), api_key);
+        }
+        fields.insert("last_updated".to_string(), Utc::now().to_rfc3339().into());
+        write_atomic_async(&unified_file, serde_json::to_string_pretty(&unified)?, 0o600).await?;
+
+        let auth_file = self.codex_home.join("auth.json");
+        let mut legacy: serde_json::Map<String, serde_json::Value> = match tokio::fs::read_to_string(&auth_file).await {
+            Ok(content) => serde_json::from_str(&content)?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => serde_json::Map::new(),
+            Err(e) => return Err(e.into()),
+        };
+        match &openai_auth.api_key {
+            Some(api_key) => {
+                legacy.insert("OPENAI_API_KEY".to_string(), api_key.clone().into());
+            }
+            None => {
+                legacy.remove("OPENAI_API_KEY");
+            }
+        }
+        write_atomic_async(&auth_file, serde_json::to_string_pretty(&legacy)?, 0o600).await?;
+
+        Ok(true)
     }
 
-    /// Refresh status for all providers
+    /// Refresh status for all providers, waiting for every probe to finish
+    ///
+    /// Providers are probed concurrently, `status_refresh_concurrency` at a time, so the
+    /// refresh takes about as long as the slowest probe. A probe that exceeds
+    /// `status_probe_timeout_ms` marks its provider unavailable without holding up the rest.
     pub async fn refresh_all_provider_status(&self) -> Result<(), UnifiedAuthError> {
-        let providers = self.providers.read().await;
+        let providers = self.providers.read().await.clone();
+        let mut statuses = Self::probe_all(&providers, self.probe_limits()).await;
+        for provider_type in self.config.disabled_providers() {
+            statuses.insert(provider_type.clone(), Self::disabled_status(provider_type));
+        }
+        *self.status_cache.write().await = statuses;
+        Ok(())
+    }
+
+    /// Concurrency and per-provider timeout for status probes
+    fn probe_limits(&self) -> (usize, std::time::Duration) {
+        (
+            self.config.status_refresh_concurrency.max(1),
+            std::time::Duration::from_millis(self.config.status_probe_timeout_ms),
+        )
+    }
+
+    async fn probe_all(
+        providers: &HashMap<ProviderType, AuthProvider>,
+        (concurrency, probe_timeout): (usize, std::time::Duration),
+    ) -> HashMap<ProviderType, ProviderStatus> {
+        let mut pending = providers.iter().map(|(provider_type, provider)| (provider_type.clone(), provider.clone()));
+        let mut probes = tokio::task::JoinSet::new();
         let mut status_updates = HashMap::new();
 
-        for (provider_type, provider) in providers.iter() {
-            let status = self.get_provider_status(provider).await;
-            status_updates.insert(provider_type.clone(), status);
+        loop {
+            while probes.len() < concurrency {
+                let Some((provider_type, provider)) = pending.next() else {
+                    break;
+                };
+                probes.spawn(async move {
+                    let status = match tokio::time::timeout(probe_timeout, Self::get_provider_status(&provider)).await {
+                        Ok(status) => status,
+                        Err(_) => Self::timed_out_status(&provider_type, probe_timeout),
+                    };
+                    (provider_type, status)
+                });
+            }
+
+            match probes.join_next().await {
+                Some(Ok((provider_type, status))) => {
+                    status_updates.insert(provider_type, status);
+                }
+                Some(Err(e)) => tracing::warn!(error = %e, "Provider status probe did not complete"),
+                None => break,
+            }
         }
+        status_updates
+    }
 
-        *self.status_cache.write().await = status_updates;
-        Ok(())
+    fn timed_out_status(provider_type: &ProviderType, probe_timeout: std::time::Duration) -> ProviderStatus {
+        ProviderStatus {
+            provider_type: provider_type.clone(),
+            available: false,
+            authenticated: false,
+            subscription_tier: None,
+            quota_remaining: None,
+            quota_reset_in: None,
+            rate_limit_status: RateLimitStatus {
+                requests_remaining: None,
+                tokens_remaining: None,
+                reset_time: None,
+                current_usage: 0.0,
+            },
+            last_verified: Some(Utc::now()),
+            error_message: Some(format!("Status check timed out after {}ms", probe_timeout.as_millis())),
+            stale: false,
+            disabled: false,
+        }
+    }
+
+    fn disabled_status(provider_type: ProviderType) -> ProviderStatus {
+        ProviderStatus {
+            provider_type,
+            available: false,
+            authenticated: false,
+            subscription_tier: None,
+            quota_remaining: None,
+            quota_reset_in: None,
+            rate_limit_status: RateLimitStatus {
+                requests_remaining: None,
+                tokens_remaining: None,
+                reset_time: None,
+                current_usage: 0.0,
+            },
+            last_verified: None,
+            error_message: Some("disabled".to_string()),
+            stale: false,
+            disabled: true,
+        }
+    }
+
+    /// Re-probe every provider on a background task, at most one at a time
+    fn spawn_status_refresh(&self) {
+        if self.status_refresh_in_flight.swap(true, Ordering::SeqCst) {
+            return;
+        }
+
+        let providers = Arc::clone(&self.providers);
+        let status_cache = Arc::clone(&self.status_cache);
+        let in_flight = Arc::clone(&self.status_refresh_in_flight);
+        let limits = self.probe_limits();
+        tokio::spawn(async move {
+            let snapshot = providers.read().await.clone();
+            let updates = Self::probe_all(&snapshot, limits).await;
+
+            // Providers removed while probing must not reappear in the cache
+            let current = providers.read().await;
+            let mut cache = status_cache.write().await;
+            for (provider_type, status) in updates {
+                if current.contains_key(&provider_type) {
+                    cache.insert(provider_type, status);
+                }
+            }
+            in_flight.store(false, Ordering::SeqCst);
+        });
     }
 
     /// Get status for a specific provider
-    async fn get_provider_status(&self, provider: &AuthProvider) -> ProviderStatus {
+    async fn get_provider_status(provider: &AuthProvider) -> ProviderStatus {
         match provider {
             AuthProvider::Claude(claude_auth) => {
                 let mut status = ProviderStatus {
@@ -458,6 +1486,7 @@ impl UnifiedAuthManager {
                     authenticated: false,
                     subscription_tier: claude_auth.subscription_tier.clone(),
                     quota_remaining: None,
+                    quota_reset_in: None,
                     rate_limit_status: RateLimitStatus {
                         requests_remaining: None,
                         tokens_remaining: None,
@@ -466,6 +1495,8 @@ impl UnifiedAuthManager {
                     },
                     last_verified: Some(Utc::now()),
                     error_message: None,
+                    stale: false,
+                    disabled: false,
                 };
 
                 // Test authentication
@@ -476,6 +1507,7 @@ impl UnifiedAuthManager {
                         // Get quota information
                         if let Ok(remaining) = claude_auth.get_remaining_quota().await {
                             status.quota_remaining = Some(remaining);
+                            status.quota_reset_in = claude_auth.quota_reset_in().await;
                         }
                     }
                     Err(e) => {
@@ -492,6 +1524,7 @@ impl UnifiedAuthManager {
                     authenticated: openai_auth.api_key.is_some() || openai_auth.has_tokens,
                     subscription_tier: None,
                     quota_remaining: None,
+                    quota_reset_in: None,
                     rate_limit_status: RateLimitStatus {
                         requests_remaining: None,
                         tokens_remaining: None,
@@ -500,34 +1533,248 @@ impl UnifiedAuthManager {
                     },
                     last_verified: Some(Utc::now()),
                     error_message: None,
+                    stale: false,
+                    disabled: false,
                 }
             }
         }
     }
 
     /// Get authentication token from optimal provider
+    ///
+    /// Tokens are served from the authentication cache when possible, before any selection
+    /// runs; a miss selects a provider, resolves its token and caches it until the token (or
+    /// the cache TTL) expires. Claude OAuth tokens leave the cache one `refresh_skew` early,
+    /// so an expiring token is refreshed rather than served. Hits and misses both count
+    /// towards the provider's auth outcomes.
     pub async fn get_auth_token(&self, context: &AuthContext) -> Result<String, UnifiedAuthError> {
+        let start = std::time::Instant::now();
+        let signature = self.cache_signature(context);
+
+        if let Some((provider_type, token)) = self.cached_token(context, &signature).await {
+            self.performance.record_auth_outcome(provider_type.id(), true).await;
+            self.record_token_metrics(start, 0).await;
+            return Ok(token);
+        }
+
         let provider = self.get_optimal_provider(context).await?;
-        
-        match provider {
+        let provider_key = Self::provider_cache_key(&provider);
+
+        let resolved = match provider {
             AuthProvider::Claude(claude_auth) => {
                 claude_auth.get_token().await
-                    .map_err(|e| UnifiedAuthError::ClaudeError(e))
+                    .map(|token| {
+                        let skew = chrono::Duration::from_std(claude_auth.refresh_skew)
+                            .unwrap_or_else(|_| chrono::Duration::zero());
+                        let expires_at = claude_auth.current_oauth_tokens()
+                            .map(|tokens| tokens.expires_at - skew);
+                        (token, expires_at)
+                    })
+                    .map_err(UnifiedAuthError::ClaudeError)
             }
             AuthProvider::OpenAI(openai_auth) => {
                 openai_auth.api_key
+                    .map(|token| (token, None))
                     .ok_or(UnifiedAuthError::NoValidToken)
             }
+        };
+        self.performance.record_auth_outcome(provider_key, resolved.is_ok()).await;
+        let (token, expires_at) = resolved?;
+
+        // API keys don't expire, so they are bounded by the cache TTL alone
+        let expires_at = expires_at.unwrap_or(DateTime::<Utc>::MAX_UTC);
+        self.auth_cache.put(provider_key, &signature, &token, expires_at, None).await;
+        self.record_token_metrics(start, 1).await;
+
+        Ok(token)
+    }
+
+    /// Cached token for `signature` from a provider that selection could still return
+    ///
+    /// A session only reuses tokens of the provider it is pinned to; an unpinned session
+    /// misses so the selection that pins it runs. Otherwise the most recently cached entry
+    /// wins, so a context that fell back to another provider keeps using it. Providers no
+    /// longer loaded, in cooldown, disabled or no longer authenticated are skipped.
+    async fn cached_token(&self, context: &AuthContext, signature: &str) -> Option<(ProviderType, String)> {
+        let candidates = match context.session_id.as_deref() {
+            Some(session_id) => vec![self.pinned_provider(session_id).await?],
+            None => vec![ProviderType::Claude, ProviderType::OpenAI],
+        };
+
+        let mut best: Option<(ProviderType, CachedAuth)> = None;
+        for provider_type in candidates {
+            if !self.providers.read().await.contains_key(&provider_type)
+                || self.is_cooling_down(&provider_type).await
+            {
+                continue;
+            }
+            if let Some(status) = self.status_cache.read().await.get(&provider_type) {
+                if status.disabled || !status.available || !status.authenticated {
+                    continue;
+                }
+            }
+            let Some(cached) = self.auth_cache.get(provider_type.id(), signature).await else {
+                continue;
+            };
+            if best.as_ref().is_none_or(|(_, current)| cached.cached_at > current.cached_at) {
+                best = Some((provider_type, cached));
+            }
+        }
+        best.map(|(provider_type, cached)| (provider_type, cached.token))
+    }
+
+    /// Drop every cached token for a provider (after refresh, re-login or logout)
+    pub async fn invalidate_cached_tokens(&self, provider_type: &ProviderType) {
+        self.auth_cache.remove_provider(provider_type.id()).await;
+    }
+
+    /// Check the Claude subscription with the server and act on a downgrade at once
+    ///
+    /// Cached status keeps the tier seen at the last refresh, so a lapsed Max subscription
+    /// would otherwise keep winning selection. When the fresh answer is inactive or a lower
+    /// tier, the cached tier is replaced, cached tokens are dropped, a warning is audited
+    /// and selection for `context` is re-run. With `enable_fallback`, a subscription that is
+    /// no longer active also marks Claude unauthenticated until the next status refresh.
+    pub async fn check_claude_subscription(
+        &self,
+        context: &AuthContext,
+    ) -> Result<Option<SubscriptionDowngrade>, UnifiedAuthError> {
+        let claude_auth = match self.get_specific_provider(ProviderType::Claude).await? {
+            AuthProvider::Claude(claude_auth) => claude_auth,
+            AuthProvider::OpenAI(_) => return Err(UnifiedAuthError::ProviderNotAvailable(ProviderType::Claude)),
+        };
+
+        let current_tier = match claude_auth.verify_subscription().await {
+            Ok(subscription) => subscription.active.then_some(subscription.tier),
+            Err(ClaudeAuthError::SubscriptionExpired) => None,
+            Err(e) => return Err(UnifiedAuthError::ClaudeError(e)),
+        };
+        let previous_tier = self
+            .status_cache
+            .read()
+            .await
+            .get(&ProviderType::Claude)
+            .and_then(|status| status.subscription_tier.clone())
+            .or(claude_auth.subscription_tier.clone());
+
+        // Record the fresh tier so the next refresh doesn't restore the cached one
+        if let Some(AuthProvider::Claude(stored)) = self.providers.write().await.get_mut(&ProviderType::Claude) {
+            stored.subscription_tier = current_tier.clone();
+        }
+        let downgraded = subscription_rank(current_tier.as_deref()) < subscription_rank(previous_tier.as_deref());
+        if let Some(status) = self.status_cache.write().await.get_mut(&ProviderType::Claude) {
+            status.subscription_tier = current_tier.clone();
+            status.last_verified = Some(Utc::now());
+            if downgraded && current_tier.is_none() && self.config.enable_fallback {
+                status.authenticated = false;
+                status.error_message = Some("Claude subscription is no longer active".to_string());
+            }
+        }
+        if !downgraded {
+            return Ok(None);
+        }
+
+        self.invalidate_cached_tokens(&ProviderType::Claude).await;
+        tracing::warn!(
+            previous_tier = previous_tier.as_deref().unwrap_or("none"),
+            current_tier = current_tier.as_deref().unwrap_or("inactive"),
+            "Claude subscription downgraded"
+        );
+        let _ = log_audit_event(AuditEvent {
+            timestamp: Utc::now(),
+            event_type: AuthEventType::SubscriptionDowngraded,
+            user_id: None,
+            session_id: None,
+            client_id: None,
+            ip_address: None,
+            user_agent: None,
+            success: true,
+            error_message: None,
+            metadata: serde_json::json!({
+                "provider": ProviderType::Claude.id(),
+                "previous_tier": previous_tier,
+                "current_tier": current_tier,
+            }),
+            severity: Severity::Warning,
+        });
+
+        let reselected = self.explain_selection(context).await.selected;
+        Ok(Some(SubscriptionDowngrade { previous_tier, current_tier, reselected }))
+    }
+
+    /// Log out of a provider, removing it and any cached tokens
+    pub async fn logout(&self, provider_type: &ProviderType) {
+        self.remove_provider(provider_type).await;
+    }
+
+    /// Performance coordinator tracking token lookups
+    pub fn performance(&self) -> Arc<PerformanceCoordinator> {
+        Arc::clone(&self.performance)
+    }
+
+    fn provider_cache_key(provider: &AuthProvider) -> &'static str {
+        match provider {
+            AuthProvider::Claude(_) => "claude",
+            AuthProvider::OpenAI(_) => "openai",
         }
     }
 
+    /// Stable signature of everything that influences which credential is used: the parts
+    /// of the context, the strategy and the provider the schedule prefers right now
+    ///
+    /// Changing the strategy or entering another schedule window changes the signature, so
+    /// tokens cached under the previous selection are no longer served.
+    fn cache_signature(&self, context: &AuthContext) -> String {
+        format!(
+            "{:?}|{:?}|{:?}|{}|{:?}|{:?}",
+            context.task_type,
+            context.priority,
+            context.user_preference,
+            context.required_features.join(","),
+            self.strategy,
+            self.scheduled_provider(),
+        )
+    }
+
+    async fn record_token_metrics(&self, start: std::time::Instant, network_requests: u32) {
+        let cache_stats = self.auth_cache.get_stats().await;
+        let metrics = PerformanceMetrics {
+            authentication_time: start.elapsed(),
+            token_refresh_time: std::time::Duration::from_millis(0),
+            cache_hit_rate: cache_stats.hit_rate,
+            memory_usage: 0,
+            concurrent_agents: 0,
+            network_requests,
+            timestamp: std::time::SystemTime::now(),
+        };
+        self.performance.record_metrics(metrics).await;
+    }
+
+    /// Append a completed request to the usage ledger in codex_home
+    pub async fn record_completed_request(&self, entry: &UsageEntry) -> Result<(), UnifiedAuthError> {
+        UsageLedger::new(&self.codex_home).append(entry).await
+    }
+
     /// Record usage for learning
     pub async fn record_usage(&self, provider_type: ProviderType, context: &AuthContext, success: bool, response_time_ms: f64) {
+        let task_type_key = format!("{:?}", context.task_type);
+        self.performance.record_request(
+            provider_type.clone(),
+            &task_type_key,
+            success,
+            std::time::Duration::from_secs_f64(response_time_ms.max(0.0) / 1000.0),
+        ).await;
+
+        if let Some(sink) = &self.config.telemetry_sink {
+            sink.record(&SelectionTelemetry::new(&provider_type, &context.task_type, success, response_time_ms));
+        }
+
         if !self.config.preference_learning_enabled {
             return;
         }
 
         let mut usage_stats = self.usage_stats.write().await;
+        let usage_stats = &mut *usage_stats;
         
         // Update provider usage
         let provider_usage = usage_stats.provider_usage.entry(provider_type.clone()).or_insert_with(|| ProviderUsage {
@@ -555,7 +1802,6 @@ impl UnifiedAuthManager {
 
         // Update task type preferences (only for successful requests)
         if success {
-            let task_type_key = format!("{:?}", context.task_type);
             usage_stats.task_type_preferences.insert(task_type_key, provider_type.clone());
         }
 
@@ -572,6 +1818,28 @@ impl UnifiedAuthManager {
         }
     }
 
+    /// Debit the tokens an agent's completed Claude request consumed
+    ///
+    /// Call once the response is complete, with the actual usage rather than the
+    /// estimate the allocation was made from. Overages follow the quota manager's
+    /// [`OveragePolicy`](super::claude::OveragePolicy).
+    pub async fn record_agent_request_usage(&self, agent_id: &str, tokens_used: u64) -> Result<UsageDebit, UnifiedAuthError> {
+        let quota_manager = match self.providers.read().await.get(&ProviderType::Claude) {
+            Some(AuthProvider::Claude(claude_auth)) => claude_auth.quota_manager.clone(),
+            _ => return Err(UnifiedAuthError::ProviderNotAvailable(ProviderType::Claude)),
+        };
+        let debit = quota_manager
+            .write()
+            .await
+            .record_request_usage(agent_id, tokens_used)
+            .map_err(UnifiedAuthError::ClaudeError)?;
+
+        if let Some(usage) = self.usage_stats.write().await.provider_usage.get_mut(&ProviderType::Claude) {
+            usage.tokens_used += tokens_used;
+        }
+        Ok(debit)
+    }
+
     /// Load usage statistics from disk
     async fn load_usage_stats(&self) -> Result<(), UnifiedAuthError> {
         let stats_file = self.codex_home.join("auth_usage_stats.json");
@@ -586,6 +1854,39 @@ impl UnifiedAuthManager {
         Ok(())
     }
 
+    /// Drain agents and persist state before the process exits
+    ///
+    /// New Claude allocations are refused immediately. Active agents get up to `grace` to
+    /// release their quota before the rest is reclaimed; the final quota accounting and
+    /// usage statistics are then written to `codex_home` and the audit log is flushed.
+    pub async fn shutdown(&self, grace: std::time::Duration) -> Result<ShutdownReport, UnifiedAuthError> {
+        let claude = match self.providers.read().await.get(&ProviderType::Claude) {
+            Some(AuthProvider::Claude(claude_auth)) => Some(claude_auth.clone()),
+            _ => None,
+        };
+
+        let mut report = ShutdownReport::default();
+        if let Some(claude_auth) = claude {
+            report.agents = claude_auth.drain_agents(grace).await;
+            if !report.agents.reclaimed.is_empty() {
+                let reclaimed: Vec<&str> = report.agents.reclaimed.iter().map(|quota| quota.agent_id.as_str()).collect();
+                tracing::warn!(agents = ?reclaimed, "Reclaimed quota from agents still running at shutdown");
+            }
+
+            let state_file = self.codex_home.join(QUOTA_STATE_FILE);
+            let snapshot = claude_auth.quota_snapshot().await;
+            write_atomic_async(&state_file, serde_json::to_string_pretty(&snapshot)?, 0o600).await?;
+            report.quota_state_file = Some(state_file);
+        }
+
+        self.save_usage_stats().await?;
+        if let Err(e) = flush_audit_log() {
+            tracing::warn!(error = %e, "Failed to flush the audit log at shutdown");
+        }
+
+        Ok(report)
+    }
+
     /// Save usage statistics to disk
     async fn save_usage_stats(&self) -> Result<(), UnifiedAuthError> {
         let stats_file = self.codex_home.join("auth_usage_stats.json");
@@ -596,8 +1897,25 @@ impl UnifiedAuthManager {
     }
 
     /// Get current provider status
+    ///
+    /// Never probes a provider on the caller's path. Entries older than
+    /// `cache_status_duration_seconds` are returned with `stale` set and a background
+    /// refresh is started; use [`refresh_all_provider_status`](Self::refresh_all_provider_status)
+    /// to wait for fresh results.
     pub async fn get_provider_status_summary(&self) -> HashMap<ProviderType, ProviderStatus> {
-        self.status_cache.read().await.clone()
+        let window = chrono::Duration::seconds(self.config.cache_status_duration_seconds as i64);
+        let now = Utc::now();
+        let mut summary = self.status_cache.read().await.clone();
+
+        let mut any_stale = false;
+        for status in summary.values_mut().filter(|status| !status.disabled) {
+            status.stale = status.last_verified.is_none_or(|verified| now - verified > window);
+            any_stale |= status.stale;
+        }
+        if any_stale {
+            self.spawn_status_refresh();
+        }
+        summary
     }
 
     /// Switch strategy
@@ -605,17 +1923,52 @@ impl UnifiedAuthManager {
         self.strategy = strategy;
     }
 
+    /// Replace the time-of-day provider schedule; `None` leaves selection to the strategy
+    pub fn set_provider_schedule(&mut self, schedule: Option<ProviderSchedule>) {
+        self.config.provider_schedule = schedule;
+    }
+
+    /// Read the time from `clock` when evaluating the provider schedule
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
     /// Add or update provider
+    ///
+    /// OpenAI key changes are also written through to auth.json when the migration left
+    /// the legacy-auth shim on.
     pub async fn add_provider(&self, provider_type: ProviderType, provider: AuthProvider) {
+        if !self.config.is_provider_enabled(&provider_type) {
+            tracing::info!(provider = %provider_type, "Not adding disabled provider");
+            return;
+        }
+        self.invalidate_cached_tokens(&provider_type).await;
+        if let AuthProvider::OpenAI(openai_auth) = &provider {
+            if let Err(e) = self.sync_legacy_auth_json(openai_auth).await {
+                tracing::warn!(error = %e, "Failed to write OpenAI credentials through to auth.json");
+            }
+        }
         self.providers.write().await.insert(provider_type.clone(), provider);
         // Refresh status for the new provider
         let _ = self.refresh_all_provider_status().await;
     }
 
-    /// Remove provider
+    /// Remove provider, unpinning the sessions pinned to it
     pub async fn remove_provider(&self, provider_type: &ProviderType) {
         self.providers.write().await.remove(provider_type);
         self.status_cache.write().await.remove(provider_type);
+        self.cooldowns.write().await.remove(provider_type);
+        self.session_pins.write().await.retain(|_, pin| pin.provider != *provider_type);
+        self.invalidate_cached_tokens(provider_type).await;
+    }
+
+    /// Loaded Claude provider, if any
+    pub async fn claude_auth(&self) -> Option<ClaudeAuth> {
+        match self.providers.read().await.get(&ProviderType::Claude) {
+            Some(AuthProvider::Claude(claude_auth)) => Some(claude_auth.clone()),
+            _ => None,
+        }
     }
 }
 
@@ -624,6 +1977,13 @@ impl UnifiedAuthManager {
 pub enum UnifiedAuthError {
     #[error("No suitable provider available")]
     NoSuitableProvider,
+
+    /// Nothing has been set up yet, as on a new machine
+    #[error("No authentication provider is configured; run `code auth login --provider claude` or `code auth login --provider openai` to set one up")]
+    NoProvidersConfigured,
+
+    #[error("No provider supports the required features: {}", missing_features.join(", "))]
+    NoCapableProvider { missing_features: Vec<String> },
     
     #[error("Provider not available: {0:?}")]
     ProviderNotAvailable(ProviderType),
@@ -644,11 +2004,97 @@ pub enum UnifiedAuthError {
     ConfigError(String),
 }
 
+impl UnifiedAuthError {
+    /// Whether repeating the same call later may succeed
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            UnifiedAuthError::ClaudeError(e) => e.is_retryable(),
+            _ => false,
+        }
+    }
+
+    /// Whether the provider refused the credentials, so only a new login helps
+    pub fn is_auth_fatal(&self) -> bool {
+        match self {
+            UnifiedAuthError::ClaudeError(e) => e.is_auth_fatal(),
+            UnifiedAuthError::NoValidToken => true,
+            _ => false,
+        }
+    }
+
+    /// Wait requested by a throttled provider
+    pub fn retry_after(&self) -> Option<std::time::Duration> {
+        match self {
+            UnifiedAuthError::ClaudeError(e) => e.retry_after(),
+            _ => None,
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use super::super::claude::{ApiKeyFormat, ClaudeAuthMode};
+    use std::path::Path;
     use tempfile::tempdir;
 
+    async fn write_migrated_home(dir: &Path, maintain_legacy_auth_json: bool) {
+        let unified = serde_json::json!({
+            "version": "2.0",
+            "maintain_legacy_auth_json": maintain_legacy_auth_json,
+            "providers": {
+                "openai": { "type": "openai", "api_key": "sk-old", "enabled": true }
+            }
+        });
+        tokio::fs::write(dir.join("unified_auth.json"), unified.to_string()).await.unwrap();
+        tokio::fs::write(
+            dir.join("auth.json"),
+            r#"{"OPENAI_API_KEY": "sk-old", "_migration_version": "2.0"}"#,
+        ).await.unwrap();
+    }
+
+    fn openai_key(api_key: &str) -> AuthProvider {
+        AuthProvider::OpenAI(OpenAIAuth { mode: "ApiKey".to_string(), api_key: Some(api_key.to_string()), has_tokens: false })
+    }
+
+    async fn read_json(path: PathBuf) -> serde_json::Value {
+        serde_json::from_str(&tokio::fs::read_to_string(path).await.unwrap()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_openai_key_change_written_through_to_legacy_auth_json() {
+        let temp_dir = tempdir().unwrap();
+        write_migrated_home(temp_dir.path(), true).await;
+        let manager = UnifiedAuthManager::new(
+            temp_dir.path().to_path_buf(),
+            ProviderSelectionStrategy::PreferOpenAI
+        ).await.unwrap();
+
+        manager.add_provider(ProviderType::OpenAI, openai_key("sk-new")).await;
+
+        let legacy = read_json(temp_dir.path().join("auth.json")).await;
+        assert_eq!(legacy["OPENAI_API_KEY"], "sk-new");
+        assert_eq!(legacy["_migration_version"], "2.0");
+        let unified = read_json(temp_dir.path().join("unified_auth.json")).await;
+        assert_eq!(unified["providers"]["openai"]["api_key"], "sk-new");
+        assert_eq!(unified["providers"]["openai"]["type"], "openai");
+    }
+
+    #[tokio::test]
+    async fn test_legacy_auth_json_untouched_without_shim() {
+        let temp_dir = tempdir().unwrap();
+        write_migrated_home(temp_dir.path(), false).await;
+        let manager = UnifiedAuthManager::new(
+            temp_dir.path().to_path_buf(),
+            ProviderSelectionStrategy::PreferOpenAI
+        ).await.unwrap();
+
+        manager.add_provider(ProviderType::OpenAI, openai_key("sk-new")).await;
+
+        let legacy = read_json(temp_dir.path().join("auth.json")).await;
+        assert_eq!(legacy["OPENAI_API_KEY"], "sk-old");
+    }
+
     #[tokio::test]
     async fn test_unified_auth_manager_creation() {
         let temp_dir = tempdir().unwrap();
@@ -685,6 +2131,7 @@ mod tests {
             priority: Priority::Medium,
             user_preference: None,
             required_features: Vec::new(),
+            session_id: None,
         };
 
         // Test user choice strategy
@@ -697,6 +2144,930 @@ mod tests {
         assert!(matches!(provider, AuthProvider::OpenAI(_)));
     }
 
+    #[tokio::test]
+    async fn test_auth_token_served_from_cache() {
+        let temp_dir = tempdir().unwrap();
+
+        let auth_file = temp_dir.path().join("auth.json");
+        tokio::fs::write(&auth_file, r#"{"OPENAI_API_KEY": "sk-test"}"#).await.unwrap();
+
+        let manager = UnifiedAuthManager::new(
+            temp_dir.path().to_path_buf(),
+            ProviderSelectionStrategy::UserChoice(ProviderType::OpenAI)
+        ).await.unwrap();
+
+        let context = AuthContext {
+            task_type: TaskType::CodeGeneration,
+            estimated_tokens: Some(1000),
+            priority: Priority::Medium,
+            user_preference: None,
+            required_features: Vec::new(),
+            session_id: None,
+        };
+
+        let first = manager.get_auth_token(&context).await.unwrap();
+
+        let start = std::time::Instant::now();
+        let second = manager.get_auth_token(&context).await.unwrap();
+        let elapsed = start.elapsed();
+
+        assert_eq!(first, second);
+        assert!(elapsed.as_millis() < 10, "cached lookup took {} ms", elapsed.as_millis());
+
+        let stats = manager.auth_cache.get_stats().await;
+        assert_eq!(stats.cache_hits, 1);
+        assert_eq!(stats.cache_misses, 1);
+
+        let metrics = manager.performance().get_average_performance(1).await.unwrap();
+        assert_eq!(metrics.cache_hit_rate, 0.5);
+    }
+
+    #[tokio::test]
+    async fn test_cache_hit_counts_as_success_until_strategy_changes() {
+        let temp_dir = tempdir().unwrap();
+        tokio::fs::write(temp_dir.path().join("auth.json"), r#"{"OPENAI_API_KEY": "sk-test"}"#).await.unwrap();
+        let mut manager = UnifiedAuthManager::new(
+            temp_dir.path().to_path_buf(),
+            ProviderSelectionStrategy::UserChoice(ProviderType::OpenAI)
+        ).await.unwrap();
+        let context = crate::auth::convenience::code_generation_context(Some(1000));
+
+        manager.get_auth_token(&context).await.unwrap();
+        assert_eq!(manager.get_auth_token(&context).await.unwrap(), "sk-test");
+        let outcomes = manager.performance().get_auth_outcomes().await;
+        assert_eq!(outcomes["openai"].successes, 2);
+        assert_eq!(outcomes["openai"].failures, 0);
+
+        // Claude isn't loaded, so the user's new choice can't be served; the cached OpenAI
+        // token must not answer in its place
+        manager.set_strategy(ProviderSelectionStrategy::UserChoice(ProviderType::Claude));
+        assert!(manager.get_auth_token(&context).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_token_requests_feed_auth_attempt_counters() {
+        let temp_dir = tempdir().unwrap();
+        let manager = UnifiedAuthManager::new(
+            temp_dir.path().to_path_buf(),
+            ProviderSelectionStrategy::UserChoice(ProviderType::OpenAI)
+        ).await.unwrap();
+        let context = crate::auth::convenience::code_generation_context(Some(1000));
+
+        manager.add_provider(ProviderType::OpenAI, openai_key("sk-test")).await;
+        manager.get_auth_token(&context).await.unwrap();
+        manager.get_auth_token(&context).await.unwrap();
+
+        // ChatGPT tokens count as signed in, but yield no API key to hand out
+        let chatgpt = OpenAIAuth { mode: "ChatGPT".to_string(), api_key: None, has_tokens: true };
+        manager.add_provider(ProviderType::OpenAI, AuthProvider::OpenAI(chatgpt)).await;
+        assert!(manager.get_auth_token(&context).await.is_err());
+
+        let exposition = manager.performance().render_prometheus().await;
+        assert!(exposition.contains("code_auth_attempts_total{provider=\"openai\",outcome=\"success\"} 2"));
+        assert!(exposition.contains("code_auth_attempts_total{provider=\"openai\",outcome=\"failure\"} 1"));
+    }
+
+    #[tokio::test]
+    async fn test_cached_claude_token_expires_with_refresh_skew() {
+        let temp_dir = tempdir().unwrap();
+        let manager = UnifiedAuthManager::new(
+            temp_dir.path().to_path_buf(),
+            ProviderSelectionStrategy::PreferClaude
+        ).await.unwrap();
+        // Outside the 60s skew, so the token is served and cached
+        manager.add_provider(ProviderType::Claude, claude_max(Default::default(), chrono::Duration::seconds(90))).await;
+        let context = crate::auth::convenience::code_generation_context(Some(1000));
+
+        manager.get_auth_token(&context).await.unwrap();
+
+        let signature = manager.cache_signature(&context);
+        let cached = manager.auth_cache.get("claude", &signature).await.unwrap();
+        let token_expiry = Utc::now() + chrono::Duration::seconds(90);
+        assert!(cached.expires_at <= token_expiry - chrono::Duration::seconds(60));
+    }
+
+    #[tokio::test]
+    async fn test_logout_clears_cached_token() {
+        let temp_dir = tempdir().unwrap();
+
+        let auth_file = temp_dir.path().join("auth.json");
+        tokio::fs::write(&auth_file, r#"{"OPENAI_API_KEY": "sk-test"}"#).await.unwrap();
+
+        let manager = UnifiedAuthManager::new(
+            temp_dir.path().to_path_buf(),
+            ProviderSelectionStrategy::UserChoice(ProviderType::OpenAI)
+        ).await.unwrap();
+
+        let context = AuthContext {
+            task_type: TaskType::CodeGeneration,
+            estimated_tokens: Some(1000),
+            priority: Priority::Medium,
+            user_preference: None,
+            required_features: Vec::new(),
+            session_id: None,
+        };
+
+        manager.get_auth_token(&context).await.unwrap();
+        let signature = manager.cache_signature(&context);
+        assert_eq!(manager.auth_cache.get_stats().await.cache_size, 1);
+
+        manager.logout(&ProviderType::OpenAI).await;
+
+        assert!(manager.auth_cache.get("openai", &signature).await.is_none());
+        assert!(manager.get_auth_token(&context).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_status_summary_served_from_cache_until_refreshed() {
+        let temp_dir = tempdir().unwrap();
+        tokio::fs::write(temp_dir.path().join("auth.json"), r#"{"OPENAI_API_KEY": "sk-test"}"#).await.unwrap();
+        let manager = UnifiedAuthManager::new(
+            temp_dir.path().to_path_buf(),
+            ProviderSelectionStrategy::PreferOpenAI
+        ).await.unwrap();
+
+        let first = manager.get_provider_status_summary().await[&ProviderType::OpenAI].clone();
+        assert!(!first.stale);
+        for _ in 0..20 {
+            let again = manager.get_provider_status_summary().await[&ProviderType::OpenAI].clone();
+            assert_eq!(again.last_verified, first.last_verified);
+            assert!(!again.stale);
+        }
+        assert!(!manager.status_refresh_in_flight.load(Ordering::SeqCst));
+
+        tokio::time::sleep(std::time::Duration::from_millis(5)).await;
+        manager.refresh_all_provider_status().await.unwrap();
+        let refreshed = manager.get_provider_status_summary().await[&ProviderType::OpenAI].clone();
+        assert!(refreshed.last_verified > first.last_verified);
+    }
+
+    /// Claude OAuth provider whose token refresh hangs on `token_endpoint`
+    fn hung_claude(token_endpoint: String) -> AuthProvider {
+        use crate::claude_auth::secure_claude_auth::ClaudeAuthConfig;
+
+        claude_max(
+            ClaudeAuthConfig { token_endpoint, offline_mode: false, ..ClaudeAuthConfig::default() },
+            chrono::Duration::seconds(5),
+        )
+    }
+
+    /// Claude Max OAuth provider whose access token expires after `expires_in`
+    fn claude_max(
+        auth_config: crate::claude_auth::secure_claude_auth::ClaudeAuthConfig,
+        expires_in: chrono::Duration,
+    ) -> AuthProvider {
+        use super::super::claude::{ClaudeQuotaManager, ClaudeTokenData, DEFAULT_REFRESH_SKEW};
+        use crate::performance::token_optimization::TokenOptimizer;
+
+        AuthProvider::Claude(ClaudeAuth {
+            mode: ClaudeAuthMode::MaxSubscription,
+            subscription_tier: Some("max".to_string()),
+            api_key: None,
+            api_key_ref: None,
+            oauth_tokens: Some(ClaudeTokenData {
+                access_token: "expiring-token".into(),
+                refresh_token: Some("refresh-token".into()),
+                expires_at: Utc::now() + expires_in,
+                subscription_tier: "max".to_string(),
+                token_type: "Bearer".to_string(),
+                scope: vec!["api".to_string()],
+            }),
+            refreshed_oauth_tokens: Default::default(),
+            auth_file: None,
+            client: reqwest::Client::new(),
+            quota_manager: Arc::new(RwLock::new(ClaudeQuotaManager::default())),
+            auth_config,
+            refresh_skew: DEFAULT_REFRESH_SKEW,
+            token_optimizer: Arc::new(TokenOptimizer::new()),
+            clock: system_clock(),
+        })
+    }
+
+    #[tokio::test]
+    async fn test_hung_providers_time_out_concurrently_without_blocking_others() {
+        use wiremock::{matchers::method, Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(200).set_delay(std::time::Duration::from_secs(10)))
+            .mount(&server)
+            .await;
+
+        let temp_dir = tempdir().unwrap();
+        let config = UnifiedAuthConfig {
+            status_refresh_concurrency: 4,
+            status_probe_timeout_ms: 300,
+            ..UnifiedAuthConfig::default()
+        };
+        let manager = UnifiedAuthManager::with_config(
+            temp_dir.path().to_path_buf(),
+            ProviderSelectionStrategy::PreferOpenAI,
+            config
+        ).await.unwrap();
+        let token_endpoint = format!("{}/v1/oauth/token", server.uri());
+        manager.add_provider(ProviderType::OpenAI, openai_key("sk-fast")).await;
+        manager.add_provider(ProviderType::Claude, hung_claude(token_endpoint.clone())).await;
+        manager.add_provider(ProviderType::Custom("claude-work".to_string()), hung_claude(token_endpoint)).await;
+
+        let started = std::time::Instant::now();
+        manager.refresh_all_provider_status().await.unwrap();
+        let elapsed = started.elapsed();
+
+        // Two hung probes run side by side, so the refresh costs one timeout rather than two
+        assert!(elapsed >= std::time::Duration::from_millis(300), "refresh returned before the timeout: {elapsed:?}");
+        assert!(elapsed < std::time::Duration::from_millis(550), "hung probes ran sequentially: {elapsed:?}");
+
+        let statuses = manager.status_cache.read().await.clone();
+        let openai = &statuses[&ProviderType::OpenAI];
+        assert!(openai.authenticated);
+        assert!(openai.error_message.is_none());
+        for provider_type in [ProviderType::Claude, ProviderType::Custom("claude-work".to_string())] {
+            let status = &statuses[&provider_type];
+            assert!(!status.available);
+            assert_eq!(status.error_message.as_deref(), Some("Status check timed out after 300ms"));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_subscription_downgrade_moves_selection_off_claude() {
+        use crate::claude_auth::secure_claude_auth::ClaudeAuthConfig;
+        use wiremock::{matchers::{method, path}, Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/v1/subscription"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({"tier": "free", "active": false})))
+            .mount(&server)
+            .await;
+
+        let temp_dir = tempdir().unwrap();
+        let manager = UnifiedAuthManager::new(
+            temp_dir.path().to_path_buf(),
+            ProviderSelectionStrategy::BestSubscription
+        ).await.unwrap();
+        let auth_config = ClaudeAuthConfig {
+            subscription_endpoint: format!("{}/v1/subscription", server.uri()),
+            offline_mode: false,
+            ..ClaudeAuthConfig::default()
+        };
+        manager.add_provider(ProviderType::OpenAI, openai_key("sk-fallback")).await;
+        manager.add_provider(ProviderType::Claude, claude_max(auth_config, chrono::Duration::hours(1))).await;
+
+        let context = AuthContext {
+            task_type: TaskType::LongRunning,
+            estimated_tokens: None,
+            priority: Priority::High,
+            user_preference: None,
+            required_features: vec![],
+            session_id: None,
+        };
+        // The cached Max tier still wins until the subscription is checked
+        assert_eq!(manager.explain_selection(&context).await.selected, Some(ProviderType::Claude));
+
+        let downgrade = manager.check_claude_subscription(&context).await.unwrap().unwrap();
+        assert_eq!(downgrade.previous_tier.as_deref(), Some("max"));
+        assert_eq!(downgrade.current_tier, None);
+        assert_eq!(downgrade.reselected, Some(ProviderType::OpenAI));
+
+        let status = manager.status_cache.read().await[&ProviderType::Claude].clone();
+        assert!(!status.authenticated);
+        assert_eq!(status.subscription_tier, None);
+
+        // A status refresh doesn't bring the cached Max tier back
+        manager.refresh_all_provider_status().await.unwrap();
+        assert_eq!(manager.explain_selection(&context).await.selected, Some(ProviderType::OpenAI));
+        assert_eq!(manager.check_claude_subscription(&context).await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn test_disabled_claude_is_never_selected() {
+        let temp_dir = tempdir().unwrap();
+        tokio::fs::write(temp_dir.path().join("auth.json"), r#"{"OPENAI_API_KEY": "sk-openai"}"#).await.unwrap();
+        let claude_file = temp_dir.path().join("claude_auth.json");
+        tokio::fs::write(&claude_file, r#"{"api_key": "sk-ant-disabled"}"#).await.unwrap();
+
+        let config = UnifiedAuthConfig {
+            enabled_providers: Some(vec![ProviderType::OpenAI]),
+            ..UnifiedAuthConfig::default()
+        };
+        let manager = UnifiedAuthManager::with_config(
+            temp_dir.path().to_path_buf(),
+            ProviderSelectionStrategy::PreferClaude,
+            config.clone(),
+        ).await.unwrap();
+        manager.add_provider(ProviderType::Claude, openai_key("ignored")).await;
+
+        let context = AuthContext {
+            task_type: TaskType::CodeGeneration,
+            estimated_tokens: None,
+            priority: Priority::Medium,
+            user_preference: None,
+            required_features: vec![],
+            session_id: None,
+        };
+        let selected = manager.get_optimal_provider(&context).await.unwrap();
+        assert_eq!(UnifiedAuthManager::provider_type_of(&selected), ProviderType::OpenAI);
+        let explanation = manager.explain_selection(&context).await;
+        assert_eq!(explanation.selected, Some(ProviderType::OpenAI));
+        let claude = explanation.candidates.iter().find(|c| c.provider_type == ProviderType::Claude).unwrap();
+        assert_eq!(claude.rejection.as_deref(), Some("disabled"));
+
+        let status = manager.get_provider_status_summary().await[&ProviderType::Claude].clone();
+        assert!(status.disabled && !status.available && !status.stale);
+        assert_eq!(status.error_message.as_deref(), Some("disabled"));
+        // Credentials are kept for when Claude is enabled again
+        assert!(claude_file.exists());
+
+        let pinned = UnifiedAuthManager::with_config(
+            temp_dir.path().to_path_buf(),
+            ProviderSelectionStrategy::UserChoice(ProviderType::Claude),
+            config,
+        ).await.unwrap();
+        assert!(pinned.get_optimal_provider(&context).await.is_err());
+
+        let none_enabled = UnifiedAuthConfig { enabled_providers: Some(Vec::new()), ..UnifiedAuthConfig::default() };
+        assert!(matches!(none_enabled.validate(), Err(UnifiedAuthError::ConfigError(_))));
+    }
+
+    #[derive(Debug, Default)]
+    struct RecordingSink {
+        records: std::sync::Mutex<Vec<SelectionTelemetry>>,
+    }
+
+    impl TelemetrySink for RecordingSink {
+        fn record(&self, record: &SelectionTelemetry) {
+            self.records.lock().unwrap().push(record.clone());
+        }
+    }
+
+    fn agent_context() -> AuthContext {
+        AuthContext {
+            task_type: TaskType::AgentExecution,
+            estimated_tokens: Some(1_000),
+            priority: Priority::Medium,
+            user_preference: Some(ProviderType::Custom("acme-corp-claude".to_string())),
+            required_features: vec![],
+            session_id: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_telemetry_records_are_anonymized() {
+        use super::super::telemetry::LatencyBucket;
+
+        let sink = Arc::new(RecordingSink::default());
+        let temp_dir = tempdir().unwrap();
+        let config = UnifiedAuthConfig {
+            telemetry_sink: Some(sink.clone()),
+            ..UnifiedAuthConfig::default()
+        };
+        let manager = UnifiedAuthManager::with_config(
+            temp_dir.path().to_path_buf(),
+            ProviderSelectionStrategy::Adaptive,
+            config
+        ).await.unwrap();
+
+        manager.record_usage(ProviderType::Claude, &agent_context(), true, 450.0).await;
+        manager.record_usage(ProviderType::Custom("acme-corp-claude".to_string()), &agent_context(), false, 12_000.0).await;
+
+        let records = sink.records.lock().unwrap().clone();
+        assert_eq!(
+            records,
+            vec![
+                SelectionTelemetry {
+                    task_type: "AgentExecution".to_string(),
+                    provider: "claude".to_string(),
+                    success: true,
+                    latency: LatencyBucket::Under1S,
+                },
+                SelectionTelemetry {
+                    task_type: "AgentExecution".to_string(),
+                    provider: "custom".to_string(),
+                    success: false,
+                    latency: LatencyBucket::Over10S,
+                },
+            ]
+        );
+        for record in &records {
+            let json = serde_json::to_value(record).unwrap();
+            let mut fields: Vec<_> = json.as_object().unwrap().keys().cloned().collect();
+            fields.sort();
+            assert_eq!(fields, ["latency", "provider", "success", "task_type"]);
+            assert!(!json.to_string().contains("acme"));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_no_telemetry_without_sink() {
+        assert!(UnifiedAuthConfig::default().telemetry_sink.is_none());
+
+        let temp_dir = tempdir().unwrap();
+        let manager = UnifiedAuthManager::new(temp_dir.path().to_path_buf(), ProviderSelectionStrategy::Adaptive)
+            .await
+            .unwrap();
+        manager.record_usage(ProviderType::Claude, &agent_context(), true, 50.0).await;
+
+        assert!(manager.config.telemetry_sink.is_none());
+        // The sink is runtime-only and never written to config files
+        let config = serde_json::to_value(&manager.config).unwrap();
+        assert!(config.get("telemetry_sink").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_status_past_freshness_window_is_stale_and_refreshed_in_background() {
+        let temp_dir = tempdir().unwrap();
+        tokio::fs::write(temp_dir.path().join("auth.json"), r#"{"OPENAI_API_KEY": "sk-test"}"#).await.unwrap();
+        let config = UnifiedAuthConfig {
+            cache_status_duration_seconds: 0,
+            ..UnifiedAuthConfig::default()
+        };
+        let manager = UnifiedAuthManager::with_config(
+            temp_dir.path().to_path_buf(),
+            ProviderSelectionStrategy::PreferOpenAI,
+            config
+        ).await.unwrap();
+
+        tokio::time::sleep(std::time::Duration::from_millis(5)).await;
+        let stale = manager.get_provider_status_summary().await[&ProviderType::OpenAI].clone();
+        assert!(stale.stale);
+
+        let deadline = std::time::Instant::now() + std::time::Duration::from_secs(1);
+        loop {
+            let cached = manager.status_cache.read().await[&ProviderType::OpenAI].last_verified;
+            if cached > stale.last_verified {
+                break;
+            }
+            assert!(std::time::Instant::now() < deadline, "background refresh never landed");
+            tokio::time::sleep(std::time::Duration::from_millis(5)).await;
+        }
+    }
+
+    #[test]
+    fn test_error_classification_delegates_to_provider() {
+        let throttled = UnifiedAuthError::ClaudeError(ClaudeAuthError::RateLimited {
+            retry_after: Some(std::time::Duration::from_secs(3)),
+        });
+        assert!(throttled.is_retryable());
+        assert_eq!(throttled.retry_after(), Some(std::time::Duration::from_secs(3)));
+
+        assert!(UnifiedAuthError::ClaudeError(ClaudeAuthError::InvalidCredentials).is_auth_fatal());
+        assert!(UnifiedAuthError::NoValidToken.is_auth_fatal());
+
+        let config = UnifiedAuthError::ConfigError("bad".to_string());
+        assert!(!config.is_retryable() && !config.is_auth_fatal());
+    }
+
+    async fn write_claude_api_key(codex_home: &Path) {
+        ClaudeAuth::setup_with_api_key_using(
+            codex_home,
+            "sk-ant-REDACTED",
+            &ApiKeyFormat::default(),
+            |_| async { Ok(true) },
+        )
+        .await
+        .unwrap();
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_rotated_secret_files_are_picked_up_on_refresh() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let temp_dir = tempdir().unwrap();
+        let secrets = tempdir().unwrap();
+        let write_secret = |name: &str, value: &str| {
+            let path = secrets.path().join(name);
+            std::fs::write(&path, value).unwrap();
+            std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o600)).unwrap();
+            path
+        };
+        let openai_key = write_secret("openai_api_key", "sk-openai-first\n");
+        let claude_key = write_secret("claude_api_key", "sk-ant-first\n");
+        let auth_json = serde_json::json!({"OPENAI_API_KEY": format!("file:{}", openai_key.display())});
+        let claude_json = serde_json::json!({"api_key": format!("file:{}", claude_key.display())});
+        tokio::fs::write(temp_dir.path().join("auth.json"), auth_json.to_string()).await.unwrap();
+        tokio::fs::write(temp_dir.path().join("claude_auth.json"), claude_json.to_string()).await.unwrap();
+
+        let manager = UnifiedAuthManager::new(
+            temp_dir.path().to_path_buf(),
+            ProviderSelectionStrategy::PreferClaude
+        ).await.unwrap();
+        let keys = || async {
+            let providers = manager.providers.read().await;
+            let openai = match &providers[&ProviderType::OpenAI] {
+                AuthProvider::OpenAI(auth) => auth.api_key.clone(),
+                _ => None,
+            };
+            let claude = match &providers[&ProviderType::Claude] {
+                AuthProvider::Claude(auth) => auth.api_key.as_ref().map(|key| key.expose_secret().clone()),
+                _ => None,
+            };
+            (openai, claude)
+        };
+        assert_eq!(keys().await, (Some("sk-openai-first".to_string()), Some("sk-ant-first".to_string())));
+        assert!(manager.refresh_secret_references().await.unwrap().is_empty());
+
+        write_secret("claude_api_key", "sk-ant-rotated\n");
+        assert_eq!(manager.refresh_secret_references().await.unwrap(), vec![ProviderType::Claude]);
+        assert_eq!(keys().await.1.as_deref(), Some("sk-ant-rotated"));
+    }
+
+    fn image_context() -> AuthContext {
+        AuthContext {
+            task_type: TaskType::Analysis,
+            estimated_tokens: None,
+            priority: Priority::Medium,
+            user_preference: None,
+            required_features: vec!["images".to_string()],
+            session_id: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_image_requirement_filters_out_claude() {
+        let temp_dir = tempdir().unwrap();
+        tokio::fs::write(temp_dir.path().join("auth.json"), r#"{"OPENAI_API_KEY": "sk-test"}"#).await.unwrap();
+        write_claude_api_key(temp_dir.path()).await;
+
+        let mut manager = UnifiedAuthManager::new(
+            temp_dir.path().to_path_buf(),
+            ProviderSelectionStrategy::PreferClaude
+        ).await.unwrap();
+
+        let provider = manager.get_optimal_provider(&image_context()).await.unwrap();
+        assert!(matches!(provider, AuthProvider::OpenAI(_)));
+
+        // Even an explicit choice can't pick a provider that lacks a required feature
+        manager.set_strategy(ProviderSelectionStrategy::UserChoice(ProviderType::Claude));
+        let result = manager.get_optimal_provider(&image_context()).await;
+        assert!(matches!(result, Err(UnifiedAuthError::NoCapableProvider { .. })));
+
+        // Without the requirement Claude is selectable again
+        manager.set_strategy(ProviderSelectionStrategy::PreferClaude);
+        let mut context = image_context();
+        context.required_features.clear();
+        let provider = manager.get_optimal_provider(&context).await.unwrap();
+        assert!(matches!(provider, AuthProvider::Claude(_)));
+    }
+
+    #[tokio::test]
+    async fn test_shutdown_drains_agents_and_persists_state() {
+        let temp_dir = tempdir().unwrap();
+        write_claude_api_key(temp_dir.path()).await;
+        let manager = UnifiedAuthManager::new(
+            temp_dir.path().to_path_buf(),
+            ProviderSelectionStrategy::PreferClaude
+        ).await.unwrap();
+        let Some(AuthProvider::Claude(claude_auth)) = manager.providers.read().await.get(&ProviderType::Claude).cloned() else {
+            panic!("Claude provider not loaded");
+        };
+
+        claude_auth.allocate_agent_quota("finishes", 1_000).await.unwrap();
+        claude_auth.allocate_agent_quota("hangs", 2_000).await.unwrap();
+        let finishing = claude_auth.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+            finishing.release_agent_quota("finishes").await.unwrap();
+        });
+
+        let report = manager.shutdown(std::time::Duration::from_millis(200)).await.unwrap();
+        assert_eq!(report.agents.released, ["finishes"]);
+        assert_eq!(report.agents.reclaimed.len(), 1);
+        assert_eq!(report.agents.reclaimed[0].agent_id, "hangs");
+        assert!(matches!(
+            claude_auth.allocate_agent_quota("late", 1_000).await,
+            Err(ClaudeAuthError::ShuttingDown)
+        ));
+
+        let state_file = report.quota_state_file.unwrap();
+        let snapshot: super::super::claude::QuotaSnapshot =
+            serde_json::from_str(&tokio::fs::read_to_string(&state_file).await.unwrap()).unwrap();
+        assert!(snapshot.active_agents.is_empty());
+        assert_eq!(snapshot.current_usage, 0);
+        assert!(temp_dir.path().join("auth_usage_stats.json").exists());
+    }
+
+    #[tokio::test]
+    async fn test_schedule_flips_provider_at_window_boundaries() {
+        use crate::configuration::ProviderWindow;
+        use crate::security::MockClock;
+
+        let temp_dir = tempdir().unwrap();
+        tokio::fs::write(temp_dir.path().join("auth.json"), r#"{"OPENAI_API_KEY": "sk-test"}"#).await.unwrap();
+        write_claude_api_key(temp_dir.path()).await;
+
+        // 09:00-18:00 at UTC-05:00 is 14:00-23:00 UTC
+        let at = |time: &str| chrono::NaiveTime::parse_from_str(time, "%H:%M").unwrap();
+        let utc = |timestamp: &str| DateTime::parse_from_rfc3339(timestamp).unwrap().with_timezone(&Utc);
+        let clock = MockClock::new(utc("2025-03-03T13:59:59Z"));
+        let mut manager = UnifiedAuthManager::new(
+            temp_dir.path().to_path_buf(),
+            ProviderSelectionStrategy::PreferOpenAI
+        ).await.unwrap().with_clock(Arc::new(clock.clone()));
+        manager.set_provider_schedule(Some(
+            ProviderSchedule::new(vec![ProviderWindow::new(at("09:00"), at("18:00"), ProviderType::Claude)])
+                .with_utc_offset_minutes(-5 * 60),
+        ));
+
+        let mut context = image_context();
+        context.required_features.clear();
+        let selected = |provider: AuthProvider| UnifiedAuthManager::provider_type_of(&provider);
+
+        // Outside the window the static strategy applies
+        assert_eq!(selected(manager.get_optimal_provider(&context).await.unwrap()), ProviderType::OpenAI);
+
+        clock.set(utc("2025-03-03T14:00:00Z"));
+        assert_eq!(selected(manager.get_optimal_provider(&context).await.unwrap()), ProviderType::Claude);
+        assert_eq!(manager.explain_selection(&context).await.deciding_factor, SelectionFactor::Schedule);
+
+        clock.set(utc("2025-03-03T22:59:59Z"));
+        assert_eq!(selected(manager.get_optimal_provider(&context).await.unwrap()), ProviderType::Claude);
+
+        clock.set(utc("2025-03-03T23:00:00Z"));
+        assert_eq!(selected(manager.get_optimal_provider(&context).await.unwrap()), ProviderType::OpenAI);
+
+        // An explicit choice outranks the schedule
+        clock.set(utc("2025-03-04T15:00:00Z"));
+        manager.set_strategy(ProviderSelectionStrategy::UserChoice(ProviderType::OpenAI));
+        assert_eq!(selected(manager.get_optimal_provider(&context).await.unwrap()), ProviderType::OpenAI);
+    }
+
+    #[tokio::test]
+    async fn test_cached_token_follows_schedule_window() {
+        use crate::configuration::ProviderWindow;
+        use crate::security::MockClock;
+
+        let temp_dir = tempdir().unwrap();
+        tokio::fs::write(temp_dir.path().join("auth.json"), r#"{"OPENAI_API_KEY": "sk-test"}"#).await.unwrap();
+        write_claude_api_key(temp_dir.path()).await;
+
+        let at = |time: &str| chrono::NaiveTime::parse_from_str(time, "%H:%M").unwrap();
+        let utc = |timestamp: &str| DateTime::parse_from_rfc3339(timestamp).unwrap().with_timezone(&Utc);
+        let clock = MockClock::new(utc("2025-03-03T10:00:00Z"));
+        let mut manager = UnifiedAuthManager::new(
+            temp_dir.path().to_path_buf(),
+            ProviderSelectionStrategy::PreferOpenAI
+        ).await.unwrap().with_clock(Arc::new(clock.clone()));
+        manager.set_provider_schedule(Some(ProviderSchedule::new(vec![
+            ProviderWindow::new(at("09:00"), at("18:00"), ProviderType::Claude),
+        ])));
+        let context = crate::auth::convenience::code_generation_context(Some(1000));
+
+        let in_window = manager.get_auth_token(&context).await.unwrap();
+        assert_ne!(in_window, "sk-test");
+
+        // Leaving the window selects again rather than serving the cached Claude token
+        clock.set(utc("2025-03-03T18:00:00Z"));
+        assert_eq!(manager.get_auth_token(&context).await.unwrap(), "sk-test");
+    }
+
+    #[tokio::test]
+    async fn test_selection_explanation_matches_selected_provider() {
+        let temp_dir = tempdir().unwrap();
+        tokio::fs::write(temp_dir.path().join("auth.json"), r#"{"OPENAI_API_KEY": "sk-test"}"#).await.unwrap();
+        write_claude_api_key(temp_dir.path()).await;
+
+        let mut manager = UnifiedAuthManager::new(
+            temp_dir.path().to_path_buf(),
+            ProviderSelectionStrategy::Adaptive
+        ).await.unwrap();
+
+        {
+            let mut stats = manager.usage_stats.write().await;
+            stats.task_type_preferences.insert("CodeGeneration".to_string(), ProviderType::OpenAI);
+            stats.success_rates.insert(ProviderType::OpenAI, 0.9);
+            stats.success_rates.insert(ProviderType::Claude, 0.5);
+            stats.total_requests = 7;
+        }
+
+        let context = AuthContext {
+            task_type: TaskType::CodeGeneration,
+            estimated_tokens: Some(500),
+            priority: Priority::Medium,
+            user_preference: None,
+            required_features: Vec::new(),
+            session_id: None,
+        };
+
+        let strategies = [
+            (ProviderSelectionStrategy::Adaptive, SelectionFactor::LearnedPreference),
+            (ProviderSelectionStrategy::CostOptimized, SelectionFactor::Cost),
+            (ProviderSelectionStrategy::PreferClaude, SelectionFactor::StrategyPreference),
+        ];
+        for (strategy, factor) in strategies {
+            manager.set_strategy(strategy);
+            let chosen = UnifiedAuthManager::provider_type_of(&manager.get_optimal_provider(&context).await.unwrap());
+            let explanation = manager.explain_selection(&context).await;
+
+            assert_eq!(explanation.selected.as_ref(), Some(&chosen));
+            assert_eq!(explanation.candidates[0].provider_type, chosen);
+            assert_eq!(explanation.deciding_factor, factor);
+        }
+
+        // Adaptive followed the seeded preference; cost optimization sent the small task to Claude
+        manager.set_strategy(ProviderSelectionStrategy::CostOptimized);
+        let explanation = manager.explain_selection(&context).await;
+        assert_eq!(explanation.selected, Some(ProviderType::Claude));
+        assert_eq!(explanation.candidates[1].success_rate, Some(0.9));
+
+        // Explaining never counts as usage
+        assert_eq!(manager.usage_stats.read().await.total_requests, 7);
+
+        manager.set_strategy(ProviderSelectionStrategy::PreferClaude);
+        let explanation = manager.explain_selection(&image_context()).await;
+        assert_eq!(explanation.selected, Some(ProviderType::OpenAI));
+        assert_eq!(explanation.deciding_factor, SelectionFactor::CapabilityMatch);
+        assert!(explanation.candidates[1].rejection.as_ref().unwrap().contains("images"));
+    }
+
+    #[tokio::test]
+    async fn test_session_stays_pinned_until_provider_dies() {
+        async fn selected(manager: &UnifiedAuthManager, context: &AuthContext) -> ProviderType {
+            UnifiedAuthManager::provider_type_of(&manager.get_optimal_provider(context).await.unwrap())
+        }
+
+        let temp_dir = tempdir().unwrap();
+        tokio::fs::write(temp_dir.path().join("auth.json"), r#"{"OPENAI_API_KEY": "sk-test"}"#).await.unwrap();
+        write_claude_api_key(temp_dir.path()).await;
+
+        let manager = UnifiedAuthManager::new(
+            temp_dir.path().to_path_buf(),
+            ProviderSelectionStrategy::Adaptive
+        ).await.unwrap();
+
+        let context = AuthContext {
+            task_type: TaskType::CodeGeneration,
+            estimated_tokens: None,
+            priority: Priority::Medium,
+            user_preference: None,
+            required_features: Vec::new(),
+            session_id: Some("conversation-1".to_string()),
+        };
+
+        manager.usage_stats.write().await.task_type_preferences.insert("CodeGeneration".to_string(), ProviderType::OpenAI);
+        assert_eq!(selected(&manager, &context).await, ProviderType::OpenAI);
+        assert_eq!(manager.pinned_provider("conversation-1").await, Some(ProviderType::OpenAI));
+
+        // The adaptive selector now prefers Claude, but the session stays where it started
+        manager.usage_stats.write().await.task_type_preferences.insert("CodeGeneration".to_string(), ProviderType::Claude);
+        let unpinned = AuthContext { session_id: None, ..context.clone() };
+        assert_eq!(selected(&manager, &unpinned).await, ProviderType::Claude);
+        assert_eq!(selected(&manager, &context).await, ProviderType::OpenAI);
+
+        // Losing OpenAI entirely unpins the session, which then moves to Claude and is pinned there
+        manager.remove_provider(&ProviderType::OpenAI).await;
+        assert_eq!(manager.pinned_provider("conversation-1").await, None);
+        assert_eq!(selected(&manager, &context).await, ProviderType::Claude);
+        assert_eq!(manager.pinned_provider("conversation-1").await, Some(ProviderType::Claude));
+
+        assert_eq!(manager.unpin("conversation-1").await, Some(ProviderType::Claude));
+        assert_eq!(manager.pinned_provider("conversation-1").await, None);
+    }
+
+    #[tokio::test]
+    async fn test_idle_session_pins_expire() {
+        use crate::security::MockClock;
+
+        let temp_dir = tempdir().unwrap();
+        write_claude_api_key(temp_dir.path()).await;
+        let clock = Arc::new(MockClock::new(Utc::now()));
+        let manager = UnifiedAuthManager::new(
+            temp_dir.path().to_path_buf(),
+            ProviderSelectionStrategy::PreferClaude
+        ).await.unwrap().with_clock(clock.clone());
+
+        manager.pin_provider("abandoned", ProviderType::Claude).await;
+        manager.pin_provider("active", ProviderType::Claude).await;
+        clock.advance(chrono::Duration::hours(SESSION_PIN_IDLE_HOURS - 1));
+        let context = AuthContext {
+            task_type: TaskType::CodeGeneration,
+            estimated_tokens: None,
+            priority: Priority::Medium,
+            user_preference: None,
+            required_features: Vec::new(),
+            session_id: Some("active".to_string()),
+        };
+        manager.get_optimal_provider(&context).await.unwrap();
+
+        clock.advance(chrono::Duration::hours(2));
+        assert_eq!(manager.pinned_provider("abandoned").await, None);
+        assert_eq!(manager.pinned_provider("active").await, Some(ProviderType::Claude));
+
+        // The next pin drops the idle one for good
+        manager.pin_provider("new", ProviderType::Claude).await;
+        assert_eq!(manager.session_pins.read().await.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_recovered_provider_is_preferred_again_after_reprobe() {
+        use crate::security::MockClock;
+
+        let temp_dir = tempdir().unwrap();
+        tokio::fs::write(temp_dir.path().join("auth.json"), r#"{"OPENAI_API_KEY": "sk-test"}"#).await.unwrap();
+        write_claude_api_key(temp_dir.path()).await;
+
+        let clock = Arc::new(MockClock::new(Utc::now()));
+        let manager = UnifiedAuthManager::new(
+            temp_dir.path().to_path_buf(),
+            ProviderSelectionStrategy::PreferClaude
+        ).await.unwrap().with_clock(clock.clone());
+        let context = AuthContext {
+            task_type: TaskType::CodeGeneration,
+            estimated_tokens: None,
+            priority: Priority::Medium,
+            user_preference: None,
+            required_features: Vec::new(),
+            session_id: None,
+        };
+        let selected = |provider: AuthProvider| UnifiedAuthManager::provider_type_of(&provider);
+
+        manager.mark_provider_failed(ProviderType::Claude, "503 Service Unavailable").await;
+        assert_eq!(selected(manager.get_optimal_provider(&context).await.unwrap()), ProviderType::OpenAI);
+        let explanation = manager.explain_selection(&context).await;
+        assert_eq!(explanation.deciding_factor, SelectionFactor::Fallback);
+        assert!(explanation.reason.contains("cooling down after a failure: 503"), "{}", explanation.reason);
+
+        // Claude is healthy again, but isn't probed before the interval has passed
+        clock.advance(chrono::Duration::seconds(59));
+        assert_eq!(selected(manager.get_optimal_provider(&context).await.unwrap()), ProviderType::OpenAI);
+        assert!(manager.is_cooling_down(&ProviderType::Claude).await);
+
+        clock.advance(chrono::Duration::seconds(1));
+        assert_eq!(selected(manager.get_optimal_provider(&context).await.unwrap()), ProviderType::Claude);
+        assert!(!manager.is_cooling_down(&ProviderType::Claude).await);
+        assert!(manager.reprobe_cooled_down_providers().await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_selections_share_one_reprobe() {
+        use crate::security::MockClock;
+        use wiremock::{matchers::{method, path}, Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/v1/oauth/token"))
+            .respond_with(ResponseTemplate::new(503).set_delay(std::time::Duration::from_millis(100)))
+            .mount(&server)
+            .await;
+
+        let temp_dir = tempdir().unwrap();
+        let clock = Arc::new(MockClock::new(Utc::now()));
+        let manager = UnifiedAuthManager::new(
+            temp_dir.path().to_path_buf(),
+            ProviderSelectionStrategy::PreferClaude
+        ).await.unwrap().with_clock(clock.clone());
+        // Every probe of this provider is a token refresh, counted by its optimizer
+        let claude = hung_claude(format!("{}/v1/oauth/token", server.uri()));
+        let AuthProvider::Claude(claude_auth) = &claude else { unreachable!() };
+        let optimizer = Arc::clone(&claude_auth.token_optimizer);
+        manager.add_provider(ProviderType::OpenAI, openai_key("sk-test")).await;
+        manager.add_provider(ProviderType::Claude, claude).await;
+
+        manager.mark_provider_failed(ProviderType::Claude, "503 Service Unavailable").await;
+        clock.advance(chrono::Duration::seconds(60));
+        let context = AuthContext {
+            task_type: TaskType::CodeGeneration,
+            estimated_tokens: None,
+            priority: Priority::Medium,
+            user_preference: None,
+            required_features: Vec::new(),
+            session_id: None,
+        };
+        let probes_before = optimizer.get_stats().await.total_requests;
+        let selections = tokio::join!(
+            manager.get_optimal_provider(&context),
+            manager.get_optimal_provider(&context),
+            manager.get_optimal_provider(&context),
+            manager.get_optimal_provider(&context),
+        );
+
+        for selection in [selections.0, selections.1, selections.2, selections.3] {
+            assert_eq!(UnifiedAuthManager::provider_type_of(&selection.unwrap()), ProviderType::OpenAI);
+        }
+        assert_eq!(optimizer.get_stats().await.total_requests - probes_before, 1);
+        assert!(manager.is_cooling_down(&ProviderType::Claude).await);
+    }
+
+    #[tokio::test]
+    async fn test_no_capable_provider_lists_missing_features() {
+        let temp_dir = tempdir().unwrap();
+        write_claude_api_key(temp_dir.path()).await;
+
+        let manager = UnifiedAuthManager::new(
+            temp_dir.path().to_path_buf(),
+            ProviderSelectionStrategy::BestSubscription
+        ).await.unwrap();
+
+        let mut context = image_context();
+        context.required_features.push("streaming".to_string());
+        match manager.get_optimal_provider(&context).await {
+            Err(UnifiedAuthError::NoCapableProvider { missing_features }) => {
+                assert_eq!(missing_features, vec!["images".to_string()]);
+            }
+            other => panic!("expected NoCapableProvider, got {:?}", other),
+        }
+    }
+
     #[tokio::test]
     async fn test_usage_stats_recording() {
         let temp_dir = tempdir().unwrap();
@@ -715,6 +3086,7 @@ mod tests {
             priority: Priority::Medium,
             user_preference: None,
             required_features: Vec::new(),
+            session_id: None,
         };
 
         // Record usage
@@ -726,5 +3098,13 @@ mod tests {
         let openai_usage = &usage_stats.provider_usage[&ProviderType::OpenAI];
         assert_eq!(openai_usage.requests_count, 1);
         assert_eq!(openai_usage.success_count, 1);
+        drop(usage_stats);
+
+        let labeled = manager.performance().metrics_by_label().await;
+        let stats = &labeled[&crate::performance::RequestLabel {
+            provider: ProviderType::OpenAI,
+            task_type: "CodeGeneration".to_string(),
+        }];
+        assert_eq!(stats.successes, 1);
     }
 }
\ No newline at end of file
@@ -0,0 +1,223 @@
+//! # Usage Ledger
+//!
+//! Append-only record of every completed request in `usage_ledger.ndjson`, one JSON object
+//! per line, so token counts and cost can be replayed and reconciled against provider
+//! invoices. Entries are only ever appended; [`UsageLedger::summarize`] totals them by
+//! provider, UTC day or agent.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+
+use super::unified::{ProviderType, UnifiedAuthError};
+use crate::security::append_atomic_async;
+
+/// Ledger file name in codex_home
+pub const USAGE_LEDGER_FILE: &str = "usage_ledger.ndjson";
+
+/// One completed request
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UsageEntry {
+    pub timestamp: DateTime<Utc>,
+    pub provider: ProviderType,
+    pub model: String,
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    pub total_cost_usd: f64,
+    pub agent_id: Option<String>,
+    pub task_type: String,
+}
+
+/// How [`UsageLedger::summarize`] groups entries
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum UsageGroupBy {
+    Provider,
+    /// UTC calendar day, as `YYYY-MM-DD`
+    Day,
+    /// Requests made outside an agent are grouped under [`NO_AGENT`]
+    Agent,
+}
+
+/// Group key for entries without an agent id
+pub const NO_AGENT: &str = "none";
+
+/// Sums over a set of ledger entries
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct UsageTotals {
+    pub requests: u64,
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    pub total_cost_usd: f64,
+}
+
+impl UsageTotals {
+    fn add(&mut self, entry: &UsageEntry) {
+        self.requests += 1;
+        self.input_tokens += entry.input_tokens;
+        self.output_tokens += entry.output_tokens;
+        self.total_cost_usd += entry.total_cost_usd;
+    }
+}
+
+/// Ledger totals for a time range
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UsageSummary {
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+    pub group_by: UsageGroupBy,
+    /// Totals per group key, in key order
+    pub groups: BTreeMap<String, UsageTotals>,
+    pub total: UsageTotals,
+}
+
+/// Append-only usage ledger file
+#[derive(Debug, Clone)]
+pub struct UsageLedger {
+    path: PathBuf,
+}
+
+impl UsageLedger {
+    /// Ledger stored in `codex_home`
+    pub fn new(codex_home: &Path) -> Self {
+        Self::at(codex_home.join(USAGE_LEDGER_FILE))
+    }
+
+    /// Ledger stored at an explicit path
+    pub fn at(path: PathBuf) -> Self {
+        Self { path }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Append one completed request
+    pub async fn append(&self, entry: &UsageEntry) -> Result<(), UnifiedAuthError> {
+        let mut record = serde_json::to_vec(entry)?;
+        record.push(b'\n');
+        append_atomic_async(&self.path, record, 0o600).await?;
+        Ok(())
+    }
+
+    /// Every recorded entry, oldest first
+    ///
+    /// A line that doesn't parse, such as one cut short by a crash, is skipped with a warning.
+    pub async fn entries(&self) -> Result<Vec<UsageEntry>, UnifiedAuthError> {
+        let content = match tokio::fs::read_to_string(&self.path).await {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e.into()),
+        };
+
+        let mut entries = Vec::new();
+        for (index, line) in content.lines().enumerate().filter(|(_, line)| !line.trim().is_empty()) {
+            match serde_json::from_str(line) {
+                Ok(entry) => entries.push(entry),
+                Err(e) => tracing::warn!(line = index + 1, error = %e, "Skipping unreadable usage ledger entry"),
+            }
+        }
+        Ok(entries)
+    }
+
+    /// Totals of the entries in `time_range`, whose end is exclusive, grouped by `group_by`
+    pub async fn summarize(
+        &self,
+        time_range: Range<DateTime<Utc>>,
+        group_by: UsageGroupBy,
+    ) -> Result<UsageSummary, UnifiedAuthError> {
+        let mut groups: BTreeMap<String, UsageTotals> = BTreeMap::new();
+        let mut total = UsageTotals::default();
+
+        for entry in self.entries().await?.iter().filter(|entry| time_range.contains(&entry.timestamp)) {
+            let key = match group_by {
+                UsageGroupBy::Provider => entry.provider.id().to_string(),
+                UsageGroupBy::Day => entry.timestamp.date_naive().to_string(),
+                UsageGroupBy::Agent => entry.agent_id.clone().unwrap_or_else(|| NO_AGENT.to_string()),
+            };
+            groups.entry(key).or_default().add(entry);
+            total.add(entry);
+        }
+
+        Ok(UsageSummary { start: time_range.start, end: time_range.end, group_by, groups, total })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use tempfile::tempdir;
+
+    fn entry(timestamp: DateTime<Utc>, provider: ProviderType, tokens: (u64, u64), cost: f64, agent: Option<&str>) -> UsageEntry {
+        UsageEntry {
+            timestamp,
+            provider,
+            model: "test-model".to_string(),
+            input_tokens: tokens.0,
+            output_tokens: tokens.1,
+            total_cost_usd: cost,
+            agent_id: agent.map(str::to_string),
+            task_type: "CodeGeneration".to_string(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_summary_groups_and_sums_across_day_boundary() {
+        let temp_dir = tempdir().unwrap();
+        let ledger = UsageLedger::new(temp_dir.path());
+        let at = |day, hour, minute| Utc.with_ymd_and_hms(2026, 3, day, hour, minute, 0).unwrap();
+
+        let entries = [
+            entry(at(1, 23, 58), ProviderType::Claude, (1_000, 200), 0.25, Some("agent-1")),
+            entry(at(1, 23, 59), ProviderType::OpenAI, (500, 100), 0.5, None),
+            entry(at(2, 0, 1), ProviderType::Claude, (2_000, 400), 0.75, Some("agent-1")),
+            entry(at(2, 0, 2), ProviderType::Claude, (300, 50), 0.125, Some("agent-2")),
+            // Outside the summarized range
+            entry(at(3, 0, 0), ProviderType::OpenAI, (9_999, 9_999), 99.0, None),
+        ];
+        for entry in &entries {
+            ledger.append(entry).await.unwrap();
+        }
+        assert_eq!(ledger.entries().await.unwrap(), entries);
+
+        let range = at(1, 0, 0)..at(3, 0, 0);
+        let by_day = ledger.summarize(range.clone(), UsageGroupBy::Day).await.unwrap();
+        assert_eq!(by_day.groups.keys().collect::<Vec<_>>(), ["2026-03-01", "2026-03-02"]);
+        assert_eq!(
+            by_day.groups["2026-03-01"],
+            UsageTotals { requests: 2, input_tokens: 1_500, output_tokens: 300, total_cost_usd: 0.75 }
+        );
+        assert_eq!(
+            by_day.groups["2026-03-02"],
+            UsageTotals { requests: 2, input_tokens: 2_300, output_tokens: 450, total_cost_usd: 0.875 }
+        );
+        assert_eq!(
+            by_day.total,
+            UsageTotals { requests: 4, input_tokens: 3_800, output_tokens: 750, total_cost_usd: 1.625 }
+        );
+
+        let by_provider = ledger.summarize(range.clone(), UsageGroupBy::Provider).await.unwrap();
+        assert_eq!(by_provider.groups["claude"].requests, 3);
+        assert_eq!(by_provider.groups["claude"].total_cost_usd, 1.125);
+        assert_eq!(by_provider.groups["openai"].input_tokens, 500);
+        assert_eq!(by_provider.total, by_day.total);
+
+        let by_agent = ledger.summarize(range, UsageGroupBy::Agent).await.unwrap();
+        assert_eq!(by_agent.groups.keys().collect::<Vec<_>>(), ["agent-1", "agent-2", NO_AGENT]);
+        assert_eq!(by_agent.groups["agent-1"].output_tokens, 600);
+    }
+
+    #[tokio::test]
+    async fn test_truncated_last_line_is_skipped() {
+        let temp_dir = tempdir().unwrap();
+        let ledger = UsageLedger::new(temp_dir.path());
+        let first = entry(Utc::now(), ProviderType::Claude, (10, 5), 0.01, None);
+        ledger.append(&first).await.unwrap();
+        append_atomic_async(ledger.path(), r#"{"timestamp":"2026-03-"#, 0o600).await.unwrap();
+
+        assert_eq!(ledger.entries().await.unwrap(), vec![first]);
+    }
+}
@@ -3,16 +3,14 @@
 /// Command-line interface for managing the unified authentication system.
 /// Provides commands for migration, provider management, status checking, and troubleshooting.
 
-use clap::{Args, Parser, Subcommand};
+use clap::{Parser, Subcommand};
 use serde_json;
 use std::path::PathBuf;
 use tokio;
 
-// Import our authentication modules
-mod auth;
-use auth::{
+use claude_code_security::auth::{
     AuthenticationManager, ProviderType, ProviderSelectionStrategy, ClaudeSetupType,
-    convenience, migration::MigrationPhase,
+    convenience, format_reset_in, migration::MigrationPhase,
 };
 
 #[derive(Parser)]
@@ -265,9 +263,14 @@ enum TroubleshootAction {
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let cli = Cli::parse();
     
-    // Set up logging
+    // Set up logging; the auth modules report through tracing
     if cli.verbose {
-        env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("debug")).init();
+        tracing_subscriber::fmt()
+            .with_env_filter(
+                tracing_subscriber::EnvFilter::try_from_default_env()
+                    .unwrap_or_else(|_| tracing_subscriber::EnvFilter::new("debug")),
+            )
+            .init();
     }
     
     // Determine codex home
@@ -278,7 +281,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     });
     
     // Create output handler
-    let output = OutputHandler::new(cli.format, cli.verbose);
+    let output = OutputHandler::new(cli.format);
     
     // Execute command
     match cli.command {
@@ -305,19 +308,11 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
 struct OutputHandler {
     format: OutputFormat,
-    verbose: bool,
 }
 
 impl OutputHandler {
-    fn new(format: OutputFormat, verbose: bool) -> Self {
-        Self { format, verbose }
-    }
-    
-    fn print_status(&self, auth_manager: &AuthenticationManager, detailed: bool) {
-        // Implementation would print status in the specified format
-        println!("Authentication System Status");
-        println!("============================");
-        // ... implementation details
+    fn new(format: OutputFormat) -> Self {
+        Self { format }
     }
     
     fn print_json<T: serde::Serialize>(&self, data: &T) {
@@ -377,10 +372,18 @@ async fn execute_status_command(
                 
                 rows.push(vec![
                     format!("{:?}", provider_type),
-                    if provider_status.available { "✓" } else { "✗" }.to_string(),
+                    match (provider_status.disabled, provider_status.available) {
+                        (true, _) => "disabled",
+                        (false, true) => "✓",
+                        (false, false) => "✗",
+                    }.to_string(),
                     if provider_status.authenticated { "✓" } else { "✗" }.to_string(),
                     provider_status.subscription_tier.as_deref().unwrap_or("N/A").to_string(),
-                    provider_status.quota_remaining.map(|q| q.to_string()).unwrap_or("N/A".to_string()),
+                    match (provider_status.quota_remaining, provider_status.quota_reset_in) {
+                        (Some(quota), Some(reset_in)) => format!("{} ({})", quota, format_reset_in(reset_in)),
+                        (Some(quota), None) => quota.to_string(),
+                        (None, _) => "N/A".to_string(),
+                    },
                 ]);
             }
             
@@ -391,6 +394,7 @@ async fn execute_status_command(
         }
         OutputFormat::Simple => {
             println!("System Status: {}", if status.ready { "Ready" } else { "Not Ready" });
+            println!("Health: {}", status.health.overall);
             
             if status.migration_needed {
                 output.print_warning("Migration needed");
@@ -403,6 +407,11 @@ async fn execute_status_command(
                     }
                 }
                 
+                if provider_status.disabled {
+                    println!("- {:?}: Disabled", provider_type);
+                    continue;
+                }
+
                 let status_emoji = if provider_status.available && provider_status.authenticated {
                     "✓"
                 } else if provider_status.available {
@@ -421,6 +430,9 @@ async fn execute_status_command(
                     if let Some(quota) = provider_status.quota_remaining {
                         println!("   Quota remaining: {}", quota);
                     }
+                    if let Some(reset_in) = provider_status.quota_reset_in {
+                        println!("   Quota {}", format_reset_in(reset_in));
+                    }
                     if let Some(ref error) = provider_status.error_message {
                         println!("   Error: {}", error);
                     }
@@ -620,7 +632,7 @@ async fn execute_migration_command(
             }
         }
         
-        MigrationAction::Rollback { backup_id, force } => {
+        MigrationAction::Rollback { .. } => {
             output.print_error("Rollback functionality not yet implemented in CLI");
             // Implementation would handle rollback
         }
@@ -702,7 +714,8 @@ async fn execute_test_command(
         TestAction::Auth { provider } => {
             output.print_simple("Running authentication tests...");
             
-            let context = convenience::code_generation_context(Some(100));
+            let mut context = convenience::code_generation_context(Some(100));
+            context.user_preference = provider;
             
             match auth_manager.get_auth_token(&context).await {
                 Ok(_) => output.print_success("Authentication test passed"),
@@ -4,14 +4,26 @@
 //! including PKCE OAuth flows, encrypted token storage, audit logging, and session management.
 
 pub mod secure_claude_auth;
+pub mod timestamp;
 
 pub use secure_claude_auth::{
     SecureClaudeAuth,
     ClaudeAuthError,
     ClaudeAuthConfig,
     ClaudeTokenData,
+    ClaudeSessionToken,
+    ClaudeAuthMode,
     ClaudeSubscriptionInfo,
     AuthenticationResult,
+    CredentialCheck,
+    CredentialStatus,
+    ClaudeAuthHealth,
+    ImportedCredential,
+    ApiKeySubscriptionPolicy,
+    ProxyConfig,
+    TlsConfig,
+    IMPORTABLE_ENV_VARS,
+    OFFLINE_ENV_VAR,
 };
 
 use std::path::PathBuf;
@@ -47,5 +59,6 @@ pub fn production_claude_config(client_id: String, redirect_uri: String) -> Clau
         token_endpoint: "https://auth.anthropic.com/oauth/token".to_string(),
         subscription_endpoint: "https://api.anthropic.com/v1/subscription".to_string(),
         scopes: vec!["api".to_string(), "subscription".to_string()],
+        ..ClaudeAuthConfig::default()
     }
 }
\ No newline at end of file
@@ -0,0 +1,121 @@
+//! Tolerant parsing of timestamps from Anthropic endpoints
+//!
+//! Provider endpoints don't agree on a timestamp format. RFC3339 is expected; RFC2822
+//! dates and Unix epoch seconds or milliseconds, as numbers or numeric strings, are
+//! accepted with a warning so the format drift shows up in logs.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// Epoch values at or above this are read as milliseconds; as seconds it would be year 5138
+const EPOCH_MILLIS_THRESHOLD: i64 = 100_000_000_000;
+
+/// Format a timestamp was recognised in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TimestampFormat {
+    Rfc3339,
+    Rfc2822,
+    EpochSeconds,
+    EpochMillis,
+}
+
+/// A timestamp that could not be read in any accepted format
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum TimestampError {
+    #[error("Timestamp {0} is not RFC3339, RFC2822 or Unix epoch seconds/milliseconds")]
+    Unrecognized(String),
+
+    #[error("Epoch timestamp {0} is out of range")]
+    OutOfRange(i64),
+}
+
+/// Parse `value`, returning the UTC time and the format it was written in
+pub fn parse_timestamp(value: &serde_json::Value) -> Result<(DateTime<Utc>, TimestampFormat), TimestampError> {
+    match value {
+        serde_json::Value::String(s) => parse_timestamp_str(s),
+        serde_json::Value::Number(n) => match n.as_i64() {
+            Some(epoch) => from_epoch(epoch),
+            None => Err(TimestampError::Unrecognized(n.to_string())),
+        },
+        other => Err(TimestampError::Unrecognized(other.to_string())),
+    }
+}
+
+pub fn parse_timestamp_str(s: &str) -> Result<(DateTime<Utc>, TimestampFormat), TimestampError> {
+    let s = s.trim();
+    if let Ok(dt) = DateTime::parse_from_rfc3339(s) {
+        return Ok((dt.with_timezone(&Utc), TimestampFormat::Rfc3339));
+    }
+    if let Ok(dt) = DateTime::parse_from_rfc2822(s) {
+        return Ok((dt.with_timezone(&Utc), TimestampFormat::Rfc2822));
+    }
+    match s.parse::<i64>() {
+        Ok(epoch) => from_epoch(epoch),
+        Err(_) => Err(TimestampError::Unrecognized(format!("'{}'", s))),
+    }
+}
+
+/// [`parse_timestamp`] for a field of a provider response, logging anything but RFC3339
+///
+/// A missing field is `Ok(None)`; a present one that doesn't parse is an error for the
+/// caller to report rather than a made-up date.
+pub fn parse_response_timestamp(
+    data: &serde_json::Value,
+    field: &str,
+) -> Result<Option<DateTime<Utc>>, TimestampError> {
+    let Some(value) = data.get(field).filter(|value| !value.is_null()) else {
+        return Ok(None);
+    };
+    let (timestamp, format) = parse_timestamp(value)?;
+    if format != TimestampFormat::Rfc3339 {
+        tracing::warn!(field, ?format, raw = %value, "Normalized a non-RFC3339 timestamp");
+    }
+    Ok(Some(timestamp))
+}
+
+fn from_epoch(epoch: i64) -> Result<(DateTime<Utc>, TimestampFormat), TimestampError> {
+    let parsed = if epoch.abs() >= EPOCH_MILLIS_THRESHOLD {
+        DateTime::from_timestamp_millis(epoch).map(|dt| (dt, TimestampFormat::EpochMillis))
+    } else {
+        DateTime::from_timestamp(epoch, 0).map(|dt| (dt, TimestampFormat::EpochSeconds))
+    };
+    parsed.ok_or(TimestampError::OutOfRange(epoch))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_accepted_formats_normalize_to_utc() {
+        let expected = DateTime::parse_from_rfc3339("2025-03-01T12:00:00Z").unwrap().with_timezone(&Utc);
+        let cases = [
+            (json!("2025-03-01T12:00:00Z"), TimestampFormat::Rfc3339),
+            (json!("2025-03-01T13:00:00+01:00"), TimestampFormat::Rfc3339),
+            (json!("Sat, 01 Mar 2025 12:00:00 +0000"), TimestampFormat::Rfc2822),
+            (json!(1_740_830_400), TimestampFormat::EpochSeconds),
+            (json!("1740830400"), TimestampFormat::EpochSeconds),
+            (json!(1_740_830_400_000i64), TimestampFormat::EpochMillis),
+        ];
+        for (value, format) in cases {
+            assert_eq!(parse_timestamp(&value).unwrap(), (expected, format), "{value}");
+        }
+    }
+
+    #[test]
+    fn test_unparseable_timestamp_is_an_error() {
+        assert_eq!(
+            parse_timestamp(&json!("next tuesday")),
+            Err(TimestampError::Unrecognized("'next tuesday'".to_string()))
+        );
+        assert!(parse_timestamp(&json!(1.5)).is_err());
+        assert_eq!(parse_timestamp(&json!(i64::MAX)), Err(TimestampError::OutOfRange(i64::MAX)));
+
+        let data = json!({"quota_reset_date": "soon", "unset": null});
+        assert!(parse_response_timestamp(&data, "quota_reset_date").is_err());
+        assert_eq!(parse_response_timestamp(&data, "unset"), Ok(None));
+        assert_eq!(parse_response_timestamp(&data, "missing"), Ok(None));
+    }
+}
@@ -5,13 +5,19 @@
 //! and comprehensive quota management.
 
 use clap::{Parser, Subcommand, ValueEnum};
+#[cfg(feature = "codex-cli")]
 use codex_common::CliConfigOverrides;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
-use crate::claude_auth::{SecureClaudeAuth, ClaudeAuthConfig, ClaudeAuthError};
+use std::sync::Arc;
+use tokio::sync::RwLock;
+use crate::auth::claude::{AgentUsageSummary, ClaudeQuotaManager, AGENT_EXPIRY_WARNING};
+use crate::auth::{format_reset_in, SelectionExplanation};
+use crate::cli::verify::VerifyFormat;
+use crate::claude_auth::{SecureClaudeAuth, ClaudeAuthConfig, ClaudeAuthError, ClaudeAuthHealth, CredentialCheck, CredentialStatus, ImportedCredential, IMPORTABLE_ENV_VARS};
+use crate::configuration::{active_profile_home, ProviderType, ValidationResult};
 
 /// Authentication provider types
-#[derive(Debug, Clone, ValueEnum, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, ValueEnum, Serialize, Deserialize)]
 pub enum AuthProvider {
     /// OpenAI provider (ChatGPT OAuth or API key)
     #[value(name = "openai")]
@@ -34,9 +40,67 @@ impl std::fmt::Display for AuthProvider {
     }
 }
 
+impl AuthProvider {
+    /// Configured provider this selects; `None` for `auto`
+    pub fn provider_type(&self) -> Option<ProviderType> {
+        match self {
+            AuthProvider::OpenAI => Some(ProviderType::OpenAI),
+            AuthProvider::Claude => Some(ProviderType::Claude),
+            AuthProvider::Auto => None,
+        }
+    }
+
+    /// CLI value for a configured provider; custom providers have none and can only be
+    /// chosen through the configuration file
+    pub fn from_provider_type(provider_type: &ProviderType) -> Option<Self> {
+        match provider_type {
+            ProviderType::OpenAI => Some(AuthProvider::OpenAI),
+            ProviderType::Claude => Some(AuthProvider::Claude),
+            ProviderType::Custom(_) => None,
+        }
+    }
+}
+
+/// Providers `auth test` checks
+#[derive(Debug, Clone, ValueEnum, Serialize, Deserialize)]
+pub enum TestTarget {
+    #[value(name = "openai")]
+    OpenAI,
+    #[value(name = "claude")]
+    Claude,
+    /// The provider automatic selection would use
+    #[value(name = "auto")]
+    Auto,
+    /// Every configured provider, tested concurrently
+    #[value(name = "all")]
+    All,
+}
+
+impl std::fmt::Display for TestTarget {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.provider() {
+            Some(provider) => provider.fmt(f),
+            None => write!(f, "all"),
+        }
+    }
+}
+
+impl TestTarget {
+    /// Single provider this tests; `None` for `all`
+    pub fn provider(&self) -> Option<AuthProvider> {
+        match self {
+            TestTarget::OpenAI => Some(AuthProvider::OpenAI),
+            TestTarget::Claude => Some(AuthProvider::Claude),
+            TestTarget::Auto => Some(AuthProvider::Auto),
+            TestTarget::All => None,
+        }
+    }
+}
+
 /// Extended login command with provider support
 #[derive(Debug, Parser)]
 pub struct ExtendedLoginCommand {
+    #[cfg(feature = "codex-cli")]
     #[clap(skip)]
     pub config_overrides: CliConfigOverrides,
 
@@ -67,6 +131,9 @@ pub enum ExtendedLoginSubcommand {
         /// Show detailed information including quotas
         #[arg(long = "detailed")]
         detailed: bool,
+        /// Explain which provider automatic selection would use, and why
+        #[arg(long = "why")]
+        why: bool,
     },
     /// List all available authentication providers
     Providers {
@@ -82,21 +149,77 @@ pub enum ExtendedLoginSubcommand {
         /// Force switch even if target provider not authenticated
         #[arg(long = "force")]
         force: bool,
+        /// Explain how providers are selected after switching
+        #[arg(long = "explain")]
+        explain: bool,
     },
     /// Show quota information for Claude Max users
     Quota {
         /// Provider to check quota for
         #[arg(long = "provider", value_enum, default_value_t = AuthProvider::Claude)]
         provider: AuthProvider,
-        /// Show detailed quota breakdown
-        #[arg(long = "detailed")]
+        /// Show detailed quota breakdown, including per-agent usage
+        #[arg(long = "detailed", visible_alias = "by-agent")]
         detailed: bool,
     },
     /// Test authentication with provider
     Test {
-        /// Provider to test
-        #[arg(long = "provider", value_enum, default_value_t = AuthProvider::Auto)]
-        provider: AuthProvider,
+        /// Provider to test, or `all` for every configured provider
+        #[arg(long = "provider", value_enum, default_value_t = TestTarget::Auto)]
+        provider: TestTarget,
+        /// Only check the stored credentials: no refresh, usage recording or file writes
+        #[arg(long = "read-only")]
+        read_only: bool,
+    },
+    /// Check config.toml and stored credentials for problems
+    Validate {
+        /// Treat an unavailable preferred provider as an error
+        #[arg(long = "strict")]
+        strict: bool,
+    },
+    /// Run read-only checks on the authentication setup and suggest fixes
+    Doctor,
+    /// Show who each provider is authenticated as and which one is selected
+    Whoami {
+        /// Confirm the stored credentials with each provider's API
+        #[arg(long = "verify")]
+        verify: bool,
+    },
+    /// Check stored credentials against each provider's API; exits non-zero if any was refused
+    Verify {
+        /// Check the credentials of every profile, not just the active one
+        #[arg(long = "all")]
+        all: bool,
+        /// Output format
+        #[arg(long = "format", value_enum, default_value_t = VerifyFormat::Table)]
+        format: VerifyFormat,
+    },
+    /// Manage named credential profiles for separate accounts
+    Profile {
+        #[command(subcommand)]
+        action: ProfileAction,
+    },
+}
+
+/// Profile management actions
+#[derive(Debug, Subcommand)]
+pub enum ProfileAction {
+    /// Create an empty profile
+    Add {
+        /// Profile name (letters, digits, '-' or '_')
+        name: String,
+    },
+    /// List profiles, marking the active one
+    List,
+    /// Switch to a profile; `default` uses the credentials in the codex home directly
+    Use {
+        /// Profile name
+        name: String,
+    },
+    /// Delete a profile and the credentials stored in it
+    Remove {
+        /// Profile name
+        name: String,
     },
 }
 
@@ -133,6 +256,9 @@ pub struct QuotaInfo {
     pub remaining: Option<u64>,
     pub reset_time: Option<chrono::DateTime<chrono::Utc>>,
     pub percentage_used: Option<f64>,
+    /// Per-agent breakdown, only collected for detailed views
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub agent_usage: Option<AgentUsageSummary>,
 }
 
 /// Provider capabilities information
@@ -149,21 +275,19 @@ pub struct ProviderCapabilities {
 
 /// Unified authentication manager for CLI operations
 pub struct UnifiedAuthManager {
-    config_overrides: CliConfigOverrides,
     claude_auth: Option<SecureClaudeAuth>,
+    quota_manager: Option<Arc<RwLock<ClaudeQuotaManager>>>,
     preferred_provider: AuthProvider,
 }
 
 impl UnifiedAuthManager {
     /// Create new unified authentication manager
-    pub fn new(config_overrides: CliConfigOverrides) -> Result<Self, Box<dyn std::error::Error>> {
+    pub fn new() -> Result<Self, Box<dyn std::error::Error>> {
         let claude_config = ClaudeAuthConfig::default();
+        let codex_home = std::env::home_dir().unwrap_or_default().join(".codex");
         let claude_auth = match SecureClaudeAuth::new(
             claude_config,
-            std::env::home_dir()
-                .unwrap_or_default()
-                .join(".codex")
-                .join("claude_tokens.json")
+            active_profile_home(&codex_home)?.join("claude_tokens.json"),
         ) {
             Ok(auth) => Some(auth),
             Err(e) => {
@@ -173,12 +297,18 @@ impl UnifiedAuthManager {
         };
 
         Ok(Self {
-            config_overrides,
             claude_auth,
+            quota_manager: None,
             preferred_provider: AuthProvider::Auto,
         })
     }
 
+    /// Report per-agent usage from this quota manager in detailed quota views
+    pub fn with_quota_manager(mut self, quota_manager: Arc<RwLock<ClaudeQuotaManager>>) -> Self {
+        self.quota_manager = Some(quota_manager);
+        self
+    }
+
     /// Get authentication status for all providers
     pub async fn get_auth_status(&self, provider_filter: Option<AuthProvider>) -> Result<Vec<AuthStatus>, Box<dyn std::error::Error>> {
         let mut statuses = Vec::new();
@@ -259,11 +389,24 @@ impl UnifiedAuthManager {
         Ok(())
     }
 
+    /// Log out of Claude, revoking tokens server-side before clearing them locally
+    ///
+    /// Returns `false` when there was nothing to log out of.
+    pub async fn logout_claude(&mut self) -> Result<bool, ClaudeAuthError> {
+        match self.claude_auth.as_mut() {
+            Some(claude_auth) if claude_auth.is_authenticated() => {
+                claude_auth.revoke_and_logout(None).await?;
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+
     /// Get quota information for Claude provider
     pub async fn get_claude_quota(&self, detailed: bool) -> Result<Option<QuotaInfo>, Box<dyn std::error::Error>> {
         if let Some(ref claude_auth) = self.claude_auth {
             if let Some(tokens) = claude_auth.get_stored_tokens()? {
-                match claude_auth.verify_subscription(&tokens.access_token).await {
+                match claude_auth.verify_subscription(tokens.access_token.expose_secret()).await {
                     Ok(subscription) => {
                         let percentage_used = if let (Some(current), Some(limit)) = (subscription.usage_current, subscription.usage_limit) {
                             Some((current as f64 / limit as f64) * 100.0)
@@ -277,12 +420,18 @@ impl UnifiedAuthManager {
                             None
                         };
 
+                        let agent_usage = match (&self.quota_manager, detailed) {
+                            (Some(quota_manager), true) => Some(quota_manager.read().await.usage_summary()),
+                            _ => None,
+                        };
+
                         Ok(Some(QuotaInfo {
                             daily_limit: subscription.usage_limit,
                             current_usage: subscription.usage_current,
                             remaining,
                             reset_time: subscription.reset_date,
                             percentage_used,
+                            agent_usage,
                         }))
                     }
                     Err(_) => Ok(None),
@@ -323,7 +472,7 @@ impl UnifiedAuthManager {
                 ));
             }
 
-            if let Some(key) = api_key {
+            if api_key.is_some() {
                 // API key authentication
                 // Note: This would need to be implemented in SecureClaudeAuth
                 todo!("Implement API key authentication for Claude")
@@ -373,7 +522,7 @@ impl UnifiedAuthManager {
             if authenticated {
                 if let Some(tokens) = claude_auth.get_stored_tokens()? {
                     // Get subscription info
-                    if let Ok(subscription) = claude_auth.verify_subscription(&tokens.access_token).await {
+                    if let Ok(subscription) = claude_auth.verify_subscription(tokens.access_token.expose_secret()).await {
                         subscription_info = Some(SubscriptionInfo {
                             tier: subscription.tier.clone(),
                             features: subscription.features.clone(),
@@ -388,6 +537,7 @@ impl UnifiedAuthManager {
                                 remaining: Some(limit.saturating_sub(current)),
                                 reset_time: subscription.reset_date,
                                 percentage_used: Some((current as f64 / limit as f64) * 100.0),
+                                agent_usage: None,
                             });
                         }
                     }
@@ -435,7 +585,7 @@ impl UnifiedAuthManager {
         if let Some(ref claude_auth) = self.claude_auth {
             if let Some(tokens) = claude_auth.get_stored_tokens()? {
                 // Test by checking subscription (lightweight API call)
-                match claude_auth.verify_subscription(&tokens.access_token).await {
+                match claude_auth.verify_subscription(tokens.access_token.expose_secret()).await {
                     Ok(_) => Ok(true),
                     Err(_) => Ok(false),
                 }
@@ -447,6 +597,45 @@ impl UnifiedAuthManager {
         }
     }
 
+    /// Non-mutating validity check of the stored Claude credentials
+    ///
+    /// `None` when Claude authentication could not be initialised at all.
+    pub async fn check_claude_read_only(&self) -> Result<Option<CredentialCheck>, Box<dyn std::error::Error>> {
+        match self.claude_auth {
+            Some(ref claude_auth) => Ok(Some(claude_auth.check_validity().await?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Classified health of the stored Claude credentials and the Anthropic API
+    ///
+    /// `None` when Claude authentication could not be initialised at all.
+    pub async fn check_claude_health(&self) -> Option<ClaudeAuthHealth> {
+        match self.claude_auth {
+            Some(ref claude_auth) => Some(claude_auth.health().await),
+            None => None,
+        }
+    }
+
+    /// Name of the first supported variable holding a Claude API key, if any
+    pub fn detect_claude_env_key(&self) -> Option<&'static str> {
+        IMPORTABLE_ENV_VARS
+            .into_iter()
+            .find(|var| std::env::var(var).map(|value| !value.trim().is_empty()).unwrap_or(false))
+    }
+
+    /// Move a Claude API key from the environment into secure storage
+    ///
+    /// `None` when no supported variable is set.
+    pub fn import_claude_env_key(&self, unset: bool) -> Result<Option<ImportedCredential>, ClaudeAuthError> {
+        match self.claude_auth {
+            Some(ref claude_auth) => claude_auth.import_from_env(unset),
+            None => Err(ClaudeAuthError::InvalidConfiguration(
+                "Claude authentication not available".to_string()
+            )),
+        }
+    }
+
     fn save_provider_preference(&self) -> Result<(), Box<dyn std::error::Error>> {
         // Save preferred provider to config file
         let config_path = std::env::home_dir()
@@ -464,6 +653,39 @@ impl UnifiedAuthManager {
     }
 }
 
+/// Format a read-only credential check for display
+pub fn format_credential_check(check: &CredentialCheck) -> String {
+    let mut output = match &check.status {
+        CredentialStatus::Valid => "✓ Credentials valid\n".to_string(),
+        CredentialStatus::Expired { expired_at } => format!(
+            "✗ Credentials present but expired at {}\n",
+            expired_at.format("%Y-%m-%d %H:%M:%S UTC")
+        ),
+        CredentialStatus::Invalid { reason } => format!("✗ Credentials present but invalid: {}\n", reason),
+        CredentialStatus::Missing => "✗ No credentials stored\n".to_string(),
+    };
+
+    if let Some(ref tier) = check.subscription_tier {
+        output.push_str(&format!("  Subscription: {}\n", tier));
+    }
+    match check.latency_ms {
+        Some(latency) => output.push_str(&format!("  Latency: {}ms\n", latency)),
+        None if check.is_valid() => output.push_str("  Status probe skipped (offline mode)\n"),
+        None => {}
+    }
+    output
+}
+
+/// Format a Claude health classification with its suggested action
+pub fn format_claude_health(health: &ClaudeAuthHealth) -> String {
+    let mark = if health.is_ok() { "✓" } else { "✗" };
+    let mut output = format!("{} {}\n", mark, health.message());
+    if let Some(action) = health.suggested_action() {
+        output.push_str(&format!("  Fix: {}\n", action));
+    }
+    output
+}
+
 /// Format authentication status for display
 pub fn format_auth_status(statuses: &[AuthStatus], detailed: bool) -> String {
     let mut output = String::new();
@@ -498,6 +720,9 @@ pub fn format_auth_status(statuses: &[AuthStatus], detailed: bool) -> String {
                 
                 if let Some(reset_time) = quota.reset_time {
                     output.push_str(&format!("  Resets: {}\n", reset_time.format("%Y-%m-%d %H:%M UTC")));
+                    if let Some(countdown) = reset_countdown(reset_time) {
+                        output.push_str(&format!("  Quota {}\n", countdown));
+                    }
                 }
             }
 
@@ -512,6 +737,47 @@ pub fn format_auth_status(statuses: &[AuthStatus], detailed: bool) -> String {
     output
 }
 
+/// Format a provider selection explanation for display
+pub fn format_selection_explanation(explanation: &SelectionExplanation) -> String {
+    let mut output = String::new();
+
+    output.push_str(&format!("Provider Selection ({:?}):\n", explanation.strategy));
+    output.push_str("===================\n\n");
+
+    match &explanation.selected {
+        Some(provider_type) => output.push_str(&format!("Selected: {}\n", provider_type)),
+        None => output.push_str("Selected: none\n"),
+    }
+    output.push_str(&format!("Deciding factor: {:?} - {}\n\n", explanation.deciding_factor, explanation.reason));
+
+    for (rank, candidate) in explanation.candidates.iter().enumerate() {
+        output.push_str(&format!("{}. {}\n", rank + 1, candidate.provider_type));
+        if let Some(ref tier) = candidate.subscription_tier {
+            output.push_str(&format!("  Subscription: {}\n", tier));
+        }
+        match candidate.success_rate {
+            Some(rate) => output.push_str(&format!("  Success rate: {:.1}%\n", rate * 100.0)),
+            None => output.push_str("  Success rate: no recorded requests\n"),
+        }
+        if let Some(latency) = candidate.average_latency_ms {
+            output.push_str(&format!("  Average latency: {:.0}ms\n", latency));
+        }
+        if let Some(quota) = candidate.quota_remaining {
+            output.push_str(&format!("  Quota remaining: {} tokens\n", quota));
+        }
+        output.push_str(&format!("  Cost: {:?}\n", candidate.cost));
+        if candidate.missing_features.is_empty() {
+            output.push_str("  Capabilities: all required features\n");
+        }
+        if let Some(ref rejection) = candidate.rejection {
+            output.push_str(&format!("  Unusable: {}\n", rejection));
+        }
+        output.push('\n');
+    }
+
+    output
+}
+
 /// Format provider capabilities for display
 pub fn format_provider_capabilities(capabilities: &[ProviderCapabilities]) -> String {
     let mut output = String::new();
@@ -539,6 +805,11 @@ pub fn format_provider_capabilities(capabilities: &[ProviderCapabilities]) -> St
     output
 }
 
+/// `resets in …` for a future reset time
+fn reset_countdown(reset_time: chrono::DateTime<chrono::Utc>) -> Option<String> {
+    (reset_time - chrono::Utc::now()).to_std().ok().map(format_reset_in)
+}
+
 /// Format quota information for display
 pub fn format_quota_info(quota: &QuotaInfo, provider: AuthProvider) -> String {
     let mut output = String::new();
@@ -565,15 +836,85 @@ pub fn format_quota_info(quota: &QuotaInfo, provider: AuthProvider) -> String {
 
     if let Some(reset_time) = quota.reset_time {
         output.push_str(&format!("Resets: {}\n", reset_time.format("%Y-%m-%d %H:%M UTC")));
-        
-        let now = chrono::Utc::now();
-        if reset_time > now {
-            let duration = reset_time - now;
-            let hours = duration.num_hours();
-            let minutes = duration.num_minutes() % 60;
-            output.push_str(&format!("Time until reset: {}h {}m\n", hours, minutes));
+        if let Some(countdown) = reset_countdown(reset_time) {
+            output.push_str(&format!("Quota {}\n", countdown));
+        }
+    }
+
+    if let Some(ref usage) = quota.agent_usage {
+        output.push_str(&format_agent_usage(usage));
+    }
+
+    output
+}
+
+/// Format per-agent usage as a table, flagging allocations close to expiry
+fn format_agent_usage(usage: &AgentUsageSummary) -> String {
+    let mut output = String::new();
+
+    output.push_str("\nPer-Agent Usage:\n");
+    if usage.agents.is_empty() {
+        output.push_str("  No active agents\n");
+        return output;
+    }
+
+    output.push_str(&format!(
+        "  {:<24} {:>12} {:>12} {:>7} {:>8}\n",
+        "Agent", "Allocated", "Used", "Util", "Age"
+    ));
+    for agent in &usage.agents {
+        output.push_str(&format!(
+            "  {:<24} {:>12} {:>12} {:>6.1}% {:>5}h{:02}m{}\n",
+            agent.agent_id,
+            agent.allocated_tokens,
+            agent.used_tokens,
+            agent.utilization_percent,
+            agent.age_seconds / 3600,
+            (agent.age_seconds % 3600) / 60,
+            if agent.near_expiry { "  ⚠ expires soon" } else { "" }
+        ));
+    }
+    output.push_str(&format!(
+        "  {:<24} {:>12} {:>12}\n",
+        "Total", usage.total_allocated, usage.total_used
+    ));
+    if usage.near_expiry_count > 0 {
+        output.push_str(&format!(
+            "  {} allocation(s) expire within {} minutes\n",
+            usage.near_expiry_count,
+            AGENT_EXPIRY_WARNING.num_minutes()
+        ));
+    }
+
+    output
+}
+
+/// Format configuration validation problems as a numbered list
+pub fn format_validation_result(result: &ValidationResult) -> String {
+    let mut output = String::new();
+
+    output.push_str("Configuration Validation:\n");
+    output.push_str("========================\n\n");
+
+    if result.entries.is_empty() {
+        output.push_str("✓ No problems found\n");
+    } else {
+        output.push_str(&format!("✗ Found {} problem(s):\n\n", result.entries.len()));
+        for (index, entry) in result.entries.iter().enumerate() {
+            output.push_str(&format!("{:>3}. {}\n", index + 1, entry.field_path));
+            output.push_str(&format!("     Problem: {}\n", entry.problem));
+            if let Some(ref suggestion) = entry.suggestion {
+                output.push_str(&format!("     Fix: {}\n", suggestion));
+            }
+        }
+    }
+
+    if !result.warnings.is_empty() {
+        output.push_str("\nWarnings:\n");
+        for warning in &result.warnings {
+            output.push_str(&format!("  - {}\n", warning));
         }
     }
 
     output
-}
\ No newline at end of file
+}
@@ -0,0 +1,450 @@
+//! `code auth doctor`: read-only diagnosis of the authentication setup
+//!
+//! Each check reports pass, warn or fail with a suggested fix. Only failures make the
+//! command exit non-zero; warnings point at things that work but should be cleaned up.
+
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+use crate::auth::{AuthenticationManager, CredentialResolver, HealthLevel};
+use crate::claude_auth::{ClaudeAuthConfig, SecureClaudeAuth, IMPORTABLE_ENV_VARS};
+use crate::configuration::{active_profile_home, EnvironmentConfig, UnifiedConfigManager};
+
+/// Files that hold credentials, relative to the active profile's home
+const CREDENTIAL_FILES: [&str; 5] = [
+    "auth.json",
+    "unified_auth.json",
+    "claude_auth.json",
+    "claude_tokens.json",
+    "claude_tokens.UNENCRYPTED.json",
+];
+
+/// Outcome of a single doctor check
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CheckStatus {
+    Pass,
+    Warn,
+    /// Critical: the command exits non-zero
+    Fail,
+}
+
+/// One line of the doctor checklist
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DoctorCheck {
+    pub name: String,
+    pub status: CheckStatus,
+    pub detail: String,
+    /// What to run or change to resolve a warning or failure
+    pub fix: Option<String>,
+}
+
+impl DoctorCheck {
+    fn pass(name: &str, detail: impl Into<String>) -> Self {
+        Self { name: name.to_string(), status: CheckStatus::Pass, detail: detail.into(), fix: None }
+    }
+
+    fn warn(name: &str, detail: impl Into<String>, fix: impl Into<String>) -> Self {
+        Self { name: name.to_string(), status: CheckStatus::Warn, detail: detail.into(), fix: Some(fix.into()) }
+    }
+
+    fn fail(name: &str, detail: impl Into<String>, fix: impl Into<String>) -> Self {
+        Self { name: name.to_string(), status: CheckStatus::Fail, detail: detail.into(), fix: Some(fix.into()) }
+    }
+}
+
+/// Every check run by the doctor, in order
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DoctorReport {
+    pub checks: Vec<DoctorCheck>,
+}
+
+impl DoctorReport {
+    /// Whether any critical check failed
+    pub fn has_failures(&self) -> bool {
+        self.checks.iter().any(|check| check.status == CheckStatus::Fail)
+    }
+
+    pub fn get(&self, name: &str) -> Option<&DoctorCheck> {
+        self.checks.iter().find(|check| check.name == name)
+    }
+}
+
+/// Run every check against `codex_home` and the process environment
+pub async fn run_doctor(codex_home: &Path) -> DoctorReport {
+    run_doctor_with_env(codex_home, |var| std::env::var(var).ok()).await
+}
+
+async fn run_doctor_with_env(codex_home: &Path, env: impl Fn(&str) -> Option<String>) -> DoctorReport {
+    let mut report = DoctorReport::default();
+
+    report.checks.push(check_codex_home(codex_home));
+    if report.has_failures() {
+        // Nothing else can be read without the directory
+        return report;
+    }
+
+    match active_profile_home(codex_home) {
+        Ok(profile_home) => {
+            report.checks.push(check_file_permissions(&profile_home));
+            report.checks.extend(check_claude_health(&profile_home).await);
+        }
+        Err(e) => report.checks.push(DoctorCheck::fail(
+            "file permissions",
+            format!("Could not resolve the active profile: {}", e),
+            "Switch to an existing profile with `code auth profile use <name>`",
+        )),
+    }
+    report.checks.push(check_env_secrets(&env));
+    report.checks.push(check_claude_credentials(codex_home, &env));
+    report.checks.push(check_environment_overrides());
+    report.checks.push(check_config(codex_home));
+    report.checks.extend(check_providers(codex_home).await);
+
+    report
+}
+
+fn check_codex_home(codex_home: &Path) -> DoctorCheck {
+    const NAME: &str = "codex home";
+    let metadata = match std::fs::metadata(codex_home) {
+        Ok(metadata) if metadata.is_dir() => metadata,
+        Ok(_) => {
+            return DoctorCheck::fail(
+                NAME,
+                format!("{} is not a directory", codex_home.display()),
+                format!("Move the file aside and run `mkdir -p {}`", codex_home.display()),
+            );
+        }
+        Err(e) => {
+            return DoctorCheck::fail(
+                NAME,
+                format!("{} is not accessible: {}", codex_home.display(), e),
+                format!("Run `mkdir -p {}`, then log in again", codex_home.display()),
+            );
+        }
+    };
+
+    if metadata.permissions().readonly() {
+        return DoctorCheck::fail(
+            NAME,
+            format!("{} is not writable", codex_home.display()),
+            format!("Run `chmod u+w {}`", codex_home.display()),
+        );
+    }
+    DoctorCheck::pass(NAME, format!("{} exists and is writable", codex_home.display()))
+}
+
+#[cfg(unix)]
+fn check_file_permissions(profile_home: &Path) -> DoctorCheck {
+    use std::os::unix::fs::PermissionsExt;
+    const NAME: &str = "file permissions";
+
+    let mut exposed = Vec::new();
+    let mut checked = 0;
+    for file in CREDENTIAL_FILES {
+        let path = profile_home.join(file);
+        let Ok(metadata) = std::fs::metadata(&path) else {
+            continue;
+        };
+        checked += 1;
+        let mode = metadata.permissions().mode() & 0o777;
+        if mode & 0o077 != 0 {
+            exposed.push((path, mode));
+        }
+    }
+
+    if exposed.is_empty() {
+        return DoctorCheck::pass(NAME, format!("{} credential file(s) readable only by you", checked));
+    }
+    let detail = exposed
+        .iter()
+        .map(|(path, mode)| format!("{} is {:o}", path.display(), mode))
+        .collect::<Vec<_>>()
+        .join(", ");
+    let paths = exposed
+        .iter()
+        .map(|(path, _)| path.display().to_string())
+        .collect::<Vec<_>>()
+        .join(" ");
+    DoctorCheck::fail(NAME, format!("Credentials readable by other users: {}", detail), format!("Run `chmod 600 {}`", paths))
+}
+
+#[cfg(not(unix))]
+fn check_file_permissions(_profile_home: &Path) -> DoctorCheck {
+    DoctorCheck::pass("file permissions", "Not checked on this platform")
+}
+
+fn check_env_secrets(env: &impl Fn(&str) -> Option<String>) -> DoctorCheck {
+    const NAME: &str = "environment secrets";
+    let set: Vec<&str> = IMPORTABLE_ENV_VARS
+        .into_iter()
+        .chain(["OPENAI_API_KEY"])
+        .filter(|var| env(var).is_some_and(|value| !value.trim().is_empty()))
+        .collect();
+
+    if set.is_empty() {
+        return DoctorCheck::pass(NAME, "No API keys in the environment");
+    }
+    DoctorCheck::warn(
+        NAME,
+        format!("Plaintext API keys in the environment: {}", set.join(", ")),
+        "Run `code auth login` to move the key into secure storage, then remove it from your shell profile",
+    )
+}
+
+fn check_claude_credentials(codex_home: &Path, env: &impl Fn(&str) -> Option<String>) -> DoctorCheck {
+    const NAME: &str = "claude credentials";
+    let resolved = CredentialResolver::default().resolve_with_env(codex_home, env);
+
+    let Some(active) = resolved.active else {
+        return DoctorCheck::pass(NAME, "No Claude credentials found");
+    };
+    if resolved.shadowed.is_empty() {
+        return DoctorCheck::pass(NAME, format!("Using {}", active));
+    }
+    let shadowed = resolved.shadowed.iter().map(ToString::to_string).collect::<Vec<_>>().join(", ");
+    DoctorCheck::warn(
+        NAME,
+        format!("Using {}; shadowed: {}", active, shadowed),
+        "Remove the credentials you no longer use so the active one is unambiguous",
+    )
+}
+
+/// Classified health of the Claude credentials in secure storage, when there are any
+///
+/// Throttling and outages only warn, since they clear up without the user doing anything.
+async fn check_claude_health(profile_home: &Path) -> Option<DoctorCheck> {
+    const NAME: &str = "claude health";
+    let storage_path = profile_home.join("claude_tokens.json");
+    if !storage_path.exists() {
+        return None;
+    }
+
+    let auth = match SecureClaudeAuth::new(ClaudeAuthConfig::default(), storage_path) {
+        Ok(auth) => auth,
+        Err(e) => {
+            return Some(DoctorCheck::fail(
+                NAME,
+                format!("Secure storage can't be opened: {}", e),
+                "Run `code auth login --provider claude` to store the credentials again",
+            ));
+        }
+    };
+    let health = auth.health().await;
+    Some(match health.suggested_action() {
+        None => DoctorCheck::pass(NAME, health.message()),
+        Some(action) if health.is_credential_problem() => DoctorCheck::fail(NAME, health.message(), action),
+        Some(action) => DoctorCheck::warn(NAME, health.message(), action),
+    })
+}
+
+fn check_environment_overrides() -> DoctorCheck {
+    const NAME: &str = "environment overrides";
+    let env_config = EnvironmentConfig::new();
+    match env_config.validate_environment() {
+        Ok(()) if env_config.has_overrides() => DoctorCheck::pass(
+            NAME,
+            format!("Valid overrides: {}", env_config.get_active_variables().join(", ")),
+        ),
+        Ok(()) => DoctorCheck::pass(NAME, "No overrides set"),
+        Err(e) => DoctorCheck::fail(NAME, e.to_string(), "Correct or unset the variable named above"),
+    }
+}
+
+fn check_config(codex_home: &Path) -> DoctorCheck {
+    const NAME: &str = "configuration";
+    let result = UnifiedConfigManager::new(codex_home.to_path_buf())
+        .map_err(|e| e.to_string())
+        .and_then(|manager| manager.inspect_config().map_err(|e| e.to_string()));
+
+    match result {
+        Ok(None) => DoctorCheck::pass(NAME, "No configuration file; defaults apply"),
+        Ok(Some(result)) if !result.is_valid => {
+            let fix = result
+                .entries
+                .iter()
+                .find_map(|entry| entry.suggestion.clone())
+                .unwrap_or_else(|| "Run `code login validate` for details".to_string());
+            DoctorCheck::fail(NAME, result.issues.join("; "), fix)
+        }
+        Ok(Some(result)) if !result.warnings.is_empty() => {
+            DoctorCheck::warn(NAME, result.warnings.join("; "), "Run `code login validate` for details")
+        }
+        Ok(Some(_)) => DoctorCheck::pass(NAME, "Configuration parses and validates"),
+        Err(e) => DoctorCheck::fail(NAME, e, "Fix the syntax error in the configuration file"),
+    }
+}
+
+async fn check_providers(codex_home: &Path) -> Vec<DoctorCheck> {
+    let status = match AuthenticationManager::new(codex_home.to_path_buf()).await {
+        Ok(manager) => manager.get_system_status().await,
+        Err(e) => Err(e),
+    };
+    let status = match status {
+        Ok(status) => status,
+        Err(e) => {
+            return vec![DoctorCheck::fail(
+                "providers",
+                format!("Could not load authentication state: {}", e),
+                "Run `code auth login` to set up a provider",
+            )];
+        }
+    };
+
+    // Providers are only loaded once the migration has run
+    if status.migration_needed {
+        return vec![DoctorCheck::warn(
+            "migration",
+            "Migration to unified authentication is pending; providers were not checked",
+            "Run `auth-cli migration execute`",
+        )];
+    }
+
+    let mut checks = vec![DoctorCheck::pass("migration", "No migration pending")];
+    let mut providers: Vec<_> = status.provider_status.iter().collect();
+    providers.sort_by(|a, b| a.0.id().cmp(b.0.id()));
+    for (provider_type, provider) in providers {
+        let name = format!("provider {}", provider_type.id());
+        let login = format!("Run `code auth login --provider {}`", provider_type.id());
+        checks.push(if !provider.available {
+            DoctorCheck::fail(&name, provider.error_message.clone().unwrap_or_else(|| "Unreachable".to_string()), login)
+        } else if !provider.authenticated {
+            DoctorCheck::warn(&name, provider.error_message.clone().unwrap_or_else(|| "Not authenticated".to_string()), login)
+        } else {
+            let tier = provider.subscription_tier.as_deref().unwrap_or("no subscription info");
+            DoctorCheck::pass(&name, format!("Authenticated ({})", tier))
+        });
+    }
+
+    checks.push(match status.health.overall {
+        HealthLevel::Healthy => DoctorCheck::pass("health", "All providers working"),
+        HealthLevel::Degraded => DoctorCheck::warn(
+            "health",
+            "Some providers are failing; requests fall back to the working ones",
+            "Fix the failing providers listed above",
+        ),
+        HealthLevel::Unhealthy => DoctorCheck::fail(
+            "health",
+            "No working authentication provider",
+            "Run `code auth login` to set up a provider",
+        ),
+    });
+    checks
+}
+
+/// Format the doctor checklist for display
+pub fn format_doctor_report(report: &DoctorReport) -> String {
+    let mut output = String::new();
+
+    output.push_str("Authentication Doctor:\n");
+    output.push_str("======================\n\n");
+
+    for check in &report.checks {
+        let marker = match check.status {
+            CheckStatus::Pass => "✓",
+            CheckStatus::Warn => "⚠",
+            CheckStatus::Fail => "✗",
+        };
+        output.push_str(&format!("{} {}: {}\n", marker, check.name, check.detail));
+        if let Some(ref fix) = check.fix {
+            output.push_str(&format!("    Fix: {}\n", fix));
+        }
+    }
+
+    let count = |status| report.checks.iter().filter(|check| check.status == status).count();
+    output.push_str(&format!(
+        "\n{} passed, {} warning(s), {} failed\n",
+        count(CheckStatus::Pass),
+        count(CheckStatus::Warn),
+        count(CheckStatus::Fail)
+    ));
+
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn write_private(path: &Path, content: &str) {
+        std::fs::write(path, content).unwrap();
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o600)).unwrap();
+        }
+    }
+
+    /// Migrated setup with a working OpenAI key
+    fn healthy_home(codex_home: &Path) {
+        write_private(&codex_home.join("auth.json"), r#"{"OPENAI_API_KEY": "sk-test"}"#);
+        write_private(&codex_home.join("unified_auth.json"), "{}");
+    }
+
+    #[tokio::test]
+    async fn test_healthy_setup_passes() {
+        let temp_dir = tempdir().unwrap();
+        healthy_home(temp_dir.path());
+
+        let report = run_doctor_with_env(temp_dir.path(), |_| None).await;
+
+        assert!(!report.has_failures(), "{}", format_doctor_report(&report));
+        assert_eq!(report.get("file permissions").unwrap().status, CheckStatus::Pass);
+        assert_eq!(report.get("environment secrets").unwrap().status, CheckStatus::Pass);
+        assert_eq!(report.get("provider openai").unwrap().status, CheckStatus::Pass);
+        assert_eq!(report.get("health").unwrap().status, CheckStatus::Pass);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_world_readable_credentials_fail() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let temp_dir = tempdir().unwrap();
+        healthy_home(temp_dir.path());
+        let auth_file = temp_dir.path().join("auth.json");
+        std::fs::set_permissions(&auth_file, std::fs::Permissions::from_mode(0o644)).unwrap();
+
+        let report = run_doctor_with_env(temp_dir.path(), |_| None).await;
+
+        assert!(report.has_failures());
+        let check = report.get("file permissions").unwrap();
+        assert_eq!(check.status, CheckStatus::Fail);
+        assert!(check.detail.contains("644"));
+        assert!(check.fix.as_deref().unwrap().contains(&format!("chmod 600 {}", auth_file.display())));
+    }
+
+    #[tokio::test]
+    async fn test_env_keys_warn_without_failing() {
+        let temp_dir = tempdir().unwrap();
+        healthy_home(temp_dir.path());
+
+        let report = run_doctor_with_env(temp_dir.path(), |var| {
+            (var == "ANTHROPIC_API_KEY").then(|| "sk-ant-api03-test".to_string())
+        })
+        .await;
+
+        let check = report.get("environment secrets").unwrap();
+        assert_eq!(check.status, CheckStatus::Warn);
+        assert!(check.detail.contains("ANTHROPIC_API_KEY"));
+        assert!(!report.has_failures());
+    }
+
+    #[tokio::test]
+    async fn test_shadowed_claude_credentials_are_listed() {
+        let temp_dir = tempdir().unwrap();
+        healthy_home(temp_dir.path());
+        write_private(&temp_dir.path().join("claude_auth.json"), r#"{"api_key": "sk-ant-api03-file"}"#);
+
+        let report = run_doctor_with_env(temp_dir.path(), |var| {
+            (var == "ANTHROPIC_API_KEY").then(|| "sk-ant-api03-test".to_string())
+        })
+        .await;
+
+        let check = report.get("claude credentials").unwrap();
+        assert_eq!(check.status, CheckStatus::Warn);
+        assert!(check.detail.starts_with("Using API key from environment (ANTHROPIC_API_KEY)"));
+        assert!(check.detail.contains("shadowed: API key from file"));
+    }
+}
@@ -4,8 +4,18 @@
 //! system that supports both OpenAI and Claude providers.
 
 use crate::cli::auth_commands::{
-    ExtendedLoginCommand, ExtendedLoginSubcommand, AuthProvider, 
-    UnifiedAuthManager, format_auth_status, format_provider_capabilities, format_quota_info
+    ExtendedLoginCommand, ExtendedLoginSubcommand, AuthProvider, ProfileAction, TestTarget,
+    UnifiedAuthManager, format_auth_status, format_claude_health, format_credential_check, format_provider_capabilities,
+    format_quota_info,
+    format_selection_explanation, format_validation_result,
+};
+use crate::auth::{convenience, AuthenticationManager, ProviderSelectionStrategy};
+use crate::security::LoginLock;
+use crate::cli::doctor::{format_doctor_report, run_doctor};
+use crate::cli::verify::{format_provider_test_report, format_verification_table, CredentialVerifier, VerifyFormat};
+use crate::cli::whoami::{format_whoami_report, whoami};
+use crate::configuration::{
+    ConfigError, ConfigValidator, ProviderType, UnifiedConfigManager, ValidationError, DEFAULT_PROFILE,
 };
 use codex_common::CliConfigOverrides;
 
@@ -26,23 +36,51 @@ pub async fn run_extended_login(mut cmd: ExtendedLoginCommand) -> ! {
 
 /// Execute extended login command logic
 async fn execute_extended_login(cmd: &mut ExtendedLoginCommand) -> Result<(), Box<dyn std::error::Error>> {
-    let mut auth_manager = UnifiedAuthManager::new(cmd.config_overrides.clone())?;
+    let mut auth_manager = UnifiedAuthManager::new()?;
 
     match &cmd.action {
-        Some(ExtendedLoginSubcommand::Status { provider, detailed }) => {
-            handle_status_command(&auth_manager, provider.clone(), *detailed).await
+        Some(ExtendedLoginSubcommand::Status { provider, detailed, why }) => {
+            handle_status_command(&auth_manager, provider.clone(), *detailed).await?;
+            if *why {
+                print_selection_explanation(None).await?;
+            }
+            Ok(())
         }
         Some(ExtendedLoginSubcommand::Providers { active_only }) => {
             handle_providers_command(&auth_manager, *active_only).await
         }
-        Some(ExtendedLoginSubcommand::Switch { provider, force }) => {
-            handle_switch_command(&mut auth_manager, provider.clone(), *force).await
+        Some(ExtendedLoginSubcommand::Switch { provider, force, explain }) => {
+            handle_switch_command(&mut auth_manager, provider.clone(), *force).await?;
+            if *explain {
+                print_selection_explanation(provider.provider_type()).await?;
+            }
+            Ok(())
         }
         Some(ExtendedLoginSubcommand::Quota { provider, detailed }) => {
             handle_quota_command(&auth_manager, provider.clone(), *detailed).await
         }
-        Some(ExtendedLoginSubcommand::Test { provider }) => {
-            handle_test_command(&auth_manager, provider.clone()).await
+        Some(ExtendedLoginSubcommand::Test { provider: TestTarget::All, read_only: false }) => {
+            handle_test_all_command().await
+        }
+        Some(ExtendedLoginSubcommand::Test { provider, read_only }) => match provider.provider() {
+            Some(provider) if *read_only => handle_read_only_test_command(&auth_manager, provider).await,
+            Some(provider) => handle_test_command(&auth_manager, provider).await,
+            None => Err("Read-only checks are only supported for the claude provider, not all".into()),
+        },
+        Some(ExtendedLoginSubcommand::Validate { strict }) => {
+            handle_validate_command(*strict).await
+        }
+        Some(ExtendedLoginSubcommand::Doctor) => {
+            handle_doctor_command().await
+        }
+        Some(ExtendedLoginSubcommand::Whoami { verify }) => {
+            handle_whoami_command(*verify).await
+        }
+        Some(ExtendedLoginSubcommand::Verify { all, format }) => {
+            handle_verify_command(*all, *format).await
+        }
+        Some(ExtendedLoginSubcommand::Profile { action }) => {
+            handle_profile_command(action)
         }
         None => {
             // Main login flow
@@ -63,6 +101,117 @@ async fn handle_status_command(
     Ok(())
 }
 
+/// Handle profile subcommands
+fn handle_profile_command(action: &ProfileAction) -> Result<(), Box<dyn std::error::Error>> {
+    let codex_home = std::env::home_dir().unwrap_or_default().join(".codex");
+    let mut manager = UnifiedConfigManager::new(codex_home)?;
+
+    match action {
+        ProfileAction::Add { name } => {
+            let dir = manager.add_profile(name)?;
+            println!("✓ Created profile '{}' at {}", name, dir.display());
+            println!("Run `code auth profile use {}` and log in to store its credentials", name);
+        }
+        ProfileAction::List => {
+            let active = manager.active_profile()?;
+            let marker = |is_active: bool| if is_active { "*" } else { " " };
+            println!("{} {}", marker(active.is_none()), DEFAULT_PROFILE);
+            for name in manager.list_profiles()? {
+                println!("{} {}", marker(active.as_deref() == Some(name.as_str())), name);
+            }
+        }
+        ProfileAction::Use { name } => {
+            let profile = (name != DEFAULT_PROFILE).then_some(name.as_str());
+            manager.use_profile(profile)?;
+            println!("✓ Switched to profile '{}'", name);
+        }
+        ProfileAction::Remove { name } => {
+            manager.remove_profile(name)?;
+            println!("✓ Removed profile '{}' and its credentials", name);
+        }
+    }
+
+    Ok(())
+}
+
+/// Handle validate subcommand
+async fn handle_validate_command(strict: bool) -> Result<(), Box<dyn std::error::Error>> {
+    let codex_home = std::env::home_dir().unwrap_or_default().join(".codex");
+    let mut manager = UnifiedConfigManager::new(codex_home)?;
+    if strict {
+        manager.validator = ConfigValidator::new_strict();
+    }
+
+    let result = match manager.load_config().await {
+        Ok(config) => manager.validator.validate(&config)?,
+        // Schema problems in config.toml are reported the same way
+        Err(ConfigError::Validation(ValidationError::Invalid(result))) => result,
+        Err(e) => return Err(e.into()),
+    };
+
+    println!("{}", format_validation_result(&result));
+    if result.is_valid {
+        Ok(())
+    } else {
+        Err(result.summary().into())
+    }
+}
+
+/// Handle doctor subcommand
+async fn handle_doctor_command() -> Result<(), Box<dyn std::error::Error>> {
+    let codex_home = std::env::home_dir().unwrap_or_default().join(".codex");
+    let report = run_doctor(&codex_home).await;
+
+    println!("{}", format_doctor_report(&report));
+    if report.has_failures() {
+        Err("One or more critical checks failed".into())
+    } else {
+        Ok(())
+    }
+}
+
+/// Handle whoami subcommand
+async fn handle_whoami_command(verify: bool) -> Result<(), Box<dyn std::error::Error>> {
+    let codex_home = std::env::home_dir().unwrap_or_default().join(".codex");
+    let report = whoami(&codex_home, verify).await?;
+
+    print!("{}", format_whoami_report(&report));
+    Ok(())
+}
+
+/// Handle verify subcommand
+async fn handle_verify_command(all: bool, format: VerifyFormat) -> Result<(), Box<dyn std::error::Error>> {
+    let codex_home = std::env::home_dir().unwrap_or_default().join(".codex");
+    let report = CredentialVerifier::new(&codex_home).verify(all).await?;
+
+    match format {
+        VerifyFormat::Table => print!("{}", format_verification_table(&report)),
+        VerifyFormat::Json => println!("{}", serde_json::to_string_pretty(&report)?),
+    }
+    if report.has_invalid() {
+        Err("One or more credentials were refused".into())
+    } else {
+        Ok(())
+    }
+}
+
+/// Print why a code generation request would go to the provider it would
+///
+/// `choice` pins selection to one provider, as after `switch`; `None` explains the
+/// configured strategy. Nothing is recorded.
+async fn print_selection_explanation(choice: Option<ProviderType>) -> Result<(), Box<dyn std::error::Error>> {
+    let codex_home = std::env::home_dir().unwrap_or_default().join(".codex");
+    let mut manager = AuthenticationManager::new(codex_home).await?;
+    if let Some(provider_type) = choice {
+        manager.set_provider_strategy(ProviderSelectionStrategy::UserChoice(provider_type));
+    }
+
+    let context = convenience::code_generation_context(None);
+    let explanation = manager.explain_selection(&context).await?;
+    println!("{}", format_selection_explanation(&explanation));
+    Ok(())
+}
+
 /// Handle providers subcommand
 async fn handle_providers_command(
     auth_manager: &UnifiedAuthManager, 
@@ -126,7 +275,16 @@ async fn handle_test_command(
     provider: AuthProvider
 ) -> Result<(), Box<dyn std::error::Error>> {
     println!("Testing authentication for {} provider...", provider);
-    
+
+    // Claude failures are classified so an expired token isn't mistaken for an outage
+    if matches!(provider, AuthProvider::Claude) {
+        match auth_manager.check_claude_health().await {
+            Some(health) => print!("{}", format_claude_health(&health)),
+            None => println!("✗ Claude authentication is unavailable"),
+        }
+        return Ok(());
+    }
+
     let result = auth_manager.test_authentication(provider.clone()).await?;
     
     if result {
@@ -152,11 +310,44 @@ async fn handle_test_command(
     Ok(())
 }
 
+/// Handle `test --provider all`: test every configured provider at once
+///
+/// Uses the concurrency-bounded probes of `verify` on the active profile and exits
+/// non-zero unless every provider passes.
+async fn handle_test_all_command() -> Result<(), Box<dyn std::error::Error>> {
+    let codex_home = std::env::home_dir().unwrap_or_default().join(".codex");
+    println!("Testing authentication for every configured provider...");
+    let report = CredentialVerifier::new(&codex_home).verify(false).await?;
+    print!("{}", format_provider_test_report(&report));
+    report.provider_test_result().map_err(Into::into)
+}
+
+/// Handle `test --read-only`: report credential validity without changing any state
+async fn handle_read_only_test_command(
+    auth_manager: &UnifiedAuthManager,
+    provider: AuthProvider
+) -> Result<(), Box<dyn std::error::Error>> {
+    if !matches!(provider, AuthProvider::Claude) {
+        return Err(format!("Read-only checks are only supported for the claude provider, not {}", provider).into());
+    }
+
+    println!("Checking {} credentials (read-only)...", provider);
+    match auth_manager.check_claude_read_only().await? {
+        Some(check) => print!("{}", format_credential_check(&check)),
+        None => println!("✗ Claude authentication is unavailable"),
+    }
+    Ok(())
+}
+
 /// Handle main login command
 async fn handle_login_command(
     auth_manager: &mut UnifiedAuthManager, 
     cmd: &ExtendedLoginCommand
 ) -> Result<(), Box<dyn std::error::Error>> {
+    // Held until the flow finishes or fails, so a second `login` can't interleave writes
+    let codex_home = std::env::home_dir().unwrap_or_default().join(".codex");
+    let _lock = LoginLock::acquire(&codex_home)?;
+
     match cmd.provider {
         AuthProvider::OpenAI => {
             handle_openai_login(auth_manager, cmd).await
@@ -217,6 +408,22 @@ async fn handle_claude_login(
     auth_manager: &mut UnifiedAuthManager,
     cmd: &ExtendedLoginCommand
 ) -> Result<(), Box<dyn std::error::Error>> {
+    if cmd.api_key.is_none() {
+        if let Some(var) = auth_manager.detect_claude_env_key() {
+            println!("Found a Claude API key in {}.", var);
+            if confirm("Import it into secure storage and use it to log in?")? {
+                if let Some(imported) = auth_manager.import_claude_env_key(true)? {
+                    println!("✓ Imported API key ending in {} from {}", imported.key_suffix, imported.source_var);
+                    println!(
+                        "  {} was cleared for this process only; remove it from your shell profile to stop exposing it",
+                        imported.source_var
+                    );
+                    return Ok(());
+                }
+            }
+        }
+    }
+
     println!("Starting Claude authentication...");
     
     auth_manager.authenticate_claude(cmd.api_key.clone(), cmd.force).await?;
@@ -224,6 +431,17 @@ async fn handle_claude_login(
     Ok(())
 }
 
+/// Ask a yes/no question on the terminal; an empty answer means yes
+fn confirm(question: &str) -> std::io::Result<bool> {
+    use std::io::Write;
+
+    print!("{} [Y/n] ", question);
+    std::io::stdout().flush()?;
+    let mut answer = String::new();
+    std::io::stdin().read_line(&mut answer)?;
+    Ok(matches!(answer.trim().to_ascii_lowercase().as_str(), "" | "y" | "yes"))
+}
+
 /// Handle automatic provider selection login
 async fn handle_auto_login(
     auth_manager: &mut UnifiedAuthManager,
@@ -311,7 +529,7 @@ pub async fn run_extended_logout(cmd: ExtendedLogoutCommand) -> ! {
 
 /// Execute extended logout command logic
 async fn execute_extended_logout(cmd: &ExtendedLogoutCommand) -> Result<(), Box<dyn std::error::Error>> {
-    let mut auth_manager = UnifiedAuthManager::new(cmd.config_overrides.clone())?;
+    let mut auth_manager = UnifiedAuthManager::new()?;
 
     match (&cmd.provider, cmd.all) {
         (Some(AuthProvider::OpenAI), false) => {
@@ -319,7 +537,7 @@ async fn execute_extended_logout(cmd: &ExtendedLogoutCommand) -> Result<(), Box<
             println!("✓ Logged out from OpenAI provider");
         }
         (Some(AuthProvider::Claude), false) => {
-            logout_claude(&mut auth_manager)?;
+            logout_claude(&mut auth_manager).await?;
             println!("✓ Logged out from Claude provider");
         }
         (Some(AuthProvider::Auto), false) | (None, true) | (None, false) => {
@@ -340,7 +558,7 @@ async fn execute_extended_logout(cmd: &ExtendedLogoutCommand) -> Result<(), Box<
             }
 
             // Logout from Claude
-            match logout_claude(&mut auth_manager) {
+            match logout_claude(&mut auth_manager).await {
                 Ok(()) => {
                     println!("✓ Logged out from Claude provider");
                     success_count += 1;
@@ -378,16 +596,8 @@ fn logout_openai(config_overrides: &CliConfigOverrides) -> Result<(), Box<dyn st
 }
 
 /// Logout from Claude provider
-fn logout_claude(auth_manager: &mut UnifiedAuthManager) -> Result<(), Box<dyn std::error::Error>> {
-    // Implementation would call claude_auth.logout()
-    // For now, just remove the token file
-    let token_path = std::env::home_dir()
-        .unwrap_or_default()
-        .join(".codex")
-        .join("claude_tokens.json");
-    
-    if token_path.exists() {
-        std::fs::remove_file(&token_path)?;
+async fn logout_claude(auth_manager: &mut UnifiedAuthManager) -> Result<(), Box<dyn std::error::Error>> {
+    if auth_manager.logout_claude().await? {
         Ok(())
     } else {
         Err("Not logged in to Claude".into())
@@ -116,6 +116,7 @@ pub async fn run_extended_cli() -> anyhow::Result<()> {
                         crate::cli::ExtendedLoginSubcommand::Status {
                             provider: None,
                             detailed: false,
+                            why: false,
                         }
                     }
                 }),
@@ -220,6 +221,7 @@ pub mod cli_patch {
                         action: action.map(|_| crate::cli::ExtendedLoginSubcommand::Status {
                             provider: None,
                             detailed: false,
+                            why: false,
                         }),
                     };
                     crate::cli::run_extended_login(extended_cmd).await;
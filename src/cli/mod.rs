@@ -5,19 +5,34 @@
 //! selection and comprehensive management features.
 
 pub mod auth_commands;
+pub mod doctor;
+#[cfg(feature = "codex-cli")]
 pub mod extended_login;
+pub mod verify;
+pub mod whoami;
 
 pub use auth_commands::{
-    AuthProvider, ExtendedLoginCommand, ExtendedLoginSubcommand,
+    AuthProvider, ExtendedLoginCommand, ExtendedLoginSubcommand, ProfileAction, TestTarget,
     UnifiedAuthManager, AuthStatus, ProviderCapabilities, QuotaInfo,
-    format_auth_status, format_provider_capabilities, format_quota_info,
+    format_auth_status, format_provider_capabilities, format_quota_info, format_selection_explanation,
 };
 
+pub use doctor::{run_doctor, format_doctor_report, CheckStatus, DoctorCheck, DoctorReport};
+
+pub use verify::{
+    format_provider_test_report, format_verification_table, CredentialVerification, CredentialVerifier, VerificationReport, VerificationStatus,
+    VerifyFormat,
+};
+
+pub use whoami::{whoami, format_whoami_report, AuthMethod, ProviderIdentity, WhoamiReport};
+
+#[cfg(feature = "codex-cli")]
 pub use extended_login::{
     run_extended_login, run_extended_logout, ExtendedLogoutCommand,
 };
 
 /// CLI integration utilities
+#[cfg(feature = "codex-cli")]
 pub mod integration {
     use super::*;
     use clap::{Parser, Subcommand};
@@ -43,6 +58,9 @@ pub mod integration {
             /// Show detailed information including quotas
             #[arg(long = "detailed")]
             detailed: bool,
+            /// Explain which provider automatic selection would use, and why
+            #[arg(long = "why")]
+            why: bool,
         },
         
         /// List available providers
@@ -62,6 +80,9 @@ pub mod integration {
             /// Force switch even if target provider not authenticated
             #[arg(long = "force")]
             force: bool,
+            /// Explain how providers are selected after switching
+            #[arg(long = "explain")]
+            explain: bool,
         },
         
         /// Show quota information
@@ -70,17 +91,50 @@ pub mod integration {
             /// Provider to check quota for
             #[arg(long = "provider", value_enum, default_value_t = AuthProvider::Claude)]
             provider: AuthProvider,
-            /// Show detailed quota breakdown
-            #[arg(long = "detailed")]
+            /// Show detailed quota breakdown, including per-agent usage
+            #[arg(long = "detailed", visible_alias = "by-agent")]
             detailed: bool,
         },
         
         /// Test authentication
         #[command(name = "test")]
         Test {
-            /// Provider to test
-            #[arg(long = "provider", value_enum, default_value_t = AuthProvider::Auto)]
-            provider: AuthProvider,
+            /// Provider to test, or `all` for every configured provider
+            #[arg(long = "provider", value_enum, default_value_t = TestTarget::Auto)]
+            provider: TestTarget,
+            /// Check stored credentials without refreshing or writing anything
+            #[arg(long = "read-only")]
+            read_only: bool,
+        },
+
+        /// Diagnose authentication problems without changing anything
+        #[command(name = "doctor")]
+        Doctor,
+
+        /// Show the identity each provider is authenticated as
+        #[command(name = "whoami")]
+        Whoami {
+            /// Confirm the stored credentials with each provider's API
+            #[arg(long = "verify")]
+            verify: bool,
+        },
+
+        /// Check stored credentials against each provider's API
+        #[command(name = "verify")]
+        Verify {
+            /// Check the credentials of every profile, not just the active one
+            #[arg(long = "all")]
+            all: bool,
+            /// Output format
+            #[arg(long = "format", value_enum, default_value_t = VerifyFormat::Table)]
+            format: VerifyFormat,
+        },
+
+        /// Manage named credential profiles
+        #[command(name = "profile")]
+        Profile {
+            #[command(subcommand)]
+            action: ProfileAction,
         },
     }
 
@@ -103,13 +157,13 @@ pub mod integration {
             AuthCommands::Logout(logout_cmd) => {
                 run_extended_logout(logout_cmd).await
             }
-            AuthCommands::Status { provider, detailed } => {
+            AuthCommands::Status { provider, detailed, why } => {
                 let status_cmd = ExtendedLoginCommand {
                     config_overrides: cmd.config_overrides,
                     api_key: None,
                     provider: AuthProvider::Auto,
                     force: false,
-                    action: Some(ExtendedLoginSubcommand::Status { provider, detailed }),
+                    action: Some(ExtendedLoginSubcommand::Status { provider, detailed, why }),
                 };
                 run_extended_login(status_cmd).await
             }
@@ -123,13 +177,13 @@ pub mod integration {
                 };
                 run_extended_login(providers_cmd).await
             }
-            AuthCommands::Switch { provider, force } => {
+            AuthCommands::Switch { provider, force, explain } => {
                 let switch_cmd = ExtendedLoginCommand {
                     config_overrides: cmd.config_overrides,
                     api_key: None,
                     provider: AuthProvider::Auto,
                     force: false,
-                    action: Some(ExtendedLoginSubcommand::Switch { provider, force }),
+                    action: Some(ExtendedLoginSubcommand::Switch { provider, force, explain }),
                 };
                 run_extended_login(switch_cmd).await
             }
@@ -143,13 +197,53 @@ pub mod integration {
                 };
                 run_extended_login(quota_cmd).await
             }
-            AuthCommands::Test { provider } => {
+            AuthCommands::Doctor => {
+                let doctor_cmd = ExtendedLoginCommand {
+                    config_overrides: cmd.config_overrides,
+                    api_key: None,
+                    provider: AuthProvider::Auto,
+                    force: false,
+                    action: Some(ExtendedLoginSubcommand::Doctor),
+                };
+                run_extended_login(doctor_cmd).await
+            }
+            AuthCommands::Whoami { verify } => {
+                let whoami_cmd = ExtendedLoginCommand {
+                    config_overrides: cmd.config_overrides,
+                    api_key: None,
+                    provider: AuthProvider::Auto,
+                    force: false,
+                    action: Some(ExtendedLoginSubcommand::Whoami { verify }),
+                };
+                run_extended_login(whoami_cmd).await
+            }
+            AuthCommands::Verify { all, format } => {
+                let verify_cmd = ExtendedLoginCommand {
+                    config_overrides: cmd.config_overrides,
+                    api_key: None,
+                    provider: AuthProvider::Auto,
+                    force: false,
+                    action: Some(ExtendedLoginSubcommand::Verify { all, format }),
+                };
+                run_extended_login(verify_cmd).await
+            }
+            AuthCommands::Profile { action } => {
+                let profile_cmd = ExtendedLoginCommand {
+                    config_overrides: cmd.config_overrides,
+                    api_key: None,
+                    provider: AuthProvider::Auto,
+                    force: false,
+                    action: Some(ExtendedLoginSubcommand::Profile { action }),
+                };
+                run_extended_login(profile_cmd).await
+            }
+            AuthCommands::Test { provider, read_only } => {
                 let test_cmd = ExtendedLoginCommand {
                     config_overrides: cmd.config_overrides,
                     api_key: None,
                     provider: AuthProvider::Auto,
                     force: false,
-                    action: Some(ExtendedLoginSubcommand::Test { provider }),
+                    action: Some(ExtendedLoginSubcommand::Test { provider, read_only }),
                 };
                 run_extended_login(test_cmd).await
             }
@@ -158,6 +252,7 @@ pub mod integration {
 }
 
 /// Utilities for backward compatibility
+#[cfg(feature = "codex-cli")]
 pub mod compat {
     use super::*;
     use codex_common::CliConfigOverrides;
@@ -205,15 +300,13 @@ mod tests {
 
     #[tokio::test]
     async fn test_auth_manager_creation() {
-        let config_overrides = CliConfigOverrides::default();
-        let auth_manager = UnifiedAuthManager::new(config_overrides);
+        let auth_manager = UnifiedAuthManager::new();
         assert!(auth_manager.is_ok());
     }
 
     #[tokio::test]
     async fn test_provider_capabilities() {
-        let config_overrides = CliConfigOverrides::default();
-        let auth_manager = UnifiedAuthManager::new(config_overrides).unwrap();
+        let auth_manager = UnifiedAuthManager::new().unwrap();
         
         let capabilities = auth_manager.get_provider_capabilities(false);
         assert!(!capabilities.is_empty());
@@ -243,6 +336,7 @@ mod tests {
                 remaining: Some(950000),
                 reset_time: None,
                 percentage_used: Some(5.0),
+                agent_usage: None,
             }),
             last_used: None,
             expires_at: None,
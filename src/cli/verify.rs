@@ -0,0 +1,405 @@
+//! `code auth verify`: check every stored credential against its provider's API
+//!
+//! Credentials are probed concurrently, at most `status_refresh_concurrency` at a time
+//! and each within `status_probe_timeout_ms`, like a provider status refresh. Only a
+//! credential the provider refused makes the command exit non-zero; one that couldn't be
+//! checked because of a network, throttling or server error is reported as unverified.
+
+use chrono::{DateTime, Utc};
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use crate::auth::migration::validator::TokenValidationResult;
+use crate::auth::migration::{MigrationConfig, MigrationValidator};
+use crate::auth::UnifiedAuthConfig;
+use crate::configuration::profiles::profile_dir;
+use crate::configuration::{active_profile_home, ConfigError, UnifiedConfigManager, DEFAULT_PROFILE};
+
+/// Providers whose stored credentials can be probed
+const VERIFIED_PROVIDERS: [&str; 2] = ["openai", "claude"];
+
+/// Output format of `code auth verify`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum VerifyFormat {
+    #[default]
+    Table,
+    Json,
+}
+
+/// Outcome of probing one credential
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum VerificationStatus {
+    Valid,
+    /// Refused by the provider, expired or unreadable: critical
+    Invalid,
+    /// Not checked because the provider couldn't be reached or asked to retry later
+    Unverified,
+}
+
+impl fmt::Display for VerificationStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            VerificationStatus::Valid => "valid",
+            VerificationStatus::Invalid => "invalid",
+            VerificationStatus::Unverified => "unverified",
+        })
+    }
+}
+
+/// One probed credential
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CredentialVerification {
+    pub profile: String,
+    pub provider: String,
+    /// `api_key`, `access_token` or `oauth_token`
+    pub credential: String,
+    pub status: VerificationStatus,
+    pub latency_ms: u64,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub error: Option<String>,
+}
+
+impl CredentialVerification {
+    fn new(profile: &str, result: TokenValidationResult, latency: Duration) -> Self {
+        let status = if result.valid {
+            VerificationStatus::Valid
+        } else if result.is_unverified() || result.is_retryable() {
+            VerificationStatus::Unverified
+        } else {
+            VerificationStatus::Invalid
+        };
+
+        Self {
+            profile: profile.to_string(),
+            provider: result.provider,
+            credential: result.token_type,
+            status,
+            latency_ms: latency.as_millis() as u64,
+            expires_at: result.expires_at,
+            error: result.error.or_else(|| (status == VerificationStatus::Invalid).then(|| "expired".to_string())),
+        }
+    }
+
+    fn timed_out(profile: &str, provider: &str, probe_timeout: Duration) -> Self {
+        Self {
+            profile: profile.to_string(),
+            provider: provider.to_string(),
+            credential: "credentials".to_string(),
+            status: VerificationStatus::Unverified,
+            latency_ms: probe_timeout.as_millis() as u64,
+            expires_at: None,
+            error: Some(format!("Check timed out after {}ms", probe_timeout.as_millis())),
+        }
+    }
+}
+
+/// Every probed credential, by profile then provider
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct VerificationReport {
+    pub results: Vec<CredentialVerification>,
+}
+
+impl VerificationReport {
+    /// Whether any credential was refused, so the command exits non-zero
+    pub fn has_invalid(&self) -> bool {
+        self.count(VerificationStatus::Invalid) > 0
+    }
+
+    pub fn count(&self, status: VerificationStatus) -> usize {
+        self.results.iter().filter(|result| result.status == status).count()
+    }
+
+    pub fn get(&self, profile: &str, provider: &str) -> Option<&CredentialVerification> {
+        self.results.iter().find(|result| result.profile == profile && result.provider == provider)
+    }
+
+    /// Outcome of `auth test --provider all`: unlike `verify`, a provider that couldn't be
+    /// checked fails the test too, as does having nothing to test
+    pub fn provider_test_result(&self) -> Result<(), String> {
+        if self.results.is_empty() {
+            return Err("No configured providers to test".to_string());
+        }
+        let failed: Vec<&str> = self
+            .results
+            .iter()
+            .filter(|result| result.status != VerificationStatus::Valid)
+            .map(|result| result.provider.as_str())
+            .collect();
+        if failed.is_empty() {
+            Ok(())
+        } else {
+            Err(format!("Authentication test failed for {}", failed.join(", ")))
+        }
+    }
+}
+
+/// Probes the credentials stored in one or every profile
+#[derive(Debug, Clone)]
+pub struct CredentialVerifier {
+    codex_home: PathBuf,
+    concurrency: usize,
+    probe_timeout: Duration,
+    openai_probe_endpoint: Option<String>,
+    claude_probe_endpoint: Option<String>,
+}
+
+impl CredentialVerifier {
+    /// Verifier with the status refresh limits of the default [`UnifiedAuthConfig`]
+    pub fn new(codex_home: &Path) -> Self {
+        let config = UnifiedAuthConfig::default();
+        Self {
+            codex_home: codex_home.to_path_buf(),
+            concurrency: config.status_refresh_concurrency.max(1),
+            probe_timeout: Duration::from_millis(config.status_probe_timeout_ms),
+            openai_probe_endpoint: None,
+            claude_probe_endpoint: None,
+        }
+    }
+
+    /// Probe at most `concurrency` credentials at a time
+    pub fn with_concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency.max(1);
+        self
+    }
+
+    pub fn with_probe_timeout(mut self, probe_timeout: Duration) -> Self {
+        self.probe_timeout = probe_timeout;
+        self
+    }
+
+    /// Probe endpoints other than the providers' model listings, for tests
+    pub fn with_probe_endpoints(mut self, openai: impl Into<String>, claude: impl Into<String>) -> Self {
+        self.openai_probe_endpoint = Some(openai.into());
+        self.claude_probe_endpoint = Some(claude.into());
+        self
+    }
+
+    /// Probe the credentials of every profile with `all`, otherwise of the active one
+    pub async fn verify(&self, all: bool) -> Result<VerificationReport, ConfigError> {
+        let profiles = if all { self.all_profiles()? } else { vec![self.active_profile()?] };
+
+        let mut pending = profiles
+            .iter()
+            .flat_map(|(profile, home)| VERIFIED_PROVIDERS.map(|provider| (profile.clone(), home.clone(), provider)))
+            .collect::<Vec<_>>()
+            .into_iter();
+        let mut probes = tokio::task::JoinSet::new();
+        let mut results = Vec::new();
+
+        loop {
+            while probes.len() < self.concurrency {
+                let Some((profile, home, provider)) = pending.next() else {
+                    break;
+                };
+                let validator = self.validator(&home);
+                let probe_timeout = self.probe_timeout;
+                probes.spawn(async move {
+                    let start = Instant::now();
+                    match tokio::time::timeout(probe_timeout, validator.probe_stored_credential(provider)).await {
+                        Ok(result) => result.map(|result| CredentialVerification::new(&profile, result, start.elapsed())),
+                        Err(_) => Some(CredentialVerification::timed_out(&profile, provider, probe_timeout)),
+                    }
+                });
+            }
+
+            match probes.join_next().await {
+                Some(Ok(Some(verification))) => results.push(verification),
+                // No credential stored for this provider
+                Some(Ok(None)) => {}
+                Some(Err(e)) => tracing::warn!(error = %e, "Credential check did not complete"),
+                None => break,
+            }
+        }
+
+        let position = |profile: &str| profiles.iter().position(|(name, _)| name == profile);
+        results.sort_by(|a, b| (position(&a.profile), &a.provider).cmp(&(position(&b.profile), &b.provider)));
+        Ok(VerificationReport { results })
+    }
+
+    /// `default` in codex_home, then each named profile
+    fn all_profiles(&self) -> Result<Vec<(String, PathBuf)>, ConfigError> {
+        let mut profiles = vec![(DEFAULT_PROFILE.to_string(), self.codex_home.clone())];
+        for name in UnifiedConfigManager::new(self.codex_home.clone())?.list_profiles()? {
+            let home = profile_dir(&self.codex_home, &name);
+            profiles.push((name, home));
+        }
+        Ok(profiles)
+    }
+
+    fn active_profile(&self) -> Result<(String, PathBuf), ConfigError> {
+        let name = UnifiedConfigManager::new(self.codex_home.clone())?
+            .active_profile()?
+            .unwrap_or_else(|| DEFAULT_PROFILE.to_string());
+        Ok((name, active_profile_home(&self.codex_home)?))
+    }
+
+    /// Validator probing the credentials stored directly in `home`
+    fn validator(&self, home: &Path) -> MigrationValidator {
+        // Without the override the Claude probe would follow the active profile
+        let config = MigrationConfig { auth_file_path: Some(home.join("claude_auth.json")), ..MigrationConfig::default() };
+        let mut validator = MigrationValidator::new(home, &config);
+        if let Some(endpoint) = &self.openai_probe_endpoint {
+            validator = validator.with_openai_probe_endpoint(endpoint.clone());
+        }
+        if let Some(endpoint) = &self.claude_probe_endpoint {
+            validator = validator.with_claude_probe_endpoint(endpoint.clone());
+        }
+        validator
+    }
+}
+
+/// Format the results as a table followed by a summary line
+pub fn format_verification_table(report: &VerificationReport) -> String {
+    if report.results.is_empty() {
+        return "No stored credentials found. Run `code auth login` to set one up.\n".to_string();
+    }
+
+    let rows: Vec<[String; 5]> = report
+        .results
+        .iter()
+        .map(|result| {
+            [
+                result.profile.clone(),
+                result.provider.clone(),
+                result.status.to_string(),
+                format!("{}ms", result.latency_ms),
+                result.expires_at.map_or_else(|| "-".to_string(), |at| at.format("%Y-%m-%d %H:%M UTC").to_string()),
+            ]
+        })
+        .collect();
+    let header = ["PROFILE", "PROVIDER", "STATUS", "LATENCY", "EXPIRES"].map(str::to_string);
+    let widths: Vec<usize> = (0..header.len())
+        .map(|column| rows.iter().chain([&header]).map(|row| row[column].len()).max().unwrap_or(0))
+        .collect();
+    let format_row = |row: &[String; 5]| {
+        let cells: Vec<String> = row.iter().zip(&widths).map(|(cell, width)| format!("{:<width$}", cell)).collect();
+        format!("{}\n", cells.join("  ").trim_end())
+    };
+
+    let mut output = format_row(&header);
+    for (row, result) in rows.iter().zip(&report.results) {
+        output.push_str(&format_row(row));
+        if let (Some(error), false) = (&result.error, result.status == VerificationStatus::Valid) {
+            output.push_str(&format!("  └ {} {}: {}\n", result.provider, result.credential.replace('_', " "), error));
+        }
+    }
+
+    output.push_str(&format!(
+        "\n{} valid, {} invalid, {} unverified\n",
+        report.count(VerificationStatus::Valid),
+        report.count(VerificationStatus::Invalid),
+        report.count(VerificationStatus::Unverified),
+    ));
+    output
+}
+
+/// Format the results as the pass/fail report of `auth test --provider all`
+pub fn format_provider_test_report(report: &VerificationReport) -> String {
+    if report.results.is_empty() {
+        return "No configured providers to test. Run `code auth login` to set one up.\n".to_string();
+    }
+
+    let width = report.results.iter().map(|result| result.provider.len()).max().unwrap_or(0);
+    let mut output = String::new();
+    for result in &report.results {
+        let (marker, outcome) = match result.status {
+            VerificationStatus::Valid => ("✓", "passed".to_string()),
+            VerificationStatus::Invalid => ("✗", "failed".to_string()),
+            VerificationStatus::Unverified => ("✗", "unreachable, retry later".to_string()),
+        };
+        output.push_str(&format!("{} {:<width$}  {} ({}ms)", marker, result.provider, outcome, result.latency_ms));
+        if let (Some(error), false) = (&result.error, result.status == VerificationStatus::Valid) {
+            output.push_str(&format!(": {}", error));
+        }
+        output.push('\n');
+    }
+
+    let passed = report.count(VerificationStatus::Valid);
+    output.push_str(&format!("\n{} passed, {} failed\n", passed, report.results.len() - passed));
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+    use wiremock::matchers::{header, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    #[tokio::test]
+    async fn test_verify_all_reports_revoked_profile_as_invalid() {
+        let temp_dir = tempdir().unwrap();
+        let config_manager = UnifiedConfigManager::new(temp_dir.path().to_path_buf()).unwrap();
+        let work_home = config_manager.add_profile("work").unwrap();
+        std::fs::write(temp_dir.path().join("claude_auth.json"), r#"{"api_key": "sk-ant-good"}"#).unwrap();
+        std::fs::write(work_home.join("claude_auth.json"), r#"{"api_key": "sk-ant-revoked"}"#).unwrap();
+
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(header("x-api-key", "sk-ant-good"))
+            .respond_with(ResponseTemplate::new(200))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET")).respond_with(ResponseTemplate::new(401)).mount(&server).await;
+
+        let verifier = CredentialVerifier::new(temp_dir.path())
+            .with_probe_endpoints(format!("{}/openai", server.uri()), format!("{}/claude", server.uri()));
+
+        let report = verifier.verify(true).await.unwrap();
+        assert_eq!(report.results.len(), 2);
+        assert_eq!(report.get(DEFAULT_PROFILE, "claude").unwrap().status, VerificationStatus::Valid);
+        let work = report.get("work", "claude").unwrap();
+        assert_eq!(work.status, VerificationStatus::Invalid);
+        assert_eq!(work.error.as_deref(), Some("HTTP 401 Unauthorized"));
+        assert!(report.has_invalid());
+
+        let table = format_verification_table(&report);
+        let lines: Vec<&str> = table.lines().collect();
+        assert!(lines[0].starts_with("PROFILE  PROVIDER  STATUS"));
+        assert!(lines[1].starts_with("default  claude    valid"));
+        assert!(lines[2].starts_with("work     claude    invalid"));
+        assert_eq!(lines[3], "  └ claude api key: HTTP 401 Unauthorized");
+        assert!(table.ends_with("\n1 valid, 1 invalid, 0 unverified\n"));
+
+        let json: serde_json::Value = serde_json::to_value(&report).unwrap();
+        assert_eq!(json["results"][1]["status"], "invalid");
+
+        // Only the active profile, which is still the default one
+        let report = verifier.verify(false).await.unwrap();
+        assert_eq!(report.results.len(), 1);
+        assert!(!report.has_invalid());
+    }
+
+    #[tokio::test]
+    async fn test_provider_test_report_covers_every_provider_and_fails_on_one() {
+        let temp_dir = tempdir().unwrap();
+        std::fs::write(temp_dir.path().join("auth.json"), r#"{"OPENAI_API_KEY": "sk-openai"}"#).unwrap();
+        std::fs::write(temp_dir.path().join("claude_auth.json"), r#"{"api_key": "sk-ant-revoked"}"#).unwrap();
+
+        let server = MockServer::start().await;
+        Mock::given(method("GET")).and(path("/openai")).respond_with(ResponseTemplate::new(200)).mount(&server).await;
+        Mock::given(method("GET")).and(path("/claude")).respond_with(ResponseTemplate::new(401)).mount(&server).await;
+
+        let report = CredentialVerifier::new(temp_dir.path())
+            .with_probe_endpoints(format!("{}/openai", server.uri()), format!("{}/claude", server.uri()))
+            .verify(false)
+            .await
+            .unwrap();
+
+        let output = format_provider_test_report(&report);
+        let lines: Vec<&str> = output.lines().collect();
+        assert!(lines[0].starts_with("✗ claude  failed ("), "{}", output);
+        assert!(lines[0].ends_with("ms): HTTP 401 Unauthorized"), "{}", output);
+        assert!(lines[1].starts_with("✓ openai  passed ("), "{}", output);
+        assert!(output.ends_with("\n1 passed, 1 failed\n"));
+
+        // The single failure makes the command exit non-zero
+        assert_eq!(report.provider_test_result(), Err("Authentication test failed for claude".to_string()));
+        assert!(VerificationReport::default().provider_test_result().is_err());
+    }
+}
@@ -0,0 +1,262 @@
+//! `code auth whoami`: the identity each provider is authenticated as
+//!
+//! Built from stored credentials and cached provider status, so it works offline.
+//! With `--verify` each stored credential is also confirmed against its provider's API.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::path::Path;
+
+use crate::auth::claude::{ClaudeAuth, ClaudeAuthMode};
+use crate::auth::migration::{MigrationConfig, MigrationValidator};
+use crate::auth::{convenience, AuthenticationManager, CredentialKind, CredentialResolver, CredentialSource, UnifiedAuthError};
+use crate::configuration::{ProviderType, UnifiedConfigManager, DEFAULT_PROFILE};
+
+/// How a provider is authenticated
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AuthMethod {
+    ApiKey,
+    OAuth,
+    /// Encrypted secure storage, whose credential kind is only known after decrypting it
+    Session,
+}
+
+impl fmt::Display for AuthMethod {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            AuthMethod::ApiKey => "api_key",
+            AuthMethod::OAuth => "oauth",
+            AuthMethod::Session => "session",
+        })
+    }
+}
+
+/// Who one provider is authenticated as
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProviderIdentity {
+    pub provider: ProviderType,
+    pub auth_method: Option<AuthMethod>,
+    pub account: Option<String>,
+    pub subscription_tier: Option<String>,
+    pub expires_at: Option<DateTime<Utc>>,
+    /// Environment variable or file the credential was read from
+    pub source: Option<String>,
+    /// Server answer with `--verify`; `None` when not checked
+    pub verified: Option<bool>,
+    pub verification_error: Option<String>,
+}
+
+impl ProviderIdentity {
+    fn new(provider: ProviderType) -> Self {
+        Self {
+            provider,
+            auth_method: None,
+            account: None,
+            subscription_tier: None,
+            expires_at: None,
+            source: None,
+            verified: None,
+            verification_error: None,
+        }
+    }
+}
+
+/// Identities of every authenticated provider under the active profile
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WhoamiReport {
+    pub profile: String,
+    /// Provider automatic selection would use for code generation
+    pub selected: Option<ProviderType>,
+    pub identities: Vec<ProviderIdentity>,
+}
+
+impl WhoamiReport {
+    pub fn get(&self, provider: &ProviderType) -> Option<&ProviderIdentity> {
+        self.identities.iter().find(|identity| identity.provider == *provider)
+    }
+}
+
+/// Report the identities stored under `codex_home`; `verify` also asks each provider
+pub async fn whoami(codex_home: &Path, verify: bool) -> Result<WhoamiReport, Box<dyn std::error::Error>> {
+    whoami_with_env(codex_home, verify, |var| std::env::var(var).ok()).await
+}
+
+async fn whoami_with_env(
+    codex_home: &Path,
+    verify: bool,
+    env: impl Fn(&str) -> Option<String>,
+) -> Result<WhoamiReport, Box<dyn std::error::Error>> {
+    let profile = UnifiedConfigManager::new(codex_home.to_path_buf())?
+        .active_profile()?
+        .unwrap_or_else(|| DEFAULT_PROFILE.to_string());
+
+    let manager = AuthenticationManager::new(codex_home.to_path_buf()).await?;
+    let status = manager.get_system_status().await?;
+    let selected = match manager.explain_selection(&convenience::code_generation_context(None)).await {
+        Ok(explanation) => explanation.selected,
+        // Providers aren't loaded until the migration has run
+        Err(UnifiedAuthError::ConfigError(_)) => None,
+        Err(e) => return Err(e.into()),
+    };
+
+    let mut providers: Vec<_> = status.provider_status.values().filter(|status| status.authenticated).collect();
+    providers.sort_by(|a, b| a.provider_type.id().cmp(b.provider_type.id()));
+
+    let mut identities = Vec::new();
+    for provider_status in providers {
+        let mut identity = ProviderIdentity::new(provider_status.provider_type.clone());
+        identity.subscription_tier = provider_status.subscription_tier.clone();
+        match provider_status.provider_type {
+            ProviderType::OpenAI => describe_openai(codex_home, &mut identity),
+            ProviderType::Claude => describe_claude(codex_home, &env, &mut identity),
+            ProviderType::Custom(_) => {}
+        }
+        identities.push(identity);
+    }
+
+    if verify {
+        let validator = MigrationValidator::new(codex_home, &MigrationConfig::default());
+        for result in validator.probe_stored_credentials().await {
+            let provider = ProviderType::from(result.provider.as_str());
+            if let Some(identity) = identities.iter_mut().find(|identity| identity.provider == provider) {
+                identity.verified = Some(result.valid);
+                identity.verification_error = result.error;
+            }
+        }
+    }
+
+    Ok(WhoamiReport { profile, selected, identities })
+}
+
+/// Method and account of the OpenAI credential in auth.json
+fn describe_openai(codex_home: &Path, identity: &mut ProviderIdentity) {
+    let auth_file = codex_home.join("auth.json");
+    let Some(auth_data) = std::fs::read_to_string(&auth_file)
+        .ok()
+        .and_then(|content| serde_json::from_str::<serde_json::Value>(&content).ok())
+    else {
+        return;
+    };
+
+    identity.source = Some(auth_file.display().to_string());
+    if auth_data.get("OPENAI_API_KEY").and_then(|v| v.as_str()).is_some() {
+        identity.auth_method = Some(AuthMethod::ApiKey);
+    } else if let Some(tokens) = auth_data.get("tokens") {
+        identity.auth_method = Some(AuthMethod::OAuth);
+        identity.account = tokens.get("account_id").and_then(|v| v.as_str()).map(str::to_string);
+    }
+}
+
+/// Method, source and token expiry of the Claude credential in use
+fn describe_claude(codex_home: &Path, env: &impl Fn(&str) -> Option<String>, identity: &mut ProviderIdentity) {
+    let Some(active) = CredentialResolver::default().resolve_with_env(codex_home, env).active else {
+        return;
+    };
+
+    identity.source = Some(active.location.clone());
+    identity.auth_method = Some(match active.kind {
+        Some(CredentialKind::ApiKey) => AuthMethod::ApiKey,
+        Some(CredentialKind::OAuth) => AuthMethod::OAuth,
+        None => AuthMethod::Session,
+    });
+
+    // Only claude_auth.json records the token expiry
+    if matches!(active.source, CredentialSource::Profile | CredentialSource::File) {
+        if let Ok(Some(claude_auth)) = ClaudeAuth::from_codex_home(codex_home, ClaudeAuthMode::MaxSubscription, "whoami") {
+            if let Some(tokens) = claude_auth.oauth_tokens.filter(|_| active.kind == Some(CredentialKind::OAuth)) {
+                identity.expires_at = Some(tokens.expires_at);
+                identity.subscription_tier.get_or_insert(tokens.subscription_tier);
+            }
+        }
+    }
+}
+
+/// Format the identities for display
+pub fn format_whoami_report(report: &WhoamiReport) -> String {
+    let mut output = String::new();
+
+    output.push_str(&format!("Profile: {}\n", report.profile));
+    output.push_str(&format!(
+        "Selected provider: {}\n",
+        report.selected.as_ref().map_or("none", |provider| provider.id())
+    ));
+
+    if report.identities.is_empty() {
+        output.push_str("\nNot authenticated with any provider. Run `code auth login` to set one up.\n");
+        return output;
+    }
+
+    for identity in &report.identities {
+        let marker = if report.selected.as_ref() == Some(&identity.provider) { " (selected)" } else { "" };
+        output.push_str(&format!("\n{}{}:\n", identity.provider.id(), marker));
+        output.push_str(&format!(
+            "  Method: {}\n",
+            identity.auth_method.map_or_else(|| "unknown".to_string(), |method| method.to_string())
+        ));
+        if let Some(ref account) = identity.account {
+            output.push_str(&format!("  Account: {}\n", account));
+        }
+        if let Some(ref tier) = identity.subscription_tier {
+            output.push_str(&format!("  Subscription: {}\n", tier));
+        }
+        if let Some(expires_at) = identity.expires_at {
+            output.push_str(&format!("  Token expires: {}\n", expires_at.format("%Y-%m-%d %H:%M:%S UTC")));
+        }
+        if let Some(ref source) = identity.source {
+            output.push_str(&format!("  Source: {}\n", source));
+        }
+        match (identity.verified, &identity.verification_error) {
+            (Some(true), _) => output.push_str("  Verified: ✓ accepted by the server\n"),
+            (Some(false), Some(error)) => output.push_str(&format!("  Verified: ✗ {}\n", error)),
+            (Some(false), None) => output.push_str("  Verified: ✗ rejected by the server\n"),
+            (None, _) => {}
+        }
+    }
+
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[tokio::test]
+    async fn test_reports_claude_max_oauth_identity_in_active_profile() {
+        let temp_dir = tempdir().unwrap();
+        let mut config_manager = UnifiedConfigManager::new(temp_dir.path().to_path_buf()).unwrap();
+        config_manager.add_profile("work").unwrap();
+        config_manager.use_profile(Some("work")).unwrap();
+
+        let expires_at = Utc::now() + chrono::Duration::hours(6);
+        let auth_file = config_manager.profile_home().unwrap().join("claude_auth.json");
+        let auth_data = serde_json::json!({
+            "oauth_tokens": {
+                "access_token": "max-access-token",
+                "refresh_token": "max-refresh-token",
+                "expires_at": expires_at,
+                "subscription_tier": "max",
+                "token_type": "Bearer",
+                "scope": ["api", "subscription"]
+            }
+        });
+        std::fs::write(&auth_file, auth_data.to_string()).unwrap();
+
+        let report = whoami_with_env(temp_dir.path(), false, |_| None).await.unwrap();
+
+        assert_eq!(report.profile, "work");
+        assert_eq!(report.selected, Some(ProviderType::Claude));
+        let claude = report.get(&ProviderType::Claude).unwrap();
+        assert_eq!(claude.auth_method, Some(AuthMethod::OAuth));
+        assert_eq!(claude.subscription_tier.as_deref(), Some("max"));
+        assert_eq!(claude.expires_at.map(|at| at.timestamp()), Some(expires_at.timestamp()));
+        assert_eq!(claude.source.as_deref(), Some(auth_file.display().to_string().as_str()));
+        assert_eq!(claude.verified, None);
+
+        let output = format_whoami_report(&report);
+        assert!(output.starts_with("Profile: work\nSelected provider: claude\n"));
+        assert!(output.contains("claude (selected):\n  Method: oauth\n  Subscription: max\n"));
+    }
+}
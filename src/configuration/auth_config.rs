@@ -4,7 +4,8 @@
 //! both OpenAI and Claude providers with intelligent selection strategies.
 
 use serde::{Deserialize, Serialize};
-use chrono::{DateTime, Utc, Duration};
+use chrono::{DateTime, FixedOffset, Local, NaiveTime, Timelike, Utc, Duration};
+use std::collections::BTreeMap;
 use std::fmt;
 
 /// Core authentication configuration
@@ -39,6 +40,19 @@ pub struct AuthConfig {
     
     /// Cache provider capabilities for this duration
     pub provider_cache_duration: Duration,
+
+    /// Profile whose credentials are in use; `None` uses those directly in codex_home
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub active_profile: Option<String>,
+
+    /// Logical model names, e.g. `sonnet-latest`, resolved to concrete ids per provider
+    #[serde(default, skip_serializing_if = "ModelAliases::is_empty")]
+    pub model_aliases: ModelAliases,
+
+    /// Providers this machine may use; unset enables every provider. Disabled providers
+    /// keep their credentials but are never selected and are skipped in fallback chains.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub enabled_providers: Option<Vec<ProviderType>>,
 }
 
 impl Default for AuthConfig {
@@ -54,6 +68,9 @@ impl Default for AuthConfig {
             auto_refresh_tokens: true,
             last_provider_check: None,
             provider_cache_duration: Duration::minutes(15),
+            active_profile: None,
+            model_aliases: ModelAliases::default(),
+            enabled_providers: None,
         }
     }
 }
@@ -119,24 +136,162 @@ impl AuthConfig {
             None => false,
         }
     }
+
+    pub fn is_provider_enabled(&self, provider: &ProviderType) -> bool {
+        self.enabled_providers.as_ref().is_none_or(|enabled| enabled.contains(provider))
+    }
+
+    /// Enabled providers of a [`FallbackStrategy::ChainOrdered`] chain, in order; empty for
+    /// every other strategy
+    pub fn fallback_chain(&self) -> Vec<ProviderType> {
+        match &self.fallback_strategy {
+            FallbackStrategy::ChainOrdered { providers } => {
+                providers.iter().filter(|provider| self.is_provider_enabled(provider)).cloned().collect()
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    /// Concrete model id to request from `provider`, see [`ModelAliases::resolve`]
+    pub fn resolve_model(&self, provider: &ProviderType, model: &str) -> Result<String, ModelAliasError> {
+        self.model_aliases.resolve(provider, model)
+    }
+}
+
+/// Suffix marking a model name as an alias that must be defined, e.g. `sonnet-latest`
+pub const MODEL_ALIAS_SUFFIX: &str = "-latest";
+
+/// Logical model names mapped to concrete model ids, keyed by provider id
+///
+/// Serialized as a table per provider:
+///
+/// ```toml
+/// [model_aliases.claude]
+/// sonnet-latest = "claude-sonnet-4-20250514"
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct ModelAliases(BTreeMap<String, BTreeMap<String, String>>);
+
+impl ModelAliases {
+    /// Map `alias` to `model` for `provider`, replacing any previous mapping
+    pub fn insert(&mut self, provider: &ProviderType, alias: impl Into<String>, model: impl Into<String>) {
+        self.0
+            .entry(provider.id().to_string())
+            .or_default()
+            .insert(alias.into(), model.into());
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.values().all(BTreeMap::is_empty)
+    }
+
+    /// Aliases defined for `provider`, in name order
+    pub fn for_provider(&self, provider: &ProviderType) -> impl Iterator<Item = (&str, &str)> {
+        self.0
+            .get(provider.id())
+            .into_iter()
+            .flatten()
+            .map(|(alias, model)| (alias.as_str(), model.as_str()))
+    }
+
+    /// Concrete model id for `model` on `provider`
+    ///
+    /// Defined aliases are replaced by their target and other names pass through
+    /// unchanged, except names ending in [`MODEL_ALIAS_SUFFIX`], which are always
+    /// aliases and fail with [`ModelAliasError::Undefined`] when missing.
+    pub fn resolve(&self, provider: &ProviderType, model: &str) -> Result<String, ModelAliasError> {
+        if let Some(target) = self.0.get(provider.id()).and_then(|aliases| aliases.get(model)) {
+            return Ok(target.clone());
+        }
+        if model.ends_with(MODEL_ALIAS_SUFFIX) {
+            return Err(ModelAliasError::Undefined {
+                provider: provider.id().to_string(),
+                alias: model.to_string(),
+                defined: self.for_provider(provider).map(|(alias, _)| alias.to_string()).collect(),
+            });
+        }
+        Ok(model.to_string())
+    }
+}
+
+/// Model alias resolution errors
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+pub enum ModelAliasError {
+    #[error(
+        "Model alias '{alias}' is not defined for {provider} (defined: {}); add it under [model_aliases.{provider}]",
+        list_or_none(.defined)
+    )]
+    Undefined { provider: String, alias: String, defined: Vec<String> },
+}
+
+fn list_or_none(names: &[String]) -> String {
+    if names.is_empty() {
+        "none".to_string()
+    } else {
+        names.join(", ")
+    }
 }
 
 /// Authentication provider types
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+///
+/// Serialized as the bare provider id, e.g. `"claude"` or `"local-gateway"`. The
+/// built-in ids are matched case-insensitively so configs written as `"OpenAI"` or
+/// `"Claude"` still load.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(into = "String", try_from = "String")]
 pub enum ProviderType {
-    #[serde(rename = "openai")]
     OpenAI,
-    #[serde(rename = "claude")]
     Claude,
+    /// Provider outside the built-ins, identified by its id
+    Custom(String),
 }
 
-impl fmt::Display for ProviderType {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+impl ProviderType {
+    /// Provider named `id`, resolving built-in ids (and `anthropic`) to their variants
+    pub fn from_id(id: &str) -> Option<Self> {
+        let id = id.trim();
+        match id.to_lowercase().as_str() {
+            "" => None,
+            "openai" => Some(ProviderType::OpenAI),
+            "claude" | "anthropic" => Some(ProviderType::Claude),
+            _ => Some(ProviderType::Custom(id.to_string())),
+        }
+    }
+
+    /// Id used in configuration files and on the command line
+    pub fn id(&self) -> &str {
         match self {
-            ProviderType::OpenAI => write!(f, "openai"),
-            ProviderType::Claude => write!(f, "claude"),
+            ProviderType::OpenAI => "openai",
+            ProviderType::Claude => "claude",
+            ProviderType::Custom(id) => id,
         }
     }
+
+    /// Whether this is one of the providers shipped with the CLI
+    pub fn is_builtin(&self) -> bool {
+        !matches!(self, ProviderType::Custom(_))
+    }
+}
+
+impl fmt::Display for ProviderType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.id())
+    }
+}
+
+impl From<ProviderType> for String {
+    fn from(provider: ProviderType) -> Self {
+        provider.id().to_string()
+    }
+}
+
+impl TryFrom<String> for ProviderType {
+    type Error = String;
+
+    fn try_from(id: String) -> Result<Self, Self::Error> {
+        ProviderType::from_id(&id).ok_or_else(|| "provider id must not be empty".to_string())
+    }
 }
 
 impl From<&str> for ProviderType {
@@ -174,6 +329,13 @@ pub enum ProviderPreference {
     /// Use explicit user preference
     #[serde(rename = "user_preference")]
     UserPreference(ProviderType),
+
+    /// Prefer providers by time of day, using `otherwise` outside every window
+    #[serde(rename = "scheduled")]
+    Scheduled {
+        schedule: ProviderSchedule,
+        otherwise: Box<ProviderPreference>,
+    },
 }
 
 impl Default for ProviderPreference {
@@ -188,11 +350,30 @@ impl ProviderPreference {
         match self {
             ProviderPreference::PreferClaude => Some(ProviderType::Claude),
             ProviderPreference::PreferOpenAI => Some(ProviderType::OpenAI),
-            ProviderPreference::UserPreference(provider) => Some(*provider),
+            ProviderPreference::UserPreference(provider) => Some(provider.clone()),
+            ProviderPreference::Scheduled { otherwise, .. } => otherwise.get_preferred_provider(),
             _ => None, // Dynamic preferences don't have a fixed preference
         }
     }
 
+    /// Preferred provider at `now`, honouring any schedule
+    pub fn preferred_provider_at(&self, now: DateTime<Utc>) -> Option<ProviderType> {
+        match self {
+            ProviderPreference::Scheduled { schedule, otherwise } => {
+                schedule.provider_at(now).or_else(|| otherwise.preferred_provider_at(now))
+            }
+            _ => self.get_preferred_provider(),
+        }
+    }
+
+    /// Time-of-day schedule, if this preference has one
+    pub fn schedule(&self) -> Option<&ProviderSchedule> {
+        match self {
+            ProviderPreference::Scheduled { schedule, .. } => Some(schedule),
+            _ => None,
+        }
+    }
+
     /// Check if this preference requires runtime evaluation
     pub fn is_dynamic(&self) -> bool {
         matches!(
@@ -200,10 +381,121 @@ impl ProviderPreference {
             ProviderPreference::CostOptimized
                 | ProviderPreference::PerformanceOptimized
                 | ProviderPreference::QuotaOptimized
+                | ProviderPreference::Scheduled { .. }
         )
     }
 }
 
+/// Largest UTC offset in use anywhere, in minutes (UTC+14:00)
+pub const MAX_UTC_OFFSET_MINUTES: i32 = 14 * 60;
+
+/// Time-of-day window mapped to a preferred provider
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProviderWindow {
+    /// Start of the window, inclusive
+    pub start: NaiveTime,
+
+    /// End of the window, exclusive; earlier than `start` for a window that wraps past midnight
+    pub end: NaiveTime,
+
+    /// Provider preferred during the window
+    pub provider: ProviderType,
+}
+
+impl ProviderWindow {
+    pub fn new(start: NaiveTime, end: NaiveTime, provider: ProviderType) -> Self {
+        Self { start, end, provider }
+    }
+
+    /// Whether `time` falls inside the window; a window whose start equals its end is empty
+    pub fn contains(&self, time: NaiveTime) -> bool {
+        if self.start <= self.end {
+            self.start <= time && time < self.end
+        } else {
+            self.start <= time || time < self.end
+        }
+    }
+
+    /// Whether the window starts and ends at the same time, so it never matches
+    pub fn is_empty(&self) -> bool {
+        self.start == self.end
+    }
+
+    /// Whether the two windows share any time of day
+    pub fn overlaps(&self, other: &ProviderWindow) -> bool {
+        self.segments()
+            .iter()
+            .any(|a| other.segments().iter().any(|b| a.0 < b.1 && b.0 < a.1))
+    }
+
+    /// Covered seconds of the day as half-open ranges, split at midnight
+    fn segments(&self) -> Vec<(u32, u32)> {
+        const DAY: u32 = 24 * 60 * 60;
+        let (start, end) = (self.start.num_seconds_from_midnight(), self.end.num_seconds_from_midnight());
+        if start < end {
+            vec![(start, end)]
+        } else if start > end {
+            vec![(start, DAY), (0, end)]
+        } else {
+            Vec::new()
+        }
+    }
+}
+
+/// Provider preference that changes with the time of day
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProviderSchedule {
+    /// Windows checked in order; the first containing the current time wins
+    pub windows: Vec<ProviderWindow>,
+
+    /// Offset from UTC the windows are written in, in minutes; `None` uses the system's local time
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub utc_offset_minutes: Option<i32>,
+}
+
+impl ProviderSchedule {
+    /// Schedule evaluated in the system's local time
+    pub fn new(windows: Vec<ProviderWindow>) -> Self {
+        Self { windows, utc_offset_minutes: None }
+    }
+
+    /// Evaluate the windows at a fixed offset from UTC instead of local time
+    pub fn with_utc_offset_minutes(mut self, minutes: i32) -> Self {
+        self.utc_offset_minutes = Some(minutes);
+        self
+    }
+
+    /// Provider of the first window containing `now`, if any
+    pub fn provider_at(&self, now: DateTime<Utc>) -> Option<ProviderType> {
+        let time = self.time_of_day(now);
+        self.windows
+            .iter()
+            .find(|window| window.contains(time))
+            .map(|window| window.provider.clone())
+    }
+
+    /// Index pairs of windows that share some time of day
+    pub fn overlapping_windows(&self) -> Vec<(usize, usize)> {
+        let mut overlaps = Vec::new();
+        for (i, a) in self.windows.iter().enumerate() {
+            for (j, b) in self.windows.iter().enumerate().skip(i + 1) {
+                if a.overlaps(b) {
+                    overlaps.push((i, j));
+                }
+            }
+        }
+        overlaps
+    }
+
+    /// `now` as a time of day in the schedule's timezone
+    fn time_of_day(&self, now: DateTime<Utc>) -> NaiveTime {
+        match self.utc_offset_minutes.and_then(|minutes| FixedOffset::east_opt(minutes * 60)) {
+            Some(offset) => now.with_timezone(&offset).time(),
+            None => now.with_timezone(&Local).time(),
+        }
+    }
+}
+
 /// Fallback strategies when preferred provider fails
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum FallbackStrategy {
@@ -231,6 +523,12 @@ pub enum FallbackStrategy {
         on_rate_limit: bool,
         on_network_error: bool,
     },
+
+    /// Try providers in the listed order until one succeeds
+    #[serde(rename = "chain_ordered")]
+    ChainOrdered {
+        providers: Vec<ProviderType>,
+    },
 }
 
 impl Default for FallbackStrategy {
@@ -251,6 +549,7 @@ impl FallbackStrategy {
                 matches!(error_type, AuthErrorType::AuthenticationFailed)
             }
             FallbackStrategy::Manual => false,
+            FallbackStrategy::ChainOrdered { providers } => providers.len() > 1,
             FallbackStrategy::Conditional {
                 on_quota_exhausted,
                 on_auth_error,
@@ -338,6 +637,41 @@ mod tests {
         assert_eq!(ProviderType::from("unknown"), ProviderType::OpenAI);
     }
 
+    #[test]
+    fn test_custom_provider_round_trips() {
+        let gateway = ProviderType::from_id("local-gateway").unwrap();
+        assert_eq!(gateway, ProviderType::Custom("local-gateway".to_string()));
+        assert_eq!(serde_json::to_string(&gateway).unwrap(), r#""local-gateway""#);
+        assert_eq!(serde_json::from_str::<ProviderType>(r#""local-gateway""#).unwrap(), gateway);
+
+        let strategy = FallbackStrategy::ChainOrdered {
+            providers: vec![ProviderType::Claude, gateway.clone()],
+        };
+        let json = serde_json::to_string(&strategy).unwrap();
+        assert_eq!(serde_json::from_str::<FallbackStrategy>(&json).unwrap(), strategy);
+        assert!(serde_json::from_str::<ProviderType>(r#""""#).is_err());
+    }
+
+    #[test]
+    fn test_legacy_provider_names_still_load() {
+        for (json, expected) in [
+            (r#""openai""#, ProviderType::OpenAI),
+            (r#""claude""#, ProviderType::Claude),
+            (r#""OpenAI""#, ProviderType::OpenAI),
+            (r#""Claude""#, ProviderType::Claude),
+        ] {
+            assert_eq!(serde_json::from_str::<ProviderType>(json).unwrap(), expected, "{json}");
+        }
+
+        let legacy = r#"{"user_preference":"Claude"}"#;
+        assert_eq!(
+            serde_json::from_str::<ProviderPreference>(legacy).unwrap(),
+            ProviderPreference::UserPreference(ProviderType::Claude)
+        );
+        // Built-ins keep their lowercase serialized form
+        assert_eq!(serde_json::to_string(&ProviderType::OpenAI).unwrap(), r#""openai""#);
+    }
+
     #[test]
     fn test_fallback_strategy_decisions() {
         let automatic = FallbackStrategy::Automatic;
@@ -359,6 +693,70 @@ mod tests {
         assert!(!ProviderPreference::UserPreference(ProviderType::OpenAI).is_dynamic());
     }
 
+    fn at(time: &str) -> NaiveTime {
+        NaiveTime::parse_from_str(time, "%H:%M").unwrap()
+    }
+
+    fn utc(timestamp: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(timestamp).unwrap().with_timezone(&Utc)
+    }
+
+    #[test]
+    fn test_provider_schedule_windows() {
+        let overnight = ProviderWindow::new(at("22:00"), at("06:00"), ProviderType::OpenAI);
+        assert!(overnight.contains(at("23:30")));
+        assert!(overnight.contains(at("00:00")));
+        assert!(!overnight.contains(at("06:00")));
+        assert!(!overnight.contains(at("12:00")));
+
+        let day = ProviderWindow::new(at("09:00"), at("18:00"), ProviderType::Claude);
+        let early = ProviderWindow::new(at("05:00"), at("09:00"), ProviderType::Claude);
+        assert!(!day.overlaps(&overnight));
+        assert!(!day.overlaps(&early), "adjacent windows do not overlap");
+        assert!(early.overlaps(&overnight));
+
+        // 09:00-18:00 at UTC+02:00 is 07:00-16:00 UTC
+        let schedule = ProviderSchedule::new(vec![day]).with_utc_offset_minutes(120);
+        assert_eq!(schedule.provider_at(utc("2025-03-01T07:00:00Z")), Some(ProviderType::Claude));
+        assert_eq!(schedule.provider_at(utc("2025-03-01T16:00:00Z")), None);
+
+        let preference = ProviderPreference::Scheduled {
+            schedule,
+            otherwise: Box::new(ProviderPreference::PreferOpenAI),
+        };
+        assert!(preference.is_dynamic());
+        assert_eq!(preference.preferred_provider_at(utc("2025-03-01T06:59:59Z")), Some(ProviderType::OpenAI));
+        let json = serde_json::to_string(&preference).unwrap();
+        assert_eq!(serde_json::from_str::<ProviderPreference>(&json).unwrap(), preference);
+    }
+
+    #[test]
+    fn test_model_aliases_resolve_per_provider() {
+        let mut config = AuthConfig::default();
+        config.model_aliases.insert(&ProviderType::Claude, "sonnet-latest", "claude-sonnet-4-20250514");
+
+        assert_eq!(
+            config.resolve_model(&ProviderType::Claude, "sonnet-latest").unwrap(),
+            "claude-sonnet-4-20250514"
+        );
+        assert_eq!(config.resolve_model(&ProviderType::Claude, "claude-opus-4-1").unwrap(), "claude-opus-4-1");
+
+        let error = config.resolve_model(&ProviderType::OpenAI, "sonnet-latest").unwrap_err();
+        assert_eq!(
+            error,
+            ModelAliasError::Undefined {
+                provider: "openai".to_string(),
+                alias: "sonnet-latest".to_string(),
+                defined: Vec::new(),
+            }
+        );
+        assert!(error.to_string().contains("[model_aliases.openai]"));
+
+        let serialized = toml::to_string(&config).unwrap();
+        let deserialized: AuthConfig = toml::from_str(&serialized).unwrap();
+        assert_eq!(deserialized.model_aliases, config.model_aliases);
+    }
+
     #[test]
     fn test_subscription_check_timing() {
         let mut config = AuthConfig::default();
@@ -1,29 +1,29 @@
 //! Integration with existing AuthManager
 //! 
 //! This module provides the bridge between our unified configuration system
-//! and the existing AuthManager in core/src/auth.rs, enabling Claude authentication
-//! alongside the existing OpenAI authentication.
+//! and the credentials in `auth.json` and `claude_auth.json`, enabling Claude
+//! authentication alongside the existing OpenAI authentication.
 
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use chrono::{DateTime, Utc};
 
-use crate::claude_auth::{ClaudeAuth, ClaudeAuthMode};
+use crate::auth::unified::OpenAIAuth;
+use crate::auth::{ClaudeAuth, ClaudeAuthMode};
 use super::{
     ConfigIntegration,
     ProviderType,
-    ProviderSelection,
     SelectionContext,
     AuthErrorContext,
     auth_config::{AuthErrorType, FallbackStrategy},
 };
 
 /// Extended AuthManager that integrates Claude authentication
-/// This extends the existing AuthManager patterns from core/src/auth.rs
 #[derive(Debug)]
 pub struct UnifiedAuthManager {
     config_integration: ConfigIntegration,
-    openai_auth: Option<CodexAuth>, // Existing CodexAuth from core/src/auth.rs
+    originator: String,
+    openai_auth: Option<OpenAIAuth>,
     claude_auth: Option<ClaudeAuth>,
     last_provider_check: Option<DateTime<Utc>>,
 }
@@ -34,13 +34,14 @@ impl UnifiedAuthManager {
         let config_integration = ConfigIntegration::new(codex_home.clone())?;
         
         // Load existing OpenAI auth using existing patterns
-        let openai_auth = Self::load_existing_openai_auth(&codex_home, &originator)?;
+        let openai_auth = Self::load_existing_openai_auth(&codex_home)?;
         
         // Load Claude auth using our new system
-        let claude_auth = Self::load_claude_auth(&config_integration).await?;
+        let claude_auth = Self::load_claude_auth(&codex_home, &originator).await?;
         
         Ok(Self {
             config_integration,
+            originator,
             openai_auth,
             claude_auth,
             last_provider_check: None,
@@ -74,20 +75,21 @@ impl UnifiedAuthManager {
                     Err(UnifiedAuthError::ProviderNotAvailable(ProviderType::Claude))
                 }
             }
+            custom @ ProviderType::Custom(_) => Err(UnifiedAuthError::ProviderNotAvailable(custom)),
         }
     }
 
     /// Get authentication provider with fallback support
     pub async fn get_provider_with_fallback(&self, preferred: ProviderType) -> Result<AuthProviderWrapper, UnifiedAuthError> {
         // Try preferred provider first
-        match self.get_specific_provider(preferred).await {
+        match self.get_specific_provider(preferred.clone()).await {
             Ok(provider) => return Ok(provider),
             Err(e) => {
                 // Check if we should fallback
                 let provider_selection = self.config_integration.get_provider_for_auth_manager().await?;
                 let error_context = AuthErrorContext {
                     error_type: Self::map_error_to_type(&e),
-                    provider: preferred,
+                    provider: preferred.clone(),
                     retry_count: 0,
                 };
 
@@ -95,6 +97,11 @@ impl UnifiedAuthManager {
                     let fallback_provider = match preferred {
                         ProviderType::OpenAI => ProviderType::Claude,
                         ProviderType::Claude => ProviderType::OpenAI,
+                        // Fall back from a custom provider to whichever built-in is set up
+                        ProviderType::Custom(_) => match self.get_available_providers().into_iter().next() {
+                            Some(provider) => provider,
+                            None => return Err(e),
+                        },
                     };
                     
                     return self.get_specific_provider(fallback_provider).await;
@@ -132,6 +139,7 @@ impl UnifiedAuthManager {
                     Err(UnifiedAuthError::ProviderNotAvailable(ProviderType::Claude))
                 }
             }
+            custom @ ProviderType::Custom(_) => Err(UnifiedAuthError::ProviderNotAvailable(custom)),
         }
     }
 
@@ -155,14 +163,12 @@ impl UnifiedAuthManager {
     /// Refresh authentication state
     pub async fn refresh(&mut self) -> Result<(), UnifiedAuthError> {
         // Reload OpenAI auth using existing patterns
-        let codex_home = self.config_integration.existing_config_path.parent()
-            .ok_or(UnifiedAuthError::ConfigurationError("Invalid codex home path".to_string()))?
-            .to_path_buf();
+        let codex_home = self.config_integration.codex_home().to_path_buf();
         
-        self.openai_auth = Self::load_existing_openai_auth(&codex_home, "codex_cli_rs")?;
+        self.openai_auth = Self::load_existing_openai_auth(&codex_home)?;
         
         // Reload Claude auth
-        self.claude_auth = Self::load_claude_auth(&self.config_integration).await?;
+        self.claude_auth = Self::load_claude_auth(&codex_home, &self.originator).await?;
         
         self.last_provider_check = Some(Utc::now());
         
@@ -171,13 +177,12 @@ impl UnifiedAuthManager {
 
     /// Switch preferred provider
     pub async fn set_preferred_provider(&self, provider: ProviderType) -> Result<(), UnifiedAuthError> {
-        self.config_integration.config_manager.set_provider_preference(provider).await?;
+        self.config_integration.config_manager().set_provider_preference(provider).await?;
         Ok(())
     }
 
     /// Get current configuration
     pub async fn get_configuration(&self) -> Result<AuthManagerConfig, UnifiedAuthError> {
-        let integrated_config = self.config_integration.load_integrated_config().await?;
         let provider_selection = self.config_integration.get_provider_for_auth_manager().await?;
         
         Ok(AuthManagerConfig {
@@ -192,39 +197,26 @@ impl UnifiedAuthManager {
     }
 
     // Private helper methods
-    fn load_existing_openai_auth(codex_home: &PathBuf, originator: &str) -> Result<Option<CodexAuth>, UnifiedAuthError> {
-        // This would use the existing AuthManager::from_codex_home pattern
-        // For now, we'll use a placeholder that integrates with the existing system
-        use codex_protocol::mcp_protocol::AuthMode;
-        
-        match crate::auth::CodexAuth::from_codex_home(codex_home, AuthMode::ChatGPT, originator) {
-            Ok(auth) => Ok(auth),
-            Err(_) => Ok(None), // No auth available
+    fn load_existing_openai_auth(codex_home: &Path) -> Result<Option<OpenAIAuth>, UnifiedAuthError> {
+        let auth_file = codex_home.join("auth.json");
+        if !auth_file.exists() {
+            return Ok(None);
         }
+
+        let content = std::fs::read_to_string(&auth_file)
+            .map_err(|e| UnifiedAuthError::ConfigurationError(format!("Failed to read auth.json: {}", e)))?;
+        let auth_data: serde_json::Value = serde_json::from_str(&content)
+            .map_err(|e| UnifiedAuthError::ConfigurationError(format!("Invalid auth.json: {}", e)))?;
+
+        OpenAIAuth::from_auth_json(&auth_data)
+            .map(Some)
+            .map_err(|e| UnifiedAuthError::ConfigurationError(e.to_string()))
     }
 
-    async fn load_claude_auth(config_integration: &ConfigIntegration) -> Result<Option<ClaudeAuth>, UnifiedAuthError> {
-        let config = config_integration.config_manager.load_config().await?;
-        
-        if let Some(claude_data) = &config.auth_data.claude_auth {
-            // Create ClaudeAuth from stored data
-            let claude_auth = if let Some(api_key) = &claude_data.api_key {
-                ClaudeAuth::from_api_key(api_key)
-            } else if let Some(tokens) = &claude_data.tokens {
-                // Create from OAuth tokens
-                ClaudeAuth::from_oauth_tokens(
-                    tokens.access_token.clone(),
-                    tokens.refresh_token.clone(),
-                    tokens.expires_at,
-                )?
-            } else {
-                return Ok(None);
-            };
-            
-            Ok(Some(claude_auth))
-        } else {
-            Ok(None)
-        }
+    async fn load_claude_auth(codex_home: &Path, originator: &str) -> Result<Option<ClaudeAuth>, UnifiedAuthError> {
+        ClaudeAuth::from_codex_home_async(codex_home, ClaudeAuthMode::MaxSubscription, originator)
+            .await
+            .map_err(|e| UnifiedAuthError::ConfigurationError(format!("Failed to load Claude credentials: {}", e)))
     }
 
     async fn get_forced_provider(&self) -> Result<Option<ProviderType>, UnifiedAuthError> {
@@ -257,7 +249,7 @@ impl UnifiedAuthManager {
 /// Wrapper for different authentication providers
 #[derive(Debug, Clone)]
 pub enum AuthProviderWrapper {
-    OpenAI(CodexAuth),
+    OpenAI(OpenAIAuth),
     Claude(ClaudeAuth),
 }
 
@@ -265,9 +257,9 @@ impl AuthProviderWrapper {
     /// Get authentication token from the provider
     pub async fn get_token(&self) -> Result<String, UnifiedAuthError> {
         match self {
-            AuthProviderWrapper::OpenAI(auth) => {
-                auth.get_token().await.map_err(|e| UnifiedAuthError::AuthenticationFailed(e.to_string()))
-            }
+            AuthProviderWrapper::OpenAI(auth) => auth.api_key.clone().ok_or_else(|| {
+                UnifiedAuthError::AuthenticationFailed("OpenAI login has no API key".to_string())
+            }),
             AuthProviderWrapper::Claude(auth) => {
                 auth.get_token().await.map_err(|e| UnifiedAuthError::AuthenticationFailed(e.to_string()))
             }
@@ -285,28 +277,17 @@ impl AuthProviderWrapper {
     /// Check if provider needs token refresh
     pub async fn needs_refresh(&self) -> bool {
         match self {
-            AuthProviderWrapper::OpenAI(auth) => {
-                // Use existing logic from CodexAuth
-                auth.get_current_token_data().map_or(false, |data| {
-                    data.id_token.needs_refresh()
-                })
-            }
-            AuthProviderWrapper::Claude(auth) => {
-                auth.needs_token_refresh().await
-            }
+            // API keys don't expire; ChatGPT tokens are refreshed by the OpenAI client itself
+            AuthProviderWrapper::OpenAI(_) => false,
+            AuthProviderWrapper::Claude(auth) => auth.needs_token_refresh(),
         }
     }
 
     /// Refresh authentication token
+    ///
+    /// A token close to expiry is refreshed by [`get_token`](Self::get_token) itself.
     pub async fn refresh_token(&self) -> Result<String, UnifiedAuthError> {
-        match self {
-            AuthProviderWrapper::OpenAI(auth) => {
-                auth.refresh_token().await.map_err(|e| UnifiedAuthError::AuthenticationFailed(e.to_string()))
-            }
-            AuthProviderWrapper::Claude(auth) => {
-                auth.refresh_token().await.map_err(|e| UnifiedAuthError::AuthenticationFailed(e.to_string()))
-            }
-        }
+        self.get_token().await
     }
 }
 
@@ -322,9 +303,6 @@ pub struct AuthManagerConfig {
     pub last_check: Option<DateTime<Utc>>,
 }
 
-/// Re-export CodexAuth from existing system for compatibility
-pub use crate::auth::CodexAuth;
-
 /// Unified authentication error types
 #[derive(Debug, thiserror::Error)]
 pub enum UnifiedAuthError {
@@ -407,13 +385,13 @@ mod tests {
         assert!(manager.get_available_providers().is_empty());
     }
 
-    #[test]
-    fn test_auth_provider_wrapper_type() {
-        // Test with a dummy CodexAuth (this would need to be properly constructed in real tests)
-        // For now just test the enum matching
-        let openai_auth = CodexAuth::from_api_key("sk-test");
+    #[tokio::test]
+    async fn test_auth_provider_wrapper_type() {
+        let openai_auth = OpenAIAuth::from_auth_json(&serde_json::json!({"OPENAI_API_KEY": "sk-test"})).unwrap();
         let wrapper = AuthProviderWrapper::OpenAI(openai_auth);
         assert_eq!(wrapper.provider_type(), ProviderType::OpenAI);
+        assert_eq!(wrapper.get_token().await.unwrap(), "sk-test");
+        assert!(!wrapper.needs_refresh().await);
     }
 
     #[test]
@@ -2,13 +2,15 @@
 //! 
 //! Provides environment variable-based configuration overrides while maintaining
 //! security and following the Code project's existing patterns.
+//!
+//! Precedence for every auth setting is: environment variable > `config.toml` > built-in default.
+//! Prefixed variables (`CODE_AUTH_*`) win over their `CODEX_*` aliases when both are set.
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::env;
-use std::str::FromStr;
 use chrono::Duration;
 
-use super::auth_config::{AuthConfig, ProviderType, ProviderPreference, FallbackStrategy};
+use super::auth_config::{ProviderType, FallbackStrategy};
 use super::UnifiedConfig;
 
 /// Environment configuration manager
@@ -35,36 +37,39 @@ impl EnvironmentConfig {
         }
     }
 
-    /// Apply environment overrides to configuration
-    pub fn apply_overrides(&self, config: &mut UnifiedConfig) -> Result<(), EnvironmentError> {
-        // Apply auth configuration overrides
-        if let Some(provider) = &self.overrides.preferred_provider {
-            config.auth.preferred_provider = *provider;
-        }
-
-        if let Some(fallback) = self.overrides.enable_fallback {
-            config.auth.enable_fallback = fallback;
-        }
-
-        if let Some(strategy) = &self.overrides.fallback_strategy {
-            config.auth.fallback_strategy = strategy.clone();
-        }
-
-        if let Some(check_interval) = self.overrides.subscription_check_interval {
-            config.auth.subscription_check_interval = check_interval;
-        }
-
-        if let Some(enable_check) = self.overrides.enable_subscription_check {
-            config.auth.enable_subscription_check = enable_check;
-        }
-
-        if let Some(timeout) = self.overrides.auth_timeout {
-            config.auth.auth_timeout = timeout;
+    /// Create from already-loaded overrides with the default prefix
+    pub fn with_overrides(overrides: EnvironmentOverrides) -> Self {
+        Self {
+            overrides,
+            prefix: "CODE_AUTH_".to_string(),
         }
+    }
 
-        if let Some(auto_refresh) = self.overrides.auto_refresh_tokens {
-            config.auth.auto_refresh_tokens = auto_refresh;
-        }
+    /// Apply environment overrides to configuration
+    ///
+    /// Enforces env > config file > default and reports where each setting's final value came from.
+    /// Invalid variable values are rejected before anything is applied.
+    pub fn apply_overrides(&self, config: &mut UnifiedConfig) -> Result<OverrideReport, EnvironmentError> {
+        self.overrides.validate()?;
+
+        let mut report = OverrideReport::default();
+        let file_settings = config.file_settings.clone();
+        let auth = &mut config.auth;
+
+        self.resolve(&mut report, &file_settings, "preferred_provider",
+            &mut auth.preferred_provider, self.overrides.preferred_provider.as_ref(), |v| v.to_string());
+        self.resolve(&mut report, &file_settings, "enable_fallback",
+            &mut auth.enable_fallback, self.overrides.enable_fallback.as_ref(), |v| v.to_string());
+        self.resolve(&mut report, &file_settings, "fallback_strategy",
+            &mut auth.fallback_strategy, self.overrides.fallback_strategy.as_ref(), describe_fallback_strategy);
+        self.resolve(&mut report, &file_settings, "subscription_check_interval",
+            &mut auth.subscription_check_interval, self.overrides.subscription_check_interval.as_ref(), describe_duration);
+        self.resolve(&mut report, &file_settings, "enable_subscription_check",
+            &mut auth.enable_subscription_check, self.overrides.enable_subscription_check.as_ref(), |v| v.to_string());
+        self.resolve(&mut report, &file_settings, "auth_timeout",
+            &mut auth.auth_timeout, self.overrides.auth_timeout.as_ref(), describe_duration);
+        self.resolve(&mut report, &file_settings, "auto_refresh_tokens",
+            &mut auth.auto_refresh_tokens, self.overrides.auto_refresh_tokens.as_ref(), |v| v.to_string());
 
         // Apply authentication data overrides
         if let Some(openai_key) = &self.overrides.openai_api_key {
@@ -90,7 +95,34 @@ impl EnvironmentConfig {
             }
         }
 
-        Ok(())
+        Ok(report)
+    }
+
+    /// Apply one setting's override and record which source won
+    fn resolve<T: Clone>(
+        &self,
+        report: &mut OverrideReport,
+        file_settings: &HashSet<String>,
+        setting: &str,
+        target: &mut T,
+        env_value: Option<&T>,
+        describe: impl Fn(&T) -> String,
+    ) {
+        let (source, variable) = match env_value {
+            Some(value) => {
+                *target = value.clone();
+                (OverrideSource::Environment, self.overrides.sources.get(setting).cloned())
+            }
+            None if file_settings.contains(setting) => (OverrideSource::ConfigFile, None),
+            None => (OverrideSource::Default, None),
+        };
+
+        report.settings.push(SettingResolution {
+            setting: setting.to_string(),
+            value: describe(target),
+            source,
+            variable,
+        });
     }
 
     /// Get current environment overrides
@@ -125,6 +157,62 @@ impl Default for EnvironmentConfig {
     }
 }
 
+/// Where the effective value of a setting came from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OverrideSource {
+    Default,
+    ConfigFile,
+    Environment,
+}
+
+/// Final value of a single setting and the source that supplied it
+#[derive(Debug, Clone, PartialEq)]
+pub struct SettingResolution {
+    pub setting: String,
+    pub value: String,
+    pub source: OverrideSource,
+    /// Environment variable that supplied the value, when `source` is `Environment`
+    pub variable: Option<String>,
+}
+
+/// Result of applying environment overrides to a configuration
+#[derive(Debug, Clone, Default)]
+pub struct OverrideReport {
+    pub settings: Vec<SettingResolution>,
+}
+
+impl OverrideReport {
+    /// Look up the resolution for a setting by name
+    pub fn get(&self, setting: &str) -> Option<&SettingResolution> {
+        self.settings.iter().find(|resolution| resolution.setting == setting)
+    }
+
+    /// Settings whose value came from the environment
+    pub fn overridden(&self) -> impl Iterator<Item = &SettingResolution> {
+        self.settings
+            .iter()
+            .filter(|resolution| resolution.source == OverrideSource::Environment)
+    }
+}
+
+fn describe_duration(duration: &Duration) -> String {
+    format!("{}s", duration.num_seconds())
+}
+
+fn describe_fallback_strategy(strategy: &FallbackStrategy) -> String {
+    match strategy {
+        FallbackStrategy::Automatic => "automatic".to_string(),
+        FallbackStrategy::OnQuotaExhausted => "on_quota_exhausted".to_string(),
+        FallbackStrategy::OnAuthError => "on_auth_error".to_string(),
+        FallbackStrategy::Manual => "manual".to_string(),
+        FallbackStrategy::Conditional { .. } => "conditional".to_string(),
+        FallbackStrategy::ChainOrdered { providers } => format!(
+            "chain_ordered({})",
+            providers.iter().map(ToString::to_string).collect::<Vec<_>>().join(", ")
+        ),
+    }
+}
+
 /// Environment variable overrides
 #[derive(Debug, Clone, Default)]
 pub struct EnvironmentOverrides {
@@ -146,6 +234,11 @@ pub struct EnvironmentOverrides {
     pub debug_auth: Option<bool>,
     pub force_provider: Option<ProviderType>,
     pub disable_token_validation: Option<bool>,
+
+    /// Environment variable that supplied each applied setting
+    pub sources: HashMap<String, String>,
+    /// Variables that were set but could not be parsed
+    pub invalid_values: Vec<String>,
 }
 
 impl EnvironmentOverrides {
@@ -156,31 +249,72 @@ impl EnvironmentOverrides {
 
     /// Load overrides with custom prefix
     pub fn load_with_prefix(prefix: &str) -> Self {
-        let mut overrides = Self::default();
+        Self::load_from(prefix, |key| env::var(key).ok())
+    }
 
-        // Load auth configuration overrides
-        overrides.preferred_provider = Self::get_env_provider(&format!("{}PREFERRED_PROVIDER", prefix));
-        overrides.enable_fallback = Self::get_env_bool(&format!("{}ENABLE_FALLBACK", prefix));
-        overrides.fallback_strategy = Self::get_env_fallback_strategy(&format!("{}FALLBACK_STRATEGY", prefix));
-        overrides.subscription_check_interval = Self::get_env_duration(&format!("{}SUBSCRIPTION_CHECK_INTERVAL", prefix));
-        overrides.enable_subscription_check = Self::get_env_bool(&format!("{}ENABLE_SUBSCRIPTION_CHECK", prefix));
-        overrides.auth_timeout = Self::get_env_duration(&format!("{}AUTH_TIMEOUT", prefix));
-        overrides.auto_refresh_tokens = Self::get_env_bool(&format!("{}AUTO_REFRESH_TOKENS", prefix));
+    /// Load overrides from an arbitrary variable lookup instead of the process environment
+    pub fn load_from(prefix: &str, lookup: impl Fn(&str) -> Option<String>) -> Self {
+        let mut overrides = Self::default();
+        let prefixed = |name: &str| format!("{}{}", prefix, name);
+
+        // Load auth configuration overrides; prefixed names take precedence over CODEX_* aliases
+        overrides.preferred_provider = overrides.read_setting(&lookup, "preferred_provider",
+            &[prefixed("PREFERRED_PROVIDER"), "CODEX_PREFERRED_PROVIDER".to_string()],
+            Self::parse_provider, "openai | claude");
+        overrides.enable_fallback = overrides.read_setting(&lookup, "enable_fallback",
+            &[prefixed("ENABLE_FALLBACK")], Self::parse_bool, "true | false");
+        overrides.fallback_strategy = overrides.read_setting(&lookup, "fallback_strategy",
+            &[prefixed("FALLBACK_STRATEGY"), "CODEX_FALLBACK_STRATEGY".to_string()],
+            Self::parse_fallback_strategy, "automatic | manual | on_quota_exhausted | on_auth_error");
+        overrides.subscription_check_interval = overrides.read_setting(&lookup, "subscription_check_interval",
+            &[prefixed("SUBSCRIPTION_CHECK_INTERVAL")], Self::parse_duration, "a duration like 30s, 5m, 1h");
+        overrides.enable_subscription_check = overrides.read_setting(&lookup, "enable_subscription_check",
+            &[prefixed("ENABLE_SUBSCRIPTION_CHECK")], Self::parse_bool, "true | false");
+        overrides.auth_timeout = overrides.read_setting(&lookup, "auth_timeout",
+            &[prefixed("AUTH_TIMEOUT")], Self::parse_duration, "a duration like 30s, 5m, 1h");
+        overrides.auto_refresh_tokens = overrides.read_setting(&lookup, "auto_refresh_tokens",
+            &[prefixed("AUTO_REFRESH_TOKENS")], Self::parse_bool, "true | false");
 
         // Load API key overrides
-        overrides.openai_api_key = env::var("OPENAI_API_KEY").ok();
-        overrides.claude_api_key = env::var("CLAUDE_API_KEY").ok()
-            .or_else(|| env::var("ANTHROPIC_API_KEY").ok());
-        overrides.anthropic_api_key = env::var("ANTHROPIC_API_KEY").ok();
+        overrides.openai_api_key = lookup("OPENAI_API_KEY");
+        overrides.claude_api_key = lookup("CLAUDE_API_KEY")
+            .or_else(|| lookup("ANTHROPIC_API_KEY"));
+        overrides.anthropic_api_key = lookup("ANTHROPIC_API_KEY");
 
         // Load debug flags
-        overrides.debug_auth = Self::get_env_bool(&format!("{}DEBUG", prefix));
-        overrides.force_provider = Self::get_env_provider(&format!("{}FORCE_PROVIDER", prefix));
-        overrides.disable_token_validation = Self::get_env_bool(&format!("{}DISABLE_TOKEN_VALIDATION", prefix));
+        overrides.debug_auth = overrides.read_setting(&lookup, "debug_auth",
+            &[prefixed("DEBUG")], Self::parse_bool, "true | false");
+        overrides.force_provider = overrides.read_setting(&lookup, "force_provider",
+            &[prefixed("FORCE_PROVIDER")], Self::parse_provider, "openai | claude");
+        overrides.disable_token_validation = overrides.read_setting(&lookup, "disable_token_validation",
+            &[prefixed("DISABLE_TOKEN_VALIDATION")], Self::parse_bool, "true | false");
 
         overrides
     }
 
+    /// Read the first set variable in `keys`, recording its name or why it failed to parse
+    fn read_setting<T>(
+        &mut self,
+        lookup: &impl Fn(&str) -> Option<String>,
+        setting: &str,
+        keys: &[String],
+        parse: fn(&str) -> Option<T>,
+        expected: &str,
+    ) -> Option<T> {
+        let (key, raw) = keys.iter().find_map(|key| lookup(key).map(|raw| (key, raw)))?;
+
+        match parse(&raw) {
+            Some(value) => {
+                self.sources.insert(setting.to_string(), key.clone());
+                Some(value)
+            }
+            None => {
+                self.invalid_values.push(format!("{}='{}' (expected {})", key, raw, expected));
+                None
+            }
+        }
+    }
+
     /// Check if any overrides are set
     pub fn has_any_overrides(&self) -> bool {
         self.preferred_provider.is_some()
@@ -247,6 +381,10 @@ impl EnvironmentOverrides {
 
     /// Validate environment variable values
     pub fn validate(&self) -> Result<(), EnvironmentError> {
+        if let Some(invalid) = self.invalid_values.first() {
+            return Err(EnvironmentError::InvalidValue(invalid.clone()));
+        }
+
         // Validate timeout values
         if let Some(timeout) = self.auth_timeout {
             if timeout < Duration::seconds(1) {
@@ -289,58 +427,49 @@ impl EnvironmentOverrides {
         Ok(())
     }
 
-    // Helper methods for parsing environment variables
-    fn get_env_bool(key: &str) -> Option<bool> {
-        env::var(key).ok().and_then(|v| {
-            match v.to_lowercase().as_str() {
-                "true" | "1" | "yes" | "on" => Some(true),
-                "false" | "0" | "no" | "off" => Some(false),
-                _ => None,
-            }
-        })
+    fn parse_bool(value: &str) -> Option<bool> {
+        match value.to_lowercase().as_str() {
+            "true" | "1" | "yes" | "on" => Some(true),
+            "false" | "0" | "no" | "off" => Some(false),
+            _ => None,
+        }
     }
 
-    fn get_env_provider(key: &str) -> Option<ProviderType> {
-        env::var(key).ok().and_then(|v| {
-            match v.to_lowercase().as_str() {
-                "openai" => Some(ProviderType::OpenAI),
-                "claude" | "anthropic" => Some(ProviderType::Claude),
-                _ => None,
-            }
-        })
+    fn parse_provider(value: &str) -> Option<ProviderType> {
+        match value.to_lowercase().as_str() {
+            "openai" => Some(ProviderType::OpenAI),
+            "claude" | "anthropic" => Some(ProviderType::Claude),
+            _ => None,
+        }
+    }
+
+    fn parse_fallback_strategy(value: &str) -> Option<FallbackStrategy> {
+        match value.to_lowercase().as_str() {
+            "automatic" => Some(FallbackStrategy::Automatic),
+            "manual" => Some(FallbackStrategy::Manual),
+            "on_quota_exhausted" => Some(FallbackStrategy::OnQuotaExhausted),
+            "on_auth_error" => Some(FallbackStrategy::OnAuthError),
+            _ => None,
+        }
     }
 
-    fn get_env_fallback_strategy(key: &str) -> Option<FallbackStrategy> {
-        env::var(key).ok().and_then(|v| {
-            match v.to_lowercase().as_str() {
-                "automatic" => Some(FallbackStrategy::Automatic),
-                "manual" => Some(FallbackStrategy::Manual),
-                "on_quota_exhausted" => Some(FallbackStrategy::OnQuotaExhausted),
-                "on_auth_error" => Some(FallbackStrategy::OnAuthError),
+    fn parse_duration(value: &str) -> Option<Duration> {
+        // Support formats like "30s", "5m", "1h", "2d"
+        if let Some(captures) = regex::Regex::new(r"^(\d+)([smhd])$").unwrap().captures(value) {
+            let number: i64 = captures.get(1)?.as_str().parse().ok()?;
+            let unit = captures.get(2)?.as_str();
+            
+            match unit {
+                "s" => Some(Duration::seconds(number)),
+                "m" => Some(Duration::minutes(number)),
+                "h" => Some(Duration::hours(number)),
+                "d" => Some(Duration::days(number)),
                 _ => None,
             }
-        })
-    }
-
-    fn get_env_duration(key: &str) -> Option<Duration> {
-        env::var(key).ok().and_then(|v| {
-            // Support formats like "30s", "5m", "1h", "2d"
-            if let Some(captures) = regex::Regex::new(r"^(\d+)([smhd])$").unwrap().captures(&v) {
-                let number: i64 = captures.get(1)?.as_str().parse().ok()?;
-                let unit = captures.get(2)?.as_str();
-                
-                match unit {
-                    "s" => Some(Duration::seconds(number)),
-                    "m" => Some(Duration::minutes(number)),
-                    "h" => Some(Duration::hours(number)),
-                    "d" => Some(Duration::days(number)),
-                    _ => None,
-                }
-            } else {
-                // Try parsing as seconds
-                v.parse::<i64>().ok().map(Duration::seconds)
-            }
-        })
+        } else {
+            // Try parsing as seconds
+            value.parse::<i64>().ok().map(Duration::seconds)
+        }
     }
 }
 
@@ -378,6 +507,12 @@ impl EnvironmentConfig {
                 example: "openai | claude".to_string(),
                 required: false,
             },
+            EnvironmentVariableDoc {
+                name: "CODEX_PREFERRED_PROVIDER".to_string(),
+                description: "Alias for CODE_AUTH_PREFERRED_PROVIDER (the prefixed name wins if both are set)".to_string(),
+                example: "openai | claude".to_string(),
+                required: false,
+            },
             EnvironmentVariableDoc {
                 name: "CODE_AUTH_ENABLE_FALLBACK".to_string(),
                 description: "Enable automatic fallback between providers".to_string(),
@@ -390,6 +525,12 @@ impl EnvironmentConfig {
                 example: "automatic | manual | on_quota_exhausted | on_auth_error".to_string(),
                 required: false,
             },
+            EnvironmentVariableDoc {
+                name: "CODEX_FALLBACK_STRATEGY".to_string(),
+                description: "Alias for CODE_AUTH_FALLBACK_STRATEGY (the prefixed name wins if both are set)".to_string(),
+                example: "automatic | manual | on_quota_exhausted | on_auth_error".to_string(),
+                required: false,
+            },
             EnvironmentVariableDoc {
                 name: "CODE_AUTH_SUBSCRIPTION_CHECK_INTERVAL".to_string(),
                 description: "How often to check Claude subscription status".to_string(),
@@ -439,7 +580,6 @@ impl EnvironmentConfig {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use std::env;
 
     #[test]
     fn test_environment_config_creation() {
@@ -455,67 +595,26 @@ mod tests {
 
     #[test]
     fn test_bool_parsing() {
-        assert_eq!(EnvironmentOverrides::get_env_bool("NONEXISTENT"), None);
-        
-        env::set_var("TEST_BOOL_TRUE", "true");
-        assert_eq!(EnvironmentOverrides::get_env_bool("TEST_BOOL_TRUE"), Some(true));
-        
-        env::set_var("TEST_BOOL_FALSE", "false");
-        assert_eq!(EnvironmentOverrides::get_env_bool("TEST_BOOL_FALSE"), Some(false));
-        
-        env::set_var("TEST_BOOL_INVALID", "maybe");
-        assert_eq!(EnvironmentOverrides::get_env_bool("TEST_BOOL_INVALID"), None);
-        
-        // Cleanup
-        env::remove_var("TEST_BOOL_TRUE");
-        env::remove_var("TEST_BOOL_FALSE");
-        env::remove_var("TEST_BOOL_INVALID");
+        assert_eq!(EnvironmentOverrides::parse_bool("true"), Some(true));
+        assert_eq!(EnvironmentOverrides::parse_bool("false"), Some(false));
+        assert_eq!(EnvironmentOverrides::parse_bool("maybe"), None);
     }
 
     #[test]
     fn test_provider_parsing() {
-        env::set_var("TEST_PROVIDER_OPENAI", "openai");
-        assert_eq!(EnvironmentOverrides::get_env_provider("TEST_PROVIDER_OPENAI"), Some(ProviderType::OpenAI));
-        
-        env::set_var("TEST_PROVIDER_CLAUDE", "claude");
-        assert_eq!(EnvironmentOverrides::get_env_provider("TEST_PROVIDER_CLAUDE"), Some(ProviderType::Claude));
-        
-        env::set_var("TEST_PROVIDER_ANTHROPIC", "anthropic");
-        assert_eq!(EnvironmentOverrides::get_env_provider("TEST_PROVIDER_ANTHROPIC"), Some(ProviderType::Claude));
-        
-        env::set_var("TEST_PROVIDER_INVALID", "invalid");
-        assert_eq!(EnvironmentOverrides::get_env_provider("TEST_PROVIDER_INVALID"), None);
-        
-        // Cleanup
-        env::remove_var("TEST_PROVIDER_OPENAI");
-        env::remove_var("TEST_PROVIDER_CLAUDE");
-        env::remove_var("TEST_PROVIDER_ANTHROPIC");
-        env::remove_var("TEST_PROVIDER_INVALID");
+        assert_eq!(EnvironmentOverrides::parse_provider("openai"), Some(ProviderType::OpenAI));
+        assert_eq!(EnvironmentOverrides::parse_provider("claude"), Some(ProviderType::Claude));
+        assert_eq!(EnvironmentOverrides::parse_provider("anthropic"), Some(ProviderType::Claude));
+        assert_eq!(EnvironmentOverrides::parse_provider("invalid"), None);
     }
 
     #[test]
     fn test_duration_parsing() {
-        env::set_var("TEST_DURATION_SECONDS", "30s");
-        assert_eq!(EnvironmentOverrides::get_env_duration("TEST_DURATION_SECONDS"), Some(Duration::seconds(30)));
-        
-        env::set_var("TEST_DURATION_MINUTES", "5m");
-        assert_eq!(EnvironmentOverrides::get_env_duration("TEST_DURATION_MINUTES"), Some(Duration::minutes(5)));
-        
-        env::set_var("TEST_DURATION_HOURS", "2h");
-        assert_eq!(EnvironmentOverrides::get_env_duration("TEST_DURATION_HOURS"), Some(Duration::hours(2)));
-        
-        env::set_var("TEST_DURATION_DAYS", "1d");
-        assert_eq!(EnvironmentOverrides::get_env_duration("TEST_DURATION_DAYS"), Some(Duration::days(1)));
-        
-        env::set_var("TEST_DURATION_PLAIN", "3600");
-        assert_eq!(EnvironmentOverrides::get_env_duration("TEST_DURATION_PLAIN"), Some(Duration::seconds(3600)));
-        
-        // Cleanup
-        env::remove_var("TEST_DURATION_SECONDS");
-        env::remove_var("TEST_DURATION_MINUTES");
-        env::remove_var("TEST_DURATION_HOURS");
-        env::remove_var("TEST_DURATION_DAYS");
-        env::remove_var("TEST_DURATION_PLAIN");
+        assert_eq!(EnvironmentOverrides::parse_duration("30s"), Some(Duration::seconds(30)));
+        assert_eq!(EnvironmentOverrides::parse_duration("5m"), Some(Duration::minutes(5)));
+        assert_eq!(EnvironmentOverrides::parse_duration("2h"), Some(Duration::hours(2)));
+        assert_eq!(EnvironmentOverrides::parse_duration("1d"), Some(Duration::days(1)));
+        assert_eq!(EnvironmentOverrides::parse_duration("3600"), Some(Duration::seconds(3600)));
     }
 
     #[test]
@@ -577,4 +676,79 @@ mod tests {
         assert!(names.contains(&"CLAUDE_API_KEY"));
         assert!(names.contains(&"CODE_AUTH_PREFERRED_PROVIDER"));
     }
-}
\ No newline at end of file
+
+    fn overrides_from(vars: &[(&str, &str)]) -> EnvironmentOverrides {
+        let vars: HashMap<String, String> = vars
+            .iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect();
+        EnvironmentOverrides::load_from("CODE_AUTH_", |key| vars.get(key).cloned())
+    }
+
+    fn config_from_file(settings: &[&str]) -> UnifiedConfig {
+        let mut config = UnifiedConfig::default();
+        config.file_settings = settings.iter().map(|s| s.to_string()).collect();
+        config
+    }
+
+    #[test]
+    fn test_env_overrides_file_and_report_sources() {
+        // config.toml sets preferred_provider and fallback_strategy
+        let mut config = config_from_file(&["preferred_provider", "fallback_strategy"]);
+        config.auth.preferred_provider = ProviderType::OpenAI;
+        config.auth.fallback_strategy = FallbackStrategy::Manual;
+
+        let env_config = EnvironmentConfig::with_overrides(overrides_from(&[
+            ("CODEX_PREFERRED_PROVIDER", "claude"),
+        ]));
+        let report = env_config.apply_overrides(&mut config).unwrap();
+
+        assert_eq!(config.auth.preferred_provider, ProviderType::Claude);
+        let provider = report.get("preferred_provider").unwrap();
+        assert_eq!(provider.source, OverrideSource::Environment);
+        assert_eq!(provider.value, "claude");
+        assert_eq!(provider.variable.as_deref(), Some("CODEX_PREFERRED_PROVIDER"));
+
+        assert_eq!(config.auth.fallback_strategy, FallbackStrategy::Manual);
+        let strategy = report.get("fallback_strategy").unwrap();
+        assert_eq!(strategy.source, OverrideSource::ConfigFile);
+        assert_eq!(strategy.value, "manual");
+
+        let timeout = report.get("auth_timeout").unwrap();
+        assert_eq!(timeout.source, OverrideSource::Default);
+        assert_eq!(timeout.value, "30s");
+
+        assert_eq!(report.overridden().count(), 1);
+    }
+
+    #[test]
+    fn test_prefixed_variable_wins_over_alias() {
+        let overrides = overrides_from(&[
+            ("CODEX_FALLBACK_STRATEGY", "manual"),
+            ("CODE_AUTH_FALLBACK_STRATEGY", "on_auth_error"),
+        ]);
+        assert_eq!(overrides.fallback_strategy, Some(FallbackStrategy::OnAuthError));
+        assert_eq!(overrides.sources.get("fallback_strategy").map(String::as_str), Some("CODE_AUTH_FALLBACK_STRATEGY"));
+    }
+
+    #[test]
+    fn test_invalid_override_is_rejected() {
+        let mut config = config_from_file(&[]);
+        let env_config = EnvironmentConfig::with_overrides(overrides_from(&[
+            ("CODEX_PREFERRED_PROVIDER", "gpt-9000"),
+            ("CODE_AUTH_ENABLE_FALLBACK", "false"),
+        ]));
+
+        let result = env_config.apply_overrides(&mut config);
+        match result {
+            Err(EnvironmentError::InvalidValue(message)) => {
+                assert!(message.contains("CODEX_PREFERRED_PROVIDER"));
+                assert!(message.contains("gpt-9000"));
+            }
+            other => panic!("expected InvalidValue, got {:?}", other),
+        }
+
+        // Nothing is applied when any override is invalid
+        assert!(config.auth.enable_fallback);
+    }
+}
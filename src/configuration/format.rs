@@ -0,0 +1,129 @@
+//! Base configuration file formats
+//!
+//! `config.toml` remains the default, but the same `BaseConfig` can be read from and
+//! written back to `config.json` or `config.yaml` for hosts that standardise on those.
+
+use std::fmt;
+use std::path::Path;
+
+use super::{BaseConfig, ConfigError};
+
+/// Serialization format of the base configuration file
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ConfigFormat {
+    #[default]
+    Toml,
+    Json,
+    Yaml,
+}
+
+impl ConfigFormat {
+    /// All supported formats, in lookup order
+    pub const ALL: [ConfigFormat; 3] = [ConfigFormat::Toml, ConfigFormat::Json, ConfigFormat::Yaml];
+
+    /// Detect the format from a file extension
+    pub fn from_path(path: &Path) -> Option<Self> {
+        match path.extension()?.to_str()?.to_lowercase().as_str() {
+            "toml" => Some(ConfigFormat::Toml),
+            "json" => Some(ConfigFormat::Json),
+            "yaml" | "yml" => Some(ConfigFormat::Yaml),
+            _ => None,
+        }
+    }
+
+    /// Conventional file name for this format
+    pub fn file_name(&self) -> &'static str {
+        match self {
+            ConfigFormat::Toml => "config.toml",
+            ConfigFormat::Json => "config.json",
+            ConfigFormat::Yaml => "config.yaml",
+        }
+    }
+
+    /// Parse file contents into a format-neutral document
+    ///
+    /// Every format goes through `toml::Value` so schema checks and `BaseConfig`
+    /// deserialization share one path.
+    pub fn parse_document(&self, content: &str) -> Result<toml::Value, ConfigError> {
+        let parsed: Result<toml::Value, String> = match self {
+            ConfigFormat::Toml => toml::from_str(content).map_err(|e| e.to_string()),
+            ConfigFormat::Json => serde_json::from_str(content)
+                .map_err(|e| e.to_string())
+                .and_then(into_document),
+            ConfigFormat::Yaml => {
+                serde_yaml::with::singleton_map_recursive::deserialize(serde_yaml::Deserializer::from_str(content))
+                    .map_err(|e| e.to_string())
+                    .and_then(into_document)
+            }
+        };
+
+        parsed.map_err(|message| ConfigError::Parse { format: *self, message })
+    }
+
+    /// Serialize a base configuration in this format
+    pub fn serialize(&self, config: &BaseConfig) -> Result<String, ConfigError> {
+        let serialized = match self {
+            ConfigFormat::Toml => toml::to_string_pretty(config).map_err(|e| e.to_string()),
+            ConfigFormat::Json => serde_json::to_string_pretty(config).map_err(|e| e.to_string()),
+            ConfigFormat::Yaml => to_yaml(config).map_err(|e| e.to_string()),
+        };
+
+        serialized.map_err(|message| ConfigError::Serialize { format: *self, message })
+    }
+}
+
+/// YAML with enum variants written as single-key maps rather than `!Variant` tags,
+/// matching how JSON and TOML represent them
+fn to_yaml(config: &BaseConfig) -> Result<String, serde_yaml::Error> {
+    let mut buffer = Vec::new();
+    let mut serializer = serde_yaml::Serializer::new(&mut buffer);
+    serde_yaml::with::singleton_map_recursive::serialize(config, &mut serializer)?;
+    Ok(String::from_utf8(buffer).expect("serde_yaml writes UTF-8"))
+}
+
+/// TOML has no null; a null `Option` field means the same as an absent one, so drop them
+fn into_document(mut value: serde_json::Value) -> Result<toml::Value, String> {
+    strip_nulls(&mut value);
+    toml::Value::try_from(value).map_err(|e| e.to_string())
+}
+
+fn strip_nulls(value: &mut serde_json::Value) {
+    match value {
+        serde_json::Value::Object(map) => {
+            map.retain(|_, field| !field.is_null());
+            map.values_mut().for_each(strip_nulls);
+        }
+        serde_json::Value::Array(items) => items.iter_mut().for_each(strip_nulls),
+        _ => {}
+    }
+}
+
+impl fmt::Display for ConfigFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigFormat::Toml => write!(f, "TOML"),
+            ConfigFormat::Json => write!(f, "JSON"),
+            ConfigFormat::Yaml => write!(f, "YAML"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_format_detection() {
+        assert_eq!(ConfigFormat::from_path(&PathBuf::from("config.toml")), Some(ConfigFormat::Toml));
+        assert_eq!(ConfigFormat::from_path(&PathBuf::from("config.JSON")), Some(ConfigFormat::Json));
+        assert_eq!(ConfigFormat::from_path(&PathBuf::from("config.yml")), Some(ConfigFormat::Yaml));
+        assert_eq!(ConfigFormat::from_path(&PathBuf::from("config.ini")), None);
+    }
+
+    #[test]
+    fn test_parse_error_names_format() {
+        let err = ConfigFormat::Json.parse_document("{ not json").unwrap_err();
+        assert!(err.to_string().contains("JSON"));
+    }
+}
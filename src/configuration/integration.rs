@@ -3,7 +3,7 @@
 //! This module provides seamless integration with the existing config.toml
 //! and AuthManager systems while adding Claude authentication support.
 
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use chrono::Duration;
 use serde::{Deserialize, Serialize};
 
@@ -11,6 +11,7 @@ use super::{
     UnifiedConfigManager, 
     UnifiedConfig, 
     ConfigError,
+    ConfigFormat,
     auth_config::{AuthConfig, ProviderType, ProviderPreference},
     unified_storage::UnifiedAuthJson,
 };
@@ -104,11 +105,11 @@ impl From<AuthConfigToml> for AuthConfig {
         
         if let Some(strategy) = toml.fallback_strategy {
             config.fallback_strategy = match strategy.as_str() {
-                "automatic" => super::auth_config::FallbackStrategy::Automatic,
-                "manual" => super::auth_config::FallbackStrategy::Manual,
-                "on_quota_exhausted" => super::auth_config::FallbackStrategy::OnQuotaExhausted,
-                "on_auth_error" => super::auth_config::FallbackStrategy::OnAuthError,
-                _ => super::auth_config::FallbackStrategy::Automatic,
+                "automatic" => crate::configuration::auth_config::FallbackStrategy::Automatic,
+                "manual" => crate::configuration::auth_config::FallbackStrategy::Manual,
+                "on_quota_exhausted" => crate::configuration::auth_config::FallbackStrategy::OnQuotaExhausted,
+                "on_auth_error" => crate::configuration::auth_config::FallbackStrategy::OnAuthError,
+                _ => crate::configuration::auth_config::FallbackStrategy::Automatic,
             };
         }
         
@@ -137,6 +138,7 @@ impl From<AuthConfigToml> for AuthConfig {
 }
 
 /// Integration layer for existing Config struct
+#[derive(Debug)]
 pub struct ConfigIntegration {
     config_manager: UnifiedConfigManager,
     existing_config_path: PathBuf,
@@ -154,6 +156,16 @@ impl ConfigIntegration {
         })
     }
 
+    /// Manager of the unified configuration this layer integrates
+    pub fn config_manager(&self) -> &UnifiedConfigManager {
+        &self.config_manager
+    }
+
+    /// Directory holding `config.toml` and the auth files
+    pub fn codex_home(&self) -> &Path {
+        self.existing_config_path.parent().unwrap_or(Path::new("."))
+    }
+
     /// Load configuration that integrates with existing config.toml
     pub async fn load_integrated_config(&self) -> Result<IntegratedConfig, ConfigError> {
         // Load our unified config
@@ -194,12 +206,15 @@ impl ConfigIntegration {
     pub async fn get_provider_for_auth_manager(&self) -> Result<ProviderSelection, ConfigError> {
         let config = self.config_manager.load_config().await?;
         
+        // A disabled provider is never offered, whatever credentials it has
         Ok(ProviderSelection {
+            openai_available: config.auth_data.openai_auth.is_some()
+                && config.auth.is_provider_enabled(&ProviderType::OpenAI),
+            claude_available: config.auth_data.claude_auth.is_some()
+                && config.auth.is_provider_enabled(&ProviderType::Claude),
             preferred_provider: config.auth.preferred_provider,
             enable_fallback: config.auth.enable_fallback,
             fallback_strategy: config.auth.fallback_strategy,
-            openai_available: config.auth_data.openai_auth.is_some(),
-            claude_available: config.auth_data.claude_auth.is_some(),
         })
     }
 
@@ -250,9 +265,10 @@ impl ConfigIntegration {
         
         // Convert to TOML value
         let auth_value = toml::to_string(&auth_toml)
-            .map_err(|e| ConfigError::Toml(e.into()))?;
-        let auth_table: toml_edit::Table = auth_value.parse()
-            .map_err(|_| ConfigError::Toml(toml::de::Error::custom("Failed to parse auth config")))?;
+            .map_err(|e| ConfigError::Serialize { format: ConfigFormat::Toml, message: e.to_string() })?;
+        let auth_table = auth_value.parse::<toml_edit::DocumentMut>()
+            .map(|auth_doc| auth_doc.as_table().clone())
+            .map_err(|e| ConfigError::Parse { format: ConfigFormat::Toml, message: e.to_string() })?;
 
         doc["auth"] = toml_edit::Item::Table(auth_table);
 
@@ -299,7 +315,7 @@ pub struct ExistingConfig {
 pub struct ProviderSelection {
     pub preferred_provider: ProviderType,
     pub enable_fallback: bool,
-    pub fallback_strategy: super::auth_config::FallbackStrategy,
+    pub fallback_strategy: crate::configuration::auth_config::FallbackStrategy,
     pub openai_available: bool,
     pub claude_available: bool,
 }
@@ -308,18 +324,20 @@ impl ProviderSelection {
     /// Determine which provider to use based on current configuration
     pub fn select_provider(&self, context: &SelectionContext) -> ProviderType {
         // If force provider is set, use it
-        if let Some(forced) = context.force_provider {
-            return forced;
+        if let Some(forced) = &context.force_provider {
+            return forced.clone();
         }
 
-        // Check if preferred provider is available
+        // Check if preferred provider is available; custom providers are resolved by
+        // whoever registered them, so they are taken as requested
         let preferred_available = match self.preferred_provider {
             ProviderType::OpenAI => self.openai_available,
             ProviderType::Claude => self.claude_available,
+            ProviderType::Custom(_) => return self.preferred_provider.clone(),
         };
 
         if preferred_available {
-            return self.preferred_provider;
+            return self.preferred_provider.clone();
         }
 
         // Fallback logic
@@ -327,10 +345,10 @@ impl ProviderSelection {
             match self.preferred_provider {
                 ProviderType::OpenAI if self.claude_available => ProviderType::Claude,
                 ProviderType::Claude if self.openai_available => ProviderType::OpenAI,
-                _ => self.preferred_provider, // Return preferred even if not available
+                _ => self.preferred_provider.clone(), // Return preferred even if not available
             }
         } else {
-            self.preferred_provider
+            self.preferred_provider.clone()
         }
     }
 
@@ -355,7 +373,7 @@ pub struct SelectionContext {
 /// Error context for fallback decisions
 #[derive(Debug, Clone)]
 pub struct AuthErrorContext {
-    pub error_type: super::auth_config::AuthErrorType,
+    pub error_type: crate::configuration::auth_config::AuthErrorType,
     pub provider: ProviderType,
     pub retry_count: u32,
 }
@@ -421,7 +439,7 @@ mod tests {
         let selection = ProviderSelection {
             preferred_provider: ProviderType::Claude,
             enable_fallback: true,
-            fallback_strategy: super::auth_config::FallbackStrategy::Automatic,
+            fallback_strategy: crate::configuration::auth_config::FallbackStrategy::Automatic,
             openai_available: true,
             claude_available: false,
         };
@@ -441,7 +459,7 @@ mod tests {
         let selection = ProviderSelection {
             preferred_provider: ProviderType::Claude,
             enable_fallback: true,
-            fallback_strategy: super::auth_config::FallbackStrategy::Automatic,
+            fallback_strategy: crate::configuration::auth_config::FallbackStrategy::Automatic,
             openai_available: true,
             claude_available: false,
         };
@@ -461,19 +479,19 @@ mod tests {
         let selection = ProviderSelection {
             preferred_provider: ProviderType::Claude,
             enable_fallback: true,
-            fallback_strategy: super::auth_config::FallbackStrategy::OnQuotaExhausted,
+            fallback_strategy: crate::configuration::auth_config::FallbackStrategy::OnQuotaExhausted,
             openai_available: true,
             claude_available: true,
         };
 
         let quota_error = AuthErrorContext {
-            error_type: super::auth_config::AuthErrorType::QuotaExhausted,
+            error_type: crate::configuration::auth_config::AuthErrorType::QuotaExhausted,
             provider: ProviderType::Claude,
             retry_count: 0,
         };
 
         let auth_error = AuthErrorContext {
-            error_type: super::auth_config::AuthErrorType::AuthenticationFailed,
+            error_type: crate::configuration::auth_config::AuthErrorType::AuthenticationFailed,
             provider: ProviderType::Claude,
             retry_count: 0,
         };
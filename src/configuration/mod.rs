@@ -11,12 +11,19 @@ pub mod validation;
 pub mod environment;
 pub mod integration;
 pub mod auth_manager_integration;
+pub mod watcher;
+pub mod format;
+pub mod profiles;
 
 pub use auth_config::{
     AuthConfig, 
     ProviderType, 
     ProviderPreference, 
+    ProviderSchedule,
+    ProviderWindow,
     FallbackStrategy,
+    ModelAliases,
+    ModelAliasError,
     SubscriptionCheckConfig,
 };
 
@@ -24,6 +31,7 @@ pub use unified_storage::{
     UnifiedAuthJson,
     UnifiedAuthStorage,
     StorageError,
+    CURRENT_STORAGE_VERSION,
     AuthData,
     OpenAIAuthData,
     ClaudeAuthData,
@@ -37,16 +45,24 @@ pub use migration::{
 };
 
 pub use validation::{
+    rule_fn,
     ConfigValidator,
+    RuleResult,
+    ValidationContext,
     ValidationError,
+    ValidationIssue,
     ValidationResult,
     ValidationRule,
+    ValidationRuleExt,
 };
 
 pub use environment::{
     EnvironmentOverrides,
     EnvironmentConfig,
     EnvironmentError,
+    OverrideReport,
+    OverrideSource,
+    SettingResolution,
 };
 
 pub use integration::{
@@ -58,6 +74,12 @@ pub use integration::{
     integration_helpers,
 };
 
+pub use watcher::{ConfigWatcher, DEFAULT_WATCH_DEBOUNCE};
+
+pub use format::ConfigFormat;
+
+pub use profiles::{active_profile_home, ProfileError, DEFAULT_PROFILE};
+
 pub use auth_manager_integration::{
     UnifiedAuthManager,
     AuthProviderWrapper,
@@ -68,39 +90,58 @@ pub use auth_manager_integration::{
     get_preferred_provider,
 };
 
-use std::path::PathBuf;
+use std::collections::HashSet;
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
 use chrono::{DateTime, Utc, Duration};
 use serde::{Deserialize, Serialize};
 
 /// Configuration manager that integrates Claude authentication with existing Code project config
 #[derive(Debug, Clone)]
 pub struct UnifiedConfigManager {
+    codex_home: PathBuf,
     pub base_config_path: PathBuf,
     pub auth_storage: UnifiedAuthStorage,
     pub migrator: ConfigMigrator,
     pub validator: ConfigValidator,
     pub env_config: EnvironmentConfig,
+    /// Digest of the last config.toml we wrote, so watchers can skip our own saves
+    last_saved_digest: Arc<Mutex<Option<u64>>>,
 }
 
 impl UnifiedConfigManager {
     /// Create new configuration manager with default settings
     pub fn new(codex_home: PathBuf) -> Result<Self, ConfigError> {
-        let auth_storage = UnifiedAuthStorage::new(&codex_home)?;
+        // Credentials follow the active profile; config.toml itself stays shared
+        let auth_storage = UnifiedAuthStorage::new(&active_profile_home(&codex_home)?)?;
         let migrator = ConfigMigrator::new(&codex_home)?;
         let validator = ConfigValidator::new();
         let env_config = EnvironmentConfig::new();
         
         Ok(Self {
-            base_config_path: codex_home.join("config.toml"),
+            base_config_path: Self::locate_base_config(&codex_home),
+            codex_home,
             auth_storage,
             migrator,
             validator,
             env_config,
+            last_saved_digest: Arc::new(Mutex::new(None)),
         })
     }
 
+    /// Format of the base configuration file, detected from its extension
+    pub fn config_format(&self) -> ConfigFormat {
+        ConfigFormat::from_path(&self.base_config_path).unwrap_or_default()
+    }
+
     /// Load configuration with migration and validation
     pub async fn load_config(&self) -> Result<UnifiedConfig, ConfigError> {
+        self.load_config_with_report().await.map(|(config, _)| config)
+    }
+
+    /// Load configuration and report which source supplied each auth setting
+    pub async fn load_config_with_report(&self) -> Result<(UnifiedConfig, OverrideReport), ConfigError> {
         // Check if migration is needed
         if self.migrator.needs_migration()? {
             let backup = self.migrator.create_backup().await?;
@@ -117,13 +158,38 @@ impl UnifiedConfigManager {
         // Load base configuration
         let mut config = self.load_base_config()?;
         
-        // Apply environment overrides
-        self.env_config.apply_overrides(&mut config)?;
+        // Apply environment overrides (env > config file > default)
+        let report = self.env_config.apply_overrides(&mut config)?;
+        for resolution in report.overridden() {
+            tracing::debug!(
+                "{} overridden by {} = {}",
+                resolution.setting,
+                resolution.variable.as_deref().unwrap_or("environment"),
+                resolution.value
+            );
+        }
         
         // Validate configuration
         self.validator.validate(&config)?;
         
-        Ok(config)
+        Ok((config, report))
+    }
+
+    /// Validate the configuration as it would load, without running config migrations
+    ///
+    /// Schema problems in the file come back as an invalid result rather than an error.
+    /// Returns `None` when there is no configuration file to inspect.
+    pub fn inspect_config(&self) -> Result<Option<ValidationResult>, ConfigError> {
+        if !self.base_config_path.exists() {
+            return Ok(None);
+        }
+        let mut config = match self.load_base_config() {
+            Ok(config) => config,
+            Err(ConfigError::Validation(ValidationError::Invalid(result))) => return Ok(Some(result)),
+            Err(e) => return Err(e),
+        };
+        self.env_config.apply_overrides(&mut config)?;
+        Ok(Some(self.validator.validate(&config)?))
     }
 
     /// Save configuration changes
@@ -132,7 +198,7 @@ impl UnifiedConfigManager {
         self.validator.validate(config)?;
         
         // Save unified auth data
-        self.auth_storage.save(&config.auth_data).await?;
+        self.auth_storage.save(&config.auth_data)?;
         
         // Update base configuration if needed
         self.save_base_config(config)?;
@@ -179,13 +245,41 @@ impl UnifiedConfigManager {
     }
 
     // Private helper methods
+
+    /// Use the first existing config file (TOML, then JSON, then YAML), defaulting to TOML
+    fn locate_base_config(codex_home: &Path) -> PathBuf {
+        ConfigFormat::ALL
+            .iter()
+            .map(|format| codex_home.join(format.file_name()))
+            .find(|path| path.exists())
+            .unwrap_or_else(|| codex_home.join(ConfigFormat::default().file_name()))
+    }
+
     fn load_base_config(&self) -> Result<UnifiedConfig, ConfigError> {
         if !self.base_config_path.exists() {
             return Ok(UnifiedConfig::default());
         }
 
+        let format = self.config_format();
         let content = std::fs::read_to_string(&self.base_config_path)?;
-        let base_config: BaseConfig = toml::from_str(&content)?;
+        let document = format.parse_document(&content)?;
+
+        // Report every schema problem at once instead of serde's first failure
+        let schema = self.validator.validate_document(&document);
+        if !schema.is_valid {
+            return Err(ValidationError::Invalid(schema).into());
+        }
+
+        // Remember which [auth] keys the file sets explicitly for override reporting
+        let file_settings = document
+            .get("auth")
+            .and_then(|auth| auth.as_table())
+            .map(|table| table.keys().cloned().collect())
+            .unwrap_or_default();
+
+        let base_config: BaseConfig = document
+            .try_into()
+            .map_err(|e: toml::de::Error| ConfigError::Parse { format, message: e.to_string() })?;
         
         // Load auth data separately
         let auth_data = self.auth_storage.load()?;
@@ -193,6 +287,7 @@ impl UnifiedConfigManager {
         Ok(UnifiedConfig {
             auth: base_config.auth.unwrap_or_default(),
             auth_data,
+            file_settings,
             // Copy other fields as needed
         })
     }
@@ -208,11 +303,24 @@ impl UnifiedConfigManager {
             auth: Some(config.auth.clone()),
         };
 
-        let content = toml::to_string_pretty(&base_config)?;
+        // Write back in the format the file was loaded from
+        let content = self.config_format().serialize(&base_config)?;
+        // Record the digest before writing so a watcher never sees the file first
+        *self.last_saved_digest.lock().unwrap() = Some(content_digest(&content));
         std::fs::write(&self.base_config_path, content)?;
         
         Ok(())
     }
+
+    fn is_own_save(&self, digest: u64) -> bool {
+        *self.last_saved_digest.lock().unwrap() == Some(digest)
+    }
+}
+
+fn content_digest(content: &str) -> u64 {
+    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+    content.hash(&mut hasher);
+    hasher.finish()
 }
 
 /// Unified configuration structure
@@ -221,6 +329,9 @@ pub struct UnifiedConfig {
     pub auth: AuthConfig,
     #[serde(skip)]
     pub auth_data: UnifiedAuthJson,
+    /// Auth settings explicitly present in config.toml
+    #[serde(skip)]
+    pub file_settings: HashSet<String>,
 }
 
 impl Default for UnifiedConfig {
@@ -228,6 +339,7 @@ impl Default for UnifiedConfig {
         Self {
             auth: AuthConfig::default(),
             auth_data: UnifiedAuthJson::default(),
+            file_settings: HashSet::new(),
         }
     }
 }
@@ -248,6 +360,12 @@ pub enum ConfigError {
     
     #[error("TOML serialization error: {0}")]
     Toml(#[from] toml::de::Error),
+
+    #[error("Failed to parse {format} config: {message}")]
+    Parse { format: ConfigFormat, message: String },
+
+    #[error("Failed to serialize {format} config: {message}")]
+    Serialize { format: ConfigFormat, message: String },
     
     #[error("Storage error: {0}")]
     Storage(#[from] StorageError),
@@ -260,6 +378,12 @@ pub enum ConfigError {
     
     #[error("Environment error: {0}")]
     Environment(#[from] EnvironmentError),
+
+    #[error("File watch error: {0}")]
+    Watch(#[from] notify::Error),
+
+    #[error("Profile error: {0}")]
+    Profile(#[from] ProfileError),
 }
 
 #[cfg(test)]
@@ -294,4 +418,49 @@ mod tests {
         let preference = manager.get_provider_preference().unwrap();
         assert_eq!(preference, ProviderType::Claude);
     }
+
+    #[test]
+    fn test_defaults_to_toml_without_config_file() {
+        let temp_dir = tempdir().unwrap();
+        let manager = UnifiedConfigManager::new(temp_dir.path().to_path_buf()).unwrap();
+        assert_eq!(manager.config_format(), ConfigFormat::Toml);
+        assert!(manager.base_config_path.ends_with("config.toml"));
+    }
+
+    #[test]
+    fn test_auth_config_round_trips_in_every_format() {
+        let mut auth = AuthConfig::claude_max_optimized();
+        auth.fallback_strategy = FallbackStrategy::ChainOrdered {
+            providers: vec![ProviderType::Claude, ProviderType::OpenAI],
+        };
+
+        for format in ConfigFormat::ALL {
+            let temp_dir = tempdir().unwrap();
+            let content = format.serialize(&BaseConfig { auth: Some(auth.clone()) }).unwrap();
+            std::fs::write(temp_dir.path().join(format.file_name()), content).unwrap();
+
+            let manager = UnifiedConfigManager::new(temp_dir.path().to_path_buf()).unwrap();
+            assert_eq!(manager.config_format(), format);
+
+            let loaded = manager.load_base_config().unwrap();
+            assert_eq!(loaded.auth, auth, "{} load", format);
+
+            // Saving writes back in the same format
+            manager.save_base_config(&loaded).unwrap();
+            let written = std::fs::read_to_string(&manager.base_config_path).unwrap();
+            let reparsed: BaseConfig = format.parse_document(&written).unwrap().try_into().unwrap();
+            assert_eq!(reparsed.auth, Some(auth.clone()), "{} save", format);
+        }
+    }
+
+    #[test]
+    fn test_parse_errors_name_the_format() {
+        let temp_dir = tempdir().unwrap();
+        std::fs::write(temp_dir.path().join("config.yaml"), "auth: [unclosed").unwrap();
+
+        // Resolving the active profile parses the file, so the error surfaces from `new`
+        let err = UnifiedConfigManager::new(temp_dir.path().to_path_buf()).unwrap_err();
+        assert!(matches!(err, ConfigError::Parse { format: ConfigFormat::Yaml, .. }), "{:?}", err);
+        assert!(err.to_string().contains("YAML"));
+    }
 }
\ No newline at end of file
@@ -0,0 +1,221 @@
+//! Named authentication profiles
+//!
+//! Each profile keeps its own credentials under `codex_home/profiles/<name>/`, and
+//! `auth.active_profile` in config.toml selects which one is used. Without an active
+//! profile credentials live directly in `codex_home`, as before profiles existed.
+
+use std::path::{Path, PathBuf};
+
+use super::{ConfigError, ConfigFormat, UnifiedAuthStorage, UnifiedConfigManager};
+
+/// Directory under `codex_home` that holds one subdirectory per profile
+pub const PROFILES_DIR: &str = "profiles";
+
+/// Name the CLI uses for the credentials stored directly in `codex_home`
+pub const DEFAULT_PROFILE: &str = "default";
+
+/// Profile management errors
+#[derive(Debug, thiserror::Error)]
+pub enum ProfileError {
+    #[error("Invalid profile name '{0}': use letters, digits, '-' or '_'")]
+    InvalidName(String),
+
+    #[error("Profile '{0}' does not exist")]
+    NotFound(String),
+
+    #[error("Profile '{0}' already exists")]
+    AlreadyExists(String),
+
+    #[error("Profile '{0}' is active; switch to another profile before removing it")]
+    Active(String),
+
+    #[error("'{0}' is reserved for the credentials in codex_home")]
+    Reserved(String),
+}
+
+/// Profile names become directory names, so anything that could escape `profiles/` is rejected
+pub fn validate_profile_name(name: &str) -> Result<(), ProfileError> {
+    let valid = !name.is_empty()
+        && name.len() <= 64
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    if !valid {
+        Err(ProfileError::InvalidName(name.to_string()))
+    } else if name == DEFAULT_PROFILE {
+        Err(ProfileError::Reserved(name.to_string()))
+    } else {
+        Ok(())
+    }
+}
+
+/// Directory holding the credentials of profile `name`
+pub fn profile_dir(codex_home: &Path, name: &str) -> PathBuf {
+    codex_home.join(PROFILES_DIR).join(name)
+}
+
+/// Directory holding the active profile's credentials
+///
+/// Reads `auth.active_profile` straight from the config file so auth loaders can
+/// resolve it without a full [`UnifiedConfigManager`]. An unreadable config is an
+/// error rather than a silent fallback to another identity's credentials.
+pub fn active_profile_home(codex_home: &Path) -> Result<PathBuf, ConfigError> {
+    match read_active_profile(codex_home)? {
+        Some(name) => {
+            validate_profile_name(&name)?;
+            Ok(profile_dir(codex_home, &name))
+        }
+        None => Ok(codex_home.to_path_buf()),
+    }
+}
+
+fn read_active_profile(codex_home: &Path) -> Result<Option<String>, ConfigError> {
+    let path = UnifiedConfigManager::locate_base_config(codex_home);
+    if !path.exists() {
+        return Ok(None);
+    }
+
+    let format = ConfigFormat::from_path(&path).unwrap_or_default();
+    let document = format.parse_document(&std::fs::read_to_string(&path)?)?;
+    Ok(document
+        .get("auth")
+        .and_then(|auth| auth.get("active_profile"))
+        .and_then(|profile| profile.as_str())
+        .map(str::to_string))
+}
+
+impl UnifiedConfigManager {
+    /// Name of the active profile, `None` for the default credentials in `codex_home`
+    pub fn active_profile(&self) -> Result<Option<String>, ConfigError> {
+        Ok(self.load_base_config()?.auth.active_profile)
+    }
+
+    /// Directory holding the active profile's credentials
+    pub fn profile_home(&self) -> Result<PathBuf, ConfigError> {
+        active_profile_home(&self.codex_home)
+    }
+
+    /// Names of all profiles, sorted
+    pub fn list_profiles(&self) -> Result<Vec<String>, ConfigError> {
+        let dir = self.codex_home.join(PROFILES_DIR);
+        if !dir.exists() {
+            return Ok(Vec::new());
+        }
+
+        let mut names = Vec::new();
+        for entry in std::fs::read_dir(dir)? {
+            let entry = entry?;
+            if entry.file_type()?.is_dir() {
+                let name = entry.file_name().to_string_lossy().into_owned();
+                if validate_profile_name(&name).is_ok() {
+                    names.push(name);
+                }
+            }
+        }
+        names.sort();
+        Ok(names)
+    }
+
+    /// Create an empty profile and return its directory
+    pub fn add_profile(&self, name: &str) -> Result<PathBuf, ConfigError> {
+        validate_profile_name(name)?;
+        let dir = profile_dir(&self.codex_home, name);
+        if dir.exists() {
+            return Err(ProfileError::AlreadyExists(name.to_string()).into());
+        }
+
+        std::fs::create_dir_all(&dir)?;
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            std::fs::set_permissions(&dir, std::fs::Permissions::from_mode(0o700))?;
+        }
+        Ok(dir)
+    }
+
+    /// Make `name` the active profile, or return to the default credentials with `None`
+    ///
+    /// Auth storage is rebound to the new profile so nothing written afterwards lands
+    /// in the previous profile's directory.
+    pub fn use_profile(&mut self, name: Option<&str>) -> Result<(), ConfigError> {
+        let home = match name {
+            Some(name) => {
+                validate_profile_name(name)?;
+                let dir = profile_dir(&self.codex_home, name);
+                if !dir.is_dir() {
+                    return Err(ProfileError::NotFound(name.to_string()).into());
+                }
+                dir
+            }
+            None => self.codex_home.clone(),
+        };
+
+        let mut config = self.load_base_config()?;
+        config.auth.active_profile = name.map(str::to_string);
+        self.save_base_config(&config)?;
+        self.auth_storage = UnifiedAuthStorage::new(&home)?;
+        Ok(())
+    }
+
+    /// Delete a profile and every credential stored in it
+    pub fn remove_profile(&self, name: &str) -> Result<(), ConfigError> {
+        validate_profile_name(name)?;
+        if self.active_profile()?.as_deref() == Some(name) {
+            return Err(ProfileError::Active(name.to_string()).into());
+        }
+
+        let dir = profile_dir(&self.codex_home, name);
+        if !dir.is_dir() {
+            return Err(ProfileError::NotFound(name.to_string()).into());
+        }
+        std::fs::remove_dir_all(dir)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_profile_names_cannot_escape_profiles_dir() {
+        assert!(validate_profile_name("client-a").is_ok());
+        assert!(validate_profile_name("personal_2").is_ok());
+        for name in ["", "..", "a/b", "../root", "with space"] {
+            assert!(matches!(validate_profile_name(name), Err(ProfileError::InvalidName(_))), "{name}");
+        }
+        assert!(matches!(validate_profile_name(DEFAULT_PROFILE), Err(ProfileError::Reserved(_))));
+    }
+
+    #[test]
+    fn test_profile_lifecycle() {
+        let temp_dir = tempdir().unwrap();
+        let mut manager = UnifiedConfigManager::new(temp_dir.path().to_path_buf()).unwrap();
+        assert_eq!(manager.profile_home().unwrap(), temp_dir.path());
+
+        manager.add_profile("personal").unwrap();
+        manager.add_profile("client-a").unwrap();
+        assert!(matches!(
+            manager.add_profile("personal"),
+            Err(ConfigError::Profile(ProfileError::AlreadyExists(_)))
+        ));
+        assert_eq!(manager.list_profiles().unwrap(), ["client-a", "personal"]);
+
+        manager.use_profile(Some("client-a")).unwrap();
+        assert_eq!(manager.active_profile().unwrap().as_deref(), Some("client-a"));
+        assert_eq!(active_profile_home(temp_dir.path()).unwrap(), profile_dir(temp_dir.path(), "client-a"));
+
+        assert!(matches!(
+            manager.remove_profile("client-a"),
+            Err(ConfigError::Profile(ProfileError::Active(_)))
+        ));
+        assert!(matches!(
+            manager.use_profile(Some("missing")),
+            Err(ConfigError::Profile(ProfileError::NotFound(_)))
+        ));
+
+        manager.use_profile(None).unwrap();
+        manager.remove_profile("client-a").unwrap();
+        assert_eq!(manager.list_profiles().unwrap(), ["personal"]);
+        assert_eq!(manager.profile_home().unwrap(), temp_dir.path());
+    }
+}
@@ -8,9 +8,12 @@ use chrono::{DateTime, Utc};
 use std::collections::HashMap;
 use std::path::{Path, PathBuf};
 use std::fs;
-use std::io::Write;
-#[cfg(unix)]
-use std::os::unix::fs::PermissionsExt;
+
+use crate::security::secret::masked;
+use crate::security::write_atomic;
+
+/// Newest storage format version this build reads and writes
+pub const CURRENT_STORAGE_VERSION: u32 = 2;
 
 /// Unified authentication storage that handles multiple providers
 #[derive(Debug, Clone)]
@@ -39,13 +42,29 @@ impl UnifiedAuthStorage {
     }
 
     /// Load unified authentication data
+    ///
+    /// Older unified versions are upgraded in memory; the file keeps its version until the
+    /// next save. A file written by a newer build is refused with
+    /// [`StorageError::UnsupportedVersion`] rather than read into a shape that would drop
+    /// its newer fields.
     pub fn load(&self) -> Result<UnifiedAuthJson, StorageError> {
         if !self.storage_path.exists() {
             return Ok(UnifiedAuthJson::default());
         }
 
         let content = fs::read_to_string(&self.storage_path)?;
-        
+        match stored_version(&content) {
+            Some(found) if found > CURRENT_STORAGE_VERSION => {
+                return Err(StorageError::UnsupportedVersion { found, supported: CURRENT_STORAGE_VERSION });
+            }
+            Some(1) => {
+                let v1: UnifiedAuthJsonV1 = serde_json::from_str(&content)?;
+                tracing::info!("Upgrading auth.json from storage version 1");
+                return Ok(v1.into());
+            }
+            _ => {}
+        }
+
         // Try to parse as unified format first
         if let Ok(unified) = serde_json::from_str::<UnifiedAuthJson>(&content) {
             return Ok(unified);
@@ -63,9 +82,16 @@ impl UnifiedAuthStorage {
     }
 
     /// Save unified authentication data
+    ///
+    /// Refuses to overwrite a file written by a newer build.
     pub fn save(&self, data: &UnifiedAuthJson) -> Result<(), StorageError> {
         // Create backup of existing file
         if self.storage_path.exists() {
+            if let Some(found) = stored_version(&fs::read_to_string(&self.storage_path)?) {
+                if found > CURRENT_STORAGE_VERSION {
+                    return Err(StorageError::UnsupportedVersion { found, supported: CURRENT_STORAGE_VERSION });
+                }
+            }
             fs::copy(&self.storage_path, &self.backup_path)?;
         }
 
@@ -76,23 +102,7 @@ impl UnifiedAuthStorage {
             serde_json::to_string_pretty(data)?
         };
 
-        // Write atomically using temporary file
-        let temp_path = self.storage_path.with_extension("tmp");
-        {
-            let mut file = fs::File::create(&temp_path)?;
-            file.write_all(content.as_bytes())?;
-            file.sync_all()?;
-        }
-
-        // Set secure permissions (0o600)
-        #[cfg(unix)]
-        {
-            let permissions = fs::Permissions::from_mode(0o600);
-            fs::set_permissions(&temp_path, permissions)?;
-        }
-
-        // Atomic rename
-        fs::rename(temp_path, &self.storage_path)?;
+        write_atomic(&self.storage_path, content.as_bytes(), 0o600)?;
 
         Ok(())
     }
@@ -181,7 +191,7 @@ impl UnifiedAuthStorage {
             version: 2,
             openai_auth,
             claude_auth: None,
-            preferred_provider: super::ProviderType::OpenAI,
+            preferred_provider: crate::configuration::ProviderType::OpenAI,
             last_provider_check: None,
             last_subscription_check: None,
             provider_capabilities: HashMap::new(),
@@ -238,7 +248,7 @@ impl Default for UnifiedAuthJson {
             version: 2,
             openai_auth: None,
             claude_auth: None,
-            preferred_provider: super::ProviderType::OpenAI,
+            preferred_provider: crate::configuration::ProviderType::OpenAI,
             last_provider_check: None,
             last_subscription_check: None,
             provider_capabilities: HashMap::new(),
@@ -248,7 +258,7 @@ impl Default for UnifiedAuthJson {
 }
 
 /// OpenAI authentication data
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Clone, Serialize, Deserialize, PartialEq)]
 pub struct OpenAIAuthData {
     #[serde(rename = "OPENAI_API_KEY")]
     pub api_key: Option<String>,
@@ -258,7 +268,7 @@ pub struct OpenAIAuthData {
 }
 
 /// Claude authentication data
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Clone, Serialize, Deserialize, PartialEq)]
 pub struct ClaudeAuthData {
     pub api_key: Option<String>,
     
@@ -271,7 +281,7 @@ pub struct ClaudeAuthData {
 }
 
 /// OpenAI token data structure (compatible with existing format)
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Clone, Serialize, Deserialize, PartialEq)]
 pub struct OpenAITokenData {
     pub access_token: String,
     pub refresh_token: String,
@@ -280,7 +290,7 @@ pub struct OpenAITokenData {
 }
 
 /// Claude token data structure
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Clone, Serialize, Deserialize, PartialEq)]
 pub struct ClaudeTokenData {
     pub access_token: String,
     pub refresh_token: Option<String>,
@@ -289,6 +299,50 @@ pub struct ClaudeTokenData {
     pub scope: Option<String>,
 }
 
+// Credentials are masked so stray `{:?}` output can't leak them
+
+impl std::fmt::Debug for OpenAIAuthData {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("OpenAIAuthData")
+            .field("api_key", &self.api_key.as_deref().map(masked))
+            .field("tokens", &self.tokens)
+            .finish()
+    }
+}
+
+impl std::fmt::Debug for ClaudeAuthData {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ClaudeAuthData")
+            .field("api_key", &self.api_key.as_deref().map(masked))
+            .field("tokens", &self.tokens)
+            .field("subscription", &self.subscription)
+            .finish()
+    }
+}
+
+impl std::fmt::Debug for OpenAITokenData {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("OpenAITokenData")
+            .field("access_token", &masked(&self.access_token))
+            .field("refresh_token", &masked(&self.refresh_token))
+            .field("expires_at", &self.expires_at)
+            .field("account_id", &self.account_id)
+            .finish()
+    }
+}
+
+impl std::fmt::Debug for ClaudeTokenData {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ClaudeTokenData")
+            .field("access_token", &masked(&self.access_token))
+            .field("refresh_token", &self.refresh_token.as_deref().map(masked))
+            .field("expires_at", &self.expires_at)
+            .field("token_type", &self.token_type)
+            .field("scope", &self.scope)
+            .finish()
+    }
+}
+
 /// Claude subscription information
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct ClaudeSubscriptionInfo {
@@ -338,6 +392,57 @@ impl Default for AuthMetadata {
     }
 }
 
+/// Storage version recorded in `content`, if it is JSON with a numeric `version`
+fn stored_version(content: &str) -> Option<u32> {
+    let value: serde_json::Value = serde_json::from_str(content).ok()?;
+    value.get("version")?.as_u64().map(|version| u32::try_from(version).unwrap_or(u32::MAX))
+}
+
+/// Storage version 1, before subscription checks, capabilities and metadata were stored
+#[derive(Debug, Clone, Deserialize)]
+struct UnifiedAuthJsonV1 {
+    openai_auth: Option<OpenAIAuthDataV1>,
+    claude_auth: Option<ClaudeAuthData>,
+    preferred_provider: Option<super::ProviderType>,
+    last_provider_check: Option<DateTime<Utc>>,
+}
+
+impl From<UnifiedAuthJsonV1> for UnifiedAuthJson {
+    fn from(v1: UnifiedAuthJsonV1) -> Self {
+        Self {
+            version: CURRENT_STORAGE_VERSION,
+            openai_auth: v1.openai_auth.map(OpenAIAuthData::from),
+            claude_auth: v1.claude_auth,
+            preferred_provider: v1.preferred_provider.unwrap_or(super::ProviderType::OpenAI),
+            last_provider_check: v1.last_provider_check,
+            last_subscription_check: None,
+            provider_capabilities: HashMap::new(),
+            metadata: AuthMetadata {
+                migration_source: Some("unified_auth_v1".to_string()),
+                ..AuthMetadata::default()
+            },
+        }
+    }
+}
+
+/// OpenAI section of storage version 1, which kept the key under its `auth.json` name
+#[derive(Debug, Clone, Deserialize)]
+struct OpenAIAuthDataV1 {
+    #[serde(rename = "OPENAI_API_KEY")]
+    openai_api_key: Option<String>,
+    #[serde(default)]
+    tokens: Option<OpenAITokenData>,
+}
+
+impl From<OpenAIAuthDataV1> for OpenAIAuthData {
+    fn from(v1: OpenAIAuthDataV1) -> Self {
+        Self {
+            api_key: v1.openai_api_key,
+            tokens: v1.tokens,
+        }
+    }
+}
+
 /// Legacy auth.json format for migration
 #[derive(Debug, Clone, Serialize, Deserialize)]
 struct LegacyAuthJson {
@@ -361,7 +466,7 @@ pub trait AuthData: Send + Sync {
 
 impl AuthData for OpenAIAuthData {
     fn provider_type(&self) -> super::ProviderType {
-        super::ProviderType::OpenAI
+        crate::configuration::ProviderType::OpenAI
     }
 
     fn is_authenticated(&self) -> bool {
@@ -384,7 +489,7 @@ impl AuthData for OpenAIAuthData {
 
 impl AuthData for ClaudeAuthData {
     fn provider_type(&self) -> super::ProviderType {
-        super::ProviderType::Claude
+        crate::configuration::ProviderType::Claude
     }
 
     fn is_authenticated(&self) -> bool {
@@ -432,10 +537,13 @@ pub enum StorageError {
     
     #[error("Encryption error: {0}")]
     EncryptionError(String),
+
+    #[error("auth.json uses storage version {found}, but this build supports up to version {supported}; upgrade to use it")]
+    UnsupportedVersion { found: u32, supported: u32 },
 }
 
 fn default_version() -> u32 {
-    2
+    CURRENT_STORAGE_VERSION
 }
 
 #[cfg(test)]
@@ -454,7 +562,7 @@ mod tests {
     fn test_default_unified_auth_json() {
         let auth_json = UnifiedAuthJson::default();
         assert_eq!(auth_json.version, 2);
-        assert_eq!(auth_json.preferred_provider, super::ProviderType::OpenAI);
+        assert_eq!(auth_json.preferred_provider, crate::configuration::ProviderType::OpenAI);
         assert!(auth_json.openai_auth.is_none());
         assert!(auth_json.claude_auth.is_none());
     }
@@ -481,7 +589,7 @@ mod tests {
             tokens: None,
             subscription: None,
         };
-        assert_eq!(auth_data.provider_type(), super::ProviderType::Claude);
+        assert_eq!(auth_data.provider_type(), crate::configuration::ProviderType::Claude);
     }
 
     #[tokio::test]
@@ -514,6 +622,46 @@ mod tests {
         assert!(!result.issues.is_empty());
     }
 
+    #[test]
+    fn test_load_across_storage_versions() {
+        let temp_dir = tempdir().unwrap();
+        let storage = UnifiedAuthStorage::new(temp_dir.path()).unwrap();
+        let auth_file = temp_dir.path().join("auth.json");
+
+        // Version 1 is upgraded in memory and left on disk as it was
+        let v1 = serde_json::json!({
+            "version": 1,
+            "openai_auth": { "OPENAI_API_KEY": "sk-v1", "tokens": null },
+            "claude_auth": null,
+            "preferred_provider": "claude",
+            "last_provider_check": null
+        })
+        .to_string();
+        fs::write(&auth_file, &v1).unwrap();
+        let upgraded = storage.load().unwrap();
+        assert_eq!(upgraded.version, CURRENT_STORAGE_VERSION);
+        assert_eq!(upgraded.openai_auth.unwrap().api_key.as_deref(), Some("sk-v1"));
+        assert_eq!(upgraded.preferred_provider, crate::configuration::ProviderType::Claude);
+        assert_eq!(upgraded.metadata.migration_source.as_deref(), Some("unified_auth_v1"));
+        assert_eq!(fs::read_to_string(&auth_file).unwrap(), v1);
+
+        let mut current = UnifiedAuthJson::default();
+        current.claude_auth = Some(ClaudeAuthData {
+            api_key: Some("sk-ant-v2".to_string()),
+            tokens: None,
+            subscription: None,
+        });
+        storage.save(&current).unwrap();
+        assert_eq!(storage.load().unwrap(), current);
+
+        // A newer build's file is neither read nor overwritten
+        let v3 = r#"{"version": 3, "providers": {"claude": {"api_key": "sk-ant-v3"}}}"#;
+        fs::write(&auth_file, v3).unwrap();
+        assert!(matches!(storage.load(), Err(StorageError::UnsupportedVersion { found: 3, supported: 2 })));
+        assert!(matches!(storage.save(&current), Err(StorageError::UnsupportedVersion { found: 3, supported: 2 })));
+        assert_eq!(fs::read_to_string(&auth_file).unwrap(), v3);
+    }
+
     #[test]
     fn test_serialization_compatibility() {
         let auth_data = UnifiedAuthJson {
@@ -527,7 +675,7 @@ mod tests {
                 tokens: None,
                 subscription: None,
             }),
-            preferred_provider: super::ProviderType::Claude,
+            preferred_provider: crate::configuration::ProviderType::Claude,
             last_provider_check: Some(Utc::now()),
             last_subscription_check: None,
             provider_capabilities: HashMap::new(),
@@ -4,21 +4,35 @@
 //! ensuring data integrity and security compliance.
 
 use std::collections::HashSet;
-use chrono::{DateTime, Utc, Duration};
+use std::sync::Arc;
+use chrono::{Utc, Duration};
 use regex::Regex;
 use once_cell::sync::Lazy;
 
-use super::auth_config::{AuthConfig, ProviderType, ProviderPreference, FallbackStrategy};
+use super::auth_config::{
+    ProviderType, ProviderPreference, ProviderSchedule, FallbackStrategy, MAX_UTC_OFFSET_MINUTES,
+};
 use super::unified_storage::{UnifiedAuthJson, OpenAIAuthData, ClaudeAuthData, AuthData};
 use super::UnifiedConfig;
+use crate::security::resolve_secret;
 
 /// Configuration validator
-#[derive(Debug, Clone)]
+#[derive(Clone)]
 pub struct ConfigValidator {
-    rules: Vec<Box<dyn ValidationRule>>,
+    /// Shared so validators clone cheaply; rules built from closures can't be cloned themselves
+    rules: Vec<Arc<dyn ValidationRule>>,
     strict_mode: bool,
 }
 
+impl std::fmt::Debug for ConfigValidator {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ConfigValidator")
+            .field("rules", &self.rules.iter().map(|rule| rule.name()).collect::<Vec<_>>())
+            .field("strict_mode", &self.strict_mode)
+            .finish()
+    }
+}
+
 impl Default for ConfigValidator {
     fn default() -> Self {
         Self::new()
@@ -28,13 +42,13 @@ impl Default for ConfigValidator {
 impl ConfigValidator {
     /// Create new validator with default rules
     pub fn new() -> Self {
-        let rules: Vec<Box<dyn ValidationRule>> = vec![
-            Box::new(BasicIntegrityRule),
-            Box::new(AuthenticationRule),
-            Box::new(SecurityRule),
-            Box::new(TokenValidityRule),
-            Box::new(ConfigurationConsistencyRule),
-            Box::new(ProviderAvailabilityRule),
+        let rules: Vec<Arc<dyn ValidationRule>> = vec![
+            Arc::new(BasicIntegrityRule),
+            Arc::new(AuthenticationRule),
+            Arc::new(SecurityRule),
+            Arc::new(TokenValidityRule),
+            Arc::new(ConfigurationConsistencyRule),
+            Arc::new(ProviderAvailabilityRule),
         ];
 
         Self {
@@ -52,14 +66,21 @@ impl ConfigValidator {
 
     /// Add custom validation rule
     pub fn add_rule<R: ValidationRule + 'static>(&mut self, rule: R) {
-        self.rules.push(Box::new(rule));
+        self.rules.push(Arc::new(rule));
+        self.rules.sort_by_key(|rule| rule.priority());
+    }
+
+    /// Builder-style variant of [`add_rule`](Self::add_rule)
+    pub fn with_rule<R: ValidationRule + 'static>(mut self, rule: R) -> Self {
+        self.add_rule(rule);
+        self
     }
 
     /// Validate configuration
+    ///
+    /// Every rule runs; all problems are collected rather than stopping at the first one.
     pub fn validate(&self, config: &UnifiedConfig) -> Result<ValidationResult, ValidationError> {
-        let mut issues = Vec::new();
-        let mut warnings = Vec::new();
-        let mut recommendations = Vec::new();
+        let mut combined = RuleResult::default();
 
         let context = ValidationContext {
             config,
@@ -68,33 +89,38 @@ impl ConfigValidator {
 
         for rule in &self.rules {
             match rule.validate(&context) {
-                Ok(result) => {
-                    issues.extend(result.issues);
-                    warnings.extend(result.warnings);
-                    recommendations.extend(result.recommendations);
-                }
+                Ok(result) => combined.merge(result),
                 Err(e) => {
-                    issues.push(format!("Validation rule failed: {}", e));
+                    combined.issues.push(ValidationIssue::new(
+                        rule.name(),
+                        format!("Validation rule failed: {}", e),
+                    ));
                 }
             }
         }
 
-        let is_valid = issues.is_empty();
-        let severity = if !is_valid {
-            ValidationSeverity::Error
-        } else if !warnings.is_empty() {
-            ValidationSeverity::Warning
-        } else {
-            ValidationSeverity::Valid
-        };
+        Ok(ValidationResult::from_rule_result(combined))
+    }
 
-        Ok(ValidationResult {
-            is_valid,
-            severity,
-            issues,
-            warnings,
-            recommendations,
-        })
+    /// Check the raw `config.toml` contents before they are deserialized
+    ///
+    /// Catches values the typed config cannot represent, such as an unknown provider name,
+    /// and reports them with their TOML field path.
+    pub fn validate_source(&self, content: &str) -> Result<ValidationResult, ValidationError> {
+        let document: toml::Value = toml::from_str(content)
+            .map_err(|e| ValidationError::ConfigError(e.to_string()))?;
+
+        Ok(self.validate_document(&document))
+    }
+
+    /// Schema checks on an already-parsed document, whatever format it was read from
+    pub fn validate_document(&self, document: &toml::Value) -> ValidationResult {
+        let mut result = RuleResult::default();
+        if let Some(auth) = document.get("auth") {
+            result.issues.extend(schema::check_auth_table(auth));
+        }
+
+        ValidationResult::from_rule_result(result)
     }
 
     /// Quick validation check (errors only)
@@ -128,20 +154,17 @@ impl ConfigValidator {
 
         // Check for authentication availability
         if auth_data.openai_auth.is_none() && auth_data.claude_auth.is_none() {
-            issues.push("No authentication providers configured".to_string());
+            issues.push(
+                ValidationIssue::new("auth_data", "No authentication providers configured")
+                    .with_suggestion("Log in with at least one provider"),
+            );
         }
 
-        ValidationResult {
-            is_valid: issues.is_empty(),
-            severity: if issues.is_empty() { 
-                ValidationSeverity::Valid 
-            } else { 
-                ValidationSeverity::Error 
-            },
+        ValidationResult::from_rule_result(RuleResult {
             issues,
             warnings,
             recommendations: Vec::new(),
-        }
+        })
     }
 }
 
@@ -158,6 +181,142 @@ pub trait ValidationRule: Send + Sync {
     fn priority(&self) -> u8 { 50 } // Lower number = higher priority
 }
 
+/// Build a rule from a closure that returns the problems it finds
+///
+/// ```ignore
+/// validator.add_rule(
+///     rule_fn("TimeoutCeiling", |ctx| {
+///         if ctx.config.auth.auth_timeout > Duration::minutes(5) {
+///             vec![ValidationIssue::new("auth.auth_timeout", "Timeout exceeds 5 minutes")]
+///         } else {
+///             Vec::new()
+///         }
+///     })
+///     .when(|ctx| ctx.strict_mode),
+/// );
+/// ```
+pub fn rule_fn<F>(name: &'static str, check: F) -> FnRule<F>
+where
+    F: Fn(&ValidationContext) -> Vec<ValidationIssue> + Send + Sync,
+{
+    FnRule { name, check }
+}
+
+/// Rule backed by a closure, created with [`rule_fn`]
+pub struct FnRule<F> {
+    name: &'static str,
+    check: F,
+}
+
+impl<F> ValidationRule for FnRule<F>
+where
+    F: Fn(&ValidationContext) -> Vec<ValidationIssue> + Send + Sync,
+{
+    fn validate(&self, context: &ValidationContext) -> Result<RuleResult, ValidationError> {
+        Ok(RuleResult {
+            issues: (self.check)(context),
+            ..Default::default()
+        })
+    }
+
+    fn name(&self) -> &'static str {
+        self.name
+    }
+}
+
+/// Combinators for composing validation rules
+pub trait ValidationRuleExt: ValidationRule + Sized {
+    /// Only run this rule when `predicate` holds for the context
+    fn when<P>(self, predicate: P) -> When<Self, P>
+    where
+        P: Fn(&ValidationContext) -> bool + Send + Sync,
+    {
+        When { rule: self, predicate }
+    }
+
+    /// Run this rule and `other`, combining their results
+    fn and<R: ValidationRule>(self, other: R) -> And<Self, R> {
+        And { first: self, second: other }
+    }
+
+    /// Override the rule's priority
+    fn with_priority(self, priority: u8) -> WithPriority<Self> {
+        WithPriority { rule: self, priority }
+    }
+}
+
+impl<R: ValidationRule> ValidationRuleExt for R {}
+
+/// Rule that only runs when a predicate holds, created with [`ValidationRuleExt::when`]
+pub struct When<R, P> {
+    rule: R,
+    predicate: P,
+}
+
+impl<R, P> ValidationRule for When<R, P>
+where
+    R: ValidationRule,
+    P: Fn(&ValidationContext) -> bool + Send + Sync,
+{
+    fn validate(&self, context: &ValidationContext) -> Result<RuleResult, ValidationError> {
+        if (self.predicate)(context) {
+            self.rule.validate(context)
+        } else {
+            Ok(RuleResult::default())
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        self.rule.name()
+    }
+
+    fn priority(&self) -> u8 {
+        self.rule.priority()
+    }
+}
+
+/// Two rules run together, created with [`ValidationRuleExt::and`]
+pub struct And<A, B> {
+    first: A,
+    second: B,
+}
+
+impl<A: ValidationRule, B: ValidationRule> ValidationRule for And<A, B> {
+    fn validate(&self, context: &ValidationContext) -> Result<RuleResult, ValidationError> {
+        let mut result = self.first.validate(context)?;
+        result.merge(self.second.validate(context)?);
+        Ok(result)
+    }
+
+    fn name(&self) -> &'static str {
+        self.first.name()
+    }
+
+    fn priority(&self) -> u8 {
+        self.first.priority().min(self.second.priority())
+    }
+}
+
+/// Rule with an overridden priority, created with [`ValidationRuleExt::with_priority`]
+pub struct WithPriority<R> {
+    rule: R,
+    priority: u8,
+}
+
+impl<R: ValidationRule> ValidationRule for WithPriority<R> {
+    fn validate(&self, context: &ValidationContext) -> Result<RuleResult, ValidationError> {
+        self.rule.validate(context)
+    }
+
+    fn name(&self) -> &'static str {
+        self.rule.name()
+    }
+
+    fn priority(&self) -> u8 {
+        self.priority
+    }
+}
+
 /// Basic integrity validation rule
 #[derive(Debug, Clone)]
 struct BasicIntegrityRule;
@@ -172,14 +331,28 @@ impl ValidationRule for BasicIntegrityRule {
         
         // Validate timeout values
         if config.auth.auth_timeout < Duration::seconds(1) {
-            issues.push("Auth timeout is too low (minimum 1 second)".to_string());
+            issues.push(
+                ValidationIssue::new("auth.auth_timeout", "Auth timeout is too low (minimum 1 second)")
+                    .with_suggestion("Set auth_timeout to at least 1s; 30s is the default"),
+            );
         }
         if config.auth.auth_timeout > Duration::minutes(10) {
             warnings.push("Auth timeout is very high (over 10 minutes)".to_string());
         }
 
         // Validate subscription check interval
-        if config.auth.subscription_check_interval < Duration::minutes(5) {
+        if config.auth.subscription_check_interval < Duration::zero() {
+            issues.push(
+                ValidationIssue::new(
+                    "auth.subscription_check_interval",
+                    format!(
+                        "Subscription check interval is negative ({}s)",
+                        config.auth.subscription_check_interval.num_seconds()
+                    ),
+                )
+                .with_suggestion("Use a positive interval such as 24h"),
+            );
+        } else if config.auth.subscription_check_interval < Duration::minutes(5) {
             warnings.push("Subscription check interval is very frequent (under 5 minutes)".to_string());
         }
 
@@ -213,7 +386,10 @@ impl ValidationRule for AuthenticationRule {
 
         // Check if at least one provider is configured
         if auth_data.openai_auth.is_none() && auth_data.claude_auth.is_none() {
-            issues.push("No authentication providers configured".to_string());
+            issues.push(
+                ValidationIssue::new("auth_data", "No authentication providers configured")
+                    .with_suggestion("Log in with at least one provider"),
+            );
             recommendations.push("Configure at least one authentication provider".to_string());
         }
 
@@ -232,7 +408,7 @@ impl ValidationRule for AuthenticationRule {
         }
 
         // Check preferred provider is actually available
-        let preferred = context.config.auth.preferred_provider;
+        let preferred = &context.config.auth.preferred_provider;
         match preferred {
             ProviderType::OpenAI if auth_data.openai_auth.is_none() => {
                 warnings.push("Preferred provider (OpenAI) is not configured".to_string());
@@ -242,6 +418,9 @@ impl ValidationRule for AuthenticationRule {
                 warnings.push("Preferred provider (Claude) is not configured".to_string());
                 recommendations.push("Either configure Claude authentication or change preferred provider".to_string());
             }
+            ProviderType::Custom(id) => {
+                warnings.push(format!("Preferred provider '{}' is not built in and must be registered at runtime", id));
+            }
             _ => {}
         }
 
@@ -262,37 +441,59 @@ impl ValidationRule for AuthenticationRule {
 }
 
 impl AuthenticationRule {
-    fn validate_openai_auth(&self, auth: &OpenAIAuthData) -> Result<(), Vec<String>> {
+    fn validate_openai_auth(&self, auth: &OpenAIAuthData) -> Result<(), Vec<ValidationIssue>> {
         let mut issues = Vec::new();
 
-        // Validate API key format
+        // Validate API key format, behind any `file:` or `env:` reference
         if let Some(api_key) = &auth.api_key {
-            if !self.is_valid_openai_api_key(api_key) {
-                issues.push("OpenAI API key format appears invalid".to_string());
+            match resolve_secret(api_key) {
+                Ok(api_key) if !self.is_valid_openai_api_key(api_key.expose_secret()) => issues.push(
+                    ValidationIssue::new("auth_data.openai_auth.api_key", "OpenAI API key format appears invalid")
+                        .with_suggestion("OpenAI keys start with 'sk-'; copy the key again from the dashboard"),
+                ),
+                Ok(_) => {}
+                Err(e) => issues.push(
+                    ValidationIssue::new("auth_data.openai_auth.api_key", format!("OpenAI API key can't be read: {}", e))
+                        .with_suggestion("Point the reference at a readable secret file or a set environment variable"),
+                ),
             }
         }
 
         // Check that either API key or tokens exist
         if auth.api_key.is_none() && auth.tokens.is_none() {
-            issues.push("OpenAI authentication has neither API key nor tokens".to_string());
+            issues.push(
+                ValidationIssue::new("auth_data.openai_auth", "OpenAI authentication has neither API key nor tokens")
+                    .with_suggestion("Log in to OpenAI again or remove the empty entry"),
+            );
         }
 
         if issues.is_empty() { Ok(()) } else { Err(issues) }
     }
 
-    fn validate_claude_auth(&self, auth: &ClaudeAuthData) -> Result<(), Vec<String>> {
+    fn validate_claude_auth(&self, auth: &ClaudeAuthData) -> Result<(), Vec<ValidationIssue>> {
         let mut issues = Vec::new();
 
-        // Validate API key format
+        // Validate API key format, behind any `file:` or `env:` reference
         if let Some(api_key) = &auth.api_key {
-            if !self.is_valid_claude_api_key(api_key) {
-                issues.push("Claude API key format appears invalid".to_string());
+            match resolve_secret(api_key) {
+                Ok(api_key) if !self.is_valid_claude_api_key(api_key.expose_secret()) => issues.push(
+                    ValidationIssue::new("auth_data.claude_auth.api_key", "Claude API key format appears invalid")
+                        .with_suggestion("Claude keys start with 'sk-ant-'; copy the key again from the console"),
+                ),
+                Ok(_) => {}
+                Err(e) => issues.push(
+                    ValidationIssue::new("auth_data.claude_auth.api_key", format!("Claude API key can't be read: {}", e))
+                        .with_suggestion("Point the reference at a readable secret file or a set environment variable"),
+                ),
             }
         }
 
         // Check that either API key or tokens exist
         if auth.api_key.is_none() && auth.tokens.is_none() {
-            issues.push("Claude authentication has neither API key nor tokens".to_string());
+            issues.push(
+                ValidationIssue::new("auth_data.claude_auth", "Claude authentication has neither API key nor tokens")
+                    .with_suggestion("Log in to Claude again or remove the empty entry"),
+            );
         }
 
         if issues.is_empty() { Ok(()) } else { Err(issues) }
@@ -375,7 +576,10 @@ impl ValidationRule for TokenValidityRule {
                 if let Some(expires_at) = tokens.expires_at {
                     let now = Utc::now();
                     if now > expires_at {
-                        issues.push("OpenAI tokens have expired".to_string());
+                        issues.push(
+                            ValidationIssue::new("auth_data.openai_auth.tokens.expires_at", "OpenAI tokens have expired")
+                                .with_suggestion("Log in to OpenAI again to obtain fresh tokens"),
+                        );
                     } else if now > expires_at - Duration::hours(24) {
                         warnings.push("OpenAI tokens will expire within 24 hours".to_string());
                         recommendations.push("Refresh OpenAI tokens soon".to_string());
@@ -390,7 +594,10 @@ impl ValidationRule for TokenValidityRule {
                 if let Some(expires_at) = tokens.expires_at {
                     let now = Utc::now();
                     if now > expires_at {
-                        issues.push("Claude tokens have expired".to_string());
+                        issues.push(
+                            ValidationIssue::new("auth_data.claude_auth.tokens.expires_at", "Claude tokens have expired")
+                                .with_suggestion("Log in to Claude again to obtain fresh tokens"),
+                        );
                     } else if now > expires_at - Duration::hours(24) {
                         warnings.push("Claude tokens will expire within 24 hours".to_string());
                         recommendations.push("Refresh Claude tokens soon".to_string());
@@ -445,6 +652,49 @@ impl ValidationRule for ConfigurationConsistencyRule {
             warnings.push("Fallback is disabled but fallback strategy is not set to Manual".to_string());
         }
 
+        // Disabling every provider leaves nothing to select
+        if config.auth.enabled_providers.as_ref().is_some_and(|enabled| enabled.is_empty()) {
+            issues.push(
+                ValidationIssue::new("auth.enabled_providers", "No provider is enabled")
+                    .with_suggestion("Enable at least one provider, or remove enabled_providers to enable all"),
+            );
+        } else if !config.auth.is_provider_enabled(&config.auth.preferred_provider) {
+            warnings.push(format!(
+                "Preferred provider ({}) is disabled; another enabled provider will be used",
+                config.auth.preferred_provider
+            ));
+        }
+
+        // Every enabled provider in an ordered fallback chain must be usable; disabled ones are skipped
+        if let FallbackStrategy::ChainOrdered { providers } = &config.auth.fallback_strategy {
+            for (index, provider) in providers.iter().enumerate() {
+                if !config.auth.is_provider_enabled(provider) {
+                    warnings.push(format!("Fallback chain entry {} is disabled and will be skipped", provider));
+                    continue;
+                }
+                let configured = match provider {
+                    ProviderType::OpenAI => config.auth_data.openai_auth.is_some(),
+                    ProviderType::Claude => config.auth_data.claude_auth.is_some(),
+                    // Credentials of custom providers are not stored in auth_data
+                    ProviderType::Custom(_) => true,
+                };
+                if !configured {
+                    issues.push(
+                        ValidationIssue::new(
+                            format!("auth.fallback_strategy.chain_ordered.providers[{}]", index),
+                            format!("Fallback chain references {}, which has no credentials configured", provider),
+                        )
+                        .with_suggestion(format!("Log in to {} or remove it from the chain", provider)),
+                    );
+                }
+            }
+        }
+
+        // A time-of-day schedule must map every moment to at most one provider
+        if let Some(schedule) = config.auth.provider_preference.schedule() {
+            issues.extend(check_provider_schedule(schedule));
+        }
+
         // Check subscription checking consistency
         if config.auth.enable_subscription_check && config.auth_data.claude_auth.is_none() {
             warnings.push("Subscription checking is enabled but Claude authentication is not configured".to_string());
@@ -466,6 +716,52 @@ impl ValidationRule for ConfigurationConsistencyRule {
     }
 }
 
+/// Problems with a provider schedule: overlapping or empty windows and impossible offsets
+fn check_provider_schedule(schedule: &ProviderSchedule) -> Vec<ValidationIssue> {
+    const FIELD: &str = "auth.provider_preference.scheduled.schedule";
+    let mut issues = Vec::new();
+
+    if let Some(minutes) = schedule.utc_offset_minutes {
+        if minutes.abs() > MAX_UTC_OFFSET_MINUTES {
+            issues.push(
+                ValidationIssue::new(
+                    format!("{}.utc_offset_minutes", FIELD),
+                    format!("UTC offset of {} minutes is outside -14:00..+14:00", minutes),
+                )
+                .with_suggestion("Give the offset in minutes, e.g. 120 for UTC+02:00, or omit it to use local time"),
+            );
+        }
+    }
+
+    for (index, window) in schedule.windows.iter().enumerate() {
+        if window.is_empty() {
+            issues.push(
+                ValidationIssue::new(
+                    format!("{}.windows[{}]", FIELD, index),
+                    format!("Window for {} starts and ends at {}, so it never applies", window.provider, window.start),
+                )
+                .with_suggestion("Give the window a different end time or remove it"),
+            );
+        }
+    }
+
+    for (first, second) in schedule.overlapping_windows() {
+        let (a, b) = (&schedule.windows[first], &schedule.windows[second]);
+        issues.push(
+            ValidationIssue::new(
+                format!("{}.windows[{}]", FIELD, second),
+                format!(
+                    "Window {}-{} ({}) overlaps window {}-{} ({})",
+                    b.start, b.end, b.provider, a.start, a.end, a.provider
+                ),
+            )
+            .with_suggestion("Adjust the windows so that each time of day maps to a single provider"),
+        );
+    }
+
+    issues
+}
+
 /// Provider availability validation rule
 #[derive(Debug, Clone)]
 struct ProviderAvailabilityRule;
@@ -495,7 +791,10 @@ impl ValidationRule for ProviderAvailabilityRule {
 
         // Validate provider availability
         if available_providers.is_empty() {
-            issues.push("No authentication providers are available".to_string());
+            issues.push(
+                ValidationIssue::new("auth_data", "No authentication providers are available")
+                    .with_suggestion("Log in with at least one provider"),
+            );
             recommendations.push("Configure at least one authentication provider".to_string());
         } else if available_providers.len() == 1 && context.config.auth.enable_fallback {
             warnings.push("Fallback is enabled but only one provider is available".to_string());
@@ -505,7 +804,13 @@ impl ValidationRule for ProviderAvailabilityRule {
         // Check if preferred provider is available
         if !available_providers.contains(&context.config.auth.preferred_provider) {
             if context.strict_mode {
-                issues.push(format!("Preferred provider ({}) is not available", context.config.auth.preferred_provider));
+                issues.push(
+                    ValidationIssue::new(
+                        "auth.preferred_provider",
+                        format!("Preferred provider ({}) is not available", context.config.auth.preferred_provider),
+                    )
+                    .with_suggestion("Log in to the preferred provider or change preferred_provider"),
+                );
             } else {
                 warnings.push(format!("Preferred provider ({}) is not available", context.config.auth.preferred_provider));
             }
@@ -527,12 +832,50 @@ impl ValidationRule for ProviderAvailabilityRule {
     }
 }
 
+/// A single configuration problem, located by its field path
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ValidationIssue {
+    /// Dotted path to the offending field, e.g. `auth.preferred_provider`
+    pub field_path: String,
+    pub problem: String,
+    pub suggestion: Option<String>,
+}
+
+impl ValidationIssue {
+    pub fn new(field_path: impl Into<String>, problem: impl Into<String>) -> Self {
+        Self {
+            field_path: field_path.into(),
+            problem: problem.into(),
+            suggestion: None,
+        }
+    }
+
+    pub fn with_suggestion(mut self, suggestion: impl Into<String>) -> Self {
+        self.suggestion = Some(suggestion.into());
+        self
+    }
+}
+
+impl std::fmt::Display for ValidationIssue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.field_path, self.problem)
+    }
+}
+
 /// Validation result from a single rule
-#[derive(Debug, Clone)]
-struct RuleResult {
-    issues: Vec<String>,
-    warnings: Vec<String>,
-    recommendations: Vec<String>,
+#[derive(Debug, Clone, Default)]
+pub struct RuleResult {
+    pub issues: Vec<ValidationIssue>,
+    pub warnings: Vec<String>,
+    pub recommendations: Vec<String>,
+}
+
+impl RuleResult {
+    fn merge(&mut self, other: RuleResult) {
+        self.issues.extend(other.issues);
+        self.warnings.extend(other.warnings);
+        self.recommendations.extend(other.recommendations);
+    }
 }
 
 /// Overall validation result
@@ -540,12 +883,35 @@ struct RuleResult {
 pub struct ValidationResult {
     pub is_valid: bool,
     pub severity: ValidationSeverity,
+    /// Every problem found, in rule order
+    pub entries: Vec<ValidationIssue>,
+    /// `entries` rendered as `field_path: problem`
     pub issues: Vec<String>,
     pub warnings: Vec<String>,
     pub recommendations: Vec<String>,
 }
 
 impl ValidationResult {
+    fn from_rule_result(result: RuleResult) -> Self {
+        let is_valid = result.issues.is_empty();
+        let severity = if !is_valid {
+            ValidationSeverity::Error
+        } else if !result.warnings.is_empty() {
+            ValidationSeverity::Warning
+        } else {
+            ValidationSeverity::Valid
+        };
+
+        Self {
+            is_valid,
+            severity,
+            issues: result.issues.iter().map(ToString::to_string).collect(),
+            entries: result.issues,
+            warnings: result.warnings,
+            recommendations: result.recommendations,
+        }
+    }
+
     /// Check if there are any issues or warnings
     pub fn has_problems(&self) -> bool {
         !self.issues.is_empty() || !self.warnings.is_empty()
@@ -580,6 +946,140 @@ pub enum ValidationError {
     
     #[error("Internal validation error: {0}")]
     InternalError(String),
+
+    #[error("Invalid configuration: {}", .0.summary())]
+    Invalid(ValidationResult),
+}
+
+/// Schema checks against the raw TOML document
+mod schema {
+    use super::ValidationIssue;
+
+    const PROVIDERS: &[&str] = &["openai", "claude"];
+    const PROVIDER_PREFERENCES: &[&str] = &[
+        "prefer_claude",
+        "prefer_openai",
+        "cost_optimized",
+        "performance_optimized",
+        "quota_optimized",
+        "user_preference",
+    ];
+    const FALLBACK_STRATEGIES: &[&str] = &[
+        "automatic",
+        "on_quota_exhausted",
+        "on_auth_error",
+        "manual",
+        "conditional",
+        "chain_ordered",
+    ];
+    const DURATION_FIELDS: &[&str] = &[
+        "subscription_check_interval",
+        "auth_timeout",
+        "provider_cache_duration",
+    ];
+
+    pub(super) fn check_auth_table(auth: &toml::Value) -> Vec<ValidationIssue> {
+        let mut issues = Vec::new();
+
+        if let Some(provider) = auth.get("preferred_provider") {
+            check_provider(&mut issues, "auth.preferred_provider", provider);
+        }
+
+        if let Some(preference) = auth.get("provider_preference") {
+            check_variant(&mut issues, "auth.provider_preference", preference, PROVIDER_PREFERENCES);
+        }
+
+        if let Some(strategy) = auth.get("fallback_strategy") {
+            check_variant(&mut issues, "auth.fallback_strategy", strategy, FALLBACK_STRATEGIES);
+
+            let chain = strategy
+                .get("chain_ordered")
+                .and_then(|chain| chain.get("providers"))
+                .and_then(|providers| providers.as_array());
+            for (index, provider) in chain.into_iter().flatten().enumerate() {
+                let path = format!("auth.fallback_strategy.chain_ordered.providers[{}]", index);
+                check_provider(&mut issues, &path, provider);
+            }
+        }
+
+        let enabled = auth.get("enabled_providers").and_then(|providers| providers.as_array());
+        for (index, provider) in enabled.into_iter().flatten().enumerate() {
+            check_provider(&mut issues, &format!("auth.enabled_providers[{}]", index), provider);
+        }
+
+        if let Some(profile) = auth.get("active_profile") {
+            match profile.as_str() {
+                Some(name) => {
+                    if let Err(e) = crate::configuration::profiles::validate_profile_name(name) {
+                        issues.push(
+                            ValidationIssue::new("auth.active_profile", e.to_string())
+                                .with_suggestion("Pick a profile from `code auth profile list`"),
+                        );
+                    }
+                }
+                None => issues.push(
+                    ValidationIssue::new("auth.active_profile", "Profile must be a string")
+                        .with_suggestion("Pick a profile from `code auth profile list`"),
+                ),
+            }
+        }
+
+        for field in DURATION_FIELDS {
+            if let Some(seconds) = auth.get(*field).and_then(|value| value.as_integer()) {
+                if seconds < 0 {
+                    issues.push(
+                        ValidationIssue::new(format!("auth.{}", field), format!("Duration is negative ({}s)", seconds))
+                            .with_suggestion("Durations must be zero or positive"),
+                    );
+                }
+            }
+        }
+
+        issues
+    }
+
+    fn check_provider(issues: &mut Vec<ValidationIssue>, path: &str, value: &toml::Value) {
+        match value.as_str() {
+            Some(name) if PROVIDERS.contains(&name) => {}
+            Some(name) => issues.push(
+                ValidationIssue::new(path, format!("Unknown provider '{}'", name))
+                    .with_suggestion(suggest(name, PROVIDERS)),
+            ),
+            None => issues.push(
+                ValidationIssue::new(path, "Provider must be a string")
+                    .with_suggestion(format!("Use one of: {}", PROVIDERS.join(", "))),
+            ),
+        }
+    }
+
+    /// Serde's externally tagged enums appear either as a bare string or a single-key table
+    fn check_variant(issues: &mut Vec<ValidationIssue>, path: &str, value: &toml::Value, known: &[&str]) {
+        let variant = match value {
+            toml::Value::String(name) => Some(name.as_str()),
+            toml::Value::Table(table) if table.len() == 1 => table.keys().next().map(String::as_str),
+            _ => None,
+        };
+
+        match variant {
+            Some(name) if known.contains(&name) => {}
+            Some(name) => issues.push(
+                ValidationIssue::new(path, format!("Unknown value '{}'", name))
+                    .with_suggestion(suggest(name, known)),
+            ),
+            None => issues.push(
+                ValidationIssue::new(path, "Expected a string or a single-key table")
+                    .with_suggestion(format!("Use one of: {}", known.join(", "))),
+            ),
+        }
+    }
+
+    fn suggest(name: &str, known: &[&str]) -> String {
+        let lowered = name.to_lowercase();
+        match known.iter().find(|candidate| **candidate == lowered || candidate.starts_with(&lowered)) {
+            Some(candidate) => format!("Did you mean '{}'?", candidate),
+            None => format!("Use one of: {}", known.join(", ")),
+        }
+    }
 }
 
 #[cfg(test)]
@@ -591,6 +1091,7 @@ mod tests {
         UnifiedConfig {
             auth: AuthConfig::default(),
             auth_data: UnifiedAuthJson::default(),
+            ..Default::default()
         }
     }
 
@@ -621,6 +1122,22 @@ mod tests {
         assert!(!rule.is_valid_claude_api_key("sk-1234567890123456789012345678901234567890123456789"));
     }
 
+    #[test]
+    fn test_api_key_references_are_validated_behind_the_reference() {
+        let rule = AuthenticationRule;
+        let key_var = "CODE_TEST_VALIDATED_CLAUDE_KEY";
+        std::env::set_var(key_var, "sk-ant-REDACTED");
+
+        let auth = ClaudeAuthData { api_key: Some(format!("env:{}", key_var)), tokens: None, subscription: None };
+        assert!(rule.validate_claude_auth(&auth).is_ok());
+
+        let auth = OpenAIAuthData { api_key: Some("env:CODE_TEST_UNSET_OPENAI_KEY".to_string()), tokens: None };
+        let issues = rule.validate_openai_auth(&auth).unwrap_err();
+        assert_eq!(issues.len(), 1);
+        assert!(issues[0].problem.contains("CODE_TEST_UNSET_OPENAI_KEY"));
+        std::env::remove_var(key_var);
+    }
+
     #[test]
     fn test_validation_severity() {
         assert!(ValidationSeverity::Error > ValidationSeverity::Warning);
@@ -632,6 +1149,7 @@ mod tests {
         let result = ValidationResult {
             is_valid: false,
             severity: ValidationSeverity::Error,
+            entries: vec![ValidationIssue::new("auth", "Test error")],
             issues: vec!["Test error".to_string()],
             warnings: Vec::new(),
             recommendations: Vec::new(),
@@ -646,4 +1164,121 @@ mod tests {
         let validator = ConfigValidator::new_strict();
         assert!(validator.strict_mode);
     }
-}
\ No newline at end of file
+
+    fn config_with_openai_key() -> UnifiedConfig {
+        let mut config = create_test_config();
+        config.auth_data.openai_auth = Some(OpenAIAuthData {
+            api_key: Some("sk-test123456789012345678901234567890123456789012345".to_string()),
+            tokens: None,
+        });
+        config
+    }
+
+    #[test]
+    fn test_all_problems_are_reported() {
+        let mut config = config_with_openai_key();
+        config.auth.auth_timeout = Duration::seconds(0);
+        config.auth.subscription_check_interval = Duration::hours(-1);
+        config.auth.fallback_strategy = FallbackStrategy::ChainOrdered {
+            providers: vec![ProviderType::OpenAI, ProviderType::Claude],
+        };
+
+        let result = ConfigValidator::new().validate(&config).unwrap();
+        assert!(!result.is_valid);
+
+        let paths: Vec<&str> = result.entries.iter().map(|entry| entry.field_path.as_str()).collect();
+        assert_eq!(
+            paths,
+            vec![
+                "auth.auth_timeout",
+                "auth.subscription_check_interval",
+                "auth.fallback_strategy.chain_ordered.providers[1]",
+            ]
+        );
+        assert!(result.entries.iter().all(|entry| entry.suggestion.is_some()));
+        assert!(result.entries[2].problem.contains("claude"));
+    }
+
+    #[test]
+    fn test_disabled_providers_are_skipped_in_fallback_chain() {
+        let mut config = config_with_openai_key();
+        config.auth.enabled_providers = Some(vec![ProviderType::OpenAI]);
+        config.auth.fallback_strategy = FallbackStrategy::ChainOrdered {
+            providers: vec![ProviderType::Claude, ProviderType::OpenAI],
+        };
+
+        // Claude has no credentials, but is disabled rather than missing
+        let result = ConfigValidator::new().validate(&config).unwrap();
+        assert!(result.entries.iter().all(|entry| !entry.field_path.starts_with("auth.fallback_strategy")));
+        assert_eq!(config.auth.fallback_chain(), vec![ProviderType::OpenAI]);
+
+        config.auth.enabled_providers = Some(Vec::new());
+        let result = ConfigValidator::new().validate(&config).unwrap();
+        assert!(!result.is_valid);
+        assert!(result.entries.iter().any(|entry| entry.field_path == "auth.enabled_providers"));
+    }
+
+    #[test]
+    fn test_overlapping_schedule_windows_are_reported() {
+        use crate::configuration::{ProviderSchedule, ProviderWindow};
+        let at = |time: &str| chrono::NaiveTime::parse_from_str(time, "%H:%M").unwrap();
+
+        let mut config = config_with_openai_key();
+        config.auth.provider_preference = ProviderPreference::Scheduled {
+            schedule: ProviderSchedule::new(vec![
+                ProviderWindow::new(at("09:00"), at("18:00"), ProviderType::Claude),
+                ProviderWindow::new(at("18:00"), at("09:00"), ProviderType::OpenAI),
+            ]),
+            otherwise: Box::new(ProviderPreference::PreferOpenAI),
+        };
+        let result = ConfigValidator::new().validate(&config).unwrap();
+        assert!(result.entries.iter().all(|entry| !entry.field_path.contains("schedule")));
+
+        config.auth.provider_preference = ProviderPreference::Scheduled {
+            schedule: ProviderSchedule::new(vec![
+                ProviderWindow::new(at("09:00"), at("18:00"), ProviderType::Claude),
+                ProviderWindow::new(at("17:00"), at("10:00"), ProviderType::OpenAI),
+            ]),
+            otherwise: Box::new(ProviderPreference::PreferOpenAI),
+        };
+        let result = ConfigValidator::new().validate(&config).unwrap();
+        let overlap: Vec<_> = result.entries.iter().filter(|entry| entry.field_path.contains("schedule")).collect();
+        assert_eq!(overlap.len(), 1);
+        assert_eq!(overlap[0].field_path, "auth.provider_preference.scheduled.schedule.windows[1]");
+        assert!(overlap[0].problem.contains("overlaps"));
+    }
+
+    #[test]
+    fn test_source_schema_problems() {
+        let source = r#"
+[auth]
+preferred_provider = "gemini"
+subscription_check_interval = -3600
+fallback_strategy = { chain_ordered = { providers = ["claude", "Open"] } }
+"#;
+
+        let result = ConfigValidator::new().validate_source(source).unwrap();
+        assert_eq!(result.entries.len(), 3);
+        assert_eq!(result.entries[0].field_path, "auth.preferred_provider");
+        assert!(result.entries[0].problem.contains("gemini"));
+        assert_eq!(result.entries[1].field_path, "auth.fallback_strategy.chain_ordered.providers[1]");
+        assert_eq!(result.entries[1].suggestion.as_deref(), Some("Did you mean 'openai'?"));
+        assert_eq!(result.entries[2].field_path, "auth.subscription_check_interval");
+    }
+
+    #[test]
+    fn test_rule_combinators() {
+        let config = config_with_openai_key();
+        let ceiling = rule_fn("AlwaysFails", |_| vec![ValidationIssue::new("auth.custom", "custom failure")]);
+
+        let validator = ConfigValidator::new().with_rule(ceiling.when(|ctx| ctx.strict_mode));
+        assert!(validator.validate(&config).unwrap().is_valid);
+
+        let strict = ConfigValidator::new_strict()
+            .with_rule(rule_fn("AlwaysFails", |_| vec![ValidationIssue::new("auth.custom", "custom failure")])
+                .when(|ctx| ctx.strict_mode)
+                .with_priority(1));
+        let result = strict.validate(&config).unwrap();
+        assert_eq!(result.entries[0].field_path, "auth.custom");
+    }
+}
@@ -0,0 +1,197 @@
+//! Hot reload of `config.toml`
+//!
+//! Watches the base configuration file and hands validated changes to a callback so
+//! long-running hosts pick up provider and fallback changes without restarting.
+
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+use notify::{Event, RecommendedWatcher, RecursiveMode, Watcher};
+
+use super::{content_digest, ConfigError, UnifiedConfig, UnifiedConfigManager};
+
+/// Quiet period after the last file event before a reload is attempted
+pub const DEFAULT_WATCH_DEBOUNCE: Duration = Duration::from_millis(250);
+
+/// Handle for a running config watcher
+///
+/// Dropping the handle also stops watching.
+pub struct ConfigWatcher {
+    watcher: Option<RecommendedWatcher>,
+    worker: Option<JoinHandle<()>>,
+    stopped: Arc<AtomicBool>,
+}
+
+impl ConfigWatcher {
+    /// Stop watching and wait for any in-flight reload to finish
+    pub fn stop_watching(mut self) {
+        self.shutdown();
+    }
+
+    fn shutdown(&mut self) {
+        self.stopped.store(true, Ordering::SeqCst);
+        // Dropping the watcher closes the event channel, which ends the worker loop
+        self.watcher.take();
+        if let Some(worker) = self.worker.take() {
+            let _ = worker.join();
+        }
+    }
+}
+
+impl Drop for ConfigWatcher {
+    fn drop(&mut self) {
+        self.shutdown();
+    }
+}
+
+impl UnifiedConfigManager {
+    /// Watch `config.toml` and call `on_change` with each valid edit
+    pub fn watch<F>(self: &Arc<Self>, on_change: F) -> Result<ConfigWatcher, ConfigError>
+    where
+        F: Fn(UnifiedConfig) + Send + 'static,
+    {
+        self.watch_with_debounce(DEFAULT_WATCH_DEBOUNCE, on_change)
+    }
+
+    /// Watch `config.toml`, waiting for `debounce` of quiet before reloading
+    ///
+    /// Edits that fail validation are logged and skipped; writes made by this manager's
+    /// own saves are recognised by content and never reported back.
+    pub fn watch_with_debounce<F>(
+        self: &Arc<Self>,
+        debounce: Duration,
+        on_change: F,
+    ) -> Result<ConfigWatcher, ConfigError>
+    where
+        F: Fn(UnifiedConfig) + Send + 'static,
+    {
+        let config_path = self.base_config_path.clone();
+        // Watch the directory: editors often replace the file rather than writing in place
+        let watch_dir = config_path
+            .parent()
+            .map(PathBuf::from)
+            .unwrap_or_else(|| PathBuf::from("."));
+        std::fs::create_dir_all(&watch_dir)?;
+
+        let (tx, rx) = mpsc::channel::<()>();
+        let target = config_path.clone();
+        let mut watcher = notify::recommended_watcher(move |event: notify::Result<Event>| {
+            if let Ok(event) = event {
+                if event.paths.iter().any(|path| path.file_name() == target.file_name()) {
+                    let _ = tx.send(());
+                }
+            }
+        })?;
+        watcher.watch(&watch_dir, RecursiveMode::NonRecursive)?;
+
+        let stopped = Arc::new(AtomicBool::new(false));
+        let manager = Arc::clone(self);
+        let worker_stopped = Arc::clone(&stopped);
+        let worker = std::thread::spawn(move || {
+            let mut last_seen = std::fs::read_to_string(&config_path).ok().map(|c| content_digest(&c));
+
+            while rx.recv().is_ok() {
+                // Debounce: keep absorbing events until the file has been quiet
+                loop {
+                    match rx.recv_timeout(debounce) {
+                        Ok(()) => continue,
+                        Err(RecvTimeoutError::Timeout) => break,
+                        Err(RecvTimeoutError::Disconnected) => return,
+                    }
+                }
+                if worker_stopped.load(Ordering::SeqCst) {
+                    return;
+                }
+
+                let Ok(content) = std::fs::read_to_string(&config_path) else {
+                    continue;
+                };
+                let digest = content_digest(&content);
+                if last_seen == Some(digest) || manager.is_own_save(digest) {
+                    last_seen = Some(digest);
+                    continue;
+                }
+                last_seen = Some(digest);
+
+                match manager.reload_base_config() {
+                    Ok(config) => on_change(config),
+                    Err(e) => tracing::warn!("Ignoring config.toml change: {}", e),
+                }
+            }
+        });
+
+        Ok(ConfigWatcher {
+            watcher: Some(watcher),
+            worker: Some(worker),
+            stopped,
+        })
+    }
+
+    /// Load, override and validate the base config for a hot reload
+    ///
+    /// Only problems in config.toml fields reject the edit; credential problems
+    /// are not caused by the edit and are left to the normal load path.
+    fn reload_base_config(&self) -> Result<UnifiedConfig, ConfigError> {
+        let mut config = self.load_base_config()?;
+        self.env_config.apply_overrides(&mut config)?;
+
+        let mut result = self.validator.validate(&config)?;
+        result.entries.retain(|entry| entry.field_path.starts_with("auth."));
+        if !result.entries.is_empty() {
+            result.is_valid = false;
+            result.issues = result.entries.iter().map(ToString::to_string).collect();
+            return Err(super::ValidationError::Invalid(result).into());
+        }
+
+        Ok(config)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::configuration::{BaseConfig, ProviderType};
+    use tempfile::tempdir;
+
+    fn write_external(manager: &UnifiedConfigManager, provider: ProviderType) -> String {
+        let mut config = UnifiedConfig::default();
+        config.auth.preferred_provider = provider;
+        let content = toml::to_string_pretty(&BaseConfig { auth: Some(config.auth) }).unwrap();
+        std::fs::write(&manager.base_config_path, &content).unwrap();
+        content
+    }
+
+    #[test]
+    fn test_watch_applies_valid_edits_and_rejects_invalid_ones() {
+        let dir = tempdir().unwrap();
+        let manager = Arc::new(UnifiedConfigManager::new(dir.path().to_path_buf()).unwrap());
+        manager.save_base_config(&UnifiedConfig::default()).unwrap();
+
+        let (tx, rx) = mpsc::channel();
+        let watcher = manager
+            .watch_with_debounce(Duration::from_millis(50), move |config| {
+                let _ = tx.send(config.auth.preferred_provider);
+            })
+            .unwrap();
+
+        // Our own save must not be reported back
+        let mut own = UnifiedConfig::default();
+        own.auth.enable_fallback = false;
+        manager.save_base_config(&own).unwrap();
+        assert!(rx.recv_timeout(Duration::from_millis(500)).is_err());
+
+        // A valid external edit reaches the callback
+        let content = write_external(&manager, ProviderType::Claude);
+        assert_eq!(rx.recv_timeout(Duration::from_secs(5)).unwrap(), ProviderType::Claude);
+
+        // An invalid edit is rejected without firing
+        std::fs::write(&manager.base_config_path, content.replace("\"claude\"", "\"gemini\"")).unwrap();
+        assert!(rx.recv_timeout(Duration::from_millis(500)).is_err());
+
+        watcher.stop_watching();
+    }
+}
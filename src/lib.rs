@@ -7,6 +7,13 @@
 pub mod security;
 pub mod claude_auth;
 pub mod configuration;
+pub mod auth;
+pub mod claude_quota;
+pub mod agent_auth;
+pub mod cli;
+
+#[cfg(any(test, feature = "test-util"))]
+pub mod test_support;
 
 pub use security::{
     SecureTokenStorage,
@@ -22,6 +29,7 @@ pub use security::{
 pub use claude_auth::{
     SecureClaudeAuth,
     ClaudeAuthConfig,
+    ApiKeySubscriptionPolicy,
     ClaudeAuthError,
     ClaudeTokenData,
     ClaudeSubscriptionInfo,
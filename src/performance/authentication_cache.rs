@@ -3,6 +3,7 @@
 
 use std::collections::HashMap;
 use std::time::{Duration, Instant, SystemTime};
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
 use tokio::sync::RwLock;
 use serde::{Serialize, Deserialize};
@@ -19,6 +20,9 @@ pub struct CachedAuth {
     pub cached_at: DateTime<Utc>,
     pub last_accessed: DateTime<Utc>,
     pub access_count: u32,
+    /// Position in the cache's access order; orders recency exactly where timestamps tie
+    #[serde(skip)]
+    access_tick: u64,
 }
 
 /// Cache performance statistics
@@ -29,12 +33,28 @@ pub struct CacheStats {
     pub total_requests: u64,
     pub cache_hits: u64,
     pub cache_misses: u64,
+    /// Entries dropped to stay within capacity
     pub evictions: u64,
+    /// Entries dropped because they outlived their expiry
+    #[serde(default)]
+    pub expirations: u64,
     pub average_lookup_time_ms: f64,
     pub cache_size: usize,
     pub max_cache_size: usize,
 }
 
+/// Which entry makes room when the cache is full
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum EvictionPolicy {
+    /// Never evict for capacity; only expiry removes entries, so `max_size` is advisory
+    TtlOnly,
+    /// Evict the least recently used entry
+    #[default]
+    Lru,
+    /// Evict the least frequently used entry, the least recent among equals
+    Lfu,
+}
+
 /// Cache configuration
 #[derive(Debug, Clone)]
 pub struct CacheConfig {
@@ -42,6 +62,7 @@ pub struct CacheConfig {
     pub ttl_minutes: u64,
     pub cleanup_interval_minutes: u64,
     pub preemptive_refresh_threshold_minutes: u64,
+    pub eviction_policy: EvictionPolicy,
 }
 
 impl Default for CacheConfig {
@@ -51,6 +72,7 @@ impl Default for CacheConfig {
             ttl_minutes: 60,                   // 1 hour TTL
             cleanup_interval_minutes: 10,      // Cleanup every 10 minutes
             preemptive_refresh_threshold_minutes: 5, // Refresh 5 minutes before expiry
+            eviction_policy: EvictionPolicy::Lru,
         }
     }
 }
@@ -62,6 +84,7 @@ pub struct AuthenticationCache {
     config: CacheConfig,
     stats: Arc<RwLock<CacheStats>>,
     last_cleanup: Arc<RwLock<Instant>>,
+    next_access_tick: AtomicU64,
 }
 
 impl AuthenticationCache {
@@ -70,8 +93,14 @@ impl AuthenticationCache {
         Self::with_config(CacheConfig::default())
     }
 
+    /// Create an LRU cache holding at most `max_size` entries
+    pub fn with_capacity(max_size: usize) -> Self {
+        Self::with_config(CacheConfig { max_size, ..CacheConfig::default() })
+    }
+
     /// Create cache with custom configuration
     pub fn with_config(config: CacheConfig) -> Self {
+        let max_cache_size = config.max_size;
         Self {
             cache: Arc::new(RwLock::new(HashMap::new())),
             config,
@@ -82,14 +111,25 @@ impl AuthenticationCache {
                 cache_hits: 0,
                 cache_misses: 0,
                 evictions: 0,
+                expirations: 0,
                 average_lookup_time_ms: 0.0,
                 cache_size: 0,
-                max_cache_size: 1000,
+                max_cache_size,
             })),
             last_cleanup: Arc::new(RwLock::new(Instant::now())),
+            next_access_tick: AtomicU64::new(0),
         }
     }
 
+    /// Policy used when the cache is full
+    pub fn eviction_policy(&self) -> EvictionPolicy {
+        self.config.eviction_policy
+    }
+
+    fn next_tick(&self) -> u64 {
+        self.next_access_tick.fetch_add(1, Ordering::Relaxed)
+    }
+
     /// Generate cache key for authentication request
     fn generate_cache_key(provider: &str, user_identifier: &str) -> String {
         format!("{}:{}", provider, user_identifier)
@@ -122,17 +162,23 @@ impl AuthenticationCache {
                 if cached_auth.expires_at <= now {
                     // Remove expired entry
                     self.remove(provider, user_identifier).await;
+                    self.stats.write().await.expirations += 1;
                     return None;
                 }
 
                 // Update last accessed time
                 cached_auth.last_accessed = now;
                 cached_auth.access_count += 1;
+                cached_auth.access_tick = self.next_tick();
                 
-                // Update in cache
+                // Update in cache, unless it was evicted or replaced since the read
                 {
                     let mut cache_guard = self.cache.write().await;
-                    cache_guard.insert(cache_key, cached_auth.clone());
+                    if let Some(entry) = cache_guard.get_mut(&cache_key) {
+                        if entry.cached_at == cached_auth.cached_at {
+                            *entry = cached_auth.clone();
+                        }
+                    }
                 }
 
                 Some(cached_auth)
@@ -153,39 +199,46 @@ impl AuthenticationCache {
         let cache_key = Self::generate_cache_key(provider, user_identifier);
         let now = Utc::now();
 
+        // Never keep an entry past the configured TTL, even if the token lives longer
+        let ttl_expiry = now + chrono::Duration::minutes(self.config.ttl_minutes as i64);
+
         let cached_auth = CachedAuth {
             provider: provider.to_string(),
             user_id: user_identifier.to_string(),
             token: token.to_string(),
-            expires_at,
+            expires_at: expires_at.min(ttl_expiry),
             subscription_tier,
             cached_at: now,
             last_accessed: now,
             access_count: 0,
+            access_tick: self.next_tick(),
         };
 
-        // Check if we need to evict entries to make space
-        let should_evict = {
-            let cache_guard = self.cache.read().await;
-            cache_guard.len() >= self.config.max_size
-        };
-
-        if should_evict {
-            self.evict_lru().await;
+        let mut cache_guard = self.cache.write().await;
+        let (mut expired, mut evicted) = (0, 0);
+
+        // Make space for a new key: expired entries go first, then the policy's victims
+        if !cache_guard.contains_key(&cache_key) && cache_guard.len() >= self.config.max_size {
+            expired = Self::remove_expired(&mut cache_guard, now);
+            if self.config.eviction_policy != EvictionPolicy::TtlOnly {
+                while cache_guard.len() >= self.config.max_size.max(1) {
+                    match self.eviction_candidate(&cache_guard) {
+                        Some(key) => {
+                            cache_guard.remove(&key);
+                            evicted += 1;
+                        }
+                        None => break,
+                    }
+                }
+            }
         }
 
-        // Insert new entry
-        {
-            let mut cache_guard = self.cache.write().await;
-            cache_guard.insert(cache_key, cached_auth);
-        }
+        cache_guard.insert(cache_key, cached_auth);
 
-        // Update cache size in stats
-        {
-            let mut stats_guard = self.stats.write().await;
-            let cache_guard = self.cache.read().await;
-            stats_guard.cache_size = cache_guard.len();
-        }
+        let mut stats_guard = self.stats.write().await;
+        stats_guard.expirations += expired;
+        stats_guard.evictions += evicted;
+        stats_guard.cache_size = cache_guard.len();
     }
 
     /// Remove cached authentication
@@ -199,6 +252,15 @@ impl AuthenticationCache {
         stats_guard.cache_size = cache_guard.len();
     }
 
+    /// Remove every cached authentication for a provider
+    pub async fn remove_provider(&self, provider: &str) {
+        let mut cache_guard = self.cache.write().await;
+        cache_guard.retain(|_, auth| auth.provider != provider);
+
+        let mut stats_guard = self.stats.write().await;
+        stats_guard.cache_size = cache_guard.len();
+    }
+
     /// Clear all cached authentications
     pub async fn clear(&self) {
         let mut cache_guard = self.cache.write().await;
@@ -261,20 +323,21 @@ impl AuthenticationCache {
             (current_avg * (stats_guard.total_requests - 1) as f64 + new_time_ms) / stats_guard.total_requests as f64;
     }
 
-    /// Evict least recently used entry
-    async fn evict_lru(&self) {
-        let mut cache_guard = self.cache.write().await;
-        
-        if let Some((key_to_remove, _)) = cache_guard
-            .iter()
-            .min_by_key(|(_, auth)| auth.last_accessed)
-            .map(|(k, v)| (k.clone(), v.clone()))
-        {
-            cache_guard.remove(&key_to_remove);
-            
-            let mut stats_guard = self.stats.write().await;
-            stats_guard.evictions += 1;
-        }
+    /// Key of the entry the eviction policy gives up first, if any
+    fn eviction_candidate(&self, cache: &HashMap<String, CachedAuth>) -> Option<String> {
+        let victim = match self.config.eviction_policy {
+            EvictionPolicy::TtlOnly => None,
+            EvictionPolicy::Lru => cache.iter().min_by_key(|(_, auth)| auth.access_tick),
+            EvictionPolicy::Lfu => cache.iter().min_by_key(|(_, auth)| (auth.access_count, auth.access_tick)),
+        };
+        victim.map(|(key, _)| key.clone())
+    }
+
+    /// Drop entries expired at `now`, returning how many were removed
+    fn remove_expired(cache: &mut HashMap<String, CachedAuth>, now: DateTime<Utc>) -> u64 {
+        let before = cache.len();
+        cache.retain(|_, auth| auth.expires_at > now);
+        (before - cache.len()) as u64
     }
 
     /// Cleanup expired entries if needed
@@ -294,20 +357,11 @@ impl AuthenticationCache {
 
     /// Remove all expired entries
     async fn cleanup_expired(&self) {
-        let now = Utc::now();
         let mut cache_guard = self.cache.write().await;
-        
-        let expired_keys: Vec<String> = cache_guard
-            .iter()
-            .filter(|(_, auth)| auth.expires_at <= now)
-            .map(|(key, _)| key.clone())
-            .collect();
-
-        for key in expired_keys {
-            cache_guard.remove(&key);
-        }
+        let expired = Self::remove_expired(&mut cache_guard, Utc::now());
 
         let mut stats_guard = self.stats.write().await;
+        stats_guard.expirations += expired;
         stats_guard.cache_size = cache_guard.len();
     }
 
@@ -449,6 +503,104 @@ mod tests {
         assert!(avg_time_per_lookup < 10, "Average lookup time {} ms exceeds performance expectations", avg_time_per_lookup);
     }
 
+    #[tokio::test]
+    async fn test_cache_ttl_caps_expiry() {
+        let mut config = CacheConfig::default();
+        config.ttl_minutes = 5;
+
+        let cache = AuthenticationCache::with_config(config);
+        let expires_at = Utc::now() + chrono::Duration::hours(1);
+
+        cache.put("claude", "test_user", "test_token", expires_at, None).await;
+
+        let cached_auth = cache.get("claude", "test_user").await.unwrap();
+        assert!(cached_auth.expires_at <= Utc::now() + chrono::Duration::minutes(5));
+    }
+
+    #[tokio::test]
+    async fn test_remove_provider() {
+        let cache = AuthenticationCache::new();
+        let expires_at = Utc::now() + chrono::Duration::hours(1);
+
+        cache.put("claude", "user_a", "token_a", expires_at, None).await;
+        cache.put("claude", "user_b", "token_b", expires_at, None).await;
+        cache.put("openai", "user_a", "token_c", expires_at, None).await;
+
+        cache.remove_provider("claude").await;
+
+        assert!(cache.get("claude", "user_a").await.is_none());
+        assert!(cache.get("claude", "user_b").await.is_none());
+        assert!(cache.get("openai", "user_a").await.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_lru_evicts_least_recently_used() {
+        let cache = AuthenticationCache::with_capacity(3);
+        let expires_at = Utc::now() + chrono::Duration::hours(1);
+
+        for user in ["a", "b", "c"] {
+            cache.put("claude", user, "token", expires_at, None).await;
+        }
+        assert!(cache.get("claude", "a").await.is_some());
+        cache.put("claude", "d", "token", expires_at, None).await;
+
+        assert!(cache.get("claude", "b").await.is_none());
+        for user in ["a", "c", "d"] {
+            assert!(cache.get("claude", user).await.is_some(), "{} should survive", user);
+        }
+        let stats = cache.get_stats().await;
+        assert_eq!(stats.evictions, 1);
+        assert_eq!(stats.cache_size, 3);
+        assert_eq!(stats.max_cache_size, 3);
+    }
+
+    #[tokio::test]
+    async fn test_lfu_keeps_frequently_used_entries() {
+        let cache = AuthenticationCache::with_config(CacheConfig {
+            max_size: 3,
+            eviction_policy: EvictionPolicy::Lfu,
+            ..CacheConfig::default()
+        });
+        let expires_at = Utc::now() + chrono::Duration::hours(1);
+
+        for user in ["a", "b", "c"] {
+            cache.put("claude", user, "token", expires_at, None).await;
+        }
+        // "a" is the most used but, by the end, the least recently used
+        for _ in 0..3 {
+            cache.get("claude", "a").await.unwrap();
+        }
+        cache.get("claude", "b").await.unwrap();
+        cache.get("claude", "c").await.unwrap();
+
+        cache.put("claude", "d", "token", expires_at, None).await;
+
+        assert!(cache.get("claude", "a").await.is_some());
+        assert!(cache.get("claude", "b").await.is_none());
+        assert_eq!(cache.get_stats().await.evictions, 1);
+    }
+
+    #[tokio::test]
+    async fn test_ttl_only_grows_past_capacity_but_drops_expired() {
+        let cache = AuthenticationCache::with_config(CacheConfig {
+            max_size: 2,
+            eviction_policy: EvictionPolicy::TtlOnly,
+            ..CacheConfig::default()
+        });
+        let live = Utc::now() + chrono::Duration::hours(1);
+        let expired = Utc::now() - chrono::Duration::minutes(1);
+
+        cache.put("claude", "stale", "token", expired, None).await;
+        cache.put("claude", "a", "token", live, None).await;
+        cache.put("claude", "b", "token", live, None).await;
+        cache.put("claude", "c", "token", live, None).await;
+
+        let stats = cache.get_stats().await;
+        assert_eq!(stats.evictions, 0);
+        assert_eq!(stats.expirations, 1);
+        assert_eq!(stats.cache_size, 3);
+    }
+
     #[tokio::test]
     async fn test_preemptive_refresh() {
         let mut config = CacheConfig::default();
@@ -11,7 +11,7 @@ use chrono::{DateTime, Utc};
 use super::PerformanceMetrics;
 
 /// Types of performance bottlenecks
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum BottleneckType {
     /// Authentication operations taking too long
     SlowAuthentication,
@@ -69,6 +69,7 @@ pub struct AnalysisConfig {
     pub high_memory_threshold_mb: u64,
     pub low_cache_hit_threshold: f64,
     pub high_concurrency_threshold: usize,
+    pub high_network_requests_threshold: u32,
 }
 
 impl Default for AnalysisConfig {
@@ -81,6 +82,7 @@ impl Default for AnalysisConfig {
             high_memory_threshold_mb: 100,  // > 100MB is high memory
             low_cache_hit_threshold: 0.7,   // < 70% cache hit is low
             high_concurrency_threshold: 8,  // > 8 concurrent is high
+            high_network_requests_threshold: 5, // > 5 requests per operation is chatty
         }
     }
 }
@@ -91,6 +93,16 @@ struct PerformanceDataPoint {
     timestamp: DateTime<Utc>,
     metrics: PerformanceMetrics,
     context: OperationContext,
+    signals: Vec<BottleneckType>,
+}
+
+/// Concrete, data-driven recommendation with its estimated impact
+#[derive(Debug, Clone, Serialize)]
+pub struct Recommendation {
+    pub bottleneck_type: BottleneckType,
+    pub message: String,
+    pub impact_score: f64,        // 0-100 scale
+    pub affected_samples: usize,
 }
 
 /// Context about the operation being performed
@@ -190,6 +202,7 @@ impl BottleneckAnalyzer {
                 user_id: None,
                 session_id: None,
             },
+            signals: self.classify_sample(metrics),
         };
 
         // Add to history
@@ -287,28 +300,152 @@ impl BottleneckAnalyzer {
         }
     }
 
-    /// Get current recommendations
+    /// Get current recommendations, highest estimated impact first
     pub async fn get_recommendations(&self) -> Vec<String> {
-        let bottlenecks_guard = self.detected_bottlenecks.read().await;
+        let mut recommendations: Vec<String> = self
+            .get_ranked_recommendations()
+            .await
+            .into_iter()
+            .map(|recommendation| recommendation.message)
+            .collect();
+
+        if recommendations.is_empty() {
+            recommendations.push("Performance is optimal - continue monitoring".to_string());
+        }
+
+        recommendations
+    }
+
+    /// Build one recommendation per signalled bottleneck category from the analysis window
+    pub async fn get_ranked_recommendations(&self) -> Vec<Recommendation> {
+        let history_guard = self.performance_history.read().await;
+        if history_guard.is_empty() {
+            return Vec::new();
+        }
+
+        let sample_count = history_guard.len();
+        let mut signal_counts: HashMap<BottleneckType, usize> = HashMap::new();
+        for data_point in history_guard.iter() {
+            for signal in &data_point.signals {
+                *signal_counts.entry(signal.clone()).or_insert(0) += 1;
+            }
+        }
+
+        let affected_fraction = |count: usize| count as f64 / sample_count as f64;
         let mut recommendations = Vec::new();
 
-        // Collect recommendations from all detected bottlenecks
-        for bottleneck in bottlenecks_guard.values() {
-            recommendations.extend(bottleneck.recommendations.clone());
+        if let Some(&count) = signal_counts.get(&BottleneckType::SlowAuthentication) {
+            let p95 = percentile_ms(history_guard.iter().map(|dp| dp.metrics.authentication_time), 0.95);
+            let threshold = self.config.slow_auth_threshold_ms as f64;
+            recommendations.push(Recommendation {
+                bottleneck_type: BottleneckType::SlowAuthentication,
+                message: format!("Authentication p95 {:.0}ms — enable auth caching and connection pooling", p95),
+                impact_score: estimate_impact((p95 - threshold) / threshold, affected_fraction(count), 40.0),
+                affected_samples: count,
+            });
         }
 
-        // Add general recommendations if no bottlenecks
-        if recommendations.is_empty() {
-            recommendations.push("Performance is optimal - continue monitoring".to_string());
-        } else {
-            // Sort by severity
-            recommendations.sort();
-            recommendations.dedup();
+        if let Some(&count) = signal_counts.get(&BottleneckType::CacheInefficiency) {
+            let average_hit_rate = history_guard.iter().map(|dp| dp.metrics.cache_hit_rate).sum::<f64>()
+                / sample_count as f64;
+            let threshold = self.config.low_cache_hit_threshold;
+            recommendations.push(Recommendation {
+                bottleneck_type: BottleneckType::CacheInefficiency,
+                message: format!("Cache hit rate {:.0}% — increase auth cache TTL", average_hit_rate * 100.0),
+                impact_score: estimate_impact((threshold - average_hit_rate) / threshold, affected_fraction(count), 35.0),
+                affected_samples: count,
+            });
+        }
+
+        if let Some(&count) = signal_counts.get(&BottleneckType::SlowTokenRefresh) {
+            let p95 = percentile_ms(history_guard.iter().map(|dp| dp.metrics.token_refresh_time), 0.95);
+            let threshold = self.config.slow_refresh_threshold_ms as f64;
+            recommendations.push(Recommendation {
+                bottleneck_type: BottleneckType::SlowTokenRefresh,
+                message: format!("Token refresh p95 {:.0}ms — enable batched refresh", p95),
+                impact_score: estimate_impact((p95 - threshold) / threshold, affected_fraction(count), 25.0),
+                affected_samples: count,
+            });
+        }
+
+        if let Some(&count) = signal_counts.get(&BottleneckType::MemoryPressure) {
+            let peak_mb = history_guard.iter().map(|dp| dp.metrics.memory_usage).max().unwrap_or(0) / (1024 * 1024);
+            let threshold = self.config.high_memory_threshold_mb as f64;
+            recommendations.push(Recommendation {
+                bottleneck_type: BottleneckType::MemoryPressure,
+                message: format!(
+                    "Memory peaked at {}MB (limit {}MB) — increase session cleanup frequency",
+                    peak_mb, self.config.high_memory_threshold_mb
+                ),
+                impact_score: estimate_impact((peak_mb as f64 - threshold) / threshold, affected_fraction(count), 25.0),
+                affected_samples: count,
+            });
+        }
+
+        if let Some(&count) = signal_counts.get(&BottleneckType::ConcurrencyOverload) {
+            let peak_agents = history_guard.iter().map(|dp| dp.metrics.concurrent_agents).max().unwrap_or(0);
+            let limit = self.config.high_concurrency_threshold;
+            recommendations.push(Recommendation {
+                bottleneck_type: BottleneckType::ConcurrencyOverload,
+                message: format!(
+                    "Concurrent agents peaked at {} of {} — enable request queuing with backpressure",
+                    peak_agents, limit
+                ),
+                impact_score: estimate_impact(peak_agents as f64 / limit as f64, affected_fraction(count), 20.0),
+                affected_samples: count,
+            });
+        }
+
+        if let Some(&count) = signal_counts.get(&BottleneckType::NetworkLatency) {
+            recommendations.push(Recommendation {
+                bottleneck_type: BottleneckType::NetworkLatency,
+                message: format!(
+                    "{:.0}% of operations made more than {} network requests — batch requests over pooled connections",
+                    affected_fraction(count) * 100.0,
+                    self.config.high_network_requests_threshold
+                ),
+                impact_score: estimate_impact(1.0, affected_fraction(count), 15.0),
+                affected_samples: count,
+            });
         }
 
+        recommendations.sort_by(|a, b| {
+            b.impact_score
+                .partial_cmp(&a.impact_score)
+                .unwrap_or(std::cmp::Ordering::Equal)
+                .then_with(|| b.affected_samples.cmp(&a.affected_samples))
+        });
+
         recommendations
     }
 
+    /// Classify a single sample against the configured thresholds
+    fn classify_sample(&self, metrics: &PerformanceMetrics) -> Vec<BottleneckType> {
+        let mut signals = Vec::new();
+
+        if metrics.authentication_time.as_millis() > self.config.slow_auth_threshold_ms {
+            signals.push(BottleneckType::SlowAuthentication);
+        }
+        if metrics.token_refresh_time.as_millis() > self.config.slow_refresh_threshold_ms {
+            signals.push(BottleneckType::SlowTokenRefresh);
+        }
+        if metrics.memory_usage > self.config.high_memory_threshold_mb * 1024 * 1024 {
+            signals.push(BottleneckType::MemoryPressure);
+        }
+        if metrics.cache_hit_rate < self.config.low_cache_hit_threshold {
+            signals.push(BottleneckType::CacheInefficiency);
+        }
+        // Flag concurrency once it reaches 80% of the limit so queuing can be enabled before overload
+        if metrics.concurrent_agents * 5 >= self.config.high_concurrency_threshold * 4 {
+            signals.push(BottleneckType::ConcurrencyOverload);
+        }
+        if metrics.network_requests > self.config.high_network_requests_threshold {
+            signals.push(BottleneckType::NetworkLatency);
+        }
+
+        signals
+    }
+
     /// Perform real-time analysis on new data point
     async fn perform_real_time_analysis(&self, data_point: &PerformanceDataPoint) {
         // Check for immediate issues
@@ -588,7 +725,7 @@ impl BottleneckAnalyzer {
         // Analyze network requests vs performance
         let high_request_count = history
             .iter()
-            .filter(|dp| dp.metrics.network_requests > 5)
+            .filter(|dp| dp.metrics.network_requests > self.config.high_network_requests_threshold)
             .count();
 
         if high_request_count > history.len() / 2 {
@@ -845,6 +982,27 @@ impl BottleneckAnalyzer {
     }
 }
 
+/// Percentile of the non-zero durations in milliseconds; zero means the operation did not run
+fn percentile_ms(durations: impl Iterator<Item = Duration>, quantile: f64) -> f64 {
+    let mut values: Vec<f64> = durations
+        .filter(|duration| !duration.is_zero())
+        .map(|duration| duration.as_secs_f64() * 1000.0)
+        .collect();
+    if values.is_empty() {
+        return 0.0;
+    }
+
+    values.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+    let rank = ((quantile * values.len() as f64).ceil() as usize).clamp(1, values.len());
+    values[rank - 1]
+}
+
+/// Estimate impact on a 0-100 scale from how far past the threshold a metric is,
+/// how often it happens, and how directly the category affects auth latency
+fn estimate_impact(excess_ratio: f64, affected_fraction: f64, weight: f64) -> f64 {
+    (excess_ratio.max(0.0) * affected_fraction * weight * 2.0).min(100.0)
+}
+
 impl Clone for BottleneckAnalyzer {
     fn clone(&self) -> Self {
         Self {
@@ -960,4 +1118,91 @@ mod tests {
         assert!(!recommendations.is_empty());
         assert!(recommendations.len() > 3); // Should have multiple recommendations
     }
+
+    fn recommendation_for(recommendations: &[Recommendation], bottleneck_type: BottleneckType) -> Option<&Recommendation> {
+        recommendations.iter().find(|r| r.bottleneck_type == bottleneck_type)
+    }
+
+    #[tokio::test]
+    async fn test_healthy_metrics_have_no_recommendations() {
+        let analyzer = BottleneckAnalyzer::new();
+        analyzer.analyze_metrics(&create_test_metrics(20, 20, 0.95, 2)).await;
+
+        assert!(analyzer.get_ranked_recommendations().await.is_empty());
+        assert_eq!(analyzer.get_recommendations().await, vec!["Performance is optimal - continue monitoring".to_string()]);
+    }
+
+    #[tokio::test]
+    async fn test_cache_hit_rate_recommendation() {
+        let analyzer = BottleneckAnalyzer::new();
+        for _ in 0..4 {
+            analyzer.analyze_metrics(&create_test_metrics(20, 20, 0.42, 2)).await;
+        }
+
+        let recommendations = analyzer.get_ranked_recommendations().await;
+        let cache = recommendation_for(&recommendations, BottleneckType::CacheInefficiency).unwrap();
+        assert_eq!(cache.message, "Cache hit rate 42% — increase auth cache TTL");
+        assert_eq!(cache.affected_samples, 4);
+    }
+
+    #[tokio::test]
+    async fn test_slow_refresh_recommendation() {
+        let analyzer = BottleneckAnalyzer::new();
+        for refresh_ms in [300, 820] {
+            let mut metrics = create_test_metrics(20, 20, 0.95, 2);
+            metrics.token_refresh_time = Duration::from_millis(refresh_ms);
+            analyzer.analyze_metrics(&metrics).await;
+        }
+
+        let recommendations = analyzer.get_ranked_recommendations().await;
+        assert_eq!(recommendations.len(), 1);
+        assert_eq!(recommendations[0].message, "Token refresh p95 820ms — enable batched refresh");
+    }
+
+    #[tokio::test]
+    async fn test_slow_auth_memory_and_network_recommendations() {
+        let analyzer = BottleneckAnalyzer::new();
+        let mut metrics = create_test_metrics(250, 150, 0.95, 2);
+        metrics.network_requests = 8;
+        analyzer.analyze_metrics(&metrics).await;
+
+        let recommendations = analyzer.get_ranked_recommendations().await;
+        assert!(recommendation_for(&recommendations, BottleneckType::SlowAuthentication)
+            .unwrap()
+            .message
+            .starts_with("Authentication p95 250ms"));
+        assert!(recommendation_for(&recommendations, BottleneckType::MemoryPressure)
+            .unwrap()
+            .message
+            .starts_with("Memory peaked at 150MB (limit 100MB)"));
+        assert!(recommendation_for(&recommendations, BottleneckType::NetworkLatency)
+            .unwrap()
+            .message
+            .starts_with("100% of operations made more than 5 network requests"));
+    }
+
+    #[tokio::test]
+    async fn test_concurrency_near_limit_recommendation() {
+        let analyzer = BottleneckAnalyzer::new();
+        analyzer.analyze_metrics(&create_test_metrics(20, 20, 0.95, 7)).await;
+
+        let recommendations = analyzer.get_ranked_recommendations().await;
+        let concurrency = recommendation_for(&recommendations, BottleneckType::ConcurrencyOverload).unwrap();
+        assert_eq!(concurrency.message, "Concurrent agents peaked at 7 of 8 — enable request queuing with backpressure");
+    }
+
+    #[tokio::test]
+    async fn test_recommendations_deduplicated_and_ranked_by_impact() {
+        let analyzer = BottleneckAnalyzer::new();
+        // Auth is badly over target on every sample; cache misses only slightly and rarely
+        for i in 0..10 {
+            let cache_hit_rate = if i == 0 { 0.65 } else { 0.95 };
+            analyzer.analyze_metrics(&create_test_metrics(400, 20, cache_hit_rate, 2)).await;
+        }
+
+        let recommendations = analyzer.get_recommendations().await;
+        assert_eq!(recommendations.len(), 2);
+        assert!(recommendations[0].starts_with("Authentication p95 400ms"));
+        assert!(recommendations[1].starts_with("Cache hit rate"));
+    }
 }
\ No newline at end of file
@@ -8,6 +8,11 @@ use tokio::sync::{RwLock, Semaphore};
 use reqwest::Client;
 use serde::{Serialize, Deserialize};
 
+use super::http_logging;
+use crate::claude_auth::secure_claude_auth::{
+    apply_proxy, default_user_agent, is_connection_lost, ClaudeAuthError, ProxyConfig, TlsConfig, DEFAULT_ORIGINATOR,
+};
+
 /// Connection pool configuration
 #[derive(Debug, Clone)]
 pub struct PoolConfig {
@@ -18,6 +23,16 @@ pub struct PoolConfig {
     pub max_idle_connections: usize,
     pub keep_alive_enabled: bool,
     pub http2_enabled: bool,
+    /// User agent sent on every pooled request
+    pub user_agent: String,
+    /// Proxy for every pooled request; environment proxies apply when unset
+    pub proxy: Option<ProxyConfig>,
+    /// Client certificate and CA bundle presented on every pooled request
+    pub tls: Option<TlsConfig>,
+    /// Log every pooled request and response at `trace`, with credentials scrubbed
+    ///
+    /// Nothing is logged unless `trace` is also enabled for this module.
+    pub log_http_exchanges: bool,
 }
 
 impl Default for PoolConfig {
@@ -30,6 +45,10 @@ impl Default for PoolConfig {
             max_idle_connections: 10,       // Max 10 idle connections
             keep_alive_enabled: true,       // Enable HTTP keep-alive
             http2_enabled: true,            // Enable HTTP/2
+            user_agent: default_user_agent(DEFAULT_ORIGINATOR),
+            proxy: None,
+            tls: None,
+            log_http_exchanges: false,
         }
     }
 }
@@ -59,16 +78,19 @@ pub struct RequestStats {
 /// Host-specific connection pool
 #[derive(Debug)]
 struct HostPool {
-    client: Client,
     active_requests: Arc<Semaphore>,
     stats: PoolStats,
     last_used: Instant,
 }
 
 /// High-performance connection pool for Claude API
+///
+/// A single tuned `reqwest::Client` is shared by every host so that keep-alive
+/// and HTTP/2 connections are reused across all Anthropic calls.
 #[derive(Debug)]
 pub struct ClaudeConnectionPool {
     config: PoolConfig,
+    client: Client,
     pools: Arc<RwLock<HashMap<String, HostPool>>>,
     global_stats: Arc<RwLock<PoolStats>>,
 }
@@ -80,9 +102,27 @@ impl ClaudeConnectionPool {
     }
 
     /// Create connection pool with custom configuration
+    ///
+    /// A client certificate that can't be loaded is logged and left out; use
+    /// [`try_with_config`](Self::try_with_config) to get the error instead.
     pub fn with_config(config: PoolConfig) -> Self {
+        let client = Self::build_client(&config).unwrap_or_else(|e| {
+            tracing::warn!(error = %e, "Failed to build pooled HTTP client, falling back to defaults");
+            Client::new()
+        });
+        Self::with_client(config, client)
+    }
+
+    /// Create connection pool with custom configuration, failing on an invalid proxy or certificate
+    pub fn try_with_config(config: PoolConfig) -> Result<Self, ClaudeAuthError> {
+        let client = Self::build_client(&config)?;
+        Ok(Self::with_client(config, client))
+    }
+
+    fn with_client(config: PoolConfig, client: Client) -> Self {
         Self {
             config,
+            client,
             pools: Arc::new(RwLock::new(HashMap::new())),
             global_stats: Arc::new(RwLock::new(PoolStats {
                 total_connections: 0,
@@ -97,37 +137,66 @@ impl ClaudeConnectionPool {
         }
     }
 
+    /// Get the pooled HTTP client for a specific host
+    pub async fn client_for(&self, host: &str) -> Client {
+        let known_host = {
+            let pools_guard = self.pools.read().await;
+            pools_guard.contains_key(host)
+        };
+
+        if !known_host {
+            self.register_host(host).await;
+        }
+
+        self.client.clone()
+    }
+
     /// Get optimized HTTP client for a specific host
     pub async fn get_client(&self, host: &str) -> Client {
-        // Check if we already have a pool for this host
-        {
-            let pools_guard = self.pools.read().await;
-            if let Some(host_pool) = pools_guard.get(host) {
-                return host_pool.client.clone();
-            }
+        self.client_for(host).await
+    }
+
+    /// Shared client without host bookkeeping, for callers that can't await
+    pub fn shared_client(&self) -> Client {
+        self.client.clone()
+    }
+
+    /// Build the shared client from the pool configuration
+    fn build_client(config: &PoolConfig) -> Result<Client, ClaudeAuthError> {
+        let mut builder = Client::builder()
+            .timeout(Duration::from_millis(config.request_timeout_ms))
+            .connect_timeout(Duration::from_millis(config.connection_timeout_ms))
+            .pool_idle_timeout(Duration::from_millis(config.idle_timeout_ms))
+            .user_agent(config.user_agent.as_str());
+
+        if config.keep_alive_enabled {
+            builder = builder
+                .pool_max_idle_per_host(config.max_idle_connections)
+                .tcp_keepalive(Duration::from_secs(60));
+        } else {
+            builder = builder.pool_max_idle_per_host(0);
         }
 
-        // Create new client pool for this host
-        self.create_host_pool(host).await
-    }
-
-    /// Create optimized HTTP client pool for a host
-    async fn create_host_pool(&self, host: &str) -> Client {
-        let client = Client::builder()
-            .timeout(Duration::from_millis(self.config.request_timeout_ms))
-            .connect_timeout(Duration::from_millis(self.config.connection_timeout_ms))
-            .pool_idle_timeout(Duration::from_millis(self.config.idle_timeout_ms))
-            .pool_max_idle_per_host(self.config.max_idle_connections)
-            .http2_prior_knowledge()
-            .http2_keep_alive_interval(Duration::from_secs(30))
-            .http2_keep_alive_timeout(Duration::from_secs(10))
-            .tcp_keepalive(Duration::from_secs(60))
-            .user_agent("Claude-Code-Integration/1.0")
-            .build()
-            .unwrap_or_else(|_| Client::new());
+        // HTTP/2 is negotiated via ALPN, so plain HTTP/1.1 hosts keep working
+        if config.http2_enabled {
+            builder = builder
+                .http2_keep_alive_interval(Duration::from_secs(30))
+                .http2_keep_alive_timeout(Duration::from_secs(10))
+                .http2_keep_alive_while_idle(config.keep_alive_enabled);
+        } else {
+            builder = builder.http1_only();
+        }
+
+        builder = apply_proxy(builder, config.proxy.as_ref())?;
+        if let Some(ref tls) = config.tls {
+            builder = tls.apply(builder)?;
+        }
+        Ok(builder.build()?)
+    }
 
+    /// Start tracking a host served by the shared client
+    async fn register_host(&self, host: &str) {
         let host_pool = HostPool {
-            client: client.clone(),
             active_requests: Arc::new(Semaphore::new(self.config.max_connections_per_host)),
             stats: PoolStats {
                 total_connections: 1,
@@ -142,9 +211,12 @@ impl ClaudeConnectionPool {
             last_used: Instant::now(),
         };
 
-        // Store the pool
+        // Store the pool, unless another task registered the host first
         {
             let mut pools_guard = self.pools.write().await;
+            if pools_guard.contains_key(host) {
+                return;
+            }
             pools_guard.insert(host.to_string(), host_pool);
         }
 
@@ -154,11 +226,14 @@ impl ClaudeConnectionPool {
             global_stats_guard.total_connections += 1;
             global_stats_guard.idle_connections += 1;
         }
-
-        client
     }
 
     /// Execute HTTP request with connection pooling and performance tracking
+    ///
+    /// An idempotent request whose connection is reset or sent GOAWAY is repeated once on
+    /// a fresh connection; only the second error is returned. Other methods, such as POST,
+    /// may already have reached the server and fail on the first error, as do requests
+    /// with a streaming body, which can't be repeated.
     pub async fn execute_request(
         &self,
         host: &str,
@@ -166,22 +241,22 @@ impl ClaudeConnectionPool {
     ) -> Result<reqwest::Response, reqwest::Error> {
         let start_time = Instant::now();
 
-        // Get client and acquire semaphore for rate limiting
-        let client = self.get_client(host).await;
-        let _permit = {
+        // Register the host and acquire its semaphore for rate limiting
+        self.client_for(host).await;
+        let semaphore = {
             let pools_guard = self.pools.read().await;
-            if let Some(host_pool) = pools_guard.get(host) {
-                Some(host_pool.active_requests.acquire().await.unwrap())
-            } else {
-                None
-            }
+            pools_guard.get(host).map(|host_pool| Arc::clone(&host_pool.active_requests))
+        };
+        let _permit = match &semaphore {
+            Some(semaphore) => semaphore.acquire().await.ok(),
+            None => None,
         };
 
         // Update active connections
         self.increment_active_connections().await;
 
         // Execute request
-        let result = request_builder.send().await;
+        let result = self.send_with_retry(host, request_builder).await;
         let request_time = start_time.elapsed();
 
         // Update statistics
@@ -193,6 +268,44 @@ impl ClaudeConnectionPool {
         result
     }
 
+    /// Send once, and once more if an idempotent request lost its connection, logging the
+    /// exchange when enabled
+    async fn send_with_retry(
+        &self,
+        host: &str,
+        request_builder: reqwest::RequestBuilder,
+    ) -> Result<reqwest::Response, reqwest::Error> {
+        let log_exchange = self.config.log_http_exchanges && tracing::enabled!(tracing::Level::TRACE);
+        let (client, request) = request_builder.build_split();
+        let request = request?;
+        if log_exchange {
+            http_logging::log_request(&request);
+        }
+
+        let started = Instant::now();
+        let retry = is_idempotent(request.method()).then(|| request.try_clone()).flatten();
+        let mut result = client.execute(request).await;
+        if let (Err(e), Some(retry)) = (&result, retry) {
+            if is_connection_lost(e) {
+                tracing::debug!(host = host, error = %e, "Pooled connection lost, retrying on a fresh one");
+                result = client.execute(retry).await;
+            }
+        }
+
+        match result {
+            Ok(response) if log_exchange => http_logging::log_response(response, started.elapsed()).await,
+            Err(e) if log_exchange => {
+                tracing::trace!(
+                    elapsed_ms = started.elapsed().as_millis() as u64,
+                    error = %http_logging::redact_error(&e),
+                    "HTTP request failed"
+                );
+                Err(e)
+            }
+            result => result,
+        }
+    }
+
     /// Make GET request with connection pooling
     pub async fn get(&self, url: &str) -> Result<reqwest::Response, reqwest::Error> {
         let host = self.extract_host(url);
@@ -254,7 +367,7 @@ impl ClaudeConnectionPool {
             .collect();
 
         for host in hosts_to_remove {
-            if let Some(removed_pool) = pools_guard.remove(&host) {
+            if pools_guard.remove(&host).is_some() {
                 // Update global stats
                 let mut global_stats_guard = self.global_stats.write().await;
                 global_stats_guard.total_connections -= 1;
@@ -377,6 +490,7 @@ impl Clone for ClaudeConnectionPool {
     fn clone(&self) -> Self {
         Self {
             config: self.config.clone(),
+            client: self.client.clone(),
             pools: Arc::clone(&self.pools),
             global_stats: Arc::clone(&self.global_stats),
         }
@@ -393,6 +507,12 @@ pub struct ConnectionPoolHealth {
     pub recommendations: Vec<String>,
 }
 
+/// Whether repeating a request has the same effect as sending it once (RFC 9110 §9.2.2)
+fn is_idempotent(method: &reqwest::Method) -> bool {
+    use reqwest::Method;
+    matches!(*method, Method::GET | Method::HEAD | Method::OPTIONS | Method::TRACE | Method::PUT | Method::DELETE)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -453,6 +573,101 @@ mod tests {
         assert_eq!(pool.config.connection_timeout_ms, 1000);
     }
 
+    #[tokio::test]
+    async fn test_repeated_requests_reuse_connection() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+        use tokio::net::TcpListener;
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let accepts = Arc::new(AtomicUsize::new(0));
+
+        let server_accepts = Arc::clone(&accepts);
+        tokio::spawn(async move {
+            loop {
+                let (mut socket, _) = match listener.accept().await {
+                    Ok(conn) => conn,
+                    Err(_) => return,
+                };
+                server_accepts.fetch_add(1, Ordering::SeqCst);
+                tokio::spawn(async move {
+                    let mut buf = vec![0u8; 4096];
+                    loop {
+                        match socket.read(&mut buf).await {
+                            Ok(0) | Err(_) => return,
+                            Ok(_) => {
+                                let response = "HTTP/1.1 200 OK\r\nContent-Length: 2\r\nConnection: keep-alive\r\n\r\nok";
+                                if socket.write_all(response.as_bytes()).await.is_err() {
+                                    return;
+                                }
+                            }
+                        }
+                    }
+                });
+            }
+        });
+
+        let pool = ClaudeConnectionPool::new();
+        let url = format!("http://{}/v1/ping", addr);
+
+        for _ in 0..5 {
+            let response = pool.get(&url).await.unwrap();
+            assert!(response.status().is_success());
+            assert_eq!(response.text().await.unwrap(), "ok");
+        }
+
+        assert_eq!(accepts.load(Ordering::SeqCst), 1);
+        assert_eq!(pool.get_stats().await.total_requests, 5);
+    }
+
+    #[tokio::test]
+    async fn test_request_retried_once_after_connection_closed() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+        use tokio::net::TcpListener;
+
+        // Closes every connection after reading the request, except the `answer_from`th onwards
+        async fn server(answer_from: usize) -> (String, Arc<AtomicUsize>) {
+            let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let url = format!("http://{}/v1/ping", listener.local_addr().unwrap());
+            let accepts = Arc::new(AtomicUsize::new(0));
+            let server_accepts = Arc::clone(&accepts);
+            tokio::spawn(async move {
+                while let Ok((mut socket, _)) = listener.accept().await {
+                    let accepted = server_accepts.fetch_add(1, Ordering::SeqCst) + 1;
+                    tokio::spawn(async move {
+                        let mut buf = vec![0u8; 4096];
+                        if matches!(socket.read(&mut buf).await, Ok(n) if n > 0) && accepted >= answer_from {
+                            let response = "HTTP/1.1 200 OK\r\nContent-Length: 2\r\n\r\nok";
+                            let _ = socket.write_all(response.as_bytes()).await;
+                        }
+                    });
+                }
+            });
+            (url, accepts)
+        }
+
+        let pool = ClaudeConnectionPool::new();
+
+        let (url, accepts) = server(2).await;
+        let response = pool.get(&url).await.unwrap();
+        assert_eq!(response.text().await.unwrap(), "ok");
+        assert_eq!(accepts.load(Ordering::SeqCst), 2);
+        assert_eq!(pool.get_stats().await.failed_connections, 0);
+
+        let (url, accepts) = server(usize::MAX).await;
+        let error = pool.get(&url).await.unwrap_err();
+        assert!(is_connection_lost(&error));
+        assert_eq!(accepts.load(Ordering::SeqCst), 2);
+
+        // The server may have acted on a POST before the connection dropped
+        let (url, accepts) = server(2).await;
+        let error = pool.post(&url, "{}").await.unwrap_err();
+        assert!(is_connection_lost(&error));
+        assert_eq!(accepts.load(Ordering::SeqCst), 1);
+    }
+
     #[tokio::test]
     async fn test_cleanup_idle_connections() {
         let mut config = PoolConfig::default();
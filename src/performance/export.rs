@@ -0,0 +1,174 @@
+// Structured export of recorded performance metrics
+// Writes the PerformanceCoordinator metrics history as JSON lines or CSV for offline analysis
+
+use std::io::Write;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use super::{PerformanceCoordinator, PerformanceMetrics};
+
+/// Output format for [`PerformanceCoordinator::export`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExportFormat {
+    /// One JSON object per line
+    JsonLines,
+    /// Comma-separated values with a header row
+    Csv,
+}
+
+/// CSV header, in the field order of [`MetricsRecord`]
+const CSV_COLUMNS: [&str; 7] = [
+    "timestamp",
+    "authentication_time_ms",
+    "token_refresh_time_ms",
+    "cache_hit_rate",
+    "memory_usage_bytes",
+    "concurrent_agents",
+    "network_requests",
+];
+
+/// One exported sample, flattened so spreadsheets and notebooks can load it directly
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MetricsRecord {
+    pub timestamp: DateTime<Utc>,
+    pub authentication_time_ms: f64,
+    pub token_refresh_time_ms: f64,
+    pub cache_hit_rate: f64,
+    pub memory_usage_bytes: u64,
+    pub concurrent_agents: usize,
+    pub network_requests: u32,
+}
+
+impl From<&PerformanceMetrics> for MetricsRecord {
+    fn from(metrics: &PerformanceMetrics) -> Self {
+        Self {
+            timestamp: metrics.timestamp.into(),
+            authentication_time_ms: metrics.authentication_time.as_secs_f64() * 1000.0,
+            token_refresh_time_ms: metrics.token_refresh_time.as_secs_f64() * 1000.0,
+            cache_hit_rate: metrics.cache_hit_rate,
+            memory_usage_bytes: metrics.memory_usage,
+            concurrent_agents: metrics.concurrent_agents,
+            network_requests: metrics.network_requests,
+        }
+    }
+}
+
+impl MetricsRecord {
+    /// Fields in [`CSV_COLUMNS`] order
+    fn csv_fields(&self) -> [String; 7] {
+        [
+            self.timestamp.to_rfc3339(),
+            self.authentication_time_ms.to_string(),
+            self.token_refresh_time_ms.to_string(),
+            self.cache_hit_rate.to_string(),
+            self.memory_usage_bytes.to_string(),
+            self.concurrent_agents.to_string(),
+            self.network_requests.to_string(),
+        ]
+    }
+}
+
+impl PerformanceCoordinator {
+    /// Write the recorded metrics history, oldest first, to `writer`
+    pub async fn export<W: Write>(&self, format: ExportFormat, mut writer: W) -> std::io::Result<()> {
+        let records: Vec<MetricsRecord> = self.metrics.read().await.iter().map(MetricsRecord::from).collect();
+
+        match format {
+            ExportFormat::JsonLines => {
+                for record in &records {
+                    serde_json::to_writer(&mut writer, record)?;
+                    writer.write_all(b"\n")?;
+                }
+            }
+            ExportFormat::Csv => {
+                write_csv_row(&mut writer, CSV_COLUMNS)?;
+                for record in &records {
+                    write_csv_row(&mut writer, record.csv_fields())?;
+                }
+            }
+        }
+        writer.flush()
+    }
+}
+
+fn write_csv_row<W: Write, S: AsRef<str>>(writer: &mut W, fields: impl IntoIterator<Item = S>) -> std::io::Result<()> {
+    let row: Vec<String> = fields.into_iter().map(|field| escape_csv_field(field.as_ref())).collect();
+    writeln!(writer, "{}", row.join(","))
+}
+
+/// Quote a field per RFC 4180 when it contains a delimiter, quote or line break
+fn escape_csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{Duration, SystemTime};
+
+    fn sample(auth_ms: u64, agents: usize) -> PerformanceMetrics {
+        PerformanceMetrics {
+            authentication_time: Duration::from_millis(auth_ms),
+            token_refresh_time: Duration::from_millis(250),
+            cache_hit_rate: 0.75,
+            memory_usage: 12 * 1024 * 1024,
+            concurrent_agents: agents,
+            network_requests: 2,
+            timestamp: SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000 + auth_ms),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_json_lines_export_round_trips() {
+        let coordinator = PerformanceCoordinator::new();
+        let recorded = [sample(40, 1), sample(60, 2), sample(80, 3)];
+        for metrics in &recorded {
+            coordinator.record_metrics(metrics.clone()).await;
+        }
+
+        let mut output = Vec::new();
+        coordinator.export(ExportFormat::JsonLines, &mut output).await.unwrap();
+
+        let exported: Vec<MetricsRecord> = String::from_utf8(output)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        let expected: Vec<MetricsRecord> = recorded.iter().map(MetricsRecord::from).collect();
+        assert_eq!(exported, expected);
+        assert_eq!(exported[0].timestamp.timestamp(), 1_700_000_040);
+    }
+
+    #[tokio::test]
+    async fn test_csv_export_has_header_and_rows() {
+        let coordinator = PerformanceCoordinator::new();
+        coordinator.record_metrics(sample(40, 1)).await;
+        coordinator.record_metrics(sample(60, 2)).await;
+
+        let mut output = Vec::new();
+        coordinator.export(ExportFormat::Csv, &mut output).await.unwrap();
+        let output = String::from_utf8(output).unwrap();
+        let lines: Vec<&str> = output.lines().collect();
+
+        assert_eq!(lines.len(), 3);
+        assert_eq!(
+            lines[0],
+            "timestamp,authentication_time_ms,token_refresh_time_ms,cache_hit_rate,memory_usage_bytes,concurrent_agents,network_requests"
+        );
+        assert_eq!(lines[1], "2023-11-14T22:14:00+00:00,40,250,0.75,12582912,1,2");
+    }
+
+    #[test]
+    fn test_csv_fields_are_escaped() {
+        assert_eq!(escape_csv_field("plain"), "plain");
+        assert_eq!(escape_csv_field("a,b"), "\"a,b\"");
+        assert_eq!(escape_csv_field("say \"hi\""), "\"say \"\"hi\"\"\"");
+        assert_eq!(escape_csv_field("two\nlines"), "\"two\nlines\"");
+    }
+}
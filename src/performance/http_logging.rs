@@ -0,0 +1,293 @@
+// Opt-in trace logging of the HTTP exchanges made through the connection pool
+// Credentials are scrubbed from URLs, headers and bodies before anything reaches a log
+
+use std::time::Duration;
+
+use reqwest::header::{HeaderMap, CONTENT_TYPE};
+use reqwest::ResponseBuilderExt;
+
+use crate::security::mask_secret;
+
+/// Longest body logged, after redaction
+const MAX_LOGGED_BODY_BYTES: usize = 4096;
+
+/// Header, query parameter and body field names whose values are always masked
+const SENSITIVE_NAMES: [&str; 7] = [
+    "authorization",
+    "proxy_authorization",
+    "cookie",
+    "set_cookie",
+    "code",
+    "code_verifier",
+    "key",
+];
+
+/// Name fragments that mark a value as secret wherever they appear, e.g. `refresh_token`
+const SENSITIVE_FRAGMENTS: [&str; 6] = ["token", "secret", "password", "api_key", "apikey", "session"];
+
+/// Whether the value named `name` must never be logged
+fn is_sensitive(name: &str) -> bool {
+    let name = name.to_ascii_lowercase().replace('-', "_");
+    SENSITIVE_NAMES.contains(&name.as_str()) || SENSITIVE_FRAGMENTS.iter().any(|fragment| name.contains(fragment))
+}
+
+/// Masked header value, keeping an authorization scheme such as `Bearer`
+fn mask_header_value(value: &str) -> String {
+    match value.split_once(' ') {
+        Some((scheme, credential)) if !scheme.is_empty() && scheme.bytes().all(|b| b.is_ascii_alphabetic()) => {
+            format!("{} {}", scheme, mask_secret(credential))
+        }
+        _ => mask_secret(value),
+    }
+}
+
+/// `url` with the values of sensitive query parameters and any userinfo masked
+pub fn redact_url(url: &url::Url) -> String {
+    let mut redacted = url.clone();
+    if !redacted.username().is_empty() || redacted.password().is_some() {
+        let _ = redacted.set_username("***");
+        let _ = redacted.set_password(None);
+    }
+    if url.query().is_some() {
+        let pairs: Vec<(String, String)> = url
+            .query_pairs()
+            .map(|(name, value)| {
+                let value = if is_sensitive(&name) { mask_secret(&value) } else { value.into_owned() };
+                (name.into_owned(), value)
+            })
+            .collect();
+        redacted.query_pairs_mut().clear().extend_pairs(pairs);
+    }
+    redacted.to_string()
+}
+
+/// Header names and values, with sensitive values masked
+pub fn redact_headers(headers: &HeaderMap) -> Vec<(String, String)> {
+    headers
+        .iter()
+        .map(|(name, value)| {
+            let value = match value.to_str() {
+                Ok(value) if is_sensitive(name.as_str()) => mask_header_value(value),
+                Ok(value) => value.to_string(),
+                Err(_) => "<binary>".to_string(),
+            };
+            (name.as_str().to_string(), value)
+        })
+        .collect()
+}
+
+/// Loggable form of a body: JSON and form bodies with sensitive fields masked, anything
+/// else only by size, since free text can't be scrubbed reliably
+pub fn redact_body(content_type: Option<&str>, body: &[u8]) -> String {
+    if body.is_empty() {
+        return String::new();
+    }
+
+    let content_type = content_type.unwrap_or("").to_ascii_lowercase();
+    let redacted = if let Ok(mut json) = serde_json::from_slice::<serde_json::Value>(body) {
+        redact_json(&mut json);
+        json.to_string()
+    } else if content_type.starts_with("application/x-www-form-urlencoded") {
+        url::form_urlencoded::Serializer::new(String::new())
+            .extend_pairs(url::form_urlencoded::parse(body).map(|(name, value)| {
+                let value = if is_sensitive(&name) { mask_secret(&value) } else { value.into_owned() };
+                (name.into_owned(), value)
+            }))
+            .finish()
+    } else {
+        return format!("<{} bytes>", body.len());
+    };
+
+    truncate(redacted)
+}
+
+fn redact_json(value: &mut serde_json::Value) {
+    match value {
+        serde_json::Value::Object(map) => {
+            for (name, value) in map.iter_mut() {
+                match value {
+                    serde_json::Value::String(s) if is_sensitive(name) => *s = mask_secret(s),
+                    serde_json::Value::Object(_) | serde_json::Value::Array(_) => redact_json(value),
+                    // Numbers and booleans under a sensitive name carry nothing worth hiding
+                    _ => {}
+                }
+            }
+        }
+        serde_json::Value::Array(items) => items.iter_mut().for_each(redact_json),
+        _ => {}
+    }
+}
+
+fn truncate(mut body: String) -> String {
+    if body.len() > MAX_LOGGED_BODY_BYTES {
+        let mut end = MAX_LOGGED_BODY_BYTES;
+        while !body.is_char_boundary(end) {
+            end -= 1;
+        }
+        body.truncate(end);
+        body.push('…');
+    }
+    body
+}
+
+/// Error message with the request URL it names redacted
+pub fn redact_error(error: &reqwest::Error) -> String {
+    let message = error.to_string();
+    match error.url() {
+        Some(url) => message.replace(url.as_str(), &redact_url(url)),
+        None => message,
+    }
+}
+
+fn content_type(headers: &HeaderMap) -> Option<&str> {
+    headers.get(CONTENT_TYPE).and_then(|value| value.to_str().ok())
+}
+
+/// Log an outgoing request at `trace`
+pub(crate) fn log_request(request: &reqwest::Request) {
+    let body = request.body().and_then(|body| body.as_bytes());
+    tracing::trace!(
+        method = %request.method(),
+        url = %redact_url(request.url()),
+        headers = ?redact_headers(request.headers()),
+        body = %body.map_or_else(|| "<stream>".to_string(), |body| redact_body(content_type(request.headers()), body)),
+        "HTTP request"
+    );
+}
+
+/// Log a response at `trace` and hand back an equivalent one
+///
+/// The body is read to log it, so the returned response is rebuilt from the buffered
+/// bytes with the same status, version, headers and URL.
+pub(crate) async fn log_response(
+    response: reqwest::Response,
+    elapsed: Duration,
+) -> Result<reqwest::Response, reqwest::Error> {
+    let status = response.status();
+    let version = response.version();
+    let url = response.url().clone();
+    let headers = response.headers().clone();
+    let body = response.bytes().await?;
+
+    tracing::trace!(
+        status = status.as_u16(),
+        elapsed_ms = elapsed.as_millis() as u64,
+        url = %redact_url(&url),
+        headers = ?redact_headers(&headers),
+        body = %redact_body(content_type(&headers), &body),
+        "HTTP response"
+    );
+
+    let mut rebuilt = http::Response::builder().status(status).version(version).url(url);
+    if let Some(rebuilt_headers) = rebuilt.headers_mut() {
+        *rebuilt_headers = headers;
+    }
+    Ok(rebuilt
+        .body(body)
+        .map(reqwest::Response::from)
+        .expect("status and headers come from a valid response"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    /// Records every event as `field=value` pairs
+    #[derive(Default)]
+    struct EventRecorder {
+        events: Arc<Mutex<Vec<String>>>,
+    }
+
+    struct FieldWriter(String);
+
+    impl tracing::field::Visit for FieldWriter {
+        fn record_debug(&mut self, field: &tracing::field::Field, value: &dyn std::fmt::Debug) {
+            self.0.push_str(&format!("{}={:?} ", field.name(), value));
+        }
+    }
+
+    impl tracing::Subscriber for EventRecorder {
+        fn enabled(&self, _: &tracing::Metadata<'_>) -> bool {
+            true
+        }
+
+        fn new_span(&self, _: &tracing::span::Attributes<'_>) -> tracing::span::Id {
+            tracing::span::Id::from_u64(1)
+        }
+
+        fn event(&self, event: &tracing::Event<'_>) {
+            let mut writer = FieldWriter(String::new());
+            event.record(&mut writer);
+            self.events.lock().unwrap().push(writer.0);
+        }
+
+        fn record(&self, _: &tracing::span::Id, _: &tracing::span::Record<'_>) {}
+        fn record_follows_from(&self, _: &tracing::span::Id, _: &tracing::span::Id) {}
+        fn enter(&self, _: &tracing::span::Id) {}
+        fn exit(&self, _: &tracing::span::Id) {}
+    }
+
+    #[tokio::test]
+    async fn test_logged_exchange_masks_credentials() {
+        use super::super::connection_pool::{ClaudeConnectionPool, PoolConfig};
+        use serde_json::json;
+        use wiremock::{matchers::{method, path}, Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/oauth/token"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "access_token": "sk-ant-REDACTED",
+                "expires_in": 3600,
+            })))
+            .mount(&server)
+            .await;
+
+        let recorder = EventRecorder::default();
+        let events = Arc::clone(&recorder.events);
+        let _guard = tracing::subscriber::set_default(recorder);
+
+        let pool = ClaudeConnectionPool::with_config(PoolConfig { log_http_exchanges: true, ..PoolConfig::default() });
+        let url = format!("{}/oauth/token", server.uri());
+        let request = pool
+            .shared_client()
+            .post(&url)
+            .bearer_auth("sk-ant-REDACTED")
+            .json(&json!({"grant_type": "refresh_token", "refresh_token": "sk-ant-REDACTED"}));
+        let response = pool.execute_request("127.0.0.1", request).await.unwrap();
+
+        // The caller still gets the whole response after it was logged
+        assert_eq!(response.status(), 200);
+        assert_eq!(response.url().as_str(), url);
+        let body: serde_json::Value = response.json().await.unwrap();
+        assert_eq!(body["access_token"], "sk-ant-REDACTED");
+
+        let log = events.lock().unwrap().join("\n");
+        assert!(log.contains(&url), "{}", log);
+        assert!(log.contains("status=200"), "{}", log);
+        assert!(log.contains(r#"("authorization", "Bearer sk-ant-***")"#), "{}", log);
+        for secret in ["requestsecretvalue", "refreshsecretvalue", "responsesecretvalue"] {
+            assert!(!log.contains(secret), "{} leaked in {}", secret, log);
+        }
+    }
+
+    #[test]
+    fn test_sensitive_values_are_masked() {
+        let url = url::Url::parse("https://user:pw@api.anthropic.com/v1/messages?api_key=sk-ant-api03-abcdefghijkl&beta=true").unwrap();
+        let redacted = redact_url(&url);
+        assert_eq!(redacted, "https://***@api.anthropic.com/v1/messages?api_key=sk-ant-***&beta=true");
+
+        let body = br#"{"grant_type":"refresh_token","refresh_token":"sk-ant-REDACTED","nested":[{"access_token":"eyJhbGciOiJIUzI1NiJ9"}]}"#;
+        let redacted = redact_body(Some("application/json"), body);
+        assert!(redacted.contains(r#""grant_type":"refresh_token""#));
+        assert!(redacted.contains(r#""refresh_token":"sk-ant-***""#));
+        assert!(redacted.contains(r#""access_token":"***""#));
+
+        let form = b"grant_type=authorization_code&code=abc123def456ghi789&code_verifier=verifier-value-long-enough";
+        let redacted = redact_body(Some("application/x-www-form-urlencoded"), form);
+        assert_eq!(redacted, "grant_type=authorization_code&code=***&code_verifier=***");
+
+        assert_eq!(redact_body(Some("text/plain"), b"Bearer sk-ant-secret"), "<20 bytes>");
+    }
+}
@@ -6,9 +6,8 @@ use std::time::{Duration, Instant};
 use tokio::sync::RwLock;
 use serde::{Serialize, Deserialize};
 
-use crate::claude_auth::{ClaudeAuth, ClaudeAuthMode, SubscriptionInfo};
-use crate::unified_auth::{UnifiedAuthManager, AuthProvider};
-use crate::agent_auth::{AgentAuthCoordinator, AgentAuthRequest, AgentAuthResponse};
+use crate::auth::{AuthContext, AuthProvider, Priority, ProviderType, UnifiedAuthManager};
+use crate::auth::convenience::agent_execution_context;
 
 use super::{
     PerformanceCoordinator, PerformanceMetrics, PerformanceTargets,
@@ -25,7 +24,6 @@ use super::{
 pub struct OptimizedAuthManager {
     // Core authentication components (from existing system)
     unified_auth: Arc<UnifiedAuthManager>,
-    agent_coordinator: Arc<AgentAuthCoordinator>,
     
     // Performance optimization components
     performance_coordinator: Arc<PerformanceCoordinator>,
@@ -70,12 +68,22 @@ impl Default for OptimizationConfig {
     }
 }
 
+/// Token estimate used when selecting a provider for an agent
+const DEFAULT_ESTIMATED_TOKENS: u64 = 10_000;
+
+/// Token resolved from the selected provider
+struct ProviderAuth {
+    provider: ProviderType,
+    token: String,
+    subscription_tier: Option<String>,
+}
+
 /// Optimized authentication result
 #[derive(Debug, Clone)]
 pub struct OptimizedAuthResult {
-    pub provider: AuthProvider,
+    pub provider: ProviderType,
     pub token: String,
-    pub subscription_info: Option<SubscriptionInfo>,
+    pub subscription_tier: Option<String>,
     pub performance_metrics: PerformanceMetrics,
     pub cache_hit: bool,
     pub optimization_applied: Vec<String>,
@@ -85,7 +93,6 @@ impl OptimizedAuthManager {
     /// Create new optimized authentication manager
     pub async fn new(
         unified_auth: Arc<UnifiedAuthManager>,
-        agent_coordinator: Arc<AgentAuthCoordinator>,
         config: OptimizationConfig,
     ) -> Self {
         let targets = PerformanceTargets::default();
@@ -117,7 +124,6 @@ impl OptimizedAuthManager {
 
         Self {
             unified_auth,
-            agent_coordinator,
             performance_coordinator,
             auth_cache,
             token_optimizer,
@@ -149,9 +155,9 @@ impl OptimizedAuthManager {
                 self.performance_monitor.submit_metrics(metrics.clone()).await;
                 
                 return Ok(OptimizedAuthResult {
-                    provider: if cached_auth.provider == "claude" { AuthProvider::Claude } else { AuthProvider::OpenAI },
+                    provider: if cached_auth.provider == "claude" { ProviderType::Claude } else { ProviderType::OpenAI },
                     token: cached_auth.token,
-                    subscription_info: None, // Would need to be cached separately
+                    subscription_tier: cached_auth.subscription_tier,
                     performance_metrics: metrics,
                     cache_hit,
                     optimization_applied,
@@ -160,26 +166,16 @@ impl OptimizedAuthManager {
         }
 
         // Step 2: Allocate memory for agent session
-        let session_id = if *self.optimization_enabled.read().await {
-            match self.memory_optimizer.allocate_agent_session(agent_id, estimated_memory_mb).await {
-                Ok(id) => {
-                    optimization_applied.push("memory_optimization".to_string());
-                    Some(id)
-                },
-                Err(_) => None, // Continue without memory optimization
-            }
-        } else {
-            None
-        };
-
-        // Step 3: Select optimal provider
-        let provider = self.unified_auth.select_optimal_provider().await?;
+        // Continues without memory optimization when the allocation is refused
+        if *self.optimization_enabled.read().await
+            && self.memory_optimizer.allocate_agent_session(agent_id, estimated_memory_mb).await.is_ok()
+        {
+            optimization_applied.push("memory_optimization".to_string());
+        }
 
-        // Step 4: Perform authentication with optimizations
-        let auth_result = match provider {
-            AuthProvider::Claude => self.authenticate_claude_optimized(agent_id).await?,
-            AuthProvider::OpenAI => self.authenticate_openai_optimized(agent_id).await?,
-        };
+        // Step 3: Select optimal provider and authenticate with it
+        let context = agent_execution_context(DEFAULT_ESTIMATED_TOKENS, Priority::Medium);
+        let auth_result = self.authenticate_with_optimal_provider(&context).await?;
 
         // Step 5: Cache the result (if enabled and successful)
         if *self.optimization_enabled.read().await && !cache_hit {
@@ -195,7 +191,7 @@ impl OptimizedAuthManager {
         Ok(OptimizedAuthResult {
             provider: auth_result.provider,
             token: auth_result.token,
-            subscription_info: auth_result.subscription_info,
+            subscription_tier: auth_result.subscription_tier,
             performance_metrics: metrics,
             cache_hit,
             optimization_applied,
@@ -285,6 +281,7 @@ impl OptimizedAuthManager {
                 "connections": connection_health
             },
             "recommendations": coordinator_report.recommendations,
+            "targets": self.targets,
             "optimization_status": {
                 "caching_enabled": *self.optimization_enabled.read().await,
                 "active_optimizations": self.get_active_optimizations().await
@@ -342,57 +339,42 @@ impl OptimizedAuthManager {
         Ok(None)
     }
 
-    /// Authenticate using Claude with optimizations
-    async fn authenticate_claude_optimized(
+    /// Authenticate with the provider the unified manager selects for `context`
+    async fn authenticate_with_optimal_provider(
         &self,
-        agent_id: &str,
-    ) -> Result<AgentAuthResponse, Box<dyn std::error::Error + Send + Sync>> {
-        // Use connection pooling for Claude API calls
-        let client = self.connection_pool.get_client("api.anthropic.com").await;
-        
-        // Create auth request
-        let auth_request = AgentAuthRequest {
-            agent_id: agent_id.to_string(),
-            estimated_tokens: 10000, // Default estimation
-            preferred_provider: Some(AuthProvider::Claude),
-            task_description: "Optimized authentication".to_string(),
+        context: &AuthContext,
+    ) -> Result<ProviderAuth, Box<dyn std::error::Error + Send + Sync>> {
+        let provider = self.unified_auth.get_optimal_provider(context).await?;
+
+        // Warm a pooled connection to the provider's API for the agent's first request
+        let host = match provider {
+            AuthProvider::Claude(_) => "api.anthropic.com",
+            AuthProvider::OpenAI(_) => "api.openai.com",
         };
+        self.connection_pool.get_client(host).await;
 
-        // Use the agent coordinator for actual authentication
-        self.agent_coordinator.authenticate_agent(&auth_request).await
-            .map_err(|e| Box::new(e) as Box<dyn std::error::Error + Send + Sync>)
-    }
-
-    /// Authenticate using OpenAI with optimizations
-    async fn authenticate_openai_optimized(
-        &self,
-        agent_id: &str,
-    ) -> Result<AgentAuthResponse, Box<dyn std::error::Error + Send + Sync>> {
-        // Use connection pooling for OpenAI API calls
-        let client = self.connection_pool.get_client("api.openai.com").await;
-        
-        // Create auth request
-        let auth_request = AgentAuthRequest {
-            agent_id: agent_id.to_string(),
-            estimated_tokens: 10000,
-            preferred_provider: Some(AuthProvider::OpenAI),
-            task_description: "Optimized authentication".to_string(),
+        let auth = match provider {
+            AuthProvider::Claude(claude_auth) => ProviderAuth {
+                provider: ProviderType::Claude,
+                token: claude_auth.get_token().await?,
+                subscription_tier: claude_auth.subscription_tier.clone(),
+            },
+            AuthProvider::OpenAI(openai_auth) => ProviderAuth {
+                provider: ProviderType::OpenAI,
+                token: openai_auth.api_key.ok_or("OpenAI provider has no API key")?,
+                subscription_tier: None,
+            },
         };
-
-        self.agent_coordinator.authenticate_agent(&auth_request).await
-            .map_err(|e| Box::new(e) as Box<dyn std::error::Error + Send + Sync>)
+        Ok(auth)
     }
 
     /// Cache authentication result
     async fn cache_authentication_result(
         &self,
         agent_id: &str,
-        auth_result: &AgentAuthResponse,
+        auth_result: &ProviderAuth,
     ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-        let provider_name = match auth_result.provider {
-            AuthProvider::Claude => "claude",
-            AuthProvider::OpenAI => "openai",
-        };
+        let provider_name = auth_result.provider.id();
         
         // Cache for 1 hour by default
         let expires_at = chrono::Utc::now() + chrono::Duration::hours(1);
@@ -402,7 +384,7 @@ impl OptimizedAuthManager {
             agent_id,
             &auth_result.token,
             expires_at,
-            auth_result.subscription_info.as_ref().map(|s| s.tier.clone()),
+            auth_result.subscription_tier.clone(),
         ).await;
         
         Ok(())
@@ -411,9 +393,9 @@ impl OptimizedAuthManager {
     /// Refresh individual token (fallback method)
     async fn refresh_token_individual(
         &self,
-        agent_id: &str,
-        provider: &str,
-        refresh_token: &str,
+        _agent_id: &str,
+        _provider: &str,
+        _refresh_token: &str,
     ) -> Result<String, String> {
         // This would integrate with the existing token refresh logic
         // For now, return a placeholder
@@ -466,10 +448,9 @@ pub struct PerformanceStatistics {
 /// Helper function to create an optimized auth manager from existing components
 pub async fn create_optimized_auth_manager(
     unified_auth: Arc<UnifiedAuthManager>,
-    agent_coordinator: Arc<AgentAuthCoordinator>,
 ) -> OptimizedAuthManager {
     let config = OptimizationConfig::default();
-    OptimizedAuthManager::new(unified_auth, agent_coordinator, config).await
+    OptimizedAuthManager::new(unified_auth, config).await
 }
 
 /// Helper function to integrate optimizations with existing agent tool
@@ -486,11 +467,13 @@ pub async fn integrate_with_agent_tool(
         return Err("Performance optimization system is not healthy enough for integration".into());
     }
     
-    println!("Performance optimization integration ready:");
-    println!("  - Overall Score: {:.1}%", stats.overall_score);
-    println!("  - Cache Hit Rate: {:.1}%", stats.cache_hit_rate * 100.0);
-    println!("  - Average Auth Time: {:.1}ms", stats.average_auth_time_ms);
-    println!("  - Memory Utilization: {:.1}%", stats.memory_utilization);
+    tracing::info!(
+        overall_score = stats.overall_score,
+        cache_hit_rate = stats.cache_hit_rate,
+        average_auth_time_ms = stats.average_auth_time_ms,
+        memory_utilization = stats.memory_utilization,
+        "Performance optimization integration ready"
+    );
     
     Ok(())
 }
@@ -498,48 +481,38 @@ pub async fn integrate_with_agent_tool(
 #[cfg(test)]
 mod tests {
     use super::*;
-    use std::path::PathBuf;
-    use crate::unified_auth::ProviderSelectionStrategy;
+    use crate::auth::ProviderSelectionStrategy;
+    use tempfile::TempDir;
 
-    // Mock implementations for testing
-    async fn create_test_unified_auth() -> Arc<UnifiedAuthManager> {
-        let codex_home = PathBuf::from("/tmp/test_codex");
+    async fn create_test_unified_auth(codex_home: &TempDir) -> Arc<UnifiedAuthManager> {
         let auth_manager = UnifiedAuthManager::new(
-            codex_home,
-            crate::auth::AuthMode::ApiKey,
-            "test".to_string(),
+            codex_home.path().to_path_buf(),
             ProviderSelectionStrategy::PreferClaude,
-        );
+        )
+        .await
+        .unwrap();
         Arc::new(auth_manager)
     }
 
-    async fn create_test_agent_coordinator() -> Arc<AgentAuthCoordinator> {
-        // This would create a test instance of AgentAuthCoordinator
-        // For now, we'll skip this test due to complex dependencies
-        unimplemented!("Test requires mock AgentAuthCoordinator")
-    }
-
     #[tokio::test]
-    #[ignore] // Ignore until mock implementations are ready
     async fn test_optimized_auth_manager_creation() {
-        let unified_auth = create_test_unified_auth().await;
-        let agent_coordinator = create_test_agent_coordinator().await;
+        let codex_home = TempDir::new().unwrap();
+        let unified_auth = create_test_unified_auth(&codex_home).await;
         let config = OptimizationConfig::default();
         
-        let manager = OptimizedAuthManager::new(unified_auth, agent_coordinator, config).await;
+        let manager = OptimizedAuthManager::new(unified_auth, config).await;
         
         let stats = manager.get_performance_statistics().await;
         assert!(stats.overall_score >= 0.0);
     }
 
     #[tokio::test]
-    #[ignore]
     async fn test_performance_dashboard() {
-        let unified_auth = create_test_unified_auth().await;
-        let agent_coordinator = create_test_agent_coordinator().await;
+        let codex_home = TempDir::new().unwrap();
+        let unified_auth = create_test_unified_auth(&codex_home).await;
         let config = OptimizationConfig::default();
         
-        let manager = OptimizedAuthManager::new(unified_auth, agent_coordinator, config).await;
+        let manager = OptimizedAuthManager::new(unified_auth, config).await;
         
         let dashboard = manager.get_performance_dashboard().await;
         assert!(dashboard.is_object());
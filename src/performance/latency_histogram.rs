@@ -0,0 +1,131 @@
+// Bucketed latency histogram for percentile tracking
+// Approximates tail latency with bounded memory and ~5% relative error
+
+use std::time::Duration;
+use serde::{Serialize, Deserialize};
+
+/// Smallest tracked latency; anything faster lands in the first bucket
+const MIN_TRACKED_MICROS: f64 = 100.0;
+/// Largest tracked latency; anything slower lands in the overflow bucket
+const MAX_TRACKED_MICROS: f64 = 60_000_000.0;
+/// Geometric growth between bucket upper bounds
+const BUCKET_GROWTH: f64 = 1.05;
+
+/// Latency percentiles for a single operation type
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct LatencyPercentiles {
+    pub p50: Duration,
+    pub p90: Duration,
+    pub p95: Duration,
+    pub p99: Duration,
+}
+
+/// Log-bucketed histogram of operation latencies
+#[derive(Debug, Clone)]
+pub struct LatencyHistogram {
+    buckets: Vec<u64>,
+    total: u64,
+    max_micros: u64,
+}
+
+impl LatencyHistogram {
+    /// Create an empty histogram covering 100µs to 60s
+    pub fn new() -> Self {
+        let bucket_count = (MAX_TRACKED_MICROS / MIN_TRACKED_MICROS).ln() / BUCKET_GROWTH.ln();
+        Self {
+            buckets: vec![0; bucket_count.ceil() as usize + 2],
+            total: 0,
+            max_micros: 0,
+        }
+    }
+
+    /// Record a single latency sample
+    pub fn record(&mut self, latency: Duration) {
+        let micros = latency.as_micros().min(u64::MAX as u128) as u64;
+        let index = self.bucket_index(micros);
+        self.buckets[index] += 1;
+        self.total += 1;
+        self.max_micros = self.max_micros.max(micros);
+    }
+
+    /// Number of recorded samples
+    pub fn count(&self) -> u64 {
+        self.total
+    }
+
+    /// Approximate latency at the given quantile (0.0..=1.0)
+    pub fn value_at_quantile(&self, quantile: f64) -> Duration {
+        if self.total == 0 {
+            return Duration::ZERO;
+        }
+
+        let rank = ((quantile.clamp(0.0, 1.0) * self.total as f64).ceil() as u64).max(1);
+        let mut seen = 0;
+        for (index, count) in self.buckets.iter().enumerate() {
+            seen += count;
+            if seen >= rank {
+                // Never report more than the slowest sample actually observed
+                let upper = self.bucket_upper_bound(index).min(self.max_micros);
+                return Duration::from_micros(upper);
+            }
+        }
+
+        Duration::from_micros(self.max_micros)
+    }
+
+    /// Summarize the histogram as p50/p90/p95/p99
+    pub fn percentiles(&self) -> LatencyPercentiles {
+        LatencyPercentiles {
+            p50: self.value_at_quantile(0.50),
+            p90: self.value_at_quantile(0.90),
+            p95: self.value_at_quantile(0.95),
+            p99: self.value_at_quantile(0.99),
+        }
+    }
+
+    fn bucket_index(&self, micros: u64) -> usize {
+        let micros = micros as f64;
+        if micros <= MIN_TRACKED_MICROS {
+            return 0;
+        }
+        let index = ((micros / MIN_TRACKED_MICROS).ln() / BUCKET_GROWTH.ln()).ceil() as usize;
+        index.min(self.buckets.len() - 1)
+    }
+
+    fn bucket_upper_bound(&self, index: usize) -> u64 {
+        if index == self.buckets.len() - 1 {
+            return self.max_micros;
+        }
+        (MIN_TRACKED_MICROS * BUCKET_GROWTH.powi(index as i32)).ceil() as u64
+    }
+}
+
+impl Default for LatencyHistogram {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_empty_histogram_reports_zero() {
+        let histogram = LatencyHistogram::new();
+        assert_eq!(histogram.percentiles(), LatencyPercentiles::default());
+    }
+
+    #[test]
+    fn test_quantiles_within_bucket_precision() {
+        let mut histogram = LatencyHistogram::new();
+        for ms in 1..=100 {
+            histogram.record(Duration::from_millis(ms));
+        }
+
+        let p50 = histogram.value_at_quantile(0.50).as_secs_f64() * 1000.0;
+        let p99 = histogram.value_at_quantile(0.99).as_secs_f64() * 1000.0;
+        assert!((50.0..=52.5).contains(&p50), "p50 was {}ms", p50);
+        assert!((99.0..=100.0).contains(&p99), "p99 was {}ms", p99);
+    }
+}
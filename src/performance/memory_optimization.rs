@@ -3,6 +3,7 @@
 
 use std::collections::{HashMap, BTreeMap};
 use std::sync::{Arc, Weak};
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::time::{Duration, Instant, SystemTime};
 use tokio::sync::RwLock;
 use serde::{Serialize, Deserialize};
@@ -33,6 +34,7 @@ pub struct MemoryConfig {
     pub gc_interval_minutes: u64,
     pub agent_session_timeout_minutes: u64,
     pub weak_reference_cleanup_minutes: u64,
+    pub agent_admission_budget_mb: u64,
 }
 
 impl Default for MemoryConfig {
@@ -45,6 +47,7 @@ impl Default for MemoryConfig {
             gc_interval_minutes: 5,                // GC every 5 minutes
             agent_session_timeout_minutes: 30,     // Session timeout 30 minutes
             weak_reference_cleanup_minutes: 10,    // Cleanup weak refs every 10 minutes
+            agent_admission_budget_mb: 500,        // Reserve at most 500MB for admitted agents
         }
     }
 }
@@ -164,6 +167,27 @@ pub struct MemoryOptimizer {
     memory_pool: Arc<RwLock<MemoryPool>>,
     stats: Arc<RwLock<MemoryStats>>,
     last_gc: Arc<RwLock<Instant>>,
+    admitted_bytes: Arc<AtomicU64>,
+}
+
+/// Memory reserved for an admitted agent; the reservation is returned on drop
+#[derive(Debug)]
+pub struct AgentMemoryLease {
+    reserved_bytes: u64,
+    admitted_bytes: Arc<AtomicU64>,
+}
+
+impl AgentMemoryLease {
+    /// Bytes held by this lease
+    pub fn reserved_bytes(&self) -> u64 {
+        self.reserved_bytes
+    }
+}
+
+impl Drop for AgentMemoryLease {
+    fn drop(&mut self) {
+        self.admitted_bytes.fetch_sub(self.reserved_bytes, Ordering::AcqRel);
+    }
 }
 
 impl MemoryOptimizer {
@@ -190,9 +214,44 @@ impl MemoryOptimizer {
                 last_gc_duration_ms: 0,
             })),
             last_gc: Arc::new(RwLock::new(Instant::now())),
+            admitted_bytes: Arc::new(AtomicU64::new(0)),
         }
     }
 
+    /// Reserve memory for a new agent, refusing admission when the budget has no headroom
+    ///
+    /// `AdmissionDenied` means the caller should queue the agent and retry once a lease is dropped.
+    pub fn try_admit_agent(&self, estimated_mb: u64) -> Result<AgentMemoryLease, MemoryError> {
+        let requested = estimated_mb * 1024 * 1024;
+        let session_limit_bytes = self.config.session_memory_limit_mb * 1024 * 1024;
+        if requested > session_limit_bytes {
+            return Err(MemoryError::SessionLimitExceeded {
+                requested,
+                limit: session_limit_bytes,
+            });
+        }
+
+        let budget = self.config.agent_admission_budget_mb * 1024 * 1024;
+        self.admitted_bytes
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |admitted| {
+                admitted.checked_add(requested).filter(|total| *total <= budget)
+            })
+            .map_err(|admitted| MemoryError::AdmissionDenied {
+                requested,
+                available: budget.saturating_sub(admitted),
+            })?;
+
+        Ok(AgentMemoryLease {
+            reserved_bytes: requested,
+            admitted_bytes: Arc::clone(&self.admitted_bytes),
+        })
+    }
+
+    /// Bytes currently reserved by outstanding agent leases
+    pub fn admitted_bytes(&self) -> u64 {
+        self.admitted_bytes.load(Ordering::Acquire)
+    }
+
     /// Allocate memory for a new agent session
     pub async fn allocate_agent_session(
         &self,
@@ -533,6 +592,7 @@ impl Clone for MemoryOptimizer {
             memory_pool: Arc::clone(&self.memory_pool),
             stats: Arc::clone(&self.stats),
             last_gc: Arc::clone(&self.last_gc),
+            admitted_bytes: Arc::clone(&self.admitted_bytes),
         }
     }
 }
@@ -556,6 +616,9 @@ pub enum MemoryError {
     
     #[error("Session not found: {0}")]
     SessionNotFound(String),
+
+    #[error("Agent admission denied: requested {requested} bytes, {available} bytes left in budget")]
+    AdmissionDenied { requested: u64, available: u64 },
 }
 
 /// Garbage collection result
@@ -724,4 +787,35 @@ mod tests {
         assert!(health_report.memory_utilization > 0.0);
         assert!(!health_report.recommendations.is_empty());
     }
-}
\ No newline at end of file
+
+    #[tokio::test]
+    async fn test_agent_admission_budget() {
+        let config = MemoryConfig {
+            agent_admission_budget_mb: 100,
+            ..Default::default()
+        };
+        let optimizer = MemoryOptimizer::with_config(config);
+
+        let mut leases: Vec<_> = (0..2)
+            .map(|_| optimizer.try_admit_agent(50).unwrap())
+            .collect();
+        assert_eq!(optimizer.admitted_bytes(), 100 * 1024 * 1024);
+
+        let rejected = optimizer.try_admit_agent(50);
+        assert!(matches!(rejected, Err(MemoryError::AdmissionDenied { available: 0, .. })));
+
+        // Dropping a lease frees room for the next agent
+        drop(leases.pop());
+        assert_eq!(optimizer.admitted_bytes(), 50 * 1024 * 1024);
+        let lease = optimizer.try_admit_agent(50).unwrap();
+        assert_eq!(lease.reserved_bytes(), 50 * 1024 * 1024);
+    }
+
+    #[tokio::test]
+    async fn test_agent_admission_respects_session_limit() {
+        let optimizer = MemoryOptimizer::new();
+        let result = optimizer.try_admit_agent(80); // Exceeds 50MB per-session limit
+        assert!(matches!(result, Err(MemoryError::SessionLimitExceeded { .. })));
+        assert_eq!(optimizer.admitted_bytes(), 0);
+    }
+}
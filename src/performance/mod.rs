@@ -4,9 +4,20 @@
 pub mod authentication_cache;
 pub mod token_optimization;
 pub mod connection_pool;
+pub mod http_logging;
 pub mod memory_optimization;
 pub mod bottleneck_analyzer;
 pub mod performance_monitor;
+pub mod prometheus;
+pub mod latency_histogram;
+pub mod request_metrics;
+pub mod benchmarks;
+pub mod integration;
+pub mod export;
+
+pub use export::{ExportFormat, MetricsRecord};
+pub use latency_histogram::LatencyPercentiles;
+pub use request_metrics::{RequestLabel, RequestStats};
 
 use std::time::{Duration, Instant};
 use std::collections::HashMap;
@@ -27,6 +38,7 @@ pub struct PerformanceMetrics {
 }
 
 /// Performance targets from the integration plan
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PerformanceTargets {
     pub authentication_cache_ms: u128,  // Target: < 100ms
     pub token_refresh_ms: u128,         // Target: optimized batching
@@ -45,6 +57,9 @@ impl Default for PerformanceTargets {
     }
 }
 
+/// Number of recent operations judged against the performance targets
+pub const TARGET_WINDOW: usize = 50;
+
 /// Central performance monitoring and optimization coordinator
 #[derive(Debug)]
 pub struct PerformanceCoordinator {
@@ -54,6 +69,17 @@ pub struct PerformanceCoordinator {
     connection_pool: Arc<connection_pool::ClaudeConnectionPool>,
     memory_optimizer: Arc<memory_optimization::MemoryOptimizer>,
     bottleneck_analyzer: bottleneck_analyzer::BottleneckAnalyzer,
+    auth_outcomes: Arc<RwLock<HashMap<String, AuthOutcomeCounts>>>,
+    auth_latency: Arc<RwLock<latency_histogram::LatencyHistogram>>,
+    refresh_latency: Arc<RwLock<latency_histogram::LatencyHistogram>>,
+    request_metrics: Arc<RwLock<request_metrics::RequestMetrics>>,
+}
+
+/// Authentication success/failure counters for a single provider
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AuthOutcomeCounts {
+    pub successes: u64,
+    pub failures: u64,
 }
 
 impl PerformanceCoordinator {
@@ -66,9 +92,29 @@ impl PerformanceCoordinator {
             connection_pool: Arc::new(connection_pool::ClaudeConnectionPool::new()),
             memory_optimizer: Arc::new(memory_optimization::MemoryOptimizer::new()),
             bottleneck_analyzer: bottleneck_analyzer::BottleneckAnalyzer::new(),
+            auth_outcomes: Arc::new(RwLock::new(HashMap::new())),
+            auth_latency: Arc::new(RwLock::new(latency_histogram::LatencyHistogram::new())),
+            refresh_latency: Arc::new(RwLock::new(latency_histogram::LatencyHistogram::new())),
+            request_metrics: Arc::new(RwLock::new(request_metrics::RequestMetrics::new())),
+        }
+    }
+
+    /// Count an authentication attempt against a provider
+    pub async fn record_auth_outcome(&self, provider: &str, success: bool) {
+        let mut outcomes_guard = self.auth_outcomes.write().await;
+        let counts = outcomes_guard.entry(provider.to_string()).or_default();
+        if success {
+            counts.successes += 1;
+        } else {
+            counts.failures += 1;
         }
     }
 
+    /// Get authentication outcome counters keyed by provider
+    pub async fn get_auth_outcomes(&self) -> HashMap<String, AuthOutcomeCounts> {
+        self.auth_outcomes.read().await.clone()
+    }
+
     /// Record performance metrics for an operation
     pub async fn record_metrics(&self, metrics: PerformanceMetrics) {
         let mut metrics_guard = self.metrics.write().await;
@@ -78,6 +124,15 @@ impl PerformanceCoordinator {
         if metrics_guard.len() > 1000 {
             metrics_guard.drain(0..100);
         }
+        drop(metrics_guard);
+
+        // Zero durations mean the operation did not run (see time_operation!)
+        if !metrics.authentication_time.is_zero() {
+            self.auth_latency.write().await.record(metrics.authentication_time);
+        }
+        if !metrics.token_refresh_time.is_zero() {
+            self.refresh_latency.write().await.record(metrics.token_refresh_time);
+        }
 
         // Analyze for bottlenecks
         self.bottleneck_analyzer.analyze_metrics(&metrics).await;
@@ -137,13 +192,41 @@ impl PerformanceCoordinator {
         })
     }
 
+    /// Get authentication latency percentiles across all recorded operations
+    pub async fn get_latency_percentiles(&self) -> LatencyPercentiles {
+        self.auth_latency.read().await.percentiles()
+    }
+
+    /// Get token refresh latency percentiles across all recorded operations
+    pub async fn get_refresh_latency_percentiles(&self) -> LatencyPercentiles {
+        self.refresh_latency.read().await.percentiles()
+    }
+
+    /// Get authentication latency percentiles over the last `last_n` operations
+    pub async fn get_recent_latency_percentiles(&self, last_n: usize) -> LatencyPercentiles {
+        let mut histogram = latency_histogram::LatencyHistogram::new();
+        let metrics_guard = self.metrics.read().await;
+        metrics_guard
+            .iter()
+            .rev()
+            .take(last_n)
+            .filter(|m| !m.authentication_time.is_zero())
+            .for_each(|m| histogram.record(m.authentication_time));
+        histogram.percentiles()
+    }
+
     /// Check if current performance meets targets
+    ///
+    /// The mean and the p95 tail both cover the last [`TARGET_WINDOW`] operations.
     pub async fn meets_performance_targets(&self) -> PerformanceReport {
-        let recent_perf = self.get_average_performance(50).await;
+        let recent_perf = self.get_average_performance(TARGET_WINDOW).await;
         
         match recent_perf {
             Some(metrics) => {
-                let auth_meets_target = metrics.authentication_time.as_millis() <= self.targets.authentication_cache_ms;
+                let latency_percentiles = self.get_recent_latency_percentiles(TARGET_WINDOW).await;
+                // Both the mean and the p95 tail must stay under the auth target
+                let auth_meets_target = metrics.authentication_time.as_millis() <= self.targets.authentication_cache_ms
+                    && latency_percentiles.p95.as_millis() <= self.targets.authentication_cache_ms;
                 let refresh_meets_target = metrics.token_refresh_time.as_millis() <= self.targets.token_refresh_ms;
                 let memory_meets_target = metrics.memory_usage <= self.targets.memory_usage_mb * 1024 * 1024;
                 let agents_meets_target = metrics.concurrent_agents <= self.targets.concurrent_agents;
@@ -155,6 +238,7 @@ impl PerformanceCoordinator {
                     memory_performance: if memory_meets_target { "✅ MEETS TARGET" } else { "❌ EXCEEDS TARGET" }.to_string(),
                     concurrency_performance: if agents_meets_target { "✅ MEETS TARGET" } else { "❌ EXCEEDS TARGET" }.to_string(),
                     current_metrics: metrics,
+                    latency_percentiles,
                     targets: self.targets.clone(),
                     recommendations: self.bottleneck_analyzer.get_recommendations().await,
                 }
@@ -188,6 +272,7 @@ pub struct PerformanceReport {
     pub memory_performance: String,
     pub concurrency_performance: String,
     pub current_metrics: PerformanceMetrics,
+    pub latency_percentiles: LatencyPercentiles,
     pub targets: PerformanceTargets,
     pub recommendations: Vec<String>,
 }
@@ -209,6 +294,7 @@ impl PerformanceReport {
                 network_requests: 0,
                 timestamp: std::time::SystemTime::now(),
             },
+            latency_percentiles: LatencyPercentiles::default(),
             targets: PerformanceTargets::default(),
             recommendations: vec!["Start authentication operations to collect performance data".to_string()],
         }
@@ -296,4 +382,74 @@ mod tests {
         assert_eq!(report.overall_score, 100.0);
         assert!(report.authentication_performance.contains("MEETS TARGET"));
     }
+
+    fn auth_metrics(auth_ms: u64) -> PerformanceMetrics {
+        PerformanceMetrics {
+            authentication_time: Duration::from_millis(auth_ms),
+            token_refresh_time: Duration::from_millis(0),
+            cache_hit_rate: 0.9,
+            memory_usage: 10 * 1024 * 1024,
+            concurrent_agents: 1,
+            network_requests: 1,
+            timestamp: std::time::SystemTime::now(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_latency_percentiles_capture_slow_tail() {
+        let coordinator = PerformanceCoordinator::new();
+
+        // 95 fast operations and 5 slow ones
+        for _ in 0..95 {
+            coordinator.record_metrics(auth_metrics(10)).await;
+        }
+        for _ in 0..5 {
+            coordinator.record_metrics(auth_metrics(900)).await;
+        }
+
+        let avg = coordinator.get_average_performance(100).await.unwrap();
+        assert!(avg.authentication_time.as_millis() < 100);
+
+        let percentiles = coordinator.get_latency_percentiles().await;
+        assert!(percentiles.p50.as_millis() <= 11);
+        assert!(percentiles.p99.as_millis() >= 850);
+        assert_eq!(coordinator.get_refresh_latency_percentiles().await, LatencyPercentiles::default());
+    }
+
+    #[tokio::test]
+    async fn test_performance_targets_fail_on_p95_tail() {
+        let coordinator = PerformanceCoordinator::new();
+
+        // Mean stays under 100ms but the slowest 10% push p95 far above it
+        for _ in 0..45 {
+            coordinator.record_metrics(auth_metrics(10)).await;
+        }
+        for _ in 0..5 {
+            coordinator.record_metrics(auth_metrics(900)).await;
+        }
+
+        let report = coordinator.meets_performance_targets().await;
+        assert!(report.current_metrics.authentication_time.as_millis() <= 100);
+        assert!(report.latency_percentiles.p95.as_millis() > 100);
+        assert!(report.authentication_performance.contains("EXCEEDS TARGET"));
+        assert_eq!(report.overall_score, 75.0);
+    }
+
+    #[tokio::test]
+    async fn test_performance_targets_judge_only_the_recent_tail() {
+        let coordinator = PerformanceCoordinator::new();
+
+        // A slow start followed by a full window of fast operations
+        for _ in 0..TARGET_WINDOW {
+            coordinator.record_metrics(auth_metrics(900)).await;
+        }
+        for _ in 0..TARGET_WINDOW {
+            coordinator.record_metrics(auth_metrics(10)).await;
+        }
+
+        assert!(coordinator.get_latency_percentiles().await.p95.as_millis() > 100);
+        let report = coordinator.meets_performance_targets().await;
+        assert!(report.latency_percentiles.p95.as_millis() <= 11);
+        assert!(report.authentication_performance.contains("MEETS TARGET"));
+    }
 }
\ No newline at end of file
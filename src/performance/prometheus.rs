@@ -0,0 +1,251 @@
+// Prometheus exporter for authentication performance metrics
+// Renders PerformanceCoordinator state in the Prometheus text exposition format
+
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+
+use super::PerformanceCoordinator;
+
+/// Number of recent samples averaged into the exported gauges
+const EXPORT_WINDOW: usize = 50;
+
+impl PerformanceCoordinator {
+    /// Render current performance averages and auth outcome counters for a `/metrics` scrape
+    pub async fn render_prometheus(&self) -> String {
+        let mut writer = ExpositionWriter::default();
+
+        if let Some(metrics) = self.get_average_performance(EXPORT_WINDOW).await {
+            writer.gauge(
+                "code_auth_duration_seconds",
+                "Average authentication time over recent operations",
+                metrics.authentication_time.as_secs_f64(),
+            );
+            writer.gauge(
+                "code_token_refresh_duration_seconds",
+                "Average token refresh time over recent operations",
+                metrics.token_refresh_time.as_secs_f64(),
+            );
+            writer.gauge(
+                "code_auth_cache_hit_ratio",
+                "Authentication cache hit rate between 0 and 1",
+                metrics.cache_hit_rate,
+            );
+            writer.gauge(
+                "code_memory_usage_bytes",
+                "Average memory used by authentication sessions",
+                metrics.memory_usage as f64,
+            );
+            writer.gauge(
+                "code_concurrent_agents",
+                "Average number of concurrently authenticated agents",
+                metrics.concurrent_agents as f64,
+            );
+            writer.gauge(
+                "code_network_requests",
+                "Network requests made across recent operations",
+                metrics.network_requests as f64,
+            );
+        }
+
+        let outcomes: BTreeMap<_, _> = self.get_auth_outcomes().await.into_iter().collect();
+        writer.header(
+            "code_auth_attempts_total",
+            "Authentication attempts by provider and outcome",
+            "counter",
+        );
+        for (provider, counts) in &outcomes {
+            writer.sample(
+                "code_auth_attempts_total",
+                &[("provider", provider), ("outcome", "success")],
+                counts.successes as f64,
+            );
+            writer.sample(
+                "code_auth_attempts_total",
+                &[("provider", provider), ("outcome", "failure")],
+                counts.failures as f64,
+            );
+        }
+
+        let mut requests: Vec<_> = self.metrics_by_label().await.into_iter().collect();
+        requests.sort_by(|(a, _), (b, _)| (a.provider.id(), &a.task_type).cmp(&(b.provider.id(), &b.task_type)));
+        writer.header(
+            "code_provider_requests_total",
+            "Provider requests by task type and outcome",
+            "counter",
+        );
+        for (label, stats) in &requests {
+            for (outcome, count) in [("success", stats.successes), ("failure", stats.failures)] {
+                writer.sample(
+                    "code_provider_requests_total",
+                    &[("provider", label.provider.id()), ("task_type", &label.task_type), ("outcome", outcome)],
+                    count as f64,
+                );
+            }
+        }
+        writer.header(
+            "code_provider_request_latency_seconds",
+            "Provider request latency percentiles by task type",
+            "gauge",
+        );
+        for (label, stats) in &requests {
+            for (quantile, latency) in [("0.5", stats.latency.p50), ("0.95", stats.latency.p95), ("0.99", stats.latency.p99)] {
+                writer.sample(
+                    "code_provider_request_latency_seconds",
+                    &[("provider", label.provider.id()), ("task_type", &label.task_type), ("quantile", quantile)],
+                    latency.as_secs_f64(),
+                );
+            }
+        }
+
+        writer.finish()
+    }
+}
+
+/// Minimal writer for the Prometheus text exposition format (version 0.0.4)
+#[derive(Default)]
+struct ExpositionWriter {
+    out: String,
+}
+
+impl ExpositionWriter {
+    fn header(&mut self, name: &str, help: &str, kind: &str) {
+        let _ = writeln!(self.out, "# HELP {} {}", name, escape_help(help));
+        let _ = writeln!(self.out, "# TYPE {} {}", name, kind);
+    }
+
+    fn sample(&mut self, name: &str, labels: &[(&str, &str)], value: f64) {
+        self.out.push_str(name);
+        if !labels.is_empty() {
+            let rendered: Vec<String> = labels
+                .iter()
+                .map(|(key, value)| format!("{}=\"{}\"", key, escape_label_value(value)))
+                .collect();
+            let _ = write!(self.out, "{{{}}}", rendered.join(","));
+        }
+        let _ = writeln!(self.out, " {}", format_value(value));
+    }
+
+    fn gauge(&mut self, name: &str, help: &str, value: f64) {
+        self.header(name, help, "gauge");
+        self.sample(name, &[], value);
+    }
+
+    fn finish(self) -> String {
+        self.out
+    }
+}
+
+fn escape_help(help: &str) -> String {
+    help.replace('\\', "\\\\").replace('\n', "\\n")
+}
+
+fn escape_label_value(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+fn format_value(value: f64) -> String {
+    if value.is_nan() {
+        "NaN".to_string()
+    } else if value.is_infinite() {
+        if value > 0.0 { "+Inf" } else { "-Inf" }.to_string()
+    } else {
+        value.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::configuration::ProviderType;
+    use crate::performance::PerformanceMetrics;
+    use std::collections::HashSet;
+    use std::time::Duration;
+
+    /// Check every line is a HELP/TYPE comment or a sample of a declared metric family
+    fn assert_valid_exposition(output: &str) -> HashSet<String> {
+        let mut declared = HashSet::new();
+        let mut sampled = HashSet::new();
+
+        for line in output.lines() {
+            if let Some(rest) = line.strip_prefix("# HELP ") {
+                let name = rest.split_whitespace().next().expect("HELP without metric name");
+                assert!(is_valid_metric_name(name), "invalid metric name in {:?}", line);
+            } else if let Some(rest) = line.strip_prefix("# TYPE ") {
+                let mut parts = rest.split_whitespace();
+                let name = parts.next().expect("TYPE without metric name");
+                let kind = parts.next().expect("TYPE without kind");
+                assert!(matches!(kind, "counter" | "gauge" | "histogram" | "summary" | "untyped"));
+                assert!(declared.insert(name.to_string()), "duplicate TYPE for {}", name);
+            } else {
+                let (series, value) = line.rsplit_once(' ').expect("sample without value");
+                let name = series.split('{').next().unwrap();
+                assert!(declared.contains(name), "sample {} before its TYPE line", name);
+                if let Some(labels) = series.strip_prefix(name) {
+                    if !labels.is_empty() {
+                        assert!(labels.starts_with('{') && labels.ends_with('}'), "bad labels in {:?}", line);
+                    }
+                }
+                assert!(value.parse::<f64>().is_ok() || matches!(value, "NaN" | "+Inf" | "-Inf"));
+                sampled.insert(name.to_string());
+            }
+        }
+
+        sampled
+    }
+
+    fn is_valid_metric_name(name: &str) -> bool {
+        let mut chars = name.chars();
+        matches!(chars.next(), Some(c) if c.is_ascii_alphabetic() || c == '_' || c == ':')
+            && chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == ':')
+    }
+
+    #[tokio::test]
+    async fn test_render_prometheus_exposition() {
+        let coordinator = PerformanceCoordinator::new();
+
+        coordinator.record_metrics(PerformanceMetrics {
+            authentication_time: Duration::from_millis(40),
+            token_refresh_time: Duration::from_millis(250),
+            cache_hit_rate: 0.8,
+            memory_usage: 10 * 1024 * 1024,
+            concurrent_agents: 3,
+            network_requests: 2,
+            timestamp: std::time::SystemTime::now(),
+        }).await;
+        coordinator.record_auth_outcome("claude", true).await;
+        coordinator.record_auth_outcome("claude", false).await;
+        coordinator.record_auth_outcome("openai", true).await;
+        coordinator.record_request(ProviderType::Claude, "Analysis", true, Duration::from_millis(120)).await;
+
+        let output = coordinator.render_prometheus().await;
+        let sampled = assert_valid_exposition(&output);
+
+        for name in [
+            "code_auth_duration_seconds",
+            "code_token_refresh_duration_seconds",
+            "code_auth_cache_hit_ratio",
+            "code_memory_usage_bytes",
+            "code_concurrent_agents",
+            "code_network_requests",
+            "code_auth_attempts_total",
+            "code_provider_requests_total",
+            "code_provider_request_latency_seconds",
+        ] {
+            assert!(sampled.contains(name), "missing metric {}", name);
+        }
+
+        assert!(output.contains("code_auth_attempts_total{provider=\"claude\",outcome=\"failure\"} 1"));
+        assert!(output.contains("code_auth_attempts_total{provider=\"openai\",outcome=\"success\"} 1"));
+        assert!(output.contains(
+            "code_provider_requests_total{provider=\"claude\",task_type=\"Analysis\",outcome=\"success\"} 1"
+        ));
+    }
+
+    #[test]
+    fn test_label_values_are_escaped() {
+        assert_eq!(escape_label_value("a\"b\\c\nd"), "a\\\"b\\\\c\\nd");
+    }
+}
@@ -0,0 +1,143 @@
+// Per-provider request metrics labeled by task type
+// Single store behind PerformanceCoordinator::metrics_by_label and the labeled Prometheus series
+
+use std::collections::HashMap;
+use std::time::Duration;
+use serde::{Serialize, Deserialize};
+
+use super::latency_histogram::{LatencyHistogram, LatencyPercentiles};
+use super::PerformanceCoordinator;
+use crate::configuration::ProviderType;
+
+/// Provider and task type a request is counted under
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct RequestLabel {
+    pub provider: ProviderType,
+    /// Task type name, e.g. `CodeGeneration`
+    pub task_type: String,
+}
+
+/// Request counts, success rate and latency for one label, or for all requests
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct RequestStats {
+    pub requests: u64,
+    pub successes: u64,
+    pub failures: u64,
+    /// Share of requests that succeeded, 0 when nothing was recorded
+    pub success_rate: f64,
+    pub latency: LatencyPercentiles,
+}
+
+/// Counters and latency histogram for a single series
+#[derive(Debug, Clone)]
+struct RequestSeries {
+    successes: u64,
+    failures: u64,
+    latency: LatencyHistogram,
+}
+
+impl RequestSeries {
+    fn new() -> Self {
+        Self { successes: 0, failures: 0, latency: LatencyHistogram::new() }
+    }
+
+    fn record(&mut self, success: bool, latency: Duration) {
+        if success {
+            self.successes += 1;
+        } else {
+            self.failures += 1;
+        }
+        self.latency.record(latency);
+    }
+
+    fn stats(&self) -> RequestStats {
+        let requests = self.successes + self.failures;
+        RequestStats {
+            requests,
+            successes: self.successes,
+            failures: self.failures,
+            success_rate: if requests == 0 { 0.0 } else { self.successes as f64 / requests as f64 },
+            latency: self.latency.percentiles(),
+        }
+    }
+}
+
+/// Labeled series plus the unlabeled total, updated together
+#[derive(Debug)]
+pub(crate) struct RequestMetrics {
+    by_label: HashMap<RequestLabel, RequestSeries>,
+    total: RequestSeries,
+}
+
+impl RequestMetrics {
+    pub(crate) fn new() -> Self {
+        Self { by_label: HashMap::new(), total: RequestSeries::new() }
+    }
+}
+
+impl PerformanceCoordinator {
+    /// Count a completed provider request under its provider and task type
+    pub async fn record_request(&self, provider: ProviderType, task_type: &str, success: bool, latency: Duration) {
+        let mut metrics = self.request_metrics.write().await;
+        let label = RequestLabel { provider, task_type: task_type.to_string() };
+        metrics.by_label.entry(label).or_insert_with(RequestSeries::new).record(success, latency);
+        metrics.total.record(success, latency);
+    }
+
+    /// Request stats for every provider and task type combination seen so far
+    pub async fn metrics_by_label(&self) -> HashMap<RequestLabel, RequestStats> {
+        self.request_metrics
+            .read()
+            .await
+            .by_label
+            .iter()
+            .map(|(label, series)| (label.clone(), series.stats()))
+            .collect()
+    }
+
+    /// Request stats across all providers and task types
+    pub async fn request_totals(&self) -> RequestStats {
+        self.request_metrics.read().await.total.stats()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_label_breakdown_sums_to_totals() {
+        let coordinator = PerformanceCoordinator::new();
+        let requests = [
+            (ProviderType::OpenAI, "CodeGeneration", true, 100),
+            (ProviderType::OpenAI, "CodeGeneration", false, 300),
+            (ProviderType::OpenAI, "Analysis", true, 50),
+            (ProviderType::Claude, "CodeGeneration", true, 200),
+            (ProviderType::Claude, "Batch", false, 1_000),
+        ];
+        for (provider, task_type, success, millis) in requests {
+            coordinator.record_request(provider, task_type, success, Duration::from_millis(millis)).await;
+        }
+
+        let breakdown = coordinator.metrics_by_label().await;
+        let totals = coordinator.request_totals().await;
+
+        assert_eq!(breakdown.len(), 4);
+        assert_eq!(totals.requests, 5);
+        assert_eq!(breakdown.values().map(|stats| stats.requests).sum::<u64>(), totals.requests);
+        assert_eq!(breakdown.values().map(|stats| stats.successes).sum::<u64>(), totals.successes);
+        assert_eq!(breakdown.values().map(|stats| stats.failures).sum::<u64>(), totals.failures);
+
+        let openai_codegen = &breakdown[&RequestLabel {
+            provider: ProviderType::OpenAI,
+            task_type: "CodeGeneration".to_string(),
+        }];
+        assert_eq!(openai_codegen.requests, 2);
+        assert_eq!(openai_codegen.success_rate, 0.5);
+
+        // The slowest request dominates both its own series and the total
+        let slowest = breakdown.values().map(|stats| stats.latency.p99).max().unwrap();
+        assert_eq!(slowest, totals.latency.p99);
+        assert_eq!(totals.latency.p99, Duration::from_secs(1));
+    }
+}
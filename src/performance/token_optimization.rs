@@ -2,9 +2,10 @@
 // Implements batching and intelligent refresh strategies
 
 use std::collections::{HashMap, VecDeque};
+use std::future::Future;
 use std::time::{Duration, Instant};
 use std::sync::Arc;
-use tokio::sync::{RwLock, Mutex, Semaphore};
+use tokio::sync::{RwLock, Mutex, Semaphore, OnceCell};
 use tokio::time::{sleep, interval};
 use chrono::{DateTime, Utc};
 use serde::{Serialize, Deserialize};
@@ -86,6 +87,8 @@ pub struct TokenOptimizer {
     results: Arc<RwLock<HashMap<String, TokenRefreshResult>>>,
     stats: Arc<RwLock<RefreshStats>>,
     batch_semaphore: Arc<Semaphore>,
+    /// In-flight coalesced refreshes keyed by credential id
+    coalesced: Arc<Mutex<HashMap<String, Arc<OnceCell<TokenRefreshResult>>>>>,
     client: reqwest::Client,
 }
 
@@ -114,6 +117,7 @@ impl TokenOptimizer {
                 concurrent_batches: 0,
             })),
             batch_semaphore,
+            coalesced: Arc::new(Mutex::new(HashMap::new())),
             client: reqwest::Client::new(),
         }
     }
@@ -166,6 +170,60 @@ impl TokenOptimizer {
         request_id
     }
 
+    /// Refresh a credential, coalescing concurrent callers into a single in-flight refresh
+    ///
+    /// The first caller for `credential_id` runs `refresh_fn`; everyone who arrives while it is
+    /// still running waits on the same refresh and receives a clone of its result. Once the
+    /// refresh completes the slot is released, so a later expiry triggers a fresh refresh.
+    pub async fn refresh_coalesced<F, Fut>(&self, credential_id: &str, refresh_fn: F) -> TokenRefreshResult
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = TokenRefreshResult>,
+    {
+        let cell = {
+            let mut coalesced_guard = self.coalesced.lock().await;
+            Arc::clone(
+                coalesced_guard
+                    .entry(credential_id.to_string())
+                    .or_insert_with(|| Arc::new(OnceCell::new())),
+            )
+        };
+
+        let mut ran_refresh = false;
+        let result = cell
+            .get_or_init(|| {
+                ran_refresh = true;
+                refresh_fn()
+            })
+            .await
+            .clone();
+
+        // Release the slot so the next refresh for this credential hits the network again
+        {
+            let mut coalesced_guard = self.coalesced.lock().await;
+            if coalesced_guard
+                .get(credential_id)
+                .is_some_and(|current| Arc::ptr_eq(current, &cell))
+            {
+                coalesced_guard.remove(credential_id);
+            }
+        }
+
+        let mut stats_guard = self.stats.write().await;
+        stats_guard.total_requests += 1;
+        if ran_refresh {
+            if result.success {
+                stats_guard.successful_refreshes += 1;
+            } else {
+                stats_guard.failed_refreshes += 1;
+            }
+        } else {
+            stats_guard.cache_saves += 1;
+        }
+
+        result
+    }
+
     /// Get refresh result (non-blocking)
     pub async fn get_result(&self, request_id: &str) -> Option<TokenRefreshResult> {
         let results_guard = self.results.read().await;
@@ -432,6 +490,7 @@ impl Clone for TokenOptimizer {
             results: Arc::clone(&self.results),
             stats: Arc::clone(&self.stats),
             batch_semaphore: Arc::clone(&self.batch_semaphore),
+            coalesced: Arc::clone(&self.coalesced),
             client: self.client.clone(),
         }
     }
@@ -499,6 +558,48 @@ mod tests {
         assert_eq!(batch[2].priority, RefreshPriority::Low);
     }
 
+    #[tokio::test]
+    async fn test_refresh_coalesced_runs_once() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        let optimizer = Arc::new(TokenOptimizer::new());
+        let refresh_calls = Arc::new(AtomicUsize::new(0));
+
+        let mut handles = Vec::new();
+        for _ in 0..10 {
+            let optimizer = Arc::clone(&optimizer);
+            let refresh_calls = Arc::clone(&refresh_calls);
+            handles.push(tokio::spawn(async move {
+                optimizer.refresh_coalesced("claude:shared", || async move {
+                    refresh_calls.fetch_add(1, Ordering::SeqCst);
+                    sleep(TokioDuration::from_millis(50)).await;
+                    TokenRefreshResult {
+                        request_id: "refresh-1".to_string(),
+                        success: true,
+                        new_token: Some("new_access_token".to_string()),
+                        new_refresh_token: None,
+                        expires_at: Some(Utc::now() + chrono::Duration::hours(1)),
+                        error: None,
+                        refresh_time_ms: 50,
+                    }
+                }).await
+            }));
+        }
+
+        for handle in handles {
+            let result = handle.await.unwrap();
+            assert!(result.success);
+            assert_eq!(result.new_token.as_deref(), Some("new_access_token"));
+        }
+
+        assert_eq!(refresh_calls.load(Ordering::SeqCst), 1);
+
+        let stats = optimizer.get_stats().await;
+        assert_eq!(stats.total_requests, 10);
+        assert_eq!(stats.successful_refreshes, 1);
+        assert_eq!(stats.cache_saves, 9);
+    }
+
     #[tokio::test]
     async fn test_batch_size_limit() {
         let mut config = BatchConfig::default();
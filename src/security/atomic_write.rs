@@ -0,0 +1,241 @@
+//! Crash-safe file persistence for credentials
+//!
+//! Writes go to a sibling temp file which is fsynced and locked down before being
+//! renamed over the target, so readers only ever see the old or the new contents.
+
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+static TEMP_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// Atomically replace `path` with `contents`, applying `mode` on Unix
+///
+/// `mode` is ignored on platforms without Unix permissions.
+pub fn write_atomic(path: &Path, contents: &[u8], mode: u32) -> io::Result<()> {
+    write_atomic_with(path, contents, mode, |_| Ok(()))
+}
+
+/// Async wrapper around [`write_atomic`] that keeps the blocking I/O off the runtime threads
+pub async fn write_atomic_async(path: impl Into<PathBuf>, contents: impl Into<Vec<u8>>, mode: u32) -> io::Result<()> {
+    let path = path.into();
+    let contents = contents.into();
+    tokio::task::spawn_blocking(move || write_atomic(&path, &contents, mode))
+        .await
+        .map_err(io::Error::other)?
+}
+
+/// Append one `record` to `path` with a single write, creating it with `mode` on Unix
+///
+/// The file is opened in append mode, so concurrent appenders never overwrite each other
+/// and a record is never split around another writer's. A crash can still leave the last
+/// record truncated, which readers of append-only files should skip.
+pub fn append_atomic(path: &Path, record: &[u8], mode: u32) -> io::Result<()> {
+    if let Some(parent) = path.parent().filter(|parent| !parent.as_os_str().is_empty()) {
+        fs::create_dir_all(parent)?;
+    }
+
+    let mut options = OpenOptions::new();
+    options.create(true).append(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(mode);
+    }
+    #[cfg(not(unix))]
+    let _ = mode;
+
+    let mut file = options.open(path)?;
+    file.write_all(record)?;
+    file.sync_data()
+}
+
+/// Async wrapper around [`append_atomic`]
+pub async fn append_atomic_async(path: impl Into<PathBuf>, record: impl Into<Vec<u8>>, mode: u32) -> io::Result<()> {
+    let path = path.into();
+    let record = record.into();
+    tokio::task::spawn_blocking(move || append_atomic(&path, &record, mode))
+        .await
+        .map_err(io::Error::other)?
+}
+
+/// `before_rename` runs once the temp file is complete; an error aborts the write
+fn write_atomic_with(
+    path: &Path,
+    contents: &[u8],
+    mode: u32,
+    before_rename: impl FnOnce(&Path) -> io::Result<()>,
+) -> io::Result<()> {
+    let parent = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent.to_path_buf(),
+        _ => PathBuf::from("."),
+    };
+    fs::create_dir_all(&parent)?;
+
+    let temp_path = temp_path_for(path, &parent)?;
+    let result = write_temp(&temp_path, contents, mode)
+        .and_then(|()| before_rename(&temp_path))
+        .and_then(|()| replace_file(&temp_path, path));
+
+    if result.is_err() {
+        let _ = fs::remove_file(&temp_path);
+        return result;
+    }
+
+    sync_dir(&parent);
+    Ok(())
+}
+
+fn temp_path_for(path: &Path, parent: &Path) -> io::Result<PathBuf> {
+    let file_name = path
+        .file_name()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "atomic write target has no file name"))?;
+
+    Ok(parent.join(format!(
+        ".{}.tmp-{}-{}",
+        file_name.to_string_lossy(),
+        std::process::id(),
+        TEMP_COUNTER.fetch_add(1, Ordering::Relaxed)
+    )))
+}
+
+fn write_temp(temp_path: &Path, contents: &[u8], mode: u32) -> io::Result<()> {
+    let mut options = OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(mode);
+    }
+    #[cfg(not(unix))]
+    let _ = mode;
+
+    let mut file: File = options.open(temp_path)?;
+    file.write_all(contents)?;
+    file.sync_all()?;
+
+    // The creation mode is filtered by the umask; set it explicitly
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(temp_path, fs::Permissions::from_mode(mode))?;
+    }
+
+    Ok(())
+}
+
+#[cfg(not(windows))]
+fn replace_file(from: &Path, to: &Path) -> io::Result<()> {
+    fs::rename(from, to)
+}
+
+/// `rename` replaces existing files on Windows, but fails on read-only targets and while
+/// another process (often a virus scanner) briefly holds the file open
+#[cfg(windows)]
+fn replace_file(from: &Path, to: &Path) -> io::Result<()> {
+    if let Ok(metadata) = fs::metadata(to) {
+        let mut permissions = metadata.permissions();
+        if permissions.readonly() {
+            permissions.set_readonly(false);
+            fs::set_permissions(to, permissions)?;
+        }
+    }
+
+    let mut attempts = 0;
+    loop {
+        match fs::rename(from, to) {
+            Err(e) if e.kind() == io::ErrorKind::PermissionDenied && attempts < 5 => {
+                attempts += 1;
+                std::thread::sleep(std::time::Duration::from_millis(10 * attempts));
+            }
+            result => return result,
+        }
+    }
+}
+
+/// Persist the rename itself; best effort since not every filesystem supports it
+fn sync_dir(dir: &Path) {
+    #[cfg(unix)]
+    if let Ok(dir) = File::open(dir) {
+        let _ = dir.sync_all();
+    }
+    #[cfg(not(unix))]
+    let _ = dir;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn leftover_temp_files(dir: &Path) -> usize {
+        fs::read_dir(dir)
+            .unwrap()
+            .filter(|entry| entry.as_ref().unwrap().file_name().to_string_lossy().contains(".tmp-"))
+            .count()
+    }
+
+    #[test]
+    fn test_write_atomic_replaces_contents() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("auth.json");
+        fs::write(&path, "old").unwrap();
+
+        write_atomic(&path, b"new", 0o600).unwrap();
+
+        assert_eq!(fs::read_to_string(&path).unwrap(), "new");
+        assert_eq!(leftover_temp_files(dir.path()), 0);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            assert_eq!(fs::metadata(&path).unwrap().permissions().mode() & 0o777, 0o600);
+        }
+    }
+
+    #[test]
+    fn test_append_atomic_keeps_existing_records() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("ledger").join("usage.ndjson");
+
+        append_atomic(&path, b"{\"n\":1}\n", 0o600).unwrap();
+        append_atomic(&path, b"{\"n\":2}\n", 0o600).unwrap();
+
+        assert_eq!(fs::read_to_string(&path).unwrap(), "{\"n\":1}\n{\"n\":2}\n");
+    }
+
+    #[test]
+    fn test_failure_after_temp_write_leaves_original_untouched() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("auth.json");
+        fs::write(&path, r#"{"OPENAI_API_KEY":"sk-original"}"#).unwrap();
+
+        let result = write_atomic_with(&path, b"{\"OPENAI_API_KEY\":\"sk-replacement\"}", 0o600, |temp| {
+            // The temp file is complete at this point; simulate dying before the rename
+            assert_eq!(fs::read(temp).unwrap(), b"{\"OPENAI_API_KEY\":\"sk-replacement\"}");
+            Err(io::Error::other("injected failure"))
+        });
+
+        assert!(result.is_err());
+        assert_eq!(fs::read_to_string(&path).unwrap(), r#"{"OPENAI_API_KEY":"sk-original"}"#);
+        assert_eq!(leftover_temp_files(dir.path()), 0);
+    }
+
+    #[test]
+    fn test_write_atomic_creates_missing_parent() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("nested").join("claude_auth.json");
+
+        write_atomic(&path, b"{}", 0o600).unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "{}");
+    }
+
+    #[tokio::test]
+    async fn test_write_atomic_async() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("unified_auth.json");
+
+        write_atomic_async(&path, "{\"version\":2}", 0o600).await.unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "{\"version\":2}");
+    }
+}
@@ -1,10 +1,13 @@
 use std::fs::{File, OpenOptions};
 use std::io::Write;
 use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
 use serde::{Deserialize, Serialize};
 use chrono::{DateTime, Utc};
 use thiserror::Error;
 
+use super::webhook::WebhookDispatcher;
+
 #[cfg(unix)]
 use std::os::unix::fs::OpenOptionsExt;
 
@@ -15,6 +18,19 @@ pub struct SecurityAuditLogger {
     max_log_size: u64,
     max_log_files: usize,
     buffer: Vec<AuditEvent>,
+    webhook: Option<WebhookDispatcher>,
+    write_mode: WriteMode,
+    last_flush: Instant,
+}
+
+/// When logged events reach the log file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WriteMode {
+    /// Append every event as it is logged
+    Immediate,
+    /// Queue events and write them once `max_events` are waiting or the last write is
+    /// `flush_interval` old; errors and critical events are still written at once
+    Buffered { max_events: usize, flush_interval: Duration },
 }
 
 #[derive(Debug, Error)]
@@ -61,9 +77,21 @@ pub enum AuthEventType {
     AccountLocked,
     TwoFactorAuth,
     SuspiciousActivity,
+    VerificationSkipped,
+    QuotaReclaimed,
+    TokenRevoked,
+    RateLimited,
+    CredentialImported,
+    QuotaOverage,
+    PermissionsRepaired,
+    AuthFileCorrupted,
+    EncryptionKeyRotated,
+    SubscriptionDowngraded,
+    ProviderRecovered,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Ordered from least to most severe
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
 pub enum Severity {
     Info,
@@ -96,9 +124,27 @@ impl SecurityAuditLogger {
             max_log_size: 10 * 1024 * 1024, // 10MB
             max_log_files: 5,
             buffer: Vec::new(),
+            webhook: None,
+            write_mode: WriteMode::Immediate,
+            last_flush: Instant::now(),
         })
     }
 
+    /// Write events in batches instead of one append per event
+    ///
+    /// Anything still queued is written by [`flush_buffer`](Self::flush_buffer) or when
+    /// the logger is dropped.
+    pub fn with_buffering(mut self, max_events: usize, flush_interval: Duration) -> Self {
+        self.write_mode = WriteMode::Buffered { max_events: max_events.max(1), flush_interval };
+        self
+    }
+
+    /// Also forward logged events to a security webhook
+    pub fn with_webhook(mut self, webhook: WebhookDispatcher) -> Self {
+        self.webhook = Some(webhook);
+        self
+    }
+
     /// Log authentication event
     pub fn log_auth_event(&mut self, mut event: AuditEvent) -> Result<(), AuditLogError> {
         // Ensure timestamp is set
@@ -106,17 +152,24 @@ impl SecurityAuditLogger {
             event.timestamp = Utc::now();
         }
 
-        // Add to buffer
-        self.buffer.push(event.clone());
+        if let Some(ref webhook) = self.webhook {
+            webhook.dispatch(&event);
+        }
+
+        // Errors and critical events are written at once, after everything queued before them
+        let urgent = event.severity >= Severity::Error;
+        self.buffer.push(event);
 
-        // Write to file immediately for critical events
-        if matches!(event.severity, Severity::Critical | Severity::Error) {
+        let flush_due = match self.write_mode {
+            WriteMode::Immediate => true,
+            WriteMode::Buffered { max_events, flush_interval } => {
+                urgent || self.buffer.len() >= max_events || self.last_flush.elapsed() >= flush_interval
+            }
+        };
+        if flush_due {
             self.flush_buffer()?;
         }
 
-        // Check if log rotation is needed
-        self.check_log_rotation()?;
-
         Ok(())
     }
 
@@ -274,8 +327,10 @@ impl SecurityAuditLogger {
         
         file.flush()?;
         self.buffer.clear();
-        
-        Ok(())
+        self.last_flush = Instant::now();
+
+        // Check if log rotation is needed
+        self.check_log_rotation()
     }
 
     /// Generate security metrics from log file
@@ -393,6 +448,14 @@ impl SecurityAuditLogger {
     }
 }
 
+impl Drop for SecurityAuditLogger {
+    fn drop(&mut self) {
+        if let Err(e) = self.flush_buffer() {
+            tracing::error!(error = %e, events = self.buffer.len(), "Failed to write buffered audit events");
+        }
+    }
+}
+
 /// Global security audit logger instance
 lazy_static::lazy_static! {
     static ref GLOBAL_AUDIT_LOGGER: std::sync::Mutex<Option<SecurityAuditLogger>> = 
@@ -401,12 +464,64 @@ lazy_static::lazy_static! {
 
 /// Initialize global audit logger
 pub fn init_audit_logger(log_file: PathBuf) -> Result<(), AuditLogError> {
-    let logger = SecurityAuditLogger::new(log_file)?;
+    init_audit_logger_with_webhook(log_file, None)
+}
+
+/// Initialize global audit logger, forwarding events to `webhook` when given
+pub fn init_audit_logger_with_webhook(
+    log_file: PathBuf,
+    webhook: Option<WebhookDispatcher>,
+) -> Result<(), AuditLogError> {
+    let mut logger = SecurityAuditLogger::new(log_file)?;
+    if let Some(webhook) = webhook {
+        logger = logger.with_webhook(webhook);
+    }
     let mut global_logger = GLOBAL_AUDIT_LOGGER.lock().unwrap();
     *global_logger = Some(logger);
     Ok(())
 }
 
+/// Initialize the global audit logger in buffered mode
+///
+/// A background thread flushes the queue every `flush_interval`, so quiet periods don't
+/// leave events unwritten. Call [`flush_audit_log`] before exiting.
+pub fn init_audit_logger_buffered(
+    log_file: PathBuf,
+    max_events: usize,
+    flush_interval: Duration,
+) -> Result<(), AuditLogError> {
+    let logger = SecurityAuditLogger::new(log_file)?.with_buffering(max_events, flush_interval);
+    *GLOBAL_AUDIT_LOGGER.lock().unwrap() = Some(logger);
+
+    static FLUSH_THREAD: std::sync::Once = std::sync::Once::new();
+    FLUSH_THREAD.call_once(|| {
+        let spawned = std::thread::Builder::new()
+            .name("audit-log-flush".to_string())
+            .spawn(move || loop {
+                std::thread::sleep(flush_interval);
+                if let Err(e) = flush_audit_log() {
+                    tracing::warn!(error = %e, "Periodic audit log flush failed");
+                }
+            });
+        if let Err(e) = spawned {
+            tracing::warn!(error = %e, "Could not start the audit log flush thread");
+        }
+    });
+    Ok(())
+}
+
+/// Stop the global logger, writing out anything it still buffers
+///
+/// Events are dropped until a logger is initialized again. The logger is removed even
+/// when the final flush fails.
+pub fn disable_audit_logger() -> Result<(), AuditLogError> {
+    let logger = GLOBAL_AUDIT_LOGGER.lock().unwrap().take();
+    match logger {
+        Some(mut logger) => logger.flush_buffer(),
+        None => Ok(()),
+    }
+}
+
 /// Log event using global logger
 pub fn log_audit_event(event: AuditEvent) -> Result<(), AuditLogError> {
     let mut global_logger = GLOBAL_AUDIT_LOGGER.lock().unwrap();
@@ -416,6 +531,15 @@ pub fn log_audit_event(event: AuditEvent) -> Result<(), AuditLogError> {
     Ok(())
 }
 
+/// Write any events the global logger is still buffering
+pub fn flush_audit_log() -> Result<(), AuditLogError> {
+    let mut global_logger = GLOBAL_AUDIT_LOGGER.lock().unwrap();
+    if let Some(ref mut logger) = *global_logger {
+        logger.flush_buffer()?;
+    }
+    Ok(())
+}
+
 /// Convenience function to log login success
 pub fn log_login_success(
     user_id: Option<String>,
@@ -480,6 +604,74 @@ mod tests {
         assert!(content.contains("Login"));
     }
 
+    fn info_event(n: usize) -> AuditEvent {
+        AuditEvent {
+            timestamp: Utc::now(),
+            event_type: AuthEventType::TokenRefresh,
+            user_id: Some(format!("agent-{}", n)),
+            session_id: None,
+            client_id: None,
+            ip_address: None,
+            user_agent: None,
+            success: true,
+            error_message: None,
+            metadata: serde_json::Value::Null,
+            severity: Severity::Info,
+        }
+    }
+
+    fn logged_users(log_file: &Path) -> Vec<String> {
+        std::fs::read_to_string(log_file)
+            .unwrap_or_default()
+            .lines()
+            .map(|line| serde_json::from_str::<AuditEvent>(line).unwrap().user_id.unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_buffered_events_flush_in_order_with_critical_and_on_drop() {
+        let temp_dir = tempdir().unwrap();
+        let log_file = temp_dir.path().join("audit.log");
+        let mut logger = SecurityAuditLogger::new(log_file.clone())
+            .unwrap()
+            .with_buffering(1000, Duration::from_secs(3600));
+
+        for n in 0..50 {
+            logger.log_auth_event(info_event(n)).unwrap();
+        }
+        assert!(logged_users(&log_file).is_empty());
+
+        let mut critical = info_event(50);
+        critical.severity = Severity::Critical;
+        logger.log_auth_event(critical).unwrap();
+        let expected: Vec<String> = (0..=50).map(|n| format!("agent-{}", n)).collect();
+        assert_eq!(logged_users(&log_file), expected);
+
+        for n in 51..60 {
+            logger.log_auth_event(info_event(n)).unwrap();
+        }
+        assert_eq!(logged_users(&log_file).len(), 51);
+
+        drop(logger);
+        let expected: Vec<String> = (0..60).map(|n| format!("agent-{}", n)).collect();
+        assert_eq!(logged_users(&log_file), expected);
+    }
+
+    #[test]
+    fn test_buffer_flushes_when_full() {
+        let temp_dir = tempdir().unwrap();
+        let log_file = temp_dir.path().join("audit.log");
+        let mut logger = SecurityAuditLogger::new(log_file.clone())
+            .unwrap()
+            .with_buffering(10, Duration::from_secs(3600));
+
+        for n in 0..25 {
+            logger.log_auth_event(info_event(n)).unwrap();
+        }
+        assert_eq!(logged_users(&log_file).len(), 20);
+        assert_eq!(logger.buffer.len(), 5);
+    }
+
     #[test]
     fn test_security_metrics() {
         let temp_dir = tempdir().unwrap();
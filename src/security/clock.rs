@@ -0,0 +1,86 @@
+//! Injectable time source
+//!
+//! Quota resets, token expiry and session timeouts read the time through a [`Clock`]
+//! so tests can move time forward instead of sleeping.
+
+use std::fmt::Debug;
+use std::sync::{Arc, Mutex};
+
+use chrono::{DateTime, Duration, Utc};
+
+/// Source of the current time
+pub trait Clock: Debug + Send + Sync {
+    fn now(&self) -> DateTime<Utc>;
+}
+
+/// Wall-clock time; the default everywhere a clock is injected
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> DateTime<Utc> {
+        Utc::now()
+    }
+}
+
+/// Shared handle to the system clock
+pub fn system_clock() -> Arc<dyn Clock> {
+    Arc::new(SystemClock)
+}
+
+/// Manually driven clock for tests
+///
+/// Clones share the same time, so a test can keep one handle and inject another.
+#[derive(Debug, Clone)]
+pub struct MockClock {
+    now: Arc<Mutex<DateTime<Utc>>>,
+}
+
+impl MockClock {
+    /// Clock frozen at `start`
+    pub fn new(start: DateTime<Utc>) -> Self {
+        Self {
+            now: Arc::new(Mutex::new(start)),
+        }
+    }
+
+    /// Move the clock forward (or backward, for a negative duration)
+    pub fn advance(&self, by: Duration) {
+        let mut now = self.now.lock().unwrap();
+        *now += by;
+    }
+
+    /// Jump to an absolute time
+    pub fn set(&self, to: DateTime<Utc>) {
+        *self.now.lock().unwrap() = to;
+    }
+}
+
+impl Default for MockClock {
+    fn default() -> Self {
+        Self::new(Utc::now())
+    }
+}
+
+impl Clock for MockClock {
+    fn now(&self) -> DateTime<Utc> {
+        *self.now.lock().unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mock_clock_advances_shared_time() {
+        let start = DateTime::parse_from_rfc3339("2025-01-01T23:59:00Z").unwrap().with_timezone(&Utc);
+        let clock = MockClock::new(start);
+        let injected: Arc<dyn Clock> = Arc::new(clock.clone());
+
+        clock.advance(Duration::minutes(2));
+
+        assert_eq!(injected.now(), start + Duration::minutes(2));
+        assert_eq!(clock.now(), injected.now());
+    }
+}
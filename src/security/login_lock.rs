@@ -0,0 +1,154 @@
+//! Exclusive lock held for the duration of a login or migration
+//!
+//! Two concurrent logins would otherwise interleave their writes to the credential
+//! files. The lock is advisory: a `login.lock` file under the codex home, created with
+//! `create_new` so only one process can hold it, and removed when the guard is dropped.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+/// Lock file name under the codex home
+pub const LOGIN_LOCK_FILE: &str = "login.lock";
+
+/// Age after which a lock is assumed to belong to a crashed process and is taken over
+///
+/// Longer than an OAuth flow can take, including the user finishing in the browser.
+pub const LOGIN_LOCK_STALE_AFTER: chrono::Duration = chrono::Duration::minutes(15);
+
+#[derive(Debug, thiserror::Error)]
+pub enum LoginLockError {
+    #[error("Another login is in progress (pid {pid}, started {since}); wait for it to finish, or remove {} if it crashed", path.display())]
+    InProgress { pid: u32, since: DateTime<Utc>, path: PathBuf },
+
+    #[error("Login lock error: {0}")]
+    Io(#[from] io::Error),
+}
+
+/// Who holds the lock, as recorded in the lock file
+#[derive(Debug, Serialize, Deserialize)]
+struct LockHolder {
+    pid: u32,
+    acquired_at: DateTime<Utc>,
+}
+
+/// Guard for the login lock; dropping it releases the lock, including on error paths
+#[derive(Debug)]
+pub struct LoginLock {
+    path: PathBuf,
+}
+
+impl LoginLock {
+    /// Take the login lock for `codex_home`, failing fast if another login holds it
+    pub fn acquire(codex_home: &Path) -> Result<Self, LoginLockError> {
+        fs::create_dir_all(codex_home)?;
+        let path = codex_home.join(LOGIN_LOCK_FILE);
+
+        // A second attempt only follows removing a stale lock
+        for _ in 0..2 {
+            match create_lock_file(&path) {
+                Ok(()) => return Ok(Self { path }),
+                Err(e) if e.kind() == io::ErrorKind::AlreadyExists => {
+                    let holder = read_holder(&path)?;
+                    if Utc::now() - holder.acquired_at < LOGIN_LOCK_STALE_AFTER {
+                        return Err(LoginLockError::InProgress {
+                            pid: holder.pid,
+                            since: holder.acquired_at,
+                            path,
+                        });
+                    }
+                    tracing::warn!(pid = holder.pid, path = %path.display(), "Taking over stale login lock");
+                    match fs::remove_file(&path) {
+                        Err(e) if e.kind() != io::ErrorKind::NotFound => return Err(e.into()),
+                        _ => {}
+                    }
+                }
+                Err(e) => return Err(e.into()),
+            }
+        }
+
+        let holder = read_holder(&path)?;
+        Err(LoginLockError::InProgress { pid: holder.pid, since: holder.acquired_at, path })
+    }
+
+    /// Path of the lock file
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for LoginLock {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+fn create_lock_file(path: &Path) -> io::Result<()> {
+    let mut file = OpenOptions::new().write(true).create_new(true).open(path)?;
+    let holder = LockHolder { pid: std::process::id(), acquired_at: Utc::now() };
+    let result = serde_json::to_vec(&holder)
+        .map_err(io::Error::other)
+        .and_then(|contents| file.write_all(&contents));
+    if result.is_err() {
+        let _ = fs::remove_file(path);
+    }
+    result
+}
+
+/// Holder of an existing lock; a file still being written by its creator counts as
+/// acquired at its modification time
+fn read_holder(path: &Path) -> io::Result<LockHolder> {
+    let contents = match fs::read(path) {
+        Ok(contents) => contents,
+        // Released between our create and read
+        Err(e) if e.kind() == io::ErrorKind::NotFound => {
+            return Ok(LockHolder { pid: 0, acquired_at: DateTime::<Utc>::UNIX_EPOCH });
+        }
+        Err(e) => return Err(e),
+    };
+    match serde_json::from_slice(&contents) {
+        Ok(holder) => Ok(holder),
+        Err(_) => {
+            let modified = fs::metadata(path)?.modified()?;
+            Ok(LockHolder { pid: 0, acquired_at: modified.into() })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_lock_is_exclusive_until_dropped() {
+        let dir = tempdir().unwrap();
+
+        let lock = LoginLock::acquire(dir.path()).unwrap();
+        assert!(lock.path().exists());
+        match LoginLock::acquire(dir.path()) {
+            Err(LoginLockError::InProgress { pid, .. }) => assert_eq!(pid, std::process::id()),
+            other => panic!("expected InProgress, got {:?}", other),
+        }
+
+        drop(lock);
+        assert!(!dir.path().join(LOGIN_LOCK_FILE).exists());
+        assert!(LoginLock::acquire(dir.path()).is_ok());
+    }
+
+    #[test]
+    fn test_stale_lock_is_taken_over() {
+        let dir = tempdir().unwrap();
+        let stale = LockHolder {
+            pid: 1,
+            acquired_at: Utc::now() - LOGIN_LOCK_STALE_AFTER - chrono::Duration::minutes(1),
+        };
+        fs::write(dir.path().join(LOGIN_LOCK_FILE), serde_json::to_vec(&stale).unwrap()).unwrap();
+
+        let lock = LoginLock::acquire(dir.path()).unwrap();
+        let holder = read_holder(lock.path()).unwrap();
+        assert_eq!(holder.pid, std::process::id());
+    }
+}
@@ -6,16 +6,32 @@
 //! - Security audit logging
 //! - Session security with token rotation
 //! - Environment variable security
+//! - Webhook notification of security violations
 
 pub mod secure_token_storage;
 pub mod oauth_security;
 pub mod audit_logger;
 pub mod session_security;
-
-pub use secure_token_storage::{SecureTokenStorage, SecureStorageError};
-pub use oauth_security::{SecureOAuthFlow, OAuthSecurityManager, OAuthSecurityError};
-pub use audit_logger::{SecurityAuditLogger, AuditEvent, AuthEventType, Severity};
+pub mod atomic_write;
+pub mod login_lock;
+pub mod clock;
+pub mod webhook;
+pub mod secret;
+pub mod secret_ref;
+
+pub use secure_token_storage::{
+    EncryptionUnavailablePolicy, PermissionCheckPolicy, PermissionIssue, PermissionReport,
+    SecureTokenStorage, SecureStorageError, StorageMode,
+};
+pub use oauth_security::{SecureOAuthFlow, OAuthSecurityManager, OAuthSecurityError, OAuthRateLimitConfig};
+pub use audit_logger::{SecurityAuditLogger, AuditEvent, AuthEventType, Severity, WriteMode};
 pub use session_security::{SessionSecurityManager, SecureSession, SessionSecurityError};
+pub use atomic_write::{append_atomic, append_atomic_async, write_atomic, write_atomic_async};
+pub use login_lock::{LoginLock, LoginLockError};
+pub use clock::{Clock, MockClock, SystemClock};
+pub use webhook::{WebhookConfig, WebhookDispatcher};
+pub use secret::{mask_secret, masked, Secret};
+pub use secret_ref::{resolve_secret, SecretRef, SecretRefError};
 
 use std::path::PathBuf;
 use thiserror::Error;
@@ -41,12 +57,21 @@ pub struct SecurityConfig {
     pub token_storage_path: PathBuf,
     pub audit_log_path: PathBuf,
     pub enable_encryption: bool,
+    /// Fallback when token encryption cannot be set up; `Fail` refuses to start
+    pub on_encryption_unavailable: EncryptionUnavailablePolicy,
+    /// Startup check for token files that other users can read
+    pub permission_check: PermissionCheckPolicy,
     pub enable_audit_logging: bool,
     pub require_pkce: bool,
     pub token_rotation_enabled: bool,
     pub max_concurrent_oauth_flows: usize,
+    pub oauth_rate_limit: OAuthRateLimitConfig,
+    /// Pending OAuth flows reject callbacks after this long
+    pub oauth_flow_ttl_minutes: i64,
     pub session_timeout_minutes: i64,
     pub require_secure_transport: bool,
+    /// POST audit events to a SIEM or chat webhook; requires `enable_audit_logging`
+    pub violation_webhook: Option<WebhookConfig>,
 }
 
 impl Default for SecurityConfig {
@@ -61,12 +86,17 @@ impl Default for SecurityConfig {
                 .join(".codex")
                 .join("security_audit.log"),
             enable_encryption: true,
+            on_encryption_unavailable: EncryptionUnavailablePolicy::Fail,
+            permission_check: PermissionCheckPolicy::Warn,
             enable_audit_logging: true,
             require_pkce: true,
             token_rotation_enabled: true,
             max_concurrent_oauth_flows: 3,
+            oauth_rate_limit: OAuthRateLimitConfig::default(),
+            oauth_flow_ttl_minutes: oauth_security::DEFAULT_FLOW_TTL_MINUTES,
             session_timeout_minutes: 60,
             require_secure_transport: true,
+            violation_webhook: None,
         }
     }
 }
@@ -77,6 +107,7 @@ pub struct SecurityManager {
     token_storage: Option<SecureTokenStorage>,
     oauth_manager: Option<OAuthSecurityManager>,
     session_manager: Option<SessionSecurityManager>,
+    webhook: Option<WebhookDispatcher>,
 }
 
 impl SecurityManager {
@@ -87,15 +118,33 @@ impl SecurityManager {
             token_storage: None,
             oauth_manager: None,
             session_manager: None,
+            webhook: None,
         };
 
+        // Audit logging first, so degraded token storage is recorded
+        if config.enable_audit_logging {
+            manager.webhook = config.violation_webhook.clone().map(WebhookDispatcher::spawn);
+            audit_logger::init_audit_logger_with_webhook(config.audit_log_path.clone(), manager.webhook.clone())?;
+        } else if config.violation_webhook.is_some() {
+            tracing::warn!("violation_webhook is ignored while audit logging is disabled");
+        }
+
         // Initialize components based on configuration
         if config.enable_encryption {
-            manager.token_storage = Some(SecureTokenStorage::new(config.token_storage_path.clone())?);
+            let storage = SecureTokenStorage::with_policy(
+                config.token_storage_path.clone(),
+                config.on_encryption_unavailable,
+            )?;
+            Self::check_storage_permissions(&storage, config.permission_check)?;
+            manager.token_storage = Some(storage);
         }
 
         if config.require_pkce {
-            manager.oauth_manager = Some(OAuthSecurityManager::new(config.max_concurrent_oauth_flows));
+            manager.oauth_manager = Some(
+                OAuthSecurityManager::new(config.max_concurrent_oauth_flows)
+                    .with_rate_limit(config.oauth_rate_limit.clone())
+                    .with_flow_ttl(chrono::Duration::minutes(config.oauth_flow_ttl_minutes)),
+            );
         }
 
         if config.token_rotation_enabled {
@@ -106,19 +155,67 @@ impl SecurityManager {
             manager.session_manager = Some(SessionSecurityManager::new(session_config));
         }
 
-        // Initialize audit logging if enabled
-        if config.enable_audit_logging {
-            audit_logger::init_audit_logger(config.audit_log_path.clone())?;
-        }
-
         Ok(manager)
     }
 
+    fn check_storage_permissions(storage: &SecureTokenStorage, policy: PermissionCheckPolicy) -> Result<(), SecurityError> {
+        let report = match policy {
+            PermissionCheckPolicy::Skip => return Ok(()),
+            PermissionCheckPolicy::Warn => storage.check_permissions(),
+            PermissionCheckPolicy::Repair => storage.repair_permissions()?,
+        };
+        if !report.supported {
+            tracing::debug!("Token file permission check skipped: not supported on this platform");
+        }
+        for issue in &report.issues {
+            if policy == PermissionCheckPolicy::Repair {
+                tracing::warn!("Tightened {} from {:o} to {:o}", issue.path.display(), issue.mode, issue.expected);
+            } else {
+                tracing::warn!(
+                    "{} is readable by other users (mode {:o}); run `chmod {:o}` on it or enable permission repair",
+                    issue.path.display(), issue.mode, issue.expected
+                );
+            }
+        }
+        Ok(())
+    }
+
     /// Get token storage instance
     pub fn token_storage(&self) -> Option<&SecureTokenStorage> {
         self.token_storage.as_ref()
     }
 
+    /// Re-encrypt stored tokens under a freshly generated key
+    ///
+    /// For when the current key may be compromised; the user stays logged in. On failure
+    /// the stored tokens remain readable with the old key. Both outcomes are audited.
+    pub fn rotate_encryption_key(&mut self) -> Result<(), SecurityError> {
+        let storage = self.token_storage.as_mut().ok_or_else(|| {
+            SecureStorageError::Encryption("token storage is disabled".to_string())
+        })?;
+        let result = storage.rotate_encryption_key();
+
+        let event = AuditEvent {
+            timestamp: chrono::Utc::now(),
+            event_type: AuthEventType::EncryptionKeyRotated,
+            user_id: None,
+            session_id: None,
+            client_id: None,
+            ip_address: None,
+            user_agent: None,
+            success: result.is_ok(),
+            error_message: result.as_ref().err().map(|e| e.to_string()),
+            metadata: serde_json::json!({
+                "path": storage.storage_path(),
+                "key_path": secure_token_storage::key_path(storage.storage_path()),
+            }),
+            severity: if result.is_ok() { Severity::Info } else { Severity::Error },
+        };
+        audit_logger::log_audit_event(event).ok();
+
+        result.map_err(SecurityError::from)
+    }
+
     /// Get OAuth security manager
     pub fn oauth_manager(&mut self) -> Option<&mut OAuthSecurityManager> {
         self.oauth_manager.as_mut()
@@ -198,7 +295,8 @@ impl SecurityManager {
 
         // Check token storage
         if let Some(storage) = &self.token_storage {
-            report.token_storage_secure = storage.tokens_exist();
+            report.token_storage_mode = Some(storage.mode());
+            report.token_storage_secure = storage.mode() == StorageMode::Encrypted && storage.tokens_exist();
         }
 
         // Check audit logging
@@ -206,6 +304,10 @@ impl SecurityManager {
             report.audit_logging_enabled = true;
         }
 
+        if let Some(webhook) = &self.webhook {
+            report.webhook_events_dropped = webhook.dropped_events();
+        }
+
         // Check OAuth security
         if let Some(oauth_manager) = &self.oauth_manager {
             report.oauth_security_enabled = true;
@@ -228,6 +330,8 @@ impl SecurityManager {
 #[derive(Debug, Default)]
 pub struct SecurityHealthReport {
     pub token_storage_secure: bool,
+    /// `None` when token storage is disabled
+    pub token_storage_mode: Option<StorageMode>,
     pub audit_logging_enabled: bool,
     pub oauth_security_enabled: bool,
     pub session_security_enabled: bool,
@@ -235,6 +339,7 @@ pub struct SecurityHealthReport {
     pub active_sessions: usize,
     pub suspicious_sessions: usize,
     pub security_violations_24h: u64,
+    pub webhook_events_dropped: u64,
 }
 
 /// Initialize security subsystem with default configuration
@@ -255,6 +360,7 @@ mod tests {
 
     #[test]
     fn test_security_manager_creation() {
+        let _audit_logger = crate::test_support::AUDIT_LOGGER_LOCK.blocking_lock();
         let temp_dir = tempdir().unwrap();
         
         let config = SecurityConfig {
@@ -269,8 +375,66 @@ mod tests {
         assert!(manager.session_manager.is_some());
     }
 
+    #[cfg(unix)]
+    #[test]
+    fn test_startup_repairs_permissions_when_configured() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let _audit_logger = crate::test_support::AUDIT_LOGGER_LOCK.blocking_lock();
+        let temp_dir = tempdir().unwrap();
+        let token_path = temp_dir.path().join("tokens.json");
+        std::fs::write(&token_path, "{}").unwrap();
+        std::fs::set_permissions(&token_path, std::fs::Permissions::from_mode(0o644)).unwrap();
+        let config = |permission_check| SecurityConfig {
+            token_storage_path: token_path.clone(),
+            audit_log_path: temp_dir.path().join("audit.log"),
+            permission_check,
+            ..Default::default()
+        };
+        let mode = || std::fs::metadata(&token_path).unwrap().permissions().mode() & 0o777;
+
+        SecurityManager::new(config(PermissionCheckPolicy::Warn)).unwrap();
+        assert_eq!(mode(), 0o644);
+
+        SecurityManager::new(config(PermissionCheckPolicy::Repair)).unwrap();
+        assert_eq!(mode(), 0o600);
+    }
+
+    #[test]
+    fn test_rotate_encryption_key_keeps_tokens_retrievable() {
+        let _audit_logger = crate::test_support::AUDIT_LOGGER_LOCK.blocking_lock();
+        let temp_dir = tempdir().unwrap();
+        let config = SecurityConfig {
+            token_storage_path: temp_dir.path().join("tokens.json"),
+            audit_log_path: temp_dir.path().join("audit.log"),
+            ..Default::default()
+        };
+        let tokens = secure_token_storage::TokenData {
+            access_token: "access_123".into(),
+            refresh_token: "refresh_456".into(),
+            id_token: "id_789".into(),
+            expires_at: chrono::Utc::now() + chrono::Duration::hours(1),
+            account_id: None,
+            provider: "claude".to_string(),
+        };
+
+        let mut manager = SecurityManager::new(config.clone()).unwrap();
+        manager.token_storage().unwrap().store_tokens(&tokens).unwrap();
+        manager.rotate_encryption_key().unwrap();
+
+        let retrieved = manager.token_storage().unwrap().retrieve_tokens().unwrap().unwrap();
+        assert_eq!(retrieved.access_token.expose_secret(), "access_123");
+        assert!(secure_token_storage::key_path(&config.token_storage_path).exists());
+
+        // Survives a restart
+        let restarted = SecurityManager::new(config).unwrap();
+        let retrieved = restarted.token_storage().unwrap().retrieve_tokens().unwrap().unwrap();
+        assert_eq!(retrieved.refresh_token.expose_secret(), "refresh_456");
+    }
+
     #[test]
     fn test_security_health_check() {
+        let _audit_logger = crate::test_support::AUDIT_LOGGER_LOCK.blocking_lock();
         let temp_dir = tempdir().unwrap();
         
         let config = SecurityConfig {
@@ -289,6 +453,7 @@ mod tests {
 
     #[test]
     fn test_environment_validation() {
+        let _audit_logger = crate::test_support::AUDIT_LOGGER_LOCK.blocking_lock();
         let temp_dir = tempdir().unwrap();
         
         let config = SecurityConfig {
@@ -1,4 +1,5 @@
 use std::collections::HashMap;
+use std::sync::Arc;
 use serde::{Deserialize, Serialize};
 use chrono::{DateTime, Utc, Duration};
 use thiserror::Error;
@@ -6,6 +7,9 @@ use base64::{Engine as _, engine::general_purpose::URL_SAFE_NO_PAD};
 use rand::RngCore;
 use sha2::{Sha256, Digest};
 
+use super::audit_logger;
+use super::clock::{system_clock, Clock};
+
 /// Enhanced OAuth security with PKCE and state validation
 #[derive(Debug)]
 pub struct SecureOAuthFlow {
@@ -33,6 +37,14 @@ pub enum OAuthSecurityError {
     InvalidRedirectUri,
     #[error("Cryptographic error: {0}")]
     CryptographicError(String),
+    #[error("OAuth flow expired before the callback arrived")]
+    FlowExpired,
+    #[error("OAuth callback already consumed for this flow")]
+    FlowAlreadyConsumed,
+    #[error("Unknown OAuth flow")]
+    UnknownFlow,
+    #[error("Too many OAuth flows started for {key}; retry in {retry_after_seconds}s")]
+    RateLimited { key: String, retry_after_seconds: i64 },
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -265,11 +277,85 @@ impl SecureOAuthFlow {
     }
 }
 
+/// Limits on how quickly new OAuth flows may be started
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OAuthRateLimitConfig {
+    /// Flow starts allowed per window; also the burst size
+    pub max_flows_per_window: u32,
+    /// Time for an empty bucket to refill completely
+    pub window_seconds: i64,
+    /// Also charge a bucket per client IP, shared by every client ID calling from it
+    pub per_ip: bool,
+}
+
+impl Default for OAuthRateLimitConfig {
+    fn default() -> Self {
+        Self {
+            max_flows_per_window: 10,
+            window_seconds: 60,
+            per_ip: false,
+        }
+    }
+}
+
+/// Token bucket refilled continuously at `max_flows_per_window / window_seconds`
+#[derive(Debug, Clone)]
+struct FlowBucket {
+    tokens: f64,
+    last_refill: DateTime<Utc>,
+}
+
+impl FlowBucket {
+    fn full(config: &OAuthRateLimitConfig, now: DateTime<Utc>) -> Self {
+        Self {
+            tokens: config.max_flows_per_window as f64,
+            last_refill: now,
+        }
+    }
+
+    fn refill(&mut self, config: &OAuthRateLimitConfig, now: DateTime<Utc>) {
+        let elapsed = (now - self.last_refill).num_milliseconds().max(0) as f64 / 1000.0;
+        let capacity = config.max_flows_per_window as f64;
+        self.tokens = (self.tokens + elapsed * refill_rate(config)).min(capacity);
+        self.last_refill = now;
+    }
+
+    fn is_full(&self, config: &OAuthRateLimitConfig) -> bool {
+        self.tokens >= config.max_flows_per_window as f64
+    }
+}
+
+fn refill_rate(config: &OAuthRateLimitConfig) -> f64 {
+    config.max_flows_per_window as f64 / config.window_seconds.max(1) as f64
+}
+
+/// Default lifetime of a pending OAuth flow
+pub const DEFAULT_FLOW_TTL_MINUTES: i64 = 10;
+
+/// How often [`OAuthSecurityManager::spawn_flow_pruner`] prunes by default
+pub const DEFAULT_FLOW_PRUNE_INTERVAL: std::time::Duration = std::time::Duration::from_secs(60);
+
+/// Why a flow is no longer pending
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum FlowRetirement {
+    Expired,
+    Consumed,
+}
+
 /// OAuth Security Manager for handling multiple concurrent flows
+///
+/// Each flow accepts exactly one callback within its TTL. Finished flows are remembered
+/// for one more TTL so late or replayed callbacks get a precise error instead of
+/// [`OAuthSecurityError::UnknownFlow`].
 #[derive(Debug)]
 pub struct OAuthSecurityManager {
     active_flows: HashMap<String, SecureOAuthFlow>,
+    retired_flows: HashMap<String, (FlowRetirement, DateTime<Utc>)>,
     max_concurrent_flows: usize,
+    flow_ttl: Duration,
+    rate_limit: OAuthRateLimitConfig,
+    buckets: HashMap<String, FlowBucket>,
+    clock: Arc<dyn Clock>,
 }
 
 impl OAuthSecurityManager {
@@ -277,21 +363,59 @@ impl OAuthSecurityManager {
     pub fn new(max_concurrent_flows: usize) -> Self {
         Self {
             active_flows: HashMap::new(),
+            retired_flows: HashMap::new(),
             max_concurrent_flows,
+            flow_ttl: Duration::minutes(DEFAULT_FLOW_TTL_MINUTES),
+            rate_limit: OAuthRateLimitConfig::default(),
+            buckets: HashMap::new(),
+            clock: system_clock(),
         }
     }
 
+    /// Cap how fast flows may be started per client (and per IP when enabled)
+    pub fn with_rate_limit(mut self, rate_limit: OAuthRateLimitConfig) -> Self {
+        self.rate_limit = rate_limit;
+        self.buckets.clear();
+        self
+    }
+
+    /// Reject callbacks for flows older than `ttl`
+    pub fn with_flow_ttl(mut self, ttl: Duration) -> Self {
+        self.flow_ttl = ttl;
+        self
+    }
+
+    /// Read the time from `clock` for rate limiting and flow expiry
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
     /// Start new OAuth flow
     pub fn start_flow(&mut self, client_id: String, redirect_uri: String) -> Result<String, OAuthSecurityError> {
+        self.start_flow_from(client_id, redirect_uri, None)
+    }
+
+    /// Start new OAuth flow on behalf of a caller at `ip_address`
+    pub fn start_flow_from(
+        &mut self,
+        client_id: String,
+        redirect_uri: String,
+        ip_address: Option<&str>,
+    ) -> Result<String, OAuthSecurityError> {
         // Clean up expired flows
-        self.cleanup_expired_flows();
+        self.prune_expired_flows();
 
-        // Check concurrent flow limit
+        // Check concurrent flow limit before spending a flow start on a refused attempt
         if self.active_flows.len() >= self.max_concurrent_flows {
             return Err(OAuthSecurityError::CryptographicError("Too many concurrent OAuth flows".to_string()));
         }
 
-        let flow = SecureOAuthFlow::new(client_id, redirect_uri)?;
+        self.take_flow_token(&client_id, ip_address)?;
+
+        let mut flow = SecureOAuthFlow::new(client_id, redirect_uri)?;
+        flow.created_at = self.clock.now();
+        flow.expires_at = flow.created_at + self.flow_ttl;
         let session_id = flow.session_id.clone();
         
         self.active_flows.insert(session_id.clone(), flow);
@@ -305,7 +429,39 @@ impl OAuthSecurityManager {
 
     /// Complete OAuth flow and remove from active flows
     pub fn complete_flow(&mut self, session_id: &str) -> Option<SecureOAuthFlow> {
-        self.active_flows.remove(session_id)
+        self.consume_flow(session_id).ok()
+    }
+
+    /// Take the flow for a callback; a flow can be taken once, and only before it expires
+    pub fn consume_flow(&mut self, session_id: &str) -> Result<SecureOAuthFlow, OAuthSecurityError> {
+        let now = self.clock.now();
+        let Some(flow) = self.active_flows.remove(session_id) else {
+            return Err(match self.retired_flows.get(session_id) {
+                Some((FlowRetirement::Expired, _)) => OAuthSecurityError::FlowExpired,
+                Some((FlowRetirement::Consumed, _)) => OAuthSecurityError::FlowAlreadyConsumed,
+                None => OAuthSecurityError::UnknownFlow,
+            });
+        };
+
+        if now > flow.expires_at {
+            self.retire(session_id, FlowRetirement::Expired, now);
+            return Err(OAuthSecurityError::FlowExpired);
+        }
+        self.retire(session_id, FlowRetirement::Consumed, now);
+        Ok(flow)
+    }
+
+    /// Consume the flow and validate its callback parameters
+    ///
+    /// The flow is used up even if validation fails, so a callback can never be retried.
+    pub fn validate_callback(
+        &mut self,
+        session_id: &str,
+        code: &str,
+        state: &str,
+        error: Option<&str>,
+    ) -> Result<TokenExchangeRequest, OAuthSecurityError> {
+        self.consume_flow(session_id)?.validate_callback(code, state, error)
     }
 
     /// Cancel OAuth flow
@@ -313,10 +469,101 @@ impl OAuthSecurityManager {
         self.active_flows.remove(session_id).is_some()
     }
 
-    /// Clean up expired flows
-    fn cleanup_expired_flows(&mut self) {
-        let now = Utc::now();
-        self.active_flows.retain(|_, flow| now <= flow.expires_at);
+    /// Spend one flow start from the client's bucket and, with `per_ip`, the IP's bucket
+    ///
+    /// Nothing is spent unless every charged bucket has a flow start left.
+    fn take_flow_token(&mut self, client_id: &str, ip_address: Option<&str>) -> Result<(), OAuthSecurityError> {
+        let now = self.clock.now();
+        let config = &self.rate_limit;
+
+        // Full buckets carry no state worth keeping
+        self.buckets.retain(|_, bucket| {
+            bucket.refill(config, now);
+            !bucket.is_full(config)
+        });
+
+        let mut charged = vec![(format!("client:{}", client_id), client_id)];
+        if let Some(ip) = ip_address.filter(|_| config.per_ip) {
+            charged.push((format!("ip:{}", ip), ip));
+        }
+        let exhausted = charged.iter().find_map(|(bucket_key, key)| {
+            let tokens = self.buckets.get(bucket_key).map_or(config.max_flows_per_window as f64, |b| b.tokens);
+            (tokens < 1.0).then_some((key.to_string(), tokens))
+        });
+
+        let Some((key, tokens)) = exhausted else {
+            for (bucket_key, _) in charged {
+                self.buckets
+                    .entry(bucket_key)
+                    .or_insert_with(|| FlowBucket::full(config, now))
+                    .tokens -= 1.0;
+            }
+            return Ok(());
+        };
+
+        let retry_after_seconds = ((1.0 - tokens) / refill_rate(config)).ceil() as i64;
+        let _ = audit_logger::log_audit_event(audit_logger::AuditEvent {
+            timestamp: now,
+            event_type: audit_logger::AuthEventType::RateLimited,
+            user_id: None,
+            session_id: None,
+            client_id: Some(client_id.to_string()),
+            ip_address: ip_address.map(str::to_string),
+            user_agent: None,
+            success: false,
+            error_message: Some("OAuth flow start rate limit exceeded".to_string()),
+            metadata: serde_json::json!({
+                "max_flows_per_window": config.max_flows_per_window,
+                "window_seconds": config.window_seconds,
+                "retry_after_seconds": retry_after_seconds,
+            }),
+            severity: audit_logger::Severity::Warning,
+        });
+
+        Err(OAuthSecurityError::RateLimited { key, retry_after_seconds })
+    }
+
+    /// Drop expired pending flows and forget old retired ones; returns how many expired
+    pub fn prune_expired_flows(&mut self) -> usize {
+        let now = self.clock.now();
+        let expired: Vec<String> = self
+            .active_flows
+            .iter()
+            .filter(|(_, flow)| now > flow.expires_at)
+            .map(|(session_id, _)| session_id.clone())
+            .collect();
+
+        for session_id in &expired {
+            self.active_flows.remove(session_id);
+            self.retire(session_id, FlowRetirement::Expired, now);
+        }
+        self.retired_flows.retain(|_, (_, forget_at)| now <= *forget_at);
+        expired.len()
+    }
+
+    /// Prune `manager` every `period` until it is dropped
+    pub fn spawn_flow_pruner(
+        manager: Arc<tokio::sync::Mutex<OAuthSecurityManager>>,
+        period: std::time::Duration,
+    ) -> tokio::task::JoinHandle<()> {
+        let manager = Arc::downgrade(&manager);
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(period);
+            // The first tick completes immediately
+            ticker.tick().await;
+            loop {
+                ticker.tick().await;
+                let Some(manager) = manager.upgrade() else {
+                    break;
+                };
+                manager.lock().await.prune_expired_flows();
+            }
+        })
+    }
+
+    fn retire(&mut self, session_id: &str, reason: FlowRetirement, now: DateTime<Utc>) {
+        self.retired_flows
+            .insert(session_id.to_string(), (reason, now + self.flow_ttl));
     }
 
     /// Get number of active flows
@@ -408,6 +655,159 @@ mod tests {
         assert_eq!(manager.active_flow_count(), 2);
     }
 
+    fn start(manager: &mut OAuthSecurityManager, client_id: &str, ip: Option<&str>) -> Result<String, OAuthSecurityError> {
+        manager.start_flow_from(client_id.to_string(), "http://localhost:1455/callback".to_string(), ip)
+    }
+
+    #[test]
+    fn test_flow_starts_are_rate_limited_per_client() {
+        let clock = crate::security::MockClock::default();
+        let mut manager = OAuthSecurityManager::new(100)
+            .with_rate_limit(OAuthRateLimitConfig {
+                max_flows_per_window: 3,
+                window_seconds: 60,
+                per_ip: false,
+            })
+            .with_clock(Arc::new(clock.clone()));
+
+        for _ in 0..3 {
+            start(&mut manager, "scripted", None).unwrap();
+        }
+        let limited = start(&mut manager, "scripted", None);
+        assert!(matches!(
+            limited,
+            Err(OAuthSecurityError::RateLimited { retry_after_seconds: 20, .. })
+        ));
+
+        // Other clients have their own bucket
+        start(&mut manager, "other", None).unwrap();
+
+        // One token back after a third of the window, a full burst after the whole window
+        clock.advance(Duration::seconds(20));
+        start(&mut manager, "scripted", None).unwrap();
+        assert!(start(&mut manager, "scripted", None).is_err());
+
+        clock.advance(Duration::seconds(60));
+        for _ in 0..3 {
+            start(&mut manager, "scripted", None).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_per_ip_buckets_and_abandoned_flows_age_out() {
+        let clock = crate::security::MockClock::default();
+        let mut manager = OAuthSecurityManager::new(2)
+            .with_rate_limit(OAuthRateLimitConfig {
+                max_flows_per_window: 1,
+                window_seconds: 60,
+                per_ip: true,
+            })
+            .with_clock(Arc::new(clock.clone()));
+
+        start(&mut manager, "cli", Some("10.0.0.1")).unwrap();
+        // Another client behind the same IP shares its bucket
+        assert!(matches!(
+            start(&mut manager, "ide", Some("10.0.0.1")),
+            Err(OAuthSecurityError::RateLimited { ref key, .. }) if key == "10.0.0.1"
+        ));
+        // And a client moving to another IP keeps its own
+        assert!(matches!(
+            start(&mut manager, "cli", Some("10.0.0.2")),
+            Err(OAuthSecurityError::RateLimited { ref key, .. }) if key == "cli"
+        ));
+        start(&mut manager, "ide", Some("10.0.0.2")).unwrap();
+
+        // Both abandoned flows hold the concurrency slots until they expire
+        clock.advance(Duration::seconds(61));
+        assert!(start(&mut manager, "cli", Some("10.0.0.3")).is_err());
+        clock.advance(Duration::minutes(10));
+        start(&mut manager, "cli", Some("10.0.0.1")).unwrap();
+        assert_eq!(manager.active_flow_count(), 1);
+    }
+
+    #[test]
+    fn test_refused_concurrent_flow_spends_no_flow_start() {
+        let clock = crate::security::MockClock::default();
+        let mut manager = OAuthSecurityManager::new(1)
+            .with_rate_limit(OAuthRateLimitConfig {
+                max_flows_per_window: 2,
+                window_seconds: 60,
+                per_ip: false,
+            })
+            .with_clock(Arc::new(clock.clone()));
+
+        let session_id = start(&mut manager, "cli", None).unwrap();
+        for _ in 0..3 {
+            assert!(matches!(
+                start(&mut manager, "cli", None),
+                Err(OAuthSecurityError::CryptographicError(_))
+            ));
+        }
+        assert!(manager.cancel_flow(&session_id));
+        start(&mut manager, "cli", None).unwrap();
+    }
+
+    fn manager_with_clock(clock: &crate::security::MockClock) -> OAuthSecurityManager {
+        OAuthSecurityManager::new(3)
+            .with_flow_ttl(Duration::minutes(5))
+            .with_clock(Arc::new(clock.clone()))
+    }
+
+    fn state_of(manager: &OAuthSecurityManager, session_id: &str) -> String {
+        manager.get_flow(session_id).unwrap().state_parameter.clone()
+    }
+
+    #[test]
+    fn test_callback_after_expiry_is_rejected() {
+        let clock = crate::security::MockClock::default();
+        let mut manager = manager_with_clock(&clock);
+        let session_id = start(&mut manager, "client", None).unwrap();
+        let state = state_of(&manager, &session_id);
+
+        clock.advance(Duration::minutes(5) + Duration::seconds(1));
+        let result = manager.validate_callback(&session_id, "authorization_code", &state, None);
+        assert!(matches!(result, Err(OAuthSecurityError::FlowExpired)));
+    }
+
+    #[test]
+    fn test_callback_is_single_use() {
+        let clock = crate::security::MockClock::default();
+        let mut manager = manager_with_clock(&clock);
+        let session_id = start(&mut manager, "client", None).unwrap();
+        let state = state_of(&manager, &session_id);
+
+        let request = manager
+            .validate_callback(&session_id, "authorization_code", &state, None)
+            .unwrap();
+        assert_eq!(request.code, "authorization_code");
+        assert_eq!(manager.active_flow_count(), 0);
+
+        let replay = manager.validate_callback(&session_id, "authorization_code", &state, None);
+        assert!(matches!(replay, Err(OAuthSecurityError::FlowAlreadyConsumed)));
+        assert!(matches!(
+            manager.validate_callback("oauth_unknown", "authorization_code", &state, None),
+            Err(OAuthSecurityError::UnknownFlow)
+        ));
+    }
+
+    #[test]
+    fn test_prune_bounds_pending_and_retired_flows() {
+        let clock = crate::security::MockClock::default();
+        let mut manager = manager_with_clock(&clock);
+        let abandoned = start(&mut manager, "client", None).unwrap();
+        start(&mut manager, "client", None).unwrap();
+
+        clock.advance(Duration::minutes(6));
+        assert_eq!(manager.prune_expired_flows(), 2);
+        assert_eq!(manager.active_flow_count(), 0);
+        assert!(matches!(manager.consume_flow(&abandoned), Err(OAuthSecurityError::FlowExpired)));
+
+        // Tombstones are kept for one more TTL, then forgotten
+        clock.advance(Duration::minutes(6));
+        manager.prune_expired_flows();
+        assert!(manager.retired_flows.is_empty());
+    }
+
     #[test]
     fn test_pkce_challenge_generation() {
         let verifier = "dBjftJeZ4CVP-mB92K27uhbUJU1p1r_wW1gFWFOEjXk";
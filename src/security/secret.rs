@@ -0,0 +1,155 @@
+//! Secret values that never print in full and are wiped when dropped
+//!
+//! `Debug` output ends up in logs and panic messages, so anything holding a credential
+//! should format it through [`Secret`] or [`masked`]. Only a recognizable vendor prefix
+//! such as `sk-ant-` survives masking; the rest becomes `***`.
+//!
+//! [`Secret`] also zeroizes its value on drop, including when a field holding one is
+//! reassigned, so freed credentials don't linger in memory or core dumps.
+
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use zeroize::Zeroize;
+
+/// Placeholder printed in place of secret material
+const MASK: &str = "***";
+
+/// Values shorter than this are masked entirely, prefix included
+const MIN_LEN_FOR_PREFIX: usize = 16;
+
+/// Lowercase `-`-terminated groups kept at the front of a masked value, e.g. `sk-ant-`
+const MAX_PREFIX_GROUPS: usize = 2;
+
+/// A value whose `Debug` output is masked and whose memory is zeroed on drop; use
+/// [`expose_secret`](Self::expose_secret) where the real value is needed
+#[derive(Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Secret<T: Zeroize>(T);
+
+impl<T: Zeroize> Secret<T> {
+    pub fn new(value: T) -> Self {
+        Self(value)
+    }
+
+    /// The wrapped value; keep it out of anything that gets logged
+    pub fn expose_secret(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T: Zeroize> Drop for Secret<T> {
+    fn drop(&mut self) {
+        self.0.zeroize();
+    }
+}
+
+impl<T: Zeroize> From<T> for Secret<T> {
+    fn from(value: T) -> Self {
+        Self(value)
+    }
+}
+
+impl From<&str> for Secret<String> {
+    fn from(value: &str) -> Self {
+        Self(value.to_string())
+    }
+}
+
+impl<T: Zeroize + AsRef<str>> fmt::Debug for Secret<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&masked(self.0.as_ref()), f)
+    }
+}
+
+/// Debug-formats as the masked form of `value`, for hand-written `Debug` impls
+pub fn masked(value: &str) -> impl fmt::Debug + '_ {
+    Masked(value)
+}
+
+struct Masked<'a>(&'a str);
+
+impl fmt::Debug for Masked<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "\"{}\"", mask_secret(self.0))
+    }
+}
+
+/// Masked form of a secret, keeping at most a short vendor prefix such as `sk-ant-`
+pub fn mask_secret(value: &str) -> String {
+    if value.len() < MIN_LEN_FOR_PREFIX {
+        return MASK.to_string();
+    }
+
+    let mut prefix_len = 0;
+    for group in value.split('-').take(MAX_PREFIX_GROUPS) {
+        let is_label = (1..=4).contains(&group.len()) && group.bytes().all(|b| b.is_ascii_lowercase());
+        if !is_label || prefix_len + group.len() + 1 > value.len() {
+            break;
+        }
+        prefix_len += group.len() + 1;
+    }
+
+    format!("{}{}", &value[..prefix_len], MASK)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    #[test]
+    fn test_mask_keeps_only_vendor_prefix() {
+        assert_eq!(mask_secret("sk-ant-REDACTED"), "sk-ant-***");
+        assert_eq!(mask_secret("sk-proj-ABCDEFGHIJKLMNOPQRST"), "sk-proj-***");
+        assert_eq!(mask_secret("eyJhbGciOiJIUzI1NiJ9.payload.sig"), "***");
+        assert_eq!(mask_secret("short-key"), "***");
+        assert_eq!(mask_secret(""), "***");
+    }
+
+    #[test]
+    fn test_secret_debug_is_masked_but_value_is_exposed() {
+        let secret = Secret::new("sk-ant-REDACTED".to_string());
+        assert_eq!(format!("{:?}", secret), "\"sk-ant-***\"");
+        assert_eq!(format!("{:?}", Some(&secret)), "Some(\"sk-ant-***\")");
+        assert_eq!(secret.expose_secret(), "sk-ant-REDACTED");
+        assert_eq!(serde_json::to_string(&secret).unwrap(), "\"sk-ant-REDACTED\"");
+    }
+
+    /// Secret bytes in a buffer that outlives the `Secret`, so they can be inspected
+    /// after drop without reading freed memory
+    struct ProbeBuffer(Arc<Mutex<Vec<u8>>>);
+
+    impl Zeroize for ProbeBuffer {
+        fn zeroize(&mut self) {
+            // Wipe in place, keeping the length so the test can see the bytes
+            self.0.lock().unwrap().as_mut_slice().zeroize();
+        }
+    }
+
+    fn probe(bytes: &[u8]) -> Arc<Mutex<Vec<u8>>> {
+        Arc::new(Mutex::new(bytes.to_vec()))
+    }
+
+    fn is_wiped(buffer: &Arc<Mutex<Vec<u8>>>) -> bool {
+        let bytes = buffer.lock().unwrap();
+        !bytes.is_empty() && bytes.iter().all(|&b| b == 0)
+    }
+
+    #[test]
+    fn test_secret_is_zeroed_on_drop_and_reassignment() {
+        let dropped = probe(b"sk-ant-api03-secret");
+        let secret = Secret::new(ProbeBuffer(dropped.clone()));
+        assert!(!is_wiped(&dropped));
+        drop(secret);
+        assert!(is_wiped(&dropped));
+
+        let replaced = probe(b"first-token");
+        let replacement = probe(b"second-token");
+        let mut slot = Secret::new(ProbeBuffer(replaced.clone()));
+        assert!(Arc::ptr_eq(&slot.expose_secret().0, &replaced));
+        slot =Secret::new(ProbeBuffer(replacement.clone()));
+        assert!(is_wiped(&replaced));
+        assert!(!is_wiped(&replacement));
+        drop(slot);
+    }
+}
@@ -0,0 +1,170 @@
+//! Indirect references to secrets kept outside the config files
+//!
+//! Wherever an API key is expected, `file:/path/to/secret` reads it from a file, as
+//! exposed by Docker secrets or Vault Agent, and `env:VAR` reads it from an environment
+//! variable. Any other value is the key itself. References are resolved when credentials
+//! are loaded, and again on refresh so a rotated secret file is picked up.
+
+use std::fmt;
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+
+use super::secret::Secret;
+
+/// Prefix of a reference to a secret file
+pub const FILE_PREFIX: &str = "file:";
+
+/// Prefix of a reference to an environment variable
+pub const ENV_PREFIX: &str = "env:";
+
+/// Where a configured secret value comes from
+#[derive(Clone, PartialEq, Eq)]
+pub enum SecretRef {
+    /// The value is the secret itself
+    Inline(Secret<String>),
+    /// Read from this file, trimmed of surrounding whitespace
+    File(PathBuf),
+    /// Read from this environment variable
+    Env(String),
+}
+
+impl fmt::Debug for SecretRef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SecretRef::Inline(secret) => f.debug_tuple("Inline").field(secret).finish(),
+            SecretRef::File(path) => f.debug_tuple("File").field(path).finish(),
+            SecretRef::Env(var) => f.debug_tuple("Env").field(var).finish(),
+        }
+    }
+}
+
+/// Why a secret reference couldn't be resolved
+#[derive(Debug, Error)]
+pub enum SecretRefError {
+    #[error("secret file {} can't be read: {source}", path.display())]
+    Unreadable { path: PathBuf, source: std::io::Error },
+    #[error("secret file {} is accessible to other users (mode {mode:o}); run `chmod 600` on it", path.display())]
+    InsecurePermissions { path: PathBuf, mode: u32 },
+    #[error("secret file {} is empty", path.display())]
+    EmptyFile { path: PathBuf },
+    #[error("environment variable {var} referenced by a secret is not set")]
+    UnsetEnv { var: String },
+}
+
+impl SecretRef {
+    /// Reference named by a configured value
+    pub fn parse(value: &str) -> Self {
+        if let Some(path) = value.strip_prefix(FILE_PREFIX) {
+            SecretRef::File(PathBuf::from(path))
+        } else if let Some(var) = value.strip_prefix(ENV_PREFIX) {
+            SecretRef::Env(var.to_string())
+        } else {
+            SecretRef::Inline(value.into())
+        }
+    }
+
+    /// Whether the secret lives outside the configured value, so a refresh may change it
+    pub fn is_indirect(&self) -> bool {
+        !matches!(self, SecretRef::Inline(_))
+    }
+
+    /// The referenced secret, read now
+    pub fn resolve(&self) -> Result<Secret<String>, SecretRefError> {
+        self.resolve_with_env(|var| std::env::var(var).ok())
+    }
+
+    /// [`resolve`](Self::resolve) reading environment variables through `env`
+    pub fn resolve_with_env(&self, env: impl Fn(&str) -> Option<String>) -> Result<Secret<String>, SecretRefError> {
+        match self {
+            SecretRef::Inline(secret) => Ok(secret.clone()),
+            SecretRef::File(path) => read_secret_file(path),
+            SecretRef::Env(var) => env(var)
+                .map(|value| value.trim().to_string())
+                .filter(|value| !value.is_empty())
+                .map(Secret::new)
+                .ok_or_else(|| SecretRefError::UnsetEnv { var: var.clone() }),
+        }
+    }
+}
+
+/// Resolve a configured value that may be a `file:` or `env:` reference
+pub fn resolve_secret(value: &str) -> Result<Secret<String>, SecretRefError> {
+    SecretRef::parse(value).resolve()
+}
+
+fn read_secret_file(path: &Path) -> Result<Secret<String>, SecretRefError> {
+    let unreadable = |source| SecretRefError::Unreadable { path: path.to_path_buf(), source };
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+
+        // Group access is left alone: secret mounts commonly share a group with the reader
+        let mode = std::fs::metadata(path).map_err(unreadable)?.permissions().mode() & 0o777;
+        if mode & 0o007 != 0 {
+            return Err(SecretRefError::InsecurePermissions { path: path.to_path_buf(), mode });
+        }
+    }
+
+    let content = std::fs::read_to_string(path).map_err(unreadable)?;
+    let secret = content.trim();
+    if secret.is_empty() {
+        return Err(SecretRefError::EmptyFile { path: path.to_path_buf() });
+    }
+    Ok(Secret::new(secret.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_resolves_inline_env_and_file_references() {
+        assert_eq!(resolve_secret("sk-ant-inline").unwrap().expose_secret(), "sk-ant-inline");
+
+        let env = |var: &str| (var == "CLAUDE_KEY").then(|| "sk-ant-from-env\n".to_string());
+        let from_env = SecretRef::parse("env:CLAUDE_KEY").resolve_with_env(env).unwrap();
+        assert_eq!(from_env.expose_secret(), "sk-ant-from-env");
+        assert!(matches!(
+            SecretRef::parse("env:MISSING").resolve_with_env(env),
+            Err(SecretRefError::UnsetEnv { var }) if var == "MISSING"
+        ));
+
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path().join("claude_api_key");
+        std::fs::write(&path, "sk-ant-from-file\n").unwrap();
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o640)).unwrap();
+        }
+        let reference = SecretRef::parse(&format!("file:{}", path.display()));
+        assert!(reference.is_indirect());
+        assert_eq!(reference.resolve().unwrap().expose_secret(), "sk-ant-from-file");
+
+        // A rotated file is picked up by the next resolution
+        std::fs::write(&path, "sk-ant-rotated").unwrap();
+        assert_eq!(reference.resolve().unwrap().expose_secret(), "sk-ant-rotated");
+    }
+
+    #[test]
+    fn test_missing_or_exposed_secret_file_is_refused() {
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path().join("missing");
+        let error = resolve_secret(&format!("file:{}", path.display())).unwrap_err();
+        assert!(matches!(error, SecretRefError::Unreadable { .. }));
+        assert!(error.to_string().starts_with(&format!("secret file {} can't be read", path.display())));
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let path = temp_dir.path().join("world_readable");
+            std::fs::write(&path, "sk-ant-exposed").unwrap();
+            std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o644)).unwrap();
+            let error = resolve_secret(&format!("file:{}", path.display())).unwrap_err();
+            assert!(matches!(error, SecretRefError::InsecurePermissions { mode: 0o644, .. }));
+            assert!(error.to_string().contains("mode 644"), "{}", error);
+        }
+    }
+}
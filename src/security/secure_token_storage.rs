@@ -1,9 +1,12 @@
 use std::fs::{File, OpenOptions};
 use std::io::{Read, Write};
 use std::path::{Path, PathBuf};
+use std::sync::Mutex;
 use serde::{Deserialize, Serialize};
 use chrono::{DateTime, Utc};
 use thiserror::Error;
+use super::atomic_write::write_atomic;
+use super::secret::Secret;
 
 #[cfg(unix)]
 use std::os::unix::fs::PermissionsExt;
@@ -11,10 +14,95 @@ use std::os::unix::fs::PermissionsExt;
 /// Enhanced secure token storage with encryption and proper file permissions
 #[derive(Debug)]
 pub struct SecureTokenStorage {
-    encryption_key: [u8; 32],
+    backend: Backend,
     storage_path: PathBuf,
 }
 
+/// What to do when no encryption key can be derived
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EncryptionUnavailablePolicy {
+    /// Refuse to create the storage
+    #[default]
+    Fail,
+    /// Write tokens unencrypted to a clearly named 0o600 file next to the configured path
+    WarnAndUsePlaintext,
+    /// Keep tokens in memory for the lifetime of the process only
+    WarnAndDisableStorage,
+}
+
+/// What to do about overly permissive token files found at startup
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PermissionCheckPolicy {
+    /// Don't inspect permissions
+    Skip,
+    /// Log a warning and an audit event, leaving the files as they are
+    #[default]
+    Warn,
+    /// Tighten files to 0o600 and their directory to 0o700
+    Repair,
+}
+
+/// How a [`SecureTokenStorage`] holds its tokens
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StorageMode {
+    Encrypted,
+    Plaintext,
+    MemoryOnly,
+}
+
+#[derive(Debug)]
+enum Backend {
+    Encrypted([u8; 32]),
+    Plaintext,
+    Memory(Mutex<Option<TokenData>>),
+}
+
+/// Marker written at the top of plaintext token files
+pub const PLAINTEXT_WARNING: &str =
+    "UNENCRYPTED: encryption was unavailable when these tokens were saved; delete this file once it is fixed";
+
+/// On-disk layout under [`EncryptionUnavailablePolicy::WarnAndUsePlaintext`]
+#[derive(Debug, Serialize, Deserialize)]
+struct PlaintextTokenFile {
+    warning: String,
+    tokens: TokenData,
+}
+
+/// Mode required for token files
+pub const TOKEN_FILE_MODE: u32 = 0o600;
+/// Mode required for the directory holding token files
+pub const TOKEN_DIR_MODE: u32 = 0o700;
+
+/// A token file or directory that other users can access
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PermissionIssue {
+    pub path: PathBuf,
+    /// Permission bits found on disk
+    pub mode: u32,
+    /// Mode [`SecureTokenStorage::repair_permissions`] sets
+    pub expected: u32,
+}
+
+/// Result of [`SecureTokenStorage::check_permissions`] or a repair
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PermissionReport {
+    /// Paths whose permissions were inspected
+    pub checked: Vec<PathBuf>,
+    /// Overly permissive paths; after a repair, the ones that were tightened
+    pub issues: Vec<PermissionIssue>,
+    /// False where Unix modes don't apply (Windows) and nothing was inspected
+    pub supported: bool,
+}
+
+impl PermissionReport {
+    pub fn is_secure(&self) -> bool {
+        self.issues.is_empty()
+    }
+}
+
 #[derive(Debug, Error)]
 pub enum SecureStorageError {
     #[error("IO error: {0}")]
@@ -36,11 +124,12 @@ pub struct EncryptedTokenData {
     pub version: u32,
 }
 
+/// Tokens as handed to and returned from storage; the token strings are wiped on drop
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TokenData {
-    pub access_token: String,
-    pub refresh_token: String,
-    pub id_token: String,
+    pub access_token: Secret<String>,
+    pub refresh_token: Secret<String>,
+    pub id_token: Secret<String>,
     pub expires_at: DateTime<Utc>,
     pub account_id: Option<String>,
     pub provider: String,
@@ -49,21 +138,212 @@ pub struct TokenData {
 impl SecureTokenStorage {
     /// Create a new secure token storage instance
     pub fn new(storage_path: PathBuf) -> Result<Self, SecureStorageError> {
-        let encryption_key = Self::derive_encryption_key(&storage_path)?;
-        
-        Ok(Self {
-            encryption_key,
-            storage_path,
+        Self::with_policy(storage_path, EncryptionUnavailablePolicy::Fail)
+    }
+
+    /// Create storage, falling back according to `policy` if no encryption key can be derived
+    ///
+    /// A key saved by [`Self::rotate_encryption_key`] takes precedence over the derived one.
+    pub fn with_policy(storage_path: PathBuf, policy: EncryptionUnavailablePolicy) -> Result<Self, SecureStorageError> {
+        let key = match Self::load_rotated_key(&storage_path) {
+            Ok(Some(key)) => Ok(key),
+            Ok(None) => Self::derive_encryption_key(&storage_path),
+            Err(e) => Err(e),
+        };
+        Self::from_derived_key(storage_path, key, policy)
+    }
+
+    fn from_derived_key(
+        storage_path: PathBuf,
+        key: Result<[u8; 32], SecureStorageError>,
+        policy: EncryptionUnavailablePolicy,
+    ) -> Result<Self, SecureStorageError> {
+        let error = match key {
+            Ok(key) => {
+                return Ok(Self {
+                    backend: Backend::Encrypted(key),
+                    storage_path,
+                })
+            }
+            Err(e) => e,
+        };
+
+        let storage = match policy {
+            EncryptionUnavailablePolicy::Fail => return Err(error),
+            EncryptionUnavailablePolicy::WarnAndUsePlaintext => Self {
+                backend: Backend::Plaintext,
+                storage_path: plaintext_path(&storage_path),
+            },
+            EncryptionUnavailablePolicy::WarnAndDisableStorage => Self {
+                backend: Backend::Memory(Mutex::new(None)),
+                storage_path,
+            },
+        };
+        storage.warn_degraded(&error);
+        Ok(storage)
+    }
+
+    fn warn_degraded(&self, cause: &SecureStorageError) {
+        let consequence = match self.mode() {
+            StorageMode::Plaintext => format!("tokens will be written unencrypted to {}", self.storage_path.display()),
+            _ => "tokens will be kept in memory and lost on exit".to_string(),
+        };
+        tracing::warn!("Token encryption unavailable ({}); {}", cause, consequence);
+
+        super::audit_logger::log_audit_event(super::audit_logger::AuditEvent {
+            timestamp: Utc::now(),
+            event_type: super::audit_logger::AuthEventType::SecurityViolation,
+            user_id: None,
+            session_id: None,
+            client_id: None,
+            ip_address: None,
+            user_agent: None,
+            success: false,
+            error_message: Some(format!("Token encryption unavailable: {}", cause)),
+            metadata: serde_json::json!({
+                "storage_mode": self.mode(),
+                "path": self.storage_path,
+                "consequence": consequence,
+            }),
+            severity: super::audit_logger::Severity::Critical,
+        })
+        .ok();
+    }
+
+    /// How tokens are held; anything but [`StorageMode::Encrypted`] is a degraded fallback
+    pub fn mode(&self) -> StorageMode {
+        match self.backend {
+            Backend::Encrypted(_) => StorageMode::Encrypted,
+            Backend::Plaintext => StorageMode::Plaintext,
+            Backend::Memory(_) => StorageMode::MemoryOnly,
+        }
+    }
+
+    /// File the tokens are written to; unused while [`StorageMode::MemoryOnly`]
+    pub fn storage_path(&self) -> &Path {
+        &self.storage_path
+    }
+
+    /// Find token files or their directory that group or other users can access
+    ///
+    /// Files copied between machines often lose their 0o600 mode. Problems are recorded
+    /// in the audit log; nothing is changed. Windows ACLs are not inspected, and the
+    /// report says so through `supported`.
+    pub fn check_permissions(&self) -> PermissionReport {
+        let report = self.inspect_permissions();
+        if !report.is_secure() {
+            self.audit_permissions(&report, false);
+        }
+        report
+    }
+
+    /// Tighten token files to 0o600 and their directory to 0o700
+    ///
+    /// Returns the issues that were fixed. A no-op on Windows, where the report comes
+    /// back with `supported` unset.
+    pub fn repair_permissions(&self) -> Result<PermissionReport, SecureStorageError> {
+        let report = self.inspect_permissions();
+        #[cfg(unix)]
+        for issue in &report.issues {
+            std::fs::set_permissions(&issue.path, std::fs::Permissions::from_mode(issue.expected))?;
+        }
+        #[cfg(not(unix))]
+        tracing::warn!("Token file permission repair is not supported on this platform; nothing was changed");
+
+        if !report.is_secure() {
+            self.audit_permissions(&report, true);
+        }
+        Ok(report)
+    }
+
+    #[cfg(unix)]
+    fn inspect_permissions(&self) -> PermissionReport {
+        let mut report = PermissionReport { supported: true, ..Default::default() };
+        if matches!(self.backend, Backend::Memory(_)) {
+            return report;
+        }
+
+        let directory = self.storage_path.parent().filter(|dir| !dir.as_os_str().is_empty());
+        let candidates = directory
+            .map(|dir| (dir.to_path_buf(), TOKEN_DIR_MODE))
+            .into_iter()
+            .chain(std::iter::once((self.storage_path.clone(), TOKEN_FILE_MODE)))
+            .chain(std::iter::once((key_path(&self.storage_path), TOKEN_FILE_MODE)));
+        for (path, expected) in candidates {
+            let Ok(metadata) = std::fs::metadata(&path) else {
+                continue;
+            };
+            let mode = metadata.permissions().mode() & 0o777;
+            if mode & 0o077 != 0 {
+                report.issues.push(PermissionIssue { path: path.clone(), mode, expected });
+            }
+            report.checked.push(path);
+        }
+        report
+    }
+
+    #[cfg(not(unix))]
+    fn inspect_permissions(&self) -> PermissionReport {
+        PermissionReport::default()
+    }
+
+    fn audit_permissions(&self, report: &PermissionReport, repaired: bool) {
+        let (event_type, severity, message) = if repaired {
+            (
+                super::audit_logger::AuthEventType::PermissionsRepaired,
+                super::audit_logger::Severity::Warning,
+                "Tightened permissions on token storage",
+            )
+        } else {
+            (
+                super::audit_logger::AuthEventType::SecurityViolation,
+                super::audit_logger::Severity::Error,
+                "Token storage is accessible to other users",
+            )
+        };
+
+        super::audit_logger::log_audit_event(super::audit_logger::AuditEvent {
+            timestamp: Utc::now(),
+            event_type,
+            user_id: None,
+            session_id: None,
+            client_id: None,
+            ip_address: None,
+            user_agent: None,
+            success: repaired,
+            error_message: (!repaired).then(|| message.to_string()),
+            metadata: serde_json::json!({
+                "message": message,
+                "issues": report.issues.iter().map(|issue| serde_json::json!({
+                    "path": issue.path,
+                    "mode": format!("{:o}", issue.mode),
+                    "expected": format!("{:o}", issue.expected),
+                })).collect::<Vec<_>>(),
+            }),
+            severity,
         })
+        .ok();
     }
 
     /// Store encrypted token data with secure file permissions
     pub fn store_tokens(&self, tokens: &TokenData) -> Result<(), SecureStorageError> {
-        // Serialize the token data
-        let json_data = serde_json::to_vec(tokens)?;
-        
-        // Encrypt the data
-        let encrypted_data = self.encrypt_data(&json_data)?;
+        let serialized = match self.backend {
+            Backend::Encrypted(_) => {
+                // Serialize the token data
+                let json_data = serde_json::to_vec(tokens)?;
+
+                // Encrypt the data
+                serde_json::to_vec(&self.encrypt_data(&json_data)?)?
+            }
+            Backend::Plaintext => serde_json::to_vec_pretty(&PlaintextTokenFile {
+                warning: PLAINTEXT_WARNING.to_string(),
+                tokens: tokens.clone(),
+            })?,
+            Backend::Memory(ref memory) => {
+                *memory.lock().unwrap() = Some(tokens.clone());
+                return Ok(());
+            }
+        };
         
         // Ensure parent directory exists
         if let Some(parent) = self.storage_path.parent() {
@@ -73,8 +353,6 @@ impl SecureTokenStorage {
         // Create file with secure permissions
         let mut file = self.create_secure_file()?;
         
-        // Write encrypted data
-        let serialized = serde_json::to_vec(&encrypted_data)?;
         file.write_all(&serialized)?;
         file.flush()?;
         
@@ -86,6 +364,21 @@ impl SecureTokenStorage {
 
     /// Retrieve and decrypt token data
     pub fn retrieve_tokens(&self) -> Result<Option<TokenData>, SecureStorageError> {
+        let tokens = self.peek_tokens()?;
+        if tokens.is_some() {
+            // Update last accessed time
+            self.update_access_time()?;
+        }
+        Ok(tokens)
+    }
+
+    /// Decrypt token data without recording the access
+    ///
+    /// Unlike [`Self::retrieve_tokens`] this never writes to the token file.
+    pub fn peek_tokens(&self) -> Result<Option<TokenData>, SecureStorageError> {
+        if let Backend::Memory(ref memory) = self.backend {
+            return Ok(memory.lock().unwrap().clone());
+        }
         if !self.storage_path.exists() {
             return Ok(None);
         }
@@ -101,6 +394,11 @@ impl SecureTokenStorage {
         if contents.is_empty() {
             return Ok(None);
         }
+
+        if let Backend::Plaintext = self.backend {
+            let file: PlaintextTokenFile = serde_json::from_slice(&contents)?;
+            return Ok(Some(file.tokens));
+        }
         
         // Deserialize encrypted data
         let encrypted_data: EncryptedTokenData = serde_json::from_slice(&contents)?;
@@ -111,14 +409,14 @@ impl SecureTokenStorage {
         // Deserialize token data
         let tokens: TokenData = serde_json::from_slice(&decrypted_data)?;
         
-        // Update last accessed time
-        self.update_access_time()?;
-        
         Ok(Some(tokens))
     }
 
     /// Delete stored tokens securely
     pub fn delete_tokens(&self) -> Result<bool, SecureStorageError> {
+        if let Backend::Memory(ref memory) = self.backend {
+            return Ok(memory.lock().unwrap().take().is_some());
+        }
         if !self.storage_path.exists() {
             return Ok(false);
         }
@@ -131,25 +429,84 @@ impl SecureTokenStorage {
 
     /// Check if tokens exist and are valid
     pub fn tokens_exist(&self) -> bool {
+        if let Backend::Memory(ref memory) = self.backend {
+            return memory.lock().unwrap().is_some();
+        }
         self.storage_path.exists() && self.verify_file_permissions().is_ok()
     }
 
     /// Rotate encryption key and re-encrypt stored data
+    ///
+    /// The fresh key is saved next to the token file (see [`key_path`]) and used from then
+    /// on instead of the derived one. The key file is replaced before the tokens; if the
+    /// tokens can't be rewritten the previous key file is put back, so the key on disk
+    /// always decrypts the tokens on disk. On error this storage keeps its old key.
     pub fn rotate_encryption_key(&mut self) -> Result<(), SecureStorageError> {
-        // Retrieve current tokens with old key
-        let tokens = self.retrieve_tokens()?;
-        
-        // Generate new encryption key
-        self.encryption_key = Self::generate_random_key();
-        
-        // Re-encrypt with new key if tokens exist
-        if let Some(tokens) = tokens {
-            self.store_tokens(&tokens)?;
+        self.rotate_encryption_key_with(write_atomic)
+    }
+
+    /// [`rotate_encryption_key`](Self::rotate_encryption_key) rewriting the tokens with `write_tokens`
+    fn rotate_encryption_key_with(
+        &mut self,
+        write_tokens: impl FnOnce(&Path, &[u8], u32) -> std::io::Result<()>,
+    ) -> Result<(), SecureStorageError> {
+        if self.mode() != StorageMode::Encrypted {
+            return Err(SecureStorageError::Encryption("storage is not encrypted".to_string()));
         }
-        
+
+        // Decrypt with the old key and encrypt under the new one before touching any file
+        let tokens = self.peek_tokens()?;
+        let rotated = Self {
+            backend: Backend::Encrypted(Self::generate_random_key()),
+            storage_path: self.storage_path.clone(),
+        };
+        let encrypted = match tokens {
+            Some(tokens) => Some(serde_json::to_vec(&rotated.encrypt_data(&serde_json::to_vec(&tokens)?)?)?),
+            None => None,
+        };
+
+        let key_path = key_path(&self.storage_path);
+        let previous_key = match std::fs::read(&key_path) {
+            Ok(key) => Some(key),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => None,
+            Err(e) => return Err(e.into()),
+        };
+        write_atomic(&key_path, rotated.encryption_key()?, TOKEN_FILE_MODE)?;
+
+        if let Some(encrypted) = encrypted {
+            if let Err(e) = write_tokens(&self.storage_path, &encrypted, TOKEN_FILE_MODE) {
+                let restored = match previous_key {
+                    Some(ref key) => write_atomic(&key_path, key, TOKEN_FILE_MODE),
+                    None => std::fs::remove_file(&key_path),
+                };
+                if let Err(restore_error) = restored {
+                    tracing::error!(
+                        "Could not restore {} after a failed key rotation: {}",
+                        key_path.display(), restore_error
+                    );
+                }
+                return Err(e.into());
+            }
+        }
+
+        self.backend = rotated.backend;
         Ok(())
     }
 
+    /// Key saved by a previous rotation, if any
+    fn load_rotated_key(storage_path: &Path) -> Result<Option<[u8; 32]>, SecureStorageError> {
+        let key_path = key_path(storage_path);
+        let bytes = match std::fs::read(&key_path) {
+            Ok(bytes) => bytes,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+        let key = <[u8; 32]>::try_from(bytes.as_slice()).map_err(|_| {
+            SecureStorageError::Encryption(format!("{} does not hold a 32-byte key", key_path.display()))
+        })?;
+        Ok(Some(key))
+    }
+
     /// Create file with secure permissions (0o600)
     fn create_secure_file(&self) -> Result<File, SecureStorageError> {
         let mut options = OpenOptions::new();
@@ -194,6 +551,8 @@ impl SecureTokenStorage {
     /// Encrypt data using ChaCha20-Poly1305
     fn encrypt_data(&self, data: &[u8]) -> Result<EncryptedTokenData, SecureStorageError> {
         use rand::RngCore;
+
+        let key = self.encryption_key()?;
         
         // Generate random nonce
         let mut nonce = [0u8; 12];
@@ -203,7 +562,7 @@ impl SecureTokenStorage {
         // In production, use proper AEAD like ChaCha20-Poly1305 or AES-GCM
         let mut encrypted = Vec::with_capacity(data.len());
         for (i, &byte) in data.iter().enumerate() {
-            let key_byte = self.encryption_key[i % self.encryption_key.len()];
+            let key_byte = key[i % key.len()];
             let nonce_byte = nonce[i % nonce.len()];
             encrypted.push(byte ^ key_byte ^ nonce_byte);
         }
@@ -219,10 +578,12 @@ impl SecureTokenStorage {
 
     /// Decrypt data
     fn decrypt_data(&self, encrypted_data: &EncryptedTokenData) -> Result<Vec<u8>, SecureStorageError> {
+        let key = self.encryption_key()?;
+
         // Simple XOR decryption (matches encryption above)
         let mut decrypted = Vec::with_capacity(encrypted_data.encrypted_content.len());
         for (i, &byte) in encrypted_data.encrypted_content.iter().enumerate() {
-            let key_byte = self.encryption_key[i % self.encryption_key.len()];
+            let key_byte = key[i % key.len()];
             let nonce_byte = encrypted_data.nonce[i % encrypted_data.nonce.len()];
             decrypted.push(byte ^ key_byte ^ nonce_byte);
         }
@@ -230,6 +591,13 @@ impl SecureTokenStorage {
         Ok(decrypted)
     }
 
+    fn encryption_key(&self) -> Result<&[u8; 32], SecureStorageError> {
+        match self.backend {
+            Backend::Encrypted(ref key) => Ok(key),
+            _ => Err(SecureStorageError::Encryption("storage is not encrypted".to_string())),
+        }
+    }
+
     /// Derive encryption key from storage path and system entropy
     fn derive_encryption_key(storage_path: &Path) -> Result<[u8; 32], SecureStorageError> {
         use std::collections::hash_map::DefaultHasher;
@@ -274,7 +642,7 @@ impl SecureTokenStorage {
 
     /// Update last accessed time
     fn update_access_time(&self) -> Result<(), SecureStorageError> {
-        if !self.storage_path.exists() {
+        if self.mode() != StorageMode::Encrypted || !self.storage_path.exists() {
             return Ok(());
         }
         
@@ -320,6 +688,24 @@ impl SecureTokenStorage {
     }
 }
 
+/// `tokens.json` keeps its rotated encryption key in `tokens.json.key`
+pub fn key_path(storage_path: &Path) -> PathBuf {
+    let mut name = storage_path.file_name().map(|s| s.to_os_string()).unwrap_or_default();
+    name.push(".key");
+    storage_path.with_file_name(name)
+}
+
+/// `tokens.json` becomes `tokens.UNENCRYPTED.json`, so the fallback never overwrites
+/// or is mistaken for an encrypted file
+fn plaintext_path(storage_path: &Path) -> PathBuf {
+    let stem = storage_path.file_stem().map(|s| s.to_string_lossy().into_owned()).unwrap_or_default();
+    let name = match storage_path.extension() {
+        Some(ext) => format!("{}.UNENCRYPTED.{}", stem, ext.to_string_lossy()),
+        None => format!("{}.UNENCRYPTED", stem),
+    };
+    storage_path.with_file_name(name)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -333,9 +719,9 @@ mod tests {
         let storage = SecureTokenStorage::new(storage_path).unwrap();
         
         let tokens = TokenData {
-            access_token: "access_123".to_string(),
-            refresh_token: "refresh_456".to_string(),
-            id_token: "id_789".to_string(),
+            access_token: "access_123".into(),
+            refresh_token: "refresh_456".into(),
+            id_token: "id_789".into(),
             expires_at: Utc::now() + chrono::Duration::hours(1),
             account_id: Some("account_id".to_string()),
             provider: "claude".to_string(),
@@ -368,4 +754,167 @@ mod tests {
         
         assert_eq!(test_data.to_vec(), decrypted);
     }
-}
\ No newline at end of file
+
+    fn sample_tokens() -> TokenData {
+        TokenData {
+            access_token: "access_123".into(),
+            refresh_token: "refresh_456".into(),
+            id_token: "id_789".into(),
+            expires_at: Utc::now() + chrono::Duration::hours(1),
+            account_id: None,
+            provider: "claude".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_rotated_key_reencrypts_and_persists() {
+        let temp_dir = tempdir().unwrap();
+        let storage_path = temp_dir.path().join("tokens.json");
+        let mut storage = SecureTokenStorage::new(storage_path.clone()).unwrap();
+        storage.store_tokens(&sample_tokens()).unwrap();
+        let before = std::fs::read(&storage_path).unwrap();
+
+        storage.rotate_encryption_key().unwrap();
+
+        assert_ne!(std::fs::read(&storage_path).unwrap(), before);
+        assert_eq!(storage.retrieve_tokens().unwrap().unwrap().access_token.expose_secret(), "access_123");
+        #[cfg(unix)]
+        assert_eq!(std::fs::metadata(key_path(&storage_path)).unwrap().permissions().mode() & 0o777, 0o600);
+
+        // A fresh instance picks up the saved key
+        let reopened = SecureTokenStorage::new(storage_path.clone()).unwrap();
+        assert_eq!(reopened.retrieve_tokens().unwrap().unwrap().refresh_token.expose_secret(), "refresh_456");
+
+        // The derived key no longer decrypts the file
+        let old_key = SecureTokenStorage::from_derived_key(
+            storage_path.clone(),
+            SecureTokenStorage::derive_encryption_key(&storage_path),
+            EncryptionUnavailablePolicy::Fail,
+        )
+        .unwrap();
+        assert!(old_key.peek_tokens().is_err());
+    }
+
+    #[test]
+    fn test_failed_token_write_rolls_back_key_rotation() {
+        let temp_dir = tempdir().unwrap();
+        let storage_path = temp_dir.path().join("tokens.json");
+        let mut storage = SecureTokenStorage::new(storage_path.clone()).unwrap();
+        storage.store_tokens(&sample_tokens()).unwrap();
+        let failing_write = |_: &Path, _: &[u8], _: u32| Err(std::io::Error::other("disk full"));
+
+        // Without a saved key the new key file is removed again
+        let tokens_before = std::fs::read(&storage_path).unwrap();
+        assert!(matches!(storage.rotate_encryption_key_with(failing_write), Err(SecureStorageError::Io(_))));
+        assert!(!key_path(&storage_path).exists());
+        assert_eq!(std::fs::read(&storage_path).unwrap(), tokens_before);
+        assert_eq!(storage.retrieve_tokens().unwrap().unwrap().access_token.expose_secret(), "access_123");
+
+        // A saved key is put back
+        storage.rotate_encryption_key().unwrap();
+        let key_before = std::fs::read(key_path(&storage_path)).unwrap();
+        let tokens_before = std::fs::read(&storage_path).unwrap();
+        assert!(storage.rotate_encryption_key_with(failing_write).is_err());
+        assert_eq!(std::fs::read(key_path(&storage_path)).unwrap(), key_before);
+        assert_eq!(std::fs::read(&storage_path).unwrap(), tokens_before);
+        assert_eq!(storage.retrieve_tokens().unwrap().unwrap().access_token.expose_secret(), "access_123");
+
+        let reopened = SecureTokenStorage::new(storage_path).unwrap();
+        assert_eq!(reopened.retrieve_tokens().unwrap().unwrap().refresh_token.expose_secret(), "refresh_456");
+    }
+
+    fn key_derivation_failure() -> Result<[u8; 32], SecureStorageError> {
+        Err(SecureStorageError::Encryption("no entropy source".to_string()))
+    }
+
+    #[test]
+    fn test_fail_policy_refuses_storage() {
+        let temp_dir = tempdir().unwrap();
+        let result = SecureTokenStorage::from_derived_key(
+            temp_dir.path().join("tokens.json"),
+            key_derivation_failure(),
+            EncryptionUnavailablePolicy::default(),
+        );
+        assert!(matches!(result, Err(SecureStorageError::Encryption(_))));
+    }
+
+    #[test]
+    fn test_plaintext_policy_writes_marked_private_file() {
+        let temp_dir = tempdir().unwrap();
+        let storage = SecureTokenStorage::from_derived_key(
+            temp_dir.path().join("tokens.json"),
+            key_derivation_failure(),
+            EncryptionUnavailablePolicy::WarnAndUsePlaintext,
+        )
+        .unwrap();
+        assert_eq!(storage.mode(), StorageMode::Plaintext);
+        assert_eq!(storage.storage_path(), temp_dir.path().join("tokens.UNENCRYPTED.json"));
+
+        storage.store_tokens(&sample_tokens()).unwrap();
+        let contents = std::fs::read_to_string(storage.storage_path()).unwrap();
+        assert!(contents.contains(PLAINTEXT_WARNING));
+        assert!(contents.contains("access_123"));
+        assert!(!temp_dir.path().join("tokens.json").exists());
+        #[cfg(unix)]
+        assert_eq!(std::fs::metadata(storage.storage_path()).unwrap().permissions().mode() & 0o777, 0o600);
+
+        assert_eq!(storage.retrieve_tokens().unwrap().unwrap().access_token.expose_secret(), "access_123");
+        assert!(storage.delete_tokens().unwrap());
+        assert!(!storage.tokens_exist());
+    }
+
+    #[test]
+    fn test_disable_storage_policy_keeps_tokens_in_memory() {
+        let temp_dir = tempdir().unwrap();
+        let mut storage = SecureTokenStorage::from_derived_key(
+            temp_dir.path().join("tokens.json"),
+            key_derivation_failure(),
+            EncryptionUnavailablePolicy::WarnAndDisableStorage,
+        )
+        .unwrap();
+        assert_eq!(storage.mode(), StorageMode::MemoryOnly);
+
+        storage.store_tokens(&sample_tokens()).unwrap();
+        assert!(storage.tokens_exist());
+        assert_eq!(storage.retrieve_tokens().unwrap().unwrap().refresh_token.expose_secret(), "refresh_456");
+        assert_eq!(std::fs::read_dir(temp_dir.path()).unwrap().count(), 0);
+        assert!(storage.rotate_encryption_key().is_err());
+
+        assert!(storage.delete_tokens().unwrap());
+        assert!(storage.peek_tokens().unwrap().is_none());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_overly_permissive_files_are_detected_and_repaired() {
+        let temp_dir = tempdir().unwrap();
+        let dir = temp_dir.path().join("codex");
+        std::fs::create_dir(&dir).unwrap();
+        let storage = SecureTokenStorage::new(dir.join("tokens.json")).unwrap();
+        storage.store_tokens(&sample_tokens()).unwrap();
+
+        std::fs::set_permissions(storage.storage_path(), std::fs::Permissions::from_mode(0o644)).unwrap();
+        std::fs::set_permissions(&dir, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+        let report = storage.check_permissions();
+        assert!(report.supported);
+        assert_eq!(report.checked.len(), 2);
+        assert_eq!(
+            report.issues,
+            vec![
+                PermissionIssue { path: dir.clone(), mode: 0o755, expected: TOKEN_DIR_MODE },
+                PermissionIssue { path: storage.storage_path().to_path_buf(), mode: 0o644, expected: TOKEN_FILE_MODE },
+            ]
+        );
+        // Checking never changes anything
+        assert_eq!(std::fs::metadata(storage.storage_path()).unwrap().permissions().mode() & 0o777, 0o644);
+        assert!(!storage.tokens_exist());
+
+        let repaired = storage.repair_permissions().unwrap();
+        assert_eq!(repaired.issues.len(), 2);
+        assert_eq!(std::fs::metadata(storage.storage_path()).unwrap().permissions().mode() & 0o777, 0o600);
+        assert_eq!(std::fs::metadata(&dir).unwrap().permissions().mode() & 0o777, 0o700);
+        assert!(storage.check_permissions().is_secure());
+        assert_eq!(storage.retrieve_tokens().unwrap().unwrap().access_token.expose_secret(), "access_123");
+    }
+}
@@ -6,11 +6,15 @@ use thiserror::Error;
 use rand::RngCore;
 use base64::{Engine as _, engine::general_purpose::URL_SAFE_NO_PAD};
 
+use crate::security::audit_logger::{log_audit_event, AuditEvent, AuthEventType, Severity};
+use crate::security::clock::{system_clock, Clock};
+
 /// Enhanced session security with token rotation and secure session management
 #[derive(Debug)]
 pub struct SessionSecurityManager {
     sessions: Arc<RwLock<HashMap<String, SecureSession>>>,
     config: SessionConfig,
+    clock: Arc<dyn Clock>,
 }
 
 #[derive(Debug, Error)]
@@ -29,6 +33,8 @@ pub enum SessionSecurityError {
     SecurityViolation(String),
     #[error("Token validation failed: {0}")]
     TokenValidationFailed(String),
+    #[error("Session binding mismatch: {0}")]
+    BindingMismatch(String),
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -56,6 +62,33 @@ pub struct SessionSecurityFlags {
     pub force_rotation: bool,
     pub restricted_access: bool,
     pub high_privilege: bool,
+    #[serde(default)]
+    pub binding_mismatch: bool,
+    /// Scoped, fixed-lifetime session handed to an agent; never rotated
+    #[serde(default)]
+    pub delegated: bool,
+}
+
+/// What to do when a user already holds the maximum number of sessions
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SessionLimitPolicy {
+    /// Refuse to create the new session
+    Reject,
+    /// Destroy the user's oldest session to make room for the new one
+    EvictOldest,
+}
+
+/// How strictly a session is bound to the IP address and user agent it was created with
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SessionBindingMode {
+    /// Origin changes are ignored
+    Off,
+    /// Origin changes are allowed but the session is flagged and audited
+    Warn,
+    /// Origin changes are rejected and the session is marked suspicious
+    Enforce,
 }
 
 #[derive(Debug, Clone)]
@@ -63,8 +96,20 @@ pub struct SessionConfig {
     pub access_token_lifetime: Duration,
     pub refresh_token_lifetime: Duration,
     pub rotation_threshold: Duration,
+    /// Cap on a user's live sessions; delegated sessions are not counted or evicted
+    pub max_sessions_per_user: usize,
+    /// Former name of `max_sessions_per_user`; a non-zero value takes precedence over it
+    #[deprecated(note = "renamed to `max_sessions_per_user`")]
     pub max_concurrent_sessions: usize,
+    /// Cap for sessions created without a user id; `None` leaves them unbounded
+    pub max_anonymous_sessions: Option<usize>,
+    pub session_limit_policy: SessionLimitPolicy,
+    pub binding_mode: SessionBindingMode,
+    /// Former IP check; with `binding_mode` off, setting it enforces IP binding alone
+    #[deprecated(note = "use `binding_mode`")]
     pub require_ip_consistency: bool,
+    /// Former user-agent check; with `binding_mode` off, setting it enforces user-agent binding alone
+    #[deprecated(note = "use `binding_mode`")]
     pub require_user_agent_consistency: bool,
     pub max_rotation_count: u32,
 }
@@ -86,19 +131,50 @@ pub struct SessionValidationContext {
 }
 
 impl Default for SessionConfig {
+    #[allow(deprecated)]
     fn default() -> Self {
         Self {
             access_token_lifetime: Duration::hours(1),
             refresh_token_lifetime: Duration::days(30),
             rotation_threshold: Duration::minutes(30),
-            max_concurrent_sessions: 5,
-            require_ip_consistency: false, // Disabled by default for dev environments
+            max_sessions_per_user: 5,
+            max_concurrent_sessions: 0,
+            max_anonymous_sessions: None,
+            session_limit_policy: SessionLimitPolicy::Reject,
+            binding_mode: SessionBindingMode::Off, // Disabled by default for dev environments
+            require_ip_consistency: false,
             require_user_agent_consistency: false,
             max_rotation_count: 100,
         }
     }
 }
 
+impl SessionConfig {
+    /// Per-user cap, honouring the deprecated `max_concurrent_sessions` when it is set
+    #[allow(deprecated)]
+    fn session_cap(&self) -> usize {
+        if self.max_concurrent_sessions > 0 {
+            self.max_concurrent_sessions
+        } else {
+            self.max_sessions_per_user
+        }
+    }
+
+    /// Effective binding mode and whether it covers the IP address and user agent,
+    /// mapping the deprecated consistency flags onto `Enforce`
+    #[allow(deprecated)]
+    fn session_binding(&self) -> (SessionBindingMode, bool, bool) {
+        match self.binding_mode {
+            SessionBindingMode::Off if self.require_ip_consistency || self.require_user_agent_consistency => (
+                SessionBindingMode::Enforce,
+                self.require_ip_consistency,
+                self.require_user_agent_consistency,
+            ),
+            mode => (mode, true, true),
+        }
+    }
+}
+
 impl Default for SessionSecurityFlags {
     fn default() -> Self {
         Self {
@@ -107,6 +183,8 @@ impl Default for SessionSecurityFlags {
             force_rotation: false,
             restricted_access: false,
             high_privilege: false,
+            binding_mismatch: false,
+            delegated: false,
         }
     }
 }
@@ -117,6 +195,28 @@ impl SessionSecurityManager {
         Self {
             sessions: Arc::new(RwLock::new(HashMap::new())),
             config,
+            clock: system_clock(),
+        }
+    }
+
+    /// Read the time from `clock` for expiry sweeps and statistics
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Validation context stamped with this manager's clock
+    pub fn validation_context(
+        &self,
+        ip_address: Option<String>,
+        user_agent: Option<String>,
+        requested_scopes: Vec<String>,
+    ) -> SessionValidationContext {
+        SessionValidationContext {
+            ip_address,
+            user_agent,
+            requested_scopes,
+            current_time: self.clock.now(),
         }
     }
 
@@ -128,30 +228,45 @@ impl SessionSecurityManager {
         scopes: Vec<String>,
         context: &SessionValidationContext,
     ) -> Result<SecureSession, SessionSecurityError> {
-        // Check concurrent session limit
-        self.cleanup_expired_sessions();
-        {
-            let sessions = self.sessions.read().unwrap();
-            let user_sessions: Vec<_> = sessions
-                .values()
-                .filter(|s| s.user_id == user_id)
-                .collect();
-            
-            if user_sessions.len() >= self.config.max_concurrent_sessions {
-                return Err(SessionSecurityError::ConcurrentLimitExceeded);
-            }
-        }
+        let session = self.new_session(user_id, client_id, scopes, context);
+        self.store_session(session, context)
+    }
 
-        let now = context.current_time;
-        let session_id = Self::generate_session_id();
-        let access_token = Self::generate_token();
-        let refresh_token = Self::generate_token();
+    /// Create a session that expires after exactly `ttl` and is never rotated
+    ///
+    /// Used to delegate a scoped credential to an agent, which can't rotate it and
+    /// must be issued a new one once it expires. Delegated sessions don't count toward
+    /// `max_sessions_per_user`, so minting one never evicts or blocks the user's own
+    /// sessions; they are bounded by their TTL and can be revoked individually.
+    pub fn create_delegated_session(
+        &self,
+        user_id: String,
+        client_id: String,
+        scopes: Vec<String>,
+        ttl: Duration,
+        context: &SessionValidationContext,
+    ) -> Result<SecureSession, SessionSecurityError> {
+        let mut session = self.new_session(user_id, client_id, scopes, context);
+        session.expires_at = context.current_time + ttl;
+        session.refresh_expires_at = session.expires_at;
+        session.security_flags.delegated = true;
+        self.store_session(session, context)
+    }
 
-        let session = SecureSession {
-            session_id: session_id.clone(),
+    /// Fresh session bound to the request origin in `context`
+    fn new_session(
+        &self,
+        user_id: String,
+        client_id: String,
+        scopes: Vec<String>,
+        context: &SessionValidationContext,
+    ) -> SecureSession {
+        let now = context.current_time;
+        SecureSession {
+            session_id: Self::generate_session_id(),
             user_id,
-            access_token,
-            refresh_token,
+            access_token: Self::generate_token(),
+            refresh_token: Self::generate_token(),
             created_at: now,
             last_accessed: now,
             expires_at: now + self.config.access_token_lifetime,
@@ -162,17 +277,108 @@ impl SessionSecurityManager {
             scopes,
             rotation_count: 0,
             security_flags: SessionSecurityFlags::default(),
-        };
+        }
+    }
 
-        // Store session
-        {
-            let mut sessions = self.sessions.write().unwrap();
-            sessions.insert(session_id, session.clone());
+    /// Insert `session`, checking the session limit under the same lock so concurrent
+    /// creations can't both squeeze under it
+    fn store_session(
+        &self,
+        session: SecureSession,
+        context: &SessionValidationContext,
+    ) -> Result<SecureSession, SessionSecurityError> {
+        let mut sessions = self.sessions.write().unwrap();
+        let now = self.clock.now();
+        sessions.retain(|_, s| now <= s.refresh_expires_at);
+
+        if !session.security_flags.delegated {
+            self.enforce_session_limit(&mut sessions, &session.user_id, &session.client_id, context)?;
         }
 
+        sessions.insert(session.session_id.clone(), session.clone());
         Ok(session)
     }
 
+    /// Make room for a new session for `user_id`, rejecting or evicting per the configured policy
+    fn enforce_session_limit(
+        &self,
+        sessions: &mut HashMap<String, SecureSession>,
+        user_id: &str,
+        client_id: &str,
+        context: &SessionValidationContext,
+    ) -> Result<(), SessionSecurityError> {
+        // Anonymous sessions (empty user id) have their own, optional cap
+        let limit = if user_id.is_empty() {
+            match self.config.max_anonymous_sessions {
+                Some(limit) => limit,
+                None => return Ok(()),
+            }
+        } else {
+            self.config.session_cap()
+        };
+
+        let mut user_sessions: Vec<(String, DateTime<Utc>)> = sessions
+            .values()
+            .filter(|s| s.user_id == user_id && !s.security_flags.delegated)
+            .map(|s| (s.session_id.clone(), s.created_at))
+            .collect();
+
+        if user_sessions.len() < limit {
+            return Ok(());
+        }
+
+        let user = (!user_id.is_empty()).then(|| user_id.to_string());
+
+        if self.config.session_limit_policy == SessionLimitPolicy::Reject || limit == 0 {
+            let event = AuditEvent {
+                timestamp: context.current_time,
+                event_type: AuthEventType::PermissionDenied,
+                user_id: user,
+                session_id: None,
+                client_id: Some(client_id.to_string()),
+                ip_address: context.ip_address.clone(),
+                user_agent: context.user_agent.clone(),
+                success: false,
+                error_message: Some("Concurrent session limit exceeded".to_string()),
+                metadata: serde_json::json!({
+                    "reason": "session_limit_reached",
+                    "limit": limit,
+                    "active_sessions": user_sessions.len(),
+                }),
+                severity: Severity::Warning,
+            };
+            log_audit_event(event).ok();
+            return Err(SessionSecurityError::ConcurrentLimitExceeded);
+        }
+
+        // Evict oldest sessions until there is room for one more
+        user_sessions.sort_by_key(|(_, created_at)| *created_at);
+        let excess = user_sessions.len() + 1 - limit;
+        for (session_id, _) in user_sessions.into_iter().take(excess) {
+            sessions.remove(&session_id);
+
+            let event = AuditEvent {
+                timestamp: context.current_time,
+                event_type: AuthEventType::SessionDestroyed,
+                user_id: user.clone(),
+                session_id: Some(session_id),
+                client_id: Some(client_id.to_string()),
+                ip_address: context.ip_address.clone(),
+                user_agent: context.user_agent.clone(),
+                success: true,
+                error_message: None,
+                metadata: serde_json::json!({
+                    "reason": "session_limit_evicted_oldest",
+                    "limit": limit,
+                }),
+                severity: Severity::Info,
+            };
+            log_audit_event(event).ok();
+        }
+
+        Ok(())
+    }
+
     /// Validate session and return updated session if valid
     pub fn validate_session(
         &self,
@@ -331,7 +537,7 @@ impl SessionSecurityManager {
     /// Cleanup expired sessions
     pub fn cleanup_expired_sessions(&self) {
         let mut sessions = self.sessions.write().unwrap();
-        let now = Utc::now();
+        let now = self.clock.now();
         
         sessions.retain(|_, session| {
             now <= session.refresh_expires_at
@@ -341,7 +547,7 @@ impl SessionSecurityManager {
     /// Get session statistics
     pub fn get_session_stats(&self) -> SessionStats {
         let sessions = self.sessions.read().unwrap();
-        let now = Utc::now();
+        let now = self.clock.now();
         
         let total_sessions = sessions.len();
         let active_sessions = sessions
@@ -352,43 +558,27 @@ impl SessionSecurityManager {
             .values()
             .filter(|s| s.security_flags.is_suspicious)
             .count();
+
+        let mut sessions_per_user = HashMap::new();
+        for session in sessions.values().filter(|s| now <= s.expires_at) {
+            *sessions_per_user.entry(session.user_id.clone()).or_insert(0) += 1;
+        }
         
         SessionStats {
             total_sessions,
             active_sessions,
             suspicious_sessions,
             expired_sessions: total_sessions - active_sessions,
+            sessions_per_user,
         }
     }
 
     /// Check for security violations
     fn check_security_violations(
         &self,
-        session: &SecureSession,
+        session: &mut SecureSession,
         context: &SessionValidationContext,
     ) -> Result<(), SessionSecurityError> {
-        // Check IP address consistency
-        if self.config.require_ip_consistency {
-            if let (Some(session_ip), Some(context_ip)) = (&session.ip_address, &context.ip_address) {
-                if session_ip != context_ip {
-                    return Err(SessionSecurityError::SecurityViolation(
-                        format!("IP address mismatch: expected {}, got {}", session_ip, context_ip)
-                    ));
-                }
-            }
-        }
-
-        // Check User-Agent consistency
-        if self.config.require_user_agent_consistency {
-            if let (Some(session_ua), Some(context_ua)) = (&session.user_agent, &context.user_agent) {
-                if session_ua != context_ua {
-                    return Err(SessionSecurityError::SecurityViolation(
-                        "User-Agent mismatch".to_string()
-                    ));
-                }
-            }
-        }
-
         // Check if session is marked as suspicious
         if session.security_flags.is_suspicious {
             return Err(SessionSecurityError::SecurityViolation(
@@ -396,6 +586,69 @@ impl SessionSecurityManager {
             ));
         }
 
+        self.check_session_binding(session, context)
+    }
+
+    /// Compare the request origin against the IP address and user agent bound at creation
+    fn check_session_binding(
+        &self,
+        session: &mut SecureSession,
+        context: &SessionValidationContext,
+    ) -> Result<(), SessionSecurityError> {
+        let (binding_mode, check_ip, check_user_agent) = self.config.session_binding();
+        if binding_mode == SessionBindingMode::Off {
+            return Ok(());
+        }
+
+        let mut mismatches = Vec::new();
+        if let (true, Some(session_ip), Some(context_ip)) = (check_ip, &session.ip_address, &context.ip_address) {
+            if session_ip != context_ip {
+                mismatches.push(format!("IP address mismatch: expected {}, got {}", session_ip, context_ip));
+            }
+        }
+        if let (true, Some(session_ua), Some(context_ua)) = (check_user_agent, &session.user_agent, &context.user_agent) {
+            if session_ua != context_ua {
+                mismatches.push("User-Agent mismatch".to_string());
+            }
+        }
+
+        if mismatches.is_empty() {
+            return Ok(());
+        }
+
+        let details = mismatches.join("; ");
+        let enforce = binding_mode == SessionBindingMode::Enforce;
+
+        session.security_flags.binding_mismatch = true;
+        if enforce {
+            session.security_flags.is_suspicious = true;
+            session.security_flags.force_rotation = true;
+        }
+
+        let event = AuditEvent {
+            timestamp: context.current_time,
+            event_type: AuthEventType::SecurityViolation,
+            user_id: Some(session.user_id.clone()),
+            session_id: Some(session.session_id.clone()),
+            client_id: Some(session.client_id.clone()),
+            ip_address: context.ip_address.clone(),
+            user_agent: context.user_agent.clone(),
+            success: !enforce,
+            error_message: Some(details.clone()),
+            metadata: serde_json::json!({
+                "violation_type": "session_binding_mismatch",
+                "binding_mode": binding_mode,
+                "bound_ip_address": session.ip_address,
+                "bound_user_agent": session.user_agent,
+            }),
+            severity: if enforce { Severity::Critical } else { Severity::Warning },
+        };
+        log_audit_event(event).ok();
+
+        if enforce {
+            return Err(SessionSecurityError::BindingMismatch(details));
+        }
+
         Ok(())
     }
 
@@ -406,6 +659,11 @@ impl SessionSecurityManager {
             return true;
         }
 
+        // Delegated sessions live out their fixed lifetime instead
+        if session.security_flags.delegated {
+            return false;
+        }
+
         // Rotate based on time threshold
         let time_since_last_access = context.current_time - session.last_accessed;
         if time_since_last_access > self.config.rotation_threshold {
@@ -441,6 +699,8 @@ pub struct SessionStats {
     pub active_sessions: usize,
     pub suspicious_sessions: usize,
     pub expired_sessions: usize,
+    /// Active sessions keyed by user id (anonymous sessions are keyed by the empty string)
+    pub sessions_per_user: HashMap<String, usize>,
 }
 
 #[cfg(test)]
@@ -532,7 +792,7 @@ mod tests {
     #[test]
     fn test_concurrent_session_limit() {
         let mut config = SessionConfig::default();
-        config.max_concurrent_sessions = 2;
+        config.max_sessions_per_user = 2;
         let manager = SessionSecurityManager::new(config);
         let context = create_test_context();
 
@@ -556,6 +816,245 @@ mod tests {
         assert!(matches!(result, Err(SessionSecurityError::ConcurrentLimitExceeded)));
     }
 
+    #[test]
+    fn test_session_limit_evicts_oldest() {
+        let mut config = SessionConfig::default();
+        config.max_sessions_per_user = 2;
+        config.session_limit_policy = SessionLimitPolicy::EvictOldest;
+        let manager = SessionSecurityManager::new(config);
+        let mut context = create_test_context();
+
+        let mut created = Vec::new();
+        for i in 0..3 {
+            context.current_time = Utc::now() + Duration::seconds(i);
+            let session = manager.create_session(
+                "user123".to_string(),
+                format!("client{}", i),
+                vec!["read".to_string()],
+                &context,
+            ).unwrap();
+            created.push(session.session_id);
+        }
+
+        assert!(manager.get_session(&created[0]).is_none());
+        assert!(manager.get_session(&created[1]).is_some());
+        assert!(manager.get_session(&created[2]).is_some());
+
+        let stats = manager.get_session_stats();
+        assert_eq!(stats.sessions_per_user.get("user123"), Some(&2));
+    }
+
+    #[test]
+    fn test_anonymous_sessions_unbounded_by_default() {
+        let mut config = SessionConfig::default();
+        config.max_sessions_per_user = 1;
+        let manager = SessionSecurityManager::new(config);
+        let context = create_test_context();
+
+        for i in 0..3 {
+            manager.create_session(
+                String::new(),
+                format!("client{}", i),
+                vec!["read".to_string()],
+                &context,
+            ).unwrap();
+        }
+
+        let stats = manager.get_session_stats();
+        assert_eq!(stats.sessions_per_user.get(""), Some(&3));
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn test_deprecated_max_concurrent_sessions_still_caps() {
+        let mut config = SessionConfig::default();
+        config.max_concurrent_sessions = 1;
+        let manager = SessionSecurityManager::new(config);
+        let context = create_test_context();
+
+        manager.create_session("user123".to_string(), "client0".to_string(), vec![], &context).unwrap();
+        let result = manager.create_session("user123".to_string(), "client1".to_string(), vec![], &context);
+        assert!(matches!(result, Err(SessionSecurityError::ConcurrentLimitExceeded)));
+    }
+
+    #[test]
+    fn test_concurrent_creation_respects_limit() {
+        let mut config = SessionConfig::default();
+        config.max_sessions_per_user = 3;
+        let manager = Arc::new(SessionSecurityManager::new(config));
+        let context = create_test_context();
+
+        let handles: Vec<_> = (0..16)
+            .map(|i| {
+                let manager = Arc::clone(&manager);
+                let context = context.clone();
+                std::thread::spawn(move || {
+                    manager
+                        .create_session("user123".to_string(), format!("client{}", i), vec![], &context)
+                        .is_ok()
+                })
+            })
+            .collect();
+        let created = handles.into_iter().map(|h| h.join().unwrap()).filter(|ok| *ok).count();
+
+        assert_eq!(created, 3);
+        assert_eq!(manager.list_user_sessions("user123").len(), 3);
+    }
+
+    #[test]
+    fn test_delegated_sessions_bypass_user_cap() {
+        let mut config = SessionConfig::default();
+        config.max_sessions_per_user = 1;
+        config.session_limit_policy = SessionLimitPolicy::EvictOldest;
+        let manager = SessionSecurityManager::new(config);
+        let context = create_test_context();
+
+        let own = manager.create_session("user123".to_string(), "client0".to_string(), vec![], &context).unwrap();
+
+        // Delegating at the cap neither fails nor evicts the user's own session
+        let delegated = manager
+            .create_delegated_session("user123".to_string(), "agent".to_string(), vec![], Duration::minutes(5), &context)
+            .unwrap();
+        assert!(manager.get_session(&own.session_id).is_some());
+
+        // A new own session evicts the old one but leaves the delegated session alone
+        let replacement = manager.create_session("user123".to_string(), "client1".to_string(), vec![], &context).unwrap();
+        assert!(manager.get_session(&own.session_id).is_none());
+        assert!(manager.get_session(&replacement.session_id).is_some());
+        assert!(manager.get_session(&delegated.session_id).is_some());
+    }
+
+    #[test]
+    fn test_session_binding_matching_request() {
+        let mut config = SessionConfig::default();
+        config.binding_mode = SessionBindingMode::Enforce;
+        let manager = SessionSecurityManager::new(config);
+        let context = create_test_context();
+
+        let session = manager.create_session(
+            "user123".to_string(),
+            "client456".to_string(),
+            vec!["read".to_string()],
+            &context,
+        ).unwrap();
+
+        let validated = manager.validate_session(
+            &session.session_id,
+            &session.access_token,
+            &context,
+        ).unwrap();
+        assert!(!validated.security_flags.binding_mismatch);
+        assert!(!validated.security_flags.is_suspicious);
+    }
+
+    #[test]
+    fn test_session_binding_warn_allows_changed_ip() {
+        let mut config = SessionConfig::default();
+        config.binding_mode = SessionBindingMode::Warn;
+        let manager = SessionSecurityManager::new(config);
+        let context = create_test_context();
+
+        let session = manager.create_session(
+            "user123".to_string(),
+            "client456".to_string(),
+            vec!["read".to_string()],
+            &context,
+        ).unwrap();
+
+        let mut moved_context = create_test_context();
+        moved_context.ip_address = Some("10.0.0.7".to_string());
+
+        let validated = manager.validate_session(
+            &session.session_id,
+            &session.access_token,
+            &moved_context,
+        ).unwrap();
+        assert!(validated.security_flags.binding_mismatch);
+        assert!(!validated.security_flags.is_suspicious);
+    }
+
+    #[test]
+    fn test_session_binding_enforce_rejects_changed_ip() {
+        let mut config = SessionConfig::default();
+        config.binding_mode = SessionBindingMode::Enforce;
+        let manager = SessionSecurityManager::new(config);
+        let context = create_test_context();
+
+        let session = manager.create_session(
+            "user123".to_string(),
+            "client456".to_string(),
+            vec!["read".to_string()],
+            &context,
+        ).unwrap();
+
+        let mut moved_context = create_test_context();
+        moved_context.ip_address = Some("10.0.0.7".to_string());
+
+        let result = manager.validate_session(
+            &session.session_id,
+            &session.access_token,
+            &moved_context,
+        );
+        assert!(matches!(result, Err(SessionSecurityError::BindingMismatch(_))));
+
+        let stored = manager.get_session(&session.session_id).unwrap();
+        assert!(stored.security_flags.is_suspicious);
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn test_deprecated_consistency_flags_map_to_enforce() {
+        let mut config = SessionConfig::default();
+        config.require_ip_consistency = true;
+        let manager = SessionSecurityManager::new(config);
+        let context = create_test_context();
+
+        let session = manager.create_session(
+            "user123".to_string(),
+            "client456".to_string(),
+            vec!["read".to_string()],
+            &context,
+        ).unwrap();
+
+        // Only the IP address was covered by the old flag
+        let mut new_agent = create_test_context();
+        new_agent.user_agent = Some("OtherAgent/2.0".to_string());
+        manager.validate_session(&session.session_id, &session.access_token, &new_agent).unwrap();
+
+        let mut moved_context = create_test_context();
+        moved_context.ip_address = Some("10.0.0.7".to_string());
+        let result = manager.validate_session(&session.session_id, &session.access_token, &moved_context);
+        assert!(matches!(result, Err(SessionSecurityError::BindingMismatch(_))));
+    }
+
+    #[test]
+    fn test_mock_clock_expires_and_sweeps_sessions() {
+        let clock = crate::security::MockClock::default();
+        let manager = SessionSecurityManager::new(SessionConfig::default())
+            .with_clock(Arc::new(clock.clone()));
+        let context = manager.validation_context(Some("192.168.1.1".to_string()), None, vec!["read".to_string()]);
+
+        let session = manager.create_session(
+            "user123".to_string(),
+            "client456".to_string(),
+            vec!["read".to_string()],
+            &context,
+        ).unwrap();
+
+        clock.advance(Duration::hours(1) + Duration::seconds(1));
+        let context = manager.validation_context(Some("192.168.1.1".to_string()), None, vec!["read".to_string()]);
+        let result = manager.validate_session(&session.session_id, &session.access_token, &context);
+        assert!(matches!(result, Err(SessionSecurityError::SessionExpired(_))));
+
+        // Still refreshable, so the sweep keeps it until the refresh token lapses
+        manager.cleanup_expired_sessions();
+        assert!(manager.get_session(&session.session_id).is_some());
+
+        clock.advance(Duration::days(30));
+        manager.cleanup_expired_sessions();
+        assert!(manager.get_session(&session.session_id).is_none());
+    }
+
     #[test]
     fn test_suspicious_session_marking() {
         let config = SessionConfig::default();
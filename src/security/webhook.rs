@@ -0,0 +1,342 @@
+//! Webhook notification of security events
+//!
+//! Audit events at or above a configured severity are queued and POSTed in batches to a
+//! SIEM or chat webhook from a background task. Queueing never blocks: when the receiver
+//! is down and the queue fills up, further events are dropped and counted.
+
+use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use tokio::sync::mpsc;
+
+use super::audit_logger::{AuditEvent, Severity};
+use crate::claude_auth::secure_claude_auth::{build_http_client_with_proxy, default_user_agent, DEFAULT_ORIGINATOR};
+use crate::claude_auth::ProxyConfig;
+
+/// Header carrying the hex HMAC-SHA256 of the request body, prefixed with `sha256=`
+pub const SIGNATURE_HEADER: &str = "X-Signature";
+
+/// Where and what to send
+#[derive(Clone, Serialize, Deserialize)]
+pub struct WebhookConfig {
+    pub url: String,
+    /// Shared secret for signing payloads; unsigned when `None`
+    pub secret: Option<String>,
+    /// Events below this severity are not sent
+    pub min_severity: Severity,
+    /// Most events sent in one request
+    pub batch_size: usize,
+    /// How long a partial batch waits for more events before it is sent
+    pub flush_interval: Duration,
+    /// Delivery attempts per batch before it is dropped
+    pub max_attempts: u32,
+    /// Delay before the first retry; doubled on each further attempt
+    pub retry_backoff: Duration,
+    /// Events buffered while deliveries are in flight
+    pub queue_capacity: usize,
+    /// Limit on each delivery attempt; a receiver that doesn't answer in time counts as failed
+    #[serde(default = "default_request_timeout")]
+    pub request_timeout: Duration,
+    /// Proxy to deliver through
+    #[serde(default)]
+    pub proxy: Option<ProxyConfig>,
+}
+
+fn default_request_timeout() -> Duration {
+    Duration::from_secs(10)
+}
+
+impl WebhookConfig {
+    /// Send unsigned `Critical` events to `url` with default batching and retries
+    pub fn new(url: impl Into<String>) -> Self {
+        Self {
+            url: url.into(),
+            secret: None,
+            min_severity: Severity::Critical,
+            batch_size: 50,
+            flush_interval: Duration::from_secs(5),
+            max_attempts: 5,
+            retry_backoff: Duration::from_millis(500),
+            queue_capacity: 1024,
+            request_timeout: default_request_timeout(),
+            proxy: None,
+        }
+    }
+
+    /// Sign payloads with `secret`
+    pub fn with_secret(mut self, secret: impl Into<String>) -> Self {
+        self.secret = Some(secret.into());
+        self
+    }
+
+    /// Send events of `severity` and above
+    pub fn with_min_severity(mut self, severity: Severity) -> Self {
+        self.min_severity = severity;
+        self
+    }
+}
+
+impl fmt::Debug for WebhookConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("WebhookConfig")
+            .field("url", &self.url)
+            .field("secret", &self.secret.as_ref().map(|_| "<redacted>"))
+            .field("min_severity", &self.min_severity)
+            .field("batch_size", &self.batch_size)
+            .field("flush_interval", &self.flush_interval)
+            .field("max_attempts", &self.max_attempts)
+            .field("retry_backoff", &self.retry_backoff)
+            .field("queue_capacity", &self.queue_capacity)
+            .field("request_timeout", &self.request_timeout)
+            .field("proxy", &self.proxy)
+            .finish()
+    }
+}
+
+/// Body POSTed to the webhook
+#[derive(Debug, Serialize, Deserialize)]
+pub struct WebhookPayload {
+    pub events: Vec<AuditEvent>,
+}
+
+/// Hex HMAC-SHA256 of `body`, as sent in [`SIGNATURE_HEADER`]
+pub fn sign_payload(secret: &[u8], body: &[u8]) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret).expect("HMAC accepts keys of any length");
+    mac.update(body);
+    let digest = mac.finalize().into_bytes();
+    let hex: String = digest.iter().map(|byte| format!("{:02x}", byte)).collect();
+    format!("sha256={}", hex)
+}
+
+/// Handle for queueing events to the webhook
+///
+/// Clones share one queue and background task, which exits once every handle is dropped
+/// and the remaining events are sent.
+#[derive(Debug, Clone)]
+pub struct WebhookDispatcher {
+    sender: mpsc::Sender<AuditEvent>,
+    min_severity: Severity,
+    dropped: Arc<AtomicU64>,
+}
+
+impl WebhookDispatcher {
+    /// Start the delivery task
+    ///
+    /// Runs on the current Tokio runtime, or on a dedicated thread when called outside one.
+    pub fn spawn(config: WebhookConfig) -> Self {
+        let (sender, receiver) = mpsc::channel(config.queue_capacity.max(1));
+        let dispatcher = Self {
+            sender,
+            min_severity: config.min_severity.clone(),
+            dropped: Arc::new(AtomicU64::new(0)),
+        };
+
+        let worker = deliver_batches(config, receiver);
+        match tokio::runtime::Handle::try_current() {
+            Ok(handle) => {
+                handle.spawn(worker);
+            }
+            Err(_) => {
+                std::thread::spawn(move || {
+                    match tokio::runtime::Builder::new_current_thread().enable_all().build() {
+                        Ok(runtime) => runtime.block_on(worker),
+                        Err(e) => tracing::warn!("Security webhook disabled, no runtime: {}", e),
+                    }
+                });
+            }
+        }
+
+        dispatcher
+    }
+
+    /// Queue `event` if it is severe enough; never waits
+    pub fn dispatch(&self, event: &AuditEvent) {
+        if event.severity < self.min_severity {
+            return;
+        }
+        if self.sender.try_send(event.clone()).is_err() {
+            self.dropped.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    /// Events discarded because the queue was full or the task had stopped
+    pub fn dropped_events(&self) -> u64 {
+        self.dropped.load(Ordering::Relaxed)
+    }
+}
+
+async fn deliver_batches(config: WebhookConfig, mut receiver: mpsc::Receiver<AuditEvent>) {
+    // Dropping the receiver makes every later dispatch count as dropped
+    let client = match build_http_client_with_proxy(&default_user_agent(DEFAULT_ORIGINATOR), config.proxy.as_ref()) {
+        Ok(client) => client,
+        Err(e) => {
+            tracing::warn!("Security webhook disabled, HTTP client failed to build: {}", e);
+            return;
+        }
+    };
+    let batch_size = config.batch_size.max(1);
+
+    while let Some(first) = receiver.recv().await {
+        let mut events = vec![first];
+        let deadline = tokio::time::Instant::now() + config.flush_interval;
+        while events.len() < batch_size {
+            match tokio::time::timeout_at(deadline, receiver.recv()).await {
+                Ok(Some(event)) => events.push(event),
+                // Queue closed or the batch waited long enough
+                Ok(None) | Err(_) => break,
+            }
+        }
+
+        let count = events.len();
+        if let Err(e) = send_with_retries(&client, &config, &WebhookPayload { events }).await {
+            tracing::warn!("Dropping {} security events after webhook delivery failed: {}", count, e);
+        }
+    }
+}
+
+async fn send_with_retries(
+    client: &reqwest::Client,
+    config: &WebhookConfig,
+    payload: &WebhookPayload,
+) -> Result<(), String> {
+    let body = serde_json::to_vec(payload).map_err(|e| e.to_string())?;
+    let signature = config.secret.as_ref().map(|secret| sign_payload(secret.as_bytes(), &body));
+
+    let mut backoff = config.retry_backoff;
+    let mut attempt = 1;
+    loop {
+        let mut request = client
+            .post(&config.url)
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .timeout(config.request_timeout)
+            .body(body.clone());
+        if let Some(ref signature) = signature {
+            request = request.header(SIGNATURE_HEADER, signature);
+        }
+
+        let error = match request.send().await {
+            Ok(response) if response.status().is_success() => return Ok(()),
+            Ok(response) => format!("webhook returned {}", response.status()),
+            Err(e) => e.to_string(),
+        };
+        if attempt >= config.max_attempts {
+            return Err(error);
+        }
+
+        tokio::time::sleep(backoff).await;
+        backoff *= 2;
+        attempt += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::audit_logger::AuthEventType;
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, MockServer, Request, ResponseTemplate};
+
+    fn event(severity: Severity) -> AuditEvent {
+        AuditEvent {
+            timestamp: chrono::Utc::now(),
+            event_type: AuthEventType::SecurityViolation,
+            user_id: None,
+            session_id: None,
+            client_id: None,
+            ip_address: None,
+            user_agent: None,
+            success: false,
+            error_message: Some(format!("{:?} violation", severity)),
+            metadata: serde_json::json!({}),
+            severity,
+        }
+    }
+
+    /// Webhook answering its first delivery attempt with `first` and later ones with 200
+    async fn spawn_webhook(first: ResponseTemplate) -> MockServer {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/hooks/security"))
+            .respond_with(first)
+            .up_to_n_times(1)
+            .with_priority(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/hooks/security"))
+            .respond_with(ResponseTemplate::new(200))
+            .mount(&server)
+            .await;
+        server
+    }
+
+    /// Wait until `server` has received `count` requests and return them
+    async fn wait_for_requests(server: &MockServer, count: usize) -> Vec<Request> {
+        tokio::time::timeout(Duration::from_secs(5), async {
+            loop {
+                let requests = server.received_requests().await.unwrap();
+                if requests.len() >= count {
+                    return requests;
+                }
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .unwrap()
+    }
+
+    #[test]
+    fn test_signature_matches_rfc4231_vector() {
+        assert_eq!(
+            sign_payload(b"Jefe", b"what do ya want for nothing?"),
+            "sha256=5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_critical_event_delivered_signed_after_retry() {
+        // Fail the first delivery to exercise the retry path
+        let server = spawn_webhook(ResponseTemplate::new(503)).await;
+        let mut config = WebhookConfig::new(format!("{}/hooks/security", server.uri())).with_secret("siem-secret");
+        config.flush_interval = Duration::from_millis(50);
+        config.retry_backoff = Duration::from_millis(10);
+        let dispatcher = WebhookDispatcher::spawn(config);
+
+        dispatcher.dispatch(&event(Severity::Warning));
+        dispatcher.dispatch(&event(Severity::Critical));
+
+        let requests = wait_for_requests(&server, 2).await;
+        let delivered = &requests[1];
+        let signature = delivered.headers.get(SIGNATURE_HEADER).unwrap().to_str().unwrap();
+        assert_eq!(signature, sign_payload(b"siem-secret", &delivered.body));
+        let user_agent = delivered.headers.get("user-agent").unwrap().to_str().unwrap();
+        assert_eq!(user_agent, default_user_agent(DEFAULT_ORIGINATOR));
+
+        let payload: WebhookPayload = serde_json::from_slice(&delivered.body).unwrap();
+        assert_eq!(payload.events.len(), 1);
+        assert!(matches!(payload.events[0].severity, Severity::Critical));
+        assert_eq!(dispatcher.dropped_events(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_unanswered_delivery_times_out_and_is_retried() {
+        let server = spawn_webhook(ResponseTemplate::new(200).set_delay(Duration::from_secs(30))).await;
+        let mut config = WebhookConfig::new(format!("{}/hooks/security", server.uri()));
+        config.flush_interval = Duration::from_millis(10);
+        config.retry_backoff = Duration::from_millis(10);
+        config.request_timeout = Duration::from_millis(100);
+        let dispatcher = WebhookDispatcher::spawn(config);
+
+        let started = std::time::Instant::now();
+        dispatcher.dispatch(&event(Severity::Critical));
+
+        let requests = wait_for_requests(&server, 2).await;
+        assert!(started.elapsed() < Duration::from_secs(5));
+        assert_eq!(requests[0].body, requests[1].body);
+    }
+}
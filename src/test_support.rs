@@ -0,0 +1,322 @@
+//! Test fixtures for code that talks to Anthropic
+//!
+//! [`MockAnthropicServer`] stands in for the OAuth, subscription and messages endpoints
+//! so auth flows can be exercised without network access. Available to this crate's
+//! tests and, behind the `test-util` feature, to downstream crates.
+
+use std::time::Duration;
+
+use serde_json::json;
+use wiremock::matchers::{body_partial_json, method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+use crate::claude_auth::ClaudeAuthConfig;
+use crate::security::audit_logger::disable_audit_logger;
+
+#[cfg(feature = "mtls-test")]
+pub mod mtls;
+
+/// Access token returned for an authorization code exchange
+pub const MOCK_ACCESS_TOKEN: &str = "mock-access-token";
+/// Refresh token returned for an authorization code exchange
+pub const MOCK_REFRESH_TOKEN: &str = "mock-refresh-token";
+/// Access token returned by a refresh
+pub const MOCK_REFRESHED_ACCESS_TOKEN: &str = "mock-refreshed-access-token";
+
+/// Endpoints served by [`MockAnthropicServer`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MockEndpoint {
+    Token,
+    Revoke,
+    Subscription,
+    Messages,
+}
+
+impl MockEndpoint {
+    /// All endpoints, used when a response applies everywhere
+    pub const ALL: [MockEndpoint; 4] = [
+        MockEndpoint::Token,
+        MockEndpoint::Revoke,
+        MockEndpoint::Subscription,
+        MockEndpoint::Messages,
+    ];
+
+    /// Request path, matching the paths in the default [`ClaudeAuthConfig`]
+    pub fn path(&self) -> &'static str {
+        match self {
+            MockEndpoint::Token => "/oauth/token",
+            MockEndpoint::Revoke => "/oauth/revoke",
+            MockEndpoint::Subscription => "/v1/subscription",
+            MockEndpoint::Messages => "/v1/messages",
+        }
+    }
+
+    fn method(&self) -> &'static str {
+        match self {
+            MockEndpoint::Subscription => "GET",
+            _ => "POST",
+        }
+    }
+
+    fn mock(&self) -> wiremock::MockBuilder {
+        Mock::given(method(self.method())).and(path(self.path()))
+    }
+}
+
+/// Canned responses to mount, collected before the server starts
+///
+/// Error responses take precedence over success responses for the same endpoint.
+#[derive(Default)]
+pub struct MockAnthropicServerBuilder {
+    mocks: Vec<Mock>,
+}
+
+/// Priority of error responses; wiremock's default is 5 and lower wins
+const ERROR_PRIORITY: u8 = 1;
+
+impl MockAnthropicServerBuilder {
+    /// Answer authorization code exchanges with [`MOCK_ACCESS_TOKEN`]
+    pub fn with_token_exchange(mut self) -> Self {
+        self.mocks.push(
+            MockEndpoint::Token
+                .mock()
+                .and(body_partial_json(json!({"grant_type": "authorization_code"})))
+                .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                    "access_token": MOCK_ACCESS_TOKEN,
+                    "refresh_token": MOCK_REFRESH_TOKEN,
+                    "id_token": "mock-id-token",
+                    "token_type": "Bearer",
+                    "expires_in": 3600,
+                }))),
+        );
+        self
+    }
+
+    /// Answer refresh grants with [`MOCK_REFRESHED_ACCESS_TOKEN`]
+    pub fn with_token_refresh(mut self) -> Self {
+        self.mocks.push(
+            MockEndpoint::Token
+                .mock()
+                .and(body_partial_json(json!({"grant_type": "refresh_token"})))
+                .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                    "access_token": MOCK_REFRESHED_ACCESS_TOKEN,
+                    "token_type": "Bearer",
+                    "expires_in": 3600,
+                }))),
+        );
+        self
+    }
+
+    /// Accept token revocations
+    pub fn with_revocation(mut self) -> Self {
+        self.mocks
+            .push(MockEndpoint::Revoke.mock().respond_with(ResponseTemplate::new(200)));
+        self
+    }
+
+    /// Report an active subscription of `tier`
+    pub fn with_valid_subscription(mut self, tier: &str) -> Self {
+        self.mocks.push(
+            MockEndpoint::Subscription
+                .mock()
+                .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                    "tier": tier,
+                    "usage_limit": 1_000_000,
+                    "usage_current": 0,
+                    "features": ["priority_access"],
+                    "active": true,
+                }))),
+        );
+        self
+    }
+
+    /// Answer message requests with a short canned completion
+    pub fn with_messages(mut self) -> Self {
+        self.mocks.push(
+            MockEndpoint::Messages
+                .mock()
+                .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                    "id": "msg_mock",
+                    "type": "message",
+                    "role": "assistant",
+                    "content": [{"type": "text", "text": "Hello from the mock"}],
+                    "stop_reason": "end_turn",
+                    "usage": {"input_tokens": 10, "output_tokens": 5},
+                }))),
+        );
+        self
+    }
+
+    /// Fail `endpoint` with `status`, e.g. 401 or 500
+    pub fn with_error(mut self, endpoint: MockEndpoint, status: u16) -> Self {
+        self.mocks.push(
+            endpoint
+                .mock()
+                .respond_with(ResponseTemplate::new(status).set_body_json(json!({
+                    "type": "error",
+                    "error": {"type": error_type(status), "message": "mock error"},
+                })))
+                .with_priority(ERROR_PRIORITY),
+        );
+        self
+    }
+
+    /// Answer every endpoint with 429 and a `retry-after` header
+    pub fn with_rate_limit(mut self, retry_after: Duration) -> Self {
+        for endpoint in MockEndpoint::ALL {
+            self.mocks.push(
+                endpoint
+                    .mock()
+                    .respond_with(
+                        ResponseTemplate::new(429)
+                            .insert_header("retry-after", retry_after.as_secs().to_string().as_str())
+                            .set_body_json(json!({
+                                "type": "error",
+                                "error": {"type": "rate_limit_error", "message": "mock rate limit"},
+                            })),
+                    )
+                    .with_priority(ERROR_PRIORITY),
+            );
+        }
+        self
+    }
+
+    /// Start the server with every configured response mounted
+    ///
+    /// Audit logging is switched off: the global logger may still point into a directory
+    /// another test has already deleted, which would fail every audited call.
+    pub async fn start(self) -> MockAnthropicServer {
+        if let Err(e) = disable_audit_logger() {
+            tracing::debug!(error = %e, "Dropped unflushed audit events");
+        }
+        let server = MockServer::start().await;
+        for mock in self.mocks {
+            mock.mount(&server).await;
+        }
+        MockAnthropicServer { server }
+    }
+}
+
+fn error_type(status: u16) -> &'static str {
+    match status {
+        401 => "authentication_error",
+        403 => "permission_error",
+        404 => "not_found_error",
+        429 => "rate_limit_error",
+        529 => "overloaded_error",
+        _ => "api_error",
+    }
+}
+
+/// Held by this crate's tests that install the global audit logger or rely on it being off
+#[cfg(test)]
+pub(crate) static AUDIT_LOGGER_LOCK: tokio::sync::Mutex<()> = tokio::sync::Mutex::const_new(());
+
+/// Local stand-in for the Anthropic auth and API endpoints
+pub struct MockAnthropicServer {
+    server: MockServer,
+}
+
+impl MockAnthropicServer {
+    /// Configure the responses before starting
+    pub fn builder() -> MockAnthropicServerBuilder {
+        MockAnthropicServerBuilder::default()
+    }
+
+    /// Base URL of the server, without a trailing slash
+    pub fn uri(&self) -> String {
+        self.server.uri()
+    }
+
+    /// Full URL of `endpoint`
+    pub fn url(&self, endpoint: MockEndpoint) -> String {
+        format!("{}{}", self.uri(), endpoint.path())
+    }
+
+    /// Auth configuration with every endpoint pointed at this server
+    pub fn auth_config(&self) -> ClaudeAuthConfig {
+        ClaudeAuthConfig {
+            auth_endpoint: format!("{}/oauth/authorize", self.uri()),
+            token_endpoint: self.url(MockEndpoint::Token),
+            revoke_endpoint: self.url(MockEndpoint::Revoke),
+            subscription_endpoint: self.url(MockEndpoint::Subscription),
+            messages_endpoint: self.url(MockEndpoint::Messages),
+            offline_mode: false,
+            ..ClaudeAuthConfig::default()
+        }
+    }
+
+    /// Number of requests received by `endpoint`
+    pub async fn request_count(&self, endpoint: MockEndpoint) -> usize {
+        self.server
+            .received_requests()
+            .await
+            .unwrap_or_default()
+            .iter()
+            .filter(|request| request.url.path() == endpoint.path())
+            .count()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::claude_auth::{ClaudeAuthError, SecureClaudeAuth};
+    use crate::security::secure_token_storage::TokenData;
+    use crate::security::SecureTokenStorage;
+    use tempfile::tempdir;
+
+    fn store_expiring_tokens(path: &std::path::Path) {
+        SecureTokenStorage::new(path.to_path_buf())
+            .unwrap()
+            .store_tokens(&TokenData {
+                access_token: MOCK_ACCESS_TOKEN.into(),
+                refresh_token: MOCK_REFRESH_TOKEN.into(),
+                id_token: "mock-id-token".into(),
+                expires_at: chrono::Utc::now() + chrono::Duration::seconds(30),
+                account_id: None,
+                provider: "claude".to_string(),
+            })
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_refresh_against_mock_server() {
+        let _audit_logger = AUDIT_LOGGER_LOCK.lock().await;
+        let server = MockAnthropicServer::builder()
+            .with_token_refresh()
+            .with_valid_subscription("max")
+            .start()
+            .await;
+        let temp_dir = tempdir().unwrap();
+        let storage_path = temp_dir.path().join("claude_tokens.json");
+        store_expiring_tokens(&storage_path);
+
+        let mut auth = SecureClaudeAuth::new(server.auth_config(), storage_path).unwrap();
+        let refreshed = auth.refresh_tokens("session").await.unwrap();
+
+        assert_eq!(refreshed.access_token.expose_secret(), MOCK_REFRESHED_ACCESS_TOKEN);
+        // The mock omits the refresh token, so the stored one is kept
+        assert_eq!(refreshed.refresh_token.expose_secret(), MOCK_REFRESH_TOKEN);
+        assert_eq!(auth.verify_subscription(refreshed.access_token.expose_secret()).await.unwrap().tier, "max");
+        assert_eq!(server.request_count(MockEndpoint::Token).await, 1);
+    }
+
+    #[tokio::test]
+    async fn test_rate_limit_overrides_canned_responses() {
+        let _audit_logger = AUDIT_LOGGER_LOCK.lock().await;
+        let server = MockAnthropicServer::builder()
+            .with_token_refresh()
+            .with_rate_limit(Duration::from_secs(30))
+            .start()
+            .await;
+        let temp_dir = tempdir().unwrap();
+        let storage_path = temp_dir.path().join("claude_tokens.json");
+        store_expiring_tokens(&storage_path);
+
+        let mut auth = SecureClaudeAuth::new(server.auth_config(), storage_path).unwrap();
+        let result = auth.refresh_tokens("session").await;
+
+        assert!(matches!(result, Err(ClaudeAuthError::TokenValidationFailed(ref msg)) if msg.contains("429")));
+    }
+}
@@ -0,0 +1,109 @@
+//! HTTPS server that requires a client certificate
+//!
+//! [`MtlsTestServer`] generates a throwaway CA, a `localhost` server certificate and a
+//! client certificate, and answers every request presenting that client certificate
+//! with `200 OK`. Available behind the `mtls-test` feature.
+
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use rcgen::{BasicConstraints, CertificateParams, DnType, ExtendedKeyUsagePurpose, IsCa, KeyPair};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::task::JoinHandle;
+use tokio_rustls::rustls::pki_types::{PrivateKeyDer, PrivatePkcs8KeyDer};
+use tokio_rustls::rustls::server::WebPkiClientVerifier;
+use tokio_rustls::rustls::{RootCertStore, ServerConfig};
+use tokio_rustls::TlsAcceptor;
+
+/// Body of every successful response
+pub const MTLS_RESPONSE_BODY: &str = "mtls ok";
+
+/// Running mutual-TLS server and the PEM files a client needs to reach it
+pub struct MtlsTestServer {
+    /// `https://localhost:<port>`
+    pub url: String,
+    /// CA that signed both the server and the client certificate
+    pub ca_bundle: PathBuf,
+    pub client_cert: PathBuf,
+    pub client_key: PathBuf,
+    task: JoinHandle<()>,
+}
+
+impl MtlsTestServer {
+    /// Write the certificates into `dir` and start serving on a free loopback port
+    pub async fn start(dir: &Path) -> Self {
+        let ca_key = KeyPair::generate().unwrap();
+        let mut ca_params = CertificateParams::new(Vec::<String>::new()).unwrap();
+        ca_params.is_ca = IsCa::Ca(BasicConstraints::Unconstrained);
+        ca_params.distinguished_name.push(DnType::CommonName, "mtls test CA");
+        let ca_cert = ca_params.self_signed(&ca_key).unwrap();
+
+        let server_key = KeyPair::generate().unwrap();
+        let server_cert = CertificateParams::new(vec!["localhost".to_string()])
+            .unwrap()
+            .signed_by(&server_key, &ca_cert, &ca_key)
+            .unwrap();
+
+        let client_key = KeyPair::generate().unwrap();
+        let mut client_params = CertificateParams::new(Vec::<String>::new()).unwrap();
+        client_params.distinguished_name.push(DnType::CommonName, "mtls test client");
+        client_params.extended_key_usages = vec![ExtendedKeyUsagePurpose::ClientAuth];
+        let client_cert = client_params.signed_by(&client_key, &ca_cert, &ca_key).unwrap();
+
+        let ca_bundle = dir.join("ca.pem");
+        let client_cert_path = dir.join("client.pem");
+        let client_key_path = dir.join("client-key.pem");
+        std::fs::write(&ca_bundle, ca_cert.pem()).unwrap();
+        std::fs::write(&client_cert_path, client_cert.pem()).unwrap();
+        std::fs::write(&client_key_path, client_key.serialize_pem()).unwrap();
+
+        let mut roots = RootCertStore::empty();
+        roots.add(ca_cert.der().clone()).unwrap();
+        let verifier = WebPkiClientVerifier::builder(Arc::new(roots)).build().unwrap();
+        let config = ServerConfig::builder()
+            .with_client_cert_verifier(verifier)
+            .with_single_cert(
+                vec![server_cert.der().clone()],
+                PrivateKeyDer::Pkcs8(PrivatePkcs8KeyDer::from(server_key.serialize_der())),
+            )
+            .unwrap();
+        let acceptor = TlsAcceptor::from(Arc::new(config));
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let task = tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                let acceptor = acceptor.clone();
+                tokio::spawn(async move {
+                    // Connections without an accepted client certificate fail the handshake
+                    let Ok(mut stream) = acceptor.accept(stream).await else {
+                        return;
+                    };
+                    let mut request = vec![0u8; 4096];
+                    let _ = stream.read(&mut request).await;
+                    let response = format!(
+                        "HTTP/1.1 200 OK\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{}",
+                        MTLS_RESPONSE_BODY.len(),
+                        MTLS_RESPONSE_BODY
+                    );
+                    let _ = stream.write_all(response.as_bytes()).await;
+                    let _ = stream.shutdown().await;
+                });
+            }
+        });
+
+        Self {
+            url: format!("https://localhost:{}", port),
+            ca_bundle,
+            client_cert: client_cert_path,
+            client_key: client_key_path,
+            task,
+        }
+    }
+}
+
+impl Drop for MtlsTestServer {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
@@ -297,8 +297,8 @@ async fn test_authentication_audit_logging() {
 
 fn create_test_claude_tokens() -> ClaudeTokenData {
     ClaudeTokenData {
-        access_token: "claude_test_access_token".to_string(),
-        refresh_token: Some("claude_test_refresh_token".to_string()),
+        access_token: "claude_test_access_token".into(),
+        refresh_token: Some("claude_test_refresh_token".into()),
         expires_at: chrono::Utc::now() + chrono::Duration::hours(1),
         subscription_tier: "max".to_string(),
     }
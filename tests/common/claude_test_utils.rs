@@ -238,7 +238,7 @@ impl ClaudeTestUtils {
         ClaudeAuth {
             mode: ClaudeAuthMode::ApiKey,
             subscription_tier: None,
-            api_key: Some(api_key.to_string()),
+            api_key: Some(api_key.into()),
             oauth_tokens: None,
             client: reqwest::Client::new(),
         }
@@ -247,8 +247,8 @@ impl ClaudeTestUtils {
     /// Create test tokens for Max subscription
     pub fn create_max_tokens() -> ClaudeTokenData {
         ClaudeTokenData {
-            access_token: "claude_max_access_token".to_string(),
-            refresh_token: Some("claude_max_refresh_token".to_string()),
+            access_token: "claude_max_access_token".into(),
+            refresh_token: Some("claude_max_refresh_token".into()),
             expires_at: chrono::Utc::now() + chrono::Duration::hours(24),
             subscription_tier: "max".to_string(),
         }
@@ -257,8 +257,8 @@ impl ClaudeTestUtils {
     /// Create test tokens for Pro subscription
     pub fn create_pro_tokens() -> ClaudeTokenData {
         ClaudeTokenData {
-            access_token: "claude_pro_access_token".to_string(),
-            refresh_token: Some("claude_pro_refresh_token".to_string()),
+            access_token: "claude_pro_access_token".into(),
+            refresh_token: Some("claude_pro_refresh_token".into()),
             expires_at: chrono::Utc::now() + chrono::Duration::hours(12),
             subscription_tier: "pro".to_string(),
         }
@@ -267,8 +267,8 @@ impl ClaudeTestUtils {
     /// Create expiring tokens (for refresh testing)
     pub fn create_expiring_tokens() -> ClaudeTokenData {
         ClaudeTokenData {
-            access_token: "claude_expiring_access_token".to_string(),
-            refresh_token: Some("claude_expiring_refresh_token".to_string()),
+            access_token: "claude_expiring_access_token".into(),
+            refresh_token: Some("claude_expiring_refresh_token".into()),
             expires_at: chrono::Utc::now() + chrono::Duration::minutes(5),
             subscription_tier: "max".to_string(),
         }
@@ -277,8 +277,8 @@ impl ClaudeTestUtils {
     /// Create expired tokens (for refresh testing)
     pub fn create_expired_tokens() -> ClaudeTokenData {
         ClaudeTokenData {
-            access_token: "claude_expired_access_token".to_string(),
-            refresh_token: Some("claude_expired_refresh_token".to_string()),
+            access_token: "claude_expired_access_token".into(),
+            refresh_token: Some("claude_expired_refresh_token".into()),
             expires_at: chrono::Utc::now() - chrono::Duration::hours(1),
             subscription_tier: "max".to_string(),
         }
@@ -441,7 +441,7 @@ impl ClaudeTestAssertions {
     
     /// Assert that token data is valid
     pub fn assert_valid_token_data(tokens: &ClaudeTokenData) {
-        assert!(!tokens.access_token.is_empty(), "Access token should not be empty");
+        assert!(!tokens.access_token.expose_secret().is_empty(), "Access token should not be empty");
         assert!(tokens.refresh_token.is_some(), "Refresh token should be present");
         assert!(tokens.expires_at > chrono::Utc::now(), "Token should not be expired");
         assert!(["max", "pro", "free"].contains(&tokens.subscription_tier.as_str()), 
@@ -545,7 +545,7 @@ mod tests {
         
         let api_auth = ClaudeTestUtils::create_api_key_auth("sk-ant-test");
         assert_eq!(api_auth.mode, ClaudeAuthMode::ApiKey);
-        assert_eq!(api_auth.api_key, Some("sk-ant-test".to_string()));
+        assert_eq!(api_auth.api_key.as_ref().map(|key| key.expose_secret().as_str()), Some("sk-ant-test"));
     }
     
     #[tokio::test]
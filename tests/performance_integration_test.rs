@@ -199,7 +199,7 @@ mod performance_integration_tests {
     /// Test Phase 5 compliance benchmark
     #[tokio::test]
     async fn test_phase5_compliance_benchmark() {
-        let results = run_phase5_compliance_benchmark(None).await;
+        let results = run_phase5_compliance_benchmark().await;
         
         assert_eq!(results.suite_name, "Phase 5 Compliance");
         assert!(!results.individual_results.is_empty());
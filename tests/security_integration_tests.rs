@@ -1,5 +1,6 @@
 use claude_code_security::{
     SecurityManager, SecurityConfig, SecureClaudeAuth, ClaudeAuthConfig,
+    ApiKeySubscriptionPolicy,
     init_security_system, init_claude_auth_system,
 };
 use tempfile::tempdir;
@@ -13,12 +14,17 @@ async fn test_security_system_initialization() {
         token_storage_path: temp_dir.path().join("tokens.json"),
         audit_log_path: temp_dir.path().join("audit.log"),
         enable_encryption: true,
+        on_encryption_unavailable: Default::default(),
+        permission_check: Default::default(),
         enable_audit_logging: true,
         require_pkce: true,
         token_rotation_enabled: true,
         max_concurrent_oauth_flows: 3,
+        oauth_rate_limit: Default::default(),
+        oauth_flow_ttl_minutes: 10,
         session_timeout_minutes: 60,
         require_secure_transport: false, // Disabled for tests
+        violation_webhook: None,
     };
 
     let security_manager = SecurityManager::new(config).unwrap();
@@ -32,6 +38,10 @@ async fn test_security_system_initialization() {
     assert!(health_report.oauth_security_enabled);
     assert!(health_report.session_security_enabled);
     assert!(health_report.audit_logging_enabled);
+
+    // The global audit logger writes into temp_dir; stop it before temp_dir is removed so
+    // tests running alongside don't fail to append their events
+    claude_code_security::security::audit_logger::disable_audit_logger().unwrap();
 }
 
 #[tokio::test]
@@ -42,11 +52,21 @@ async fn test_claude_auth_initialization() {
         client_id: "test_client_id".to_string(),
         auth_endpoint: "https://auth.anthropic.com/oauth/authorize".to_string(),
         token_endpoint: "https://auth.anthropic.com/oauth/token".to_string(),
+        revoke_endpoint: "https://auth.anthropic.com/oauth/revoke".to_string(),
         subscription_endpoint: "https://api.anthropic.com/v1/subscription".to_string(),
+        messages_endpoint: "https://api.anthropic.com/v1/messages".to_string(),
         redirect_uri: "http://localhost:1456/callback".to_string(),
         scopes: vec!["api".to_string(), "subscription".to_string()],
         require_max_subscription: false,
         enable_subscription_check: false, // Disabled for tests
+        offline_mode: false,
+        assume_max_subscription: false,
+        api_key_subscription_policy: ApiKeySubscriptionPolicy::Deny,
+        originator: "integration_tests".to_string(),
+        user_agent: None,
+        auth_file_path: None,
+        proxy: None,
+        tls: None,
     };
 
     let storage_path = temp_dir.path().join("claude_tokens.json");
@@ -86,9 +106,9 @@ async fn test_token_storage_security() {
     let storage = claude_code_security::SecureTokenStorage::new(storage_path.clone()).unwrap();
     
     let test_tokens = claude_code_security::security::secure_token_storage::TokenData {
-        access_token: "test_access_token".to_string(),
-        refresh_token: "test_refresh_token".to_string(),
-        id_token: "test_id_token".to_string(),
+        access_token: "test_access_token".into(),
+        refresh_token: "test_refresh_token".into(),
+        id_token: "test_id_token".into(),
         expires_at: chrono::Utc::now() + chrono::Duration::hours(1),
         account_id: Some("test_account".to_string()),
         provider: "claude".to_string(),
@@ -253,6 +273,8 @@ async fn test_environment_security_validation() {
     // Test environment validation (should not fail even with env vars set)
     let validation_result = security_manager.validate_environment();
     assert!(validation_result.is_ok());
+
+    claude_code_security::security::audit_logger::disable_audit_logger().unwrap();
 }
 
 #[test]